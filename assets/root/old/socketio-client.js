@@ -158,8 +158,8 @@ async function connectToSocketIO(port) {
     });
 
     // 处理设备元数据
-    socket.on('scrcpy_device_meta', (deviceName) => {
-        log(`收到设备元数据: ${deviceName}`, 'success');
+    socket.on('scrcpy_device_meta', (meta) => {
+        log(`收到设备元数据: ${meta.device_name} (${meta.video_codec})`, 'success');
 
         // 重置解码器以处理新的解码数据
         if (decoder) {
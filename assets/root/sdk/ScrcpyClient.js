@@ -70,6 +70,7 @@ export class ScrcpyClient {
     #isConnected = false;
     #screenSize = { width: 1080, height: 1920 };
     #pointerId = 0n;
+    #visibilityHandler = null;
 
     /**
      * 创建 Scrcpy 客户端实例
@@ -79,9 +80,12 @@ export class ScrcpyClient {
      * @param {Function} [config.onDisconnected] - 断开连接回调
      * @param {Function} [config.onError] - 错误回调
      * @param {Function} [config.onFrame] - 帧解码回调
+     * @param {Function} [config.onAgentAction] - Agent 操作提示回调（用于在画面上绘制点击、滑动提示）
      * @param {Function} [config.onLog] - 日志回调
      * @param {Object} [config.keyMap] - 自定义按键映射
      * @param {BigInt} [config.pointerId] - 触摸点 ID (默认: 0n)
+     * @param {boolean} [config.background] - 始终不需要实时画面（Agent 监控、缩略图等），服务端据此进入低功耗模式
+     * @param {boolean} [config.trackVisibility] - 页面隐藏时自动声明为后台客户端 (默认: true)
      */
     constructor(config) {
         if (!config.canvas) {
@@ -92,6 +96,8 @@ export class ScrcpyClient {
         this.#config = {
             keyMap: DEFAULT_KEY_MAP,
             pointerId: 0n,
+            background: false,
+            trackVisibility: true,
             ...config
         };
 
@@ -108,9 +114,11 @@ export class ScrcpyClient {
      * 连接到设备
      * @param {string} deviceSerial - 设备序列号
      * @param {number} socketPort - Socket.IO 端口
+     * @param {string} [socketPath] - Socket.IO 请求路径（单端口模式下为 /scrcpy/{serial}/socket.io）
+     * @param {string} [controlToken] - `/connect` 返回的控制令牌（data.control.token），未提供时只能观看
      * @returns {Promise<void>}
      */
-    async connect(deviceSerial, socketPort) {
+    async connect(deviceSerial, socketPort, socketPath = '/socket.io', controlToken = undefined) {
         if (this.#isConnected) {
             this.#log('Already connected', 'warn');
             return;
@@ -120,18 +128,29 @@ export class ScrcpyClient {
             this.#log(`Connecting to device: ${deviceSerial}`, 'info');
 
             // 创建 Socket 连接
-            const socketUrl = `http://127.0.0.1:${socketPort}`;
+            // 反向代理下（页面注入了路径前缀）通过当前地址访问，不直连内部端口
+            const host = window.location.hostname || '127.0.0.1';
+            const socketUrl = window.SCRS_BASE_PATH
+                ? window.location.origin
+                : `${window.location.protocol === 'https:' ? 'https' : 'http'}://${host}:${socketPort}`;
             this.#socket = new ScrcpySocket(socketUrl, {
+                path: `${socketPath}/`,
+                controlToken,
+                background: this.#isBackground(),
+                onControlAuth: (result) => this.#emit('controlAuth', result),
+                onControlRevoked: (data) => this.#emit('controlRevoked', data),
                 onConnect: () => this.#onSocketConnect(),
                 onDisconnect: (reason) => this.#onSocketDisconnect(reason),
                 onError: (err) => this.#onSocketError(err),
                 onVideoData: (data) => this.#onVideoData(data),
                 onDeviceMeta: (meta) => this.#onDeviceMeta(meta),
+                onAgentAction: (action) => this.#emit('agentAction', action),
                 onControlAck: () => this.#onControlAck(),
                 onControlError: (err) => this.#onControlError(err)
             });
 
             await this.#socket.connect();
+            this.#watchVisibility();
 
             this.#log('Connection initiated', 'success');
 
@@ -146,6 +165,11 @@ export class ScrcpyClient {
      * 断开连接
      */
     disconnect() {
+        if (this.#visibilityHandler) {
+            document.removeEventListener('visibilitychange', this.#visibilityHandler);
+            this.#visibilityHandler = null;
+        }
+
         if (this.#decoder) {
             this.#decoder.destroy();
             this.#decoder = null;
@@ -340,6 +364,27 @@ export class ScrcpyClient {
 
     // ========== 私有方法 ==========
 
+    /**
+     * 当前是否为后台客户端（配置为后台或页面已隐藏）
+     * @private
+     */
+    #isBackground() {
+        return this.#config.background || (this.#config.trackVisibility && document.hidden);
+    }
+
+    /**
+     * 页面显示或隐藏时通知服务端切换前后台（服务端会延迟进入低功耗模式，频繁切换标签页不会反复重启会话）
+     * @private
+     */
+    #watchVisibility() {
+        if (!this.#config.trackVisibility || this.#config.background || this.#visibilityHandler) {
+            return;
+        }
+
+        this.#visibilityHandler = () => this.#socket?.setBackground(this.#isBackground());
+        document.addEventListener('visibilitychange', this.#visibilityHandler);
+    }
+
     /**
      * 设置内部事件处理器
      * @private
@@ -357,6 +402,9 @@ export class ScrcpyClient {
         if (this.#config.onFrame) {
             this.on('frame', this.#config.onFrame);
         }
+        if (this.#config.onAgentAction) {
+            this.on('agentAction', this.#config.onAgentAction);
+        }
     }
 
     /**
@@ -418,8 +466,8 @@ export class ScrcpyClient {
      * 接收设备元数据回调
      * @private
      */
-    #onDeviceMeta(deviceName) {
-        this.#log(`Device metadata: ${deviceName}`, 'info');
+    #onDeviceMeta(meta) {
+        this.#log(`Device metadata: ${meta.device_name} (codec: ${meta.video_codec}, server: ${meta.server_version})`, 'info');
 
         // 重置解码器
         if (this.#decoder) {
@@ -21,13 +21,26 @@ export class ScrcpySocket {
      * @param {Function} options.onDeviceMeta - 接收设备元数据回调
      * @param {Function} options.onControlAck - 控制确认回调
      * @param {Function} options.onControlError - 控制错误回调
+     * @param {Function} options.onStats - 会话统计回调 (scrcpy_stats)
+     * @param {Function} options.onAgentAction - Agent 操作提示回调 (agent_action)
+     * @param {Function} options.onControlAuth - 控制令牌校验结果回调 (scrcpy_ctl_auth_response)
+     * @param {Function} options.onControlRevoked - 控制权限被撤销回调 (scrcpy_ctl_revoked)
+     * @param {string} options.controlToken - `/connect` 返回的控制令牌，连接后自动提交以获取控制权限
+     * @param {string[]} options.videoCodecs - 客户端支持解码的视频编码 (默认: ['h264'])
+     * @param {boolean} options.background - 客户端不需要实时画面（Agent 监控、缩略图等），服务端据此进入低功耗模式
      */
     constructor(url, options = {}) {
         this.#url = url;
         this.#options = {
             path: '/socket.io/',
             transports: ['websocket', 'polling'],
-            ...options
+            ...options,
+            // 连接握手时声明支持的视频编码，服务端据此协商编码；保留调用方传入的其它 auth 字段
+            auth: {
+                ...(options.auth || {}),
+                video_codecs: options.videoCodecs || ['h264'],
+                background: !!options.background
+            }
         };
 
         // 设置事件处理器
@@ -38,6 +51,10 @@ export class ScrcpySocket {
         if (options.onDeviceMeta) this.on('scrcpy_device_meta', options.onDeviceMeta);
         if (options.onControlAck) this.on('scrcpy_ctl_ack', options.onControlAck);
         if (options.onControlError) this.on('scrcpy_ctl_error', options.onControlError);
+        if (options.onStats) this.on('scrcpy_stats', options.onStats);
+        if (options.onAgentAction) this.on('agent_action', options.onAgentAction);
+        if (options.onControlAuth) this.on('scrcpy_ctl_auth_response', options.onControlAuth);
+        if (options.onControlRevoked) this.on('scrcpy_ctl_revoked', options.onControlRevoked);
     }
 
     /**
@@ -59,6 +76,10 @@ export class ScrcpySocket {
                 this.#socket.on('connect', () => {
                     this.#isConnected = true;
                     console.log('[ScrcpySocket] Connected to', this.#url);
+                    // 每次（重新）连接后提交控制令牌，否则只能观看
+                    if (this.#options.controlToken) {
+                        this.#socket.emit('scrcpy_ctl_auth', { token: this.#options.controlToken });
+                    }
                     this.#emit('connect', this.#socket.id);
                     resolve();
                 });
@@ -83,10 +104,20 @@ export class ScrcpySocket {
                     this.#emit('test_response', data);
                 });
 
-                // 设备元数据事件
-                this.#socket.on('scrcpy_device_meta', (deviceName) => {
-                    console.log('[ScrcpySocket] Device metadata:', deviceName);
-                    this.#emit('scrcpy_device_meta', deviceName);
+                // 设备元数据事件 ({ device_name, video_codec, server_version })
+                this.#socket.on('scrcpy_device_meta', (meta) => {
+                    console.log('[ScrcpySocket] Device metadata:', meta);
+                    this.#emit('scrcpy_device_meta', meta);
+                });
+
+                // 会话统计事件
+                this.#socket.on('scrcpy_stats', (stats) => {
+                    this.#emit('scrcpy_stats', stats);
+                });
+
+                // Agent 操作提示事件 ({ type, x, y, end_x, end_y, path, duration_ms, label }，坐标为 0~1 比例)
+                this.#socket.on('agent_action', (action) => {
+                    this.#emit('agent_action', action);
                 });
 
                 // 视频数据事件
@@ -99,6 +130,18 @@ export class ScrcpySocket {
                     this.#emit('scrcpy_ctl_ack', data);
                 });
 
+                // 控制令牌校验结果 ({ success, error })
+                this.#socket.on('scrcpy_ctl_auth_response', (data) => {
+                    if (!data.success) console.warn('[ScrcpySocket] Control auth failed:', data.error);
+                    this.#emit('scrcpy_ctl_auth_response', data);
+                });
+
+                // 控制权限被撤销
+                this.#socket.on('scrcpy_ctl_revoked', (data) => {
+                    console.warn('[ScrcpySocket] Control revoked');
+                    this.#emit('scrcpy_ctl_revoked', data);
+                });
+
                 // 控制错误事件
                 this.#socket.on('scrcpy_ctl_error', (data) => {
                     console.error('[ScrcpySocket] Control error:', data);
@@ -145,6 +188,18 @@ export class ScrcpySocket {
         }
     }
 
+    /**
+     * 声明客户端是否在后台（scrcpy_visibility），所有客户端都在后台时服务端进入低功耗模式
+     * @param {boolean} background - 是否不需要实时画面
+     */
+    setBackground(background) {
+        if (!this.#isConnected || !this.#socket) {
+            return;
+        }
+
+        this.#socket.emit('scrcpy_visibility', { background: !!background });
+    }
+
     /**
      * 发送测试消息
      * @param {Object} message - 测试消息
@@ -0,0 +1,375 @@
+//! 主机 adb server 进程管理
+//!
+//! adb server 没有运行或卡死时，所有 adb 操作都只会返回难以理解的连接错误。这里负责：
+//! 启动时确保 adb server 在运行；提供重启（`kill-server` + `start-server`）；
+//! 连续出现 adb 错误时自动重启，并广播重启事件，由设备池重新建立设备连接。
+//!
+//! 也可以使用其它机器（或设备农场）上的远程 adb server，全局或按设备配置。远程 server 需要以 `adb -a` 启动，
+//! 端口转发才会监听在外部可访问的地址上；远程 server 不由本服务启动或重启。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{Ipv4Addr, SocketAddrV4, ToSocketAddrs};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use adb_client::server::ADBServer;
+use adb_client::server_device::ADBServerDevice;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+
+/// adb server 默认端口
+pub const ADB_SERVER_PORT: u16 = 5037;
+
+/// 连续失败多少次后自动重启
+const FAILURE_THRESHOLD: u32 = 3;
+/// 两次自动重启的最短间隔
+const RESTART_COOLDOWN: Duration = Duration::from_secs(30);
+/// 等待 adb server 开始监听的最长时间
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// adb server 地址
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdbServerAddr {
+    /// 配置中的主机名
+    pub host: String,
+    /// 解析后的地址（adb_client 只支持 IPv4）
+    pub addr: SocketAddrV4,
+}
+
+impl AdbServerAddr {
+    /// 解析 `host` 或 `host:port`（默认端口 5037），主机名在此时解析
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        let (host, port) = match value.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| format!("adb server 端口无效: {}", value))?),
+            None => (value, ADB_SERVER_PORT),
+        };
+        if host.is_empty() {
+            return Err(format!("adb server 地址无效: {}", value));
+        }
+        let addr = (host, port)
+            .to_socket_addrs()
+            .map_err(|e| format!("解析 adb server 地址 {} 失败: {}", value, e))?
+            .find_map(|addr| match addr {
+                std::net::SocketAddr::V4(addr) => Some(addr),
+                std::net::SocketAddr::V6(_) => None,
+            })
+            .ok_or_else(|| format!("adb server 地址 {} 没有 IPv4 地址", value))?;
+        Ok(Self { host: host.to_string(), addr })
+    }
+
+    /// 是否为本机默认的 adb server
+    pub fn is_local(&self) -> bool {
+        self.addr == SocketAddrV4::new(Ipv4Addr::LOCALHOST, ADB_SERVER_PORT)
+    }
+}
+
+impl fmt::Display for AdbServerAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.addr.port())
+    }
+}
+
+/// 全局与按设备配置的 adb server
+#[derive(Default)]
+struct Routing {
+    default: Option<AdbServerAddr>,
+    devices: HashMap<String, AdbServerAddr>,
+}
+
+fn routing() -> &'static RwLock<Routing> {
+    static ROUTING: OnceLock<RwLock<Routing>> = OnceLock::new();
+    ROUTING.get_or_init(|| RwLock::new(Routing::default()))
+}
+
+/// 设置全局 adb server，None 表示使用本机 server
+pub fn set_default_server(addr: Option<AdbServerAddr>) {
+    if let Some(addr) = &addr {
+        info!("使用 adb server: {}", addr);
+    }
+    routing().write().unwrap().default = addr.filter(|addr| !addr.is_local());
+}
+
+/// 为设备单独指定 adb server，None 表示使用全局配置
+pub fn set_device_server(serial: &str, addr: Option<AdbServerAddr>) {
+    let mut routing = routing().write().unwrap();
+    match addr {
+        Some(addr) => {
+            info!("设备 {} 使用 adb server: {}", serial, addr);
+            routing.devices.insert(serial.to_string(), addr);
+        }
+        None => {
+            routing.devices.remove(serial);
+        }
+    }
+}
+
+/// 设备使用的远程 adb server，使用本机 server 时返回 None
+pub fn server_for(serial: &str) -> Option<AdbServerAddr> {
+    let routing = routing().read().unwrap();
+    routing.devices.get(serial).or(routing.default.as_ref()).filter(|addr| !addr.is_local()).cloned()
+}
+
+/// 设备端口转发的监听主机：本机 server 为 `127.0.0.1`，远程 server 为其所在主机
+pub fn forward_host(serial: &str) -> String {
+    server_for(serial)
+        .map(|addr| addr.addr.ip().to_string())
+        .unwrap_or_else(|| Ipv4Addr::LOCALHOST.to_string())
+}
+
+/// 通过设备所在的 adb server 获取设备，没有单独配置时使用共享的 `server`
+pub fn get_device(server: &mut ADBServer, serial: &str) -> Result<ADBServerDevice, adb_client::RustADBError> {
+    let device_server = routing().read().unwrap().devices.get(serial).cloned();
+    match device_server {
+        Some(addr) => ADBServer::new(addr.addr).get_device_by_name(serial),
+        None => server.get_device_by_name(serial),
+    }
+}
+
+/// 共享的 adb server 客户端
+pub type SharedAdbServer = std::sync::Arc<tokio::sync::RwLock<ADBServer>>;
+
+/// 在阻塞线程池中使用共享的 adb server 客户端：adb_client 的调用是同步的网络 IO，
+/// 直接在异步处理函数中调用会阻塞运行时的工作线程
+pub async fn with_server<R, F>(server: &SharedAdbServer, f: F) -> R
+where
+    R: Send + 'static,
+    F: FnOnce(&mut ADBServer) -> R + Send + 'static,
+{
+    let mut guard = std::sync::Arc::clone(server).write_owned().await;
+    tokio::task::spawn_blocking(move || f(&mut guard))
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// 列出共享 adb server 上的设备，并记录结果（连续失败时自动重启）
+pub async fn list_devices(server: &SharedAdbServer) -> Result<Vec<adb_client::server::DeviceShort>, adb_client::RustADBError> {
+    let result = with_server(server, |server| server.devices()).await;
+    record_result(&result);
+    result
+}
+
+/// 通过设备所在的 adb server 获取设备（见 [`get_device`]），并记录结果
+pub async fn fetch_device(server: &SharedAdbServer, serial: &str) -> Result<ADBServerDevice, adb_client::RustADBError> {
+    let serial = serial.to_string();
+    let result = with_server(server, move |server| get_device(server, &serial)).await;
+    record_result(&result);
+    result
+}
+
+/// adb server 状态
+#[derive(Debug, Clone, Serialize)]
+pub struct AdbServerStatus {
+    /// 全局 adb server 地址
+    pub address: String,
+    /// 端口是否在监听
+    pub running: bool,
+    /// 连续失败次数
+    pub consecutive_failures: u32,
+    /// 累计重启次数
+    pub restarts_total: u64,
+    /// 距上次重启的秒数
+    pub last_restart_secs_ago: Option<u64>,
+}
+
+struct Supervisor {
+    consecutive_failures: AtomicU32,
+    restarts: AtomicU64,
+    last_restart: Mutex<Option<Instant>>,
+    /// 防止并发重启
+    restarting: tokio::sync::Mutex<()>,
+    events: broadcast::Sender<u64>,
+}
+
+fn supervisor() -> &'static Supervisor {
+    static SUPERVISOR: OnceLock<Supervisor> = OnceLock::new();
+    SUPERVISOR.get_or_init(|| Supervisor {
+        consecutive_failures: AtomicU32::new(0),
+        restarts: AtomicU64::new(0),
+        last_restart: Mutex::new(None),
+        restarting: tokio::sync::Mutex::new(()),
+        events: broadcast::channel(8).0,
+    })
+}
+
+/// 创建连接全局 adb server 的客户端（未配置远程 server 时为本机）
+pub fn new_adb_server() -> ADBServer {
+    match default_server() {
+        Some(remote) => ADBServer::new(remote.addr),
+        None => ADBServer::new_from_path(
+            SocketAddrV4::new(Ipv4Addr::LOCALHOST, ADB_SERVER_PORT),
+            Some(crate::platform::adb_path().to_string_lossy().to_string()),
+        ),
+    }
+}
+
+fn default_server() -> Option<AdbServerAddr> {
+    routing().read().unwrap().default.clone()
+}
+
+fn default_addr() -> SocketAddrV4 {
+    default_server()
+        .map(|remote| remote.addr)
+        .unwrap_or(SocketAddrV4::new(Ipv4Addr::LOCALHOST, ADB_SERVER_PORT))
+}
+
+/// 订阅重启事件（值为累计重启次数），收到后应重新建立设备连接
+pub fn subscribe_restarts() -> broadcast::Receiver<u64> {
+    supervisor().events.subscribe()
+}
+
+/// 全局 adb server 端口是否在监听
+pub async fn is_running() -> bool {
+    let addr = default_addr();
+    matches!(
+        tokio::time::timeout(Duration::from_secs(1), tokio::net::TcpStream::connect(addr)).await,
+        Ok(Ok(_))
+    )
+}
+
+/// 本机 adb server 没有运行时启动它；远程 server 只检查能否连接
+pub async fn ensure_running() -> Result<(), String> {
+    if is_running().await {
+        return Ok(());
+    }
+    if let Some(remote) = default_server() {
+        return Err(format!("无法连接远程 adb server {}", remote));
+    }
+    info!("adb server 未运行，正在启动");
+    start_server().await
+}
+
+/// 重启本机 adb server，成功后广播重启事件
+pub async fn restart() -> Result<(), String> {
+    if let Some(remote) = default_server() {
+        return Err(format!("远程 adb server {} 无法由本服务重启", remote));
+    }
+    let supervisor = supervisor();
+    let _guard = supervisor.restarting.lock().await;
+
+    warn!("正在重启 adb server");
+    match crate::platform::adb_command().arg("kill-server").output().await {
+        Ok(output) if !output.status.success() => {
+            warn!("adb kill-server 失败: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Err(e) => warn!("执行 adb kill-server 失败: {}", e),
+        _ => {}
+    }
+    start_server().await?;
+
+    supervisor.consecutive_failures.store(0, Ordering::SeqCst);
+    *supervisor.last_restart.lock().unwrap() = Some(Instant::now());
+    let restarts = supervisor.restarts.fetch_add(1, Ordering::SeqCst) + 1;
+    let _ = supervisor.events.send(restarts);
+    info!("adb server 已重启（累计 {} 次）", restarts);
+    Ok(())
+}
+
+async fn start_server() -> Result<(), String> {
+    let output = crate::platform::adb_command()
+        .arg("start-server")
+        .output()
+        .await
+        .map_err(|e| format!("执行 adb start-server 失败: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("adb start-server 失败: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let deadline = Instant::now() + START_TIMEOUT;
+    while Instant::now() < deadline {
+        if is_running().await {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    Err(format!("adb server 在 {} 秒内未开始监听端口 {}", START_TIMEOUT.as_secs(), ADB_SERVER_PORT))
+}
+
+/// 记录一次 adb 操作成功
+pub fn record_success() {
+    supervisor().consecutive_failures.store(0, Ordering::SeqCst);
+}
+
+/// 记录一次 adb 错误；连续失败达到阈值且距上次重启超过冷却时间时在后台自动重启
+pub fn record_failure(error: &impl std::fmt::Debug) {
+    let supervisor = supervisor();
+    let failures = supervisor.consecutive_failures.fetch_add(1, Ordering::SeqCst) + 1;
+    warn!("adb 操作失败（连续 {} 次）: {:?}", failures, error);
+    if failures < FAILURE_THRESHOLD {
+        return;
+    }
+
+    let cooling_down = supervisor
+        .last_restart
+        .lock()
+        .unwrap()
+        .is_some_and(|at| at.elapsed() < RESTART_COOLDOWN);
+    if cooling_down || default_server().is_some() || supervisor.restarting.try_lock().is_err() {
+        return;
+    }
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    handle.spawn(async {
+        if let Err(e) = restart().await {
+            error!("自动重启 adb server 失败: {}", e);
+        }
+    });
+}
+
+/// 按结果记录 adb 操作成功或失败
+pub fn record_result<T, E: std::fmt::Debug>(result: &Result<T, E>) {
+    match result {
+        Ok(_) => record_success(),
+        Err(e) => record_failure(e),
+    }
+}
+
+/// 当前状态
+pub async fn status() -> AdbServerStatus {
+    let supervisor = supervisor();
+    AdbServerStatus {
+        address: default_addr().to_string(),
+        running: is_running().await,
+        consecutive_failures: supervisor.consecutive_failures.load(Ordering::SeqCst),
+        restarts_total: supervisor.restarts.load(Ordering::SeqCst),
+        last_restart_secs_ago: supervisor.last_restart.lock().unwrap().map(|at| at.elapsed().as_secs()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_counting() {
+        // 没有 tokio 运行时，达到阈值也不会真正重启
+        for _ in 0..FAILURE_THRESHOLD {
+            record_failure(&"cannot connect to daemon");
+        }
+        assert_eq!(supervisor().consecutive_failures.load(Ordering::SeqCst), FAILURE_THRESHOLD);
+
+        record_result::<(), String>(&Ok(()));
+        assert_eq!(supervisor().consecutive_failures.load(Ordering::SeqCst), 0);
+        assert_eq!(supervisor().restarts.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_device_routing() {
+        let remote = AdbServerAddr::parse("127.0.0.2:5038").unwrap();
+        assert_eq!(remote.to_string(), "127.0.0.2:5038");
+        assert!(AdbServerAddr::parse("localhost").unwrap().is_local());
+        assert!(AdbServerAddr::parse("farm:port").is_err());
+
+        set_device_server("farm-device-1", Some(remote.clone()));
+        assert_eq!(server_for("farm-device-1"), Some(remote));
+        assert_eq!(forward_host("farm-device-1"), "127.0.0.2");
+        assert_eq!(forward_host("emulator-5554"), "127.0.0.1");
+
+        set_device_server("farm-device-1", None);
+        assert_eq!(server_for("farm-device-1"), None);
+    }
+}
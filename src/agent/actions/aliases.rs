@@ -0,0 +1,178 @@
+//! 应用别名
+//!
+//! 内置的应用名称映射只覆盖常见的公开应用。企业内部或自研应用可以在配置文件的 `[apps.aliases]` 段
+//! （名称 = 包名）中配置别名，也可以通过 `POST /apps/aliases` 在运行时添加；运行时添加的别名保存到
+//! [`APP_ALIASES_FILE`]，重启后仍然有效。查找时别名优先于内置映射，名称不区分大小写。
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+
+use regex::Regex;
+use serde::Serialize;
+
+/// 运行时添加的别名保存的文件
+pub const APP_ALIASES_FILE: &str = "apps/aliases.json";
+
+/// 别名来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AliasSource {
+    /// 配置文件 `[apps.aliases]` 段
+    Config,
+    /// `POST /apps/aliases`
+    Api,
+}
+
+/// 一个别名
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppAlias {
+    pub name: String,
+    pub package: String,
+    pub source: AliasSource,
+}
+
+/// 应用别名表
+#[derive(Debug, Default)]
+pub struct AppAliases {
+    /// 配置文件中的别名（小写名称 -> 包名）
+    configured: RwLock<BTreeMap<String, String>>,
+    /// 运行时添加的别名，优先于配置文件
+    custom: RwLock<BTreeMap<String, String>>,
+}
+
+/// 进程内共享的应用别名表
+pub fn app_aliases() -> &'static AppAliases {
+    static ALIASES: OnceLock<AppAliases> = OnceLock::new();
+    ALIASES.get_or_init(AppAliases::default)
+}
+
+impl AppAliases {
+    /// 按名称查找包名
+    pub fn get(&self, name: &str) -> Option<String> {
+        let key = name.trim().to_lowercase();
+        let custom = self.custom.read().unwrap().get(&key).cloned();
+        custom.or_else(|| self.configured.read().unwrap().get(&key).cloned())
+    }
+
+    /// 替换配置文件中的别名
+    pub fn set_configured(&self, aliases: BTreeMap<String, String>) -> Result<(), String> {
+        *self.configured.write().unwrap() = normalize(aliases)?;
+        Ok(())
+    }
+
+    /// 添加（或覆盖）运行时别名
+    pub fn insert(&self, aliases: BTreeMap<String, String>) -> Result<(), String> {
+        let aliases = normalize(aliases)?;
+        self.custom.write().unwrap().extend(aliases);
+        Ok(())
+    }
+
+    /// 删除运行时别名，返回是否存在
+    pub fn remove(&self, name: &str) -> bool {
+        self.custom.write().unwrap().remove(&name.trim().to_lowercase()).is_some()
+    }
+
+    /// 所有别名，同名时只列出生效的运行时别名
+    pub fn list(&self) -> Vec<AppAlias> {
+        let custom = self.custom.read().unwrap();
+        let configured = self.configured.read().unwrap();
+        let mut aliases: Vec<AppAlias> = configured
+            .iter()
+            .filter(|(name, _)| !custom.contains_key(*name))
+            .map(|(name, package)| AppAlias { name: name.clone(), package: package.clone(), source: AliasSource::Config })
+            .chain(custom.iter().map(|(name, package)| AppAlias {
+                name: name.clone(),
+                package: package.clone(),
+                source: AliasSource::Api,
+            }))
+            .collect();
+        aliases.sort_by(|a, b| a.name.cmp(&b.name));
+        aliases
+    }
+
+    /// 从配置文件的 `[apps.aliases]` 段读取别名，替换之前读取的配置别名
+    pub fn load_config_file(&self, path: &Path) -> Result<(), String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("读取配置文件 {:?} 失败: {}", path, e))?;
+        let value: toml::Table = toml::from_str(&content).map_err(|e| format!("解析配置文件 {:?} 失败: {}", path, e))?;
+        let aliases: BTreeMap<String, String> = match value.get("apps").and_then(|apps| apps.get("aliases")) {
+            Some(aliases) => aliases.clone().try_into().map_err(|e| format!("解析 [apps.aliases] 段失败: {}", e))?,
+            None => BTreeMap::new(),
+        };
+        self.set_configured(aliases)
+    }
+
+    /// 读取保存的运行时别名
+    pub fn load(&self, path: &Path) -> Result<(), String> {
+        let json = match std::fs::read(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(format!("读取应用别名 {:?} 失败: {}", path, e)),
+        };
+        let aliases: BTreeMap<String, String> =
+            serde_json::from_slice(&json).map_err(|e| format!("解析应用别名 {:?} 失败: {}", path, e))?;
+        self.insert(aliases)
+    }
+
+    /// 保存运行时别名
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_vec_pretty(&*self.custom.read().unwrap()).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("创建目录 {:?} 失败: {}", dir, e))?;
+        }
+        std::fs::write(path, json).map_err(|e| format!("保存应用别名 {:?} 失败: {}", path, e))
+    }
+}
+
+/// 校验别名并将名称转为小写
+fn normalize(aliases: BTreeMap<String, String>) -> Result<BTreeMap<String, String>, String> {
+    let package_re = Regex::new(r"^[A-Za-z][\w]*(\.[A-Za-z][\w]*)+$").unwrap();
+    aliases
+        .into_iter()
+        .map(|(name, package)| {
+            let name = name.trim().to_lowercase();
+            // 含 `.` 的名称会被当作包名直接启动，不会查找别名
+            if name.is_empty() || name.contains('.') {
+                return Err(format!("无效的应用别名: {:?}（不能为空或包含 .）", name));
+            }
+            let package = package.trim().to_string();
+            if !package_re.is_match(&package) {
+                return Err(format!("别名 {} 的包名无效: {:?}", name, package));
+            }
+            Ok((name, package))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_aliases_override_config() {
+        let aliases = AppAliases::default();
+        aliases.set_configured(BTreeMap::from([("OA".to_string(), "com.corp.oa".to_string())])).unwrap();
+        aliases.insert(BTreeMap::from([("报销".to_string(), "com.corp.expense".to_string())])).unwrap();
+        assert_eq!(aliases.get("oa").as_deref(), Some("com.corp.oa"));
+        assert_eq!(aliases.get("报销").as_deref(), Some("com.corp.expense"));
+
+        aliases.insert(BTreeMap::from([("oa".to_string(), "com.corp.oa2".to_string())])).unwrap();
+        assert_eq!(aliases.get("OA").as_deref(), Some("com.corp.oa2"));
+        let list = aliases.list();
+        assert_eq!(list.len(), 2);
+        assert_eq!((list[0].name.as_str(), list[0].source), ("oa", AliasSource::Api));
+
+        assert!(aliases.remove("OA"));
+        assert_eq!(aliases.get("oa").as_deref(), Some("com.corp.oa"));
+
+        assert!(aliases.insert(BTreeMap::from([("a.b".to_string(), "com.corp.oa".to_string())])).is_err());
+        assert!(aliases.insert(BTreeMap::from([("oa".to_string(), "not a package".to_string())])).is_err());
+
+        let path = std::env::temp_dir().join(format!("scrs_app_aliases_{}.json", std::process::id()));
+        aliases.save(&path).unwrap();
+        let restored = AppAliases::default();
+        restored.load(&path).unwrap();
+        assert_eq!(restored.get("报销").as_deref(), Some("com.corp.expense"));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -8,17 +8,22 @@ use super::touch::LongPressAction;
 use super::touch::DoubleTapAction;
 use super::swipe::SwipeAction;
 use super::swipe::ScrollAction;
+use super::swipe::{ScrollDirection, ScrollDistance};
 use super::input::TypeAction;
 use super::input::PressKeyAction;
 use super::input::KeyCode;
+use super::input::SwitchImeAction;
 use super::navigation::BackAction;
 use super::navigation::HomeAction;
 use super::navigation::RecentAction;
 use super::navigation::NotificationAction;
+use super::navigation::{ReadNotificationsAction, TapNotificationAction, ClearNotificationsAction};
 use super::system::LaunchAction;
 use super::system::WaitAction;
 use super::system::ScreenshotAction;
 use super::system::FinishAction;
+use super::system::{SetLocationAction, ClearLocationAction};
+use super::system::{TransferFileAction, TransferClipboardAction};
 
 /// 所有支持的操作类型（枚举形式）
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,11 +35,19 @@ pub enum ActionEnum {
     Scroll(ScrollAction),
     Type(TypeAction),
     PressKey(PressKeyAction),
+    SwitchIme(SwitchImeAction),
     Back(BackAction),
     Home(HomeAction),
     Recent(RecentAction),
     Notification(NotificationAction),
+    ReadNotifications(ReadNotificationsAction),
+    TapNotification(TapNotificationAction),
+    ClearNotifications(ClearNotificationsAction),
     Launch(LaunchAction),
+    SetLocation(SetLocationAction),
+    ClearLocation(ClearLocationAction),
+    TransferFile(TransferFileAction),
+    TransferClipboard(TransferClipboardAction),
     Wait(WaitAction),
     Screenshot(ScreenshotAction),
     Finish(FinishAction),
@@ -43,14 +56,23 @@ pub enum ActionEnum {
 impl ActionEnum {
     /// 解析 LLM 响应中的操作
     /// 支持两种格式：
-    /// 1. `finish(...)` - 任务完成，括号内是消息（最高优先级，单个）
+    /// 1. `finish(...)` - 任务完成，括号内是消息
     /// 2. `do(...)` - 执行操作，括号内是 `action="...", key=value` 格式（支持多个）
     ///
     /// 返回格式：
-    /// - 如果有 finish(...)，返回 (Some(thinking), vec![finish_action])
-    /// - 如果有多个 do(...)，返回 (Some(thinking), vec![action1, action2, ...])
+    /// - 按出现顺序返回所有 do(...) 操作，如果有 finish(...)，放在最后：(Some(thinking), vec![action1, ..., finish_action])
+    /// - 多个 finish(...) 只保留第一个
     /// - 如果都没有，返回 (Some(thinking), vec![])
+    ///
+    /// 执行时先依次执行 do(...) 操作，全部成功后才认为任务完成
+    ///
+    /// 不知道屏幕分辨率，百分比坐标无法换算，对应操作会被丢弃；已知分辨率时使用 [`Self::parse_for_screen`]
     pub fn parse_from_response(content: &str) -> (Option<String>, Vec<Self>) {
+        Self::parse_for_screen(content, None)
+    }
+
+    /// 解析 LLM 响应中的操作，百分比坐标（如 `y=71%`）按屏幕分辨率 `(宽, 高)` 换算为像素
+    pub fn parse_for_screen(content: &str, screen_size: Option<(u32, u32)>) -> (Option<String>, Vec<Self>) {
         use regex::Regex;
         use tracing::{debug, info, warn};
 
@@ -66,109 +88,101 @@ impl ActionEnum {
             debug!("💭 未找到 <thinking> 标签");
         }
 
-        // 规则 1: 检查 finish(...)
-        // 手动查找匹配的括号，支持多行内容
-        debug!("🔍 检查 finish(...) 模式");
-        if let Some(start_pos) = content.find("finish(") {
-            let mut bracket_count = 0;
-            let mut in_brackets = false;
-            let mut end_pos = start_pos + 6; // 跳过 "finish"
-
-            for (i, c) in content[start_pos + 6..].char_indices() {
-                let actual_i = start_pos + 6 + i;
-                if c == '(' {
-                    bracket_count += 1;
-                    in_brackets = true;
-                } else if c == ')' {
-                    bracket_count -= 1;
-                    if bracket_count == 0 && in_brackets {
-                        end_pos = actual_i;
-                        break;
-                    }
-                }
-            }
+        // 按出现顺序查找所有 do(...) 与 finish(...)
+        debug!("🔍 检查 do(...) / finish(...) 模式");
+        let mut actions = Vec::new();
+        let mut finish = None;
+        let mut search_start = 0;
+
+        loop {
+            let rest = &content[search_start..];
+            let next_do = rest.find("do(").map(|pos| (pos, "do"));
+            let next_finish = rest.find("finish(").map(|pos| (pos, "finish"));
+            let Some((pos, keyword)) = [next_do, next_finish].into_iter().flatten().min_by_key(|(pos, _)| *pos) else {
+                break;
+            };
+            let name_end = search_start + pos + keyword.len();
 
-            if end_pos > start_pos + 6 {
-                let message = content[start_pos + 7..end_pos].trim();
+            // 手动查找匹配的括号，支持多行内容
+            let Some(end_pos) = Self::find_closing_bracket(content, name_end) else {
+                // 没有找到匹配的括号，停止搜索
+                break;
+            };
+            let inner = content[name_end + 1..end_pos].trim();
+            search_start = end_pos + 1;
+
+            if keyword == "finish" {
                 debug!("✅ 匹配到 finish(...) 模式");
-                debug!("💬 message 部分: {}", message);
+                debug!("💬 message 部分: {}", inner);
+                if finish.is_some() {
+                    warn!("⚠️  响应中有多个 finish(...)，只保留第一个");
+                    continue;
+                }
 
                 // 移除可能的 message= 前缀和引号
-                let message = message
+                let message = inner
                     .strip_prefix("message=")
-                    .unwrap_or(message)
+                    .unwrap_or(inner)
                     .trim_matches('"')
                     .trim_matches('\'')
                     .to_string();
 
                 info!("✅ 解析成功: finish action with message='{}'", message);
-                return (thinking, vec![ActionEnum::Finish(FinishAction {
+                finish = Some(ActionEnum::Finish(FinishAction {
                     result: message,
                     success: true,
-                })]);
+                }));
+                continue;
             }
-        }
 
-        // 规则 2: 检查多个 do(...)
-        // 查找所有 do(...) 模式
-        debug!("🔍 检查 do(...) 模式（支持多个）");
-        let mut actions = Vec::new();
-        let mut search_start = 0;
+            debug!("✅ 匹配到 do(...) 模式 #{}", actions.len() + 1);
+            debug!("🔧 参数字符串: {}", inner);
 
-        while let Some(start_pos) = content[search_start..].find("do(") {
-            let actual_start = search_start + start_pos;
-
-            // 手动查找匹配的括号
-            let mut bracket_count = 0;
-            let mut in_brackets = false;
-            let mut end_pos = actual_start + 2; // 跳过 "do"
-
-            for (i, c) in content[actual_start + 2..].char_indices() {
-                let actual_i = actual_start + 2 + i;
-                if c == '(' {
-                    bracket_count += 1;
-                    in_brackets = true;
-                } else if c == ')' {
-                    bracket_count -= 1;
-                    if bracket_count == 0 && in_brackets {
-                        end_pos = actual_i;
-                        break;
-                    }
+            // 解析参数
+            match Self::parse_do_params(inner, screen_size) {
+                Some(action) => {
+                    info!("✅ 解析成功 #{}: {} action", actions.len() + 1, action.action_type());
+                    actions.push(action);
                 }
-            }
-
-            if end_pos > actual_start + 2 {
-                let params_str = content[actual_start + 3..end_pos].trim();
-                debug!("✅ 匹配到 do(...) 模式 #{}", actions.len() + 1);
-                debug!("🔧 参数字符串: {}", params_str);
-
-                // 解析参数
-                match Self::parse_do_params(params_str) {
-                    Some(action) => {
-                        info!("✅ 解析成功 #{}: {} action", actions.len() + 1, action.action_type());
-                        actions.push(action);
-                    }
-                    None => {
-                        warn!("⚠️  do(...) #{} 参数解析失败: {}", actions.len() + 1, params_str);
-                    }
+                None => {
+                    warn!("⚠️  do(...) #{} 参数解析失败: {}", actions.len() + 1, inner);
                 }
-
-                // 移动到下一个位置继续搜索
-                search_start = end_pos + 1;
-            } else {
-                // 没有找到匹配的括号，停止搜索
-                break;
             }
         }
 
-        if !actions.is_empty() {
-            info!("✅ 总共解析到 {} 个 do(...) 操作", actions.len());
-            return (thinking, actions);
+        if actions.is_empty() && finish.is_none() {
+            warn!("❌ 无法解析响应内容，没有匹配到 finish() 或 do() 模式");
+            // 如果没有找到匹配，返回空 Vec
+            return (thinking, vec![]);
         }
 
-        warn!("❌ 无法解析响应内容，没有匹配到 finish() 或 do() 模式");
-        // 如果没有找到匹配，返回空 Vec
-        (thinking, vec![])
+        info!("✅ 总共解析到 {} 个 do(...) 操作{}", actions.len(), if finish.is_some() { "，以及 finish(...)" } else { "" });
+        actions.extend(finish);
+        (thinking, actions)
+    }
+
+    /// 从 `open`（左括号位置）开始查找匹配的右括号
+    fn find_closing_bracket(content: &str, open: usize) -> Option<usize> {
+        let mut bracket_count = 0;
+        for (i, c) in content[open..].char_indices() {
+            if c == '(' {
+                bracket_count += 1;
+            } else if c == ')' {
+                bracket_count -= 1;
+                if bracket_count == 0 {
+                    return Some(open + i);
+                }
+            }
+        }
+        None
+    }
+
+    /// 将 finish 放到操作列表最后，多个 finish 只保留第一个
+    pub(crate) fn order_finish_last(actions: Vec<Self>) -> Vec<Self> {
+        let (finishes, mut ordered): (Vec<Self>, Vec<Self>) =
+            actions.into_iter().partition(|a| matches!(a, ActionEnum::Finish(_)));
+        ordered.extend(finishes.into_iter().next());
+        ordered
     }
 
     /// 解析 do() 括号内的参数
@@ -176,7 +190,8 @@ impl ActionEnum {
     /// - action="Tap", element=[x,y]
     /// - action="Type", text="hello"
     /// - action="Back"
-    fn parse_do_params(params_str: &str) -> Option<Self> {
+    /// - action="Tap", x=320, y=71%（坐标也可以是小数或百分比）
+    fn parse_do_params(params_str: &str, screen_size: Option<(u32, u32)>) -> Option<Self> {
         use regex::Regex;
         use tracing::{debug, info};
 
@@ -214,9 +229,9 @@ impl ActionEnum {
         for cap in array_re.captures_iter(params_str) {
             let key = cap.get(1).unwrap().as_str();
             let values_str = cap.get(2).unwrap().as_str();
-            let values: Vec<u32> = values_str
+            let values: Vec<serde_json::Value> = values_str
                 .split(',')
-                .filter_map(|s| s.trim().parse().ok())
+                .filter_map(|s| Self::parse_coordinate_literal(s.trim()))
                 .collect();
             if !values.is_empty() && key != "action" {
                 debug!("  📌 参数: {} = {:?}", key, values);
@@ -224,8 +239,8 @@ impl ActionEnum {
             }
         }
 
-        // 匹配 key=value 格式（无引号，用于数字）
-        let num_re = Regex::new(r#"(\w+)\s*=\s*(\d+)"#).unwrap();
+        // 匹配 key=value 格式（无引号，用于数字，支持负数、小数和百分比）
+        let num_re = Regex::new(r#"(\w+)\s*=\s*(-?\d+(?:\.\d+)?%?)"#).unwrap();
         for cap in num_re.captures_iter(params_str) {
             let key = cap.get(1).unwrap().as_str();
             let value = cap.get(2).unwrap().as_str();
@@ -245,7 +260,7 @@ impl ActionEnum {
         };
 
         info!("🔄 转换 ParsedAction: action_type={}", parsed.action_type);
-        let result = Self::from_parsed(parsed);
+        let result = Self::from_parsed(parsed, screen_size);
 
         if result.is_some() {
             info!("✅ 成功创建 ActionEnum");
@@ -256,8 +271,86 @@ impl ActionEnum {
         result
     }
 
-    /// 从 ParsedAction 创建 ActionEnum
-    fn from_parsed(parsed: crate::agent::core::traits::ParsedAction) -> Option<Self> {
+    /// 数组中的单个坐标：整数、小数保留为数字，百分比保留为字符串（如 "71%"）
+    fn parse_coordinate_literal(literal: &str) -> Option<serde_json::Value> {
+        if literal.ends_with('%') {
+            literal.trim_end_matches('%').trim().parse::<f64>().ok()?;
+            return Some(serde_json::json!(literal));
+        }
+        if let Ok(n) = literal.parse::<u64>() {
+            return Some(serde_json::json!(n));
+        }
+        literal.parse::<f64>().ok().map(|f| serde_json::json!(f))
+    }
+
+    /// 将单个坐标值转换为像素：支持整数、小数（四舍五入）、数字字符串和百分比
+    ///
+    /// 百分比按 `axis_len`（屏幕宽或高）换算，分辨率未知时无法换算
+    fn parse_coordinate(value: &serde_json::Value, axis_len: Option<u32>) -> Option<u32> {
+        use tracing::warn;
+
+        let pixel = |v: f64| (v.is_finite() && v >= 0.0).then(|| v.round() as u32);
+        if let Some(n) = value.as_u64() {
+            return Some(n as u32);
+        }
+        if let Some(f) = value.as_f64() {
+            return pixel(f);
+        }
+        let text = value.as_str()?.trim();
+        match text.strip_suffix('%') {
+            Some(percent) => {
+                let percent: f64 = percent.trim().parse().ok()?;
+                let Some(axis_len) = axis_len else {
+                    warn!("⚠️  屏幕分辨率未知，无法换算百分比坐标: {}", text);
+                    return None;
+                };
+                let max = axis_len.saturating_sub(1) as f64;
+                pixel((percent / 100.0 * axis_len as f64).min(max))
+            }
+            None => pixel(text.parse().ok()?),
+        }
+    }
+
+    /// 读取坐标点：`key=[x, y]` 数组，或分开的 `x`/`y`（`element` 以外为 `{key}_x`/`{key}_y`）字段
+    fn parse_point(parameters: &serde_json::Value, key: &str, screen_size: Option<(u32, u32)>) -> Option<(u32, u32)> {
+        let (width, height) = screen_size.unzip();
+        if let Some(coords) = parameters.get(key).and_then(|v| v.as_array()) {
+            if coords.len() < 2 {
+                return None;
+            }
+            return Some((Self::parse_coordinate(&coords[0], width)?, Self::parse_coordinate(&coords[1], height)?));
+        }
+        let (x_key, y_key) = if key == "element" {
+            ("x".to_string(), "y".to_string())
+        } else {
+            (format!("{}_x", key), format!("{}_y", key))
+        };
+        Some((
+            Self::parse_coordinate(parameters.get(&x_key)?, width)?,
+            Self::parse_coordinate(parameters.get(&y_key)?, height)?,
+        ))
+    }
+
+    /// 解析按键重复次数：times 为数字或 "max"（音量调到最大/最小）
+    fn parse_repeat(parameters: &serde_json::Value) -> Option<u32> {
+        use super::input::MAX_KEY_REPEAT;
+
+        match parameters.get("times") {
+            None => Some(1),
+            Some(v) => match v.as_str() {
+                Some("max") | Some("min") => Some(15),
+                Some(s) => s.parse().ok(),
+                None => v.as_u64().map(|n| n as u32),
+            }
+            .map(|n: u32| n.clamp(1, MAX_KEY_REPEAT)),
+        }
+    }
+
+    /// 从 ParsedAction 创建 ActionEnum，`screen_size` 用于换算百分比坐标
+    pub(crate) fn from_parsed(
+        parsed: crate::agent::core::traits::ParsedAction,
+        screen_size: Option<(u32, u32)>,
+    ) -> Option<Self> {
         use tracing::debug;
 
         debug!("🎯 from_parsed: 处理 action_type='{}'", parsed.action_type);
@@ -265,69 +358,46 @@ impl ActionEnum {
 
         match parsed.action_type.to_lowercase().as_str() {
             "tap" => {
-                // 尝试从 element 或 x,y 获取坐标
-                if let Some(element) = parsed.parameters.get("element") {
-                    if let Some(coords) = element.as_array() {
-                        if coords.len() >= 2 {
-                            let x = coords[0].as_u64()? as u32;
-                            let y = coords[1].as_u64()? as u32;
-                            return Some(ActionEnum::Tap(TapAction { x, y, description: None }));
-                        }
-                    }
-                }
-                // 尝试从 x, y 字段获取
-                if let (Some(x), Some(y)) = (
-                    parsed.parameters.get("x").and_then(|v| v.as_u64()).map(|v| v as u32),
-                    parsed.parameters.get("y").and_then(|v| v.as_u64()).map(|v| v as u32),
-                ) {
-                    return Some(ActionEnum::Tap(TapAction { x, y, description: None }));
-                }
-                None
+                // 从 element 或 x,y 获取坐标
+                let (x, y) = Self::parse_point(&parsed.parameters, "element", screen_size)?;
+                Some(ActionEnum::Tap(TapAction { x, y, description: None }))
             }
             "long_press" => {
-                if let Some(element) = parsed.parameters.get("element") {
-                    if let Some(coords) = element.as_array() {
-                        if coords.len() >= 2 {
-                            let x = coords[0].as_u64()? as u32;
-                            let y = coords[1].as_u64()? as u32;
-                            let duration_ms = parsed.parameters.get("duration_ms")
-                                .and_then(|v| v.as_u64()).map(|v| v as u32)
-                                .unwrap_or(1000);
-                            return Some(ActionEnum::LongPress(LongPressAction { x, y, duration_ms, description: None }));
-                        }
-                    }
-                }
-                None
+                let (x, y) = Self::parse_point(&parsed.parameters, "element", screen_size)?;
+                let duration_ms = parsed.parameters.get("duration_ms")
+                    .and_then(|v| v.as_u64()).map(|v| v as u32)
+                    .unwrap_or(1000);
+                Some(ActionEnum::LongPress(LongPressAction { x, y, duration_ms, description: None }))
             }
             "double_tap" => {
-                if let Some(element) = parsed.parameters.get("element") {
-                    if let Some(coords) = element.as_array() {
-                        if coords.len() >= 2 {
-                            let x = coords[0].as_u64()? as u32;
-                            let y = coords[1].as_u64()? as u32;
-                            return Some(ActionEnum::DoubleTap(DoubleTapAction { x, y, description: None }));
-                        }
-                    }
-                }
-                None
+                let (x, y) = Self::parse_point(&parsed.parameters, "element", screen_size)?;
+                Some(ActionEnum::DoubleTap(DoubleTapAction { x, y, description: None }))
             }
             "swipe" => {
-                if let (Some(start), Some(end)) = (
-                    parsed.parameters.get("start").and_then(|v| v.as_array()),
-                    parsed.parameters.get("end").and_then(|v| v.as_array()),
-                ) {
-                    if start.len() >= 2 && end.len() >= 2 {
-                        let start_x = start[0].as_u64()? as u32;
-                        let start_y = start[1].as_u64()? as u32;
-                        let end_x = end[0].as_u64()? as u32;
-                        let end_y = end[1].as_u64()? as u32;
-                        let duration_ms = parsed.parameters.get("duration_ms")
-                            .and_then(|v| v.as_u64()).map(|v| v as u32)
-                            .unwrap_or(500);
-                        return Some(ActionEnum::Swipe(SwipeAction { start_x, start_y, end_x, end_y, duration_ms, description: None }));
-                    }
-                }
-                None
+                let (start_x, start_y) = Self::parse_point(&parsed.parameters, "start", screen_size)?;
+                let (end_x, end_y) = Self::parse_point(&parsed.parameters, "end", screen_size)?;
+                let duration_ms = parsed.parameters.get("duration_ms")
+                    .and_then(|v| v.as_u64()).map(|v| v as u32)
+                    .unwrap_or(500);
+                Some(ActionEnum::Swipe(SwipeAction { start_x, start_y, end_x, end_y, duration_ms, description: None }))
+            }
+            "scroll" => {
+                let direction = parsed.parameters.get("direction")
+                    .and_then(|v| v.as_str())
+                    .and_then(ScrollDirection::from_name)?;
+                // distance 可以是 short/half/full，也可以是百分比数字
+                let distance_pct = match parsed.parameters.get("distance").and_then(|v| v.as_str()) {
+                    Some(d) => ScrollDistance::from_name(d)
+                        .map(|d| d.percent())
+                        .or_else(|| d.trim_end_matches('%').parse().ok())?,
+                    None => ScrollDistance::Half.percent(),
+                };
+                let anchor = Self::parse_point(&parsed.parameters, "element", screen_size);
+                let duration_ms = parsed.parameters.get("duration_ms")
+                    .and_then(|v| v.as_str().and_then(|s| s.parse().ok()).or_else(|| v.as_u64()))
+                    .map(|v| v as u32)
+                    .unwrap_or(400);
+                Some(ActionEnum::Scroll(ScrollAction { direction, distance_pct, duration_ms, anchor, description: None }))
             }
             "type" => {
                 if let Some(text) = parsed.parameters.get("text").and_then(|v| v.as_str()) {
@@ -335,29 +405,66 @@ impl ActionEnum {
                 }
                 None
             }
-            "press_key" => {
-                if let Some(keycode) = parsed.parameters.get("keycode").and_then(|v| v.as_u64()) {
-                    let key_code = match keycode as u32 {
-                        3 => KeyCode::Home,
-                        4 => KeyCode::Back,
-                        66 => KeyCode::Enter,
-                        111 => KeyCode::Escape,
-                        67 => KeyCode::Delete,
-                        61 => KeyCode::Tab,
-                        24 => KeyCode::VolumeUp,
-                        25 => KeyCode::VolumeDown,
-                        26 => KeyCode::Power,
-                        27 => KeyCode::Camera,
-                        _ => KeyCode::Back,
-                    };
-                    return Some(ActionEnum::PressKey(PressKeyAction { keycode: key_code, description: None }));
-                }
-                None
+            "press_key" | "press key" | "key" => {
+                // 支持 keycode 数字或 key 名称
+                let key_code = parsed.parameters.get("keycode")
+                    .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+                    .and_then(|code| KeyCode::from_android_keycode(code as u32))
+                    .or_else(|| parsed.parameters.get("key")
+                        .and_then(|v| v.as_str())
+                        .and_then(KeyCode::from_name))?;
+                Some(ActionEnum::PressKey(PressKeyAction::new(key_code).repeated(Self::parse_repeat(&parsed.parameters)?)))
+            }
+            "switch ime" | "switch_ime" | "ime" => {
+                let ime = parsed.parameters.get("ime").and_then(|v| v.as_str())?;
+                Some(ActionEnum::SwitchIme(SwitchImeAction { ime: ime.to_string(), description: None }))
+            }
+            "volume" => {
+                let key_code = match parsed.parameters.get("direction").and_then(|v| v.as_str()).unwrap_or("up") {
+                    "down" => KeyCode::VolumeDown,
+                    "mute" => KeyCode::VolumeMute,
+                    _ => KeyCode::VolumeUp,
+                };
+                Some(ActionEnum::PressKey(PressKeyAction::new(key_code).repeated(Self::parse_repeat(&parsed.parameters)?)))
+            }
+            "volume up" | "volume_up" => Some(ActionEnum::PressKey(
+                PressKeyAction::new(KeyCode::VolumeUp).repeated(Self::parse_repeat(&parsed.parameters)?),
+            )),
+            "volume down" | "volume_down" => Some(ActionEnum::PressKey(
+                PressKeyAction::new(KeyCode::VolumeDown).repeated(Self::parse_repeat(&parsed.parameters)?),
+            )),
+            "mute" => Some(ActionEnum::PressKey(PressKeyAction::new(KeyCode::VolumeMute))),
+            "power" => Some(ActionEnum::PressKey(PressKeyAction::new(KeyCode::Power))),
+            "media" => {
+                let key_code = match parsed.parameters.get("key").and_then(|v| v.as_str()) {
+                    Some(key) => KeyCode::from_name(key)?,
+                    None => KeyCode::MediaPlayPause,
+                };
+                Some(ActionEnum::PressKey(PressKeyAction::new(key_code)))
+            }
+            "take screenshot" | "take_screenshot" | "screenshot key" => {
+                Some(ActionEnum::PressKey(PressKeyAction::new(KeyCode::Screenshot)))
             }
             "back" => Some(ActionEnum::Back(BackAction { description: None })),
             "home" => Some(ActionEnum::Home(HomeAction { description: None })),
             "recent" => Some(ActionEnum::Recent(RecentAction { description: None })),
             "notification" => Some(ActionEnum::Notification(NotificationAction { description: None })),
+            "read notifications" | "read_notifications" => {
+                Some(ActionEnum::ReadNotifications(ReadNotificationsAction { description: None }))
+            }
+            "tap notification" | "tap_notification" => {
+                let index = parsed.parameters.get("index")
+                    .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+                    .map(|v| v as usize);
+                let text = parsed.parameters.get("text").and_then(|v| v.as_str()).map(|s| s.to_string());
+                if index.is_none() && text.is_none() {
+                    return None;
+                }
+                Some(ActionEnum::TapNotification(TapNotificationAction { index, text, description: None }))
+            }
+            "clear notifications" | "clear_notifications" => {
+                Some(ActionEnum::ClearNotifications(ClearNotificationsAction { description: None }))
+            }
             "launch" => {
                 if let Some(app) = parsed.parameters.get("app").and_then(|v| v.as_str())
                     .or_else(|| parsed.parameters.get("app_name").and_then(|v| v.as_str())) {
@@ -369,6 +476,38 @@ impl ActionEnum {
                 }
                 None
             }
+            "set location" | "set_location" => {
+                let coord = |key: &str| parsed.parameters.get(key).and_then(|v| {
+                    v.as_f64().or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+                });
+                let location = crate::agent::executor::GeoLocation {
+                    latitude: coord("latitude").or_else(|| coord("lat"))?,
+                    longitude: coord("longitude").or_else(|| coord("lng")).or_else(|| coord("lon"))?,
+                    altitude: coord("altitude"),
+                };
+                Some(ActionEnum::SetLocation(SetLocationAction { location, description: None }))
+            }
+            "clear location" | "clear_location" => {
+                Some(ActionEnum::ClearLocation(ClearLocationAction { description: None }))
+            }
+            "send file" | "transfer_file" => {
+                let param = |key: &str| parsed.parameters.get(key).and_then(|v| v.as_str()).map(str::to_string);
+                Some(ActionEnum::TransferFile(TransferFileAction {
+                    target_device: param("device").or_else(|| param("target_device"))?,
+                    source_path: param("path").or_else(|| param("source_path"))?,
+                    target_path: param("target_path"),
+                    description: None,
+                }))
+            }
+            "send clipboard" | "transfer_clipboard" => {
+                let target_device = parsed.parameters.get("device")
+                    .or_else(|| parsed.parameters.get("target_device"))
+                    .and_then(|v| v.as_str())?;
+                Some(ActionEnum::TransferClipboard(TransferClipboardAction {
+                    target_device: target_device.to_string(),
+                    description: None,
+                }))
+            }
             "wait" => {
                 let duration_ms = parsed.parameters.get("duration_ms")
                     .and_then(|v| v.as_u64()).map(|v| v as u32)
@@ -394,6 +533,18 @@ impl ActionEnum {
     }
 }
 
+/// 没有屏幕尺寸的转换（百分比坐标无法换算时转换失败）
+impl TryFrom<crate::agent::core::traits::ParsedAction> for ActionEnum {
+    type Error = crate::agent::core::traits::ModelError;
+
+    fn try_from(parsed: crate::agent::core::traits::ParsedAction) -> Result<Self, Self::Error> {
+        let action_type = parsed.action_type.clone();
+        Self::from_parsed(parsed, None).ok_or_else(|| {
+            crate::agent::core::traits::ModelError::ParseError(format!("无法识别的操作: {}", action_type))
+        })
+    }
+}
+
 impl Action for ActionEnum {
     async fn execute(&self, device: &dyn Device) -> Result<ActionResult, AppError> {
         match self {
@@ -404,11 +555,19 @@ impl Action for ActionEnum {
             ActionEnum::Scroll(a) => a.execute(device).await,
             ActionEnum::Type(a) => a.execute(device).await,
             ActionEnum::PressKey(a) => a.execute(device).await,
+            ActionEnum::SwitchIme(a) => a.execute(device).await,
             ActionEnum::Back(a) => a.execute(device).await,
             ActionEnum::Home(a) => a.execute(device).await,
             ActionEnum::Recent(a) => a.execute(device).await,
             ActionEnum::Notification(a) => a.execute(device).await,
+            ActionEnum::ReadNotifications(a) => a.execute(device).await,
+            ActionEnum::TapNotification(a) => a.execute(device).await,
+            ActionEnum::ClearNotifications(a) => a.execute(device).await,
             ActionEnum::Launch(a) => a.execute(device).await,
+            ActionEnum::SetLocation(a) => a.execute(device).await,
+            ActionEnum::ClearLocation(a) => a.execute(device).await,
+            ActionEnum::TransferFile(a) => a.execute(device).await,
+            ActionEnum::TransferClipboard(a) => a.execute(device).await,
             ActionEnum::Wait(a) => a.execute(device).await,
             ActionEnum::Screenshot(a) => a.execute(device).await,
             ActionEnum::Finish(a) => a.execute(device).await,
@@ -424,11 +583,19 @@ impl Action for ActionEnum {
             ActionEnum::Scroll(a) => a.validate(),
             ActionEnum::Type(a) => a.validate(),
             ActionEnum::PressKey(a) => a.validate(),
+            ActionEnum::SwitchIme(a) => a.validate(),
             ActionEnum::Back(a) => a.validate(),
             ActionEnum::Home(a) => a.validate(),
             ActionEnum::Recent(a) => a.validate(),
             ActionEnum::Notification(a) => a.validate(),
+            ActionEnum::ReadNotifications(a) => a.validate(),
+            ActionEnum::TapNotification(a) => a.validate(),
+            ActionEnum::ClearNotifications(a) => a.validate(),
             ActionEnum::Launch(a) => a.validate(),
+            ActionEnum::SetLocation(a) => a.validate(),
+            ActionEnum::ClearLocation(a) => a.validate(),
+            ActionEnum::TransferFile(a) => a.validate(),
+            ActionEnum::TransferClipboard(a) => a.validate(),
             ActionEnum::Wait(a) => a.validate(),
             ActionEnum::Screenshot(a) => a.validate(),
             ActionEnum::Finish(a) => a.validate(),
@@ -444,11 +611,19 @@ impl Action for ActionEnum {
             ActionEnum::Scroll(a) => a.description(),
             ActionEnum::Type(a) => a.description(),
             ActionEnum::PressKey(a) => a.description(),
+            ActionEnum::SwitchIme(a) => a.description(),
             ActionEnum::Back(a) => a.description(),
             ActionEnum::Home(a) => a.description(),
             ActionEnum::Recent(a) => a.description(),
             ActionEnum::Notification(a) => a.description(),
+            ActionEnum::ReadNotifications(a) => a.description(),
+            ActionEnum::TapNotification(a) => a.description(),
+            ActionEnum::ClearNotifications(a) => a.description(),
             ActionEnum::Launch(a) => a.description(),
+            ActionEnum::SetLocation(a) => a.description(),
+            ActionEnum::ClearLocation(a) => a.description(),
+            ActionEnum::TransferFile(a) => a.description(),
+            ActionEnum::TransferClipboard(a) => a.description(),
             ActionEnum::Wait(a) => a.description(),
             ActionEnum::Screenshot(a) => a.description(),
             ActionEnum::Finish(a) => a.description(),
@@ -464,11 +639,19 @@ impl Action for ActionEnum {
             ActionEnum::Scroll(_) => "scroll".to_string(),
             ActionEnum::Type(_) => "type".to_string(),
             ActionEnum::PressKey(_) => "press_key".to_string(),
+            ActionEnum::SwitchIme(_) => "switch_ime".to_string(),
             ActionEnum::Back(_) => "back".to_string(),
             ActionEnum::Home(_) => "home".to_string(),
             ActionEnum::Recent(_) => "recent".to_string(),
             ActionEnum::Notification(_) => "notification".to_string(),
+            ActionEnum::ReadNotifications(_) => "read_notifications".to_string(),
+            ActionEnum::TapNotification(_) => "tap_notification".to_string(),
+            ActionEnum::ClearNotifications(_) => "clear_notifications".to_string(),
             ActionEnum::Launch(_) => "launch".to_string(),
+            ActionEnum::SetLocation(_) => "set_location".to_string(),
+            ActionEnum::ClearLocation(_) => "clear_location".to_string(),
+            ActionEnum::TransferFile(_) => "transfer_file".to_string(),
+            ActionEnum::TransferClipboard(_) => "transfer_clipboard".to_string(),
             ActionEnum::Wait(_) => "wait".to_string(),
             ActionEnum::Screenshot(_) => "screenshot".to_string(),
             ActionEnum::Finish(_) => "finish".to_string(),
@@ -483,12 +666,20 @@ impl Action for ActionEnum {
             ActionEnum::Swipe(a) => a.duration_ms + 100,
             ActionEnum::Scroll(a) => a.duration_ms + 100,
             ActionEnum::Type(_) => 200,
-            ActionEnum::PressKey(_) => 100,
+            ActionEnum::PressKey(a) => a.estimated_duration(),
+            ActionEnum::SwitchIme(_) => 500,
             ActionEnum::Back(_) => 100,
             ActionEnum::Home(_) => 100,
             ActionEnum::Recent(_) => 100,
             ActionEnum::Notification(_) => 300,
+            ActionEnum::ReadNotifications(_) => 500,
+            ActionEnum::TapNotification(_) => 2000,
+            ActionEnum::ClearNotifications(_) => 500,
             ActionEnum::Launch(_) => 2000,
+            ActionEnum::SetLocation(_) => 1000,
+            ActionEnum::ClearLocation(_) => 500,
+            ActionEnum::TransferFile(_) => 1000,
+            ActionEnum::TransferClipboard(_) => 500,
             ActionEnum::Wait(a) => a.duration_ms,
             ActionEnum::Screenshot(_) => 500,
             ActionEnum::Finish(_) => 0,
@@ -507,11 +698,19 @@ impl ActionEnum {
             "scroll" => ActionEnum::Scroll(serde_json::from_value(params)?),
             "type" => ActionEnum::Type(serde_json::from_value(params)?),
             "press_key" => ActionEnum::PressKey(serde_json::from_value(params)?),
+            "switch_ime" => ActionEnum::SwitchIme(serde_json::from_value(params)?),
             "back" => ActionEnum::Back(serde_json::from_value(params)?),
             "home" => ActionEnum::Home(serde_json::from_value(params)?),
             "recent" => ActionEnum::Recent(serde_json::from_value(params)?),
             "notification" => ActionEnum::Notification(serde_json::from_value(params)?),
+            "read_notifications" => ActionEnum::ReadNotifications(serde_json::from_value(params)?),
+            "tap_notification" => ActionEnum::TapNotification(serde_json::from_value(params)?),
+            "clear_notifications" => ActionEnum::ClearNotifications(serde_json::from_value(params)?),
             "launch" => ActionEnum::Launch(serde_json::from_value(params)?),
+            "set_location" => ActionEnum::SetLocation(serde_json::from_value(params)?),
+            "clear_location" => ActionEnum::ClearLocation(serde_json::from_value(params)?),
+            "transfer_file" => ActionEnum::TransferFile(serde_json::from_value(params)?),
+            "transfer_clipboard" => ActionEnum::TransferClipboard(serde_json::from_value(params)?),
             "wait" => ActionEnum::Wait(serde_json::from_value(params)?),
             "screenshot" => ActionEnum::Screenshot(serde_json::from_value(params)?),
             "finish" => ActionEnum::Finish(serde_json::from_value(params)?),
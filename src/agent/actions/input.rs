@@ -3,6 +3,22 @@ use crate::agent::core::traits::{Action, Device, ActionResult, ActionError};
 use crate::error::AppError;
 use std::time::Instant;
 
+/// ADBKeyboard 输入法 ID，可通过广播输入任意 Unicode 文本
+pub const ADB_KEYBOARD_IME: &str = "com.android.adbkeyboard/.AdbIME";
+/// ADBKeyboard 接收 base64 文本的广播 action
+pub const ADB_KEYBOARD_INPUT_B64: &str = "ADB_INPUT_B64";
+
+/// 输入法 ID 是否为合法的组件名（`包名/服务名`）；ID 会拼入设备 shell 命令，不允许其它字符
+pub fn is_valid_ime_id(ime: &str) -> bool {
+    let Some((package, service)) = ime.split_once('/') else {
+        return false;
+    };
+    let valid = |part: &str, extra: &[char]| {
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || extra.contains(&c))
+    };
+    valid(package, &[]) && valid(service, &['$'])
+}
+
 /// 输入文本操作
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeAction {
@@ -25,8 +41,23 @@ impl Action for TypeAction {
 
         let start = Instant::now();
 
+        // `input text` 不支持非 ASCII 文本，临时切换到 ADBKeyboard 输入后恢复
+        let previous_ime = if self.text.is_ascii() {
+            None
+        } else {
+            switch_to_adb_keyboard(device).await
+        };
+
         debug!("   调用 device.input_text...");
-        device.input_text(&self.text).await?;
+        let result = device.input_text(&self.text).await;
+
+        if let Some(previous) = previous_ime {
+            debug!("   恢复输入法: {}", previous);
+            if let Err(e) = device.set_input_method(&previous).await {
+                tracing::warn!("恢复输入法 {} 失败: {}", previous, e);
+            }
+        }
+        result?;
 
         let elapsed = start.elapsed();
         info!("   ✅ 输入完成 (耗时: {}ms)", elapsed.as_millis());
@@ -63,8 +94,74 @@ impl Action for TypeAction {
     }
 }
 
-/// 按键码
+/// 在已安装 ADBKeyboard 且当前未使用它时切换过去，返回切换前的输入法
+async fn switch_to_adb_keyboard(device: &dyn Device) -> Option<String> {
+    let current = device.current_input_method().await.ok()?;
+    if current.as_deref() == Some(ADB_KEYBOARD_IME) {
+        return None;
+    }
+    let installed = device.list_input_methods().await.ok()?;
+    if !installed.iter().any(|ime| ime == ADB_KEYBOARD_IME) {
+        tracing::warn!("未安装 ADBKeyboard，非 ASCII 文本可能无法输入");
+        return None;
+    }
+    match device.set_input_method(ADB_KEYBOARD_IME).await {
+        Ok(()) => current,
+        Err(e) => {
+            tracing::warn!("切换到 ADBKeyboard 失败: {}", e);
+            None
+        }
+    }
+}
+
+/// 切换输入法操作
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchImeAction {
+    /// 输入法 ID，例如 com.android.adbkeyboard/.AdbIME
+    pub ime: String,
+    pub description: Option<String>,
+}
+
+impl Action for SwitchImeAction {
+    fn action_type(&self) -> String {
+        "switch_ime".to_string()
+    }
+
+    async fn execute(&self, device: &dyn Device) -> Result<ActionResult, AppError> {
+        let start = Instant::now();
+        let installed = device.list_input_methods().await?;
+        if !installed.iter().any(|ime| ime == &self.ime) {
+            return Ok(ActionResult::failure(
+                format!("输入法 {} 未安装，可用输入法: {}", self.ime, installed.join(", ")),
+                start.elapsed().as_millis() as u32,
+            ));
+        }
+        device.set_input_method(&self.ime).await?;
+        Ok(ActionResult::success(
+            self.description(),
+            start.elapsed().as_millis() as u32,
+        ))
+    }
+
+    fn validate(&self) -> Result<(), ActionError> {
+        if !is_valid_ime_id(&self.ime) {
+            return Err(ActionError::InvalidParameters(format!(
+                "无效的输入法 ID: {:?}（格式: 包名/服务名）",
+                self.ime
+            )));
+        }
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        self.description
+            .clone()
+            .unwrap_or_else(|| format!("切换输入法: {}", self.ime))
+    }
+}
+
+/// 按键码
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum KeyCode {
     Enter,
     Escape,
@@ -75,8 +172,14 @@ pub enum KeyCode {
     Back,
     VolumeUp,
     VolumeDown,
+    VolumeMute,
     Power,
     Camera,
+    MediaPlayPause,
+    MediaNext,
+    MediaPrevious,
+    /// 系统截图键（截图保存在设备上）
+    Screenshot,
 }
 
 impl KeyCode {
@@ -92,19 +195,121 @@ impl KeyCode {
             KeyCode::Back => 4,
             KeyCode::VolumeUp => 24,
             KeyCode::VolumeDown => 25,
+            KeyCode::VolumeMute => 164,
             KeyCode::Power => 26,
             KeyCode::Camera => 27,
+            KeyCode::MediaPlayPause => 85,
+            KeyCode::MediaNext => 87,
+            KeyCode::MediaPrevious => 88,
+            KeyCode::Screenshot => 120,
+        }
+    }
+
+    /// 从 Android keycode 转换，不支持的按键返回 None
+    pub fn from_android_keycode(keycode: u32) -> Option<Self> {
+        Some(match keycode {
+            3 => KeyCode::Home,
+            4 => KeyCode::Back,
+            24 => KeyCode::VolumeUp,
+            25 => KeyCode::VolumeDown,
+            26 => KeyCode::Power,
+            27 => KeyCode::Camera,
+            61 => KeyCode::Tab,
+            66 => KeyCode::Enter,
+            67 => KeyCode::Delete,
+            85 => KeyCode::MediaPlayPause,
+            87 => KeyCode::MediaNext,
+            88 => KeyCode::MediaPrevious,
+            111 => KeyCode::Escape,
+            120 => KeyCode::Screenshot,
+            164 => KeyCode::VolumeMute,
+            _ => return None,
+        })
+    }
+
+    /// 从按键名称解析（大小写、空格、下划线不敏感）
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalized: String = name
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .flat_map(|c| c.to_lowercase())
+            .collect();
+        Some(match normalized.as_str() {
+            "enter" => KeyCode::Enter,
+            "escape" | "esc" => KeyCode::Escape,
+            "delete" | "del" => KeyCode::Delete,
+            "backspace" => KeyCode::Backspace,
+            "tab" => KeyCode::Tab,
+            "home" => KeyCode::Home,
+            "back" => KeyCode::Back,
+            "volumeup" => KeyCode::VolumeUp,
+            "volumedown" => KeyCode::VolumeDown,
+            "volumemute" | "mute" => KeyCode::VolumeMute,
+            "power" => KeyCode::Power,
+            "camera" => KeyCode::Camera,
+            "mediaplaypause" | "playpause" | "play" | "pause" => KeyCode::MediaPlayPause,
+            "medianext" | "next" => KeyCode::MediaNext,
+            "mediaprevious" | "previous" | "prev" => KeyCode::MediaPrevious,
+            "screenshot" | "takescreenshot" => KeyCode::Screenshot,
+            _ => return None,
+        })
+    }
+
+    /// 中文名称，用于操作描述
+    fn label(&self) -> &'static str {
+        match self {
+            KeyCode::Enter => "回车键",
+            KeyCode::Escape => "Esc 键",
+            KeyCode::Delete | KeyCode::Backspace => "删除键",
+            KeyCode::Tab => "Tab 键",
+            KeyCode::Home => "Home 键",
+            KeyCode::Back => "返回键",
+            KeyCode::VolumeUp => "音量+",
+            KeyCode::VolumeDown => "音量-",
+            KeyCode::VolumeMute => "静音键",
+            KeyCode::Power => "电源键",
+            KeyCode::Camera => "相机键",
+            KeyCode::MediaPlayPause => "播放/暂停",
+            KeyCode::MediaNext => "下一曲",
+            KeyCode::MediaPrevious => "上一曲",
+            KeyCode::Screenshot => "截图键",
         }
     }
 }
 
+/// 单次按键操作允许的最大重复次数（音量从 0 调到最大约需 15 次）
+pub const MAX_KEY_REPEAT: u32 = 30;
+
+/// 连续按键之间的间隔（毫秒）
+const KEY_REPEAT_INTERVAL_MS: u64 = 100;
+
+fn default_repeat() -> u32 {
+    1
+}
+
 /// 按键操作
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PressKeyAction {
     pub keycode: KeyCode,
+    /// 连续按键次数
+    #[serde(default = "default_repeat")]
+    pub repeat: u32,
     pub description: Option<String>,
 }
 
+impl PressKeyAction {
+    /// 按一次指定按键
+    pub fn new(keycode: KeyCode) -> Self {
+        Self { keycode, repeat: 1, description: None }
+    }
+
+    /// 设置连续按键次数
+    pub fn repeated(mut self, repeat: u32) -> Self {
+        self.repeat = repeat;
+        self
+    }
+}
+
 impl Action for PressKeyAction {
     fn action_type(&self) -> String {
         "press_key".to_string()
@@ -112,22 +317,77 @@ impl Action for PressKeyAction {
 
     async fn execute(&self, device: &dyn Device) -> Result<ActionResult, AppError> {
         let start = Instant::now();
-        device.press_key(self.keycode.to_android_keycode()).await?;
+        let keycode = self.keycode.to_android_keycode();
+        for i in 0..self.repeat {
+            if i > 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(KEY_REPEAT_INTERVAL_MS)).await;
+            }
+            device.press_key(keycode).await?;
+        }
         Ok(ActionResult::success(
-            self.description
-                .clone()
-                .unwrap_or_else(|| format!("按键: {:?}", self.keycode)),
+            self.description(),
             start.elapsed().as_millis() as u32,
         ))
     }
 
     fn validate(&self) -> Result<(), ActionError> {
+        if self.repeat == 0 || self.repeat > MAX_KEY_REPEAT {
+            return Err(ActionError::InvalidParameters(format!(
+                "按键次数必须在 1-{} 之间: {}",
+                MAX_KEY_REPEAT, self.repeat
+            )));
+        }
         Ok(())
     }
 
     fn description(&self) -> String {
-        self.description
-            .clone()
-            .unwrap_or_else(|| format!("按键: {:?}", self.keycode))
+        self.description.clone().unwrap_or_else(|| {
+            if self.repeat > 1 {
+                format!("按键: {} x{}", self.keycode.label(), self.repeat)
+            } else {
+                format!("按键: {}", self.keycode.label())
+            }
+        })
+    }
+
+    fn estimated_duration(&self) -> u32 {
+        self.repeat * (KEY_REPEAT_INTERVAL_MS as u32 + 100)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::actions::ActionEnum;
+
+    fn parse_key(response: &str) -> PressKeyAction {
+        match ActionEnum::parse_from_response(response).1.as_slice() {
+            [ActionEnum::PressKey(action)] => action.clone(),
+            other => panic!("unexpected actions: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_convenience_key_actions() {
+        let max_volume = parse_key(r#"do(action="Volume", direction="up", times="max")"#);
+        assert_eq!(max_volume.keycode, KeyCode::VolumeUp);
+        assert_eq!(max_volume.repeat, 15);
+
+        assert_eq!(parse_key(r#"do(action="Power")"#).keycode, KeyCode::Power);
+        assert_eq!(parse_key(r#"do(action="Media", key="next")"#).keycode, KeyCode::MediaNext);
+        assert_eq!(parse_key(r#"do(action="Take Screenshot")"#).keycode, KeyCode::Screenshot);
+        assert_eq!(parse_key(r#"do(action="Press Key", key="volume_down")"#).keycode, KeyCode::VolumeDown);
+        assert_eq!(parse_key(r#"do(action="press_key", keycode=85)"#).keycode, KeyCode::MediaPlayPause);
+    }
+
+    #[test]
+    fn test_ime_id_validation() {
+        assert!(is_valid_ime_id(ADB_KEYBOARD_IME));
+        assert!(is_valid_ime_id("com.google.android.inputmethod.latin/com.android.inputmethod.latin.LatinIME"));
+        assert!(is_valid_ime_id("com.example/.Outer$Ime"));
+        assert!(!is_valid_ime_id("com.a/.B; rm -rf /sdcard"));
+        assert!(!is_valid_ime_id("com.a/.B/C"));
+        assert!(!is_valid_ime_id("/.AdbIME"));
+        assert!(!is_valid_ime_id("com.android.adbkeyboard"));
     }
 }
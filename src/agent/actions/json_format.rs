@@ -0,0 +1,123 @@
+//! JSON 格式的操作输出
+//!
+//! 对能严格遵守 JSON 格式的模型，要求其只输出一个 JSON 对象而不是 `do(...)` 伪代码，
+//! 按结构校验后转换为操作；模型没有按 JSON 回答时退回伪代码解析。
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::base::ActionEnum;
+use crate::agent::core::traits::ParsedAction;
+
+/// 模型输出操作的格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionFormat {
+    /// `do(action="Tap", element=[x,y])` 伪代码
+    #[default]
+    PseudoCode,
+    /// `{"thinking": "...", "actions": [{"action": "Tap", "element": [x, y]}]}`
+    Json,
+}
+
+/// JSON 格式的模型回复
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonActionResponse {
+    #[serde(default)]
+    thinking: Option<String>,
+    actions: Vec<JsonAction>,
+}
+
+/// 单个操作：`action` 为操作名称（与 `do(action=...)` 相同），其余字段为参数
+#[derive(Debug, Deserialize)]
+struct JsonAction {
+    action: String,
+    #[serde(flatten)]
+    parameters: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ActionEnum {
+    /// 按指定格式解析模型回复，JSON 解析失败时退回伪代码解析
+    ///
+    /// `screen_size` 为屏幕分辨率 `(宽, 高)`，用于换算百分比坐标
+    pub fn parse_with_format(
+        content: &str,
+        format: ActionFormat,
+        screen_size: Option<(u32, u32)>,
+    ) -> (Option<String>, Vec<Self>) {
+        if format == ActionFormat::Json {
+            match Self::parse_json_response(content, screen_size) {
+                Ok(parsed) => return parsed,
+                Err(e) => warn!("JSON 操作解析失败，退回伪代码解析: {}", e),
+            }
+        }
+        Self::parse_for_screen(content, screen_size)
+    }
+
+    /// 解析 JSON 格式的回复（允许包裹在 ```json 代码块中）
+    pub fn parse_json_response(
+        content: &str,
+        screen_size: Option<(u32, u32)>,
+    ) -> Result<(Option<String>, Vec<Self>), String> {
+        let json = extract_json_object(content).ok_or_else(|| "回复中没有 JSON 对象".to_string())?;
+        let response: JsonActionResponse =
+            serde_json::from_str(json).map_err(|e| format!("JSON 不符合格式要求: {}", e))?;
+        if response.actions.is_empty() {
+            return Err("actions 为空".to_string());
+        }
+
+        let mut actions = Vec::with_capacity(response.actions.len());
+        for action in response.actions {
+            let parsed = ParsedAction {
+                action_type: action.action.clone(),
+                parameters: serde_json::Value::Object(action.parameters),
+                reasoning: String::new(),
+            };
+            let action = Self::from_parsed(parsed, screen_size).ok_or_else(|| format!("无法识别的操作或参数: {}", action.action))?;
+            actions.push(action);
+        }
+        debug!("JSON 格式解析到 {} 个操作", actions.len());
+
+        // 与伪代码解析一致：先执行其它操作，finish 放在最后
+        Ok((response.thinking, Self::order_finish_last(actions)))
+    }
+}
+
+/// 取出回复中第一个 `{` 到最后一个 `}` 之间的内容
+fn extract_json_object(content: &str) -> Option<&str> {
+    let start = content.find('{')?;
+    let end = content.rfind('}')?;
+    (end > start).then(|| &content[start..=end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json_actions() {
+        let content = r#"```json
+{"thinking": "点击搜索框后输入", "actions": [
+  {"action": "Tap", "element": [540, 200]},
+  {"action": "Type", "text": "天气"}
+]}
+```"#;
+        let (thinking, actions) = ActionEnum::parse_with_format(content, ActionFormat::Json, None);
+        assert_eq!(thinking.as_deref(), Some("点击搜索框后输入"));
+        assert_eq!(actions.len(), 2);
+        assert!(matches!(&actions[0], ActionEnum::Tap(tap) if tap.x == 540 && tap.y == 200));
+        assert!(matches!(&actions[1], ActionEnum::Type(t) if t.text == "天气"));
+
+        let (_, actions) = ActionEnum::parse_with_format(r#"{"actions": [{"action": "finish", "message": "完成"}]}"#, ActionFormat::Json, None);
+        assert!(matches!(&actions[0], ActionEnum::Finish(f) if f.result == "完成"));
+
+        // 未知字段与未知操作都不通过校验
+        assert!(ActionEnum::parse_json_response(r#"{"actions": [], "extra": 1}"#, None).is_err());
+        assert!(ActionEnum::parse_json_response(r#"{"actions": [{"action": "Fly"}]}"#, None).is_err());
+
+        // 模型仍用伪代码回答时退回伪代码解析
+        let (_, actions) = ActionEnum::parse_with_format(r#"do(action="Back")"#, ActionFormat::Json, None);
+        assert!(matches!(&actions[0], ActionEnum::Back(_)));
+    }
+}
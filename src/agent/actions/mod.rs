@@ -1,13 +1,17 @@
+pub mod aliases;
 pub mod base;
 pub mod touch;
 pub mod swipe;
 pub mod input;
 pub mod navigation;
 pub mod system;
+pub mod json_format;
 
+pub use aliases::*;
 pub use base::*;
 pub use touch::*;
 pub use swipe::*;
 pub use input::*;
 pub use navigation::*;
 pub use system::*;
+pub use json_format::ActionFormat;
@@ -126,3 +126,140 @@ impl Action for NotificationAction {
             .unwrap_or_else(|| "打开通知栏".to_string())
     }
 }
+
+/// 读取通知列表操作，将结构化的通知列表反馈给模型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadNotificationsAction {
+    pub description: Option<String>,
+}
+
+impl Action for ReadNotificationsAction {
+    fn action_type(&self) -> String {
+        "read_notifications".to_string()
+    }
+
+    async fn execute(&self, device: &dyn Device) -> Result<ActionResult, AppError> {
+        let start = Instant::now();
+        let notifications = device.list_notifications().await?;
+        let message = if notifications.is_empty() {
+            "当前没有通知".to_string()
+        } else {
+            let lines: Vec<String> = notifications
+                .iter()
+                .enumerate()
+                .map(|(idx, n)| format!("{}. {}", idx + 1, n.summary()))
+                .collect();
+            format!("当前共 {} 条通知:\n{}", notifications.len(), lines.join("\n"))
+        };
+        Ok(ActionResult::success(message, start.elapsed().as_millis() as u32))
+    }
+
+    fn validate(&self) -> Result<(), ActionError> {
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        self.description
+            .clone()
+            .unwrap_or_else(|| "读取通知列表".to_string())
+    }
+}
+
+/// 点击指定通知操作
+///
+/// 通过 `index`（读取通知列表时的序号，从 1 开始）或 `text`（标题/正文关键字）指定通知。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TapNotificationAction {
+    pub index: Option<usize>,
+    pub text: Option<String>,
+    pub description: Option<String>,
+}
+
+impl TapNotificationAction {
+    /// 非空的关键字；空字符串视为未指定，按 index 定位
+    fn keyword(&self) -> Option<&str> {
+        self.text.as_deref().map(str::trim).filter(|text| !text.is_empty())
+    }
+}
+
+impl Action for TapNotificationAction {
+    fn action_type(&self) -> String {
+        "tap_notification".to_string()
+    }
+
+    async fn execute(&self, device: &dyn Device) -> Result<ActionResult, AppError> {
+        let start = Instant::now();
+        let target = match (self.keyword(), self.index) {
+            (Some(text), _) => text.to_string(),
+            (None, Some(index)) => {
+                let notifications = device.list_notifications().await?;
+                let notification = notifications.get(index.saturating_sub(1)).ok_or_else(|| {
+                    AppError::Unknown(format!("通知序号 {} 超出范围（共 {} 条）", index, notifications.len()))
+                })?;
+                notification
+                    .title
+                    .clone()
+                    .or_else(|| notification.text.clone())
+                    .unwrap_or_default()
+            }
+            (None, None) => unreachable!("validate 保证 index 或 text 至少有一个"),
+        };
+
+        device.tap_notification(&target).await?;
+        Ok(ActionResult::success(
+            format!("点击通知: {}", target),
+            start.elapsed().as_millis() as u32,
+        ))
+    }
+
+    fn validate(&self) -> Result<(), ActionError> {
+        if self.index.is_none() && self.keyword().is_none() {
+            return Err(ActionError::InvalidParameters(
+                "需要指定通知序号 index 或关键字 text".to_string(),
+            ));
+        }
+        if self.index == Some(0) {
+            return Err(ActionError::InvalidParameters("通知序号从 1 开始".to_string()));
+        }
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        self.description.clone().unwrap_or_else(|| match (self.keyword(), self.index) {
+            (Some(text), _) => format!("点击通知: {}", text),
+            (None, Some(index)) => format!("点击第 {} 条通知", index),
+            (None, None) => "点击通知".to_string(),
+        })
+    }
+}
+
+/// 清除所有通知操作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClearNotificationsAction {
+    pub description: Option<String>,
+}
+
+impl Action for ClearNotificationsAction {
+    fn action_type(&self) -> String {
+        "clear_notifications".to_string()
+    }
+
+    async fn execute(&self, device: &dyn Device) -> Result<ActionResult, AppError> {
+        let start = Instant::now();
+        device.clear_notifications().await?;
+        Ok(ActionResult::success(
+            self.description(),
+            start.elapsed().as_millis() as u32,
+        ))
+    }
+
+    fn validate(&self) -> Result<(), ActionError> {
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        self.description
+            .clone()
+            .unwrap_or_else(|| "清除所有通知".to_string())
+    }
+}
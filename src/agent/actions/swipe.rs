@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use crate::agent::core::traits::{Action, Device, ActionResult, ActionError};
 use crate::error::AppError;
+use crate::scrcpy::overlay::{to_logical, LOGICAL_SCALE};
 use std::time::Instant;
 
 /// 滑动操作
@@ -84,8 +85,8 @@ impl Action for SwipeAction {
     }
 }
 
-/// 滚动方向
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 滚动方向（浏览内容的方向，down 表示查看下方内容，手指向上滑动）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ScrollDirection {
     Up,
     Down,
@@ -93,15 +94,111 @@ pub enum ScrollDirection {
     Right,
 }
 
+impl ScrollDirection {
+    /// 从模型输出的方向名解析（兼容中文）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "up" | "上" | "向上" => Some(ScrollDirection::Up),
+            "down" | "下" | "向下" => Some(ScrollDirection::Down),
+            "left" | "左" | "向左" => Some(ScrollDirection::Left),
+            "right" | "右" | "向右" => Some(ScrollDirection::Right),
+            _ => None,
+        }
+    }
+}
+
+/// 滚动距离档位
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ScrollDistance {
+    Short,
+    Half,
+    Full,
+}
+
+impl ScrollDistance {
+    /// 从模型输出的距离名解析
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "short" | "small" => Some(ScrollDistance::Short),
+            "half" | "medium" => Some(ScrollDistance::Half),
+            "full" | "long" | "page" => Some(ScrollDistance::Full),
+            _ => None,
+        }
+    }
+
+    /// 滑动距离占屏幕对应边长的百分比
+    pub fn percent(&self) -> u32 {
+        match self {
+            ScrollDistance::Short => 25,
+            ScrollDistance::Half => 50,
+            ScrollDistance::Full => 80,
+        }
+    }
+}
+
+/// 滑动起止点距离屏幕边缘的最小比例（百分比），避免触发系统边缘手势
+const SCROLL_EDGE_MARGIN_PCT: u32 = 8;
+
 /// 滚动操作（特殊的滑动）
+///
+/// 根据当前分辨率计算滑动起止点，模型只需给出方向和距离。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScrollAction {
     pub direction: ScrollDirection,
-    pub distance_pct: u32, // 屏幕高度的百分比
+    pub distance_pct: u32, // 屏幕对应边长的百分比
     pub duration_ms: u32,
+    /// 滚动区域中的一点（0-1000 逻辑坐标），默认屏幕中心
+    #[serde(default)]
+    pub anchor: Option<(u32, u32)>,
     pub description: Option<String>,
 }
 
+impl ScrollAction {
+    /// 按分辨率计算滑动起止点，返回 0-1000 逻辑坐标 (start_x, start_y, end_x, end_y)
+    pub fn swipe_coords(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let (width, height) = (width.max(1), height.max(1));
+        let (anchor_x, anchor_y) = self.anchor.unwrap_or((LOGICAL_SCALE / 2, LOGICAL_SCALE / 2));
+        let center_x = anchor_x.min(LOGICAL_SCALE) * width / LOGICAL_SCALE;
+        let center_y = anchor_y.min(LOGICAL_SCALE) * height / LOGICAL_SCALE;
+
+        // 在像素坐标中计算，保证起止点落在边距范围内
+        let along = |center: u32, len: u32, forward: bool| -> (u32, u32) {
+            let margin = len * SCROLL_EDGE_MARGIN_PCT / 100;
+            let travel = (len * self.distance_pct.min(100) / 100).min(len - 2 * margin);
+            let half = travel / 2;
+            let center = center.clamp(margin + half, len - margin - half);
+            if forward {
+                (center + half, center - half)
+            } else {
+                (center - half, center + half)
+            }
+        };
+
+        let (sx, sy, ex, ey) = match self.direction {
+            // 查看下方内容：手指向上滑动
+            ScrollDirection::Down => {
+                let (sy, ey) = along(center_y, height, true);
+                (center_x, sy, center_x, ey)
+            }
+            ScrollDirection::Up => {
+                let (sy, ey) = along(center_y, height, false);
+                (center_x, sy, center_x, ey)
+            }
+            // 查看右侧内容：手指向左滑动
+            ScrollDirection::Right => {
+                let (sx, ex) = along(center_x, width, true);
+                (sx, center_y, ex, center_y)
+            }
+            ScrollDirection::Left => {
+                let (sx, ex) = along(center_x, width, false);
+                (sx, center_y, ex, center_y)
+            }
+        };
+
+        (to_logical(sx, width), to_logical(sy, height), to_logical(ex, width), to_logical(ey, height))
+    }
+}
+
 impl Action for ScrollAction {
     fn action_type(&self) -> String {
         "scroll".to_string()
@@ -110,32 +207,21 @@ impl Action for ScrollAction {
     async fn execute(&self, device: &dyn Device) -> Result<ActionResult, AppError> {
         let start = Instant::now();
 
-        // 获取屏幕尺寸
-        let (width, height) = device.screen_size().await?;
-
-        // 计算滚动距离
-        let distance_y = (height * self.distance_pct / 100) as u32;
-        let distance_x = (width * self.distance_pct / 100) as u32;
+        // 获取屏幕尺寸，失败时直接在逻辑坐标系中计算
+        let (width, height) = device.screen_size().await.unwrap_or((LOGICAL_SCALE, LOGICAL_SCALE));
+        let (start_x, start_y, end_x, end_y) = self.swipe_coords(width, height);
 
-        // 计算起点和终点
-        let (start_x, start_y, end_x, end_y) = match self.direction {
-            ScrollDirection::Up => (width / 2, height * 3 / 4, width / 2, height / 4),
-            ScrollDirection::Down => (width / 2, height / 4, width / 2, height * 3 / 4),
-            ScrollDirection::Left => (width * 3 / 4, height / 2, width / 4, height / 2),
-            ScrollDirection::Right => (width / 4, height / 2, width * 3 / 4, height / 2),
-        };
+        tracing::info!(
+            "🖱️ ScrollAction: {:?} {}% -> 滑动 ({}, {}) -> ({}, {})",
+            self.direction, self.distance_pct, start_x, start_y, end_x, end_y
+        );
 
         device
             .swipe(start_x, start_y, end_x, end_y, self.duration_ms)
             .await?;
 
         Ok(ActionResult::success(
-            self.description.clone().unwrap_or_else(|| {
-                format!(
-                    "滚动 {:?} {}% {}ms",
-                    self.direction, self.distance_pct, self.duration_ms
-                )
-            }),
+            self.description(),
             start.elapsed().as_millis() as u32,
         ))
     }
@@ -151,6 +237,11 @@ impl Action for ScrollAction {
                 "距离百分比不能小于1".to_string(),
             ));
         }
+        if let Some((x, y)) = self.anchor
+            && (x > LOGICAL_SCALE || y > LOGICAL_SCALE)
+        {
+            return Err(ActionError::OutOfBounds { x, y });
+        }
         if self.duration_ms < 50 {
             return Err(ActionError::DurationTooShort(self.duration_ms));
         }
@@ -169,3 +260,54 @@ impl Action for ScrollAction {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scroll(direction: ScrollDirection, distance: ScrollDistance) -> ScrollAction {
+        ScrollAction {
+            direction,
+            distance_pct: distance.percent(),
+            duration_ms: 400,
+            anchor: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_scroll_swipe_coords() {
+        // 查看下方内容：手指从下往上滑
+        let (sx, sy, ex, ey) = scroll(ScrollDirection::Down, ScrollDistance::Half).swipe_coords(1080, 2400);
+        assert_eq!((sx, ex), (500, 500));
+        assert_eq!((sy, ey), (750, 250));
+
+        let (sx, _, ex, _) = scroll(ScrollDirection::Left, ScrollDistance::Short).swipe_coords(1080, 2400);
+        assert!(sx < ex);
+
+        // 全屏滚动不会贴近屏幕边缘
+        let (_, sy, _, ey) = scroll(ScrollDirection::Up, ScrollDistance::Full).swipe_coords(1080, 2400);
+        assert!(sy >= 80 && ey <= 920);
+
+        // 锚点靠近底部时起止点被限制在屏幕内
+        let mut anchored = scroll(ScrollDirection::Down, ScrollDistance::Half);
+        anchored.anchor = Some((500, 950));
+        let (_, sy, _, ey) = anchored.swipe_coords(1080, 2400);
+        assert!(sy <= 920 && ey < sy);
+    }
+
+    #[test]
+    fn test_parse_scroll_action() {
+        let (_, actions) = crate::agent::actions::ActionEnum::parse_from_response(
+            r#"do(action="Scroll", direction="down", distance="full", element=[500, 300])"#,
+        );
+        match actions.as_slice() {
+            [crate::agent::actions::ActionEnum::Scroll(scroll)] => {
+                assert_eq!(scroll.direction, ScrollDirection::Down);
+                assert_eq!(scroll.distance_pct, 80);
+                assert_eq!(scroll.anchor, Some((500, 300)));
+            }
+            other => panic!("unexpected actions: {:?}", other),
+        }
+    }
+}
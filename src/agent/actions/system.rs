@@ -67,6 +67,12 @@ pub fn app_name_to_package(app_name: &str) -> Option<String> {
 
     debug!("🔍 app_name_to_package: {}", app_name);
 
+    // 用户配置的别名优先于内置映射
+    if let Some(package) = super::aliases::app_aliases().get(app_name) {
+        debug!("   ✅ 别名匹配: {} -> {}", app_name, package);
+        return Some(package);
+    }
+
     let packages = get_app_packages();
 
     // 首先尝试直接匹配
@@ -317,3 +323,152 @@ impl Action for FinishAction {
         format!("完成任务: {}", self.result)
     }
 }
+
+/// 设置模拟定位操作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetLocationAction {
+    pub location: crate::agent::executor::GeoLocation,
+    pub description: Option<String>,
+}
+
+impl Action for SetLocationAction {
+    fn action_type(&self) -> String {
+        "set_location".to_string()
+    }
+
+    async fn execute(&self, device: &dyn Device) -> Result<ActionResult, AppError> {
+        let start = Instant::now();
+        device.set_mock_location(&self.location).await?;
+        Ok(ActionResult::success(
+            self.description(),
+            start.elapsed().as_millis() as u32,
+        ))
+    }
+
+    fn validate(&self) -> Result<(), ActionError> {
+        self.location.validate().map_err(ActionError::InvalidParameters)
+    }
+
+    fn description(&self) -> String {
+        self.description.clone().unwrap_or_else(|| {
+            format!(
+                "设置模拟定位: ({}, {})",
+                self.location.latitude, self.location.longitude
+            )
+        })
+    }
+}
+
+/// 停止模拟定位操作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClearLocationAction {
+    pub description: Option<String>,
+}
+
+impl Action for ClearLocationAction {
+    fn action_type(&self) -> String {
+        "clear_location".to_string()
+    }
+
+    async fn execute(&self, device: &dyn Device) -> Result<ActionResult, AppError> {
+        let start = Instant::now();
+        device.clear_mock_location().await?;
+        Ok(ActionResult::success(
+            self.description(),
+            start.elapsed().as_millis() as u32,
+        ))
+    }
+
+    fn validate(&self) -> Result<(), ActionError> {
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        self.description
+            .clone()
+            .unwrap_or_else(|| "停止模拟定位".to_string())
+    }
+}
+
+/// 将当前设备上的文件发送到另一台设备
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferFileAction {
+    /// 目标设备序列号
+    pub target_device: String,
+    /// 当前设备上的文件路径
+    pub source_path: String,
+    /// 目标设备上的保存路径，未指定时保存到下载目录
+    #[serde(default)]
+    pub target_path: Option<String>,
+    pub description: Option<String>,
+}
+
+impl Action for TransferFileAction {
+    fn action_type(&self) -> String {
+        "transfer_file".to_string()
+    }
+
+    async fn execute(&self, device: &dyn Device) -> Result<ActionResult, AppError> {
+        let start = Instant::now();
+        let transfer = crate::agent::executor::transfer_file(
+            device.serial(),
+            &self.target_device,
+            &self.source_path,
+            self.target_path.as_deref(),
+        )
+        .await?;
+        Ok(ActionResult::success(
+            format!("已发送到 {}:{} ({} 字节)", transfer.to_device, transfer.target_path, transfer.bytes),
+            start.elapsed().as_millis() as u32,
+        ))
+    }
+
+    fn validate(&self) -> Result<(), ActionError> {
+        if self.target_device.trim().is_empty() || self.source_path.trim().is_empty() {
+            return Err(ActionError::InvalidParameters("目标设备和文件路径不能为空".to_string()));
+        }
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        self.description.clone().unwrap_or_else(|| {
+            format!("发送文件 {} 到设备 {}", self.source_path, self.target_device)
+        })
+    }
+}
+
+/// 将当前设备的剪贴板内容复制到另一台设备
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferClipboardAction {
+    /// 目标设备序列号
+    pub target_device: String,
+    pub description: Option<String>,
+}
+
+impl Action for TransferClipboardAction {
+    fn action_type(&self) -> String {
+        "transfer_clipboard".to_string()
+    }
+
+    async fn execute(&self, device: &dyn Device) -> Result<ActionResult, AppError> {
+        let start = Instant::now();
+        let text = crate::agent::executor::transfer_clipboard(device.serial(), &self.target_device).await?;
+        Ok(ActionResult::success(
+            format!("已将剪贴板内容复制到 {}: {}", self.target_device, text),
+            start.elapsed().as_millis() as u32,
+        ))
+    }
+
+    fn validate(&self) -> Result<(), ActionError> {
+        if self.target_device.trim().is_empty() {
+            return Err(ActionError::InvalidParameters("目标设备不能为空".to_string()));
+        }
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        self.description
+            .clone()
+            .unwrap_or_else(|| format!("复制剪贴板到设备 {}", self.target_device))
+    }
+}
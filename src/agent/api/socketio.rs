@@ -3,7 +3,6 @@ use serde::{Deserialize, Serialize};
 use tracing::error;
 use std::sync::Arc;
 use crate::context::IContext;
-use crate::agent::core::traits::Agent;
 
 pub async fn register_agent_handlers(socket: SocketRef, context: Arc<dyn IContext>) {
     // 提取 device_pool 的引用，使其可克隆
@@ -37,7 +36,9 @@ pub async fn register_agent_handlers(socket: SocketRef, context: Arc<dyn IContex
                             error!("启动 Agent 失败: {}", e);
                             let _ = s.emit("agent/start/response", &serde_json::json!({
                                 "success": false,
-                                "error": e.to_string()
+                                "error": e.to_string(),
+                                "status": crate::agent::pool::is_capacity_error(&e).then_some(crate::agent::pool::CAPACITY_EXCEEDED_STATUS)
+                                    .or(crate::agent::pool::is_draining_error(&e).then_some(crate::agent::pool::DRAINING_STATUS))
                             }));
                         }
                     }
@@ -68,12 +69,66 @@ pub async fn register_agent_handlers(socket: SocketRef, context: Arc<dyn IContex
 }
 
 async fn handle_agent_start_with_pool(request: AgentStartRequest, pool: Arc<crate::agent::pool::DevicePool>) -> Result<serde_json::Value, crate::error::AppError> {
+    if request.experiment_id.is_some() && request.overrides.overrides_model() {
+        return Err(crate::error::AppError::AgentError(crate::agent::core::traits::AgentError::ValidationError(
+            "实验任务不能同时指定模型参数".to_string(),
+        )));
+    }
+    let callback = match &request.callback_url {
+        Some(url) => Some(
+            crate::agent::core::callback::TaskCallback::new(url, request.task_id.clone())
+                .map_err(|e| crate::error::AppError::AgentError(crate::agent::core::traits::AgentError::ValidationError(e)))?,
+        ),
+        None => None,
+    };
+    let foreground_guard = match &request.target_app {
+        Some(app) => Some(
+            crate::agent::executor::ForegroundGuard::new(app, request.relaunch_target_app)
+                .map_err(|e| crate::error::AppError::AgentError(crate::agent::core::traits::AgentError::ValidationError(e)))?,
+        ),
+        None => None,
+    };
+    // 客户端任务 ID：重复提交时返回已提交任务的状态，不再启动新任务
+    let submission = match &request.task_id {
+        Some(task_id) => match pool.claim_submission(task_id, &request.device_serial, &request.task) {
+            crate::agent::pool::SubmissionClaim::New(ticket) => Some(ticket),
+            crate::agent::pool::SubmissionClaim::Duplicate(existing) => {
+                let task_record = pool.submission_status(&existing).await;
+                return Ok(serde_json::json!({ "success": true, "duplicate": true, "task_id": existing.task_id, "agent_id": existing.agent_id, "device_serial": existing.device_serial, "task": existing.task, "task_record": task_record }));
+            }
+            crate::agent::pool::SubmissionClaim::Conflict(existing) => {
+                return Err(crate::error::AppError::AgentError(crate::agent::core::traits::AgentError::ValidationError(
+                    format!("任务 ID {} 已用于设备 {} 上的其它任务", existing.task_id, existing.device_serial),
+                )));
+            }
+        },
+        None => None,
+    };
     let _ = pool.register_device(request.device_serial.clone(), None).await;
     let agent = pool.get_agent(&request.device_serial).await?;
-    let agent_id = agent.start(request.task.clone()).await?;
+    if !request.overrides.is_empty() {
+        pool.apply_task_overrides(&agent, &request.overrides).await?;
+    }
+    agent.set_capture_traffic(request.capture_traffic).await;
+    agent.set_record_screen(request.record_screen).await;
+    agent.set_usage_expectation(request.expected_app.clone().map(|app| crate::agent::executor::UsageExpectation {
+        app,
+        min_foreground_secs: request.min_foreground_secs,
+    })).await;
+    agent.set_humanize(request.humanize.clone()).await;
+    agent.set_foreground_guard(foreground_guard).await;
+    agent.set_callback(callback).await;
+    let variant = match &request.experiment_id {
+        Some(experiment_id) => Some(pool.assign_experiment(&agent, experiment_id).await?),
+        None => None,
+    };
+    let agent_id = pool.start_task(&agent, request.task.clone(), request.priority).await?;
+    if let Some(ticket) = submission {
+        ticket.started(&agent_id, agent.task_id().await);
+    }
     pool.update_task_status(&request.device_serial, agent_id.clone(), request.task.clone()).await?;
 
-    Ok(serde_json::json!({ "success": true, "agent_id": agent_id, "device_serial": request.device_serial, "task": request.task }))
+    Ok(serde_json::json!({ "success": true, "agent_id": agent_id, "task_id": request.task_id, "device_serial": request.device_serial, "task": request.task, "variant": variant, "priority": request.priority }))
 }
 
 async fn handle_get_devices_with_pool(pool: Arc<crate::agent::pool::DevicePool>) -> Result<serde_json::Value, crate::error::AppError> {
@@ -85,4 +140,40 @@ async fn handle_get_devices_with_pool(pool: Arc<crate::agent::pool::DevicePool>)
 pub struct AgentStartRequest {
     pub device_serial: String,
     pub task: String,
+    /// 是否在任务期间记录设备网络流量
+    #[serde(default)]
+    pub capture_traffic: bool,
+    /// 是否用设备自带的 screenrecord 录制任务过程
+    #[serde(default)]
+    pub record_screen: bool,
+    /// 任务完成后校验该应用（名称或包名）确实在前台运行过
+    #[serde(default)]
+    pub expected_app: Option<String>,
+    /// 目标应用最少前台时长（秒），未指定时从任务描述推断
+    #[serde(default)]
+    pub min_foreground_secs: Option<u64>,
+    /// 任务的目标应用（名称或包名），进入过前台后每步检查前台应用，离开时提醒模型
+    #[serde(default)]
+    pub target_app: Option<String>,
+    /// 离开目标应用时自动重新启动
+    #[serde(default)]
+    pub relaunch_target_app: bool,
+    /// 所属 A/B 实验 ID，指定时为任务分配实验变体
+    #[serde(default)]
+    pub experiment_id: Option<String>,
+    /// 输入拟人化选项，未指定时不启用
+    #[serde(default)]
+    pub humanize: Option<crate::agent::executor::HumanizeOptions>,
+    /// 客户端生成的任务 ID（幂等键），重复提交时返回已有任务的状态而不是再启动一个任务
+    #[serde(default, alias = "idempotency_key")]
+    pub task_id: Option<String>,
+    /// 任务完成、失败或被停止时 POST 最终状态与结果的地址
+    #[serde(default)]
+    pub callback_url: Option<String>,
+    /// 任务优先级（high/normal/low），设备或 Agent 名额排队时高优先级先执行
+    #[serde(default)]
+    pub priority: crate::agent::pool::TaskPriority,
+    /// 只作用于本任务的模型与参数覆盖（model_name、temperature、max_steps、max_execution_time、three_stage）
+    #[serde(flatten)]
+    pub overrides: crate::agent::pool::TaskOverrides,
 }
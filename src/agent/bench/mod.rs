@@ -0,0 +1,9 @@
+//! 基准测试模块
+//!
+//! 在选定的设备与模型/提示词变体上运行用户提供的任务集，按检查项判定成功，
+//! 汇总成功率、平均步数、耗时与 token 消耗并生成对比报告。
+
+mod runner;
+mod suite;
+
+pub use runner::{start_bench, BenchReport, BenchRunRequest};
@@ -0,0 +1,466 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+use uuid::Uuid;
+use super::suite::{suite_dir, BenchSuite, BenchTask, SuccessCheck};
+use crate::agent::actions::system::app_name_to_package;
+use crate::agent::core::traits::{Agent, AgentStatus};
+use crate::agent::executor::{find_node_center, usage::adb_shell};
+use crate::agent::experiments::{TaskOutcome, Variant, VariantResults};
+use crate::agent::pool::{DevicePool, TaskPriority};
+use crate::error::AppError;
+
+/// 基准报告目录
+pub const BENCH_DIR: &str = "bench";
+/// 轮询 Agent 状态的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+fn default_repeat() -> usize {
+    1
+}
+
+/// 基准运行请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct BenchRunRequest {
+    /// 内联的任务集
+    #[serde(default)]
+    pub suite: Option<BenchSuite>,
+    /// 任务集目录（默认 `bench/suites`）下的任务集文件（TOML 或 JSON），未提供内联任务集时使用
+    #[serde(default)]
+    pub suite_path: Option<String>,
+    /// 参与运行的设备序列号
+    pub devices: Vec<String>,
+    /// 参与比较的模型/提示词变体，为空时使用默认配置
+    #[serde(default)]
+    pub variants: Vec<Variant>,
+    /// 每个任务在每个设备、变体上的重复次数
+    #[serde(default = "default_repeat")]
+    pub repeat: usize,
+}
+
+impl BenchRunRequest {
+    /// 解析任务集与变体
+    async fn resolve(self) -> Result<(BenchSuite, Vec<String>, Vec<Variant>, usize), String> {
+        let suite = match (self.suite, self.suite_path) {
+            (Some(suite), _) => {
+                suite.validate()?;
+                suite
+            }
+            (None, Some(path)) => BenchSuite::load(&suite_dir(), &path).await?,
+            (None, None) => return Err("需要提供 suite 或 suite_path".to_string()),
+        };
+        if self.devices.is_empty() {
+            return Err("至少需要一个设备".to_string());
+        }
+        let variants = if self.variants.is_empty() {
+            vec![Variant {
+                name: "default".to_string(),
+                prompt_suffix: None,
+                model_name: None,
+                planning_model_name: None,
+                temperature: None,
+            }]
+        } else {
+            self.variants
+        };
+        Ok((suite, self.devices, variants, self.repeat.max(1)))
+    }
+}
+
+/// 单个检查项的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub check: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// 单次任务运行结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchTaskResult {
+    pub task_id: String,
+    pub device: String,
+    pub variant: String,
+    pub run: usize,
+    /// Agent 是否以 finish 正常结束
+    pub agent_completed: bool,
+    /// Agent 完成且所有检查项通过
+    pub success: bool,
+    pub checks: Vec<CheckResult>,
+    pub steps: usize,
+    pub duration_ms: u64,
+    pub tokens_used: u64,
+    pub judge_score: Option<f32>,
+    pub error: Option<String>,
+}
+
+/// 基准运行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BenchStatus {
+    Running,
+    Completed,
+}
+
+/// 基准报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub id: String,
+    pub suite: String,
+    pub status: BenchStatus,
+    pub devices: Vec<String>,
+    pub variants: Vec<String>,
+    pub total_runs: usize,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub results: Vec<BenchTaskResult>,
+    /// 按变体汇总（成功率、平均步数、平均耗时、token 消耗）
+    pub summary: Vec<VariantResults>,
+}
+
+impl BenchReport {
+    fn path(id: &str, extension: &str) -> PathBuf {
+        PathBuf::from(BENCH_DIR).join(format!("{}.{}", id, extension))
+    }
+
+    /// 加载已保存的报告
+    pub async fn load(id: &str) -> Result<Self, String> {
+        if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(format!("无效的报告 ID: {}", id));
+        }
+        let json = tokio::fs::read(Self::path(id, "json"))
+            .await
+            .map_err(|_| format!("基准报告不存在: {}", id))?;
+        serde_json::from_slice(&json).map_err(|e| format!("解析基准报告失败: {}", e))
+    }
+
+    /// 保存 JSON 报告与 Markdown 对比报告
+    async fn save(&self) -> Result<(), AppError> {
+        tokio::fs::create_dir_all(BENCH_DIR).await?;
+        tokio::fs::write(Self::path(&self.id, "json"), serde_json::to_vec_pretty(self)?).await?;
+        tokio::fs::write(Self::path(&self.id, "md"), self.to_markdown()).await?;
+        Ok(())
+    }
+
+    /// 追加一条结果并重新汇总
+    fn push(&mut self, result: BenchTaskResult) {
+        self.results.push(result);
+        let outcomes: Vec<TaskOutcome> = self
+            .results
+            .iter()
+            .map(|r| TaskOutcome {
+                variant: r.variant.clone(),
+                task: r.task_id.clone(),
+                success: r.success,
+                steps: r.steps,
+                duration_ms: r.duration_ms,
+                tokens_used: r.tokens_used,
+                judge_score: r.judge_score,
+                finished_at: Utc::now(),
+            })
+            .collect();
+        self.summary = self
+            .variants
+            .iter()
+            .map(|variant| {
+                let runs: Vec<&TaskOutcome> = outcomes.iter().filter(|o| &o.variant == variant).collect();
+                VariantResults::from_outcomes(variant, &runs)
+            })
+            .collect();
+    }
+
+    /// 生成 Markdown 对比报告
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!(
+            "# 基准报告: {}\n\n- ID: {}\n- 设备: {}\n- 进度: {}/{}\n- 开始时间: {}\n\n",
+            self.suite,
+            self.id,
+            self.devices.join(", "),
+            self.results.len(),
+            self.total_runs,
+            self.started_at.to_rfc3339(),
+        );
+
+        md.push_str("## 变体对比\n\n| 变体 | 运行次数 | 成功率 | 平均步数 | 平均耗时(s) | 平均 tokens | 总 tokens |\n");
+        md.push_str("|---|---|---|---|---|---|---|\n");
+        for s in &self.summary {
+            md.push_str(&format!(
+                "| {} | {} | {:.1}% | {:.1} | {:.1} | {:.0} | {} |\n",
+                s.variant,
+                s.tasks,
+                s.success_rate * 100.0,
+                s.avg_steps,
+                s.avg_duration_ms / 1000.0,
+                s.avg_tokens,
+                s.total_tokens,
+            ));
+        }
+
+        md.push_str("\n## 任务明细\n\n| 任务 | 设备 | 变体 | 结果 | 步数 | 耗时(s) | 未通过的检查 |\n");
+        md.push_str("|---|---|---|---|---|---|---|\n");
+        for r in &self.results {
+            let failed: Vec<String> = r
+                .checks
+                .iter()
+                .filter(|c| !c.passed)
+                .map(|c| c.check.clone())
+                .chain(r.error.clone())
+                .collect();
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {:.1} | {} |\n",
+                r.task_id,
+                r.device,
+                r.variant,
+                if r.success { "✅" } else { "❌" },
+                r.steps,
+                r.duration_ms as f64 / 1000.0,
+                failed.join("; "),
+            ));
+        }
+        md
+    }
+}
+
+/// 启动一次基准运行，返回报告 ID；运行在后台进行，进度随时写入报告文件
+pub async fn start_bench(pool: Arc<DevicePool>, request: BenchRunRequest) -> Result<String, String> {
+    let (suite, devices, variants, repeat) = request.resolve().await?;
+
+    let report = BenchReport {
+        id: Uuid::new_v4().to_string(),
+        suite: suite.name.clone(),
+        status: BenchStatus::Running,
+        devices: devices.clone(),
+        variants: variants.iter().map(|v| v.name.clone()).collect(),
+        total_runs: suite.tasks.len() * devices.len() * variants.len() * repeat,
+        started_at: Utc::now(),
+        finished_at: None,
+        results: Vec::new(),
+        summary: Vec::new(),
+    };
+    report.save().await.map_err(|e| e.to_string())?;
+    let id = report.id.clone();
+    info!("开始基准运行 {}: 任务集 {}，共 {} 次运行", id, suite.name, report.total_runs);
+
+    let report = Arc::new(Mutex::new(report));
+    tokio::spawn(async move {
+        // 各设备并行，设备内按变体、重复次数、任务顺序串行执行
+        let runs = devices.into_iter().map(|device| {
+            let pool = Arc::clone(&pool);
+            let report = Arc::clone(&report);
+            let suite = &suite;
+            let variants = &variants;
+            async move {
+                for variant in variants {
+                    for run in 1..=repeat {
+                        for task in &suite.tasks {
+                            let result = run_task(&pool, &device, variant, task, run).await;
+                            let mut report = report.lock().await;
+                            report.push(result);
+                            if let Err(e) = report.save().await {
+                                warn!("保存基准报告失败: {}", e);
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        futures::future::join_all(runs).await;
+
+        let mut report = report.lock().await;
+        report.status = BenchStatus::Completed;
+        report.finished_at = Some(Utc::now());
+        if let Err(e) = report.save().await {
+            warn!("保存基准报告失败: {}", e);
+        }
+        info!("基准运行 {} 完成", report.id);
+    });
+
+    Ok(id)
+}
+
+/// 在设备上以指定变体运行一个任务并执行检查
+async fn run_task(
+    pool: &DevicePool,
+    device: &str,
+    variant: &Variant,
+    task: &BenchTask,
+    run: usize,
+) -> BenchTaskResult {
+    let mut result = BenchTaskResult {
+        task_id: task.id.clone(),
+        device: device.to_string(),
+        variant: variant.name.clone(),
+        run,
+        agent_completed: false,
+        success: false,
+        checks: Vec::new(),
+        steps: 0,
+        duration_ms: 0,
+        tokens_used: 0,
+        judge_score: None,
+        error: None,
+    };
+
+    let status = match run_agent(pool, device, variant, task, &mut result).await {
+        Ok(status) => status,
+        Err(e) => {
+            warn!("基准任务 {} 在设备 {} 上运行失败: {}", task.id, device, e);
+            result.error = Some(e);
+            return result;
+        }
+    };
+
+    let evaluation = match &status {
+        AgentStatus::Completed { evaluation, .. } => evaluation.clone(),
+        _ => None,
+    };
+    result.agent_completed = matches!(status, AgentStatus::Completed { .. });
+    result.judge_score = evaluation.as_ref().map(|e| e.score);
+    if let AgentStatus::Failed { error, .. } = &status {
+        result.error = Some(error.clone());
+    }
+
+    for check in &task.checks {
+        let (passed, detail) = match check {
+            SuccessCheck::ForegroundApp { app } => check_foreground_app(device, app).await,
+            SuccessCheck::ScreenText { text } => check_screen_text(pool, device, text).await,
+            // 应用使用校验未通过时 Agent 会将任务标记为失败
+            SuccessCheck::AppUsage { .. } => (
+                result.agent_completed,
+                result.error.clone().unwrap_or_else(|| "应用使用校验通过".to_string()),
+            ),
+            SuccessCheck::Judge { min_score } => match &evaluation {
+                Some(e) => (e.success && e.score >= min_score.unwrap_or(0.0), e.rationale.clone()),
+                None => (false, "没有评估结果（未配置评估模型或任务未完成）".to_string()),
+            },
+        };
+        result.checks.push(CheckResult { check: check.label(), passed, detail });
+    }
+
+    result.success = result.agent_completed && result.checks.iter().all(|c| c.passed);
+    let outcome = if result.success { "成功".to_string() } else { "失败".to_string() };
+    let _ = match result.success {
+        true => pool.mark_task_completed(device, outcome).await,
+        false => pool.mark_task_failed(device, outcome).await,
+    };
+    result
+}
+
+/// 启动 Agent 并等待任务结束，超时则停止任务
+async fn run_agent(
+    pool: &DevicePool,
+    device: &str,
+    variant: &Variant,
+    task: &BenchTask,
+    result: &mut BenchTaskResult,
+) -> Result<AgentStatus, String> {
+    let _ = pool.register_device(device.to_string(), None).await;
+    let agent = pool.get_agent(device).await.map_err(|e| e.to_string())?;
+
+    let model_client = pool.variant_model_client(variant).map_err(|e| e.to_string())?;
+    agent.set_variant(variant.clone(), model_client).await;
+    agent
+        .set_usage_expectation(task.checks.iter().find_map(SuccessCheck::usage_expectation))
+        .await;
+    agent.set_humanize(task.humanize.clone()).await;
+
+    let agent_id = pool.start_task(&agent, task.task.clone(), TaskPriority::default()).await.map_err(|e| e.to_string())?;
+    let _ = pool.update_task_status(device, agent_id, task.task.clone()).await;
+
+    let deadline = Instant::now() + Duration::from_secs(task.timeout_secs);
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let status = agent.status().await;
+        let running = matches!(status, AgentStatus::Running { .. } | AgentStatus::Paused { .. });
+
+        let stats = agent.task_stats().await;
+        result.steps = stats.steps;
+        result.duration_ms = stats.duration_ms;
+        result.tokens_used = stats.tokens_used;
+
+        if !running {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = agent.stop().await;
+            return Err(format!("任务超时 ({} 秒)", task.timeout_secs));
+        }
+    }
+}
+
+/// 检查前台应用
+async fn check_foreground_app(device: &str, app: &str) -> (bool, String) {
+    let expected = app_name_to_package(app).unwrap_or_else(|| app.to_string());
+    match adb_shell(device, "dumpsys window | grep -E 'mCurrentFocus'").await {
+        Ok(output) => {
+            let passed = output.contains(&format!("{}/", expected));
+            (passed, output.trim().to_string())
+        }
+        Err(e) => (false, e.to_string()),
+    }
+}
+
+/// 检查屏幕上是否存在包含指定文本的控件
+async fn check_screen_text(pool: &DevicePool, device: &str, text: &str) -> (bool, String) {
+    let xml = match pool.get_agent(device).await {
+        Ok(agent) => agent.device().ui_dump().await,
+        Err(e) => Err(e),
+    };
+    match xml {
+        Ok(xml) => match find_node_center(&xml, text) {
+            Some((x, y)) => (true, format!("位于 ({}, {})", x, y)),
+            None => (false, "屏幕上未找到".to_string()),
+        },
+        Err(e) => (false, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_summary_and_markdown() {
+        let mut report = BenchReport {
+            id: "test".to_string(),
+            suite: "daily".to_string(),
+            status: BenchStatus::Running,
+            devices: vec!["emulator-5554".to_string()],
+            variants: vec!["a".to_string(), "b".to_string()],
+            total_runs: 3,
+            started_at: Utc::now(),
+            finished_at: None,
+            results: Vec::new(),
+            summary: Vec::new(),
+        };
+        let result = |variant: &str, success: bool, steps: usize| BenchTaskResult {
+            task_id: "wifi".to_string(),
+            device: "emulator-5554".to_string(),
+            variant: variant.to_string(),
+            run: 1,
+            agent_completed: true,
+            success,
+            checks: vec![CheckResult { check: "屏幕包含“WLAN”".to_string(), passed: success, detail: String::new() }],
+            steps,
+            duration_ms: 2000,
+            tokens_used: 100,
+            judge_score: None,
+            error: None,
+        };
+        report.push(result("a", true, 4));
+        report.push(result("a", false, 6));
+        report.push(result("b", true, 3));
+
+        assert_eq!(report.summary[0].success_rate, 0.5);
+        assert_eq!(report.summary[0].avg_steps, 5.0);
+        assert_eq!(report.summary[1].tasks, 1);
+
+        let md = report.to_markdown();
+        assert!(md.contains("| a | 2 | 50.0% | 5.0 | 2.0 | 100 | 200 |"));
+        assert!(md.contains("| wifi | emulator-5554 | a | ❌ | 6 | 2.0 | 屏幕包含“WLAN” |"));
+    }
+}
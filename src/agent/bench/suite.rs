@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+use crate::agent::executor::{HumanizeOptions, UsageExpectation};
+
+/// 任务集目录，`suite_path` 只能指向该目录下的文件
+pub const SUITE_DIR: &str = "bench/suites";
+/// 指定任务集目录的环境变量
+pub const SUITE_DIR_ENV: &str = "SCRS_BENCH_SUITE_DIR";
+
+/// 当前使用的任务集目录
+pub fn suite_dir() -> PathBuf {
+    std::env::var(SUITE_DIR_ENV).map(PathBuf::from).unwrap_or_else(|_| PathBuf::from(SUITE_DIR))
+}
+
+/// 把请求中的 `suite_path` 解析为任务集目录下的文件，拒绝绝对路径与 `..`
+fn resolve_suite_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let path = Path::new(name);
+    if name.is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(format!("任务集路径无效: {}（只能是任务集目录下的相对路径）", name));
+    }
+    Ok(dir.join(path))
+}
+
+/// 单个任务默认超时时间（秒）
+fn default_timeout_secs() -> u64 {
+    600
+}
+
+/// 任务成功判定条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SuccessCheck {
+    /// 任务结束时前台应用为指定应用（名称或包名）
+    ForegroundApp { app: String },
+    /// 任务结束时屏幕上存在包含指定文本的控件
+    ScreenText { text: String },
+    /// 任务期间指定应用在前台运行了足够时长
+    AppUsage {
+        app: String,
+        #[serde(default)]
+        min_foreground_secs: Option<u64>,
+    },
+    /// 评估模型判定成功且评分不低于阈值
+    Judge {
+        #[serde(default)]
+        min_score: Option<f32>,
+    },
+}
+
+impl SuccessCheck {
+    /// 检查项描述，用于报告
+    pub fn label(&self) -> String {
+        match self {
+            SuccessCheck::ForegroundApp { app } => format!("前台应用为 {}", app),
+            SuccessCheck::ScreenText { text } => format!("屏幕包含“{}”", text),
+            SuccessCheck::AppUsage { app, min_foreground_secs } => match min_foreground_secs {
+                Some(secs) => format!("{} 前台至少 {} 秒", app, secs),
+                None => format!("{} 到过前台", app),
+            },
+            SuccessCheck::Judge { min_score } => {
+                format!("评估模型判定成功 (评分 ≥ {:.2})", min_score.unwrap_or(0.0))
+            }
+        }
+    }
+
+    /// 应用使用校验由 Agent 在任务结束时执行，这里转换为对应的预期
+    pub fn usage_expectation(&self) -> Option<UsageExpectation> {
+        match self {
+            SuccessCheck::AppUsage { app, min_foreground_secs } => Some(UsageExpectation {
+                app: app.clone(),
+                min_foreground_secs: *min_foreground_secs,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// 基准任务定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchTask {
+    pub id: String,
+    /// 交给 Agent 的任务描述
+    pub task: String,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 所有检查项都通过才算成功；为空时以 Agent 完成为准
+    #[serde(default)]
+    pub checks: Vec<SuccessCheck>,
+    /// 输入拟人化选项，未指定时不启用
+    #[serde(default)]
+    pub humanize: Option<HumanizeOptions>,
+}
+
+/// 基准任务集
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchSuite {
+    pub name: String,
+    pub tasks: Vec<BenchTask>,
+}
+
+impl BenchSuite {
+    /// 从任务集目录加载 TOML 或 JSON 任务集（按扩展名区分）
+    ///
+    /// 读取与解析的具体错误只写入日志，返回给客户端的错误不包含文件内容
+    pub async fn load(dir: &Path, name: &str) -> Result<Self, String> {
+        let path = resolve_suite_path(dir, name)?;
+        let content = tokio::fs::read_to_string(&path).await.map_err(|e| {
+            warn!("读取任务集 {:?} 失败: {}", path, e);
+            format!("任务集不存在或无法读取: {}", name)
+        })?;
+
+        let parsed = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str::<Self>(&content).map_err(|e| e.to_string())
+        } else {
+            toml::from_str::<Self>(&content).map_err(|e| e.to_string())
+        };
+        let suite = parsed.map_err(|e| {
+            warn!("解析任务集 {:?} 失败: {}", path, e);
+            format!("任务集格式无效: {}", name)
+        })?;
+        suite.validate()?;
+        Ok(suite)
+    }
+
+    /// 校验任务集
+    pub fn validate(&self) -> Result<(), String> {
+        if self.tasks.is_empty() {
+            return Err(format!("任务集 {} 没有任务", self.name));
+        }
+        let mut ids: Vec<&str> = self.tasks.iter().map(|t| t.id.as_str()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.len() != self.tasks.len() {
+            return Err(format!("任务集 {} 中存在重复的任务 ID", self.name));
+        }
+        if let Some(task) = self.tasks.iter().find(|t| t.task.trim().is_empty()) {
+            return Err(format!("任务 {} 的描述为空", task.id));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_toml_suite() {
+        let suite: BenchSuite = toml::from_str(
+            r#"
+name = "daily"
+
+[[tasks]]
+id = "wifi"
+task = "打开设置并进入 WLAN 页面"
+checks = [
+    { type = "foreground_app", app = "设置" },
+    { type = "screen_text", text = "WLAN" },
+]
+
+[[tasks]]
+id = "video"
+task = "打开哔哩哔哩看2分钟视频"
+timeout_secs = 300
+checks = [{ type = "app_usage", app = "哔哩哔哩" }]
+"#,
+        )
+        .unwrap();
+
+        assert!(suite.validate().is_ok());
+        assert_eq!(suite.tasks[0].timeout_secs, 600);
+        assert_eq!(suite.tasks[0].checks[1], SuccessCheck::ScreenText { text: "WLAN".to_string() });
+        assert_eq!(
+            suite.tasks[1].checks[0].usage_expectation().map(|e| e.app),
+            Some("哔哩哔哩".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_restricted_to_suite_dir() {
+        let dir = std::env::temp_dir().join(format!("scrs_suites_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("daily")).unwrap();
+        std::fs::write(dir.join("daily/smoke.json"), r#"{"name": "smoke", "tasks": [{"id": "a", "task": "打开设置"}]}"#).unwrap();
+        std::fs::write(dir.join("broken.toml"), "name = ").unwrap();
+
+        assert_eq!(BenchSuite::load(&dir, "daily/smoke.json").await.unwrap().name, "smoke");
+        for name in ["../etc/passwd", "/etc/passwd", "daily/../daily/smoke.json", ""] {
+            assert!(BenchSuite::load(&dir, name).await.unwrap_err().contains("任务集路径无效"), "{}", name);
+        }
+        assert_eq!(BenchSuite::load(&dir, "missing.toml").await.unwrap_err(), "任务集不存在或无法读取: missing.toml");
+        // 不回显解析错误的细节
+        assert_eq!(BenchSuite::load(&dir, "broken.toml").await.unwrap_err(), "任务集格式无效: broken.toml");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -37,9 +37,10 @@ impl FullAgentConfig {
         let mut config = Self::from_file(path)?;
 
         // 使用环境变量覆盖 API Key
-        if let Ok(api_key) = std::env::var("OPENAI_API_KEY") {
-            config.model.api_key = api_key;
+        if let Some(api_key) = crate::secrets::env_secret("OPENAI_API_KEY") {
+            config.model.api_key = api_key.map_err(ConfigError::IoError)?;
         }
+        config.model.resolve_secrets().map_err(ConfigError::IoError)?;
 
         if let Ok(base_url) = std::env::var("OPENAI_BASE_URL") {
             config.model.base_url = base_url;
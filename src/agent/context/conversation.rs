@@ -12,14 +12,7 @@ pub struct ConversationMessage {
     pub screenshot: Option<String>,
 }
 
-/// 消息角色
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum MessageRole {
-    User,
-    Assistant,
-    System,
-}
+pub use crate::agent::core::message::MessageRole;
 
 /// 线程安全的对话上下文管理
 pub struct ConversationContext {
@@ -0,0 +1,422 @@
+//! 按应用划分的知识库
+//!
+//! 将人工演示录制的宏转换为带注释的操作序列（示范案例），按前台应用保存。
+//! 规划阶段根据任务描述检索相似的示范案例，作为参考流程提供给规划模型。
+
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use crate::scrcpy::macro_recorder::{DeviceMacro, MacroAction, MacroFrame};
+use crate::scrcpy::overlay::to_logical;
+
+/// 知识库根目录
+pub const KNOWLEDGE_DIR: &str = "knowledge";
+
+/// 未知应用的目录名
+const UNKNOWN_APP: &str = "_unknown";
+
+/// 检索示范案例的最低相似度
+const MIN_SIMILARITY: f64 = 0.3;
+
+/// 示范案例中的单个操作步骤
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedStep {
+    /// 相对演示开始的时间偏移（毫秒）
+    pub offset_ms: u64,
+    /// 操作类型（Tap / LongPress / Swipe / Scroll / Key / Type）
+    pub action_type: String,
+    /// 自然语言注释，坐标为 0-1000 逻辑坐标
+    pub annotation: String,
+    /// 操作前截图的文件名（相对示范案例目录）
+    pub screenshot: Option<String>,
+}
+
+/// 由人工演示生成的示范案例
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkedExample {
+    pub name: String,
+    /// 演示完成的任务描述
+    pub task: String,
+    /// 应用包名
+    pub app: Option<String>,
+    /// 来源宏所在的设备
+    pub device_serial: String,
+    pub created_at: DateTime<Utc>,
+    pub steps: Vec<AnnotatedStep>,
+}
+
+impl WorkedExample {
+    /// 从录制的宏生成示范案例
+    ///
+    /// 每个步骤关联其开始时刻之前最近的一张截图，返回案例及需要保存的截图
+    /// `(文件名, base64)` 列表。
+    pub fn from_macro(
+        device_macro: &DeviceMacro,
+        task: &str,
+        app: Option<String>,
+    ) -> (Self, Vec<(String, String)>) {
+        let mut screenshots = Vec::new();
+        let steps = device_macro
+            .actions
+            .iter()
+            .enumerate()
+            .map(|(idx, action)| {
+                let (offset_ms, action_type, annotation) = annotate(action);
+                let screenshot = frame_before(&device_macro.frames, offset_ms).map(|frame| {
+                    let file_name = format!("step_{:03}.png", idx + 1);
+                    screenshots.push((file_name.clone(), frame.screenshot.clone()));
+                    file_name
+                });
+                AnnotatedStep {
+                    offset_ms,
+                    action_type: action_type.to_string(),
+                    annotation,
+                    screenshot,
+                }
+            })
+            .collect();
+
+        let example = Self {
+            name: device_macro.name.clone(),
+            task: task.to_string(),
+            app: app.or_else(|| device_macro.app.clone()),
+            device_serial: device_macro.device_serial.clone(),
+            created_at: Utc::now(),
+            steps,
+        };
+        (example, screenshots)
+    }
+
+    /// 格式化为规划提示词中的参考流程
+    pub fn to_prompt(&self) -> String {
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(idx, step)| format!("{}. {}", idx + 1, step.annotation))
+            .collect();
+        format!(
+            "任务: {}\n应用: {}\n步骤:\n{}",
+            self.task,
+            self.app.as_deref().unwrap_or("未知"),
+            steps.join("\n")
+        )
+    }
+}
+
+/// 将宏录制的物理坐标点换算为 0-1000 逻辑坐标
+fn logical_point(x: i32, y: i32, width: u16, height: u16) -> (u32, u32) {
+    (to_logical(x.max(0) as u32, width.into()), to_logical(y.max(0) as u32, height.into()))
+}
+
+/// 为单个宏操作生成 (时间偏移, 操作类型, 注释)
+fn annotate(action: &MacroAction) -> (u64, &'static str, String) {
+    match action {
+        MacroAction::Tap { offset_ms, x, y, screen_width, screen_height } => {
+            let (x, y) = logical_point(*x, *y, *screen_width, *screen_height);
+            (*offset_ms, "Tap", format!("点击 [{}, {}]", x, y))
+        }
+        MacroAction::LongPress { offset_ms, x, y, duration_ms, screen_width, screen_height } => {
+            let (x, y) = logical_point(*x, *y, *screen_width, *screen_height);
+            (*offset_ms, "LongPress", format!("长按 [{}, {}] {}ms", x, y, duration_ms))
+        }
+        MacroAction::Swipe { offset_ms, from_x, from_y, to_x, to_y, screen_width, screen_height, .. } => {
+            let (dx, dy) = (to_x - from_x, to_y - from_y);
+            let direction = if dy.abs() >= dx.abs() {
+                if dy < 0 { "向上" } else { "向下" }
+            } else if dx < 0 {
+                "向左"
+            } else {
+                "向右"
+            };
+            let from = logical_point(*from_x, *from_y, *screen_width, *screen_height);
+            let to = logical_point(*to_x, *to_y, *screen_width, *screen_height);
+            (
+                *offset_ms,
+                "Swipe",
+                format!("从 [{}, {}] {}滑动到 [{}, {}]", from.0, from.1, direction, to.0, to.1),
+            )
+        }
+        MacroAction::Scroll { offset_ms, vscroll, hscroll, .. } => {
+            let direction = if *vscroll > 0 {
+                "向上滚动"
+            } else if *vscroll < 0 {
+                "向下滚动"
+            } else if *hscroll > 0 {
+                "向右滚动"
+            } else {
+                "向左滚动"
+            };
+            (*offset_ms, "Scroll", direction.to_string())
+        }
+        MacroAction::Key { offset_ms, keycode } => {
+            let annotation = match keycode {
+                3 => "按 Home 键".to_string(),
+                4 => "按返回键".to_string(),
+                66 => "按回车键".to_string(),
+                187 => "打开最近任务".to_string(),
+                _ => format!("按键 (keycode={})", keycode),
+            };
+            (*offset_ms, "Key", annotation)
+        }
+        MacroAction::Text { offset_ms, text } => (*offset_ms, "Type", format!("输入文本 \"{}\"", text)),
+    }
+}
+
+/// 查找偏移之前（含）最近的一张截图
+fn frame_before(frames: &[MacroFrame], offset_ms: u64) -> Option<&MacroFrame> {
+    frames.iter().rev().find(|f| f.offset_ms <= offset_ms)
+}
+
+/// 将任务描述切分为检索词：ASCII 单词按空白切分，其余字符使用二元组
+fn tokenize(text: &str) -> HashSet<String> {
+    let mut tokens = HashSet::new();
+    let mut word = String::new();
+    let mut cjk: Vec<char> = Vec::new();
+
+    let flush_cjk = |cjk: &mut Vec<char>, tokens: &mut HashSet<String>| {
+        if cjk.len() == 1 {
+            tokens.insert(cjk[0].to_string());
+        }
+        for pair in cjk.windows(2) {
+            tokens.insert(pair.iter().collect());
+        }
+        cjk.clear();
+    };
+
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            flush_cjk(&mut cjk, &mut tokens);
+            word.push(c.to_ascii_lowercase());
+        } else {
+            if !word.is_empty() {
+                tokens.insert(std::mem::take(&mut word));
+            }
+            if c.is_alphanumeric() {
+                cjk.push(c);
+            } else {
+                flush_cjk(&mut cjk, &mut tokens);
+            }
+        }
+    }
+    if !word.is_empty() {
+        tokens.insert(word);
+    }
+    flush_cjk(&mut cjk, &mut tokens);
+    tokens
+}
+
+/// 任务相似度：查询检索词在案例任务中出现的比例
+fn similarity(query: &HashSet<String>, task: &str) -> f64 {
+    if query.is_empty() {
+        return 0.0;
+    }
+    let task_tokens = tokenize(task);
+    query.intersection(&task_tokens).count() as f64 / query.len() as f64
+}
+
+/// 将应用包名转换为安全的目录名；只由 `.` 组成的名称（如 `..`）会指向知识库之外，按未知应用处理
+fn app_dir_name(app: Option<&str>) -> String {
+    match app {
+        Some(app) if !app.chars().all(|c| c == '.') => app
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '.' || c == '_' { c } else { '_' })
+            .collect(),
+        _ => UNKNOWN_APP.to_string(),
+    }
+}
+
+/// 按应用分目录保存示范案例的知识库
+///
+/// 目录结构: `{root}/{app}/{name}.json`，截图保存在 `{root}/{app}/{name}/`。
+pub struct KnowledgeBase {
+    root: PathBuf,
+}
+
+impl Default for KnowledgeBase {
+    fn default() -> Self {
+        Self::new(KNOWLEDGE_DIR)
+    }
+}
+
+impl KnowledgeBase {
+    /// 创建指定根目录的知识库
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 保存示范案例及其截图
+    pub async fn save(
+        &self,
+        example: &WorkedExample,
+        screenshots: &[(String, String)],
+    ) -> Result<PathBuf, String> {
+        crate::scrcpy::macro_recorder::validate_name(&example.name)?;
+        let app_dir = self.root.join(app_dir_name(example.app.as_deref()));
+        let shot_dir = app_dir.join(&example.name);
+        tokio::fs::create_dir_all(&shot_dir)
+            .await
+            .map_err(|e| format!("创建知识库目录失败: {:?}", e))?;
+
+        for (file_name, data) in screenshots {
+            let bytes = BASE64_STANDARD
+                .decode(data)
+                .map_err(|e| format!("解码截图失败: {}", e))?;
+            tokio::fs::write(shot_dir.join(file_name), bytes)
+                .await
+                .map_err(|e| format!("写入截图失败: {:?}", e))?;
+        }
+
+        let path = app_dir.join(format!("{}.json", example.name));
+        let json = serde_json::to_vec_pretty(example).map_err(|e| e.to_string())?;
+        tokio::fs::write(&path, json)
+            .await
+            .map_err(|e| format!("写入示范案例失败: {:?}", e))?;
+        Ok(path)
+    }
+
+    /// 加载某个应用目录下的所有示范案例
+    async fn load_dir(&self, dir: PathBuf) -> Vec<WorkedExample> {
+        let mut examples = Vec::new();
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            return examples;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match tokio::fs::read(&path).await.map(|json| serde_json::from_slice(&json)) {
+                Ok(Ok(example)) => examples.push(example),
+                _ => tracing::warn!("跳过无法解析的示范案例: {:?}", path),
+            }
+        }
+        examples
+    }
+
+    /// 列出示范案例；指定应用时只列出该应用的案例
+    pub async fn list(&self, app: Option<&str>) -> Vec<WorkedExample> {
+        if app.is_some() {
+            return self.load_dir(self.root.join(app_dir_name(app))).await;
+        }
+
+        let mut examples = Vec::new();
+        if let Ok(mut entries) = tokio::fs::read_dir(&self.root).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if entry.file_type().await.is_ok_and(|t| t.is_dir()) {
+                    examples.extend(self.load_dir(entry.path()).await);
+                }
+            }
+        }
+        examples
+    }
+
+    /// 检索与任务相似的示范案例，按相似度从高到低返回最多 `limit` 个
+    ///
+    /// 指定应用时优先在该应用中检索，没有结果再检索全部应用。
+    pub async fn find_similar(&self, task: &str, app: Option<&str>, limit: usize) -> Vec<WorkedExample> {
+        let query = tokenize(task);
+        let rank = |examples: Vec<WorkedExample>| {
+            let mut scored: Vec<(f64, WorkedExample)> = examples
+                .into_iter()
+                .map(|e| (similarity(&query, &e.task), e))
+                .filter(|(score, _)| *score >= MIN_SIMILARITY)
+                .collect();
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+            scored.into_iter().take(limit).map(|(_, e)| e).collect::<Vec<_>>()
+        };
+
+        if app.is_some() {
+            let found = rank(self.list(app).await);
+            if !found.is_empty() {
+                return found;
+            }
+        }
+        rank(self.list(None).await)
+    }
+}
+
+/// 将检索到的示范案例格式化为规划提示词片段，没有案例时返回 None
+pub fn format_worked_examples(examples: &[WorkedExample]) -> Option<String> {
+    if examples.is_empty() {
+        return None;
+    }
+    let body: Vec<String> = examples
+        .iter()
+        .enumerate()
+        .map(|(idx, e)| format!("## 参考案例 {}\n{}", idx + 1, e.to_prompt()))
+        .collect();
+    Some(format!(
+        "# 人工演示的参考流程\n以下是用户演示过的相似任务，坐标为 0-1000 逻辑坐标，界面可能已变化，仅作参考：\n\n{}",
+        body.join("\n\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demo_macro() -> DeviceMacro {
+        DeviceMacro {
+            name: "open_moments".to_string(),
+            device_serial: "emulator-5554".to_string(),
+            created_at: Utc::now(),
+            duration_ms: 3000,
+            actions: vec![
+                MacroAction::Tap { offset_ms: 100, x: 540, y: 1200, screen_width: 1080, screen_height: 2400 },
+                MacroAction::Swipe {
+                    offset_ms: 1000,
+                    from_x: 540,
+                    from_y: 1800,
+                    to_x: 540,
+                    to_y: 600,
+                    duration_ms: 300,
+                    screen_width: 1080,
+                    screen_height: 2400,
+                },
+                MacroAction::Key { offset_ms: 2000, keycode: 4 },
+            ],
+            events: Vec::new(),
+            app: Some("com.tencent.mm".to_string()),
+            frames: vec![
+                MacroFrame { offset_ms: 0, screenshot: "a".to_string() },
+                MacroFrame { offset_ms: 1000, screenshot: "b".to_string() },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_from_macro_annotates_steps() {
+        let (example, screenshots) = WorkedExample::from_macro(&demo_macro(), "打开微信朋友圈", None);
+
+        assert_eq!(example.app.as_deref(), Some("com.tencent.mm"));
+        assert_eq!(example.steps.len(), 3);
+        assert_eq!(example.steps[0].annotation, "点击 [500, 500]");
+        assert_eq!(example.steps[1].annotation, "从 [500, 750] 向上滑动到 [500, 250]");
+        assert_eq!(example.steps[2].annotation, "按返回键");
+
+        assert_eq!(screenshots.len(), 3);
+        assert_eq!(screenshots[0].1, "a");
+        assert_eq!(screenshots[1].1, "b");
+        assert_eq!(example.steps[1].screenshot.as_deref(), Some("step_002.png"));
+    }
+
+    #[test]
+    fn test_app_dir_name_stays_inside_root() {
+        assert_eq!(app_dir_name(Some("com.tencent.mm")), "com.tencent.mm");
+        assert_eq!(app_dir_name(Some("..")), UNKNOWN_APP);
+        assert_eq!(app_dir_name(Some(".")), UNKNOWN_APP);
+        assert_eq!(app_dir_name(Some("../etc")), ".._etc");
+        assert_eq!(app_dir_name(None), UNKNOWN_APP);
+    }
+
+    #[test]
+    fn test_similarity_ranking() {
+        let query = tokenize("打开微信查看朋友圈");
+        assert!(similarity(&query, "打开微信朋友圈") >= MIN_SIMILARITY);
+        assert!(similarity(&query, "在淘宝搜索耳机") < MIN_SIMILARITY);
+        assert!(similarity(&tokenize("Open WeChat"), "open wechat moments") > 0.9);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod conversation;
+pub mod knowledge;
 pub mod memory;
 
 pub use conversation::*;
+pub use knowledge::*;
 pub use memory::*;
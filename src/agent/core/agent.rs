@@ -2,13 +2,53 @@ use std::sync::Arc;
 use tokio::sync::{RwLock, Mutex};
 use tokio::task::AbortHandle;
 use tracing::{debug, info, warn, error};
+use crate::agent::actions::ActionEnum;
 use crate::agent::core::traits::{Device, Agent, AgentStatus, AgentFeedback, ExecutionStep, ModelClient, Action};
-use crate::agent::core::state::{AgentRuntime, AgentConfig, AgentState};
-use crate::agent::executor::ActionHandler;
-use crate::agent::context::{ConversationContext, ShortTermMemory};
+use crate::agent::core::artifacts::{artifact_store, content_type, ArtifactKind, ArtifactStore, TaskArtifact};
+use crate::agent::core::callback::{self, CallbackArtifacts, CallbackPayload, CallbackStatus, TaskCallback};
+use crate::config::API_VERSION_PREFIX;
+use crate::agent::core::history::{HistoryPage, HistoryQuery};
+use crate::agent::core::message::{append_to_last_user_message, ChatMessage};
+use crate::agent::core::progress::{self, TaskProgress};
+use crate::agent::core::prompt_builder::{PromptBuilder, SystemPromptContext};
+use crate::agent::core::result_summary::StepResultSummary;
+use crate::agent::core::screenshot_history::ScreenshotHistory;
+use crate::agent::core::screenshot_store::ScreenshotStore;
+use crate::agent::core::transcript::{self, ConversationStep};
+use crate::agent::core::state::{AgentRuntime, AgentConfig, AgentState, FailureReason, TaskLimits, TaskStats};
+use crate::agent::executor::{self as executor, ActionHandler, DeviceClock, ForegroundGuard, HumanizeOptions, Observation, ScreenObserver, ScreenRecording, TrafficCapture, UsageExpectation};
+use crate::agent::context::{format_worked_examples, ConversationContext, KnowledgeBase, ShortTermMemory};
+use crate::agent::experiments::{ExperimentAssignment, ExperimentRegistry, TaskOutcome, Variant};
+use crate::agent::llm::{JudgeClient, TaskEvaluation};
+use crate::agent::llm::logging::LoggingClient;
 use crate::agent::logger::AgentLogger;
 use crate::error::AppError;
 
+/// 规划时最多引用的人工演示案例数
+const MAX_WORKED_EXAMPLES: usize = 2;
+
+/// Agent 日志目录（操作日志、步骤截图）
+pub const AGENT_LOG_DIR: &str = "logs/agent";
+
+/// 下一个任务使用的提示词/模型变体
+struct TaskVariant {
+    variant: Variant,
+    /// 变体覆盖了模型配置时使用的模型客户端
+    model_client: Option<Arc<dyn ModelClient>>,
+    /// 所属实验（注册表与实验 ID），任务结束后记录结果
+    experiment: Option<(Arc<ExperimentRegistry>, String)>,
+}
+
+/// 下一个任务的模型与参数覆盖
+struct TaskOverride {
+    /// 覆盖了模型配置时使用的模型客户端
+    model_client: Option<Arc<dyn ModelClient>>,
+    /// 最大步数
+    max_steps: Option<usize>,
+    /// 最长执行时间（秒）
+    max_execution_time: Option<u64>,
+}
+
 /// 手机自动化 Agent
 pub struct PhoneAgent {
     id: String,
@@ -19,10 +59,53 @@ pub struct PhoneAgent {
     conversation: Arc<ConversationContext>,
     memory: Arc<ShortTermMemory>,
     abort_handle: Arc<Mutex<Option<AbortHandle>>>,
-    messages: Arc<RwLock<Vec<crate::agent::core::traits::ChatMessage>>>,
+    messages: Arc<RwLock<Vec<ChatMessage>>>,
     logger: Arc<AgentLogger>,
+    /// 任务开始时的输入法，任务结束后恢复
+    original_ime: Arc<Mutex<Option<String>>>,
+    /// 下一个任务是否记录网络流量
+    capture_traffic: Arc<Mutex<bool>>,
+    /// 进行中的流量记录
+    traffic: Arc<Mutex<Option<TrafficCapture>>>,
+    /// 最近一次任务的流量记录文件
+    traffic_artifact: Arc<RwLock<Option<String>>>,
+    /// 下一个任务是否在设备端录屏
+    record_screen: Arc<Mutex<bool>>,
+    /// 进行中的设备端录屏
+    recording: Arc<Mutex<Option<ScreenRecording>>>,
+    /// 最近一次任务的录像分段文件
+    recording_artifacts: Arc<RwLock<Vec<String>>>,
+    /// 下一个任务完成后需校验的应用使用情况
+    usage_expectation: Arc<Mutex<Option<UsageExpectation>>>,
+    /// 下一个任务的输入拟人化选项
+    humanize: Arc<Mutex<Option<HumanizeOptions>>>,
+    /// 下一个任务的目标应用，离开时提醒模型或重新启动
+    foreground_guard: Arc<Mutex<Option<ForegroundGuard>>>,
+    /// 评估模型（可选）
+    judge: Option<Arc<JudgeClient>>,
+    /// 最近一次任务的评估结果
+    evaluation: Arc<RwLock<Option<TaskEvaluation>>>,
+    /// 最近一次任务 finish 时模型给出的结果
+    task_result: Arc<RwLock<Option<String>>>,
+    /// 下一个任务使用的变体
+    variant: Arc<Mutex<Option<TaskVariant>>>,
+    /// 下一个任务的模型与参数覆盖
+    task_override: Arc<Mutex<Option<TaskOverride>>>,
+    /// 执行步骤截图的磁盘存储
+    screenshot_store: ScreenshotStore,
+    /// 下一个任务持有的资源（如设备池的执行许可），任务结束或被停止时释放
+    task_guard: Arc<Mutex<Option<TaskGuard>>>,
+    /// 下一个任务的结果回调
+    callback: Arc<Mutex<Option<TaskCallback>>>,
+    /// 进行中的任务的结果回调，任务结束或被停止时取出投递
+    active_callback: Arc<Mutex<Option<TaskCallback>>>,
+    /// 最近一次任务开始时读取的设备时钟
+    device_clock: Arc<RwLock<Option<DeviceClock>>>,
 }
 
+/// 任务执行期间持有、结束时释放的资源
+pub type TaskGuard = Box<dyn std::any::Any + Send + Sync>;
+
 impl PhoneAgent {
     /// 创建新的 PhoneAgent
     pub fn new(
@@ -34,12 +117,17 @@ impl PhoneAgent {
         let action_handler = Arc::new(ActionHandler::new(Arc::clone(&device)));
 
         // 创建日志记录器
-        let log_dir = "logs/agent";
+        let log_dir = AGENT_LOG_DIR;
         let logger = Arc::new(AgentLogger::new(&id, log_dir)
-            .map_err(|e| AppError::Unknown(format!("创建日志记录器失败: {}", e)))?);
+            .map_err(|e| AppError::Unknown(format!("创建日志记录器失败: {}", e)))?
+            .with_device_serial(device.serial())
+            .with_screenshot_policy(config.screenshot_log.clone()));
+
+        let screenshot_store = ScreenshotStore::new(format!("{}/step_screenshots/{}", log_dir, id))
+            .with_policy(config.screenshot_log.clone());
 
-        // 将 logger 传递给 model_client
-        model_client.set_logger(Some(logger.clone()));
+        // 所有模型查询都记录到 Agent 日志
+        let model_client = LoggingClient::wrap(model_client, logger.clone());
 
         Ok(Self {
             id,
@@ -52,42 +140,493 @@ impl PhoneAgent {
             abort_handle: Arc::new(Mutex::new(None)),
             messages: Arc::new(RwLock::new(Vec::new())),
             logger,
+            original_ime: Arc::new(Mutex::new(None)),
+            capture_traffic: Arc::new(Mutex::new(false)),
+            traffic: Arc::new(Mutex::new(None)),
+            traffic_artifact: Arc::new(RwLock::new(None)),
+            record_screen: Arc::new(Mutex::new(false)),
+            recording: Arc::new(Mutex::new(None)),
+            recording_artifacts: Arc::new(RwLock::new(Vec::new())),
+            usage_expectation: Arc::new(Mutex::new(None)),
+            humanize: Arc::new(Mutex::new(None)),
+            foreground_guard: Arc::new(Mutex::new(None)),
+            judge: None,
+            evaluation: Arc::new(RwLock::new(None)),
+            task_result: Arc::new(RwLock::new(None)),
+            variant: Arc::new(Mutex::new(None)),
+            task_override: Arc::new(Mutex::new(None)),
+            screenshot_store,
+            task_guard: Arc::new(Mutex::new(None)),
+            callback: Arc::new(Mutex::new(None)),
+            active_callback: Arc::new(Mutex::new(None)),
+            device_clock: Arc::new(RwLock::new(None)),
         })
     }
 
+    /// 设置评估模型，任务 finish 后由其判定是否成功
+    pub fn with_judge(mut self, judge: Option<Arc<JudgeClient>>) -> Self {
+        self.judge = judge;
+        self
+    }
+
     /// 获取 Agent ID
     pub fn id(&self) -> &str {
         &self.id
     }
 
+    /// 所在设备序列号
+    pub fn device_serial(&self) -> &str {
+        self.device.serial()
+    }
+
+    /// 正在执行的任务 ID（进度事件与日志中的 `task_id`），启动任务时即生成
+    pub async fn task_id(&self) -> Option<String> {
+        self.logger.task_id().await
+    }
+
+    /// 所在设备
+    pub fn device(&self) -> &Arc<dyn Device> {
+        &self.device
+    }
+
+    /// 是否有尚未恢复的暂停请求
+    pub fn pause_requested(&self) -> bool {
+        *self.runtime.pause_requested.borrow()
+    }
+
+    /// 操作处理器，可绕过模型直接在设备上执行操作
+    pub fn action_handler(&self) -> &Arc<ActionHandler> {
+        &self.action_handler
+    }
+
+    /// 最近一次任务 finish 时模型给出的结果
+    pub async fn task_result(&self) -> Option<String> {
+        self.task_result.read().await.clone()
+    }
+
+    /// 设置下一个任务是否通过代理记录网络流量
+    pub async fn set_capture_traffic(&self, enabled: bool) {
+        *self.capture_traffic.lock().await = enabled;
+    }
+
+    /// 设置下一个任务是否用设备自带的 screenrecord 录屏
+    pub async fn set_record_screen(&self, enabled: bool) {
+        *self.record_screen.lock().await = enabled;
+    }
+
+    /// 设置下一个任务完成后需校验的应用使用情况
+    pub async fn set_usage_expectation(&self, expectation: Option<UsageExpectation>) {
+        *self.usage_expectation.lock().await = expectation;
+    }
+
+    /// 设置下一个任务的目标应用；None 表示不检查前台应用
+    pub async fn set_foreground_guard(&self, guard: Option<ForegroundGuard>) {
+        *self.foreground_guard.lock().await = guard;
+    }
+
+    /// 设置下一个任务持有的资源，任务结束或被停止时释放；None 表示清除
+    pub async fn set_task_guard(&self, guard: Option<TaskGuard>) {
+        *self.task_guard.lock().await = guard;
+    }
+
+    /// 设置下一个任务结束时的结果回调；None 表示不回调
+    pub async fn set_callback(&self, callback: Option<TaskCallback>) {
+        *self.callback.lock().await = callback;
+    }
+
+    /// 设置下一个任务的输入拟人化选项（随机点击偏移、弧线滑动、操作间停顿）
+    pub async fn set_humanize(&self, options: Option<HumanizeOptions>) {
+        *self.humanize.lock().await = options;
+    }
+
+    /// 使用指定的提示词/模型变体运行下一个任务
+    pub async fn set_variant(&self, variant: Variant, model_client: Option<Arc<dyn ModelClient>>) {
+        let model_client = model_client.map(|client| LoggingClient::wrap(client, self.logger.clone()));
+        *self.variant.lock().await = Some(TaskVariant { variant, model_client, experiment: None });
+    }
+
+    /// 将下一个任务加入实验，使用分配到的变体运行并在结束后记录结果
+    pub async fn set_experiment(
+        &self,
+        registry: Arc<ExperimentRegistry>,
+        assignment: ExperimentAssignment,
+        model_client: Option<Arc<dyn ModelClient>>,
+    ) {
+        self.set_variant(assignment.variant, model_client).await;
+        if let Some(variant) = self.variant.lock().await.as_mut() {
+            variant.experiment = Some((registry, assignment.experiment_id));
+        }
+    }
+
+    /// 使用指定的模型客户端、最大步数与最长执行时间运行下一个任务（优先于实验变体）
+    pub async fn set_task_override(
+        &self,
+        model_client: Option<Arc<dyn ModelClient>>,
+        max_steps: Option<usize>,
+        max_execution_time: Option<u64>,
+    ) {
+        let model_client = model_client.map(|client| LoggingClient::wrap(client, self.logger.clone()));
+        *self.task_override.lock().await = Some(TaskOverride { model_client, max_steps, max_execution_time });
+    }
+
+    /// 当前（或最近一次）任务的步数、耗时与 token 消耗
+    pub async fn task_stats(&self) -> TaskStats {
+        self.runtime.stats().await
+    }
+
+    /// 最近一次任务的流量记录文件（HAR）路径
+    pub async fn traffic_artifact(&self) -> Option<String> {
+        self.traffic_artifact.read().await.clone()
+    }
+
     /// 初始化消息列表（添加系统提示词）
     async fn initialize_messages(&self, system_prompt: String) {
         let mut messages = self.messages.write().await;
         messages.clear();
-        messages.push(crate::agent::core::traits::ChatMessage {
-            role: crate::agent::core::traits::MessageRole::System,
-            content: system_prompt,
-        });
+        messages.push(ChatMessage::system(system_prompt));
     }
 
     /// 添加用户消息
     async fn add_user_message(&self, content: String) {
         let mut messages = self.messages.write().await;
-        messages.push(crate::agent::core::traits::ChatMessage {
-            role: crate::agent::core::traits::MessageRole::User,
-            content,
-        });
+        messages.push(ChatMessage::user(content));
+    }
+
+    /// 将较早步骤的操作结果消息折叠为单行，只保留最近几步的完整结果
+    async fn collapse_result_messages(&self, prompt_builder: &PromptBuilder, results: &mut [(usize, StepResultSummary, bool)]) {
+        let keep = self.runtime.config.full_result_steps;
+        let collapse_count = results.len().saturating_sub(keep);
+        let mut messages = self.messages.write().await;
+        for (index, summary, collapsed) in results[..collapse_count].iter_mut() {
+            if *collapsed {
+                continue;
+            }
+            if let Some(message) = messages.get_mut(*index) {
+                *message = ChatMessage::user(prompt_builder.collapsed_result_summary(summary));
+            }
+            *collapsed = true;
+        }
     }
 
     /// 添加助手消息（操作执行结果）
     async fn add_assistant_message(&self, content: String) {
         let mut messages = self.messages.write().await;
-        messages.push(crate::agent::core::traits::ChatMessage {
-            role: crate::agent::core::traits::MessageRole::Assistant,
-            content,
+        messages.push(ChatMessage::assistant(content));
+    }
+
+    /// 从知识库检索与任务相似的人工演示案例，生成规划参考
+    async fn worked_examples_prompt(&self, task: &str) -> Option<String> {
+        let app = self.device.current_app().await.ok();
+        let examples = KnowledgeBase::default()
+            .find_similar(task, app.as_deref(), MAX_WORKED_EXAMPLES)
+            .await;
+        if !examples.is_empty() {
+            info!("为任务检索到 {} 个参考案例", examples.len());
+        }
+        format_worked_examples(&examples)
+    }
+
+    /// 记录任务开始时的输入法
+    async fn remember_input_method(&self) {
+        match self.device.current_input_method().await {
+            Ok(ime) => *self.original_ime.lock().await = ime,
+            Err(e) => warn!("获取当前输入法失败: {}", e),
+        }
+    }
+
+    /// 任务期间输入法被切换时恢复为任务开始时的输入法
+    async fn restore_input_method(&self) {
+        let Some(original) = self.original_ime.lock().await.take() else {
+            return;
+        };
+        match self.device.current_input_method().await {
+            Ok(Some(current)) if current == original => {}
+            _ => {
+                info!("恢复任务开始时的输入法: {}", original);
+                if let Err(e) = self.device.set_input_method(&original).await {
+                    warn!("恢复输入法失败: {}", e);
+                }
+            }
+        }
+    }
+
+    /// 按任务设置启动流量记录
+    async fn start_traffic_capture(&self) {
+        if !std::mem::take(&mut *self.capture_traffic.lock().await) {
+            return;
+        }
+        match TrafficCapture::start(self.device.serial()).await {
+            Ok(capture) => *self.traffic.lock().await = Some(capture),
+            Err(e) => warn!("启动流量记录失败: {}", e),
+        }
+    }
+
+    /// 结束流量记录并保存为任务产物
+    async fn finish_traffic_capture(&self, task_id: &str) {
+        let Some(capture) = self.traffic.lock().await.take() else {
+            return;
+        };
+        let har = capture.finish().await;
+        match har.save(task_id).await {
+            Ok(path) => {
+                info!("任务 {} 的流量记录已保存: {}", task_id, path);
+                if let Err(e) = self.logger.log_task_artifact(task_id, "traffic", &path).await {
+                    warn!("记录任务产物失败: {}", e);
+                }
+                *self.traffic_artifact.write().await = Some(path);
+            }
+            Err(e) => warn!("保存流量记录失败: {}", e),
+        }
+    }
+
+    /// 按任务设置在设备上开始录屏
+    async fn start_screen_recording(&self, task_id: &str) {
+        if !std::mem::take(&mut *self.record_screen.lock().await) {
+            return;
+        }
+        // 使用连接时探测的能力矩阵，未探测时直接尝试
+        if self.device.capabilities().is_some_and(|capabilities| !capabilities.screenrecord) {
+            warn!("设备 {} 不支持 screenrecord，跳过屏幕录制", self.device.serial());
+            return;
+        }
+        match ScreenRecording::start(self.device.serial(), task_id).await {
+            Ok(recording) => *self.recording.lock().await = Some(recording),
+            Err(e) => warn!("启动屏幕录制失败: {}", e),
+        }
+    }
+
+    /// 结束录屏，拉取分段并保存为任务产物
+    async fn finish_screen_recording(&self, task_id: &str) {
+        let Some(recording) = self.recording.lock().await.take() else {
+            return;
+        };
+        match recording.finish().await {
+            Ok(files) => {
+                for path in &files {
+                    if let Err(e) = self.logger.log_task_artifact(task_id, "recording", path).await {
+                        warn!("记录任务产物失败: {}", e);
+                    }
+                }
+                *self.recording_artifacts.write().await = files;
+            }
+            Err(e) => warn!("保存屏幕录像失败: {}", e),
+        }
+    }
+
+    /// 在后台把任务的步骤截图、对话报告、流量记录与录像上传到产物存储
+    async fn upload_artifacts(&self, task_id: &str) {
+        let traffic = self.traffic_artifact.read().await.clone();
+        let recordings = self.recording_artifacts.read().await.clone();
+        let logger = Arc::clone(&self.logger);
+        let task_id = task_id.to_string();
+        tokio::spawn(async move {
+            let mut files: Vec<(ArtifactKind, String, String)> = Vec::new();
+            let log_task_id = task_id.clone();
+            let conversation =
+                tokio::task::spawn_blocking(move || transcript::load_task_conversation(AGENT_LOG_DIR, &log_task_id)).await;
+            let mut report = None;
+            match conversation {
+                Ok(Ok(Some(conversation))) => {
+                    let mut seen = std::collections::HashSet::new();
+                    // 内嵌缩略图的截图没有文件，随对话报告一起上传
+                    for screenshot in conversation.messages.iter().flat_map(|m| &m.images) {
+                        if !screenshot.path.is_empty() && seen.insert(screenshot.hash.clone()) {
+                            let extension = std::path::Path::new(&screenshot.path)
+                                .extension()
+                                .and_then(|e| e.to_str())
+                                .unwrap_or("png");
+                            let name = format!("screenshots/{}.{}", screenshot.hash, extension);
+                            files.push((ArtifactKind::Screenshot, name, screenshot.path.clone()));
+                        }
+                    }
+                    report = serde_json::to_vec_pretty(&conversation).ok();
+                }
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => warn!("读取任务 {} 的对话记录失败: {}", task_id, e),
+                Err(e) => warn!("读取任务 {} 的对话记录失败: {}", task_id, e),
+            }
+            if let Some(path) = traffic {
+                files.push((ArtifactKind::Traffic, "traffic.har".to_string(), path));
+            }
+            for (index, path) in recordings.into_iter().enumerate() {
+                files.push((ArtifactKind::Recording, format!("recordings/segment_{}.mp4", index), path));
+            }
+
+            let store = artifact_store();
+            let mut total = 0;
+            let mut uploaded = 0;
+            if let Some(report) = report {
+                total += 1;
+                if put_artifact(store.as_ref(), &logger, &task_id, ArtifactKind::Report, "conversation.json".to_string(), report).await {
+                    uploaded += 1;
+                }
+            }
+            // 逐个读取并上传，内存中同时只保留一个文件（录像分段可能很大）
+            for (kind, name, path) in files {
+                let body = match tokio::fs::read(&path).await {
+                    Ok(body) => body,
+                    Err(e) => {
+                        warn!("读取任务产物 {} 失败: {}", path, e);
+                        continue;
+                    }
+                };
+                total += 1;
+                if put_artifact(store.as_ref(), &logger, &task_id, kind, name, body).await {
+                    uploaded += 1;
+                }
+            }
+            if total > 0 {
+                info!("任务 {} 的产物已上传 {}/{} 个", task_id, uploaded, total);
+            }
+        });
+    }
+
+    /// 模型声明完成后，校验目标应用确实在前台运行了预期时长，未通过时将任务标记为失败
+    async fn verify_app_usage(&self, expectation: &UsageExpectation, since: chrono::NaiveDateTime, task: &str) {
+        if !matches!(*self.runtime.state.read().await, AgentState::Completed { .. }) {
+            return;
+        }
+
+        let locale = self.runtime.config.locale;
+        let error = match executor::verify_app_usage(self.device.serial(), since, expectation, task).await {
+            Ok(report) if report.passed() => {
+                info!("应用使用校验通过: {}", report.summary());
+                return;
+            }
+            Ok(report) => locale.failure_message(FailureReason::ActionRejected, report.summary()),
+            Err(e) => format!("{}: {}", locale.pick("应用使用校验失败", "App usage verification failed"), e),
+        };
+
+        let step = self.runtime.current_step().await;
+        self.fail(FailureReason::ActionRejected, error.clone()).await;
+        if let Err(e) = self.logger.log_task_failed(&error, step).await {
+            warn!("记录任务失败失败: {}", e);
+        }
+    }
+
+    /// 使用评估模型对已完成的任务进行评分
+    async fn evaluate_task(&self, task: &str, task_id: &str) {
+        let Some(judge) = &self.judge else {
+            return;
+        };
+        if !matches!(*self.runtime.state.read().await, AgentState::Completed { .. }) {
+            return;
+        }
+
+        // 最后一步执行前的截图与当前的最终界面
+        let result = self.task_result.read().await.clone().unwrap_or_default();
+        let mut screenshots: Vec<String> = self
+            .runtime
+            .execution_history
+            .read()
+            .await
+            .last()
+            .and_then(|step| step.screenshot.as_ref().and_then(|stored| stored.load().ok()))
+            .into_iter()
+            .collect();
+        match self.device.screenshot().await {
+            Ok(screenshot) => screenshots.push(screenshot),
+            Err(e) => warn!("获取最终截图失败: {}", e),
+        }
+        if screenshots.is_empty() {
+            warn!("没有可用于评估的截图，跳过任务评估");
+            return;
+        }
+
+        match judge.evaluate(task, &result, &screenshots).await {
+            Ok(evaluation) => {
+                info!(
+                    "任务评估结果: success={}, score={:.2}, 依据: {}",
+                    evaluation.success, evaluation.score, evaluation.rationale
+                );
+                if let Err(e) = self.logger.log_task_evaluation(task_id, &evaluation).await {
+                    warn!("记录任务评估失败: {}", e);
+                }
+                *self.evaluation.write().await = Some(evaluation);
+            }
+            Err(e) => warn!("任务评估失败: {}", e),
+        }
+    }
+
+    /// 将任务结果记录到所属实验
+    async fn record_experiment_outcome(&self, variant: &Variant, experiment: &(Arc<ExperimentRegistry>, String), task: &str) {
+        let (steps, completed) = match &*self.runtime.state.read().await {
+            AgentState::Completed { steps, .. } => (*steps, true),
+            AgentState::Failed { step, .. } => (*step, false),
+            _ => return,
+        };
+        let evaluation = self.evaluation.read().await.clone();
+
+        let outcome = TaskOutcome {
+            variant: variant.name.clone(),
+            task: task.to_string(),
+            success: completed && evaluation.as_ref().is_none_or(|e| e.success),
+            steps,
+            duration_ms: self.runtime.elapsed_ms().await,
+            tokens_used: *self.runtime.tokens_used.read().await,
+            judge_score: evaluation.map(|e| e.score),
+            finished_at: chrono::Utc::now(),
+        };
+        let (registry, experiment_id) = experiment;
+        registry.record(experiment_id, outcome).await;
+    }
+
+    /// 取出进行中任务的结果回调并在后台投递；`status` 为 None 时按任务最终状态确定
+    async fn send_callback(&self, task: &str, task_id: &str, status: Option<CallbackStatus>) {
+        let Some(task_callback) = self.active_callback.lock().await.take() else {
+            return;
+        };
+
+        let (status, failure_reason, error) = self.final_status(status).await;
+        let payload = CallbackPayload {
+            task_id: task_callback.task_id.clone(),
+            agent_id: self.id.clone(),
+            device_serial: self.device.serial().to_string(),
+            device: crate::scrcpy::device_info::cached(self.device.serial()),
+            task: task.to_string(),
+            status,
+            result: if status == CallbackStatus::Completed { self.task_result.read().await.clone() } else { None },
+            failure_reason,
+            error,
+            stats: self.runtime.stats().await,
+            device_clock: self.device_clock.read().await.clone(),
+            evaluation: self.evaluation.read().await.clone(),
+            artifacts: CallbackArtifacts {
+                history: format!("{}/device/{}/history", API_VERSION_PREFIX, self.device.serial()),
+                conversation: format!("{}/tasks/{}/conversation", API_VERSION_PREFIX, task_id),
+                screenshots: Some(self.screenshot_store.dir().to_string_lossy().into_owned()),
+                traffic: self.traffic_artifact.read().await.clone(),
+                recordings: self.recording_artifacts.read().await.clone(),
+                uploaded: format!("{}/tasks/{}/artifacts", API_VERSION_PREFIX, task_id),
+            },
+            finished_at: chrono::Utc::now(),
+        };
+
+        let secret = self.runtime.config.callback_secret.clone();
+        tokio::spawn(async move {
+            if let Err(e) = callback::deliver(&task_callback, &payload, secret.as_deref()).await {
+                warn!("{}", e);
+            }
         });
     }
 
+    /// 读取设备时区与时钟偏差，之后的步骤据此记录设备时间
+    async fn sync_device_clock(&self, task_id: &str) {
+        let clock = match DeviceClock::read(self.device.serial()).await {
+            Ok(clock) => {
+                if let Err(e) = self.logger.log_device_clock(task_id, &clock).await {
+                    warn!("记录设备时钟失败: {}", e);
+                }
+                Some(clock)
+            }
+            Err(e) => {
+                warn!("读取设备时钟失败，步骤只记录主机时间: {}", e);
+                None
+            }
+        };
+        *self.device_clock.write().await = clock;
+    }
+
     /// 运行 Agent 主循环
     async fn run_agent_loop(&self, task: String) {
         info!("Agent {} 开始执行任务: {}", self.id, task);
@@ -96,6 +635,112 @@ impl PhoneAgent {
         if let Err(e) = self.logger.log_task_start(&task).await {
             warn!("记录任务开始失败: {}", e);
         }
+        let task_id = self.logger.task_id().await.unwrap_or_else(|| self.id.clone());
+        if let Some(metadata) = crate::scrcpy::device_info::cached(self.device.serial())
+            && let Err(e) = self.logger.log_task_device(&task_id, &metadata).await
+        {
+            warn!("记录设备信息失败: {}", e);
+        }
+        self.sync_device_clock(&task_id).await;
+
+        let variant = self.variant.lock().await.take();
+        let task_override = self.task_override.lock().await.take();
+        let defaults = self.runtime.config.limits();
+        let limits = TaskLimits {
+            max_steps: task_override.as_ref().and_then(|o| o.max_steps).unwrap_or(defaults.max_steps),
+            max_execution_time: task_override
+                .as_ref()
+                .and_then(|o| o.max_execution_time)
+                .unwrap_or(defaults.max_execution_time),
+        };
+        *self.runtime.limits.write().await = limits;
+        progress::publish_progress(
+            &task_id,
+            &self.id,
+            self.device.serial(),
+            TaskProgress::Started { task: task.clone(), limits },
+        );
+
+        self.remember_input_method().await;
+        self.start_traffic_capture().await;
+        self.start_screen_recording(&task_id).await;
+        *self.active_callback.lock().await = self.callback.lock().await.take();
+        self.action_handler.set_humanize(self.humanize.lock().await.take()).await;
+        self.action_handler.set_screen_check(self.runtime.config.screen_check).await;
+        self.action_handler.set_type_verification(self.runtime.config.type_verification).await;
+
+        // 记录任务开始时的设备时间，作为应用使用校验的起点
+        let usage_check = match self.usage_expectation.lock().await.take() {
+            Some(expectation) => match executor::device_now(self.device.serial()).await {
+                Ok(since) => Some((expectation, since)),
+                Err(e) => {
+                    warn!("获取设备时间失败，跳过应用使用校验: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let model_client = task_override
+            .as_ref()
+            .and_then(|o| o.model_client.clone())
+            .or_else(|| variant.as_ref().and_then(|v| v.model_client.clone()))
+            .unwrap_or_else(|| Arc::clone(&self.model_client));
+        let prompt_suffix = variant.as_ref().and_then(|v| v.variant.prompt_suffix.clone());
+        let foreground_guard = self.foreground_guard.lock().await.take();
+
+        self.run_task_steps(task.clone(), model_client, prompt_suffix, limits, foreground_guard).await;
+        if let Some((expectation, since)) = usage_check {
+            self.verify_app_usage(&expectation, since, &task).await;
+        }
+        self.evaluate_task(&task, &task_id).await;
+        if let Some(TaskVariant { variant, experiment: Some(experiment), .. }) = &variant {
+            self.record_experiment_outcome(variant, experiment, &task).await;
+        }
+        self.finish_traffic_capture(&task_id).await;
+        self.finish_screen_recording(&task_id).await;
+        self.upload_artifacts(&task_id).await;
+        self.restore_input_method().await;
+        self.action_handler.set_humanize(None).await;
+        self.publish_finished(&task_id, None).await;
+        self.send_callback(&task, &task_id, None).await;
+    }
+
+    /// 发布任务结束的进度事件；`status` 为 None 时按任务最终状态确定
+    async fn publish_finished(&self, task_id: &str, status: Option<CallbackStatus>) {
+        let (status, failure_reason, error) = self.final_status(status).await;
+        let result = if status == CallbackStatus::Completed { self.task_result.read().await.clone() } else { None };
+        progress::publish_progress(
+            task_id,
+            &self.id,
+            self.device.serial(),
+            TaskProgress::Finished { status, result, failure_reason, error },
+        );
+    }
+
+    /// 任务的最终状态、失败类别与失败原因；`status` 为 None 时按 Agent 状态确定，被停止的任务类别为 Cancelled
+    async fn final_status(&self, status: Option<CallbackStatus>) -> (CallbackStatus, Option<FailureReason>, Option<String>) {
+        let (final_status, reason, error) = match &*self.runtime.state.read().await {
+            AgentState::Completed { .. } => (CallbackStatus::Completed, None, None),
+            AgentState::Failed { reason, error, .. } => (CallbackStatus::Failed, Some(*reason), Some(error.clone())),
+            _ => (CallbackStatus::Stopped, None, None),
+        };
+        match status.unwrap_or(final_status) {
+            CallbackStatus::Stopped => (CallbackStatus::Stopped, Some(FailureReason::Cancelled), error),
+            status => (status, reason, error),
+        }
+    }
+
+    /// 执行任务的各个步骤，直到完成、失败或超限
+    async fn run_task_steps(
+        &self,
+        task: String,
+        model_client: Arc<dyn ModelClient>,
+        prompt_suffix: Option<String>,
+        limits: TaskLimits,
+        mut foreground_guard: Option<ForegroundGuard>,
+    ) {
+        let task_id = self.logger.task_id().await.unwrap_or_else(|| self.id.clone());
 
         // 获取屏幕尺寸
         let (screen_width, screen_height) = match self.device.screen_size().await {
@@ -107,33 +752,54 @@ impl PhoneAgent {
         };
 
         // 初始化消息列表（根据模式选择系统提示词）
-        let system_prompt = if self.model_client.supports_three_stage() {
+        let prompt_builder = PromptBuilder::from_config(&self.runtime.config);
+        let locale = self.runtime.config.locale;
+        let three_stage = model_client.capabilities().three_stage;
+        if three_stage {
             // 三阶段模式：使用规划提示词
             info!("使用三阶段模式，初始化为规划阶段");
-            crate::agent::llm::prompts::get_planning_system_prompt()
         } else {
             // 单阶段模式：使用主提示词（小模型直接分析截图并执行）
             info!("使用单阶段模式，初始化为执行模式");
-            crate::agent::llm::prompts::get_main_system_prompt(screen_width, screen_height)
-        };
+        }
+        let system_prompt = prompt_builder.system_prompt(&SystemPromptContext {
+            three_stage,
+            screen_size: (screen_width, screen_height),
+            // 设备型号与系统版本，帮助模型判断界面差异
+            device_summary: crate::scrcpy::device_info::cached(self.device.serial()).map(|metadata| metadata.summary()),
+            worked_examples: self.worked_examples_prompt(&task).await,
+            prompt_suffix,
+        });
         self.initialize_messages(system_prompt).await;
 
         // 添加初始用户任务
-        let initial_user_message = format!(
-            "任务: {}",
-            task
-        );
-        self.add_user_message(initial_user_message.clone()).await;
+        self.add_user_message(prompt_builder.task_message(&task)).await;
 
         let mut step = 0;
         let mut no_action_count = 0; // 连续无操作计数
+        // 操作结果消息：(消息下标, 摘要, 是否已折叠)
+        let mut result_messages: Vec<(usize, StepResultSummary, bool)> = Vec::new();
+        let mut screenshot_history = ScreenshotHistory::new(
+            self.runtime.config.history_screenshots,
+            self.runtime.config.history_screenshot_width,
+        );
+        let mut observer = ScreenObserver::new(
+            self.runtime.config.screenshot_retries,
+            self.runtime.config.max_screenshot_misses,
+        );
+        // 已写入对话记录的消息数，每步只记录新增的消息
+        let mut transcript_len = 0;
+        let device_clock = self.device_clock.read().await.clone();
         let loop_start_time = std::time::Instant::now();
 
         loop {
+            // 收到暂停请求时在步骤边界等待恢复
+            self.wait_while_paused(step).await;
+
             // 检查是否超过最大步数
-            if step >= self.runtime.config.max_steps {
-                let error = format!("超过最大步数限制: {}", step);
-                self.fail(error.clone()).await;
+            if step >= limits.max_steps {
+                let error = locale.failure_message(FailureReason::MaxSteps, step);
+                self.fail(FailureReason::MaxSteps, error.clone()).await;
                 if let Err(e) = self.logger.log_task_failed(&error, step).await {
                     warn!("记录任务失败失败: {}", e);
                 }
@@ -142,8 +808,8 @@ impl PhoneAgent {
 
             // 检查连续无操作次数（防止无限循环）
             if no_action_count >= 3 {
-                let error = format!("连续 {} 次未返回有效操作，停止执行", no_action_count);
-                self.fail(error.clone()).await;
+                let error = locale.failure_message(FailureReason::ParseFailure, no_action_count);
+                self.fail(FailureReason::ParseFailure, error.clone()).await;
                 if let Err(e) = self.logger.log_task_failed(&error, step).await {
                     warn!("记录任务失败失败: {}", e);
                 }
@@ -152,10 +818,10 @@ impl PhoneAgent {
 
             // 检查是否超时
             let elapsed = self.runtime.elapsed_ms().await;
-            let max_time_ms = self.runtime.config.max_execution_time * 1000;
+            let max_time_ms = limits.max_execution_time * 1000;
             if elapsed > max_time_ms {
-                let error = format!("执行超时: {}ms > {}ms", elapsed, max_time_ms);
-                self.fail(error.clone()).await;
+                let error = locale.failure_message(FailureReason::Timeout, format!("{}ms > {}ms", elapsed, max_time_ms));
+                self.fail(FailureReason::Timeout, error.clone()).await;
                 if let Err(e) = self.logger.log_task_failed(&error, step).await {
                     warn!("记录任务失败失败: {}", e);
                 }
@@ -165,14 +831,20 @@ impl PhoneAgent {
             // 更新状态为分析中
             *self.runtime.state.write().await = AgentState::Analyzing { step };
 
+            // 离开目标应用（应用崩溃、广告跳转）时按配置重新启动，截图前处理以便模型看到重新启动后的界面
+            let foreground_drift = match foreground_guard.as_mut() {
+                Some(guard) => guard.check(&self.device).await,
+                None => None,
+            };
+
             // 截取屏幕
             debug!("步骤 {}: 截取屏幕", step);
             let screenshot_start = std::time::Instant::now();
-            let screenshot = match self.device.screenshot().await {
-                Ok(s) => s,
+            let observation = match observer.observe(self.device.as_ref(), (screen_width, screen_height)).await {
+                Ok(observation) => observation,
                 Err(e) => {
-                    let error = format!("截图失败: {}", e);
-                    self.fail(error.clone()).await;
+                    let error = locale.failure_message(FailureReason::DeviceOffline, e);
+                    self.fail(FailureReason::DeviceOffline, error.clone()).await;
                     if let Err(e) = self.logger.log_task_failed(&error, step).await {
                         warn!("记录任务失败失败: {}", e);
                     }
@@ -180,9 +852,25 @@ impl PhoneAgent {
                 }
             };
             let screenshot_duration = screenshot_start.elapsed();
+            let screenshot = observation.screenshot().map(str::to_string);
 
-            // 获取当前消息列表
-            let current_messages = self.messages.read().await.clone();
+            // 获取当前消息列表，附加之前几步的截图；截图失败时说明使用的是过期截图或控件摘要
+            let mut current_messages = self.messages.read().await.clone();
+            screenshot_history.attach(&mut current_messages);
+            if let Some(note) = prompt_builder.observation_note(&observation) {
+                warn!("步骤 {}: 截图失败，使用{}", step, if screenshot.is_some() { "缓存的截图" } else { "界面控件摘要" });
+                append_to_last_user_message(&mut current_messages, &note);
+            }
+            if let Some(drift) = &foreground_drift {
+                warn!(
+                    "步骤 {}: 前台应用为 {}，不是目标应用 {}{}",
+                    step,
+                    drift.foreground,
+                    drift.target,
+                    if drift.relaunched { "，已重新启动" } else { "" }
+                );
+                append_to_last_user_message(&mut current_messages, &prompt_builder.foreground_note(drift));
+            }
             let messages_count = current_messages.len();
 
             // 克隆消息用于日志记录（在移动之前）
@@ -191,11 +879,12 @@ impl PhoneAgent {
             // 使用消息列表查询 LLM
             debug!("步骤 {}: 查询 LLM (消息数: {})", step, messages_count);
             let query_start = std::time::Instant::now();
-            let model_response = match self.model_client.query_with_messages(current_messages, Some(&screenshot)).await {
+            self.logger.set_step(step);
+            let model_response = match model_client.query_with_messages(current_messages, screenshot.as_deref(), (screen_width, screen_height)).await {
                 Ok(r) => r,
                 Err(e) => {
-                    let error = format!("LLM 查询失败: {}", e);
-                    self.fail(error.clone()).await;
+                    let error = locale.failure_message(FailureReason::ModelError, e);
+                    self.fail(FailureReason::ModelError, error.clone()).await;
                     if let Err(e) = self.logger.log_task_failed(&error, step).await {
                         warn!("记录任务失败失败: {}", e);
                     }
@@ -203,20 +892,45 @@ impl PhoneAgent {
                 }
             };
             let query_duration = query_start.elapsed();
+            self.runtime.add_tokens(model_response.tokens_used).await;
+            let model_name = model_client.info().name;
+            crate::agent::llm::metrics::model_metrics().record(&model_name, &model_response.metrics);
+            if let Observation::Fresh(fresh) = &observation {
+                screenshot_history.push(fresh);
+            }
+
+            // 截图只保存一份，对话记录与同一步的多个操作共用
+            let stored_screenshot = screenshot.as_deref().and_then(|screenshot| match self.screenshot_store.save(screenshot) {
+                Ok(stored) => stored,
+                Err(e) => {
+                    warn!("保存步骤截图失败: {}", e);
+                    None
+                }
+            });
+
+            let conversation_step = ConversationStep::new(
+                step,
+                messages_for_log.get(transcript_len..).unwrap_or_default(),
+                stored_screenshot.clone(),
+                model_response.content.clone(),
+                model_response.reasoning.clone(),
+                model_response.actions.clone(),
+            );
+            if let Err(e) = self.logger.log_conversation_step(&conversation_step).await {
+                warn!("记录对话失败: {}", e);
+            }
+            transcript_len = messages_for_log.len();
 
             // 检查是否有操作
-            let parsed_actions = model_response.actions;
+            let mut parsed_actions = model_response.actions;
 
             // 检查是否为空
             if parsed_actions.is_empty() {
                 // 没有解析到有效操作，添加反馈消息让 LLM 重新回复
                 info!("没有解析到有效操作，添加反馈消息让 LLM 重新回复");
-                let feedback_msg = format!(
-                    "你的回复中没有包含 do(action=...) 格式的执行动作，我无法解析。\n\n请严格按照 do(action=ActionType, ...) 格式回复，例如：\n- do(action=\"Tap\", element=[x,y])\n- do(action=\"Type\", text=\"xxx\")\n- do(action=\"Swipe\", start=[x1,y1], end=[x2,y2])\n- do(action=\"Launch\", app=\"xxx\")\n- do(action=\"Back\")\n\n请重新分析屏幕并告诉我下一步操作。"
-                );
-                self.add_assistant_message(model_response.content).await;
-                self.add_user_message(feedback_msg).await;
                 no_action_count += 1;
+                self.add_assistant_message(model_response.content).await;
+                self.add_user_message(prompt_builder.no_action_feedback(no_action_count)).await;
                 step = self.runtime.increment_step().await;
                 continue;
             }
@@ -224,25 +938,15 @@ impl PhoneAgent {
             // 重置无操作计数
             no_action_count = 0;
 
-            // 检查是否有 finish 操作（最高优先级）
-            if let Some(finish_action) = parsed_actions.iter().find(|a| a.action_type() == "finish") {
-                // 添加助手完成消息
-                let reasoning = model_response.reasoning.clone().unwrap_or_default();
-                let completion_msg = format!(
-                    "任务完成。{}\n思考过程: {}",
-                    model_response.content,
-                    reasoning
-                );
-                self.add_assistant_message(completion_msg).await;
-
-                let total_duration = loop_start_time.elapsed().as_millis() as u64;
-                let result_content = model_response.content.clone();
-                self.complete(step, result_content.clone()).await;
-
-                // 记录任务完成
-                if let Err(e) = self.logger.log_task_complete(&result_content, step, total_duration).await {
-                    warn!("记录任务完成失败: {}", e);
-                }
+            // finish 排在最后：先执行其它操作，全部成功后再完成任务
+            let finish_requested = parsed_actions
+                .iter()
+                .position(|a| matches!(a, ActionEnum::Finish(_)))
+                .map(|index| parsed_actions.remove(index))
+                .is_some();
+            if finish_requested && parsed_actions.is_empty() {
+                self.complete_with_response(step, &model_response.content, model_response.reasoning.as_deref(), loop_start_time)
+                    .await;
                 break;
             }
 
@@ -263,17 +967,26 @@ impl PhoneAgent {
                 debug!("步骤 {}: 记录操作 {}/{}", step, idx + 1, parsed_actions.len());
 
                 // 记录步骤
+                let timestamp = chrono::Utc::now();
                 let execution_step = ExecutionStep {
                     step_number: step,
                     action_type: action.action_type(),
                     action_description: action.description(),
                     result: result.clone(),
-                    timestamp: chrono::Utc::now(),
-                    screenshot: screenshot.clone(),
+                    timestamp,
+                    device_timestamp: device_clock.as_ref().map(|clock| clock.device_time_at(timestamp)),
+                    screenshot: stored_screenshot.clone(),
                     reasoning: reasoning_text.clone(),
                 };
 
                 self.runtime.add_step(execution_step).await;
+                progress::publish_progress(&task_id, &self.id, self.device.serial(), TaskProgress::Step {
+                    step,
+                    action_type: action.action_type(),
+                    description: action.description(),
+                    success: result.success,
+                    message: result.message.clone(),
+                });
 
                 // 添加到对话上下文（包含执行结果）
                 let status = if result.success { "成功" } else { "失败" };
@@ -284,48 +997,36 @@ impl PhoneAgent {
                         status,
                         result.message
                     ),
-                    Some(screenshot.clone()),
+                    screenshot.clone(),
                 ).await;
             }
 
             // 将助手响应添加到消息列表
-            let actions_summary: Vec<String> = parsed_actions.iter()
-                .map(|a| format!("{} ({})", a.description(), a.action_type()))
-                .collect();
-            let assistant_response = format!(
-                "我决定执行 {} 个操作:\n{}\n思考: {}",
-                parsed_actions.len(),
-                actions_summary.join("\n"),
-                reasoning_text
-            );
-            self.add_assistant_message(assistant_response).await;
+            let actions_summary: Vec<(String, String)> =
+                parsed_actions.iter().map(|a| (a.description(), a.action_type())).collect();
+            self.add_assistant_message(prompt_builder.actions_message(&actions_summary, &reasoning_text)).await;
 
-            // 将操作结果格式化并添加为用户消息
-            let mut result_summary_parts = Vec::new();
-            for (idx, (action, result)) in parsed_actions.iter().zip(action_results.iter()).enumerate() {
-                let status = if result.success { "成功" } else { "失败" };
-                let detail = if result.success {
-                    format!("详情: {}", result.message)
-                } else {
-                    format!("错误: {}", result.message)
-                };
-                result_summary_parts.push(format!(
-                    "- 操作 #{}: {} ({})\n  状态: {}\n  {}\n  耗时: {}ms",
-                    idx + 1,
-                    action.action_type(),
-                    action.description(),
-                    status,
-                    detail,
-                    result.duration_ms
-                ));
-            }
-
-            let result_summary = format!(
-                "操作结果（步骤 {}）:\n{}",
+            // 将操作结果格式化并添加为用户消息，较早步骤的结果折叠为单行
+            let summary = StepResultSummary::new(
                 step,
-                result_summary_parts.join("\n")
+                parsed_actions
+                    .iter()
+                    .zip(action_results.iter())
+                    .map(|(action, result)| (action.action_type(), action.description(), result)),
             );
-            self.add_user_message(result_summary).await;
+            self.add_user_message(prompt_builder.result_summary(&summary)).await;
+            let message_index = self.messages.read().await.len() - 1;
+            result_messages.push((message_index, summary, false));
+            self.collapse_result_messages(&prompt_builder, &mut result_messages).await;
+
+            if finish_requested {
+                if action_results.iter().all(|r| r.success) {
+                    self.complete_with_response(step, &model_response.content, model_response.reasoning.as_deref(), loop_start_time)
+                        .await;
+                    break;
+                }
+                info!("finish 之前的操作执行失败，暂不完成任务，交由模型根据执行结果重新判断");
+            }
 
             // 增加步数
             step = self.runtime.increment_step().await;
@@ -337,8 +1038,30 @@ impl PhoneAgent {
         }
     }
 
+    /// 模型给出 finish 后完成任务：记录完成消息并写入日志
+    async fn complete_with_response(
+        &self,
+        step: usize,
+        content: &str,
+        reasoning: Option<&str>,
+        loop_start_time: std::time::Instant,
+    ) {
+        // 添加助手完成消息
+        let completion_msg = PromptBuilder::from_config(&self.runtime.config).completion_message(content, reasoning);
+        self.add_assistant_message(completion_msg).await;
+
+        let total_duration = loop_start_time.elapsed().as_millis() as u64;
+        self.complete(step, content.to_string()).await;
+
+        // 记录任务完成
+        if let Err(e) = self.logger.log_task_complete(content, step, total_duration).await {
+            warn!("记录任务完成失败: {}", e);
+        }
+    }
+
     /// 标记为完成
     async fn complete(&self, steps: usize, result: String) {
+        *self.task_result.write().await = Some(result.clone());
         *self.runtime.state.write().await = AgentState::Completed {
             steps,
             duration_ms: self.runtime.elapsed_ms().await,
@@ -347,12 +1070,31 @@ impl PhoneAgent {
         info!("Agent {} 完成任务: {}", self.id, result);
     }
 
+    /// 有暂停请求时停在步骤 `step` 之前，直到恢复；暂停时长不计入执行超时
+    async fn wait_while_paused(&self, step: usize) {
+        let mut pause_requested = self.runtime.pause_requested.subscribe();
+        if !*pause_requested.borrow_and_update() {
+            return;
+        }
+
+        info!("Agent {} 在步骤 {} 前暂停", self.id, step);
+        *self.runtime.state.write().await = AgentState::Paused { step };
+        let paused_at = std::time::Instant::now();
+        let _ = pause_requested.wait_for(|paused| !paused).await;
+
+        if let Some(start_time) = self.runtime.start_time.write().await.as_mut() {
+            *start_time += chrono::Duration::from_std(paused_at.elapsed()).unwrap_or_default();
+        }
+        info!("Agent {} 已恢复，暂停 {}ms", self.id, paused_at.elapsed().as_millis());
+    }
+
     /// 标记为失败
-    async fn fail(&self, error: String) {
+    async fn fail(&self, reason: FailureReason, error: String) {
         let step = self.runtime.current_step().await;
         let error_msg = error.clone();
         *self.runtime.state.write().await = AgentState::Failed {
             step,
+            reason,
             error,
         };
 
@@ -360,6 +1102,28 @@ impl PhoneAgent {
     }
 }
 
+/// 上传一个任务产物并记录到 Agent 日志，上传成功时返回 true
+async fn put_artifact(
+    store: &dyn ArtifactStore,
+    logger: &AgentLogger,
+    task_id: &str,
+    kind: ArtifactKind,
+    name: String,
+    body: Vec<u8>,
+) -> bool {
+    let key = store.key(task_id, &name);
+    let size = body.len() as u64;
+    if let Err(e) = store.put(&key, body, content_type(&name)).await {
+        warn!("{}", e);
+        return false;
+    }
+    let artifact = TaskArtifact { kind, name, key, size };
+    if let Err(e) = logger.log_artifact_uploaded(task_id, &artifact).await {
+        warn!("记录任务产物失败: {}", e);
+    }
+    true
+}
+
 #[async_trait::async_trait]
 impl Agent for PhoneAgent {
     async fn start(&self, task: String) -> Result<String, AppError> {
@@ -385,6 +1149,11 @@ impl Agent for PhoneAgent {
         *self.runtime.state.write().await = AgentState::Initializing;
         *self.runtime.current_task.write().await = Some(task.clone());
         *self.runtime.start_time.write().await = Some(chrono::Utc::now());
+        *self.traffic_artifact.write().await = None;
+        self.recording_artifacts.write().await.clear();
+        *self.evaluation.write().await = None;
+        *self.task_result.write().await = None;
+        self.logger.begin_task().await;
 
         // 在后台运行
         let agent_clone = PhoneAgent {
@@ -398,9 +1167,32 @@ impl Agent for PhoneAgent {
             abort_handle: Arc::clone(&self.abort_handle),
             messages: Arc::clone(&self.messages),
             logger: Arc::clone(&self.logger),
+            original_ime: Arc::clone(&self.original_ime),
+            capture_traffic: Arc::clone(&self.capture_traffic),
+            traffic: Arc::clone(&self.traffic),
+            traffic_artifact: Arc::clone(&self.traffic_artifact),
+            record_screen: Arc::clone(&self.record_screen),
+            recording: Arc::clone(&self.recording),
+            recording_artifacts: Arc::clone(&self.recording_artifacts),
+            usage_expectation: Arc::clone(&self.usage_expectation),
+            humanize: Arc::clone(&self.humanize),
+            foreground_guard: Arc::clone(&self.foreground_guard),
+            judge: self.judge.clone(),
+            evaluation: Arc::clone(&self.evaluation),
+            task_result: Arc::clone(&self.task_result),
+            variant: Arc::clone(&self.variant),
+            task_override: Arc::clone(&self.task_override),
+            screenshot_store: self.screenshot_store.clone(),
+            task_guard: Arc::clone(&self.task_guard),
+            callback: Arc::clone(&self.callback),
+            active_callback: Arc::clone(&self.active_callback),
+            device_clock: Arc::clone(&self.device_clock),
         };
 
+        let task_guard = self.task_guard.lock().await.take();
         let handle = tokio::spawn(async move {
+            // 任务结束或被中止时释放
+            let _task_guard = task_guard;
             agent_clone.run_agent_loop(task).await;
         });
 
@@ -416,6 +1208,26 @@ impl Agent for PhoneAgent {
         if let Some(handle) = handle_guard.take() {
             handle.abort();
         }
+        drop(handle_guard);
+        let running_task = self.logger.task_id().await;
+        let task_id = running_task.clone().unwrap_or_else(|| self.id.clone());
+        if running_task.is_some() {
+            self.publish_finished(&task_id, Some(CallbackStatus::Stopped)).await;
+        }
+        self.finish_traffic_capture(&task_id).await;
+        self.finish_screen_recording(&task_id).await;
+        if running_task.is_some() {
+            self.upload_artifacts(&task_id).await;
+        }
+        self.restore_input_method().await;
+        let task = self.runtime.current_task.read().await.clone().unwrap_or_default();
+        self.send_callback(&task, &task_id, Some(CallbackStatus::Stopped)).await;
+        if running_task.is_some() {
+            let step = self.runtime.current_step().await;
+            if let Err(e) = self.logger.log_task_stopped(step).await {
+                warn!("记录任务停止失败: {}", e);
+            }
+        }
 
         // 重置状态
         self.runtime.reset().await;
@@ -423,14 +1235,17 @@ impl Agent for PhoneAgent {
         Ok(())
     }
 
+    /// 请求暂停：当前步骤执行完后在下一步开始前停下
     async fn pause(&self) -> Result<(), AppError> {
         let state = self.runtime.state.read().await;
         match &*state {
-            AgentState::Analyzing { .. } | AgentState::Executing { .. } => {
+            AgentState::Initializing
+            | AgentState::Analyzing { .. }
+            | AgentState::Executing { .. }
+            | AgentState::Waiting { .. } => {
                 drop(state);
-                let step = self.runtime.current_step().await;
-                let task = self.runtime.current_task.read().await.clone().unwrap_or_default();
-                *self.runtime.state.write().await = AgentState::Paused { step };
+                self.runtime.pause_requested.send_replace(true);
+                info!("Agent {} 将在当前步骤结束后暂停", self.id);
                 Ok(())
             }
             _ => Err(AppError::AgentError(
@@ -440,20 +1255,17 @@ impl Agent for PhoneAgent {
     }
 
     async fn resume(&self) -> Result<(), AppError> {
-        let state = self.runtime.state.read().await;
-        match &*state {
-            AgentState::Paused { .. } => {
-                drop(state);
-                // TODO: 实现恢复逻辑
-                Ok(())
-            }
-            _ => Err(AppError::AgentError(
-                crate::agent::core::traits::AgentError::InvalidStateTransition(
-                    "NotPaused".to_string(),
-                    "Running".to_string(),
-                ),
-            )),
+        let paused = matches!(*self.runtime.state.read().await, AgentState::Paused { .. });
+        if paused || *self.runtime.pause_requested.borrow() {
+            self.runtime.pause_requested.send_replace(false);
+            return Ok(());
         }
+        Err(AppError::AgentError(
+            crate::agent::core::traits::AgentError::InvalidStateTransition(
+                "NotPaused".to_string(),
+                "Running".to_string(),
+            ),
+        ))
     }
 
     async fn status(&self) -> AgentStatus {
@@ -486,9 +1298,11 @@ impl Agent for PhoneAgent {
                 task: task.unwrap_or_default(),
                 steps: *steps,
                 duration_ms: *duration_ms,
+                evaluation: self.evaluation.read().await.clone(),
             },
-            AgentState::Failed { error, .. } => AgentStatus::Failed {
+            AgentState::Failed { reason, error, .. } => AgentStatus::Failed {
                 task: task.unwrap_or_default(),
+                reason: *reason,
                 error: error.clone(),
             },
         }
@@ -498,6 +1312,11 @@ impl Agent for PhoneAgent {
         self.runtime.execution_history.read().await.clone()
     }
 
+    async fn history_page(&self, query: &HistoryQuery) -> HistoryPage {
+        // 在锁内过滤，避免复制完整历史
+        query.apply(&self.runtime.execution_history.read().await)
+    }
+
     async fn feedback(&self, _feedback: AgentFeedback) -> Result<(), AppError> {
         // TODO: 实现反馈处理
         Ok(())
@@ -1,12 +1,17 @@
 use std::sync::Arc;
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use tokio::sync::{RwLock, broadcast};
 use tracing::{debug, info, warn};
-use crate::agent::core::traits::{Agent, Device, ModelClient};
+use crate::agent::core::traits::{Agent, AgentStatus, Device, ModelClient};
 use crate::agent::core::agent::PhoneAgent;
-use crate::agent::core::state::AgentConfig;
-use crate::agent::llm::create_model_client;
+use crate::agent::core::collaboration::{
+    CollaborationPlan, CollaborationReport, CollaborationRole, CoordinationChannel, RoleOutcome,
+};
+use crate::agent::core::state::{AgentConfig, FailureReason};
+use crate::agent::llm::{create_model_client, JudgeClient};
 use crate::agent::llm::types::ModelConfig;
+use crate::agent::pool::{DevicePool, TaskPriority};
 use crate::error::AppError;
 use uuid::Uuid;
 
@@ -23,11 +28,17 @@ pub enum AgentGroupEvent {
     AgentCompleted { agent_id: String, result: String },
 
     /// Agent 失败
-    AgentFailed { agent_id: String, error: String },
+    AgentFailed { agent_id: String, reason: FailureReason, error: String },
 
     /// Agent 停止
     AgentStopped { agent_id: String },
 
+    /// 协作任务中的角色向协作通道发布了消息
+    CollaborationMessage { collaboration_id: String, from_role: String, content: String },
+
+    /// 协作任务结束
+    CollaborationFinished { collaboration_id: String, success: bool },
+
     /// 自定义事件
     Custom { agent_id: String, event_type: String, data: String },
 }
@@ -63,6 +74,8 @@ pub struct AgentGroup {
     event_tx: broadcast::Sender<AgentGroupEvent>,
     config: AgentGroupConfig,
     model_config: ModelConfig,
+    /// 所属设备池；设置后任务经设备池启动，遵循排空与准入控制
+    pool: Option<Arc<DevicePool>>,
 }
 
 impl AgentGroup {
@@ -77,9 +90,16 @@ impl AgentGroup {
             event_tx,
             config,
             model_config,
+            pool: None,
         }
     }
 
+    /// 通过设备池启动任务，与其它入口共用设备执行名额、并发许可与排空状态
+    pub fn with_pool(mut self, pool: Arc<DevicePool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
     /// 获取 Agent 组 ID
     pub fn id(&self) -> &str {
         &self.id
@@ -127,7 +147,8 @@ impl AgentGroup {
             device,
             model_client,
             config,
-        )?);
+        )?
+        .with_judge(JudgeClient::from_config(&self.model_config)?.map(Arc::new)));
 
         // 添加到管理列表
         self.agents.write().await.insert(agent_id.clone(), agent.clone());
@@ -143,6 +164,35 @@ impl AgentGroup {
         Ok(agent_id)
     }
 
+    /// 将已有的 Agent（例如设备池创建的 Agent）加入组管理
+    pub async fn add_agent(&self, agent: Arc<PhoneAgent>) -> String {
+        let agent_id = agent.id().to_string();
+        let device_serial = agent.device_serial().to_string();
+        self.agents.write().await.insert(agent_id.clone(), agent);
+
+        let _ = self.event_tx.send(AgentGroupEvent::AgentCreated {
+            agent_id: agent_id.clone(),
+            device_serial,
+        });
+
+        agent_id
+    }
+
+    /// 获取设备上的 Agent，没有时创建
+    async fn agent_for_device(&self, device_serial: &str) -> Result<Arc<PhoneAgent>, AppError> {
+        let existing = self.agents.read().await
+            .values()
+            .find(|agent| agent.device_serial() == device_serial)
+            .cloned();
+        if let Some(agent) = existing {
+            return Ok(agent);
+        }
+
+        let agent_id = self.create_agent(device_serial, AgentConfig::default()).await?;
+        self.get_agent(&agent_id).await
+            .ok_or(AppError::AgentError(crate::agent::core::traits::AgentError::NotFound(agent_id)))
+    }
+
     /// 获取 Agent
     pub async fn get_agent(&self, agent_id: &str) -> Option<Arc<PhoneAgent>> {
         self.agents.read().await.get(agent_id).cloned()
@@ -154,7 +204,10 @@ impl AgentGroup {
             .ok_or_else(|| AppError::AgentError(crate::agent::core::traits::AgentError::NotFound(agent_id.to_string())))?;
 
         let task_for_event = task.clone();
-        agent.start(task).await?;
+        match &self.pool {
+            Some(pool) => pool.start_task(&agent, task, TaskPriority::Normal).await?,
+            None => agent.start(task).await?,
+        };
 
         // 发送事件
         let _ = self.event_tx.send(AgentGroupEvent::AgentStarted {
@@ -238,6 +291,132 @@ impl AgentGroup {
         Ok(agent_ids)
     }
 
+    /// 运行多设备协作任务
+    ///
+    /// 按依赖关系分批执行，同一批内的角色并行运行；被依赖的角色完成后，其结果发布到协作通道，
+    /// 作为上下文附加到后续角色的任务中。依赖的角色未成功时，后续角色不再执行。
+    pub async fn run_collaboration(&self, plan: CollaborationPlan) -> Result<CollaborationReport, AppError> {
+        plan.validate().map_err(AppError::Unknown)?;
+
+        let collaboration_id = Uuid::new_v4().to_string();
+        let channel = CoordinationChannel::new();
+        let started_at = chrono::Utc::now();
+        info!("开始协作任务 {} ({})，共 {} 个角色", plan.name, collaboration_id, plan.roles.len());
+
+        let mut outcomes: HashMap<String, RoleOutcome> = HashMap::new();
+        for wave in plan.execution_waves().unwrap_or_default() {
+            let runs = wave.into_iter().map(|role| self.run_role(&collaboration_id, role, &channel, &outcomes));
+            let results = futures::future::join_all(runs).await;
+            for outcome in results {
+                outcomes.insert(outcome.role.clone(), outcome);
+            }
+        }
+
+        let roles: Vec<RoleOutcome> = plan.roles.iter()
+            .filter_map(|role| outcomes.remove(&role.role))
+            .collect();
+        let success = roles.iter().all(|r| r.success);
+        let _ = self.event_tx.send(AgentGroupEvent::CollaborationFinished {
+            collaboration_id: collaboration_id.clone(),
+            success,
+        });
+        info!("协作任务 {} 结束: {}", collaboration_id, if success { "成功" } else { "失败" });
+
+        Ok(CollaborationReport {
+            id: collaboration_id,
+            name: plan.name,
+            success,
+            roles,
+            messages: channel.messages().await,
+            started_at,
+            finished_at: chrono::Utc::now(),
+        })
+    }
+
+    /// 运行协作任务中的一个角色，直到 Agent 结束或超时
+    async fn run_role(
+        &self,
+        collaboration_id: &str,
+        role: &CollaborationRole,
+        channel: &CoordinationChannel,
+        finished: &HashMap<String, RoleOutcome>,
+    ) -> RoleOutcome {
+        let mut outcome = RoleOutcome {
+            role: role.role.clone(),
+            device_serial: role.device_serial.clone(),
+            agent_id: None,
+            success: false,
+            message: String::new(),
+        };
+
+        if let Some(failed) = role.depends_on.iter().find(|d| !finished.get(*d).is_some_and(|o| o.success)) {
+            outcome.message = format!("依赖的角色 {} 未成功完成", failed);
+            return outcome;
+        }
+
+        let agent = match self.agent_for_device(&role.device_serial).await {
+            Ok(agent) => agent,
+            Err(e) => {
+                outcome.message = e.to_string();
+                return outcome;
+            }
+        };
+        let agent_id = agent.id().to_string();
+        outcome.agent_id = Some(agent_id.clone());
+
+        let task = channel.task_with_context(role).await;
+        debug!("协作角色 {} 在设备 {} 上执行: {}", role.role, role.device_serial, task);
+        if let Err(e) = self.start_agent(&agent_id, task).await {
+            outcome.message = e.to_string();
+            return outcome;
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(role.timeout_secs);
+        let status = loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            let status = agent.status().await;
+            if !matches!(status, AgentStatus::Running { .. } | AgentStatus::Paused { .. }) {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = self.stop_agent(&agent_id).await;
+                break AgentStatus::Failed {
+                    task: role.task.clone(),
+                    reason: FailureReason::Timeout,
+                    error: format!("角色 {} 超时 ({} 秒)", role.role, role.timeout_secs),
+                };
+            }
+        };
+
+        match status {
+            AgentStatus::Completed { .. } => {
+                let result = agent.task_result().await.unwrap_or_default();
+                let message = channel.post(&role.role, result.clone()).await;
+                let _ = self.event_tx.send(AgentGroupEvent::CollaborationMessage {
+                    collaboration_id: collaboration_id.to_string(),
+                    from_role: message.from_role,
+                    content: message.content,
+                });
+                let _ = self.event_tx.send(AgentGroupEvent::AgentCompleted {
+                    agent_id,
+                    result: result.clone(),
+                });
+                outcome.success = true;
+                outcome.message = result;
+            }
+            AgentStatus::Failed { reason, error, .. } => {
+                let _ = self.event_tx.send(AgentGroupEvent::AgentFailed {
+                    agent_id,
+                    reason,
+                    error: error.clone(),
+                });
+                outcome.message = error;
+            }
+            other => outcome.message = format!("Agent 未在运行: {:?}", other),
+        }
+        outcome
+    }
+
     /// 订阅事件
     pub fn subscribe_events(&self) -> broadcast::Receiver<AgentGroupEvent> {
         self.event_tx.subscribe()
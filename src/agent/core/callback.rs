@@ -0,0 +1,169 @@
+//! 任务结果回调
+//!
+//! `agent/start` 可以附带 `callback_url`，任务完成、失败或被停止时把最终状态、结果与产物链接 POST 到该地址，
+//! 适合只关心单个任务结果的集成。配置了 `callback_secret` 时请求带 HMAC-SHA256 签名：
+//! `X-Scrs-Timestamp` 为 Unix 秒，`X-Scrs-Signature` 为 `sha256=<hex>`，签名内容为 `{timestamp}.{body}`。
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::{info, warn};
+
+use super::state::{FailureReason, TaskStats};
+use crate::agent::executor::DeviceClock;
+use crate::agent::llm::TaskEvaluation;
+use crate::scrcpy::device_info::DeviceMetadata;
+
+/// 签名请求头
+pub const SIGNATURE_HEADER: &str = "X-Scrs-Signature";
+/// 时间戳请求头
+pub const TIMESTAMP_HEADER: &str = "X-Scrs-Timestamp";
+
+/// 最多投递次数
+const MAX_ATTEMPTS: u32 = 3;
+/// 单次投递超时
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 任务结束时的回调设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskCallback {
+    pub url: String,
+    /// 客户端提供的任务 ID，原样带回
+    pub task_id: Option<String>,
+}
+
+impl TaskCallback {
+    pub fn new(url: &str, task_id: Option<String>) -> Result<Self, String> {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(format!("callback_url 必须是 http(s) 地址: {}", url));
+        }
+        Ok(Self { url: url.to_string(), task_id })
+    }
+}
+
+/// 任务最终状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CallbackStatus {
+    Completed,
+    Failed,
+    Stopped,
+}
+
+/// 任务产物链接
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CallbackArtifacts {
+    /// 执行历史接口
+    pub history: String,
+    /// 任务对话接口
+    pub conversation: String,
+    /// 步骤截图目录
+    pub screenshots: Option<String>,
+    /// 网络流量记录（HAR）
+    pub traffic: Option<String>,
+    /// 设备端录屏的分段文件
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recordings: Vec<String>,
+    /// 上传到产物存储的产物列表（含下载链接），任务结束后在后台上传
+    pub uploaded: String,
+}
+
+/// 回调请求体
+#[derive(Debug, Clone, Serialize)]
+pub struct CallbackPayload {
+    pub task_id: Option<String>,
+    pub agent_id: String,
+    pub device_serial: String,
+    /// 设备型号、Android 版本等元数据
+    pub device: Option<DeviceMetadata>,
+    pub task: String,
+    pub status: CallbackStatus,
+    /// finish 时模型给出的结果
+    pub result: Option<String>,
+    /// 失败类别（失败或被停止时）
+    pub failure_reason: Option<FailureReason>,
+    /// 失败原因
+    pub error: Option<String>,
+    #[serde(flatten)]
+    pub stats: TaskStats,
+    /// 任务开始时的设备时区与时钟偏差
+    pub device_clock: Option<DeviceClock>,
+    pub evaluation: Option<TaskEvaluation>,
+    pub artifacts: CallbackArtifacts,
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 计算回调签名（`sha256=<hex>`）
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut message = format!("{}.", timestamp).into_bytes();
+    message.extend_from_slice(body);
+    let digest = hmac_sha256(secret.as_bytes(), &message);
+    format!("sha256={}", crate::hex(&digest))
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+/// 回调共用的 HTTP 客户端，连接在多次投递与重试之间复用
+fn http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+/// 投递回调，失败时按 1s、2s 退避重试
+pub async fn deliver(callback: &TaskCallback, payload: &CallbackPayload, secret: Option<&str>) -> Result<(), String> {
+    let body = serde_json::to_vec(payload).map_err(|e| format!("序列化回调内容失败: {}", e))?;
+
+    let mut last_error = String::new();
+    for attempt in 1..=MAX_ATTEMPTS {
+        let timestamp = chrono::Utc::now().timestamp();
+        let mut request = http_client()
+            .post(&callback.url)
+            .timeout(DELIVERY_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .body(body.clone());
+        if let Some(secret) = secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, timestamp, &body));
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                info!("任务回调已送达: {}", callback.url);
+                return Ok(());
+            }
+            Ok(response) => last_error = format!("HTTP {}", response.status()),
+            Err(e) => last_error = e.to_string(),
+        }
+        warn!("任务回调第 {}/{} 次投递失败: {} ({})", attempt, MAX_ATTEMPTS, callback.url, last_error);
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+        }
+    }
+    Err(format!("任务回调投递失败: {}", last_error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac_signature() {
+        // RFC 4231 测试用例 2
+        let digest = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(crate::hex(&digest), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+
+        let signature = sign("secret", 1700000000, br#"{"status":"completed"}"#);
+        assert!(signature.starts_with("sha256=") && signature.len() == 7 + 64);
+        assert_ne!(signature, sign("secret", 1700000001, br#"{"status":"completed"}"#));
+
+        assert!(TaskCallback::new("ftp://example.com", None).is_err());
+        assert!(TaskCallback::new("https://example.com/hook", Some("job-1".to_string())).is_ok());
+    }
+}
@@ -0,0 +1,201 @@
+//! 多设备协作任务
+//!
+//! 一个协作任务由多个角色组成，每个角色在一台设备上运行一个 Agent 子任务。
+//! 角色可以依赖其他角色：被依赖的角色完成后，其结果写入共享的协作通道，
+//! 并作为上下文附加到依赖方的任务描述中（例如设备 A 发送微信消息后，设备 B 校验是否收到）。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tokio::sync::RwLock;
+
+/// 角色默认超时时间（秒）
+fn default_timeout_secs() -> u64 {
+    600
+}
+
+/// 协作任务中的一个角色
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollaborationRole {
+    /// 角色名称，在协作任务内唯一
+    pub role: String,
+    pub device_serial: String,
+    /// 该角色的子任务描述
+    pub task: String,
+    /// 需要先完成的角色
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// 协作任务定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollaborationPlan {
+    pub name: String,
+    pub roles: Vec<CollaborationRole>,
+}
+
+impl CollaborationPlan {
+    /// 校验角色名与设备唯一、依赖存在且无环
+    pub fn validate(&self) -> Result<(), String> {
+        if self.roles.len() < 2 {
+            return Err("协作任务至少需要两个角色".to_string());
+        }
+        let mut roles = HashSet::new();
+        let mut devices = HashSet::new();
+        for role in &self.roles {
+            if role.role.trim().is_empty() || !roles.insert(role.role.as_str()) {
+                return Err(format!("角色名称为空或重复: {}", role.role));
+            }
+            if !devices.insert(role.device_serial.as_str()) {
+                return Err(format!("设备 {} 被多个角色使用", role.device_serial));
+            }
+        }
+        for role in &self.roles {
+            if let Some(missing) = role.depends_on.iter().find(|d| !roles.contains(d.as_str())) {
+                return Err(format!("角色 {} 依赖的角色不存在: {}", role.role, missing));
+            }
+        }
+        if self.execution_waves().is_none() {
+            return Err("角色依赖存在循环".to_string());
+        }
+        Ok(())
+    }
+
+    /// 按依赖关系分批：同一批内的角色互不依赖，可以并行执行；存在循环依赖时返回 None
+    pub fn execution_waves(&self) -> Option<Vec<Vec<&CollaborationRole>>> {
+        let mut done: HashSet<&str> = HashSet::new();
+        let mut waves = Vec::new();
+        while done.len() < self.roles.len() {
+            let wave: Vec<&CollaborationRole> = self
+                .roles
+                .iter()
+                .filter(|r| !done.contains(r.role.as_str()))
+                .filter(|r| r.depends_on.iter().all(|d| done.contains(d.as_str())))
+                .collect();
+            if wave.is_empty() {
+                return None;
+            }
+            done.extend(wave.iter().map(|r| r.role.as_str()));
+            waves.push(wave);
+        }
+        Some(waves)
+    }
+}
+
+/// 协作通道中的一条消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoordinationMessage {
+    /// 发送方角色
+    pub from_role: String,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 协作通道，角色之间共享的消息板
+#[derive(Default)]
+pub struct CoordinationChannel {
+    messages: RwLock<Vec<CoordinationMessage>>,
+}
+
+impl CoordinationChannel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 发布消息
+    pub async fn post(&self, from_role: &str, content: String) -> CoordinationMessage {
+        let message = CoordinationMessage {
+            from_role: from_role.to_string(),
+            content,
+            timestamp: Utc::now(),
+        };
+        self.messages.write().await.push(message.clone());
+        message
+    }
+
+    /// 所有消息（按时间顺序）
+    pub async fn messages(&self) -> Vec<CoordinationMessage> {
+        self.messages.read().await.clone()
+    }
+
+    /// 为角色生成包含其依赖角色消息的任务描述
+    pub async fn task_with_context(&self, role: &CollaborationRole) -> String {
+        if role.depends_on.is_empty() {
+            return role.task.clone();
+        }
+        let messages = self.messages.read().await;
+        let context: Vec<String> = messages
+            .iter()
+            .filter(|m| role.depends_on.contains(&m.from_role))
+            .map(|m| format!("- {}: {}", m.from_role, m.content))
+            .collect();
+        if context.is_empty() {
+            return role.task.clone();
+        }
+        format!("{}\n\n协作上下文（其他设备已完成的步骤）：\n{}", role.task, context.join("\n"))
+    }
+}
+
+/// 单个角色的执行结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleOutcome {
+    pub role: String,
+    pub device_serial: String,
+    pub agent_id: Option<String>,
+    pub success: bool,
+    /// 完成时为模型给出的结果，失败时为错误信息
+    pub message: String,
+}
+
+/// 协作任务报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollaborationReport {
+    pub id: String,
+    pub name: String,
+    pub success: bool,
+    pub roles: Vec<RoleOutcome>,
+    pub messages: Vec<CoordinationMessage>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(name: &str, device: &str, depends_on: &[&str]) -> CollaborationRole {
+        CollaborationRole {
+            role: name.to_string(),
+            device_serial: device.to_string(),
+            task: format!("{} 的任务", name),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            timeout_secs: 600,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan_waves_and_context() {
+        let plan = CollaborationPlan {
+            name: "微信消息".to_string(),
+            roles: vec![role("receiver", "b", &["sender"]), role("sender", "a", &[])],
+        };
+        assert!(plan.validate().is_ok());
+        let waves = plan.execution_waves().unwrap();
+        assert_eq!(waves.len(), 2);
+        assert_eq!(waves[0][0].role, "sender");
+
+        let cyclic = CollaborationPlan {
+            name: "循环".to_string(),
+            roles: vec![role("x", "a", &["y"]), role("y", "b", &["x"])],
+        };
+        assert_eq!(cyclic.validate(), Err("角色依赖存在循环".to_string()));
+
+        let channel = CoordinationChannel::new();
+        channel.post("sender", "已发送“你好”给张三".to_string()).await;
+        let task = channel.task_with_context(&plan.roles[0]).await;
+        assert!(task.starts_with("receiver 的任务"));
+        assert!(task.contains("- sender: 已发送“你好”给张三"));
+    }
+}
@@ -0,0 +1,242 @@
+//! 任务截图时间线
+//!
+//! 按步骤顺序列出任务对话中（`conversation_step` 事件）保存的截图，定位到离指定步骤或时间点最近的一帧，
+//! 返回该帧的完整截图与前后若干帧的缩略图，供界面拖动时间轴回看任务过程中设备的画面。
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::artifacts::content_type;
+use super::screenshot_store::{ScreenshotLogPolicy, StoredScreenshot};
+use super::transcript::{ConversationStep, CONVERSATION_STEP_EVENT};
+use crate::agent::logger::read_task_events;
+
+/// 默认返回前后各几帧缩略图
+const DEFAULT_RADIUS: usize = 3;
+/// 前后缩略图数量上限
+const MAX_RADIUS: usize = 10;
+
+/// 时间线查询条件，如 `?ts=2026-01-01T10:00:30Z&radius=5` 或 `?step=3`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FrameQuery {
+    /// 时间点：RFC 3339 时间或 Unix 毫秒时间戳
+    #[serde(default)]
+    pub ts: Option<String>,
+    /// 步骤号，与 `ts` 同时指定时优先
+    #[serde(default)]
+    pub step: Option<usize>,
+    /// 前后各返回几帧缩略图（默认 3，最多 10）
+    #[serde(default)]
+    pub radius: Option<usize>,
+}
+
+/// 定位帧的目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameTarget {
+    Step(usize),
+    Time(DateTime<Utc>),
+    /// 未指定时取最后一帧
+    Latest,
+}
+
+impl FrameQuery {
+    /// 解析定位目标，时间格式无效时返回错误
+    pub fn target(&self) -> Result<FrameTarget, String> {
+        if let Some(step) = self.step {
+            return Ok(FrameTarget::Step(step));
+        }
+        let Some(ts) = self.ts.as_deref().map(str::trim).filter(|ts| !ts.is_empty()) else {
+            return Ok(FrameTarget::Latest);
+        };
+        let time = match ts.parse::<i64>() {
+            Ok(millis) => DateTime::from_timestamp_millis(millis),
+            Err(_) => DateTime::parse_from_rfc3339(ts).ok().map(|t| t.with_timezone(&Utc)),
+        };
+        time.map(FrameTarget::Time).ok_or_else(|| format!("无效的时间: {}", ts))
+    }
+
+    pub fn radius(&self) -> usize {
+        self.radius.unwrap_or(DEFAULT_RADIUS).min(MAX_RADIUS)
+    }
+}
+
+/// 时间线中的一帧（截图引用）
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskFrame {
+    pub step: usize,
+    /// 记录该步对话的时间
+    pub timestamp: Option<DateTime<Utc>>,
+    pub screenshot: StoredScreenshot,
+}
+
+/// 返回给客户端的一帧
+#[derive(Debug, Clone, Serialize)]
+pub struct FrameImage {
+    /// 在时间线中的序号
+    pub index: usize,
+    pub step: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    pub hash: String,
+    pub mime_type: &'static str,
+    /// 图片 base64；截图文件已被清理时为 None
+    pub image: Option<String>,
+}
+
+/// 截图时间线
+#[derive(Debug, Clone, Serialize)]
+pub struct FrameTimeline {
+    pub task_id: String,
+    /// 时间线总帧数
+    pub total: usize,
+    /// 离目标最近的一帧（完整截图）
+    pub frame: FrameImage,
+    /// 前后相邻帧（缩略图，含当前帧），按时间顺序排列
+    pub neighbors: Vec<FrameImage>,
+}
+
+/// 离目标最近的帧的序号，距离相同时取较早的一帧
+pub fn nearest_frame(frames: &[TaskFrame], target: FrameTarget) -> Option<usize> {
+    let distance = |frame: &TaskFrame| -> Option<u64> {
+        match target {
+            FrameTarget::Step(step) => Some(frame.step.abs_diff(step) as u64),
+            FrameTarget::Time(time) => frame.timestamp.map(|t| (t - time).num_milliseconds().unsigned_abs()),
+            FrameTarget::Latest => None,
+        }
+    };
+    match target {
+        FrameTarget::Latest => frames.len().checked_sub(1),
+        _ => frames
+            .iter()
+            .enumerate()
+            .filter_map(|(index, frame)| distance(frame).map(|d| (d, index)))
+            .min()
+            .map(|(_, index)| index),
+    }
+}
+
+/// 从日志目录中读取任务的全部截图帧，按步骤排序
+pub fn load_task_frames(log_dir: impl AsRef<Path>, task_id: &str) -> std::io::Result<Vec<TaskFrame>> {
+    let mut frames = Vec::new();
+    for value in read_task_events(log_dir, CONVERSATION_STEP_EVENT, task_id)? {
+        let Ok(step) = serde_json::from_value::<ConversationStep>(value["conversation"].clone()) else {
+            continue;
+        };
+        let timestamp = value["timestamp"]
+            .as_str()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc));
+        for screenshot in step.prompt.into_iter().flat_map(|m| m.images) {
+            frames.push(TaskFrame { step: step.step, timestamp, screenshot });
+        }
+    }
+    frames.sort_by_key(|frame| (frame.step, frame.timestamp));
+    Ok(frames)
+}
+
+impl TaskFrame {
+    fn mime_type(&self) -> &'static str {
+        if self.screenshot.path.is_empty() {
+            "image/jpeg"
+        } else {
+            content_type(&self.screenshot.path)
+        }
+    }
+
+    fn image(&self, index: usize) -> FrameImage {
+        FrameImage {
+            index,
+            step: self.step,
+            timestamp: self.timestamp,
+            hash: self.screenshot.hash.clone(),
+            mime_type: self.mime_type(),
+            image: self.screenshot.load().ok(),
+        }
+    }
+
+    /// 缩略图：内嵌截图直接使用日志中的缩略图，文件截图按默认缩略图宽度缩放
+    fn thumbnail(&self, index: usize, policy: &ScreenshotLogPolicy) -> FrameImage {
+        let image = match &self.screenshot.thumbnail {
+            Some(thumbnail) => Some(thumbnail.clone()),
+            None => std::fs::read(&self.screenshot.path).ok().and_then(|bytes| policy.thumbnail(&bytes).ok()),
+        };
+        FrameImage {
+            index,
+            step: self.step,
+            timestamp: self.timestamp,
+            hash: self.screenshot.hash.clone(),
+            mime_type: "image/jpeg",
+            image,
+        }
+    }
+}
+
+/// 读取任务的截图时间线，定位到离目标最近的帧；任务没有截图时返回 None
+pub fn load_frame_timeline(
+    log_dir: impl AsRef<Path>,
+    task_id: &str,
+    target: FrameTarget,
+    radius: usize,
+) -> std::io::Result<Option<FrameTimeline>> {
+    let frames = load_task_frames(log_dir, task_id)?;
+    let Some(index) = nearest_frame(&frames, target) else {
+        return Ok(None);
+    };
+    let policy = ScreenshotLogPolicy::default();
+    let start = index.saturating_sub(radius);
+    let end = (index + radius + 1).min(frames.len());
+    let neighbors = (start..end).map(|i| frames[i].thumbnail(i, &policy)).collect();
+    Ok(Some(FrameTimeline {
+        task_id: task_id.to_string(),
+        total: frames.len(),
+        frame: frames[index].image(index),
+        neighbors,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::core::message::ChatMessage;
+
+    #[test]
+    fn test_frame_timeline() {
+        let dir = std::env::temp_dir().join(format!("scrs_frames_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let line = |step: usize, second: u32, hash: &str| {
+            let screenshot = StoredScreenshot { hash: hash.to_string(), path: String::new(), thumbnail: Some(format!("thumb_{}", hash)) };
+            let conversation = ConversationStep::new(step, &[ChatMessage::user("继续")], Some(screenshot), "完成".to_string(), None, Vec::new());
+            serde_json::json!({
+                "timestamp": format!("2026-01-01T10:00:{:02}+00:00", second),
+                "agent_id": "agent_1",
+                "task_id": "task_1",
+                "event": CONVERSATION_STEP_EVENT,
+                "conversation": conversation,
+            })
+            .to_string()
+        };
+        let log = [line(2, 20, "cc"), line(0, 0, "aa"), line(1, 10, "bb"), line(3, 30, "dd")].join("\n");
+        std::fs::write(dir.join("agent_agent_1_2026-01-01.jsonl"), log).unwrap();
+
+        let frames = load_task_frames(&dir, "task_1").unwrap();
+        assert_eq!(frames.iter().map(|f| f.screenshot.hash.as_str()).collect::<Vec<_>>(), ["aa", "bb", "cc", "dd"]);
+
+        let query = FrameQuery { ts: Some("2026-01-01T10:00:14Z".to_string()), ..Default::default() };
+        assert_eq!(nearest_frame(&frames, query.target().unwrap()), Some(1));
+        let millis = DateTime::parse_from_rfc3339("2026-01-01T10:00:26Z").unwrap().timestamp_millis();
+        let query = FrameQuery { ts: Some(millis.to_string()), ..Default::default() };
+        assert_eq!(nearest_frame(&frames, query.target().unwrap()), Some(3));
+        assert_eq!(nearest_frame(&frames, FrameTarget::Step(7)), Some(3));
+        assert_eq!(nearest_frame(&frames, FrameTarget::Latest), Some(3));
+        assert!(FrameQuery { ts: Some("yesterday".to_string()), ..Default::default() }.target().is_err());
+
+        let timeline = load_frame_timeline(&dir, "task_1", FrameTarget::Step(1), 1).unwrap().unwrap();
+        assert_eq!((timeline.total, timeline.frame.index, timeline.frame.image.as_deref()), (4, 1, Some("thumb_bb")));
+        assert_eq!(timeline.neighbors.iter().map(|f| f.step).collect::<Vec<_>>(), [0, 1, 2]);
+        assert!(load_frame_timeline(&dir, "task_2", FrameTarget::Latest, 1).unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,174 @@
+//! 执行历史的分页与过滤查询
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::traits::ExecutionStep;
+
+/// 默认每页步骤数
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+fn default_limit() -> usize {
+    DEFAULT_HISTORY_LIMIT
+}
+
+/// 执行历史查询条件
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryQuery {
+    /// 跳过过滤后的前 N 个步骤
+    #[serde(default)]
+    pub offset: usize,
+    /// 最多返回的步骤数（0 表示不限制）
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    /// 只返回步骤号大于该值的步骤
+    #[serde(default)]
+    pub since_step: usize,
+    /// 只返回指定类型的操作（如 "tap"）
+    #[serde(default)]
+    pub action_type: Option<String>,
+    /// 只返回执行失败的操作
+    #[serde(default)]
+    pub failed_only: bool,
+    /// 是否附带截图数据（从磁盘读取，体积较大）
+    #[serde(default)]
+    pub include_screenshots: bool,
+}
+
+impl Default for HistoryQuery {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            limit: DEFAULT_HISTORY_LIMIT,
+            since_step: 0,
+            action_type: None,
+            failed_only: false,
+            include_screenshots: false,
+        }
+    }
+}
+
+/// 历史中的单个步骤
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub step_number: usize,
+    pub action_type: String,
+    pub action_description: String,
+    pub success: bool,
+    pub message: String,
+    pub reasoning: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// 设备时区下的设备时间
+    pub device_timestamp: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// 截图内容哈希
+    pub screenshot_hash: Option<String>,
+    /// 截图 base64（仅在 `include_screenshots` 时返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<String>,
+}
+
+/// 一页执行历史
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryPage {
+    /// 满足过滤条件的步骤总数
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub steps: Vec<HistoryEntry>,
+}
+
+impl HistoryQuery {
+    /// 步骤是否满足过滤条件
+    pub fn matches(&self, step: &ExecutionStep) -> bool {
+        step.step_number > self.since_step
+            && !(self.failed_only && step.result.success)
+            && self
+                .action_type
+                .as_deref()
+                .is_none_or(|action_type| step.action_type.eq_ignore_ascii_case(action_type))
+    }
+
+    /// 对完整历史应用过滤与分页
+    pub fn apply(&self, history: &[ExecutionStep]) -> HistoryPage {
+        let matched: Vec<&ExecutionStep> = history.iter().filter(|step| self.matches(step)).collect();
+        let limit = if self.limit == 0 { usize::MAX } else { self.limit };
+        let steps = matched
+            .iter()
+            .skip(self.offset)
+            .take(limit)
+            .map(|step| self.entry(step))
+            .collect();
+        HistoryPage {
+            total: matched.len(),
+            offset: self.offset,
+            limit: self.limit,
+            steps,
+        }
+    }
+
+    fn entry(&self, step: &ExecutionStep) -> HistoryEntry {
+        let screenshot = match &step.screenshot {
+            Some(stored) if self.include_screenshots => match stored.load() {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    warn!("读取步骤 {} 截图失败: {}", step.step_number, e);
+                    None
+                }
+            },
+            _ => None,
+        };
+        HistoryEntry {
+            step_number: step.step_number,
+            action_type: step.action_type.clone(),
+            action_description: step.action_description.clone(),
+            success: step.result.success,
+            message: step.result.message.clone(),
+            reasoning: step.reasoning.clone(),
+            timestamp: step.timestamp,
+            device_timestamp: step.device_timestamp,
+            screenshot_hash: step.screenshot.as_ref().map(|stored| stored.hash.clone()),
+            screenshot,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::core::traits::ActionResult;
+
+    fn step(step_number: usize, action_type: &str, success: bool) -> ExecutionStep {
+        ExecutionStep {
+            step_number,
+            action_type: action_type.to_string(),
+            action_description: format!("{} #{}", action_type, step_number),
+            result: if success {
+                ActionResult::success("ok".to_string(), 10)
+            } else {
+                ActionResult::failure("fail".to_string(), 10)
+            },
+            timestamp: chrono::Utc::now(),
+            device_timestamp: None,
+            screenshot: None,
+            reasoning: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_history_filter_and_paging() {
+        let history: Vec<ExecutionStep> = (1..=10)
+            .map(|i| step(i, if i % 2 == 0 { "tap" } else { "type" }, i % 3 != 0))
+            .collect();
+
+        let page = HistoryQuery { offset: 2, limit: 3, ..Default::default() }.apply(&history);
+        assert_eq!(page.total, 10);
+        assert_eq!(page.steps.iter().map(|s| s.step_number).collect::<Vec<_>>(), [3, 4, 5]);
+
+        let page = HistoryQuery { action_type: Some("TAP".to_string()), since_step: 4, ..Default::default() }.apply(&history);
+        assert_eq!(page.steps.iter().map(|s| s.step_number).collect::<Vec<_>>(), [6, 8, 10]);
+
+        let page = HistoryQuery { failed_only: true, limit: 0, ..Default::default() }.apply(&history);
+        assert_eq!(page.total, 3);
+        assert!(page.steps.iter().all(|s| !s.success && s.screenshot.is_none()));
+    }
+}
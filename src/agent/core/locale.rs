@@ -0,0 +1,62 @@
+//! 注入消息的语言
+//!
+//! Agent 自己写入对话的文本（任务消息、无操作反馈、操作结果摘要、截图失败说明）与任务失败原因按 `[agent] locale`
+//! 选择语言，与用户任务使用的语言无关。非中文模型或用户可以设置为 `"en"`。
+
+use serde::{Deserialize, Serialize};
+
+use super::state::FailureReason;
+
+/// 注入消息的语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Locale {
+    /// 中文
+    #[default]
+    Zh,
+    /// 英文
+    En,
+}
+
+impl Locale {
+    /// 按语言选择文本
+    pub fn pick<'a>(self, zh: &'a str, en: &'a str) -> &'a str {
+        match self {
+            Locale::Zh => zh,
+            Locale::En => en,
+        }
+    }
+
+    /// 任务失败原因的说明，`detail` 为步数、耗时或底层错误等细节
+    pub fn failure_message(self, reason: FailureReason, detail: impl std::fmt::Display) -> String {
+        match (self, reason) {
+            (Locale::Zh, FailureReason::ModelError) => format!("LLM 查询失败: {}", detail),
+            (Locale::En, FailureReason::ModelError) => format!("Model query failed: {}", detail),
+            (Locale::Zh, FailureReason::ParseFailure) => format!("连续 {} 次未返回有效操作，停止执行", detail),
+            (Locale::En, FailureReason::ParseFailure) => format!("No valid action returned {} times in a row, stopping", detail),
+            (Locale::Zh, FailureReason::DeviceOffline) => format!("截图失败: {}", detail),
+            (Locale::En, FailureReason::DeviceOffline) => format!("Failed to capture the screen: {}", detail),
+            (Locale::Zh, FailureReason::MaxSteps) => format!("超过最大步数限制: {}", detail),
+            (Locale::En, FailureReason::MaxSteps) => format!("Exceeded the maximum number of steps: {}", detail),
+            (Locale::Zh, FailureReason::Timeout) => format!("执行超时: {}", detail),
+            (Locale::En, FailureReason::Timeout) => format!("Execution timed out: {}", detail),
+            (Locale::Zh, FailureReason::ActionRejected) => format!("应用使用校验未通过: {}", detail),
+            (Locale::En, FailureReason::ActionRejected) => format!("App usage verification did not pass: {}", detail),
+            (Locale::Zh, FailureReason::Cancelled) => format!("任务已停止: {}", detail),
+            (Locale::En, FailureReason::Cancelled) => format!("Task stopped: {}", detail),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_messages() {
+        assert_eq!(Locale::Zh.failure_message(FailureReason::MaxSteps, 50), "超过最大步数限制: 50");
+        assert_eq!(Locale::En.failure_message(FailureReason::MaxSteps, 50), "Exceeded the maximum number of steps: 50");
+        assert_eq!(Locale::En.pick("任务", "Task"), "Task");
+        assert_eq!(serde_json::from_str::<Locale>("\"en\"").unwrap(), Locale::En);
+    }
+}
@@ -0,0 +1,207 @@
+//! 对话消息
+//!
+//! PhoneAgent、对话上下文与各模型客户端共用的消息模型，格式与 OpenAI 兼容接口一致，
+//! 可以直接作为请求体发送，文字与截图可以混合在同一条消息中。
+
+use serde::{Deserialize, Serialize};
+
+/// 聊天消息（用于多轮对话）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: MessageRole,
+    pub content: MessageContent,
+}
+
+/// 消息角色
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageRole {
+    System,
+    User,
+    Assistant,
+}
+
+/// 消息内容（支持文本和图片）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Multimodal(Vec<ContentBlock>),
+}
+
+/// 内容块
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentBlock {
+    #[serde(rename = "type")]
+    pub block_type: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<ImageUrl>,
+}
+
+/// 图片 URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageUrl {
+    pub url: String,
+}
+
+impl ImageUrl {
+    /// 从 base64 创建图片 URL
+    pub fn from_base64(base64_data: &str) -> Self {
+        Self {
+            url: format!("data:image/png;base64,{}", base64_data),
+        }
+    }
+}
+
+impl ContentBlock {
+    /// 文字块
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            block_type: "text".to_string(),
+            text: Some(text.into()),
+            image_url: None,
+        }
+    }
+
+    /// base64 图片块
+    pub fn image(base64_data: &str) -> Self {
+        Self {
+            block_type: "image_url".to_string(),
+            text: None,
+            image_url: Some(ImageUrl::from_base64(base64_data)),
+        }
+    }
+}
+
+impl ChatMessage {
+    /// 纯文本消息
+    pub fn new(role: MessageRole, text: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: MessageContent::Text(text.into()),
+        }
+    }
+
+    /// 系统消息
+    pub fn system(text: impl Into<String>) -> Self {
+        Self::new(MessageRole::System, text)
+    }
+
+    /// 用户消息
+    pub fn user(text: impl Into<String>) -> Self {
+        Self::new(MessageRole::User, text)
+    }
+
+    /// 助手消息
+    pub fn assistant(text: impl Into<String>) -> Self {
+        Self::new(MessageRole::Assistant, text)
+    }
+
+    /// 带截图的用户消息（图片在前、文字在后）
+    pub fn user_with_image(text: impl Into<String>, screenshot: &str) -> Self {
+        Self {
+            role: MessageRole::User,
+            content: MessageContent::Multimodal(vec![ContentBlock::image(screenshot), ContentBlock::text(text)]),
+        }
+    }
+
+    /// 消息中的文字内容，多个文字块以换行连接
+    pub fn text(&self) -> String {
+        match &self.content {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Multimodal(blocks) => blocks
+                .iter()
+                .filter_map(|block| block.text.as_deref())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// 消息中的图片数量
+    pub fn image_count(&self) -> usize {
+        match &self.content {
+            MessageContent::Text(_) => 0,
+            MessageContent::Multimodal(blocks) => blocks.iter().filter(|b| b.image_url.is_some()).count(),
+        }
+    }
+
+    /// 去掉图片后的纯文本消息（用于不支持图片的模型）
+    pub fn text_only(&self) -> Self {
+        Self::new(self.role, self.text())
+    }
+
+    /// 在消息开头附加截图，文字内容保持不变
+    pub fn with_image(self, screenshot: &str) -> Self {
+        let mut blocks = vec![ContentBlock::image(screenshot)];
+        match self.content {
+            MessageContent::Text(text) => blocks.push(ContentBlock::text(text)),
+            MessageContent::Multimodal(existing) => blocks.extend(existing),
+        }
+        Self {
+            role: self.role,
+            content: MessageContent::Multimodal(blocks),
+        }
+    }
+}
+
+/// 将截图附加到最后一条用户消息上
+pub fn attach_screenshot(messages: &mut [ChatMessage], screenshot: &str) {
+    if let Some(message) = messages.iter_mut().rev().find(|m| m.role == MessageRole::User) {
+        *message = message.clone().with_image(screenshot);
+    }
+}
+
+/// 在最后一条用户消息末尾追加文字（如截图失败说明），图片保持不变
+pub fn append_to_last_user_message(messages: &mut [ChatMessage], text: &str) {
+    if let Some(message) = messages.iter_mut().rev().find(|m| m.role == MessageRole::User) {
+        match &mut message.content {
+            MessageContent::Text(existing) => {
+                existing.push_str("\n\n");
+                existing.push_str(text);
+            }
+            MessageContent::Multimodal(blocks) => blocks.push(ContentBlock::text(text)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attach_screenshot() {
+        let mut messages = vec![
+            ChatMessage::system("提示词"),
+            ChatMessage::user("任务: 打开设置"),
+            ChatMessage::assistant("点击设置"),
+            ChatMessage::user("继续"),
+        ];
+        attach_screenshot(&mut messages, "AAAA");
+
+        assert_eq!(messages[1].image_count(), 0);
+        assert_eq!(messages[3].image_count(), 1);
+        assert_eq!(messages[3].text(), "继续");
+        assert_eq!(messages[3].text_only().image_count(), 0);
+
+        let json = serde_json::to_value(&messages[3]).unwrap();
+        assert_eq!(json["content"][0]["image_url"]["url"], "data:image/png;base64,AAAA");
+        assert_eq!(json["content"][1]["text"], "继续");
+        assert_eq!(serde_json::to_value(&messages[0]).unwrap()["content"], "提示词");
+    }
+
+    #[test]
+    fn test_append_to_last_user_message() {
+        let mut messages = vec![ChatMessage::user("任务: 打开设置"), ChatMessage::assistant("点击设置")];
+        append_to_last_user_message(&mut messages, "截图失败");
+        assert_eq!(messages[0].text(), "任务: 打开设置\n\n截图失败");
+
+        attach_screenshot(&mut messages, "AAAA");
+        append_to_last_user_message(&mut messages, "过期截图");
+        assert_eq!(messages[0].image_count(), 1);
+        assert_eq!(messages[0].text(), "任务: 打开设置\n\n截图失败\n过期截图");
+    }
+}
@@ -1,4 +1,18 @@
 pub mod traits;
+pub mod message;
 pub mod state;
+pub mod locale;
+pub mod history;
+pub mod callback;
+pub mod screenshot_history;
+pub mod screenshot_store;
+pub mod artifacts;
+pub mod result_summary;
+pub mod prompt_builder;
+pub mod transcript;
+pub mod frames;
+pub mod progress;
+pub mod task_record;
 pub mod agent;
 pub mod agent_group;
+pub mod collaboration;
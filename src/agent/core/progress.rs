@@ -0,0 +1,285 @@
+//! 任务进度事件
+//!
+//! Agent 在任务开始、每个操作执行后与任务结束时发布 [`TaskProgressEvent`]，每条事件带任务内递增的序号 `seq`。
+//! 事件按任务保存在内存中（每个任务最近 [`MAX_EVENTS_PER_TASK`] 条，最多 [`MAX_TASKS`] 个任务），
+//! 断线重连的客户端通过 `agent/attach` 提交任务 ID 与上次收到的序号（游标），先补发错过的事件，再继续接收新事件。
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use super::callback::CallbackStatus;
+use super::state::{FailureReason, TaskLimits};
+
+/// 每个任务保留的事件数
+pub const MAX_EVENTS_PER_TASK: usize = 500;
+
+/// 保留事件的任务数，超出时丢弃最早的任务
+pub const MAX_TASKS: usize = 200;
+
+/// 进度内容
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TaskProgress {
+    /// 任务开始（附带生效的步数与时间限制）
+    Started { task: String, limits: TaskLimits },
+    /// 执行了一个操作
+    Step {
+        step: usize,
+        action_type: String,
+        description: String,
+        success: bool,
+        message: String,
+    },
+    /// 任务结束
+    Finished {
+        status: CallbackStatus,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        result: Option<String>,
+        /// 失败或被停止时的失败类别
+        #[serde(skip_serializing_if = "Option::is_none")]
+        failure_reason: Option<FailureReason>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+/// 一条进度事件
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskProgressEvent {
+    /// 任务 ID（与 Agent 日志、任务对话中的 ID 一致）
+    pub task_id: String,
+    pub agent_id: String,
+    pub device_serial: String,
+    /// 任务内的序号，从 1 开始
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub progress: TaskProgress,
+}
+
+/// 错过事件的摘要
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProgressSummary {
+    /// 错过的事件数（包括已不在内存中的）
+    pub missed: u64,
+    /// 错过的操作数与其中失败的操作数
+    pub steps: usize,
+    pub failed_steps: usize,
+    /// 最近一步的步骤号
+    pub last_step: Option<usize>,
+    /// 任务已结束时的最终状态
+    pub status: Option<CallbackStatus>,
+    /// 任务失败或被停止时的失败类别
+    pub failure_reason: Option<FailureReason>,
+}
+
+/// 游标之后的事件
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressReplay {
+    pub task_id: String,
+    pub agent_id: String,
+    pub device_serial: String,
+    /// 最新事件的序号，作为客户端的新游标
+    pub cursor: u64,
+    /// 部分错过的事件已不在内存中，`events` 不完整
+    pub truncated: bool,
+    pub summary: ProgressSummary,
+    pub events: Vec<TaskProgressEvent>,
+}
+
+#[derive(Debug)]
+struct TaskEvents {
+    agent_id: String,
+    device_serial: String,
+    next_seq: u64,
+    events: VecDeque<TaskProgressEvent>,
+}
+
+/// 按任务保存的进度事件
+#[derive(Debug, Default)]
+pub struct ProgressLog {
+    tasks: Mutex<(HashMap<String, TaskEvents>, VecDeque<String>)>,
+}
+
+impl ProgressLog {
+    /// 记录事件并分配序号
+    pub fn record(&self, task_id: &str, agent_id: &str, device_serial: &str, progress: TaskProgress) -> TaskProgressEvent {
+        let mut guard = self.tasks.lock().unwrap();
+        let (tasks, order) = &mut *guard;
+        if !tasks.contains_key(task_id) {
+            if order.len() >= MAX_TASKS
+                && let Some(oldest) = order.pop_front()
+            {
+                tasks.remove(&oldest);
+            }
+            order.push_back(task_id.to_string());
+        }
+        let entry = tasks.entry(task_id.to_string()).or_insert_with(|| TaskEvents {
+            agent_id: agent_id.to_string(),
+            device_serial: device_serial.to_string(),
+            next_seq: 1,
+            events: VecDeque::new(),
+        });
+
+        let event = TaskProgressEvent {
+            task_id: task_id.to_string(),
+            agent_id: agent_id.to_string(),
+            device_serial: device_serial.to_string(),
+            seq: entry.next_seq,
+            timestamp: Utc::now(),
+            progress,
+        };
+        entry.next_seq += 1;
+        if entry.events.len() >= MAX_EVENTS_PER_TASK {
+            entry.events.pop_front();
+        }
+        entry.events.push_back(event.clone());
+        event
+    }
+
+    /// 序号大于 `cursor` 的事件，任务不存在时返回 None
+    pub fn since(&self, task_id: &str, cursor: u64) -> Option<ProgressReplay> {
+        let guard = self.tasks.lock().unwrap();
+        let entry = guard.0.get(task_id)?;
+        let latest = entry.next_seq - 1;
+        let events: Vec<TaskProgressEvent> = entry.events.iter().filter(|e| e.seq > cursor).cloned().collect();
+
+        let mut summary = ProgressSummary { missed: latest.saturating_sub(cursor), ..Default::default() };
+        for event in &events {
+            match &event.progress {
+                TaskProgress::Step { step, success, .. } => {
+                    summary.steps += 1;
+                    summary.failed_steps += usize::from(!success);
+                    summary.last_step = Some(*step);
+                }
+                TaskProgress::Finished { status, failure_reason, .. } => {
+                    summary.status = Some(*status);
+                    summary.failure_reason = *failure_reason;
+                }
+                TaskProgress::Started { .. } => {}
+            }
+        }
+
+        Some(ProgressReplay {
+            task_id: task_id.to_string(),
+            agent_id: entry.agent_id.clone(),
+            device_serial: entry.device_serial.clone(),
+            cursor: latest,
+            truncated: (events.len() as u64) < summary.missed,
+            summary,
+            events,
+        })
+    }
+
+    /// Agent 最近一个任务的 ID
+    pub fn latest_task_of_agent(&self, agent_id: &str) -> Option<String> {
+        let guard = self.tasks.lock().unwrap();
+        let (tasks, order) = &*guard;
+        order.iter().rev().find(|id| tasks.get(*id).is_some_and(|t| t.agent_id == agent_id)).cloned()
+    }
+}
+
+fn progress_log() -> &'static ProgressLog {
+    static LOG: OnceLock<ProgressLog> = OnceLock::new();
+    LOG.get_or_init(ProgressLog::default)
+}
+
+fn progress_sender() -> &'static broadcast::Sender<TaskProgressEvent> {
+    static SENDER: OnceLock<broadcast::Sender<TaskProgressEvent>> = OnceLock::new();
+    SENDER.get_or_init(|| broadcast::channel(256).0)
+}
+
+/// 记录并广播进度事件
+pub fn publish_progress(task_id: &str, agent_id: &str, device_serial: &str, progress: TaskProgress) {
+    let event = progress_log().record(task_id, agent_id, device_serial, progress);
+    let _ = progress_sender().send(event);
+}
+
+/// 订阅进度事件
+pub fn subscribe_progress() -> broadcast::Receiver<TaskProgressEvent> {
+    progress_sender().subscribe()
+}
+
+/// 任务在 `cursor` 之后的事件
+pub fn progress_since(task_id: &str, cursor: u64) -> Option<ProgressReplay> {
+    progress_log().since(task_id, cursor)
+}
+
+/// Agent 最近一个任务的 ID
+pub fn latest_task_of_agent(agent_id: &str) -> Option<String> {
+    progress_log().latest_task_of_agent(agent_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(step: usize, success: bool) -> TaskProgress {
+        TaskProgress::Step {
+            step,
+            action_type: "tap".to_string(),
+            description: "点击设置".to_string(),
+            success,
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_replay_since_cursor() {
+        let log = ProgressLog::default();
+        log.record("task_1", "agent_1", "emulator-5554", TaskProgress::Started { task: "打开设置".to_string(), limits: TaskLimits::default() });
+        log.record("task_1", "agent_1", "emulator-5554", step(1, true));
+        log.record("task_1", "agent_1", "emulator-5554", step(2, false));
+        log.record(
+            "task_1",
+            "agent_1",
+            "emulator-5554",
+            TaskProgress::Finished {
+                status: CallbackStatus::Failed,
+                result: None,
+                failure_reason: Some(FailureReason::MaxSteps),
+                error: Some("超出步数".to_string()),
+            },
+        );
+
+        let replay = log.since("task_1", 1).unwrap();
+        assert_eq!(replay.cursor, 4);
+        assert!(!replay.truncated);
+        assert_eq!(replay.events.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(
+            replay.summary,
+            ProgressSummary {
+                missed: 3,
+                steps: 2,
+                failed_steps: 1,
+                last_step: Some(2),
+                status: Some(CallbackStatus::Failed),
+                failure_reason: Some(FailureReason::MaxSteps),
+            }
+        );
+        assert!(log.since("task_1", 4).unwrap().events.is_empty());
+        assert!(log.since("task_2", 0).is_none());
+        assert_eq!(log.latest_task_of_agent("agent_1").as_deref(), Some("task_1"));
+
+        let json = serde_json::to_value(&replay.events[0]).unwrap();
+        assert_eq!((json["event"].as_str(), json["seq"].as_u64()), (Some("step"), Some(2)));
+        let json = serde_json::to_value(&replay.events[2]).unwrap();
+        assert_eq!((json["event"].as_str(), json["failure_reason"].as_str()), (Some("finished"), Some("max_steps")));
+    }
+
+    #[test]
+    fn test_old_events_are_truncated() {
+        let log = ProgressLog::default();
+        for i in 0..MAX_EVENTS_PER_TASK + 10 {
+            log.record("task_1", "agent_1", "emulator-5554", step(i, true));
+        }
+        let replay = log.since("task_1", 0).unwrap();
+        assert!(replay.truncated);
+        assert_eq!(replay.events.len(), MAX_EVENTS_PER_TASK);
+        assert_eq!(replay.summary.missed, (MAX_EVENTS_PER_TASK + 10) as u64);
+    }
+}
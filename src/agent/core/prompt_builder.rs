@@ -0,0 +1,282 @@
+//! 提示词构建
+//!
+//! 任务执行中发给模型的文本（系统提示词、任务消息、无操作反馈、操作结果摘要与助手消息）统一由 [`PromptBuilder`]
+//! 根据结构化输入生成，不依赖设备与模型，渲染结果可以直接测试。无操作反馈与操作结果摘要的模板可以在
+//! `[agent.prompt_templates]` 中配置，模板中的 `{name}` 占位符会被替换为对应的值；内置文本的语言由 [`Locale`] 决定。
+
+use serde::{Deserialize, Serialize};
+
+use super::locale::Locale;
+use super::result_summary::{ResultSummaryFormat, StepResultSummary};
+use super::state::AgentConfig;
+use crate::agent::executor::{ForegroundDrift, Observation};
+use crate::agent::llm::prompts;
+
+/// 无操作反馈中的格式示例
+const ACTION_FORMAT_EXAMPLES: &str = "- do(action=\"Tap\", element=[x,y])\n- do(action=\"Type\", text=\"xxx\")\n- do(action=\"Swipe\", start=[x1,y1], end=[x2,y2])\n- do(action=\"Launch\", app=\"xxx\")\n- do(action=\"Back\")";
+
+/// 无操作反馈模板可用的占位符
+const NO_ACTION_PLACEHOLDERS: &[&str] = &["attempt"];
+/// 结果摘要标题可用的占位符
+const RESULT_HEADER_PLACEHOLDERS: &[&str] = &["step"];
+/// 结果摘要中每个操作可用的占位符
+const RESULT_ACTION_PLACEHOLDERS: &[&str] = &["index", "action_type", "description", "status", "message", "duration_ms"];
+
+/// 操作结果摘要模板
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResultSummaryTemplate {
+    /// 标题行，可用 `{step}`
+    pub header: String,
+    /// 每个操作一行，可用 `{index}`、`{action_type}`、`{description}`、`{status}`（成功 / 失败）、`{message}`、`{duration_ms}`
+    pub action: String,
+}
+
+/// 可配置的提示词模板，未设置时使用内置文本
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptTemplates {
+    /// 模型未返回可解析操作时的反馈，可用 `{attempt}`（连续次数）
+    #[serde(default)]
+    pub no_action_feedback: Option<String>,
+    /// 操作结果摘要，设置后取代 `result_summary_format`（折叠后的单行摘要不变）
+    #[serde(default)]
+    pub result_summary: Option<ResultSummaryTemplate>,
+}
+
+impl PromptTemplates {
+    /// 检查模板中只使用了支持的占位符
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(template) = &self.no_action_feedback {
+            check_placeholders("no_action_feedback", template, NO_ACTION_PLACEHOLDERS)?;
+        }
+        if let Some(template) = &self.result_summary {
+            check_placeholders("result_summary.header", &template.header, RESULT_HEADER_PLACEHOLDERS)?;
+            check_placeholders("result_summary.action", &template.action, RESULT_ACTION_PLACEHOLDERS)?;
+        }
+        Ok(())
+    }
+}
+
+/// 构建系统提示词所需的信息
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SystemPromptContext {
+    /// 是否使用三阶段模式（规划提示词）
+    pub three_stage: bool,
+    /// 屏幕分辨率（宽, 高），单阶段模式的提示词需要
+    pub screen_size: (u32, u32),
+    /// 设备型号与系统版本
+    pub device_summary: Option<String>,
+    /// 相似任务的参考案例
+    pub worked_examples: Option<String>,
+    /// 实验分组追加的提示词
+    pub prompt_suffix: Option<String>,
+}
+
+/// 提示词构建器
+#[derive(Debug, Clone, Default)]
+pub struct PromptBuilder {
+    templates: PromptTemplates,
+    result_format: ResultSummaryFormat,
+    locale: Locale,
+}
+
+impl PromptBuilder {
+    pub fn new(templates: PromptTemplates, result_format: ResultSummaryFormat, locale: Locale) -> Self {
+        Self { templates, result_format, locale }
+    }
+
+    /// 使用 Agent 配置中的模板、结果摘要格式与语言
+    pub fn from_config(config: &AgentConfig) -> Self {
+        Self::new(config.prompt_templates.clone(), config.result_summary_format, config.locale)
+    }
+
+    /// 系统提示词：基础提示词之后依次追加设备信息、参考案例与实验提示词
+    pub fn system_prompt(&self, context: &SystemPromptContext) -> String {
+        let base = if context.three_stage {
+            prompts::get_planning_system_prompt()
+        } else {
+            prompts::get_main_system_prompt(context.screen_size.0, context.screen_size.1)
+        };
+        let mut sections = vec![base];
+        if let Some(summary) = context.device_summary.as_deref().filter(|s| !s.is_empty()) {
+            sections.push(format!("{}: {}", self.locale.pick("当前设备", "Current device"), summary));
+        }
+        sections.extend(context.worked_examples.clone());
+        sections.extend(context.prompt_suffix.clone());
+        sections.join("\n\n")
+    }
+
+    /// 任务开始时的用户消息
+    pub fn task_message(&self, task: &str) -> String {
+        format!("{}: {}", self.locale.pick("任务", "Task"), task)
+    }
+
+    /// 模型连续第 `attempt` 次未返回可解析操作时的反馈
+    pub fn no_action_feedback(&self, attempt: usize) -> String {
+        match &self.templates.no_action_feedback {
+            Some(template) => fill(template, &[("attempt", attempt.to_string())]),
+            None => match self.locale {
+                Locale::Zh => format!(
+                    "你的回复中没有包含 do(action=...) 格式的执行动作，我无法解析。\n\n请严格按照 do(action=ActionType, ...) 格式回复，例如：\n{}\n\n请重新分析屏幕并告诉我下一步操作。",
+                    ACTION_FORMAT_EXAMPLES
+                ),
+                Locale::En => format!(
+                    "Your reply does not contain an action in do(action=...) format, so I cannot parse it.\n\nPlease reply strictly in do(action=ActionType, ...) format, for example:\n{}\n\nPlease analyze the screen again and tell me the next action.",
+                    ACTION_FORMAT_EXAMPLES
+                ),
+            },
+        }
+    }
+
+    /// 一步的完整操作结果摘要
+    pub fn result_summary(&self, summary: &StepResultSummary) -> String {
+        match &self.templates.result_summary {
+            Some(template) => summary.render_template(template, self.locale),
+            None => summary.render(self.result_format, self.locale),
+        }
+    }
+
+    /// 较早步骤折叠后的单行结果摘要
+    pub fn collapsed_result_summary(&self, summary: &StepResultSummary) -> String {
+        summary.one_line(self.locale)
+    }
+
+    /// 截图失败时附加到本步提示中的说明
+    pub fn observation_note(&self, observation: &Observation) -> Option<String> {
+        observation.prompt_note(self.locale)
+    }
+
+    /// 离开目标应用时追加到本步用户消息的说明
+    pub fn foreground_note(&self, drift: &ForegroundDrift) -> String {
+        drift.prompt_note(self.locale)
+    }
+
+    /// 记录模型决定执行的操作：(操作描述, 操作类型)
+    pub fn actions_message(&self, actions: &[(String, String)], reasoning: &str) -> String {
+        let lines: Vec<String> = actions
+            .iter()
+            .map(|(description, action_type)| format!("{} ({})", description, action_type))
+            .collect();
+        match self.locale {
+            Locale::Zh => format!("我决定执行 {} 个操作:\n{}\n思考: {}", actions.len(), lines.join("\n"), reasoning),
+            Locale::En => format!("I decided to perform {} action(s):\n{}\nThoughts: {}", actions.len(), lines.join("\n"), reasoning),
+        }
+    }
+
+    /// 模型给出 finish 后的完成消息
+    pub fn completion_message(&self, content: &str, reasoning: Option<&str>) -> String {
+        match self.locale {
+            Locale::Zh => format!("任务完成。{}\n思考过程: {}", content, reasoning.unwrap_or_default()),
+            Locale::En => format!("Task completed. {}\nReasoning: {}", content, reasoning.unwrap_or_default()),
+        }
+    }
+}
+
+/// 将模板中的 `{name}` 替换为对应的值
+pub(crate) fn fill(template: &str, values: &[(&str, String)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+}
+
+fn check_placeholders(field: &str, template: &str, allowed: &[&str]) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 1..start + len];
+        // 只检查形如 `{name}` 的占位符，模板中的其它花括号原样保留
+        let is_placeholder = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if is_placeholder && !allowed.contains(&name) {
+            return Err(format!("prompt_templates.{} 包含不支持的占位符 {{{}}}，可用: {}", field, name, allowed.join(", ")));
+        }
+        rest = &rest[start + len + 1..];
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::core::traits::ActionResult;
+
+    fn summary() -> StepResultSummary {
+        let ok = ActionResult::success("已点击 (540, 1200)".to_string(), 320);
+        let failed = ActionResult::failure("未找到输入框".to_string(), 15);
+        StepResultSummary::new(
+            3,
+            vec![
+                ("tap".to_string(), "点击搜索按钮".to_string(), &ok),
+                ("type".to_string(), "输入 天气".to_string(), &failed),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_default_prompts_snapshot() {
+        let builder = PromptBuilder::default();
+
+        let context = SystemPromptContext {
+            three_stage: true,
+            device_summary: Some("Pixel 7, Android 14".to_string()),
+            prompt_suffix: Some("优先使用搜索".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            builder.system_prompt(&context),
+            format!("{}\n\n当前设备: Pixel 7, Android 14\n\n优先使用搜索", prompts::get_planning_system_prompt())
+        );
+        assert_eq!(builder.task_message("打开设置"), "任务: 打开设置");
+        assert!(builder.no_action_feedback(1).starts_with("你的回复中没有包含 do(action=...) 格式的执行动作"));
+        assert_eq!(
+            builder.result_summary(&summary()),
+            "操作结果（步骤 3）:\n\
+             - 操作 #1: tap (点击搜索按钮)\n  状态: 成功\n  详情: 已点击 (540, 1200)\n  耗时: 320ms\n\
+             - 操作 #2: type (输入 天气)\n  状态: 失败\n  错误: 未找到输入框\n  耗时: 15ms"
+        );
+        assert_eq!(
+            builder.actions_message(&[("点击搜索按钮".to_string(), "tap".to_string())], "先搜索"),
+            "我决定执行 1 个操作:\n点击搜索按钮 (tap)\n思考: 先搜索"
+        );
+        assert_eq!(builder.completion_message("已打开设置", None), "任务完成。已打开设置\n思考过程: ");
+    }
+
+    #[test]
+    fn test_custom_templates_snapshot() {
+        let templates = PromptTemplates {
+            no_action_feedback: Some("第 {attempt} 次未返回操作，请用 do(...) 格式回复".to_string()),
+            result_summary: Some(ResultSummaryTemplate {
+                header: "Step {step}".to_string(),
+                action: "{index}. {action_type} [{status}] {message} ({duration_ms}ms)".to_string(),
+            }),
+        };
+        templates.validate().unwrap();
+        let builder = PromptBuilder::new(templates, ResultSummaryFormat::Compact, Locale::Zh);
+
+        assert_eq!(builder.no_action_feedback(2), "第 2 次未返回操作，请用 do(...) 格式回复");
+        assert_eq!(
+            builder.result_summary(&summary()),
+            "Step 3\n1. tap [成功] 已点击 (540, 1200) (320ms)\n2. type [失败] 未找到输入框 (15ms)"
+        );
+
+        let invalid = PromptTemplates { no_action_feedback: Some("{step}".to_string()), ..Default::default() };
+        assert!(invalid.validate().unwrap_err().contains("{step}"));
+    }
+
+    #[test]
+    fn test_english_prompts_snapshot() {
+        let builder = PromptBuilder::new(PromptTemplates::default(), ResultSummaryFormat::Compact, Locale::En);
+
+        assert_eq!(builder.task_message("打开设置"), "Task: 打开设置");
+        assert!(builder.no_action_feedback(1).starts_with("Your reply does not contain an action in do(action=...) format"));
+        assert_eq!(
+            builder.result_summary(&summary()),
+            "Result#3\ntap 点击搜索按钮 ok: 已点击 (540, 1200)\ntype 输入 天气 fail: 未找到输入框"
+        );
+        assert_eq!(
+            builder.actions_message(&[("tap search".to_string(), "tap".to_string())], "search first"),
+            "I decided to perform 1 action(s):\ntap search (tap)\nThoughts: search first"
+        );
+        assert_eq!(builder.completion_message("Settings opened", Some("done")), "Task completed. Settings opened\nReasoning: done");
+    }
+}
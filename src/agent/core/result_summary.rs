@@ -0,0 +1,197 @@
+//! 操作结果摘要
+//!
+//! 每步执行后以用户消息的形式把操作结果告诉模型。长任务中这些消息会不断累积，
+//! 因此较早步骤的结果会被折叠成单行，只保留最近几步的完整内容。
+
+use serde::{Deserialize, Serialize};
+
+use super::locale::Locale;
+use super::prompt_builder::{fill, ResultSummaryTemplate};
+use super::traits::ActionResult;
+
+/// 操作结果摘要格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultSummaryFormat {
+    /// 每个操作多行：状态、详情与耗时
+    #[default]
+    Verbose,
+    /// 每个操作一行，不含耗时
+    Compact,
+}
+
+/// 单个操作的结果
+#[derive(Debug, Clone)]
+struct ActionOutcome {
+    action_type: String,
+    description: String,
+    success: bool,
+    message: String,
+    duration_ms: u32,
+}
+
+/// 一步中所有操作的结果
+#[derive(Debug, Clone)]
+pub struct StepResultSummary {
+    step: usize,
+    outcomes: Vec<ActionOutcome>,
+}
+
+impl StepResultSummary {
+    /// 由 (操作类型, 操作描述, 执行结果) 构建
+    pub fn new<'a>(step: usize, results: impl IntoIterator<Item = (String, String, &'a ActionResult)>) -> Self {
+        let outcomes = results
+            .into_iter()
+            .map(|(action_type, description, result)| ActionOutcome {
+                action_type,
+                description,
+                success: result.success,
+                message: result.message.clone(),
+                duration_ms: result.duration_ms,
+            })
+            .collect();
+        Self { step, outcomes }
+    }
+
+    /// 按指定格式与语言生成完整摘要
+    pub fn render(&self, format: ResultSummaryFormat, locale: Locale) -> String {
+        match format {
+            ResultSummaryFormat::Verbose => self.verbose(locale),
+            ResultSummaryFormat::Compact => self.compact(locale),
+        }
+    }
+
+    /// 按配置的模板生成完整摘要：标题行之后每个操作一行
+    pub fn render_template(&self, template: &ResultSummaryTemplate, locale: Locale) -> String {
+        let mut lines = vec![fill(&template.header, &[("step", self.step.to_string())])];
+        lines.extend(self.outcomes.iter().enumerate().map(|(idx, outcome)| {
+            fill(
+                &template.action,
+                &[
+                    ("index", (idx + 1).to_string()),
+                    ("action_type", outcome.action_type.clone()),
+                    ("description", outcome.description.clone()),
+                    ("status", status_text(outcome.success, locale).to_string()),
+                    ("message", outcome.message.clone()),
+                    ("duration_ms", outcome.duration_ms.to_string()),
+                ],
+            )
+        }));
+        lines.join("\n")
+    }
+
+    fn verbose(&self, locale: Locale) -> String {
+        let parts: Vec<String> = self
+            .outcomes
+            .iter()
+            .enumerate()
+            .map(|(idx, outcome)| {
+                let detail = if outcome.success {
+                    format!("{}: {}", locale.pick("详情", "Detail"), outcome.message)
+                } else {
+                    format!("{}: {}", locale.pick("错误", "Error"), outcome.message)
+                };
+                format!(
+                    "- {} #{}: {} ({})\n  {}: {}\n  {}\n  {}: {}ms",
+                    locale.pick("操作", "Action"),
+                    idx + 1,
+                    outcome.action_type,
+                    outcome.description,
+                    locale.pick("状态", "Status"),
+                    status_text(outcome.success, locale),
+                    detail,
+                    locale.pick("耗时", "Duration"),
+                    outcome.duration_ms
+                )
+            })
+            .collect();
+        match locale {
+            Locale::Zh => format!("操作结果（步骤 {}）:\n{}", self.step, parts.join("\n")),
+            Locale::En => format!("Action results (step {}):\n{}", self.step, parts.join("\n")),
+        }
+    }
+
+    fn compact(&self, locale: Locale) -> String {
+        let parts: Vec<String> = self
+            .outcomes
+            .iter()
+            .map(|outcome| {
+                let status = if outcome.success { "ok" } else { "fail" };
+                format!("{} {} {}: {}", outcome.action_type, outcome.description, status, outcome.message)
+            })
+            .collect();
+        format!("{}#{}\n{}", locale.pick("结果", "Result"), self.step, parts.join("\n"))
+    }
+
+    /// 折叠后的单行摘要，只有失败的操作保留错误信息
+    pub fn one_line(&self, locale: Locale) -> String {
+        let parts: Vec<String> = self
+            .outcomes
+            .iter()
+            .map(|outcome| {
+                if outcome.success {
+                    format!("{} ok", outcome.action_type)
+                } else {
+                    format!("{} fail({})", outcome.action_type, outcome.message)
+                }
+            })
+            .collect();
+        format!("{}#{}: {}", locale.pick("结果", "Result"), self.step, parts.join("; "))
+    }
+}
+
+/// 操作状态的文字
+fn status_text(success: bool, locale: Locale) -> &'static str {
+    match (success, locale) {
+        (true, Locale::Zh) => "成功",
+        (false, Locale::Zh) => "失败",
+        (true, Locale::En) => "success",
+        (false, Locale::En) => "failed",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_formats() {
+        let ok = ActionResult::success("已点击 (540, 1200)".to_string(), 320);
+        let failed = ActionResult::failure("未找到输入框".to_string(), 15);
+        let summary = StepResultSummary::new(
+            3,
+            vec![
+                ("tap".to_string(), "点击搜索按钮".to_string(), &ok),
+                ("type".to_string(), "输入 天气".to_string(), &failed),
+            ],
+        );
+
+        let verbose = summary.render(ResultSummaryFormat::Verbose, Locale::Zh);
+        let compact = summary.render(ResultSummaryFormat::Compact, Locale::Zh);
+        let one_line = summary.one_line(Locale::Zh);
+
+        assert!(verbose.contains("耗时: 320ms"));
+        assert_eq!(compact, "结果#3\ntap 点击搜索按钮 ok: 已点击 (540, 1200)\ntype 输入 天气 fail: 未找到输入框");
+        assert_eq!(one_line, "结果#3: tap ok; type fail(未找到输入框)");
+        assert!(one_line.len() < compact.len() && compact.len() < verbose.len());
+    }
+
+    #[test]
+    fn test_english_summary() {
+        let ok = ActionResult::success("tapped (540, 1200)".to_string(), 320);
+        let failed = ActionResult::failure("input not found".to_string(), 15);
+        let summary = StepResultSummary::new(
+            3,
+            vec![("tap".to_string(), "tap search".to_string(), &ok), ("type".to_string(), "type weather".to_string(), &failed)],
+        );
+
+        assert_eq!(
+            summary.render(ResultSummaryFormat::Verbose, Locale::En),
+            "Action results (step 3):\n\
+             - Action #1: tap (tap search)\n  Status: success\n  Detail: tapped (540, 1200)\n  Duration: 320ms\n\
+             - Action #2: type (type weather)\n  Status: failed\n  Error: input not found\n  Duration: 15ms"
+        );
+        assert_eq!(summary.one_line(Locale::En), "Result#3: tap ok; type fail(input not found)");
+        assert!(summary.render(ResultSummaryFormat::Compact, Locale::En).is_ascii());
+    }
+}
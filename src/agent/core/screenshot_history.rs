@@ -0,0 +1,143 @@
+//! 历史截图
+//!
+//! 保存任务中前几步的截图，随每次查询一起发送给模型，便于模型判断两步之间屏幕发生了什么变化。
+//! 截图按步数逐级缩小（越旧越小），以控制图片占用的 token。
+
+use std::collections::VecDeque;
+use std::io::Cursor;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use tracing::warn;
+
+use super::message::{ChatMessage, ContentBlock, MessageContent, MessageRole};
+
+/// 历史截图缩小后的最小宽度
+const MIN_HISTORY_WIDTH: u32 = 120;
+
+/// 最近 N 步的截图（已缩小），由旧到新
+pub struct ScreenshotHistory {
+    capacity: usize,
+    max_width: u32,
+    images: VecDeque<String>,
+}
+
+impl ScreenshotHistory {
+    /// `capacity` 为保留的截图数（0 表示不附加历史截图），
+    /// `max_width` 为最近一张历史截图的宽度，更早的截图每步再缩小一半
+    pub fn new(capacity: usize, max_width: u32) -> Self {
+        Self {
+            capacity,
+            max_width: max_width.max(MIN_HISTORY_WIDTH),
+            images: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// 当前保存的截图数
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    /// 是否没有历史截图
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// 记录一步的截图，已有截图各缩小一级
+    pub fn push(&mut self, screenshot: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        for image in self.images.iter_mut() {
+            if let Some(smaller) = downscale_base64_image(image, None) {
+                *image = smaller;
+            }
+        }
+        match downscale_base64_image(screenshot, Some(self.max_width)) {
+            Some(image) => self.images.push_back(image),
+            None => warn!("历史截图缩小失败，跳过本步截图"),
+        }
+        while self.images.len() > self.capacity {
+            self.images.pop_front();
+        }
+    }
+
+    /// 将历史截图附加到最后一条用户消息末尾
+    pub fn attach(&self, messages: &mut [ChatMessage]) {
+        if self.images.is_empty() {
+            return;
+        }
+        let Some(message) = messages.iter_mut().rev().find(|m| m.role == MessageRole::User) else {
+            return;
+        };
+
+        let mut blocks = match std::mem::replace(&mut message.content, MessageContent::Multimodal(Vec::new())) {
+            MessageContent::Text(text) => vec![ContentBlock::text(text)],
+            MessageContent::Multimodal(blocks) => blocks,
+        };
+        blocks.push(ContentBlock::text(format!(
+            "以下是之前 {} 步的屏幕截图（由旧到新，已缩小），可与当前屏幕对比判断上一步操作的效果：",
+            self.images.len()
+        )));
+        blocks.extend(self.images.iter().map(|image| ContentBlock::image(image)));
+        message.content = MessageContent::Multimodal(blocks);
+    }
+}
+
+/// 缩小 base64 编码的截图，返回 PNG 的 base64
+///
+/// `width` 为 `None` 时缩小为原宽度的一半，不低于最小宽度；图片已足够小时原样返回
+pub(crate) fn downscale_base64_image(base64_data: &str, width: Option<u32>) -> Option<String> {
+    let data = crate::agent::core::screenshot_store::strip_data_url(base64_data);
+    let bytes = STANDARD.decode(data).ok()?;
+    let image = image::load_from_memory(&bytes).ok()?;
+
+    let target = width.unwrap_or(image.width() / 2).max(MIN_HISTORY_WIDTH);
+    if image.width() <= target {
+        return Some(data.to_string());
+    }
+    let height = (image.height() as u64 * target as u64 / image.width() as u64).max(1) as u32;
+    let resized = image.thumbnail(target, height);
+
+    let mut output = Cursor::new(Vec::new());
+    resized.write_to(&mut output, image::ImageFormat::Png).ok()?;
+    Some(STANDARD.encode(output.into_inner()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png_base64(width: u32, height: u32) -> String {
+        let image = image::DynamicImage::new_rgb8(width, height);
+        let mut output = Cursor::new(Vec::new());
+        image.write_to(&mut output, image::ImageFormat::Png).unwrap();
+        STANDARD.encode(output.into_inner())
+    }
+
+    fn width_of(base64_data: &str) -> u32 {
+        image::load_from_memory(&STANDARD.decode(base64_data).unwrap()).unwrap().width()
+    }
+
+    #[test]
+    fn test_history_downscales_and_attaches() {
+        let mut history = ScreenshotHistory::new(2, 480);
+        history.push(&png_base64(1080, 2400));
+        history.push(&png_base64(1080, 2400));
+        history.push(&png_base64(1080, 2400));
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(width_of(&history.images[0]), 240);
+        assert_eq!(width_of(&history.images[1]), 480);
+
+        let mut messages = vec![ChatMessage::system("提示词"), ChatMessage::user("继续")];
+        history.attach(&mut messages);
+        assert_eq!(messages[0].image_count(), 0);
+        assert_eq!(messages[1].image_count(), 2);
+        assert!(messages[1].text().starts_with("继续\n以下是之前 2 步"));
+
+        let mut disabled = ScreenshotHistory::new(0, 480);
+        disabled.push(&png_base64(100, 100));
+        assert!(disabled.is_empty());
+    }
+}
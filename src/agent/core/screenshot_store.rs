@@ -0,0 +1,275 @@
+//! 执行步骤截图的磁盘存储
+//!
+//! 截图按内容哈希保存为文件，同一张截图（如一步中的多个操作、没有变化的屏幕）只写入一次。
+//! `ExecutionStep` 只保留哈希与路径，查看历史时再按需读取图片。`[agent.screenshot_log]` 段可以改为
+//! JPEG（可设质量）/ 无损 WebP 格式、在日志中内嵌缩略图而不写文件，或完全不保存截图。
+
+use std::fs;
+use std::path::PathBuf;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, ExtendedColorType};
+use serde::{Deserialize, Serialize};
+
+/// 去掉 `data:<mime>;base64,` 前缀，返回 base64 数据
+pub fn strip_data_url(screenshot_base64: &str) -> &str {
+    match screenshot_base64.strip_prefix("data:").and_then(|rest| rest.split_once(";base64,")) {
+        Some((_, data)) => data,
+        None => screenshot_base64,
+    }
+}
+
+/// 截图保存方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenshotPersistence {
+    /// 保存为文件，日志中记录路径
+    #[default]
+    File,
+    /// 不写文件，日志中内嵌缩略图（JPEG）
+    Inline,
+    /// 不保存截图
+    None,
+}
+
+/// 截图文件格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenshotFormat {
+    #[default]
+    Png,
+    Jpeg,
+    /// 无损 WebP
+    Webp,
+}
+
+impl ScreenshotFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ScreenshotFormat::Png => "png",
+            ScreenshotFormat::Jpeg => "jpg",
+            ScreenshotFormat::Webp => "webp",
+        }
+    }
+}
+
+/// 截图保存策略（`[agent.screenshot_log]` 段）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenshotLogPolicy {
+    pub mode: ScreenshotPersistence,
+    pub format: ScreenshotFormat,
+    /// JPEG 质量（1-100），也用于内嵌缩略图
+    pub quality: u8,
+    /// 内嵌缩略图的宽度（像素）
+    pub thumbnail_width: u32,
+}
+
+impl Default for ScreenshotLogPolicy {
+    fn default() -> Self {
+        Self {
+            mode: ScreenshotPersistence::File,
+            format: ScreenshotFormat::Png,
+            quality: 80,
+            thumbnail_width: 240,
+        }
+    }
+}
+
+impl ScreenshotLogPolicy {
+    /// 校验配置项的取值范围
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=100).contains(&self.quality) {
+            return Err(format!("screenshot_log.quality 必须在 1 ~ 100 之间: {}", self.quality));
+        }
+        if self.thumbnail_width == 0 {
+            return Err("screenshot_log.thumbnail_width 必须大于 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// 按配置的格式编码截图（设备截图为 PNG，PNG 格式时原样返回）
+    pub fn encode(&self, bytes: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        match self.format {
+            ScreenshotFormat::Png => Ok(bytes.to_vec()),
+            ScreenshotFormat::Jpeg => encode_jpeg(&decode(bytes)?, self.quality),
+            ScreenshotFormat::Webp => {
+                let rgba = decode(bytes)?.to_rgba8();
+                let mut output = Vec::new();
+                WebPEncoder::new_lossless(&mut output)
+                    .encode(&rgba, rgba.width(), rgba.height(), ExtendedColorType::Rgba8)
+                    .map_err(invalid_data)?;
+                Ok(output)
+            }
+        }
+    }
+
+    /// 内嵌到日志的缩略图（JPEG 的 base64）
+    pub fn thumbnail(&self, bytes: &[u8]) -> Result<String, std::io::Error> {
+        let image = decode(bytes)?;
+        let image = if image.width() > self.thumbnail_width {
+            let height = (image.height() as u64 * self.thumbnail_width as u64 / image.width() as u64).max(1) as u32;
+            image.thumbnail(self.thumbnail_width, height)
+        } else {
+            image
+        };
+        Ok(STANDARD.encode(encode_jpeg(&image, self.quality)?))
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<DynamicImage, std::io::Error> {
+    image::load_from_memory(bytes).map_err(invalid_data)
+}
+
+fn encode_jpeg(image: &DynamicImage, quality: u8) -> Result<Vec<u8>, std::io::Error> {
+    let mut output = Vec::new();
+    JpegEncoder::new_with_quality(&mut output, quality).encode_image(&image.to_rgb8()).map_err(invalid_data)?;
+    Ok(output)
+}
+
+fn invalid_data(e: image::ImageError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, format!("截图转码失败: {}", e))
+}
+
+/// 已保存的截图
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredScreenshot {
+    /// 图片内容哈希（16 位十六进制）
+    pub hash: String,
+    /// 文件路径，内嵌模式下为空
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub path: String,
+    /// 内嵌的缩略图（JPEG 的 base64）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+}
+
+impl StoredScreenshot {
+    /// 读取截图，返回 base64；内嵌模式下返回缩略图
+    pub fn load(&self) -> Result<String, std::io::Error> {
+        if self.path.is_empty() {
+            return self
+                .thumbnail
+                .clone()
+                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "截图未保存"));
+        }
+        Ok(STANDARD.encode(fs::read(&self.path)?))
+    }
+}
+
+/// 截图存储目录
+#[derive(Debug, Clone)]
+pub struct ScreenshotStore {
+    dir: PathBuf,
+    policy: ScreenshotLogPolicy,
+}
+
+impl ScreenshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), policy: ScreenshotLogPolicy::default() }
+    }
+
+    /// 设置保存策略
+    pub fn with_policy(mut self, policy: ScreenshotLogPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// 存储目录
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+
+    /// 按策略保存 base64 截图，相同内容的截图已存在时直接返回；不保存截图时返回 None
+    pub fn save(&self, screenshot_base64: &str) -> Result<Option<StoredScreenshot>, std::io::Error> {
+        if self.policy.mode == ScreenshotPersistence::None {
+            return Ok(None);
+        }
+        let bytes = STANDARD
+            .decode(strip_data_url(screenshot_base64))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Base64 解码失败: {}", e)))?;
+
+        let hash = format!("{:016x}", content_hash(&bytes));
+        if self.policy.mode == ScreenshotPersistence::Inline {
+            let thumbnail = self.policy.thumbnail(&bytes)?;
+            return Ok(Some(StoredScreenshot { hash, path: String::new(), thumbnail: Some(thumbnail) }));
+        }
+        let path = self.dir.join(format!("{}.{}", hash, self.policy.format.extension()));
+        if !path.exists() {
+            let encoded = self.policy.encode(&bytes)?;
+            fs::create_dir_all(&self.dir)?;
+            fs::write(&path, encoded)?;
+        }
+        Ok(Some(StoredScreenshot {
+            hash,
+            path: path.to_string_lossy().into_owned(),
+            thumbnail: None,
+        }))
+    }
+}
+
+/// FNV-1a 64 位哈希（跨进程稳定，用作文件名）
+fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| (hash ^ u64::from(b)).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ImageFormat;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_save_deduplicates_and_loads() {
+        let dir = std::env::temp_dir().join(format!("scrs_screenshot_store_{}", std::process::id()));
+        let store = ScreenshotStore::new(&dir);
+
+        let screenshot = STANDARD.encode(b"fake png bytes");
+        let first = store.save(&screenshot).unwrap().unwrap();
+        let second = store.save(&format!("data:image/png;base64,{}", screenshot)).unwrap().unwrap();
+        let other = store.save(&STANDARD.encode(b"another screen")).unwrap().unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first.hash, other.hash);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        assert_eq!(first.load().unwrap(), screenshot);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_screenshot_policies() {
+        let image = DynamicImage::ImageRgb8(image::RgbImage::from_fn(480, 960, |x, y| image::Rgb([x as u8, y as u8, 128])));
+        let mut png = Cursor::new(Vec::new());
+        image.write_to(&mut png, ImageFormat::Png).unwrap();
+        let screenshot = format!("data:image/jpeg;base64,{}", STANDARD.encode(png.get_ref()));
+        assert_eq!(strip_data_url(&screenshot), STANDARD.encode(png.get_ref()));
+
+        let dir = std::env::temp_dir().join(format!("scrs_screenshot_policy_{}", std::process::id()));
+        let policy = |mode, format| ScreenshotLogPolicy { mode, format, ..Default::default() };
+
+        let jpeg = ScreenshotStore::new(&dir).with_policy(policy(ScreenshotPersistence::File, ScreenshotFormat::Jpeg));
+        let stored = jpeg.save(&screenshot).unwrap().unwrap();
+        assert!(stored.path.ends_with(".jpg"));
+        assert_eq!(image::guess_format(&fs::read(&stored.path).unwrap()).unwrap(), ImageFormat::Jpeg);
+
+        let webp = ScreenshotStore::new(&dir).with_policy(policy(ScreenshotPersistence::File, ScreenshotFormat::Webp));
+        let stored = webp.save(&screenshot).unwrap().unwrap();
+        assert_eq!(image::guess_format(&fs::read(&stored.path).unwrap()).unwrap(), ImageFormat::WebP);
+
+        let inline = ScreenshotStore::new(&dir).with_policy(policy(ScreenshotPersistence::Inline, ScreenshotFormat::Png));
+        let stored = inline.save(&screenshot).unwrap().unwrap();
+        assert!(stored.path.is_empty());
+        let thumbnail = image::load_from_memory(&STANDARD.decode(stored.load().unwrap()).unwrap()).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (240, 480));
+
+        let none = ScreenshotStore::new(&dir).with_policy(policy(ScreenshotPersistence::None, ScreenshotFormat::Png));
+        assert!(none.save(&screenshot).unwrap().is_none());
+        assert!(ScreenshotLogPolicy { quality: 0, ..Default::default() }.validate().is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, watch};
+
+use super::locale::Locale;
+use super::prompt_builder::PromptTemplates;
+use super::result_summary::ResultSummaryFormat;
+use super::screenshot_store::ScreenshotLogPolicy;
+use crate::agent::executor::{ScreenCheck, TypeVerification};
 
 /// Agent 状态机
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -12,7 +18,58 @@ pub enum AgentState {
     Waiting { step: usize, reason: String },
     Paused { step: usize },
     Completed { steps: usize, duration_ms: u64 },
-    Failed { step: usize, error: String },
+    Failed { step: usize, reason: FailureReason, error: String },
+}
+
+/// 任务失败的类别，调用方可据此区分处理（如模型错误重试、设备离线换设备）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// 模型查询失败
+    ModelError,
+    /// 模型连续多次未返回可解析的操作
+    ParseFailure,
+    /// 设备离线或无法截图
+    DeviceOffline,
+    /// 超过最大步数
+    MaxSteps,
+    /// 超过最长执行时间
+    Timeout,
+    /// 模型的操作或完成声明未通过校验
+    ActionRejected,
+    /// 任务被停止
+    Cancelled,
+}
+
+/// 单个任务允许的最大步数上限
+pub const MAX_TASK_STEPS: usize = 500;
+
+/// 单个任务允许的最长执行时间上限（秒）
+pub const MAX_TASK_EXECUTION_SECS: u64 = 4 * 3600;
+
+/// 任务的步数与时间限制，超出时任务失败
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskLimits {
+    /// 最大步数（1 ~ [`MAX_TASK_STEPS`]）
+    pub max_steps: usize,
+    /// 最长执行时间（秒，1 ~ [`MAX_TASK_EXECUTION_SECS`]）
+    pub max_execution_time: u64,
+}
+
+impl TaskLimits {
+    /// 校验取值范围
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_TASK_STEPS).contains(&self.max_steps) {
+            return Err(format!("max_steps 必须在 1 ~ {} 之间: {}", MAX_TASK_STEPS, self.max_steps));
+        }
+        if !(1..=MAX_TASK_EXECUTION_SECS).contains(&self.max_execution_time) {
+            return Err(format!(
+                "max_execution_time 必须在 1 ~ {} 秒之间: {}",
+                MAX_TASK_EXECUTION_SECS, self.max_execution_time
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Agent 配置
@@ -44,6 +101,70 @@ pub struct AgentConfig {
 
     /// 日志文件路径
     pub log_file: String,
+
+    /// 每次查询附带的历史截图数（0 表示只发送当前截图）
+    #[serde(default)]
+    pub history_screenshots: usize,
+
+    /// 最近一张历史截图的宽度（像素），更早的截图逐步缩小
+    #[serde(default = "default_history_screenshot_width")]
+    pub history_screenshot_width: u32,
+
+    /// 操作结果摘要格式
+    #[serde(default)]
+    pub result_summary_format: ResultSummaryFormat,
+
+    /// 无操作反馈与操作结果摘要的模板
+    #[serde(default)]
+    pub prompt_templates: PromptTemplates,
+
+    /// Agent 注入对话的消息与任务失败原因使用的语言
+    #[serde(default)]
+    pub locale: Locale,
+
+    /// 保留完整操作结果的最近步数，更早的结果折叠为单行
+    #[serde(default = "default_full_result_steps")]
+    pub full_result_steps: usize,
+
+    /// 一次执行多个操作时，操作之间的屏幕校验方式
+    #[serde(default)]
+    pub screen_check: ScreenCheck,
+
+    /// Type 操作后读回输入框文字的校验方式
+    #[serde(default)]
+    pub type_verification: TypeVerification,
+
+    /// 任务结果回调的签名密钥，未设置时回调不带签名
+    #[serde(default)]
+    pub callback_secret: Option<String>,
+
+    /// 每步截图失败后的重试次数（指数退避）
+    #[serde(default = "default_screenshot_retries")]
+    pub screenshot_retries: u32,
+
+    /// 允许连续截图失败的步数，期间使用上一张截图或界面控件摘要，超过后任务失败
+    #[serde(default = "default_max_screenshot_misses")]
+    pub max_screenshot_misses: u32,
+
+    /// 步骤截图的保存格式与方式（`[agent.screenshot_log]` 段）
+    #[serde(default)]
+    pub screenshot_log: ScreenshotLogPolicy,
+}
+
+fn default_history_screenshot_width() -> u32 {
+    480
+}
+
+fn default_full_result_steps() -> usize {
+    2
+}
+
+fn default_screenshot_retries() -> u32 {
+    2
+}
+
+fn default_max_screenshot_misses() -> u32 {
+    3
 }
 
 impl Default for AgentConfig {
@@ -58,8 +179,54 @@ impl Default for AgentConfig {
             enable_safety: true,
             enable_rollback: false,
             log_file: "logs/agent.log".to_string(),
+            history_screenshots: 0,
+            history_screenshot_width: default_history_screenshot_width(),
+            result_summary_format: ResultSummaryFormat::default(),
+            prompt_templates: PromptTemplates::default(),
+            locale: Locale::default(),
+            full_result_steps: default_full_result_steps(),
+            screen_check: ScreenCheck::default(),
+            type_verification: TypeVerification::default(),
+            callback_secret: None,
+            screenshot_retries: default_screenshot_retries(),
+            max_screenshot_misses: default_max_screenshot_misses(),
+            screenshot_log: ScreenshotLogPolicy::default(),
+        }
+    }
+}
+
+impl AgentConfig {
+    /// 任务的默认步数与时间限制
+    pub fn limits(&self) -> TaskLimits {
+        TaskLimits { max_steps: self.max_steps, max_execution_time: self.max_execution_time }
+    }
+
+    /// 校验配置项的取值范围
+    /// 解析回调签名密钥的外部引用（`file:`、`env:` 等），并登记为需要脱敏的密钥
+    pub fn resolve_secrets(&mut self) -> Result<(), String> {
+        if let Some(secret) = self.callback_secret.as_mut() {
+            *secret = crate::secrets::resolve_secret(secret)?;
+            crate::secrets::register_secret(secret);
         }
+        Ok(())
     }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.limits().validate()?;
+        self.screenshot_log.validate()?;
+        self.prompt_templates.validate()
+    }
+}
+
+/// 任务执行统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskStats {
+    pub steps: usize,
+    pub duration_ms: u64,
+    pub tokens_used: u64,
+    /// 任务生效的步数与时间限制
+    #[serde(default)]
+    pub limits: TaskLimits,
 }
 
 /// 线程安全的 Agent 运行时状态
@@ -71,10 +238,16 @@ pub struct AgentRuntime {
     pub execution_history: Arc<RwLock<Vec<super::traits::ExecutionStep>>>,
     pub step_counter: Arc<RwLock<usize>>,
     pub start_time: Arc<RwLock<Option<chrono::DateTime<chrono::Utc>>>>,
+    pub tokens_used: Arc<RwLock<u64>>,
+    /// 当前（或最近一次）任务生效的限制
+    pub limits: Arc<RwLock<TaskLimits>>,
+    /// 暂停请求，执行循环在步骤边界检查
+    pub pause_requested: Arc<watch::Sender<bool>>,
 }
 
 impl AgentRuntime {
     pub fn new(config: AgentConfig) -> Self {
+        let limits = config.limits();
         Self {
             state: Arc::new(RwLock::new(AgentState::Idle)),
             config,
@@ -82,6 +255,9 @@ impl AgentRuntime {
             execution_history: Arc::new(RwLock::new(Vec::new())),
             step_counter: Arc::new(RwLock::new(0)),
             start_time: Arc::new(RwLock::new(None)),
+            tokens_used: Arc::new(RwLock::new(0)),
+            limits: Arc::new(RwLock::new(limits)),
+            pause_requested: Arc::new(watch::channel(false).0),
         }
     }
 
@@ -92,6 +268,8 @@ impl AgentRuntime {
         self.execution_history.write().await.clear();
         *self.step_counter.write().await = 0;
         *self.start_time.write().await = None;
+        *self.tokens_used.write().await = 0;
+        self.pause_requested.send_replace(false);
     }
 
     /// 获取已用时间（毫秒）
@@ -115,6 +293,31 @@ impl AgentRuntime {
         *counter
     }
 
+    /// 累加模型消耗的 token 数
+    pub async fn add_tokens(&self, tokens: u32) {
+        *self.tokens_used.write().await += tokens as u64;
+    }
+
+    /// 获取任务执行统计，已结束的任务使用结束时记录的步数与耗时
+    pub async fn stats(&self) -> TaskStats {
+        let tokens_used = *self.tokens_used.read().await;
+        let limits = *self.limits.read().await;
+        match &*self.state.read().await {
+            AgentState::Completed { steps, duration_ms } => TaskStats {
+                steps: *steps,
+                duration_ms: *duration_ms,
+                tokens_used,
+                limits,
+            },
+            _ => TaskStats {
+                steps: self.current_step().await,
+                duration_ms: self.elapsed_ms().await,
+                tokens_used,
+                limits,
+            },
+        }
+    }
+
     /// 获取当前步数
     pub async fn current_step(&self) -> usize {
         *self.step_counter.read().await
@@ -0,0 +1,215 @@
+//! 任务记录
+//!
+//! 按任务 ID 查询任务状态：执行中或刚结束的任务使用内存中的进度事件，进度已过期或服务重启后
+//! 从 Agent 日志（`task_start`、`task_complete`、`task_failed`、`task_stopped` 事件）还原任务的开始、结束与结果，
+//! 其它连接或重连后的客户端都可以据此查询和跟踪 `agent/start` 启动的任务。
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::callback::CallbackStatus;
+use super::progress::{ProgressReplay, TaskProgress};
+use super::state::FailureReason;
+use crate::agent::logger::scan_log_events;
+
+/// 日志中与任务开始、结束有关的事件
+const TASK_EVENTS: [&str; 4] = ["\"task_start\"", "\"task_complete\"", "\"task_failed\"", "\"task_stopped\""];
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskRecordState {
+    /// 正在执行
+    #[default]
+    Running,
+    Completed,
+    Failed,
+    /// 被停止
+    Stopped,
+    /// 日志中没有结束记录且任务已不在执行（如服务重启），结果未知
+    Interrupted,
+}
+
+impl From<CallbackStatus> for TaskRecordState {
+    fn from(status: CallbackStatus) -> Self {
+        match status {
+            CallbackStatus::Completed => Self::Completed,
+            CallbackStatus::Failed => Self::Failed,
+            CallbackStatus::Stopped => Self::Stopped,
+        }
+    }
+}
+
+/// 任务记录
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TaskRecord {
+    pub task_id: String,
+    pub agent_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_serial: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    pub state: TaskRecordState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// 已执行的操作数
+    pub steps: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<FailureReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 最新进度事件的序号（进度仍在内存中时），可作为 `agent/attach` 的游标
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<u64>,
+}
+
+impl TaskRecord {
+    /// 由内存中的全部进度事件（游标为 0 的补发）生成
+    pub fn from_progress(replay: &ProgressReplay) -> Self {
+        let mut record = Self {
+            task_id: replay.task_id.clone(),
+            agent_id: replay.agent_id.clone(),
+            device_serial: Some(replay.device_serial.clone()),
+            steps: replay.summary.steps,
+            cursor: Some(replay.cursor),
+            ..Default::default()
+        };
+        for event in &replay.events {
+            match &event.progress {
+                TaskProgress::Started { task, .. } => {
+                    record.task = Some(task.clone());
+                    record.started_at = Some(event.timestamp);
+                }
+                TaskProgress::Step { .. } => {}
+                TaskProgress::Finished { status, result, failure_reason, error } => {
+                    record.state = (*status).into();
+                    record.finished_at = Some(event.timestamp);
+                    record.result = result.clone();
+                    record.failure_reason = *failure_reason;
+                    record.error = error.clone();
+                }
+            }
+        }
+        record
+    }
+
+    /// 用日志中的记录补全内存进度中缺失的信息（早期事件已丢弃时的任务描述、开始时间等）
+    pub fn merge_log(mut self, log: Option<TaskRecord>) -> Self {
+        let Some(log) = log else {
+            return self;
+        };
+        self.task = self.task.or(log.task);
+        self.started_at = self.started_at.or(log.started_at);
+        self.steps = self.steps.max(log.steps);
+        if self.state != TaskRecordState::Running {
+            self.finished_at = self.finished_at.or(log.finished_at);
+            self.result = self.result.or(log.result);
+            self.error = self.error.or(log.error);
+        }
+        self
+    }
+
+    /// 读取一行日志事件，属于本任务时更新记录
+    fn apply_log_event(&mut self, value: &serde_json::Value) {
+        let timestamp = value["timestamp"].as_str().and_then(|t| DateTime::parse_from_rfc3339(t).ok()).map(|t| t.with_timezone(&Utc));
+        let text = |key: &str| value[key].as_str().map(str::to_string);
+        match value["event"].as_str() {
+            Some("task_start") => {
+                self.task = text("task");
+                self.device_serial = text("device_serial").or(self.device_serial.take());
+                self.started_at = timestamp;
+            }
+            Some("task_complete") => {
+                self.state = TaskRecordState::Completed;
+                self.finished_at = timestamp;
+                self.result = text("result");
+                self.steps = value["steps"].as_u64().unwrap_or(0) as usize;
+            }
+            Some("task_failed") => {
+                self.state = TaskRecordState::Failed;
+                self.finished_at = timestamp;
+                self.error = text("error");
+                self.steps = value["step"].as_u64().unwrap_or(0) as usize;
+            }
+            Some("task_stopped") => {
+                self.state = TaskRecordState::Stopped;
+                self.finished_at = timestamp;
+                self.steps = value["step"].as_u64().unwrap_or(0) as usize;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 从日志目录中读取任务记录。`id` 可以是任务 ID，也可以是 Agent ID（返回该 Agent 最近开始的任务）；
+/// 日志中没有结束记录的任务状态为 [`TaskRecordState::Interrupted`]，由调用方结合内存中的进度判断是否仍在执行
+pub fn load_task_record(log_dir: impl AsRef<Path>, id: &str) -> std::io::Result<Option<TaskRecord>> {
+    let mut events = scan_log_events(log_dir, |line| {
+        line.contains(id) && TASK_EVENTS.iter().any(|event| line.contains(event))
+    })?;
+    events.retain(|value| value["task_id"] == id || value["agent_id"] == id);
+    // 按 Agent ID 查询时取最近开始的任务
+    events.sort_by(|a, b| a["timestamp"].as_str().cmp(&b["timestamp"].as_str()));
+    let Some(task_id) = events
+        .iter()
+        .rev()
+        .find(|value| value["event"] == "task_start" && (value["task_id"] == id || value["agent_id"] == id))
+        .and_then(|value| value["task_id"].as_str())
+        .map(str::to_string)
+    else {
+        return Ok(None);
+    };
+
+    let mut record = TaskRecord { task_id: task_id.clone(), state: TaskRecordState::Interrupted, ..Default::default() };
+    for value in events.iter().filter(|value| value["task_id"] == task_id.as_str()) {
+        if let Some(agent_id) = value["agent_id"].as_str() {
+            record.agent_id = agent_id.to_string();
+        }
+        record.apply_log_event(value);
+    }
+    Ok(Some(record))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_task_record() {
+        let dir = std::env::temp_dir().join(format!("scrs_task_record_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = [
+            r#"{"timestamp":"2026-01-01T10:00:00+00:00","agent_id":"agent_1","task_id":"agent_1_1","event":"task_start","task":"打开设置","device_serial":"emulator-5554"}"#,
+            r#"{"timestamp":"2026-01-01T10:01:00+00:00","agent_id":"agent_1","task_id":"agent_1_1","event":"task_complete","result":"已打开","steps":4,"duration_ms":60000}"#,
+            r#"{"timestamp":"2026-01-01T11:00:00+00:00","agent_id":"agent_1","task_id":"agent_1_2","event":"task_start","task":"打开相机"}"#,
+            r#"{"timestamp":"2026-01-01T09:00:00+00:00","agent_id":"agent_1","task_id":"agent_1_0","event":"task_start","task":"打开相册"}"#,
+            r#"{"timestamp":"2026-01-01T09:00:30+00:00","agent_id":"agent_1","task_id":"agent_1_0","event":"task_stopped","step":2}"#,
+        ]
+        .join("\n");
+        std::fs::write(dir.join("agent_agent_1_2026-01-01.jsonl"), log).unwrap();
+
+        let record = load_task_record(&dir, "agent_1_1").unwrap().unwrap();
+        assert_eq!(record.state, TaskRecordState::Completed);
+        assert_eq!((record.task.as_deref(), record.result.as_deref()), (Some("打开设置"), Some("已打开")));
+        assert_eq!(record.device_serial.as_deref(), Some("emulator-5554"));
+        assert_eq!(record.steps, 4);
+
+        // 按 Agent ID 查询最近的任务；没有结束记录的任务视为中断
+        let latest = load_task_record(&dir, "agent_1").unwrap().unwrap();
+        assert_eq!(latest.task_id, "agent_1_2");
+        assert_eq!(latest.state, TaskRecordState::Interrupted);
+
+        let stopped = load_task_record(&dir, "agent_1_0").unwrap().unwrap();
+        assert_eq!((stopped.state, stopped.steps), (TaskRecordState::Stopped, 2));
+        assert!(stopped.finished_at.is_some());
+
+        assert!(load_task_record(&dir, "agent_2").unwrap().is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -10,6 +10,11 @@ pub trait Device: Send + Sync {
     /// 获取设备名称
     fn name(&self) -> &str;
 
+    /// 连接时探测的设备能力，未探测时为 None
+    fn capabilities(&self) -> Option<crate::agent::executor::DeviceCapabilities> {
+        None
+    }
+
     /// 检查设备是否连接
     async fn is_connected(&self) -> bool;
 
@@ -32,6 +37,15 @@ pub trait Device: Send + Sync {
         duration_ms: u32,
     ) -> Result<(), AppError>;
 
+    /// 按屏幕像素坐标（与 [`Self::screen_size`] 同一坐标系）点击，不经过逻辑坐标换算
+    async fn tap_pixel(&self, x: u32, y: u32) -> Result<(), AppError>;
+
+    /// 按屏幕像素坐标滑动，不经过逻辑坐标换算
+    async fn swipe_pixel(&self, start: (u32, u32), end: (u32, u32), duration_ms: u32) -> Result<(), AppError>;
+
+    /// 沿折线轨迹滑动（按下、依次移动、抬起），`points` 至少包含起点和终点
+    async fn swipe_path(&self, points: &[(u32, u32)], duration_ms: u32) -> Result<(), AppError>;
+
     /// 发送长按事件
     async fn long_press(&self, x: u32, y: u32, duration_ms: u32) -> Result<(), AppError>;
 
@@ -56,6 +70,33 @@ pub trait Device: Send + Sync {
     /// 打开通知栏
     async fn notification(&self) -> Result<(), AppError>;
 
+    /// 列出已启用的输入法 ID
+    async fn list_input_methods(&self) -> Result<Vec<String>, AppError>;
+
+    /// 获取当前默认输入法 ID
+    async fn current_input_method(&self) -> Result<Option<String>, AppError>;
+
+    /// 启用并切换到指定输入法
+    async fn set_input_method(&self, ime_id: &str) -> Result<(), AppError>;
+
+    /// 设置模拟定位
+    async fn set_mock_location(&self, location: &crate::agent::executor::GeoLocation) -> Result<(), AppError>;
+
+    /// 停止模拟定位
+    async fn clear_mock_location(&self) -> Result<(), AppError>;
+
+    /// 读取当前通知列表
+    async fn list_notifications(&self) -> Result<Vec<crate::agent::executor::NotificationInfo>, AppError>;
+
+    /// 获取当前界面的 uiautomator dump（XML）
+    async fn ui_dump(&self) -> Result<String, AppError>;
+
+    /// 展开通知栏并点击标题或正文包含 `text` 的通知
+    async fn tap_notification(&self, text: &str) -> Result<(), AppError>;
+
+    /// 清除所有可清除的通知
+    async fn clear_notifications(&self) -> Result<(), AppError>;
+
     /// 启动应用
     async fn launch_app(&self, package: &str) -> Result<(), AppError>;
 
@@ -160,6 +201,12 @@ pub enum AgentError {
     #[error("Agent 已在运行")]
     AlreadyRunning,
 
+    #[error("容量已满: {0}")]
+    CapacityExceeded(String),
+
+    #[error("设备池正在排空，暂不接受新任务")]
+    Draining,
+
     #[error("Agent 未运行")]
     NotRunning,
 
@@ -200,6 +247,11 @@ pub trait Agent: Send + Sync {
     /// 获取执行历史
     async fn history(&self) -> Vec<ExecutionStep>;
 
+    /// 分页、过滤查询执行历史，默认基于完整历史实现
+    async fn history_page(&self, query: &super::history::HistoryQuery) -> super::history::HistoryPage {
+        query.apply(&self.history().await)
+    }
+
     /// 发送反馈给 agent
     async fn feedback(&self, feedback: AgentFeedback) -> Result<(), AppError>;
 }
@@ -214,8 +266,14 @@ pub enum AgentStatus {
         task: String,
         steps: usize,
         duration_ms: u64,
+        /// 评估模型对任务结果的判定（未配置评估模型时为 None）
+        evaluation: Option<crate::agent::llm::TaskEvaluation>,
+    },
+    Failed {
+        task: String,
+        reason: super::state::FailureReason,
+        error: String,
     },
-    Failed { task: String, error: String },
 }
 
 /// 单个执行步骤
@@ -226,7 +284,11 @@ pub struct ExecutionStep {
     pub action_description: String,
     pub result: ActionResult,
     pub timestamp: chrono::DateTime<chrono::Utc>,
-    pub screenshot: String,
+    /// 设备时区下的设备时间（按任务开始时测得的时钟偏差换算，读取设备时钟失败时为 None）
+    #[serde(default)]
+    pub device_timestamp: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// 执行前的截图（保存在磁盘上，需要时通过 `load` 读取）
+    pub screenshot: Option<super::screenshot_store::StoredScreenshot>,
     pub reasoning: String,
 }
 
@@ -242,14 +304,17 @@ pub enum AgentFeedback {
 #[async_trait]
 pub trait ModelClient: Send + Sync {
     /// 使用消息历史查询模型（支持多轮对话）
+    ///
+    /// `screen_size` 为设备屏幕分辨率（宽, 高），三阶段模式的执行提示词需要
     async fn query_with_messages(
         &self,
         messages: Vec<ChatMessage>,
         screenshot: Option<&str>,
+        screen_size: (u32, u32),
     ) -> Result<ModelResponse, ModelError>;
 
-    /// 设置日志记录器
-    fn set_logger(&self, logger: Option<std::sync::Arc<crate::agent::logger::AgentLogger>>);
+    /// 设置日志记录器（默认不记录模型交互）
+    fn set_logger(&self, _logger: Option<std::sync::Arc<crate::agent::logger::AgentLogger>>) {}
 
     /// 检查是否支持三阶段模式
     fn supports_three_stage(&self) -> bool {
@@ -258,32 +323,66 @@ pub trait ModelClient: Send + Sync {
 
     /// 获取模型信息
     fn info(&self) -> ModelInfo;
-}
 
-/// 聊天消息（用于多轮对话）
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct ChatMessage {
-    pub role: MessageRole,
-    pub content: String,
+    /// 模型能力，默认由 [`Self::info`] 与 [`Self::supports_three_stage`] 组合
+    fn capabilities(&self) -> ModelCapabilities {
+        ModelCapabilities {
+            vision: self.info().supports_vision,
+            three_stage: self.supports_three_stage(),
+        }
+    }
 }
 
-/// 消息角色
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum MessageRole {
-    System,
-    User,
-    Assistant,
-}
+pub use super::message::{ChatMessage, MessageRole};
 
 /// 模型响应
 #[derive(Debug, Clone)]
 pub struct ModelResponse {
     pub content: String,
+    /// 按顺序执行的操作，一次回复可以包含多个
     pub actions: Vec<crate::agent::actions::base::ActionEnum>,
     pub confidence: f32,
+    /// 模型的思考内容
     pub reasoning: Option<String>,
     pub tokens_used: u32,
+    /// 本次查询的性能指标
+    pub metrics: PerformanceMetrics,
+    /// 请求体超出限制时的裁剪情况
+    pub payload_trim: Option<crate::agent::llm::payload_guard::PayloadTrim>,
+}
+
+/// 模型查询性能指标
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct PerformanceMetrics {
+    /// 首个 token 时间（秒，仅流式响应）
+    pub time_to_first_token: Option<f64>,
+    /// 思考结束时间（秒，仅流式响应）
+    pub time_to_thinking_end: Option<f64>,
+    /// 总推理时间（秒）
+    pub total_time: f64,
+    /// 各阶段耗时（三阶段模式为规划、执行、修正）
+    pub stages: Vec<StageTiming>,
+}
+
+/// 单个阶段的耗时
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct StageTiming {
+    /// 阶段名称（inference / planning / execution / correction）
+    pub stage: String,
+    /// 该阶段使用的模型
+    pub model: String,
+    pub duration_ms: u64,
+}
+
+impl PerformanceMetrics {
+    /// 记录一个阶段的耗时
+    pub fn add_stage(&mut self, stage: &str, model: &str, duration_ms: u64) {
+        self.stages.push(StageTiming {
+            stage: stage.to_string(),
+            model: model.to_string(),
+            duration_ms,
+        });
+    }
 }
 
 /// 从模型响应中解析出的操作
@@ -312,6 +411,9 @@ pub enum ModelError {
     #[error("网络错误: {0}")]
     NetworkError(String),
 
+    #[error("服务端错误 ({status}): {message}")]
+    ServerError { status: u16, message: String },
+
     #[error("超时")]
     Timeout,
 }
@@ -325,3 +427,12 @@ pub struct ModelInfo {
     pub max_tokens: u32,
     pub context_window: u32,
 }
+
+/// 模型能力，Agent 据此选择提示词与输入
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize)]
+pub struct ModelCapabilities {
+    /// 支持截图输入
+    pub vision: bool,
+    /// 支持三阶段模式（规划、执行、修正）
+    pub three_stage: bool,
+}
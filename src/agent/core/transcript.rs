@@ -0,0 +1,180 @@
+//! 任务对话记录
+//!
+//! 每次查询模型后，Agent 把本步新增的提示消息（相对上一步）与模型的原始回复写入 AgentLogger 日志
+//! （`conversation_step` 事件）。查看任务对话时按步骤顺序拼接，还原产生每一步操作的完整对话，
+//! 用于排查提示词问题。截图只记录 [`StoredScreenshot`] 引用，不写入图片数据。
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::message::{ChatMessage, MessageRole};
+use super::screenshot_store::StoredScreenshot;
+use crate::agent::actions::ActionEnum;
+use crate::agent::logger::read_task_events;
+
+/// 日志中对话步骤事件的名称
+pub const CONVERSATION_STEP_EVENT: &str = "conversation_step";
+
+/// 对话中的一条消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptMessage {
+    pub role: MessageRole,
+    pub text: String,
+    /// 随消息发送的截图
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<StoredScreenshot>,
+    /// 模型回复所在的步骤，提示消息为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<usize>,
+    /// 模型思考过程
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    /// 从模型回复中解析出的操作
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<ActionEnum>,
+}
+
+impl TranscriptMessage {
+    /// 提示消息（只记录文字，图片由调用方附加）
+    pub fn prompt(message: &ChatMessage) -> Self {
+        Self {
+            role: message.role,
+            text: message.text(),
+            images: Vec::new(),
+            step: None,
+            reasoning: None,
+            actions: Vec::new(),
+        }
+    }
+}
+
+/// 单步对话：本步新增的提示消息与模型回复
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationStep {
+    pub step: usize,
+    pub prompt: Vec<TranscriptMessage>,
+    pub response: TranscriptMessage,
+}
+
+impl ConversationStep {
+    /// 由发送给模型的新增消息、本步截图与模型回复组成，截图附加在最后一条用户消息上
+    pub fn new(
+        step: usize,
+        new_messages: &[ChatMessage],
+        screenshot: Option<StoredScreenshot>,
+        response: String,
+        reasoning: Option<String>,
+        actions: Vec<ActionEnum>,
+    ) -> Self {
+        let mut prompt: Vec<TranscriptMessage> = new_messages.iter().map(TranscriptMessage::prompt).collect();
+        if let Some(screenshot) = screenshot
+            && let Some(message) = prompt.iter_mut().rev().find(|m| m.role == MessageRole::User)
+        {
+            message.images.push(screenshot);
+        }
+        Self {
+            step,
+            prompt,
+            response: TranscriptMessage {
+                role: MessageRole::Assistant,
+                text: response,
+                images: Vec::new(),
+                step: Some(step),
+                reasoning,
+                actions,
+            },
+        }
+    }
+}
+
+/// 任务的完整对话
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskConversation {
+    pub task_id: String,
+    pub agent_id: String,
+    /// 模型查询次数
+    pub steps: usize,
+    pub messages: Vec<TranscriptMessage>,
+}
+
+impl TaskConversation {
+    /// 按步骤顺序拼接对话
+    pub fn assemble(task_id: &str, agent_id: &str, mut steps: Vec<ConversationStep>) -> Self {
+        steps.sort_by_key(|s| s.step);
+        let count = steps.len();
+        let messages = steps
+            .into_iter()
+            .flat_map(|s| s.prompt.into_iter().chain(std::iter::once(s.response)))
+            .collect();
+        Self {
+            task_id: task_id.to_string(),
+            agent_id: agent_id.to_string(),
+            steps: count,
+            messages,
+        }
+    }
+}
+
+/// 从日志目录中读取任务的对话，任务不存在或没有对话记录时返回 None
+pub fn load_task_conversation(log_dir: impl AsRef<Path>, task_id: &str) -> std::io::Result<Option<TaskConversation>> {
+    let mut agent_id = None;
+    let mut steps = Vec::new();
+    for value in read_task_events(log_dir, CONVERSATION_STEP_EVENT, task_id)? {
+        if let Ok(step) = serde_json::from_value::<ConversationStep>(value["conversation"].clone()) {
+            agent_id = value["agent_id"].as_str().map(str::to_string);
+            steps.push(step);
+        }
+    }
+    Ok(agent_id.map(|agent_id| TaskConversation::assemble(task_id, &agent_id, steps)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screenshot(hash: &str) -> StoredScreenshot {
+        StoredScreenshot { hash: hash.to_string(), path: format!("/tmp/{}.png", hash), thumbnail: None }
+    }
+
+    #[test]
+    fn test_step_attaches_screenshot_to_last_user_message() {
+        let messages = vec![ChatMessage::system("提示词"), ChatMessage::user("任务: 打开设置")];
+        let step = ConversationStep::new(0, &messages, Some(screenshot("aa")), "do(action=\"Back\")".to_string(), None, Vec::new());
+
+        assert!(step.prompt[0].images.is_empty());
+        assert_eq!(step.prompt[1].images, vec![screenshot("aa")]);
+        assert_eq!(step.response.role, MessageRole::Assistant);
+        assert_eq!(step.response.step, Some(0));
+    }
+
+    #[test]
+    fn test_load_task_conversation_orders_steps() {
+        let dir = std::env::temp_dir().join(format!("scrs_transcript_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let first = ConversationStep::new(0, &[ChatMessage::user("任务: 打开设置")], None, "点击设置".to_string(), None, Vec::new());
+        let second = ConversationStep::new(1, &[ChatMessage::user("操作成功")], Some(screenshot("bb")), "完成".to_string(), None, Vec::new());
+        let line = |task_id: &str, step: &ConversationStep| {
+            serde_json::json!({
+                "agent_id": "agent_1",
+                "task_id": task_id,
+                "event": CONVERSATION_STEP_EVENT,
+                "conversation": step,
+            })
+            .to_string()
+        };
+        let log = [line("task_1", &second), line("task_2", &first), line("task_1", &first)].join("\n");
+        std::fs::write(dir.join("agent_agent_1_2026-01-01.jsonl"), log).unwrap();
+
+        let conversation = load_task_conversation(&dir, "task_1").unwrap().unwrap();
+        assert_eq!(conversation.agent_id, "agent_1");
+        assert_eq!(conversation.steps, 2);
+        let texts: Vec<&str> = conversation.messages.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["任务: 打开设置", "点击设置", "操作成功", "完成"]);
+        assert_eq!(conversation.messages[2].images.len(), 1);
+        assert!(load_task_conversation(&dir, "task_3").unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
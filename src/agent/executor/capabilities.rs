@@ -0,0 +1,110 @@
+//! 设备能力探测
+//!
+//! 不同厂商、不同 Android 版本的 shell 工具差异很大：旧系统的 `input swipe` 不支持持续时间，
+//! `input motionevent` 要到 Android 11 才有，部分定制系统移除了 `wm` 或 `screenrecord`。
+//! 设备连接时执行一次探测，执行器据此选择操作方式，能力矩阵也在设备信息中返回，便于排查异常设备。
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// 探测命令超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 一次 adb shell 完成全部探测，各部分输出以标记行分隔
+const PROBE_SCRIPT: &str = "echo '[input]'; input 2>&1; \
+    echo '[uhid]'; test -w /dev/uhid && echo writable; \
+    echo '[wm]'; wm size 2>&1; \
+    echo '[screenrecord]'; command -v screenrecord";
+
+/// 设备能力矩阵
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceCapabilities {
+    /// `input swipe` 支持持续时间参数（不支持时无法通过滑动实现长按）
+    pub swipe_duration: bool,
+    /// 支持 `input motionevent`，可以逐点注入滑动轨迹
+    pub motion_event: bool,
+    /// `/dev/uhid` 可写，可以模拟 HID 键盘与鼠标
+    pub uhid: bool,
+    /// `wm size` 可以查询与覆盖屏幕分辨率
+    pub wm_size: bool,
+    /// 系统自带 `screenrecord`
+    pub screenrecord: bool,
+}
+
+impl DeviceCapabilities {
+    /// 解析探测脚本的输出
+    pub fn from_probe_output(output: &str) -> Self {
+        let section = |name: &str| {
+            let marker = format!("[{}]", name);
+            output
+                .split_once(marker.as_str())
+                .map(|(_, rest)| rest.split("\n[").next().unwrap_or_default())
+                .unwrap_or_default()
+        };
+        let input = section("input");
+        let swipe_usage = input.lines().find(|line| line.contains("swipe <"));
+        Self {
+            swipe_duration: swipe_usage.is_some_and(|line| line.contains("duration")),
+            motion_event: input.contains("motionevent"),
+            uhid: section("uhid").contains("writable"),
+            wm_size: section("wm").contains("Physical size:"),
+            screenrecord: section("screenrecord").contains("screenrecord"),
+        }
+    }
+
+    /// 探测设备能力
+    pub async fn probe(serial: &str) -> Result<Self, String> {
+        let command = crate::platform::adb_device_command(serial).args(["shell", PROBE_SCRIPT]).output();
+        let output = tokio::time::timeout(PROBE_TIMEOUT, command)
+            .await
+            .map_err(|_| "探测设备能力超时".to_string())?
+            .map_err(|e| format!("探测设备能力失败: {}", e))?;
+        Ok(Self::from_probe_output(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// 日志摘要，只列出缺少的能力
+    pub fn summary(&self) -> String {
+        let missing: Vec<&str> = [
+            (self.swipe_duration, "swipe_duration"),
+            (self.motion_event, "motion_event"),
+            (self.uhid, "uhid"),
+            (self.wm_size, "wm_size"),
+            (self.screenrecord, "screenrecord"),
+        ]
+        .into_iter()
+        .filter(|(supported, _)| !supported)
+        .map(|(_, name)| name)
+        .collect();
+        if missing.is_empty() {
+            "全部支持".to_string()
+        } else {
+            format!("不支持 {}", missing.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_probe_output() {
+        let modern = "[input]\nUsage: input [<source>] <command> [<arg>...]\n\
+            \x20     swipe <x1> <y1> <x2> <y2> [duration(ms)]\n\
+            \x20     motionevent <DOWN|UP|MOVE|CANCEL> <x> <y>\n\
+            [uhid]\nwritable\n[wm]\nPhysical size: 1080x2400\n[screenrecord]\n/system/bin/screenrecord\n";
+        let capabilities = DeviceCapabilities::from_probe_output(modern);
+        assert_eq!(
+            capabilities,
+            DeviceCapabilities { swipe_duration: true, motion_event: true, uhid: true, wm_size: true, screenrecord: true }
+        );
+        assert_eq!(capabilities.summary(), "全部支持");
+
+        let legacy = "[input]\nUsage: input [<source>] <command> [<arg>...]\n\
+            \x20     swipe <x1> <y1> <x2> <y2>\n[uhid]\n[wm]\n/system/bin/sh: wm: not found\n[screenrecord]\n";
+        let capabilities = DeviceCapabilities::from_probe_output(legacy);
+        assert_eq!(capabilities, DeviceCapabilities::default());
+        assert_eq!(capabilities.summary(), "不支持 swipe_duration, motion_event, uhid, wm_size, screenrecord");
+    }
+}
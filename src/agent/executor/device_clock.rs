@@ -0,0 +1,126 @@
+//! 设备时钟
+//!
+//! 任务开始时读取设备的时区与当前时间，计算与主机时钟的偏差。之后每个步骤同时记录主机 UTC 时间与换算到
+//! 设备时区的设备时间，方便与设备上的事件（通知、应用日志）对照。偏差超过 [`CLOCK_SKEW_WARN_SECS`] 时告警。
+
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::agent::executor::usage::adb_shell;
+use crate::error::AppError;
+
+/// 设备与主机时钟偏差超过该秒数时告警
+pub const CLOCK_SKEW_WARN_SECS: i64 = 5;
+
+/// 读取设备时间（Unix 秒与 UTC 偏移）和时区名称
+const CLOCK_COMMAND: &str = "date '+%s %z'; getprop persist.sys.timezone";
+
+/// 任务开始时的设备时钟
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceClock {
+    /// 时区名称（如 `Asia/Shanghai`），读取失败时为 None
+    pub timezone: Option<String>,
+    /// 相对 UTC 的偏移（秒）
+    pub utc_offset_secs: i32,
+    /// 设备时钟减去主机时钟（毫秒，精度受 adb 往返时间限制）
+    pub skew_ms: i64,
+    /// 读取时的设备本地时间
+    pub device_time: DateTime<FixedOffset>,
+    /// 读取时的主机时间
+    pub host_time: DateTime<Utc>,
+}
+
+impl DeviceClock {
+    /// 读取设备时钟
+    pub async fn read(device_serial: &str) -> Result<Self, AppError> {
+        let before = Utc::now();
+        let output = adb_shell(device_serial, CLOCK_COMMAND).await?;
+        let after = Utc::now();
+        // adb 往返的中点作为设备执行 date 的主机时间
+        let host_time = before + (after - before) / 2;
+        let clock = parse_device_clock(&output, host_time)?;
+        if clock.skew_exceeds(CLOCK_SKEW_WARN_SECS) {
+            warn!(
+                "设备 {} 时钟与主机相差 {:.1} 秒，设备时间戳可能与设备日志对不上",
+                device_serial,
+                clock.skew_ms as f64 / 1000.0
+            );
+        }
+        Ok(clock)
+    }
+
+    /// 主机时间对应的设备本地时间
+    pub fn device_time_at(&self, host_time: DateTime<Utc>) -> DateTime<FixedOffset> {
+        let offset = FixedOffset::east_opt(self.utc_offset_secs).unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        (host_time + Duration::milliseconds(self.skew_ms)).with_timezone(&offset)
+    }
+
+    /// 时钟偏差是否超过给定秒数
+    pub fn skew_exceeds(&self, secs: i64) -> bool {
+        self.skew_ms.abs() > secs * 1000
+    }
+}
+
+/// 解析 [`CLOCK_COMMAND`] 的输出，`host_time` 为设备执行命令时的主机时间
+pub fn parse_device_clock(output: &str, host_time: DateTime<Utc>) -> Result<DeviceClock, AppError> {
+    let mut lines = output.lines().map(str::trim).filter(|l| !l.is_empty());
+    let invalid = || AppError::AdbError(format!("解析设备时间失败: {}", output.trim()));
+
+    let (epoch, offset) = lines.next().and_then(|l| l.split_once(' ')).ok_or_else(invalid)?;
+    let epoch: i64 = epoch.parse().map_err(|_| invalid())?;
+    let utc_offset_secs = parse_utc_offset(offset).ok_or_else(invalid)?;
+    let timezone = lines.next().map(str::to_string);
+
+    let offset = FixedOffset::east_opt(utc_offset_secs).ok_or_else(invalid)?;
+    let device_time = DateTime::from_timestamp(epoch, 0).ok_or_else(invalid)?.with_timezone(&offset);
+    // 设备时间只精确到秒，取主机时间的整秒比较，避免把截断误差算作偏差
+    let host_secs = host_time.timestamp();
+    Ok(DeviceClock {
+        timezone,
+        utc_offset_secs,
+        skew_ms: (epoch - host_secs) * 1000,
+        device_time,
+        host_time,
+    })
+}
+
+/// 解析 `+0800` / `-0530` 形式的 UTC 偏移
+fn parse_utc_offset(offset: &str) -> Option<i32> {
+    let (sign, digits) = match offset.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    if digits.len() != 4 {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_device_clock() {
+        let host = DateTime::from_timestamp(1_760_000_000, 400_000_000).unwrap();
+        let clock = parse_device_clock("1760000012 +0800\nAsia/Shanghai\n", host).unwrap();
+
+        assert_eq!(clock.timezone.as_deref(), Some("Asia/Shanghai"));
+        assert_eq!(clock.utc_offset_secs, 8 * 3600);
+        assert_eq!(clock.skew_ms, 12_000);
+        assert!(clock.skew_exceeds(CLOCK_SKEW_WARN_SECS));
+
+        let later = host + Duration::seconds(60);
+        let device_time = clock.device_time_at(later);
+        assert_eq!(device_time.offset().local_minus_utc(), 8 * 3600);
+        assert_eq!(device_time.timestamp(), later.timestamp() + 12);
+
+        let clock = parse_device_clock("1760000000 -0530", host).unwrap();
+        assert_eq!((clock.utc_offset_secs, clock.timezone, clock.skew_ms), (-(5 * 3600 + 30 * 60), None, 0));
+        assert!(parse_device_clock("Thu Oct 15", host).is_err());
+    }
+}
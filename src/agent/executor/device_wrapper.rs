@@ -2,11 +2,19 @@ use async_trait::async_trait;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use crate::agent::core::traits::Device;
+use crate::agent::actions::input::{is_valid_ime_id, ADB_KEYBOARD_IME, ADB_KEYBOARD_INPUT_B64};
+use crate::agent::executor::capabilities::DeviceCapabilities;
+use crate::agent::executor::location::{self, GeoLocation};
+use crate::agent::executor::notifications::{find_node_center, parse_notification_dump, NotificationInfo};
 use crate::error::AppError;
+use crate::scrcpy::overlay::{to_logical, AgentActionOverlay, LOGICAL_SCALE};
 use crate::scrcpy::scrcpy::ScrcpyConnect;
 use adb_client::server_device::ADBServerDevice;
 use tracing::{debug, info, error, warn};
 
+/// SystemUI 通知栏"全部清除"按钮的常见文案（text 或 content-desc）
+const CLEAR_ALL_LABELS: &[&str] = &["全部清除", "清除全部", "清除所有通知", "Clear all", "Dismiss all"];
+
 /// Scrcpy 设备包装器，实现 Device trait
 /// 将现有的 ScrcpyConnect 和 ADB 功能封装成统一的接口
 pub struct ScrcpyDeviceWrapper {
@@ -18,6 +26,8 @@ pub struct ScrcpyDeviceWrapper {
     physical_resolution: Arc<RwLock<Option<(u32, u32)>>>,
     /// 渲染分辨率（应用看到的逻辑分辨率）
     override_resolution: Arc<RwLock<Option<(u32, u32)>>>,
+    /// 连接时探测的设备能力，未探测时按全部支持处理
+    capabilities: Option<DeviceCapabilities>,
 }
 
 impl ScrcpyDeviceWrapper {
@@ -35,9 +45,21 @@ impl ScrcpyDeviceWrapper {
             adb_device,
             physical_resolution: Arc::new(RwLock::new(None)),
             override_resolution: Arc::new(RwLock::new(None)),
+            capabilities: None,
         }
     }
 
+    /// 设置设备能力，执行器据此选择操作方式
+    pub fn with_capabilities(mut self, capabilities: Option<DeviceCapabilities>) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// 设备是否具备某项能力（未探测时视为具备）
+    fn supports(&self, capability: impl Fn(&DeviceCapabilities) -> bool) -> bool {
+        self.capabilities.as_ref().is_none_or(capability)
+    }
+
     /// 转换坐标：从 1000x1000 逻辑坐标转换为 override_resolution 坐标
     async fn convert_to_physical_coords(&self, logical_x: u32, logical_y: u32) -> Result<(u32, u32), AppError> {
         let override_res = self.override_resolution.read().await;
@@ -45,8 +67,8 @@ impl ScrcpyDeviceWrapper {
         match *override_res {
             Some((override_w, override_h)) => {
                 // 输入坐标基于 1000x1000，转换为 override_resolution
-                let physical_x = (logical_x as f64 * override_w as f64 / 1000.0) as u32;
-                let physical_y = (logical_y as f64 * override_h as f64 / 1000.0) as u32;
+                let physical_x = (logical_x as f64 * override_w as f64 / LOGICAL_SCALE as f64) as u32;
+                let physical_y = (logical_y as f64 * override_h as f64 / LOGICAL_SCALE as f64) as u32;
 
                 debug!("坐标转换: 1000x1000 的 ({}, {}) -> {}x{} 的 ({}, {})",
                     logical_x, logical_y, override_w, override_h, physical_x, physical_y);
@@ -61,12 +83,41 @@ impl ScrcpyDeviceWrapper {
         }
     }
 
+    /// 将像素坐标换算为 1000x1000 逻辑坐标，仅用于操作提示层；优先使用缓存的分辨率，都没有时读取屏幕尺寸
+    async fn overlay_coords(&self, points: &[(u32, u32)]) -> Option<Vec<(u32, u32)>> {
+        let cached = (*self.override_resolution.read().await).or(*self.physical_resolution.read().await);
+        let (width, height) = match cached {
+            Some(size) => size,
+            None => self.screen_size().await.ok()?,
+        };
+        Some(points.iter().map(|&(x, y)| (to_logical(x, width), to_logical(y, height))).collect())
+    }
+
     /// 刷新分辨率信息
     pub async fn refresh_resolution(&self) -> Result<(), AppError> {
+        if !self.supports(|c| c.wm_size) {
+            return self.refresh_resolution_from_dumpsys().await;
+        }
         let output = self.adb_shell("wm size").await?;
         self.parse_and_store_resolution(&output).await
     }
 
+    /// 没有 `wm` 的设备从 `dumpsys window displays` 的 `init=宽x高` 读取物理分辨率
+    async fn refresh_resolution_from_dumpsys(&self) -> Result<(), AppError> {
+        let output = self.adb_shell("dumpsys window displays").await?;
+        let size = output
+            .split_whitespace()
+            .find_map(|token| token.strip_prefix("init="))
+            .and_then(|size| size.split_once('x'))
+            .and_then(|(w, h)| Some((w.parse::<u32>().ok()?, h.trim_end_matches(|c: char| !c.is_ascii_digit()).parse::<u32>().ok()?)));
+        *self.override_resolution.write().await = None;
+        *self.physical_resolution.write().await = size;
+        if let Some((w, h)) = size {
+            info!("物理分辨率（dumpsys）: {}x{}", w, h);
+        }
+        Ok(())
+    }
+
     /// 解析并存储分辨率信息
     async fn parse_and_store_resolution(&self, output: &str) -> Result<(), AppError> {
         let mut physical = self.physical_resolution.write().await;
@@ -120,8 +171,8 @@ impl ScrcpyDeviceWrapper {
     async fn adb_shell(&self, command: &str) -> Result<String, AppError> {
         debug!("执行 ADB 命令: adb -s {} shell {}", self.serial, command);
 
-        let output = tokio::process::Command::new("adb")
-            .args(["-s", &self.serial, "shell", command])
+        let output = crate::platform::adb_device_command(&self.serial)
+            .args(["shell", command])
             .output()
             .await
             .map_err(|e| AppError::AdbError(format!("执行命令失败: {}", e)))?;
@@ -258,10 +309,14 @@ impl Device for ScrcpyDeviceWrapper {
         &self.name
     }
 
+    fn capabilities(&self) -> Option<DeviceCapabilities> {
+        self.capabilities
+    }
+
     async fn is_connected(&self) -> bool {
         // 检查设备是否仍在线
-        match tokio::process::Command::new("adb")
-            .args(["-s", &self.serial, "shell", "echo", "ping"])
+        match crate::platform::adb_device_command(&self.serial)
+            .args(["shell", "echo", "ping"])
             .output()
             .await
         {
@@ -274,10 +329,8 @@ impl Device for ScrcpyDeviceWrapper {
         debug!("截取设备屏幕: {}", self.serial);
 
         // 使用 ADB 截图并转换为 base64
-        let output = tokio::process::Command::new("adb")
+        let output = crate::platform::adb_device_command(&self.serial)
             .args([
-                "-s",
-                &self.serial,
                 "shell",
                 "screencap",
                 "-p",
@@ -324,14 +377,13 @@ impl Device for ScrcpyDeviceWrapper {
         use tracing::{debug, warn};
 
         debug!("执行点击: ({}, {})", x, y);
+        self.scrcpy_connect.emit_agent_action(AgentActionOverlay::tap(x, y));
 
         // 转换坐标：从逻辑坐标转换为物理坐标
         let (physical_x, physical_y) = self.convert_to_physical_coords(x, y).await?;
 
-        let output = tokio::process::Command::new("adb")
+        let output = crate::platform::adb_device_command(&self.serial)
             .args([
-                "-s",
-                &self.serial,
                 "shell",
                 "input",
                 "tap",
@@ -376,6 +428,28 @@ impl Device for ScrcpyDeviceWrapper {
         Ok(())
     }
 
+    async fn tap_pixel(&self, x: u32, y: u32) -> Result<(), AppError> {
+        debug!("执行像素坐标点击: ({}, {})", x, y);
+        if let Some([(overlay_x, overlay_y)]) = self.overlay_coords(&[(x, y)]).await.as_deref() {
+            self.scrcpy_connect.emit_agent_action(AgentActionOverlay::tap(*overlay_x, *overlay_y));
+        }
+        self.adb_shell(&format!("input tap {} {}", x, y)).await?;
+        Ok(())
+    }
+
+    async fn swipe_pixel(&self, start: (u32, u32), end: (u32, u32), duration_ms: u32) -> Result<(), AppError> {
+        debug!("执行像素坐标滑动: {:?} -> {:?} {}ms", start, end, duration_ms);
+        if let Some([overlay_start, overlay_end]) = self.overlay_coords(&[start, end]).await.as_deref() {
+            self.scrcpy_connect.emit_agent_action(AgentActionOverlay::swipe(*overlay_start, *overlay_end, duration_ms));
+        }
+        let mut command = format!("input swipe {} {} {} {}", start.0, start.1, end.0, end.1);
+        if self.supports(|c| c.swipe_duration) {
+            command.push_str(&format!(" {}", duration_ms));
+        }
+        self.adb_shell(&command).await?;
+        Ok(())
+    }
+
     async fn swipe(
         &self,
         start_x: u32,
@@ -390,24 +464,29 @@ impl Device for ScrcpyDeviceWrapper {
             "执行滑动: ({}, {}) -> ({}, {}) {}ms",
             start_x, start_y, end_x, end_y, duration_ms
         );
+        self.scrcpy_connect.emit_agent_action(AgentActionOverlay::swipe((start_x, start_y), (end_x, end_y), duration_ms));
 
         // 转换坐标：从逻辑坐标转换为物理坐标
         let (phys_start_x, phys_start_y) = self.convert_to_physical_coords(start_x, start_y).await?;
         let (phys_end_x, phys_end_y) = self.convert_to_physical_coords(end_x, end_y).await?;
 
-        let output = tokio::process::Command::new("adb")
-            .args([
-                "-s",
-                &self.serial,
-                "shell",
-                "input",
-                "swipe",
-                &phys_start_x.to_string(),
-                &phys_start_y.to_string(),
-                &phys_end_x.to_string(),
-                &phys_end_y.to_string(),
-                &duration_ms.to_string(),
-            ])
+        let mut args = vec![
+            "shell".to_string(),
+            "input".to_string(),
+            "swipe".to_string(),
+            phys_start_x.to_string(),
+            phys_start_y.to_string(),
+            phys_end_x.to_string(),
+            phys_end_y.to_string(),
+        ];
+        // 旧系统的 input swipe 不接受持续时间参数
+        if self.supports(|c| c.swipe_duration) {
+            args.push(duration_ms.to_string());
+        } else {
+            debug!("设备不支持滑动持续时间，忽略 {}ms", duration_ms);
+        }
+        let output = crate::platform::adb_device_command(&self.serial)
+            .args(&args)
             .output()
             .await
             .map_err(|e| AppError::AdbError(format!(
@@ -453,6 +532,45 @@ impl Device for ScrcpyDeviceWrapper {
         Ok(())
     }
 
+    async fn swipe_path(&self, points: &[(u32, u32)], duration_ms: u32) -> Result<(), AppError> {
+        let (Some(&(start_x, start_y)), Some(&(end_x, end_y))) = (points.first(), points.last()) else {
+            return Err(AppError::AdbError("滑动轨迹为空".to_string()));
+        };
+        // 不支持 input motionevent 的设备直接使用直线滑动
+        if points.len() <= 2 || !self.supports(|c| c.motion_event) {
+            return self.swipe(start_x, start_y, end_x, end_y, duration_ms).await;
+        }
+        debug!("执行轨迹滑动: {} 个点 {}ms", points.len(), duration_ms);
+
+        // input motionevent（Android 11+）逐点注入，每段之间按总时长等分停顿
+        let interval = duration_ms as f64 / 1000.0 / (points.len() - 1) as f64;
+        let mut commands = Vec::with_capacity(points.len() * 2);
+        for (index, &(x, y)) in points.iter().enumerate() {
+            let (px, py) = self.convert_to_physical_coords(x, y).await?;
+            let event = match index {
+                0 => "DOWN",
+                i if i == points.len() - 1 => "UP",
+                _ => "MOVE",
+            };
+            if index > 0 {
+                commands.push(format!("sleep {:.3}", interval));
+            }
+            commands.push(format!("input motionevent {} {} {}", event, px, py));
+        }
+
+        match self.adb_shell(&commands.join(" && ")).await {
+            Ok(output) if !output.contains("Error") && !output.contains("Unknown") => {
+                self.scrcpy_connect.emit_agent_action(AgentActionOverlay::swipe_path(points, duration_ms));
+                Ok(())
+            }
+            result => {
+                // 旧系统不支持 motionevent，退化为直线滑动
+                warn!("轨迹滑动不可用，改用直线滑动: {:?}", result);
+                self.swipe(start_x, start_y, end_x, end_y, duration_ms).await
+            }
+        }
+    }
+
     async fn long_press(&self, x: u32, y: u32, duration_ms: u32) -> Result<(), AppError> {
         debug!("执行长按: ({}, {}) {}ms", x, y, duration_ms);
 
@@ -473,6 +591,20 @@ impl Device for ScrcpyDeviceWrapper {
         use tracing::{debug, warn};
 
         debug!("输入文本: {}", text);
+        self.scrcpy_connect.emit_agent_action(AgentActionOverlay::text(text));
+
+        // 当前输入法为 ADBKeyboard 时通过广播输入，支持中文等非 ASCII 文本；查询失败时按普通输入处理
+        let ime = self.current_input_method().await.unwrap_or_else(|e| {
+            warn!("查询当前输入法失败，使用 input text 输入: {}", e);
+            None
+        });
+        if ime.as_deref() == Some(ADB_KEYBOARD_IME) {
+            use base64::Engine;
+            let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+            self.adb_shell(&format!("am broadcast -a {} --es msg {}", ADB_KEYBOARD_INPUT_B64, encoded))
+                .await?;
+            return Ok(());
+        }
 
         // 转义特殊字符
         let escaped_text = text
@@ -485,10 +617,8 @@ impl Device for ScrcpyDeviceWrapper {
             .replace('<', "\\<")
             .replace('>', "\\>");
 
-        let output = tokio::process::Command::new("adb")
+        let output = crate::platform::adb_device_command(&self.serial)
             .args([
-                "-s",
-                &self.serial,
                 "shell",
                 "input",
                 "text",
@@ -534,11 +664,10 @@ impl Device for ScrcpyDeviceWrapper {
 
     async fn press_key(&self, keycode: u32) -> Result<(), AppError> {
         debug!("按下按键: {}", keycode);
+        self.scrcpy_connect.emit_agent_action(AgentActionOverlay::key(keycode));
 
-        let output = tokio::process::Command::new("adb")
+        let output = crate::platform::adb_device_command(&self.serial)
             .args([
-                "-s",
-                &self.serial,
                 "shell",
                 "input",
                 "keyevent",
@@ -575,6 +704,88 @@ impl Device for ScrcpyDeviceWrapper {
         self.swipe(540, 0, 540, 500, 300).await // 从顶部向下滑动
     }
 
+    async fn list_input_methods(&self) -> Result<Vec<String>, AppError> {
+        debug!("列出输入法");
+        let output = self.adb_shell("ime list -s -a").await?;
+        Ok(output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    async fn current_input_method(&self) -> Result<Option<String>, AppError> {
+        let output = self.adb_shell("settings get secure default_input_method").await?;
+        let ime = output.trim();
+        Ok((!ime.is_empty() && ime != "null").then(|| ime.to_string()))
+    }
+
+    async fn set_input_method(&self, ime_id: &str) -> Result<(), AppError> {
+        if !is_valid_ime_id(ime_id) {
+            return Err(AppError::AdbError(format!("无效的输入法 ID: {:?}（格式: 包名/服务名）", ime_id)));
+        }
+        info!("切换输入法: {}", ime_id);
+        self.adb_shell(&format!("ime enable {}", ime_id)).await?;
+        let output = self.adb_shell(&format!("ime set {}", ime_id)).await?;
+        if output.contains("Unknown input method") || output.contains("Error") {
+            return Err(AppError::AdbError(format!("切换输入法失败: {}", output)));
+        }
+        Ok(())
+    }
+
+    async fn set_mock_location(&self, location: &GeoLocation) -> Result<(), AppError> {
+        location::set_mock_location(&self.serial, location).await
+    }
+
+    async fn clear_mock_location(&self) -> Result<(), AppError> {
+        location::clear_mock_location(&self.serial).await
+    }
+
+    async fn list_notifications(&self) -> Result<Vec<NotificationInfo>, AppError> {
+        debug!("读取通知列表");
+        let dump = self.adb_shell("dumpsys notification --noredact").await?;
+        Ok(parse_notification_dump(&dump))
+    }
+
+    async fn ui_dump(&self) -> Result<String, AppError> {
+        debug!("获取界面控件: {}", self.serial);
+        self.adb_shell("uiautomator dump /sdcard/window_dump.xml >/dev/null && cat /sdcard/window_dump.xml").await
+    }
+
+    async fn tap_notification(&self, text: &str) -> Result<(), AppError> {
+        debug!("点击通知: {}", text);
+
+        self.adb_shell("cmd statusbar expand-notifications").await?;
+        tokio::time::sleep(std::time::Duration::from_millis(600)).await;
+
+        let xml = self.ui_dump().await?;
+        let (x, y) = find_node_center(&xml, text)
+            .ok_or_else(|| AppError::AdbError(format!("通知栏中未找到包含 \"{}\" 的通知", text)))?;
+
+        // uiautomator 返回的是实际像素坐标，直接点击
+        self.adb_shell(&format!("input tap {} {}", x, y)).await?;
+        Ok(())
+    }
+
+    async fn clear_notifications(&self) -> Result<(), AppError> {
+        debug!("清除所有通知");
+        // service call 的 transaction code 随系统版本变化，改为展开通知栏后点击 SystemUI 的"全部清除"
+        self.adb_shell("cmd statusbar expand-notifications").await?;
+        tokio::time::sleep(std::time::Duration::from_millis(600)).await;
+
+        let xml = self.ui_dump().await?;
+        match CLEAR_ALL_LABELS.iter().find_map(|label| find_node_center(&xml, label)) {
+            Some((x, y)) => {
+                self.adb_shell(&format!("input tap {} {}", x, y)).await?;
+            }
+            // 没有可清除的通知时 SystemUI 不显示该按钮
+            None => debug!("通知栏中未找到清除按钮，视为无可清除通知"),
+        }
+        self.adb_shell("cmd statusbar collapse").await?;
+        Ok(())
+    }
+
     async fn launch_app(&self, package: &str) -> Result<(), AppError> {
         use tracing::{info, debug, warn, error};
 
@@ -589,10 +800,8 @@ impl Device for ScrcpyDeviceWrapper {
         );
         debug!("   执行命令: {}", cmd);
 
-        let output = tokio::process::Command::new("adb")
+        let output = crate::platform::adb_device_command(&self.serial)
             .args([
-                "-s",
-                &self.serial,
                 "shell",
                 "monkey",
                 "-p",
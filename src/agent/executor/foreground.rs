@@ -0,0 +1,137 @@
+//! 前台应用守护
+//!
+//! 任务可以声明目标应用。目标应用进入过前台之后，每步截图前检查前台应用：应用崩溃回到桌面、广告跳转到其它应用时，
+//! 把实际的前台应用告诉模型，避免模型在错误的应用里继续点击；开启自动重启时先重新启动目标应用。
+//! 权限弹窗、安装器等系统界面会短暂覆盖目标应用，不视为离开。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::agent::actions::system::app_name_to_package;
+use crate::agent::core::locale::Locale;
+use crate::agent::core::traits::Device;
+
+/// 会短暂覆盖目标应用的系统界面
+const TRANSIENT_PACKAGES: &[&str] = &[
+    "com.android.systemui",
+    "com.android.permissioncontroller",
+    "com.google.android.permissioncontroller",
+    "com.android.packageinstaller",
+    "com.google.android.packageinstaller",
+];
+
+/// 重新启动目标应用后等待界面加载的时间
+const RELAUNCH_SETTLE: Duration = Duration::from_secs(2);
+
+/// 一次离开目标应用的记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForegroundDrift {
+    /// 目标应用包名
+    pub target: String,
+    /// 实际的前台应用包名
+    pub foreground: String,
+    /// 是否已重新启动目标应用
+    pub relaunched: bool,
+}
+
+impl ForegroundDrift {
+    /// 追加到本步用户消息的说明
+    pub fn prompt_note(&self, locale: Locale) -> String {
+        match (locale, self.relaunched) {
+            (Locale::Zh, false) => format!(
+                "注意: 当前前台应用是 {}，不是任务的目标应用 {}（可能是应用崩溃或广告跳转），请先返回目标应用再继续操作。",
+                self.foreground, self.target
+            ),
+            (Locale::Zh, true) => format!(
+                "注意: 前台应用曾变为 {}（可能是应用崩溃或广告跳转），已重新启动目标应用 {}，请确认当前页面后继续操作。",
+                self.foreground, self.target
+            ),
+            (Locale::En, false) => format!(
+                "Note: the foreground app is {}, not the target app {} (it may have crashed or an ad redirected). Return to the target app before continuing.",
+                self.foreground, self.target
+            ),
+            (Locale::En, true) => format!(
+                "Note: the foreground app changed to {} (the target app may have crashed or an ad redirected). The target app {} was relaunched; check the current page before continuing.",
+                self.foreground, self.target
+            ),
+        }
+    }
+}
+
+/// 跨步骤的前台应用检查状态
+#[derive(Debug)]
+pub struct ForegroundGuard {
+    package: String,
+    relaunch: bool,
+    /// 目标应用是否进入过前台；之前（例如任务开始时在桌面）不检查
+    seen: bool,
+}
+
+impl ForegroundGuard {
+    /// `app` 为应用名称（如"哔哩哔哩"）或包名，`relaunch` 为离开目标应用时是否自动重新启动
+    pub fn new(app: &str, relaunch: bool) -> Result<Self, String> {
+        let package = app_name_to_package(app).ok_or_else(|| format!("未知的目标应用: {}", app))?;
+        Ok(Self { package, relaunch, seen: false })
+    }
+
+    /// 目标应用包名
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    /// 根据前台应用判断是否离开了目标应用，离开时返回前台应用包名
+    pub fn observe(&mut self, foreground: &str) -> Option<String> {
+        if foreground == self.package {
+            self.seen = true;
+            return None;
+        }
+        if !self.seen || foreground.is_empty() || TRANSIENT_PACKAGES.contains(&foreground) {
+            return None;
+        }
+        Some(foreground.to_string())
+    }
+
+    /// 检查前台应用，离开目标应用时按配置重新启动；获取前台应用失败时不处理
+    pub async fn check(&mut self, device: &Arc<dyn Device>) -> Option<ForegroundDrift> {
+        let foreground = match device.current_app().await {
+            Ok(package) => package,
+            Err(e) => {
+                warn!("前台应用检查获取前台应用失败: {}", e);
+                return None;
+            }
+        };
+        let foreground = self.observe(foreground.trim())?;
+
+        let mut relaunched = false;
+        if self.relaunch {
+            match device.launch_app(&self.package).await {
+                Ok(()) => {
+                    tokio::time::sleep(RELAUNCH_SETTLE).await;
+                    relaunched = true;
+                }
+                Err(e) => warn!("重新启动目标应用 {} 失败: {}", self.package, e),
+            }
+        }
+        Some(ForegroundDrift { target: self.package.clone(), foreground, relaunched })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe_foreground() {
+        let mut guard = ForegroundGuard::new("com.corp.oa", false).unwrap();
+        // 目标应用进入前台之前不检查
+        assert_eq!(guard.observe("com.android.launcher3"), None);
+        assert_eq!(guard.observe("com.corp.oa"), None);
+        assert_eq!(guard.observe("com.android.permissioncontroller"), None);
+        assert_eq!(guard.observe("com.android.launcher3").as_deref(), Some("com.android.launcher3"));
+        assert_eq!(guard.observe("com.corp.oa"), None);
+
+        assert!(ForegroundGuard::new("不存在的应用", true).is_err());
+    }
+}
@@ -2,7 +2,11 @@ use std::sync::Arc;
 use crate::agent::core::traits::{Device, Action, ActionResult};
 use crate::agent::actions::ActionEnum;
 use crate::agent::core::traits::ParsedAction;
+use crate::agent::executor::humanize::{HumanizeOptions, HumanizedDevice};
+use crate::agent::executor::screen_check::{ScreenCheck, depends_on_screen};
+use crate::agent::executor::text_check::{self, TypeVerification};
 use crate::error::AppError;
+use tokio::sync::RwLock;
 use tracing::{debug, info, warn, error};
 
 /// 操作处理器，负责执行和调度操作
@@ -10,6 +14,12 @@ pub struct ActionHandler {
     device: Option<Arc<dyn Device>>,
     max_retries: u32,
     retry_delay_ms: u64,
+    /// 当前任务的拟人化选项
+    humanize: RwLock<Option<HumanizeOptions>>,
+    /// 批量操作之间的屏幕校验方式
+    screen_check: RwLock<ScreenCheck>,
+    /// Type 操作后的文本校验方式
+    type_verification: RwLock<TypeVerification>,
 }
 
 impl ActionHandler {
@@ -19,6 +29,9 @@ impl ActionHandler {
             device: Some(device),
             max_retries: 3,
             retry_delay_ms: 1000,
+            humanize: RwLock::new(None),
+            screen_check: RwLock::new(ScreenCheck::Off),
+            type_verification: RwLock::new(TypeVerification::Off),
         }
     }
 
@@ -34,6 +47,21 @@ impl ActionHandler {
         self
     }
 
+    /// 设置拟人化选项，None 表示关闭
+    pub async fn set_humanize(&self, options: Option<HumanizeOptions>) {
+        *self.humanize.write().await = options;
+    }
+
+    /// 设置批量操作之间的屏幕校验方式
+    pub async fn set_screen_check(&self, check: ScreenCheck) {
+        *self.screen_check.write().await = check;
+    }
+
+    /// 设置 Type 操作后的文本校验方式
+    pub async fn set_type_verification(&self, verification: TypeVerification) {
+        *self.type_verification.write().await = verification;
+    }
+
     /// 执行操作（带重试）
     pub async fn execute_with_retry(
         &self,
@@ -41,6 +69,10 @@ impl ActionHandler {
     ) -> Result<ActionResult, AppError> {
         let device = self.device.as_ref()
             .ok_or_else(|| AppError::Unknown("Device 未初始化".to_string()))?;
+        let device: Arc<dyn Device> = match self.humanize.read().await.clone() {
+            Some(options) => Arc::new(HumanizedDevice::new(Arc::clone(device), options)),
+            None => Arc::clone(device),
+        };
 
         let mut last_error = None;
 
@@ -110,8 +142,9 @@ impl ActionHandler {
     }
 
     /// 串行执行多个操作
-    /// 返回所有操作的执行结果列表
-    /// 即使某个操作失败，也会继续执行后续操作
+    /// 返回所有操作的执行结果列表（与操作一一对应）
+    /// 即使某个操作失败，也会继续执行后续操作；
+    /// 开启屏幕校验时，依赖坐标的操作执行前屏幕已与批量开始时不同，则放弃剩余操作并返回失败结果
     pub async fn execute_multiple_actions(
         &self,
         actions: &[ActionEnum],
@@ -120,7 +153,38 @@ impl ActionHandler {
 
         let mut results = Vec::with_capacity(actions.len());
 
+        let humanize = self.humanize.read().await.clone();
+
+        // 后续操作依赖坐标时才记录批量开始时的屏幕
+        let screen_check = *self.screen_check.read().await;
+        let baseline = match &self.device {
+            Some(device) if actions.iter().skip(1).any(depends_on_screen) => screen_check.capture(device).await,
+            _ => None,
+        };
+
         for (idx, action) in actions.iter().enumerate() {
+            if idx > 0
+                && depends_on_screen(action)
+                && let (Some(baseline), Some(device)) = (&baseline, &self.device)
+                && let Some(current) = screen_check.capture(device).await
+                && let Some(reason) = current.changed_from(baseline)
+            {
+                warn!("操作 #{} 执行前屏幕已变化（{}），放弃剩余 {} 个操作", idx + 1, reason, actions.len() - idx);
+                for skipped in &actions[idx..] {
+                    results.push(ActionResult::failure(
+                        format!("屏幕已变化（{}），未执行 {}，请根据当前屏幕重新决定", reason, skipped.action_type()),
+                        0,
+                    ));
+                }
+                break;
+            }
+
+            if let Some(options) = &humanize {
+                let delay = options.action_delay();
+                debug!("拟人化停顿 {}ms", delay.as_millis());
+                tokio::time::sleep(delay).await;
+            }
+
             info!("执行操作 {}/{}", idx + 1, actions.len());
             info!("  操作类型: {}", action.action_type());
             info!("  操作描述: {}", action.description());
@@ -140,6 +204,10 @@ impl ActionHandler {
             match self.execute_with_retry(action).await {
                 Ok(result) => {
                     info!("操作 #{} 执行成功: {}", idx + 1, result.message);
+                    let result = match action {
+                        ActionEnum::Type(type_action) => self.verify_typed_text(action, &type_action.text, result).await,
+                        _ => result,
+                    };
                     results.push(result);
                 }
                 Err(e) => {
@@ -158,6 +226,47 @@ impl ActionHandler {
         results
     }
 
+    /// 读回焦点输入框的文字校验 Type 的结果；严格模式下不一致时清空重新输入一次
+    async fn verify_typed_text(&self, action: &ActionEnum, expected: &str, mut result: ActionResult) -> ActionResult {
+        let verification = *self.type_verification.read().await;
+        let Some(device) = self.device.as_ref().filter(|_| verification != TypeVerification::Off) else {
+            return result;
+        };
+
+        let mut retyped = false;
+        loop {
+            let Some(field) = text_check::read_focused_field(device).await else {
+                debug!("没有找到焦点输入框，跳过输入校验");
+                return result;
+            };
+            if field.password {
+                debug!("焦点输入框为密码框，跳过输入校验");
+                return result;
+            }
+            let Some(mismatch) = text_check::check_typed_text(expected, &field.text) else {
+                if retyped {
+                    result.message = format!("{}（首次输入不一致，已清空重新输入）", result.message);
+                }
+                return result;
+            };
+            warn!("{}", mismatch);
+
+            if verification != TypeVerification::Strict || retyped {
+                let suffix = if retyped { "，清空重新输入后仍不一致" } else { "" };
+                return ActionResult::failure(format!("{}{}", mismatch, suffix), result.duration_ms);
+            }
+            info!("严格输入校验：清空输入框后重新输入");
+            retyped = true;
+            if let Err(e) = text_check::clear_focused_field(device, field.text.chars().count()).await {
+                return ActionResult::failure(format!("{}，清空输入框失败: {}", mismatch, e), result.duration_ms);
+            }
+            result = match self.execute_with_retry(action).await {
+                Ok(result) => result,
+                Err(e) => return ActionResult::failure(format!("{}，重新输入失败: {}", mismatch, e), result.duration_ms),
+            };
+        }
+    }
+
     /// 转换 Action 参数格式
     /// 将提示词中的参数格式转换为 Action 结构体需要的格式
     fn convert_action_params(
@@ -357,6 +466,9 @@ impl ActionHandler {
             device: None,
             max_retries: 3,
             retry_delay_ms: 1000,
+            humanize: RwLock::new(None),
+            screen_check: RwLock::new(ScreenCheck::Off),
+            type_verification: RwLock::new(TypeVerification::Off),
         }
     }
 }
@@ -0,0 +1,283 @@
+//! 输入拟人化
+//!
+//! 可选地为点击加入随机偏移、为滑动使用随机弧线轨迹与速度、在操作之间加入随机停顿，
+//! 避免固定坐标与固定节奏的输入被消费类应用的反自动化策略识别。
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+use crate::agent::core::traits::Device;
+use crate::agent::executor::{GeoLocation, NotificationInfo};
+use crate::error::AppError;
+
+/// 逻辑坐标上限（坐标系为 0-999）
+const MAX_COORD: f64 = 999.0;
+/// 弧线滑动的采样点数
+const SWIPE_PATH_POINTS: usize = 8;
+
+/// 拟人化选项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HumanizeOptions {
+    /// 点击位置随机偏移的最大半径（逻辑坐标，0 表示不偏移）
+    pub tap_offset: u32,
+    /// 滑动是否使用随机弧线轨迹
+    pub curved_swipes: bool,
+    /// 弧线最大偏离量，占滑动距离的比例
+    pub swipe_curvature: f64,
+    /// 滑动时长随机浮动比例（0.3 表示 ±30%）
+    pub swipe_speed_jitter: f64,
+    /// 操作前随机停顿的最小值（毫秒）
+    pub min_delay_ms: u64,
+    /// 操作前随机停顿的最大值（毫秒）
+    pub max_delay_ms: u64,
+}
+
+impl Default for HumanizeOptions {
+    fn default() -> Self {
+        Self {
+            tap_offset: 12,
+            curved_swipes: true,
+            swipe_curvature: 0.15,
+            swipe_speed_jitter: 0.3,
+            min_delay_ms: 300,
+            max_delay_ms: 1500,
+        }
+    }
+}
+
+/// [0, 1) 区间的随机数
+fn random_unit() -> f64 {
+    (Uuid::new_v4().as_u128() % 1_000_000) as f64 / 1_000_000.0
+}
+
+/// [-1, 1] 区间、集中在 0 附近的随机数（两个均匀分布之差）
+fn random_centered() -> f64 {
+    random_unit() - random_unit()
+}
+
+impl HumanizeOptions {
+    /// 对点击坐标加入随机偏移，越靠近目标中心概率越高
+    pub fn jitter_point(&self, x: u32, y: u32) -> (u32, u32) {
+        let radius = self.tap_offset as f64;
+        let jitter = |v: u32| (v as f64 + random_centered() * radius).round().clamp(0.0, MAX_COORD) as u32;
+        (jitter(x), jitter(y))
+    }
+
+    /// 随机浮动滑动时长
+    pub fn swipe_duration(&self, duration_ms: u32) -> u32 {
+        let factor = 1.0 + (random_unit() * 2.0 - 1.0) * self.swipe_speed_jitter.clamp(0.0, 0.9);
+        (duration_ms as f64 * factor).round().max(50.0) as u32
+    }
+
+    /// 生成从起点到终点的弧线轨迹（二次贝塞尔曲线，控制点在中垂线上随机偏离）
+    ///
+    /// 采样时两端密、中间疏，模拟手指先加速后减速。
+    pub fn swipe_path(&self, start: (u32, u32), end: (u32, u32)) -> Vec<(u32, u32)> {
+        let (sx, sy) = (start.0 as f64, start.1 as f64);
+        let (ex, ey) = (end.0 as f64, end.1 as f64);
+        let (dx, dy) = (ex - sx, ey - sy);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length < 1.0 {
+            return vec![start, end];
+        }
+
+        let offset = random_centered() * self.swipe_curvature * length;
+        let (cx, cy) = ((sx + ex) / 2.0 - dy / length * offset, (sy + ey) / 2.0 + dx / length * offset);
+
+        (0..SWIPE_PATH_POINTS)
+            .map(|i| {
+                let linear = i as f64 / (SWIPE_PATH_POINTS - 1) as f64;
+                let t = (1.0 - (linear * std::f64::consts::PI).cos()) / 2.0;
+                let x = (1.0 - t).powi(2) * sx + 2.0 * (1.0 - t) * t * cx + t * t * ex;
+                let y = (1.0 - t).powi(2) * sy + 2.0 * (1.0 - t) * t * cy + t * t * ey;
+                (x.round().clamp(0.0, MAX_COORD) as u32, y.round().clamp(0.0, MAX_COORD) as u32)
+            })
+            .collect()
+    }
+
+    /// 操作前的随机停顿
+    pub fn action_delay(&self) -> Duration {
+        let min = self.min_delay_ms.min(self.max_delay_ms);
+        let max = self.min_delay_ms.max(self.max_delay_ms);
+        Duration::from_millis(min + (random_unit() * (max - min) as f64) as u64)
+    }
+}
+
+/// 拟人化设备包装器，对触摸类操作应用 [`HumanizeOptions`]，其余操作直接转发
+pub struct HumanizedDevice {
+    inner: Arc<dyn Device>,
+    options: HumanizeOptions,
+}
+
+impl HumanizedDevice {
+    pub fn new(inner: Arc<dyn Device>, options: HumanizeOptions) -> Self {
+        Self { inner, options }
+    }
+}
+
+#[async_trait]
+impl Device for HumanizedDevice {
+    fn serial(&self) -> &str {
+        self.inner.serial()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.inner.is_connected().await
+    }
+
+    async fn screenshot(&self) -> Result<String, AppError> {
+        self.inner.screenshot().await
+    }
+
+    async fn screen_size(&self) -> Result<(u32, u32), AppError> {
+        self.inner.screen_size().await
+    }
+
+    async fn ui_dump(&self) -> Result<String, AppError> {
+        self.inner.ui_dump().await
+    }
+
+    async fn tap(&self, x: u32, y: u32) -> Result<(), AppError> {
+        let (x, y) = self.options.jitter_point(x, y);
+        self.inner.tap(x, y).await
+    }
+
+    async fn swipe(
+        &self,
+        start_x: u32,
+        start_y: u32,
+        end_x: u32,
+        end_y: u32,
+        duration_ms: u32,
+    ) -> Result<(), AppError> {
+        let start = self.options.jitter_point(start_x, start_y);
+        let end = self.options.jitter_point(end_x, end_y);
+        let duration_ms = self.options.swipe_duration(duration_ms);
+        if self.options.curved_swipes {
+            self.inner.swipe_path(&self.options.swipe_path(start, end), duration_ms).await
+        } else {
+            self.inner.swipe(start.0, start.1, end.0, end.1, duration_ms).await
+        }
+    }
+
+    // 像素坐标操作要求精确位置，不做抖动
+    async fn tap_pixel(&self, x: u32, y: u32) -> Result<(), AppError> {
+        self.inner.tap_pixel(x, y).await
+    }
+
+    async fn swipe_pixel(&self, start: (u32, u32), end: (u32, u32), duration_ms: u32) -> Result<(), AppError> {
+        self.inner.swipe_pixel(start, end, duration_ms).await
+    }
+
+    async fn swipe_path(&self, points: &[(u32, u32)], duration_ms: u32) -> Result<(), AppError> {
+        self.inner.swipe_path(points, self.options.swipe_duration(duration_ms)).await
+    }
+
+    async fn long_press(&self, x: u32, y: u32, duration_ms: u32) -> Result<(), AppError> {
+        let (x, y) = self.options.jitter_point(x, y);
+        self.inner.long_press(x, y, self.options.swipe_duration(duration_ms)).await
+    }
+
+    async fn double_tap(&self, x: u32, y: u32) -> Result<(), AppError> {
+        let (x, y) = self.options.jitter_point(x, y);
+        self.inner.double_tap(x, y).await
+    }
+
+    async fn input_text(&self, text: &str) -> Result<(), AppError> {
+        self.inner.input_text(text).await
+    }
+
+    async fn press_key(&self, keycode: u32) -> Result<(), AppError> {
+        self.inner.press_key(keycode).await
+    }
+
+    async fn back(&self) -> Result<(), AppError> {
+        self.inner.back().await
+    }
+
+    async fn home(&self) -> Result<(), AppError> {
+        self.inner.home().await
+    }
+
+    async fn recent(&self) -> Result<(), AppError> {
+        self.inner.recent().await
+    }
+
+    async fn notification(&self) -> Result<(), AppError> {
+        self.inner.notification().await
+    }
+
+    async fn list_input_methods(&self) -> Result<Vec<String>, AppError> {
+        self.inner.list_input_methods().await
+    }
+
+    async fn current_input_method(&self) -> Result<Option<String>, AppError> {
+        self.inner.current_input_method().await
+    }
+
+    async fn set_input_method(&self, ime_id: &str) -> Result<(), AppError> {
+        self.inner.set_input_method(ime_id).await
+    }
+
+    async fn set_mock_location(&self, location: &GeoLocation) -> Result<(), AppError> {
+        self.inner.set_mock_location(location).await
+    }
+
+    async fn clear_mock_location(&self) -> Result<(), AppError> {
+        self.inner.clear_mock_location().await
+    }
+
+    async fn list_notifications(&self) -> Result<Vec<NotificationInfo>, AppError> {
+        self.inner.list_notifications().await
+    }
+
+    async fn tap_notification(&self, text: &str) -> Result<(), AppError> {
+        self.inner.tap_notification(text).await
+    }
+
+    async fn clear_notifications(&self) -> Result<(), AppError> {
+        self.inner.clear_notifications().await
+    }
+
+    async fn launch_app(&self, package: &str) -> Result<(), AppError> {
+        self.inner.launch_app(package).await
+    }
+
+    async fn current_app(&self) -> Result<String, AppError> {
+        self.inner.current_app().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_humanize_bounds() {
+        let options = HumanizeOptions::default();
+        for _ in 0..50 {
+            let (x, y) = options.jitter_point(500, 995);
+            assert!(x.abs_diff(500) <= options.tap_offset && y >= 995 - options.tap_offset && y <= 999);
+
+            let duration = options.swipe_duration(500);
+            assert!((350..=650).contains(&duration));
+
+            let delay = options.action_delay().as_millis() as u64;
+            assert!((options.min_delay_ms..=options.max_delay_ms).contains(&delay));
+        }
+
+        let path = options.swipe_path((500, 800), (500, 200));
+        assert_eq!(path.len(), SWIPE_PATH_POINTS);
+        assert_eq!(path.first(), Some(&(500, 800)));
+        assert_eq!(path.last(), Some(&(500, 200)));
+        assert!(path.windows(2).all(|w| w[1].1 <= w[0].1));
+        assert_eq!(options.swipe_path((300, 300), (300, 300)), vec![(300, 300), (300, 300)]);
+    }
+}
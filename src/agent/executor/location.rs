@@ -0,0 +1,158 @@
+//! 模拟定位
+//!
+//! 模拟器通过 `adb emu geo fix` 直接设置 GPS；真机通过模拟定位应用
+//! （Appium Settings）注入位置，并使用 appops 授予其模拟定位权限。
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+use crate::error::AppError;
+
+/// 提供模拟定位的应用包名
+pub const MOCK_LOCATION_APP: &str = "io.appium.settings";
+/// 模拟定位应用中注入位置的服务
+const MOCK_LOCATION_SERVICE: &str = "io.appium.settings/.LocationService";
+
+/// GPS 坐标
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(default)]
+    pub altitude: Option<f64>,
+}
+
+impl GeoLocation {
+    /// 校验经纬度范围
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-90.0..=90.0).contains(&self.latitude) {
+            return Err(format!("纬度超出范围 [-90, 90]: {}", self.latitude));
+        }
+        if !(-180.0..=180.0).contains(&self.longitude) {
+            return Err(format!("经度超出范围 [-180, 180]: {}", self.longitude));
+        }
+        Ok(())
+    }
+
+    /// 模拟器控制台 `geo fix` 参数（经度在前）
+    fn geo_fix_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "geo".to_string(),
+            "fix".to_string(),
+            self.longitude.to_string(),
+            self.latitude.to_string(),
+        ];
+        if let Some(altitude) = self.altitude {
+            args.push(altitude.to_string());
+        }
+        args
+    }
+
+    /// 启动模拟定位服务的 shell 命令
+    fn mock_service_command(&self) -> String {
+        let mut command = format!(
+            "am start-foreground-service --user 0 -n {} --es longitude {} --es latitude {}",
+            MOCK_LOCATION_SERVICE, self.longitude, self.latitude
+        );
+        if let Some(altitude) = self.altitude {
+            command.push_str(&format!(" --es altitude {}", altitude));
+        }
+        command
+    }
+}
+
+/// 是否为模拟器
+fn is_emulator(device_serial: &str) -> bool {
+    device_serial.starts_with("emulator-")
+}
+
+/// 执行 adb 命令并检查输出
+async fn adb(device_serial: &str, args: &[&str]) -> Result<String, AppError> {
+    debug!("执行 ADB 命令: adb -s {} {}", device_serial, args.join(" "));
+    let output = crate::platform::adb_device_command(device_serial)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| AppError::AdbError(format!("执行命令失败: {}", e)))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || stdout.contains("Error") || stdout.contains("KO") {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::AdbError(format!("命令执行失败: {} {}", stdout, stderr)));
+    }
+    Ok(stdout)
+}
+
+/// 设置模拟定位
+pub async fn set_mock_location(device_serial: &str, location: &GeoLocation) -> Result<(), AppError> {
+    location.validate().map_err(AppError::AdbError)?;
+    info!("设备 {} 设置模拟定位: ({}, {})", device_serial, location.latitude, location.longitude);
+
+    if is_emulator(device_serial) {
+        let args = location.geo_fix_args();
+        let mut emu_args = vec!["emu"];
+        emu_args.extend(args.iter().map(String::as_str));
+        adb(device_serial, &emu_args).await?;
+        return Ok(());
+    }
+
+    let packages = adb(device_serial, &["shell", "pm", "list", "packages", MOCK_LOCATION_APP]).await?;
+    if !packages.lines().any(|line| line.trim() == format!("package:{}", MOCK_LOCATION_APP)) {
+        return Err(AppError::AdbError(format!(
+            "设备未安装模拟定位应用 {}，请先安装 Appium Settings",
+            MOCK_LOCATION_APP
+        )));
+    }
+
+    adb(
+        device_serial,
+        &["shell", "appops", "set", MOCK_LOCATION_APP, "android:mock_location", "allow"],
+    )
+    .await?;
+    adb(device_serial, &["shell", &location.mock_service_command()]).await?;
+    Ok(())
+}
+
+/// 停止模拟定位，恢复真实定位
+pub async fn clear_mock_location(device_serial: &str) -> Result<(), AppError> {
+    info!("设备 {} 停止模拟定位", device_serial);
+
+    // 模拟器没有真实 GPS，保持最后一次设置的位置
+    if is_emulator(device_serial) {
+        return Ok(());
+    }
+
+    adb(device_serial, &["shell", "am", "stopservice", MOCK_LOCATION_SERVICE]).await?;
+    adb(
+        device_serial,
+        &["shell", "appops", "set", MOCK_LOCATION_APP, "android:mock_location", "deny"],
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geo_location_commands() {
+        let location = GeoLocation { latitude: 39.9042, longitude: 116.4074, altitude: None };
+        assert!(location.validate().is_ok());
+        assert_eq!(location.geo_fix_args(), vec!["geo", "fix", "116.4074", "39.9042"]);
+        assert!(location.mock_service_command().ends_with("--es longitude 116.4074 --es latitude 39.9042"));
+
+        let (_, actions) = crate::agent::actions::ActionEnum::parse_from_response(
+            r#"do(action="Set Location", latitude=-33.8688, longitude=151.2093)"#,
+        );
+        match actions.as_slice() {
+            [crate::agent::actions::ActionEnum::SetLocation(action)] => {
+                assert_eq!(action.location.latitude, -33.8688);
+                assert_eq!(action.location.longitude, 151.2093);
+            }
+            other => panic!("unexpected actions: {:?}", other),
+        }
+
+        let invalid = GeoLocation { latitude: 91.0, longitude: 0.0, altitude: None };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -1,7 +1,35 @@
+pub mod capabilities;
+pub mod device_clock;
 pub mod device_wrapper;
+pub mod foreground;
 pub mod handler;
+pub mod humanize;
+pub mod location;
+pub mod notifications;
+pub mod observation;
 pub mod retry;
+pub mod screen_check;
+pub mod screen_record;
+pub mod text_check;
+pub mod traffic;
+pub mod transfer;
+pub mod ui_tree;
+pub mod usage;
 
+pub use capabilities::*;
+pub use device_clock::*;
 pub use device_wrapper::*;
+pub use foreground::*;
 pub use handler::*;
+pub use humanize::*;
+pub use location::*;
+pub use notifications::*;
+pub use observation::*;
 pub use retry::*;
+pub use screen_check::*;
+pub use screen_record::*;
+pub use text_check::*;
+pub use traffic::*;
+pub use transfer::*;
+pub use ui_tree::*;
+pub use usage::*;
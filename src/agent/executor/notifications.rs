@@ -0,0 +1,143 @@
+//! 通知与界面层级解析
+//!
+//! 解析 `dumpsys notification --noredact` 的输出为结构化通知列表，
+//! 以及在 `uiautomator dump` 的 XML 中按文本查找控件位置。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::ui_tree::{UiNode, UiTree};
+
+/// 设备上的一条通知
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationInfo {
+    /// 通知 key（系统内唯一）
+    pub key: String,
+    /// 发出通知的应用包名
+    pub package: String,
+    pub title: Option<String>,
+    pub text: Option<String>,
+}
+
+impl NotificationInfo {
+    /// 单行摘要，用于反馈给模型
+    pub fn summary(&self) -> String {
+        format!(
+            "[{}] {}: {}",
+            self.package,
+            self.title.as_deref().unwrap_or(""),
+            self.text.as_deref().unwrap_or("")
+        )
+    }
+}
+
+/// 提取 `key=value` 形式的字段值（到空白或行尾）
+fn field<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let start = line.find(name)? + name.len();
+    let rest = &line[start..];
+    Some(rest.split(|c: char| c.is_whitespace() || c == ':').next().unwrap_or(rest))
+}
+
+/// 提取 extras 中的字符串值，例如 `android.title=String (张三)`
+fn extra_value(line: &str) -> Option<String> {
+    let (_, value) = line.split_once('=')?;
+    let start = value.find('(')?;
+    let end = value.rfind(')')?;
+    let text = value.get(start + 1..end)?.trim();
+    (!text.is_empty() && text != "null").then(|| text.to_string())
+}
+
+/// 解析 `dumpsys notification --noredact` 的输出
+///
+/// 只保留带标题或正文的通知，按 key 去重。
+pub fn parse_notification_dump(dump: &str) -> Vec<NotificationInfo> {
+    let mut notifications: Vec<NotificationInfo> = Vec::new();
+    let mut current: Option<NotificationInfo> = None;
+
+    for line in dump.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("NotificationRecord(") {
+            notifications.extend(current.take());
+            current = match (field(trimmed, "pkg="), field(trimmed, "key=")) {
+                (Some(package), Some(key)) => Some(NotificationInfo {
+                    key: key.to_string(),
+                    package: package.to_string(),
+                    title: None,
+                    text: None,
+                }),
+                _ => None,
+            };
+            continue;
+        }
+
+        let Some(record) = current.as_mut() else {
+            continue;
+        };
+        if trimmed.starts_with("android.title=") && record.title.is_none() {
+            record.title = extra_value(trimmed);
+        } else if (trimmed.starts_with("android.text=") || trimmed.starts_with("android.bigText="))
+            && record.text.is_none()
+        {
+            record.text = extra_value(trimmed);
+        }
+    }
+    notifications.extend(current);
+
+    let mut seen = HashSet::new();
+    notifications
+        .into_iter()
+        .filter(|n| n.title.is_some() || n.text.is_some())
+        .filter(|n| seen.insert(n.key.clone()))
+        .collect()
+}
+
+/// 在 uiautomator dump 的 XML 中查找文本（或 content-desc）包含 `target` 的控件，
+/// 返回其中心点的像素坐标
+pub fn find_node_center(xml: &str, target: &str) -> Option<(u32, u32)> {
+    UiTree::parse(xml)
+        .descendants()
+        .into_iter()
+        .find(|node| [&node.text, &node.content_desc].iter().any(|value| !value.is_empty() && value.contains(target)))
+        .map(UiNode::center)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"
+Current Notification Manager state:
+  Notification List:
+    NotificationRecord(0x0e1f: pkg=com.tencent.mm user=UserHandle{0} id=40 tag=null importance=4 key=0|com.tencent.mm|40|null|10123: Notification(channel=message))
+      uid=10123 userId=0
+      extras={
+        android.title=String (张三)
+        android.text=String (晚上一起吃饭吗)
+      }
+    NotificationRecord(0x0a2b: pkg=android user=UserHandle{0} id=1 tag=null importance=1 key=0|android|1|null|1000: Notification(channel=system))
+      extras={
+        android.title=null
+      }
+    NotificationRecord(0x0e1f: pkg=com.tencent.mm user=UserHandle{0} id=40 tag=null importance=4 key=0|com.tencent.mm|40|null|10123: Notification(channel=message))
+      extras={
+        android.title=String (张三)
+      }
+"#;
+
+    #[test]
+    fn test_parse_notification_dump() {
+        let notifications = parse_notification_dump(DUMP);
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].package, "com.tencent.mm");
+        assert_eq!(notifications[0].key, "0|com.tencent.mm|40|null|10123");
+        assert_eq!(notifications[0].title.as_deref(), Some("张三"));
+        assert_eq!(notifications[0].text.as_deref(), Some("晚上一起吃饭吗"));
+    }
+
+    #[test]
+    fn test_find_node_center() {
+        let xml = r#"<hierarchy><node index="0" text="" bounds="[0,0][1080,2400]"><node index="1" text="张三" content-desc="" bounds="[100,200][500,300]" /></node></hierarchy>"#;
+        assert_eq!(find_node_center(xml, "张三"), Some((300, 250)));
+        assert_eq!(find_node_center(xml, "李四"), None);
+    }
+}
@@ -0,0 +1,171 @@
+//! 每步的屏幕观察
+//!
+//! 单次 screencap 失败不应让整个任务失败：先按退避重试；仍然失败时使用上一张成功的截图（在提示中标注为过期），
+//! 没有缓存截图时退化为只包含界面控件文字的 uiautomator dump。连续多步都拿不到截图时才判定任务失败。
+
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use crate::agent::core::locale::Locale;
+use crate::agent::core::traits::Device;
+use crate::agent::executor::retry::RetryStrategy;
+use crate::agent::executor::ui_tree::UiTree;
+use crate::scrcpy::overlay::to_logical;
+use crate::error::AppError;
+
+/// 控件摘要中最多列出的控件数
+const MAX_UI_NODES: usize = 60;
+
+/// 一步的屏幕观察结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Observation {
+    /// 本步成功截取的屏幕
+    Fresh(String),
+    /// 截图失败，使用上一张成功的截图
+    Stale { screenshot: String, age: Duration, error: String },
+    /// 截图失败且没有缓存截图，只有界面控件摘要
+    UiDump { summary: String, error: String },
+}
+
+impl Observation {
+    /// 发送给模型的截图
+    pub fn screenshot(&self) -> Option<&str> {
+        match self {
+            Observation::Fresh(screenshot) | Observation::Stale { screenshot, .. } => Some(screenshot),
+            Observation::UiDump { .. } => None,
+        }
+    }
+
+    /// 附加到本步提示中的说明，截图正常时为 None
+    pub fn prompt_note(&self, locale: Locale) -> Option<String> {
+        match (self, locale) {
+            (Observation::Fresh(_), _) => None,
+            (Observation::Stale { age, .. }, Locale::Zh) => Some(format!(
+                "注意: 本步截图失败，附带的是 {} 秒前的截图，屏幕可能已经变化，请谨慎操作（必要时先 Wait 或 Back）。",
+                age.as_secs()
+            )),
+            (Observation::Stale { age, .. }, Locale::En) => Some(format!(
+                "Note: the screenshot failed this step; the attached screenshot is {} seconds old and the screen may have changed. Act carefully (Wait or Back first if needed).",
+                age.as_secs()
+            )),
+            (Observation::UiDump { summary, .. }, Locale::Zh) => Some(format!(
+                "注意: 本步截图失败，没有可用的截图。以下是当前界面的控件（文字 @ 中心点坐标，范围 0~1000）：\n{}",
+                summary
+            )),
+            (Observation::UiDump { summary, .. }, Locale::En) => Some(format!(
+                "Note: the screenshot failed this step and no screenshot is available. These are the widgets on the current screen (text @ center coordinates, range 0~1000):\n{}",
+                summary
+            )),
+        }
+    }
+}
+
+/// 跨步骤的截图状态：缓存的最近一张截图与连续失败次数
+#[derive(Debug)]
+pub struct ScreenObserver {
+    retry: RetryStrategy,
+    /// 每步截图的最大尝试次数
+    attempts: u32,
+    /// 允许连续截图失败的步数
+    max_misses: u32,
+    last_good: Option<(String, Instant)>,
+    misses: u32,
+}
+
+impl ScreenObserver {
+    pub fn new(retries: u32, max_misses: u32) -> Self {
+        Self {
+            retry: RetryStrategy::exponential(300, 2000, 2.0),
+            attempts: retries + 1,
+            max_misses,
+            last_good: None,
+            misses: 0,
+        }
+    }
+
+    /// 截取本步屏幕，连续失败超过上限时返回错误；`screen_size` 用于把控件坐标换算为逻辑坐标
+    pub async fn observe(&mut self, device: &dyn Device, screen_size: (u32, u32)) -> Result<Observation, AppError> {
+        let mut last_error = None;
+        for attempt in 0..self.attempts {
+            if attempt > 0
+                && let Some(delay) = self.retry.next_delay(attempt - 1)
+            {
+                tokio::time::sleep(delay).await;
+            }
+            match device.screenshot().await {
+                Ok(screenshot) => {
+                    self.misses = 0;
+                    self.last_good = Some((screenshot.clone(), Instant::now()));
+                    return Ok(Observation::Fresh(screenshot));
+                }
+                Err(e) => {
+                    warn!("截图失败（第 {}/{} 次）: {}", attempt + 1, self.attempts, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        let error = last_error.map(|e| e.to_string()).unwrap_or_default();
+
+        self.misses += 1;
+        if self.misses > self.max_misses {
+            return Err(AppError::AdbError(format!("连续 {} 步截图失败: {}", self.misses, error)));
+        }
+        self.fallback(device, screen_size, error).await
+    }
+
+    async fn fallback(&self, device: &dyn Device, screen_size: (u32, u32), error: String) -> Result<Observation, AppError> {
+        if let Some((screenshot, taken_at)) = &self.last_good {
+            return Ok(Observation::Stale { screenshot: screenshot.clone(), age: taken_at.elapsed(), error });
+        }
+        match device.ui_dump().await {
+            Ok(xml) => Ok(Observation::UiDump { summary: summarize_ui_dump(&xml, screen_size), error }),
+            Err(e) => Err(AppError::AdbError(format!("截图失败: {}；获取界面控件也失败: {}", error, e))),
+        }
+    }
+}
+
+/// 把 uiautomator dump 压缩为每行一个控件：`文字 @ (x, y)`，只保留有文字或描述的控件，
+/// 像素坐标按 `screen_size` 换算为与 Agent 操作一致的 0~1000 逻辑坐标
+pub fn summarize_ui_dump(xml: &str, screen_size: (u32, u32)) -> String {
+    let (width, height) = screen_size;
+    let tree = UiTree::parse(xml);
+    let lines: Vec<String> = tree
+        .descendants()
+        .into_iter()
+        .filter_map(|node| {
+            let label = [&node.text, &node.content_desc].into_iter().map(|value| value.trim()).find(|value| !value.is_empty())?;
+            let (x, y) = node.center();
+            Some(format!("{} @ ({}, {})", label, to_logical(x, width), to_logical(y, height)))
+        })
+        .take(MAX_UI_NODES)
+        .collect();
+
+    if lines.is_empty() { "（界面上没有可识别的文字控件）".to_string() } else { lines.join("\n") }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_ui_dump() {
+        let xml = r#"<hierarchy><node text="" content-desc="" bounds="[0,0][1080,2400]"><node text="设置" bounds="[100,200][300,300]" /><node text="" content-desc="搜索" bounds="[900,100][1000,200]" /></node></hierarchy>"#;
+        assert_eq!(summarize_ui_dump(xml, (1000, 2000)), "设置 @ (200, 125)\n搜索 @ (950, 75)");
+        assert!(summarize_ui_dump("<hierarchy/>", (1000, 2000)).contains("没有可识别"));
+    }
+
+    #[test]
+    fn test_prompt_notes() {
+        assert!(Observation::Fresh("AAAA".to_string()).prompt_note(Locale::Zh).is_none());
+
+        let stale = Observation::Stale { screenshot: "AAAA".to_string(), age: Duration::from_secs(3), error: "timeout".to_string() };
+        assert_eq!(stale.screenshot(), Some("AAAA"));
+        assert!(stale.prompt_note(Locale::Zh).unwrap().contains("3 秒前"));
+        assert!(stale.prompt_note(Locale::En).unwrap().contains("3 seconds old"));
+
+        let dump = Observation::UiDump { summary: "设置 @ (200, 250)".to_string(), error: "timeout".to_string() };
+        assert!(dump.screenshot().is_none());
+        assert!(dump.prompt_note(Locale::Zh).unwrap().contains("设置 @ (200, 250)"));
+    }
+}
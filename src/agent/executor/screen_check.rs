@@ -0,0 +1,143 @@
+//! 批量操作之间的屏幕校验
+//!
+//! 模型一次给出多个操作时，后面操作的坐标都是按发出请求时的屏幕计算的。
+//! 如果前面的操作已经跳转到了其它页面，后面的点击就会落在错误的位置。
+//! 开启校验后，每个依赖坐标的后续操作执行前都与批量开始时的屏幕对比，屏幕发生变化则放弃剩余操作。
+
+use std::sync::Arc;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::agent::actions::ActionEnum;
+use crate::agent::core::traits::Device;
+
+/// 截图差异哈希的汉明距离超过该值时视为屏幕已变化（共 64 位）
+const HASH_CHANGE_THRESHOLD: u32 = 10;
+
+/// 批量操作之间的屏幕校验方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenCheck {
+    /// 不校验，依次执行所有操作
+    #[default]
+    Off,
+    /// 对比截图的感知哈希（弹出键盘等较大的变化也会被视为屏幕变化）
+    Screenshot,
+    /// 对比前台应用包名（只能发现跳转到其它应用）
+    ForegroundApp,
+}
+
+/// 屏幕特征
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScreenSignature {
+    /// 截图的差异哈希
+    Hash(u64),
+    /// 前台应用包名
+    ForegroundApp(String),
+}
+
+impl ScreenCheck {
+    /// 获取当前屏幕特征，关闭校验或获取失败时返回 None
+    pub async fn capture(&self, device: &Arc<dyn Device>) -> Option<ScreenSignature> {
+        match self {
+            ScreenCheck::Off => None,
+            ScreenCheck::Screenshot => match device.screenshot().await {
+                Ok(screenshot) => screen_hash(&screenshot).map(ScreenSignature::Hash),
+                Err(e) => {
+                    warn!("屏幕校验截图失败: {}", e);
+                    None
+                }
+            },
+            ScreenCheck::ForegroundApp => match device.current_app().await {
+                Ok(package) => Some(ScreenSignature::ForegroundApp(package)),
+                Err(e) => {
+                    warn!("屏幕校验获取前台应用失败: {}", e);
+                    None
+                }
+            },
+        }
+    }
+}
+
+impl ScreenSignature {
+    /// 与批量开始时的屏幕相比是否发生变化，变化时返回说明
+    pub fn changed_from(&self, baseline: &ScreenSignature) -> Option<String> {
+        match (baseline, self) {
+            (ScreenSignature::Hash(before), ScreenSignature::Hash(after)) => {
+                let distance = (before ^ after).count_ones();
+                (distance > HASH_CHANGE_THRESHOLD).then(|| format!("截图差异 {}/64", distance))
+            }
+            (ScreenSignature::ForegroundApp(before), ScreenSignature::ForegroundApp(after)) => {
+                (before != after).then(|| format!("前台应用从 {} 变为 {}", before, after))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 操作是否依赖屏幕坐标（屏幕变化后再执行会点错位置）
+pub fn depends_on_screen(action: &ActionEnum) -> bool {
+    match action {
+        ActionEnum::Tap(_) | ActionEnum::LongPress(_) | ActionEnum::DoubleTap(_) | ActionEnum::Swipe(_) => true,
+        ActionEnum::Scroll(scroll) => scroll.anchor.is_some(),
+        _ => false,
+    }
+}
+
+/// 计算 base64 截图的差异哈希（dHash）：缩小为 9x8 灰度图，比较相邻像素的明暗
+pub fn screen_hash(base64_data: &str) -> Option<u64> {
+    let data = crate::agent::core::screenshot_store::strip_data_url(base64_data);
+    let bytes = STANDARD.decode(data).ok()?;
+    let image = image::load_from_memory(&bytes).ok()?;
+    let small = image
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let bit = small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(bit);
+        }
+    }
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn png_base64(image: image::RgbImage) -> String {
+        let mut output = Cursor::new(Vec::new());
+        image::DynamicImage::ImageRgb8(image).write_to(&mut output, image::ImageFormat::Png).unwrap();
+        STANDARD.encode(output.into_inner())
+    }
+
+    #[test]
+    fn test_screen_change_detection() {
+        let gradient = |invert: bool| {
+            image::RgbImage::from_fn(360, 800, move |x, y| {
+                let v = (x * 255 / 360) as u8 / 2 + (y * 127 / 800) as u8;
+                let v = if invert { 255 - v } else { v };
+                image::Rgb([v, v, v])
+            })
+        };
+        let before = ScreenSignature::Hash(screen_hash(&png_base64(gradient(false))).unwrap());
+        let same = ScreenSignature::Hash(screen_hash(&png_base64(gradient(false))).unwrap());
+        let other = ScreenSignature::Hash(screen_hash(&png_base64(gradient(true))).unwrap());
+        assert!(same.changed_from(&before).is_none());
+        assert!(other.changed_from(&before).is_some());
+
+        let app = ScreenSignature::ForegroundApp("com.android.settings".to_string());
+        let moved = ScreenSignature::ForegroundApp("com.tencent.mm".to_string());
+        assert!(app.changed_from(&app.clone()).is_none());
+        assert_eq!(
+            moved.changed_from(&app).as_deref(),
+            Some("前台应用从 com.android.settings 变为 com.tencent.mm")
+        );
+    }
+}
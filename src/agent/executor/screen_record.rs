@@ -0,0 +1,158 @@
+//! 设备端屏幕录制
+//!
+//! 不希望在服务端处理 H.264 视频流的部署可以让设备自带的 `screenrecord` 录制任务过程：任务开始时在设备上
+//! 循环录制分段（`screenrecord` 单次最长 3 分钟，到时自动开始下一段），任务结束后停止录制、拉取全部分段到本地，
+//! 作为任务产物上传，并删除设备上的文件。
+
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::process::Child;
+use tracing::{debug, info, warn};
+
+use crate::error::AppError;
+
+/// 录像文件目录，每个任务一个子目录
+pub const RECORDING_DIR: &str = "logs/recordings";
+/// 设备上存放分段的目录（shell 用户可写）
+const DEVICE_DIR: &str = "/data/local/tmp";
+/// `screenrecord` 单段时长上限（秒）
+const SEGMENT_SECS: u32 = 180;
+/// 等待最后一段写完的超时
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 设备上分段文件名的前缀
+fn segment_prefix(task_id: &str) -> String {
+    let tag: String = task_id.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    format!("scrs_rec_{}", tag)
+}
+
+/// 循环录制分段的 shell 脚本，出现停止标记文件或录制失败时退出
+fn record_script(prefix: &str) -> String {
+    format!(
+        "rm -f {dir}/{prefix}.stop; i=0; while [ ! -f {dir}/{prefix}.stop ]; do \
+         screenrecord --time-limit {secs} {dir}/{prefix}_$i.mp4 || break; i=$((i+1)); done",
+        dir = DEVICE_DIR,
+        prefix = prefix,
+        secs = SEGMENT_SECS,
+    )
+}
+
+/// 解析 `ls` 列出的分段文件，按分段序号排序（`ls` 按字典序输出，`_10` 会排在 `_2` 之前）
+fn parse_segments(output: &str, prefix: &str) -> Vec<String> {
+    let mut segments: Vec<(u32, String)> = output
+        .split_whitespace()
+        .filter_map(|path| {
+            let name = path.rsplit('/').next()?;
+            let index = name.strip_prefix(prefix)?.strip_prefix('_')?.strip_suffix(".mp4")?.parse().ok()?;
+            Some((index, path.to_string()))
+        })
+        .collect();
+    segments.sort_by_key(|(index, _)| *index);
+    segments.into_iter().map(|(_, path)| path).collect()
+}
+
+/// 执行 adb 命令
+async fn adb(device_serial: &str, args: &[&str]) -> Result<String, AppError> {
+    debug!("执行 ADB 命令: adb -s {} {}", device_serial, args.join(" "));
+    let output = crate::platform::adb_device_command(device_serial)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| AppError::AdbError(format!("执行命令失败: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::AdbError(format!("命令执行失败: {}", stderr.trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// 一次任务的设备端录屏
+pub struct ScreenRecording {
+    device_serial: String,
+    task_id: String,
+    prefix: String,
+    /// 运行录制循环的 adb shell 进程
+    process: Child,
+}
+
+impl ScreenRecording {
+    /// 在设备上开始循环录制（调用方按设备能力矩阵确认设备支持 `screenrecord`）
+    pub async fn start(device_serial: &str, task_id: &str) -> Result<Self, AppError> {
+        let prefix = segment_prefix(task_id);
+        let process = crate::platform::adb_device_command(device_serial)
+            .args(["shell", &record_script(&prefix)])
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| AppError::AdbError(format!("启动屏幕录制失败: {}", e)))?;
+
+        info!("设备 {} 屏幕录制已启动", device_serial);
+        Ok(Self {
+            device_serial: device_serial.to_string(),
+            task_id: task_id.to_string(),
+            prefix,
+            process,
+        })
+    }
+
+    /// 停止录制，拉取全部分段到 `{RECORDING_DIR}/{task_id}/` 并删除设备上的文件，返回本地文件路径
+    pub async fn finish(mut self) -> Result<Vec<String>, AppError> {
+        let serial = self.device_serial.as_str();
+        // 先放置停止标记再中断当前分段，SIGINT 让 screenrecord 正常写完文件尾
+        let stop = format!("touch {dir}/{prefix}.stop; pkill -INT -f {prefix}_", dir = DEVICE_DIR, prefix = self.prefix);
+        if let Err(e) = adb(serial, &["shell", &stop]).await {
+            warn!("停止屏幕录制失败: {}", e);
+        }
+        if tokio::time::timeout(STOP_TIMEOUT, self.process.wait()).await.is_err() {
+            warn!("设备 {} 屏幕录制未在 {} 秒内结束", serial, STOP_TIMEOUT.as_secs());
+            let _ = self.process.kill().await;
+        }
+
+        let pattern = format!("{}/{}_*.mp4", DEVICE_DIR, self.prefix);
+        let listing = adb(serial, &["shell", &format!("ls {} 2>/dev/null", pattern)]).await.unwrap_or_default();
+        let segments = parse_segments(&listing, &self.prefix);
+
+        let dir = Path::new(RECORDING_DIR).join(&self.task_id);
+        tokio::fs::create_dir_all(&dir).await?;
+        let mut files = Vec::new();
+        for (index, segment) in segments.iter().enumerate() {
+            let local = dir.join(format!("segment_{}.mp4", index)).to_string_lossy().to_string();
+            match adb(serial, &["pull", segment, &local]).await {
+                Ok(_) => files.push(local),
+                Err(e) => warn!("拉取录像分段 {} 失败: {}", segment, e),
+            }
+        }
+
+        let cleanup = format!("rm -f {} {}/{}.stop", pattern, DEVICE_DIR, self.prefix);
+        if let Err(e) = adb(serial, &["shell", &cleanup]).await {
+            warn!("删除设备上的录像分段失败: {}", e);
+        }
+        info!("设备 {} 屏幕录制结束，共 {} 段", serial, files.len());
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments() {
+        let prefix = segment_prefix("agent_1:task-2");
+        assert_eq!(prefix, "scrs_rec_agent_1_task_2");
+        let script = record_script(&prefix);
+        assert!(script.contains("screenrecord --time-limit 180 /data/local/tmp/scrs_rec_agent_1_task_2_$i.mp4"));
+
+        let listing = format!(
+            "/data/local/tmp/{p}_0.mp4\n/data/local/tmp/{p}_10.mp4\n/data/local/tmp/{p}_2.mp4\n/data/local/tmp/{p}.stop\n/data/local/tmp/other_1.mp4",
+            p = prefix
+        );
+        let segments = parse_segments(&listing, &prefix);
+        assert_eq!(
+            segments,
+            [0, 2, 10].map(|i| format!("/data/local/tmp/{}_{}.mp4", prefix, i)).to_vec()
+        );
+        assert!(parse_segments("", &prefix).is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+//! 输入文本校验
+//!
+//! `input text` 与 ADBKeyboard 广播都不保证文字原样进入输入框：输入法自动纠正、联想替换或丢字都会让结果与模型预期不同，
+//! 而截图上很难看出来。Type 操作执行后从 uiautomator dump 中读取当前焦点输入框的文字并与预期对比，
+//! 不一致时让操作失败并说明差异，模型可以据此修正；严格模式下会先清空输入框重新输入一次。
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::ui_tree::UiTree;
+use crate::agent::core::traits::Device;
+use crate::error::AppError;
+
+/// KEYCODE_MOVE_END
+const KEYCODE_MOVE_END: u32 = 123;
+/// KEYCODE_DEL
+const KEYCODE_DEL: u32 = 67;
+/// 清空输入框时最多删除的字符数
+const MAX_CLEAR_CHARS: usize = 500;
+
+/// Type 操作后的文本校验方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TypeVerification {
+    /// 不校验
+    Off,
+    /// 校验，不一致时操作失败并把差异告诉模型
+    #[default]
+    Report,
+    /// 校验，不一致时清空输入框重新输入一次，仍不一致再失败
+    Strict,
+}
+
+/// 当前焦点输入框
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusedField {
+    pub text: String,
+    /// 密码框的文字被遮盖，无法校验
+    pub password: bool,
+}
+
+/// 在 uiautomator dump 的 XML 中查找获得焦点的控件
+pub fn focused_field(xml: &str) -> Option<FocusedField> {
+    UiTree::parse(xml)
+        .descendants()
+        .into_iter()
+        .find(|node| node.focused)
+        .map(|node| FocusedField { text: node.text.clone(), password: node.password })
+}
+
+/// 对比预期输入与输入框中的文字，不一致时返回说明
+///
+/// Type 会追加到输入框已有的内容之后，因此只要求输入框包含预期文字。
+pub fn check_typed_text(expected: &str, actual: &str) -> Option<String> {
+    if actual.contains(expected) {
+        return None;
+    }
+    let missing: Vec<String> = expected
+        .chars()
+        .filter(|c| !c.is_whitespace() && !actual.contains(*c))
+        .map(String::from)
+        .collect();
+    let detail = if missing.is_empty() {
+        "内容被修改，可能是输入法自动纠正或联想替换".to_string()
+    } else {
+        format!("缺少字符: {}", missing.join(" "))
+    };
+    Some(format!("输入校验未通过: 预期输入 \"{}\"，输入框中为 \"{}\"（{}）", expected, actual, detail))
+}
+
+/// 读取当前焦点输入框；没有焦点控件或读取失败时返回 None
+pub async fn read_focused_field(device: &Arc<dyn Device>) -> Option<FocusedField> {
+    match device.ui_dump().await {
+        Ok(xml) => focused_field(&xml),
+        Err(e) => {
+            warn!("输入校验获取界面控件失败: {}", e);
+            None
+        }
+    }
+}
+
+/// 将光标移到末尾并逐个删除 `len` 个字符
+pub async fn clear_focused_field(device: &Arc<dyn Device>, len: usize) -> Result<(), AppError> {
+    debug!("清空输入框: {} 个字符", len);
+    device.press_key(KEYCODE_MOVE_END).await?;
+    for _ in 0..len.min(MAX_CLEAR_CHARS) {
+        device.press_key(KEYCODE_DEL).await?;
+    }
+    Ok(())
+}
+
+pub(crate) fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#10;", "\n")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focused_field() {
+        let xml = r#"<hierarchy><node text="搜索" focused="false" password="false" /><node text="天气 &amp; 温度" class="android.widget.EditText" focused="true" password="false" /></hierarchy>"#;
+        assert_eq!(focused_field(xml), Some(FocusedField { text: "天气 & 温度".to_string(), password: false }));
+        assert!(focused_field(r#"<node text="••••" focused="true" password="true" />"#).unwrap().password);
+        assert_eq!(focused_field(r#"<node text="搜索" focused="false" />"#), None);
+    }
+
+    #[test]
+    fn test_check_typed_text() {
+        assert_eq!(check_typed_text("北京天气", "北京天气"), None);
+        // 追加到已有内容之后
+        assert_eq!(check_typed_text("天气", "北京天气"), None);
+
+        let missing = check_typed_text("hello world", "helo world").unwrap();
+        assert!(missing.contains("输入框中为 \"helo world\""), "{}", missing);
+        assert!(check_typed_text("teh", "tea").unwrap().contains("缺少字符: h"));
+        assert!(check_typed_text("teh", "the").unwrap().contains("自动纠正"));
+    }
+}
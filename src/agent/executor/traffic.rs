@@ -0,0 +1,574 @@
+//! 任务级网络流量记录
+//!
+//! 在本机启动一个 HTTP 代理并设置为设备的全局 HTTP 代理，任务运行期间记录每个请求的元数据，
+//! 结束后导出为 HAR 格式。设备位于本机 adb server 时代理只监听回环地址，通过 `adb reverse` 暴露给设备；
+//! 位于远程 adb server 时 `adb reverse` 的连接会落在远程主机上，改为监听通往该主机的本机网卡地址，由设备直接连接；
+//! 此时只接受来自设备与远程 adb 主机地址的连接，其它来源一律断开，避免成为局域网内的开放代理。
+//!
+//! 这不是中间人（MITM）代理：HTTPS 流量只记录 CONNECT 隧道的元数据（主机、端口、收发字节数与耗时），
+//! 不解密内容，也就没有 HTTPS 请求的 URL 路径、状态码与响应头。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
+use crate::error::AppError;
+
+/// 流量记录文件目录
+pub const TRAFFIC_DIR: &str = "logs/traffic";
+/// 请求头最大长度
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+/// 设备上表示“未设置代理”的值
+const NO_PROXY: &str = ":0";
+
+/// HAR 请求信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    /// 请求体字节数（CONNECT 隧道为设备发出的字节数）
+    pub body_size: i64,
+}
+
+/// HAR 响应内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    pub size: i64,
+    pub mime_type: String,
+}
+
+/// HAR 响应信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: u16,
+    pub http_version: String,
+    pub content: HarContent,
+    /// 响应体字节数（CONNECT 隧道为设备收到的字节数）
+    pub body_size: i64,
+}
+
+/// HAR 条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    pub started_date_time: DateTime<Utc>,
+    /// 总耗时（毫秒），任务结束时仍未关闭的连接为 -1
+    pub time: i64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+}
+
+/// HAR 创建者信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarCreator {
+    pub name: String,
+    pub version: String,
+}
+
+/// HAR 日志主体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarLog {
+    pub version: String,
+    pub creator: HarCreator,
+    pub entries: Vec<HarEntry>,
+}
+
+/// HAR 文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+impl Har {
+    pub fn new(entries: Vec<HarEntry>) -> Self {
+        Self {
+            log: HarLog {
+                version: "1.2".to_string(),
+                creator: HarCreator {
+                    name: env!("CARGO_PKG_NAME").to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                entries,
+            },
+        }
+    }
+
+    /// 保存到 `{TRAFFIC_DIR}/{name}.har`，返回文件路径
+    pub async fn save(&self, name: &str) -> Result<String, AppError> {
+        tokio::fs::create_dir_all(TRAFFIC_DIR).await?;
+        let path = Path::new(TRAFFIC_DIR).join(format!("{}.har", name));
+        tokio::fs::write(&path, serde_json::to_vec_pretty(self)?).await?;
+        Ok(path.to_string_lossy().to_string())
+    }
+}
+
+/// 代理收到的请求头
+#[derive(Debug, Clone, PartialEq)]
+struct ProxyRequest {
+    method: String,
+    target: String,
+    version: String,
+    headers: Vec<(String, String)>,
+}
+
+impl ProxyRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// 解析请求行与请求头
+fn parse_request_head(head: &str) -> Option<ProxyRequest> {
+    let mut lines = head.split("\r\n");
+    let mut parts = lines.next()?.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?.to_string();
+    let version = parts.next().unwrap_or("HTTP/1.1").to_string();
+
+    let headers = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    Some(ProxyRequest { method, target, version, headers })
+}
+
+/// 拆分 `host[:port]`
+fn split_host_port(authority: &str, default_port: u16) -> Option<(String, u16)> {
+    match authority.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => {
+            Some((host.trim_matches(['[', ']']).to_string(), port.parse().ok()?))
+        }
+        _ => Some((authority.to_string(), default_port)),
+    }
+    .filter(|(host, _)| !host.is_empty())
+}
+
+/// 解析普通 HTTP 请求的目标，返回 (主机, 端口, 路径)
+fn resolve_http_target(request: &ProxyRequest) -> Option<(String, u16, String)> {
+    let rest = request.target.strip_prefix("http://");
+    let (authority, path) = match rest {
+        Some(rest) => match rest.find('/') {
+            Some(idx) => (&rest[..idx], rest[idx..].to_string()),
+            None => (rest, "/".to_string()),
+        },
+        // 非绝对 URL 时回退到 Host 头
+        None => (request.header("Host")?, request.target.clone()),
+    };
+    let (host, port) = split_host_port(authority, 80)?;
+    Some((host, port, path))
+}
+
+/// 构造转发给上游的请求头：使用 origin-form，并强制每个连接只处理一个请求
+fn origin_request_head(request: &ProxyRequest, path: &str) -> String {
+    let mut head = format!("{} {} {}\r\n", request.method, path, request.version);
+    for (key, value) in &request.headers {
+        if key.eq_ignore_ascii_case("Proxy-Connection") || key.eq_ignore_ascii_case("Connection") {
+            continue;
+        }
+        head.push_str(&format!("{}: {}\r\n", key, value));
+    }
+    head.push_str("Connection: close\r\n\r\n");
+    head
+}
+
+/// 解析响应头，返回 (状态码, HTTP 版本, Content-Type)
+fn parse_response_head(head: &str) -> Option<(u16, String, Option<String>)> {
+    let mut lines = head.split("\r\n");
+    let mut parts = lines.next()?.split_whitespace();
+    let version = parts.next()?.to_string();
+    let status = parts.next()?.parse().ok()?;
+    let content_type = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("Content-Type"))
+        .map(|(_, value)| value.trim().to_string());
+    Some((status, version, content_type))
+}
+
+/// 读取直到头部结束，返回 (头部长度, 已读取的全部数据)
+async fn read_head(stream: &mut TcpStream) -> std::io::Result<Option<(usize, Vec<u8>)>> {
+    let mut buf = Vec::with_capacity(4096);
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            return Ok(Some((pos + 4, buf)));
+        }
+        if buf.len() > MAX_HEAD_SIZE {
+            return Ok(None);
+        }
+    }
+}
+
+/// 已记录的条目
+type Entries = Arc<Mutex<Vec<HarEntry>>>;
+
+/// 写入一条新记录，返回其下标
+async fn open_entry(entries: &Entries, method: &str, url: String, version: &str) -> usize {
+    let mut entries = entries.lock().await;
+    entries.push(HarEntry {
+        started_date_time: Utc::now(),
+        time: -1,
+        request: HarRequest {
+            method: method.to_string(),
+            url,
+            http_version: version.to_string(),
+            body_size: -1,
+        },
+        response: HarResponse {
+            status: 0,
+            http_version: version.to_string(),
+            content: HarContent { size: -1, mime_type: String::new() },
+            body_size: -1,
+        },
+    });
+    entries.len() - 1
+}
+
+/// 处理 CONNECT 隧道
+async fn handle_connect(mut client: TcpStream, request: ProxyRequest, entries: Entries) {
+    let started = Instant::now();
+    let url = format!("https://{}", request.target);
+    let index = open_entry(&entries, &request.method, url, &request.version).await;
+
+    let upstream = match split_host_port(&request.target, 443) {
+        Some((host, port)) => TcpStream::connect((host.as_str(), port)).await,
+        None => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "无效的 CONNECT 目标")),
+    };
+    let mut upstream = match upstream {
+        Ok(stream) => stream,
+        Err(e) => {
+            debug!("CONNECT {} 失败: {}", request.target, e);
+            let _ = client.write_all(b"HTTP/1.1 502 Bad Gateway\r\n\r\n").await;
+            let mut entries = entries.lock().await;
+            entries[index].response.status = 502;
+            entries[index].time = started.elapsed().as_millis() as i64;
+            return;
+        }
+    };
+
+    if client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n").await.is_err() {
+        return;
+    }
+    entries.lock().await[index].response.status = 200;
+
+    let (sent, received) = tokio::io::copy_bidirectional(&mut client, &mut upstream)
+        .await
+        .unwrap_or((0, 0));
+    let mut entries = entries.lock().await;
+    let entry = &mut entries[index];
+    entry.request.body_size = sent as i64;
+    entry.response.body_size = received as i64;
+    entry.response.content.size = received as i64;
+    entry.time = started.elapsed().as_millis() as i64;
+}
+
+/// 处理普通 HTTP 请求
+async fn handle_http(
+    mut client: TcpStream,
+    request: ProxyRequest,
+    body_prefix: Vec<u8>,
+    entries: Entries,
+) {
+    let started = Instant::now();
+    let Some((host, port, path)) = resolve_http_target(&request) else {
+        let _ = client.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+        return;
+    };
+    let authority = if port == 80 { host.clone() } else { format!("{}:{}", host, port) };
+    let url = format!("http://{}{}", authority, path);
+    let index = open_entry(&entries, &request.method, url, &request.version).await;
+
+    let mut upstream = match TcpStream::connect((host.as_str(), port)).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            debug!("连接 {}:{} 失败: {}", host, port, e);
+            let _ = client.write_all(b"HTTP/1.1 502 Bad Gateway\r\n\r\n").await;
+            let mut entries = entries.lock().await;
+            entries[index].response.status = 502;
+            entries[index].time = started.elapsed().as_millis() as i64;
+            return;
+        }
+    };
+
+    let head = origin_request_head(&request, &path);
+    if upstream.write_all(head.as_bytes()).await.is_err()
+        || upstream.write_all(&body_prefix).await.is_err()
+    {
+        return;
+    }
+
+    let (mut client_read, mut client_write) = client.into_split();
+    let (mut upstream_read, mut upstream_write) = upstream.into_split();
+
+    // 请求体：设备 -> 上游
+    let upload = tokio::spawn(async move {
+        let copied = tokio::io::copy(&mut client_read, &mut upstream_write).await.unwrap_or(0);
+        let _ = upstream_write.shutdown().await;
+        copied
+    });
+
+    // 响应：上游 -> 设备，先解析响应头
+    let mut response_head = Vec::new();
+    let mut header_len = None;
+    let mut chunk = [0u8; 4096];
+    while header_len.is_none() && response_head.len() <= MAX_HEAD_SIZE {
+        match upstream_read.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                response_head.extend_from_slice(&chunk[..n]);
+                header_len = response_head.windows(4).position(|w| w == b"\r\n\r\n").map(|p| p + 4);
+            }
+        }
+    }
+    let parsed = parse_response_head(&String::from_utf8_lossy(&response_head));
+    let _ = client_write.write_all(&response_head).await;
+    let rest = tokio::io::copy(&mut upstream_read, &mut client_write).await.unwrap_or(0);
+    let _ = client_write.shutdown().await;
+    upload.abort();
+    let uploaded = upload.await.unwrap_or(0);
+
+    let response_body = (response_head.len() - header_len.unwrap_or(response_head.len())) as u64 + rest;
+    let mut entries = entries.lock().await;
+    let entry = &mut entries[index];
+    entry.request.body_size = (body_prefix.len() as u64 + uploaded) as i64;
+    if let Some((status, version, content_type)) = parsed {
+        entry.response.status = status;
+        entry.response.http_version = version;
+        entry.response.content.mime_type = content_type.unwrap_or_default();
+    }
+    entry.response.body_size = response_body as i64;
+    entry.response.content.size = response_body as i64;
+    entry.time = started.elapsed().as_millis() as i64;
+}
+
+/// 处理单个代理连接
+async fn handle_connection(mut client: TcpStream, entries: Entries) {
+    let (head_len, buf) = match read_head(&mut client).await {
+        Ok(Some(head)) => head,
+        _ => return,
+    };
+    let Some(request) = parse_request_head(&String::from_utf8_lossy(&buf[..head_len])) else {
+        let _ = client.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+        return;
+    };
+
+    if request.method.eq_ignore_ascii_case("CONNECT") {
+        handle_connect(client, request, entries).await;
+    } else {
+        handle_http(client, request, buf[head_len..].to_vec(), entries).await;
+    }
+}
+
+/// 执行 adb 命令
+async fn adb(device_serial: &str, args: &[&str]) -> Result<String, AppError> {
+    debug!("执行 ADB 命令: adb -s {} {}", device_serial, args.join(" "));
+    let output = crate::platform::adb_device_command(device_serial)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| AppError::AdbError(format!("执行命令失败: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::AdbError(format!("命令执行失败: {}", stderr.trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// 代理的监听地址：本机 adb server 为回环地址（配合 `adb reverse`），远程 adb server 为通往该主机的本机网卡地址
+fn proxy_host(device_serial: &str) -> Result<IpAddr, AppError> {
+    let Some(remote) = crate::adb_server::server_for(device_serial) else {
+        return Ok(IpAddr::V4(Ipv4Addr::LOCALHOST));
+    };
+    // UDP connect 不发送数据，只按路由表选出本机出口地址
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.connect(remote.addr)?;
+    Ok(socket.local_addr()?.ip())
+}
+
+/// 远程 adb server 时允许连接代理的地址：远程 adb 主机、网络设备序列号中的地址，以及设备访问代理时使用的源地址
+async fn allowed_peers(device_serial: &str, proxy: IpAddr) -> Vec<IpAddr> {
+    let mut peers: Vec<IpAddr> = Vec::new();
+    if let Some(remote) = crate::adb_server::server_for(device_serial) {
+        peers.push(IpAddr::V4(*remote.addr.ip()));
+    }
+    if let Ok(addr) = device_serial.parse::<std::net::SocketAddr>() {
+        peers.push(addr.ip());
+    }
+    match adb(device_serial, &["shell", "ip", "route", "get", &proxy.to_string()]).await {
+        Ok(route) => peers.extend(route_source(&route)),
+        Err(e) => debug!("读取设备路由失败: {}", e),
+    }
+    peers
+}
+
+/// 从 `ip route get` 的输出中读取源地址（`src` 之后的字段）
+fn route_source(route: &str) -> Option<IpAddr> {
+    let mut fields = route.split_whitespace();
+    fields.find(|field| *field == "src")?;
+    fields.next()?.parse().ok()
+}
+
+/// 一次任务的流量记录
+pub struct TrafficCapture {
+    device_serial: String,
+    port: u16,
+    /// 是否通过 `adb reverse` 暴露代理（本机 adb server）
+    reverse: bool,
+    /// 任务开始前设备上的代理设置
+    previous_proxy: String,
+    entries: Entries,
+    server: JoinHandle<()>,
+}
+
+impl TrafficCapture {
+    /// 启动本地代理并将设备的全局 HTTP 代理指向它
+    pub async fn start(device_serial: &str) -> Result<Self, AppError> {
+        let host = proxy_host(device_serial)?;
+        let reverse = host.is_loopback();
+        let listener = TcpListener::bind((host, 0)).await?;
+        let port = listener.local_addr()?.port();
+        let entries: Entries = Arc::new(Mutex::new(Vec::new()));
+        let allowed = if reverse { Vec::new() } else { allowed_peers(device_serial, host).await };
+        let is_allowed = move |peer: IpAddr| {
+            let peer = peer.to_canonical();
+            if reverse { peer.is_loopback() } else { allowed.contains(&peer) }
+        };
+
+        let server_entries = Arc::clone(&entries);
+        let server = tokio::spawn(async move {
+            // 连接任务随 JoinSet 一起在记录结束时中止
+            let mut connections = JoinSet::new();
+            loop {
+                tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((_, peer)) if !is_allowed(peer.ip()) => {
+                            warn!("拒绝来自 {} 的代理连接", peer);
+                        }
+                        Ok((stream, _)) => {
+                            connections.spawn(handle_connection(stream, Arc::clone(&server_entries)));
+                        }
+                        Err(e) => {
+                            warn!("代理接受连接失败: {}", e);
+                            break;
+                        }
+                    },
+                    Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                }
+            }
+        });
+
+        let previous_proxy = match adb(device_serial, &["shell", "settings", "get", "global", "http_proxy"]).await {
+            Ok(value) if value != "null" && !value.is_empty() => value,
+            _ => NO_PROXY.to_string(),
+        };
+
+        let port_spec = format!("tcp:{}", port);
+        let proxy = format!("{}:{}", host, port);
+        let setup = async {
+            if reverse {
+                adb(device_serial, &["reverse", &port_spec, &port_spec]).await?;
+            }
+            adb(device_serial, &["shell", "settings", "put", "global", "http_proxy", &proxy]).await
+        };
+        if let Err(e) = setup.await {
+            server.abort();
+            if reverse {
+                let _ = adb(device_serial, &["reverse", "--remove", &port_spec]).await;
+            }
+            return Err(e);
+        }
+
+        info!("设备 {} 流量记录已启动，代理地址: {}", device_serial, proxy);
+        Ok(Self {
+            device_serial: device_serial.to_string(),
+            port,
+            reverse,
+            previous_proxy,
+            entries,
+            server,
+        })
+    }
+
+    /// 恢复设备代理设置，停止代理并返回记录结果
+    pub async fn finish(self) -> Har {
+        if let Err(e) = adb(
+            &self.device_serial,
+            &["shell", "settings", "put", "global", "http_proxy", &self.previous_proxy],
+        )
+        .await
+        {
+            warn!("恢复设备代理设置失败: {}", e);
+        }
+        let port_spec = format!("tcp:{}", self.port);
+        if self.reverse
+            && let Err(e) = adb(&self.device_serial, &["reverse", "--remove", &port_spec]).await
+        {
+            warn!("移除端口反向映射失败: {}", e);
+        }
+
+        self.server.abort();
+        let _ = self.server.await;
+
+        let entries = self.entries.lock().await.clone();
+        info!("设备 {} 流量记录结束，共 {} 条请求", self.device_serial, entries.len());
+        Har::new(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proxy_requests() {
+        let connect = parse_request_head("CONNECT api.example.com:443 HTTP/1.1\r\nHost: api.example.com:443\r\n\r\n").unwrap();
+        assert_eq!(connect.method, "CONNECT");
+        assert_eq!(split_host_port(&connect.target, 443), Some(("api.example.com".to_string(), 443)));
+
+        let get = parse_request_head(
+            "GET http://example.com:8080/a?b=1 HTTP/1.1\r\nHost: example.com:8080\r\nProxy-Connection: keep-alive\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(get.header("host"), Some("example.com:8080"));
+        let (host, port, path) = resolve_http_target(&get).unwrap();
+        assert_eq!((host.as_str(), port, path.as_str()), ("example.com", 8080, "/a?b=1"));
+        assert_eq!(
+            origin_request_head(&get, &path),
+            "GET /a?b=1 HTTP/1.1\r\nHost: example.com:8080\r\nConnection: close\r\n\r\n"
+        );
+
+        assert_eq!(
+            parse_response_head("HTTP/1.1 404 Not Found\r\ncontent-type: text/html\r\n\r\n"),
+            Some((404, "HTTP/1.1".to_string(), Some("text/html".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_route_source() {
+        let route = "192.168.1.10 via 192.168.1.1 dev wlan0 src 192.168.1.23 uid 0 \n    cache";
+        assert_eq!(route_source(route), Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 23))));
+        assert_eq!(route_source("RTNETLINK answers: Network is unreachable"), None);
+    }
+}
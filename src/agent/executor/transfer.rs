@@ -0,0 +1,161 @@
+//! 设备间传输
+//!
+//! 通过服务端中转在两台受管设备之间复制文件（先 pull 再 push）或剪贴板内容。
+//! 剪贴板读写依赖设备上安装的 Clipper 应用（`ca.zgrs.clipper`），
+//! Android 10 及以上系统只允许前台应用读取剪贴板，读取前会先将其拉到前台。
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::{debug, info};
+use uuid::Uuid;
+use crate::agent::executor::usage::adb_shell;
+use crate::error::AppError;
+
+/// 提供剪贴板读写广播的应用包名
+pub const CLIPBOARD_APP: &str = "ca.zgrs.clipper";
+/// 目标路径未指定时使用的目录
+const DEFAULT_TARGET_DIR: &str = "/sdcard/Download";
+
+/// 文件传输结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileTransfer {
+    pub from_device: String,
+    pub to_device: String,
+    pub source_path: String,
+    pub target_path: String,
+    pub bytes: u64,
+}
+
+/// 执行 adb 命令（非 shell）
+async fn adb(device_serial: &str, args: &[&str]) -> Result<String, AppError> {
+    debug!("执行 ADB 命令: adb -s {} {}", device_serial, args.join(" "));
+    let output = crate::platform::adb_device_command(device_serial)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| AppError::AdbError(format!("执行命令失败: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::AdbError(format!("命令执行失败: {}", stderr.trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// 目标路径：未指定时放到下载目录并沿用源文件名
+fn resolve_target_path(source_path: &str, target_path: Option<&str>) -> Result<String, AppError> {
+    if let Some(target) = target_path.filter(|t| !t.trim().is_empty()) {
+        return Ok(target.to_string());
+    }
+    let name = Path::new(source_path)
+        .file_name()
+        .ok_or_else(|| AppError::AdbError(format!("无法从源路径获取文件名: {}", source_path)))?;
+    Ok(format!("{}/{}", DEFAULT_TARGET_DIR, name.to_string_lossy()))
+}
+
+/// 将文件从一台设备复制到另一台设备
+pub async fn transfer_file(
+    from_device: &str,
+    to_device: &str,
+    source_path: &str,
+    target_path: Option<&str>,
+) -> Result<FileTransfer, AppError> {
+    if from_device == to_device {
+        return Err(AppError::AdbError("源设备与目标设备相同".to_string()));
+    }
+    let target_path = resolve_target_path(source_path, target_path)?;
+
+    let local = crate::platform::temp_dir().join(format!("transfer-{}", Uuid::new_v4()));
+    let local_str = local.to_string_lossy().to_string();
+    let result = async {
+        adb(from_device, &["pull", source_path, &local_str]).await?;
+        let bytes = tokio::fs::metadata(&local).await?.len();
+        adb(to_device, &["push", &local_str, &target_path]).await?;
+        Ok::<u64, AppError>(bytes)
+    }
+    .await;
+    let _ = tokio::fs::remove_file(&local).await;
+    let bytes = result?;
+
+    // 通知媒体库扫描，使相册、文件管理等应用能立即看到新文件
+    let _ = adb_shell(
+        to_device,
+        &format!("am broadcast -a android.intent.action.MEDIA_SCANNER_SCAN_FILE -d {}", shell_quote(&format!("file://{}", target_path))),
+    )
+    .await;
+
+    info!("已将 {}:{} 复制到 {}:{} ({} 字节)", from_device, source_path, to_device, target_path, bytes);
+    Ok(FileTransfer {
+        from_device: from_device.to_string(),
+        to_device: to_device.to_string(),
+        source_path: source_path.to_string(),
+        target_path,
+        bytes,
+    })
+}
+
+/// 使用单引号包裹 shell 参数
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// 从 `am broadcast` 的输出中提取结果数据
+fn parse_broadcast_data(output: &str) -> Option<String> {
+    let re = Regex::new(r#"(?s)data="(.*)""#).unwrap();
+    re.captures(output).map(|cap| cap[1].to_string())
+}
+
+/// 读取设备剪贴板
+pub async fn get_clipboard(device_serial: &str) -> Result<String, AppError> {
+    adb_shell(device_serial, &format!("am start -n {}/.Main >/dev/null", CLIPBOARD_APP)).await?;
+    let output = adb_shell(device_serial, "am broadcast -a clipper.get").await?;
+    parse_broadcast_data(&output).ok_or_else(|| {
+        AppError::AdbError(format!("读取剪贴板失败，请确认已安装 {}: {}", CLIPBOARD_APP, output.trim()))
+    })
+}
+
+/// 设置设备剪贴板
+pub async fn set_clipboard(device_serial: &str, text: &str) -> Result<(), AppError> {
+    let output = adb_shell(
+        device_serial,
+        &format!("am broadcast -a clipper.set -e text {}", shell_quote(text)),
+    )
+    .await?;
+    if !output.contains("result=-1") {
+        return Err(AppError::AdbError(format!(
+            "设置剪贴板失败，请确认已安装 {}: {}",
+            CLIPBOARD_APP,
+            output.trim()
+        )));
+    }
+    Ok(())
+}
+
+/// 将剪贴板内容从一台设备复制到另一台设备，返回复制的内容
+pub async fn transfer_clipboard(from_device: &str, to_device: &str) -> Result<String, AppError> {
+    let text = get_clipboard(from_device).await?;
+    set_clipboard(to_device, &text).await?;
+    info!("已将 {} 的剪贴板复制到 {} ({} 个字符)", from_device, to_device, text.chars().count());
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_helpers() {
+        assert_eq!(
+            resolve_target_path("/sdcard/DCIM/Camera/IMG_01.jpg", None).unwrap(),
+            "/sdcard/Download/IMG_01.jpg"
+        );
+        assert_eq!(resolve_target_path("/sdcard/a.txt", Some("/sdcard/b.txt")).unwrap(), "/sdcard/b.txt");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(
+            parse_broadcast_data("Broadcasting: Intent { act=clipper.get }\nBroadcast completed: result=-1, data=\"验证码 \"1234\"\"\n"),
+            Some("验证码 \"1234\"".to_string())
+        );
+        assert_eq!(parse_broadcast_data("Broadcast completed: result=0"), None);
+    }
+}
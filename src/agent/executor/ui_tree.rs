@@ -0,0 +1,294 @@
+//! 界面层级解析与查询
+//!
+//! 将 `uiautomator dump` 的 XML 解析为控件树，并支持类似 XPath 的简单选择器，
+//! 外部工具可以据此定位控件并计算点击坐标：
+//!
+//! - `//*[@resource-id='com.android.settings:id/search']`：属性等于
+//! - `//*[contains(@text,'蓝牙')]`：属性包含
+//! - `//android.widget.Button[@clickable='true'][contains(@text,'确定')]`：按类名匹配，多个条件同时满足
+
+use regex::Regex;
+use serde::Serialize;
+
+use super::text_check::unescape_xml;
+use crate::scrcpy::overlay::to_logical;
+
+/// 界面控件
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UiNode {
+    pub class: String,
+    pub text: String,
+    pub resource_id: String,
+    pub content_desc: String,
+    pub package: String,
+    /// 屏幕像素坐标 `[left, top, right, bottom]`
+    pub bounds: [u32; 4],
+    pub clickable: bool,
+    pub enabled: bool,
+    pub focused: bool,
+    pub scrollable: bool,
+    pub checked: bool,
+    pub selected: bool,
+    pub password: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<UiNode>,
+}
+
+impl UiNode {
+    /// 中心点的像素坐标
+    pub fn center(&self) -> (u32, u32) {
+        let [left, top, right, bottom] = self.bounds;
+        ((left + right) / 2, (top + bottom) / 2)
+    }
+
+    /// 按 uiautomator 的属性名读取属性值
+    pub fn attr(&self, name: &str) -> Option<String> {
+        let flag = |value: bool| Some(value.to_string());
+        match name {
+            "class" => Some(self.class.clone()),
+            "text" => Some(self.text.clone()),
+            "resource-id" => Some(self.resource_id.clone()),
+            "content-desc" => Some(self.content_desc.clone()),
+            "package" => Some(self.package.clone()),
+            "bounds" => {
+                let [left, top, right, bottom] = self.bounds;
+                Some(format!("[{},{}][{},{}]", left, top, right, bottom))
+            }
+            "clickable" => flag(self.clickable),
+            "enabled" => flag(self.enabled),
+            "focused" => flag(self.focused),
+            "scrollable" => flag(self.scrollable),
+            "checked" => flag(self.checked),
+            "selected" => flag(self.selected),
+            "password" => flag(self.password),
+            _ => None,
+        }
+    }
+
+    /// 深度优先遍历自身与全部子控件
+    fn walk<'a>(&'a self, out: &mut Vec<&'a UiNode>) {
+        out.push(self);
+        for child in &self.children {
+            child.walk(out);
+        }
+    }
+}
+
+/// 选择器条件
+#[derive(Debug, Clone, PartialEq)]
+enum Predicate {
+    Equals(String, String),
+    Contains(String, String),
+}
+
+/// 类似 XPath 的控件选择器
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiSelector {
+    /// 类名，`*` 或 `node` 表示任意控件
+    class: Option<String>,
+    predicates: Vec<Predicate>,
+}
+
+impl UiSelector {
+    /// 解析选择器，如 `//*[contains(@text,'设置')][@clickable='true']`
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let query = query.trim();
+        let rest = query
+            .strip_prefix("//")
+            .ok_or_else(|| format!("选择器须以 // 开头: {}", query))?;
+        let (name, mut rest) = rest.split_at(rest.find('[').unwrap_or(rest.len()));
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("选择器缺少控件名（任意控件用 *）: {}", query));
+        }
+        let class = (name != "*" && name != "node").then(|| name.to_string());
+
+        let equals_re = Regex::new(r#"^@([\w-]+)\s*=\s*(?:'([^']*)'|"([^"]*)")$"#).unwrap();
+        let contains_re = Regex::new(r#"^contains\(\s*@([\w-]+)\s*,\s*(?:'([^']*)'|"([^"]*)")\s*\)$"#).unwrap();
+        let mut predicates = Vec::new();
+        while !rest.trim().is_empty() {
+            let body = rest
+                .trim_start()
+                .strip_prefix('[')
+                .ok_or_else(|| format!("无法解析选择器: {}", rest))?;
+            let end = body.find(']').ok_or_else(|| format!("选择器缺少 ]: {}", query))?;
+            let condition = body[..end].trim();
+            rest = &body[end + 1..];
+
+            let value = |cap: &regex::Captures| cap.get(2).or(cap.get(3)).map_or(String::new(), |m| m.as_str().to_string());
+            if let Some(cap) = equals_re.captures(condition) {
+                predicates.push(Predicate::Equals(cap[1].to_string(), value(&cap)));
+            } else if let Some(cap) = contains_re.captures(condition) {
+                predicates.push(Predicate::Contains(cap[1].to_string(), value(&cap)));
+            } else {
+                return Err(format!("不支持的条件: [{}]，支持 [@属性='值'] 与 [contains(@属性,'值')]", condition));
+            }
+        }
+        Ok(Self { class, predicates })
+    }
+
+    /// 控件是否满足选择器
+    pub fn matches(&self, node: &UiNode) -> bool {
+        if self.class.as_ref().is_some_and(|class| *class != node.class) {
+            return false;
+        }
+        self.predicates.iter().all(|predicate| match predicate {
+            Predicate::Equals(name, value) => node.attr(name).is_some_and(|actual| actual == *value),
+            Predicate::Contains(name, value) => node.attr(name).is_some_and(|actual| actual.contains(value.as_str())),
+        })
+    }
+}
+
+/// 解析后的界面层级
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UiTree {
+    /// 屏幕尺寸（根控件范围）
+    pub width: u32,
+    pub height: u32,
+    pub nodes: Vec<UiNode>,
+}
+
+/// 选择器匹配到的控件（不含子控件）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UiMatch {
+    #[serde(flatten)]
+    pub node: UiNode,
+    /// 中心点的像素坐标
+    pub center: (u32, u32),
+    /// 中心点的 0~1000 逻辑坐标，可直接用于 Agent 操作与点击接口
+    pub logical_center: (u32, u32),
+}
+
+impl UiTree {
+    /// 解析 uiautomator dump 的 XML
+    pub fn parse(xml: &str) -> Self {
+        let tag_re = Regex::new(r"<(/?)node\b([^>]*?)(/?)>").unwrap();
+        let attr_re = Regex::new(r#"([\w-]+)="([^"]*)""#).unwrap();
+        let bounds_re = Regex::new(r"\[(\d+),(\d+)\]\[(\d+),(\d+)\]").unwrap();
+
+        let mut roots = Vec::new();
+        let mut stack: Vec<UiNode> = Vec::new();
+        for tag in tag_re.captures_iter(xml) {
+            if &tag[1] == "/" {
+                if let Some(node) = stack.pop() {
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(node),
+                        None => roots.push(node),
+                    }
+                }
+                continue;
+            }
+
+            let mut node = UiNode::default();
+            for attr in attr_re.captures_iter(&tag[2]) {
+                let value = unescape_xml(&attr[2]);
+                match &attr[1] {
+                    "class" => node.class = value,
+                    "text" => node.text = value,
+                    "resource-id" => node.resource_id = value,
+                    "content-desc" => node.content_desc = value,
+                    "package" => node.package = value,
+                    "bounds" => {
+                        if let Some(cap) = bounds_re.captures(&value) {
+                            node.bounds = [1, 2, 3, 4].map(|i| cap[i].parse().unwrap_or(0));
+                        }
+                    }
+                    "clickable" => node.clickable = value == "true",
+                    "enabled" => node.enabled = value == "true",
+                    "focused" => node.focused = value == "true",
+                    "scrollable" => node.scrollable = value == "true",
+                    "checked" => node.checked = value == "true",
+                    "selected" => node.selected = value == "true",
+                    "password" => node.password = value == "true",
+                    _ => {}
+                }
+            }
+            if &tag[3] == "/" {
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => roots.push(node),
+                }
+            } else {
+                stack.push(node);
+            }
+        }
+        // 不完整的 XML：把未闭合的控件逐级挂回父控件
+        while let Some(node) = stack.pop() {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => roots.push(node),
+            }
+        }
+
+        let width = roots.iter().map(|node| node.bounds[2]).max().unwrap_or(0);
+        let height = roots.iter().map(|node| node.bounds[3]).max().unwrap_or(0);
+        Self { width, height, nodes: roots }
+    }
+
+    /// 按文档顺序返回全部控件（含嵌套的子控件）
+    pub fn descendants(&self) -> Vec<&UiNode> {
+        let mut all = Vec::new();
+        for node in &self.nodes {
+            node.walk(&mut all);
+        }
+        all
+    }
+
+    /// 按文档顺序返回满足选择器的控件
+    pub fn query(&self, selector: &UiSelector) -> Vec<UiMatch> {
+        self.descendants()
+            .into_iter()
+            .filter(|node| selector.matches(node))
+            .map(|node| {
+                let center = node.center();
+                UiMatch {
+                    node: UiNode { children: Vec::new(), ..node.clone() },
+                    center,
+                    logical_center: (to_logical(center.0, self.width), to_logical(center.1, self.height)),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"<?xml version='1.0' encoding='UTF-8' standalone='yes' ?><hierarchy rotation="0"><node index="0" text="" resource-id="" class="android.widget.FrameLayout" package="com.android.settings" content-desc="" clickable="false" enabled="true" bounds="[0,0][1080,2400]"><node index="0" text="蓝牙 &amp; 设备" resource-id="android:id/title" class="android.widget.TextView" package="com.android.settings" content-desc="" clickable="false" enabled="true" bounds="[100,400][500,500]" /><node index="1" text="" resource-id="com.android.settings:id/search" class="android.widget.Button" package="com.android.settings" content-desc="搜索" clickable="true" enabled="true" bounds="[900,100][1060,260]"></node></node></hierarchy>"#;
+
+    #[test]
+    fn test_parse_tree() {
+        let tree = UiTree::parse(XML);
+        assert_eq!((tree.width, tree.height), (1080, 2400));
+        assert_eq!(tree.nodes.len(), 1);
+        let root = &tree.nodes[0];
+        assert_eq!(root.children.len(), 2);
+        assert_eq!(root.children[0].text, "蓝牙 & 设备");
+        assert_eq!(root.children[1].center(), (980, 180));
+        assert!(root.children[1].clickable);
+    }
+
+    #[test]
+    fn test_query() {
+        let tree = UiTree::parse(XML);
+
+        let selector = UiSelector::parse("//*[contains(@text,'蓝牙')]").unwrap();
+        let matches = tree.query(&selector);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].center, (300, 450));
+        assert_eq!(matches[0].logical_center, (277, 187));
+
+        let selector = UiSelector::parse(r#"//android.widget.Button[@resource-id="com.android.settings:id/search"][@clickable='true']"#).unwrap();
+        assert_eq!(tree.query(&selector)[0].node.content_desc, "搜索");
+        assert!(tree.query(&UiSelector::parse("//android.widget.TextView[@clickable='true']").unwrap()).is_empty());
+        assert_eq!(tree.query(&UiSelector::parse("//node").unwrap()).len(), 3);
+    }
+
+    #[test]
+    fn test_invalid_selector() {
+        assert!(UiSelector::parse("text=蓝牙").is_err());
+        assert!(UiSelector::parse("//*[@text").is_err());
+        assert!(UiSelector::parse("//*[starts-with(@text,'蓝')]").is_err());
+    }
+}
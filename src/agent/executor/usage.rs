@@ -0,0 +1,224 @@
+//! 应用使用情况校验
+//!
+//! 任务结束后读取设备的 `dumpsys usagestats` 事件，统计任务期间目标应用在前台的
+//! 时长，用于确认任务确实由预期应用完成（例如“看两分钟视频”），而不是仅依赖模型的 finish。
+
+use chrono::NaiveDateTime;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
+use crate::agent::actions::system::app_name_to_package;
+use crate::error::AppError;
+
+/// usagestats 与 `date` 输出使用的时间格式
+const DEVICE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 任务对应用使用情况的预期
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageExpectation {
+    /// 应用名称（如"哔哩哔哩"）或包名
+    pub app: String,
+    /// 最少前台时长（秒），未指定时从任务描述中推断
+    #[serde(default)]
+    pub min_foreground_secs: Option<u64>,
+}
+
+impl UsageExpectation {
+    /// 目标应用包名
+    pub fn package(&self) -> Option<String> {
+        app_name_to_package(&self.app)
+    }
+}
+
+/// 校验结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub package: String,
+    /// 任务期间是否到过前台
+    pub foregrounded: bool,
+    /// 任务期间的前台时长（秒）
+    pub foreground_secs: u64,
+    /// 要求的最少前台时长（秒）
+    pub required_secs: u64,
+}
+
+impl UsageReport {
+    /// 是否满足预期
+    pub fn passed(&self) -> bool {
+        self.foregrounded && self.foreground_secs >= self.required_secs
+    }
+
+    pub fn summary(&self) -> String {
+        if !self.foregrounded {
+            return format!("{} 在任务期间未进入前台", self.package);
+        }
+        format!(
+            "{} 前台时长 {} 秒（要求至少 {} 秒）",
+            self.package, self.foreground_secs, self.required_secs
+        )
+    }
+}
+
+/// 从任务描述中推断要求的时长，例如 "看2分钟视频"、"play for 30 seconds"
+pub fn parse_duration_secs(task: &str) -> Option<u64> {
+    let re = Regex::new(
+        r"(?i)(\d+(?:\.\d+)?)\s*(小时|hours?|hrs?|分钟|分|minutes?|mins?|秒钟|秒|seconds?|secs?)",
+    )
+    .unwrap();
+    let cap = re.captures(task)?;
+    let value: f64 = cap[1].parse().ok()?;
+    let unit = cap[2].to_lowercase();
+    let scale = if unit.starts_with('小') || unit.starts_with('h') {
+        3600.0
+    } else if unit.starts_with('分') || unit.starts_with('m') {
+        60.0
+    } else {
+        1.0
+    };
+    Some((value * scale).round() as u64)
+}
+
+/// 统计 `[since, until]` 区间内各应用的前台时长（秒）
+///
+/// 同一事件会出现在 daily/weekly 等多个统计段中，按内容去重后按时间排序处理。
+pub fn foreground_durations(
+    dump: &str,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> HashMap<String, u64> {
+    let event_re = Regex::new(r#"time="([^"]+)"\s+type=(\w+)\s+package=(\S+)"#).unwrap();
+
+    let mut seen = HashSet::new();
+    let mut events: Vec<(NaiveDateTime, bool, String)> = event_re
+        .captures_iter(dump)
+        .filter(|cap| seen.insert(cap[0].to_string()))
+        .filter_map(|cap| {
+            let time = NaiveDateTime::parse_from_str(&cap[1], DEVICE_TIME_FORMAT).ok()?;
+            let foreground = match &cap[2] {
+                "ACTIVITY_RESUMED" | "MOVE_TO_FOREGROUND" => true,
+                "ACTIVITY_PAUSED" | "ACTIVITY_STOPPED" | "MOVE_TO_BACKGROUND" => false,
+                _ => return None,
+            };
+            (time <= until).then(|| (time, foreground, cap[3].to_string()))
+        })
+        .collect();
+    events.sort_by_key(|(time, _, _)| *time);
+
+    let mut durations: HashMap<String, u64> = HashMap::new();
+    let mut current: Option<(String, NaiveDateTime)> = None;
+    let mut close = |current: &mut Option<(String, NaiveDateTime)>, end: NaiveDateTime| {
+        if let Some((package, start)) = current.take() {
+            let start = start.max(since);
+            if end >= since {
+                *durations.entry(package).or_default() += (end - start).num_seconds().max(0) as u64;
+            }
+        }
+    };
+
+    for (time, foreground, package) in events {
+        let same = current.as_ref().is_some_and(|(p, _)| *p == package);
+        if foreground && !same {
+            close(&mut current, time);
+            current = Some((package, time));
+        } else if !foreground && same {
+            close(&mut current, time);
+        }
+    }
+    close(&mut current, until);
+    durations
+}
+
+/// 执行 adb shell 命令
+pub(crate) async fn adb_shell(device_serial: &str, command: &str) -> Result<String, AppError> {
+    debug!("执行 ADB 命令: adb -s {} shell {}", device_serial, command);
+    let output = crate::platform::adb_device_command(device_serial)
+        .args(["shell", command])
+        .output()
+        .await
+        .map_err(|e| AppError::AdbError(format!("执行命令失败: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::AdbError(format!("命令执行失败: {}", stderr.trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// 设备当前的本地时间（与 usagestats 事件时间一致）
+pub async fn device_now(device_serial: &str) -> Result<NaiveDateTime, AppError> {
+    let output = adb_shell(device_serial, "date '+%Y-%m-%d %H:%M:%S'").await?;
+    NaiveDateTime::parse_from_str(output.trim(), DEVICE_TIME_FORMAT)
+        .map_err(|e| AppError::AdbError(format!("解析设备时间失败: {} ({})", output.trim(), e)))
+}
+
+/// 校验任务期间目标应用的前台使用情况
+pub async fn verify_app_usage(
+    device_serial: &str,
+    since: NaiveDateTime,
+    expectation: &UsageExpectation,
+    task: &str,
+) -> Result<UsageReport, AppError> {
+    let package = expectation
+        .package()
+        .ok_or_else(|| AppError::AdbError(format!("无法识别的应用名称: {}", expectation.app)))?;
+    let required_secs = expectation
+        .min_foreground_secs
+        .or_else(|| parse_duration_secs(task))
+        .unwrap_or(0);
+
+    let until = device_now(device_serial).await?;
+    let dump = adb_shell(device_serial, "dumpsys usagestats").await?;
+    let durations = foreground_durations(&dump, since, until);
+
+    Ok(UsageReport {
+        foregrounded: durations.contains_key(&package),
+        foreground_secs: durations.get(&package).copied().unwrap_or(0),
+        package,
+        required_secs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(time, DEVICE_TIME_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn test_parse_duration_secs() {
+        assert_eq!(parse_duration_secs("打开哔哩哔哩看2分钟视频"), Some(120));
+        assert_eq!(parse_duration_secs("watch a video for 90 seconds"), Some(90));
+        assert_eq!(parse_duration_secs("听1.5小时音乐"), Some(5400));
+        assert_eq!(parse_duration_secs("打开微信"), None);
+    }
+
+    #[test]
+    fn test_foreground_durations() {
+        let dump = r#"
+  In-memory daily stats
+    events
+      time="2024-03-01 09:59:00" type=ACTIVITY_RESUMED package=com.android.launcher3 class=Launcher
+      time="2024-03-01 10:00:10" type=ACTIVITY_PAUSED package=com.android.launcher3 class=Launcher
+      time="2024-03-01 10:00:10" type=ACTIVITY_RESUMED package=tv.danmaku.bili class=MainActivity
+      time="2024-03-01 10:01:00" type=ACTIVITY_RESUMED package=tv.danmaku.bili class=VideoActivity
+      time="2024-03-01 10:02:40" type=ACTIVITY_PAUSED package=tv.danmaku.bili class=VideoActivity
+  In-memory weekly stats
+    events
+      time="2024-03-01 10:00:10" type=ACTIVITY_RESUMED package=tv.danmaku.bili class=MainActivity
+"#;
+        let durations = foreground_durations(dump, at("2024-03-01 10:00:00"), at("2024-03-01 10:03:00"));
+        assert_eq!(durations.get("tv.danmaku.bili"), Some(&150));
+        assert_eq!(durations.get("com.android.launcher3"), Some(&10));
+
+        let report = UsageReport {
+            package: "tv.danmaku.bili".to_string(),
+            foregrounded: true,
+            foreground_secs: 150,
+            required_secs: 120,
+        };
+        assert!(report.passed());
+    }
+}
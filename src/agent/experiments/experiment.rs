@@ -0,0 +1,364 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+use crate::agent::llm::ModelConfig;
+
+/// 实验数据目录
+const EXPERIMENT_DIR: &str = "experiments";
+
+/// 实验变体：一组提示词与模型配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    /// 追加到系统提示词末尾的内容
+    #[serde(default)]
+    pub prompt_suffix: Option<String>,
+    /// 覆盖主模型（三阶段模式下同时作为执行模型）
+    #[serde(default)]
+    pub model_name: Option<String>,
+    /// 覆盖三阶段模式的规划模型
+    #[serde(default)]
+    pub planning_model_name: Option<String>,
+    /// 覆盖采样温度
+    #[serde(default)]
+    pub temperature: Option<f32>,
+}
+
+impl Variant {
+    /// 是否需要使用与默认配置不同的模型客户端
+    pub fn overrides_model(&self) -> bool {
+        self.model_name.is_some() || self.planning_model_name.is_some() || self.temperature.is_some()
+    }
+
+    /// 在基础模型配置上应用变体的覆盖项
+    pub fn apply(&self, base: &ModelConfig) -> ModelConfig {
+        let mut config = base.clone();
+        if let Some(model_name) = &self.model_name {
+            config.model_name = model_name.clone();
+            if config.execution_model_name.is_some() {
+                config.execution_model_name = Some(model_name.clone());
+            }
+        }
+        if let Some(planning_model_name) = &self.planning_model_name {
+            config.planning_model_name = Some(planning_model_name.clone());
+        }
+        if let Some(temperature) = self.temperature {
+            config.temperature = temperature;
+        }
+        config
+    }
+}
+
+/// 变体分配策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssignmentStrategy {
+    #[default]
+    RoundRobin,
+    Random,
+}
+
+/// 单个任务在实验中的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskOutcome {
+    pub variant: String,
+    pub task: String,
+    pub success: bool,
+    pub steps: usize,
+    pub duration_ms: u64,
+    pub tokens_used: u64,
+    /// 评估模型评分（配置了评估模型时）
+    #[serde(default)]
+    pub judge_score: Option<f32>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// 实验
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Experiment {
+    pub id: String,
+    pub name: String,
+    pub strategy: AssignmentStrategy,
+    pub variants: Vec<Variant>,
+    pub created_at: DateTime<Utc>,
+    /// 轮询分配的下一个变体下标
+    #[serde(default)]
+    next_variant: usize,
+    #[serde(default)]
+    pub outcomes: Vec<TaskOutcome>,
+}
+
+impl Experiment {
+    pub fn new(name: String, strategy: AssignmentStrategy, variants: Vec<Variant>) -> Result<Self, String> {
+        if variants.len() < 2 {
+            return Err("实验至少需要两个变体".to_string());
+        }
+        let mut names: Vec<&str> = variants.iter().map(|v| v.name.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        if names.len() != variants.len() || names.iter().any(|n| n.trim().is_empty()) {
+            return Err("变体名称不能为空且不能重复".to_string());
+        }
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            strategy,
+            variants,
+            created_at: Utc::now(),
+            next_variant: 0,
+            outcomes: Vec::new(),
+        })
+    }
+
+    /// 为新任务选择变体
+    fn assign(&mut self) -> Variant {
+        let index = match self.strategy {
+            AssignmentStrategy::RoundRobin => {
+                let index = self.next_variant % self.variants.len();
+                self.next_variant = index + 1;
+                index
+            }
+            AssignmentStrategy::Random => (Uuid::new_v4().as_u128() % self.variants.len() as u128) as usize,
+        };
+        self.variants[index].clone()
+    }
+
+    /// 按变体汇总结果
+    pub fn results(&self) -> ExperimentResults {
+        let variants = self
+            .variants
+            .iter()
+            .map(|variant| {
+                let outcomes: Vec<&TaskOutcome> =
+                    self.outcomes.iter().filter(|o| o.variant == variant.name).collect();
+                VariantResults::from_outcomes(&variant.name, &outcomes)
+            })
+            .collect();
+
+        ExperimentResults {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            total_tasks: self.outcomes.len(),
+            variants,
+        }
+    }
+}
+
+/// 单个变体的汇总结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariantResults {
+    pub variant: String,
+    pub tasks: usize,
+    pub successes: usize,
+    pub success_rate: f64,
+    pub avg_steps: f64,
+    pub avg_duration_ms: f64,
+    pub total_tokens: u64,
+    pub avg_tokens: f64,
+    /// 评估模型平均评分（没有评分时为 None）
+    pub avg_judge_score: Option<f64>,
+}
+
+impl VariantResults {
+    pub(crate) fn from_outcomes(variant: &str, outcomes: &[&TaskOutcome]) -> Self {
+        let tasks = outcomes.len();
+        let average = |sum: f64| if tasks == 0 { 0.0 } else { sum / tasks as f64 };
+        let successes = outcomes.iter().filter(|o| o.success).count();
+        let total_tokens = outcomes.iter().map(|o| o.tokens_used).sum::<u64>();
+        let scores: Vec<f64> = outcomes.iter().filter_map(|o| o.judge_score).map(f64::from).collect();
+
+        Self {
+            variant: variant.to_string(),
+            tasks,
+            successes,
+            success_rate: average(successes as f64),
+            avg_steps: average(outcomes.iter().map(|o| o.steps as f64).sum()),
+            avg_duration_ms: average(outcomes.iter().map(|o| o.duration_ms as f64).sum()),
+            total_tokens,
+            avg_tokens: average(total_tokens as f64),
+            avg_judge_score: (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64),
+        }
+    }
+}
+
+/// 实验汇总结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentResults {
+    pub id: String,
+    pub name: String,
+    pub total_tasks: usize,
+    pub variants: Vec<VariantResults>,
+}
+
+/// 分配给某个任务的实验变体
+#[derive(Debug, Clone)]
+pub struct ExperimentAssignment {
+    pub experiment_id: String,
+    pub variant: Variant,
+}
+
+/// 实验注册表，实验状态同步保存到 `experiments/{id}.json`
+pub struct ExperimentRegistry {
+    dir: PathBuf,
+    experiments: RwLock<HashMap<String, Experiment>>,
+}
+
+impl Default for ExperimentRegistry {
+    fn default() -> Self {
+        Self::new(EXPERIMENT_DIR)
+    }
+}
+
+impl ExperimentRegistry {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let experiments = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| std::fs::read(entry.path()).ok())
+            .filter_map(|json| serde_json::from_slice::<Experiment>(&json).ok())
+            .map(|experiment| (experiment.id.clone(), experiment))
+            .collect();
+
+        Self { dir, experiments: RwLock::new(experiments) }
+    }
+
+    async fn persist(&self, experiment: &Experiment) {
+        let result = async {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            let json = serde_json::to_vec_pretty(experiment)?;
+            tokio::fs::write(self.dir.join(format!("{}.json", experiment.id)), json).await?;
+            Ok::<(), crate::error::AppError>(())
+        };
+        if let Err(e) = result.await {
+            warn!("保存实验 {} 失败: {}", experiment.id, e);
+        }
+    }
+
+    /// 创建实验
+    pub async fn create(
+        &self,
+        name: String,
+        strategy: AssignmentStrategy,
+        variants: Vec<Variant>,
+    ) -> Result<Experiment, String> {
+        let experiment = Experiment::new(name, strategy, variants)?;
+        self.persist(&experiment).await;
+        self.experiments.write().await.insert(experiment.id.clone(), experiment.clone());
+        info!("创建实验: {} ({})", experiment.name, experiment.id);
+        Ok(experiment)
+    }
+
+    pub async fn get(&self, id: &str) -> Option<Experiment> {
+        self.experiments.read().await.get(id).cloned()
+    }
+
+    pub async fn list(&self) -> Vec<Experiment> {
+        let mut experiments: Vec<Experiment> = self.experiments.read().await.values().cloned().collect();
+        experiments.sort_by_key(|e| e.created_at);
+        experiments
+    }
+
+    /// 为新任务分配变体
+    pub async fn assign(&self, id: &str) -> Result<ExperimentAssignment, String> {
+        let mut experiments = self.experiments.write().await;
+        let experiment = experiments.get_mut(id).ok_or_else(|| format!("实验不存在: {}", id))?;
+        let variant = experiment.assign();
+        // 持有写锁写入文件，保证并发分配与记录结果按修改顺序落盘，较旧的快照不会覆盖较新的
+        self.persist(experiment).await;
+        drop(experiments);
+        info!("实验 {} 分配变体: {}", id, variant.name);
+        Ok(ExperimentAssignment { experiment_id: id.to_string(), variant })
+    }
+
+    /// 记录任务结果
+    pub async fn record(&self, id: &str, outcome: TaskOutcome) {
+        let mut experiments = self.experiments.write().await;
+        let Some(experiment) = experiments.get_mut(id) else {
+            warn!("记录结果时实验不存在: {}", id);
+            return;
+        };
+        experiment.outcomes.push(outcome);
+        self.persist(experiment).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(name: &str) -> Variant {
+        Variant {
+            name: name.to_string(),
+            prompt_suffix: None,
+            model_name: None,
+            planning_model_name: None,
+            temperature: None,
+        }
+    }
+
+    fn outcome(variant: &str, success: bool, steps: usize, tokens_used: u64) -> TaskOutcome {
+        TaskOutcome {
+            variant: variant.to_string(),
+            task: "打开设置".to_string(),
+            success,
+            steps,
+            duration_ms: 1000,
+            tokens_used,
+            judge_score: None,
+            finished_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_round_robin_and_results() {
+        assert!(Experiment::new("x".to_string(), AssignmentStrategy::RoundRobin, vec![variant("a")]).is_err());
+        assert!(Experiment::new("x".to_string(), AssignmentStrategy::RoundRobin, vec![variant("a"), variant("a")]).is_err());
+
+        let mut experiment =
+            Experiment::new("prompt".to_string(), AssignmentStrategy::RoundRobin, vec![variant("a"), variant("b")])
+                .unwrap();
+        let assigned: Vec<String> = (0..3).map(|_| experiment.assign().name).collect();
+        assert_eq!(assigned, vec!["a", "b", "a"]);
+
+        experiment.outcomes = vec![outcome("a", true, 4, 100), outcome("a", false, 8, 300), outcome("b", true, 3, 50)];
+        let results = experiment.results();
+        assert_eq!(results.total_tasks, 3);
+        assert_eq!(results.variants[0].success_rate, 0.5);
+        assert_eq!(results.variants[0].avg_steps, 6.0);
+        assert_eq!(results.variants[0].avg_tokens, 200.0);
+        assert_eq!(results.variants[1].successes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_results_are_persisted() {
+        let dir = std::env::temp_dir().join(format!("scrs_experiments_{}", std::process::id()));
+        let registry = std::sync::Arc::new(ExperimentRegistry::new(&dir));
+        let experiment = registry
+            .create("prompt".to_string(), AssignmentStrategy::RoundRobin, vec![variant("a"), variant("b")])
+            .await
+            .unwrap();
+
+        let tasks: Vec<_> = (0..16)
+            .map(|i| {
+                let registry = std::sync::Arc::clone(&registry);
+                let id = experiment.id.clone();
+                tokio::spawn(async move { registry.record(&id, outcome("a", i % 2 == 0, i, 10)).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // 最后写入的文件包含全部结果
+        let reloaded = ExperimentRegistry::new(&dir);
+        assert_eq!(reloaded.get(&experiment.id).await.unwrap().outcomes.len(), 16);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
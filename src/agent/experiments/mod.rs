@@ -0,0 +1,16 @@
+//! 提示词/模型 A/B 实验模块
+//!
+//! 将多组提示词与模型配置分配给真实任务，按变体汇总成功率、步数与 token 消耗。
+
+mod experiment;
+
+pub use experiment::{
+    AssignmentStrategy,
+    Experiment,
+    ExperimentAssignment,
+    ExperimentRegistry,
+    ExperimentResults,
+    TaskOutcome,
+    Variant,
+    VariantResults,
+};
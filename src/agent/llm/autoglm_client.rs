@@ -5,10 +5,15 @@ use std::sync::{Arc, Mutex as StdMutex};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn, error};
 use tokio_stream::StreamExt;
-use crate::agent::core::traits::{ModelClient, ModelResponse, ModelError, ModelInfo, ChatMessage, MessageRole};
-use crate::agent::llm::types::{ChatRequest, ModelConfig, MessageContent, ChatMessage as ApiChatMessage, MessageRole as ApiMessageRole};
+use crate::agent::actions::ActionFormat;
+use crate::agent::core::message::{attach_screenshot, ChatMessage, MessageContent, MessageRole};
+use crate::agent::core::traits::{ModelClient, ModelResponse, ModelError, ModelInfo, PerformanceMetrics};
+use crate::agent::llm::types::{ChatRequest, ModelConfig};
+use crate::agent::llm::payload_guard::{PayloadLimits, PayloadTrim};
 use crate::agent::llm::prompts;
 use crate::agent::logger::{AgentLogger, LogMessage};
+use crate::agent::llm::thinking::{publish_thinking_event, ThinkingStream};
+use crate::logger;
 use serde::{Deserialize, Serialize};
 
 // 导入 ActionEnum 用于解析响应
@@ -24,15 +29,13 @@ enum StreamEvent {
     MessageEnd,
 }
 
-/// AutoGLM 性能指标
-#[derive(Debug, Clone, Serialize)]
-pub struct PerformanceMetrics {
+/// 流式响应的内容与时间点
+struct StreamOutput {
+    content: String,
     /// 首个 token 时间（秒）
-    pub time_to_first_token: Option<f64>,
+    time_to_first_token: Option<f64>,
     /// 思考结束时间（秒）
-    pub time_to_thinking_end: Option<f64>,
-    /// 总推理时间（秒）
-    pub total_time: f64,
+    time_to_thinking_end: Option<f64>,
 }
 
 /// AutoGLM 客户端，支持流式响应和特殊标记解析
@@ -51,15 +54,14 @@ impl AutoGLMClient {
     /// 创建新的 AutoGLM 客户端
     pub fn new(config: ModelConfig) -> Result<Self, ModelError> {
         info!("创建 AutoGLM 客户端: {}", config.model_name);
-        info!("  API 端点: {}", config.base_url);
-        info!("  超时时间: {}s", config.timeout);
-        info!("  API Key: {}...", &config.api_key[..config.api_key.len().min(10)]);
+        debug!("  API 端点: {}", config.base_url);
+        debug!("  超时时间: {}s", config.timeout);
 
         // 显示辅助模型配置
         if let Some(ref aux_name) = config.auxiliary_model_name {
-            info!("  辅助模型: {}", aux_name);
+            debug!("  辅助模型: {}", aux_name);
         } else {
-            info!("  未配置辅助模型");
+            debug!("  未配置辅助模型");
         }
 
         let client = Client::builder()
@@ -87,6 +89,15 @@ impl AutoGLMClient {
         })
     }
 
+    /// 检查请求体大小与图片数量，超出限制时裁剪消息
+    fn enforce_payload_limits(&self, messages: &mut Vec<ChatMessage>) -> PayloadTrim {
+        let trim = PayloadLimits::from_config(&self.config).enforce(messages);
+        if !trim.is_empty() {
+            warn!("请求体超出限制，已裁剪: {}", trim);
+        }
+        trim
+    }
+
     /// 记录 API 对话到日志文件
     async fn log_api_call(
         &self,
@@ -99,7 +110,7 @@ impl AutoGLMClient {
         let logger = self.logger.lock().unwrap().clone();
         if let Some(logger) = logger {
             let _ = logger.log_action(
-                0, // step_number
+                logger.current_step(),
                 messages,
                 None, // screenshot
                 model_response.to_string(), // 直接记录原始响应
@@ -114,10 +125,12 @@ impl AutoGLMClient {
     }
 
     /// 发送流式聊天请求
-    async fn send_stream_request(&self, request: ChatRequest) -> Result<String, ModelError> {
+    async fn send_stream_request(&self, request: ChatRequest) -> Result<StreamOutput, ModelError> {
         let url = format!("{}/chat/completions", self.config.base_url);
+        let start_time = Instant::now();
 
         debug!("发送 AutoGLM 流式请求到: {}", url);
+        log_request_body(&request);
 
         let mut stream_request = request.clone();
         stream_request.stream = Some(true);
@@ -150,34 +163,77 @@ impl AutoGLMClient {
                 return Err(ModelError::RateLimit);
             }
 
+            if status.is_server_error() {
+                return Err(ModelError::ServerError { status: status.as_u16(), message: error_text });
+            }
+
             return Err(ModelError::ApiError(format!(
                 "请求失败: {} - {}",
                 status, error_text
             )));
         }
 
-        // 处理流式响应
-        let mut full_content = String::new();
+        // 处理流式响应（SSE，每行 `data: {...}`，以 `data: [DONE]` 结束）
+        let mut output = StreamOutput {
+            content: String::new(),
+            time_to_first_token: None,
+            time_to_thinking_end: None,
+        };
+        let mut buffer = String::new();
         let mut byte_stream = response.bytes_stream();
+        // 有 Agent 时把 token 实时推送给订阅思考过程的客户端
+        let logger = self.logger.lock().unwrap().clone();
+        let mut thinking_stream = logger.map(|logger| {
+            ThinkingStream::new(logger.agent_id(), logger.device_serial().map(str::to_string))
+        });
 
-        while let Some(chunk_result) = byte_stream.next().await {
+        'stream: while let Some(chunk_result) = byte_stream.next().await {
             let chunk = chunk_result
                 .map_err(|e| ModelError::NetworkError(format!("读取流数据失败: {}", e)))?;
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(pos) = buffer.find('\n') {
+                let line: String = buffer.drain(..=pos).collect();
+                let Some(data) = line.trim().strip_prefix("data:") else {
+                    continue;
+                };
+                let data = data.trim();
+                if data == "[DONE]" {
+                    break 'stream;
+                }
+                let Some(token) = parse_stream_token(data) else {
+                    continue;
+                };
+                if token.is_empty() {
+                    continue;
+                }
 
-            let chunk_str = String::from_utf8_lossy(&chunk);
-            full_content.push_str(&chunk_str);
+                let elapsed = start_time.elapsed().as_secs_f64();
+                output.time_to_first_token.get_or_insert(elapsed);
+                output.content.push_str(&token);
+                if output.time_to_thinking_end.is_none() && is_thinking_finished(&output.content) {
+                    output.time_to_thinking_end = Some(elapsed);
+                }
+                if let Some(event) = thinking_stream
+                    .as_mut()
+                    .and_then(|stream| stream.push(&token, output.time_to_thinking_end.is_some()))
+                {
+                    publish_thinking_event(event);
+                }
+            }
+        }
+        if let Some(stream) = thinking_stream.as_mut() {
+            publish_thinking_event(stream.finish());
         }
 
-        Ok(full_content)
+        Ok(output)
     }
 
     /// 发送非流式聊天请求
     async fn send_request(&self, request: ChatRequest) -> Result<ChatResponse, ModelError> {
         let url = format!("{}/chat/completions", self.config.base_url);
 
-        info!("发送 AutoGLM 请求到: {}", url);
-        info!("  模型: {}", request.model);
-        info!("  消息数: {}", request.messages.len());
+        debug!("发送 AutoGLM 请求到: {}, 模型: {}, 消息数: {}", url, request.model, request.messages.len());
 
         // 发送请求并处理错误
         match self._send_request(&url, &request, &self.client, &self.config.api_key).await {
@@ -214,14 +270,8 @@ impl AutoGLMClient {
         let user_message = format!("请修正以下输出，使其符合格式要求：\n\n{}", original_content);
 
         let api_messages = vec![
-            ApiChatMessage {
-                role: ApiMessageRole::System,
-                content: MessageContent::Text(system_prompt),
-            },
-            ApiChatMessage {
-                role: ApiMessageRole::User,
-                content: MessageContent::Text(user_message),
-            },
+            ChatMessage::system(system_prompt),
+            ChatMessage::user(user_message),
         ];
 
         let request = ChatRequest {
@@ -240,10 +290,7 @@ impl AutoGLMClient {
             ModelError::ParseError("辅助模型响应中没有选择项".to_string())
         })?;
 
-        let corrected_content = match &choice.message.content {
-            MessageContent::Text(text) => text.clone(),
-            _ => original_content.to_string(),
-        };
+        let corrected_content = choice.message.text();
 
         info!("辅助模型修正完成");
         debug!("原始内容: {}", original_content);
@@ -259,14 +306,11 @@ impl AutoGLMClient {
         client: &Client,
         api_key: &str,
     ) -> Result<ChatResponse, ModelError> {
-        // 打印请求详情（选择性输出，过滤图片数据）
-        info!("========== AutoGLM 请求 ==========");
-        info!("URL: {}", url);
-        info!("模型: {}", request.model);
-        info!("参数: max_tokens={:?}, temperature={:?}, top_p={:?}, stream={:?}",
-            request.max_tokens, request.temperature, request.top_p, request.stream);
-        info!("消息数量: {}", request.messages.len());
-        info!("================================");
+        debug!(
+            "AutoGLM 请求参数: max_tokens={:?}, temperature={:?}, top_p={:?}, stream={:?}",
+            request.max_tokens, request.temperature, request.top_p, request.stream
+        );
+        log_request_body(request);
 
         let response = client
             .post(url)
@@ -319,11 +363,10 @@ impl AutoGLMClient {
             .await
             .map_err(|e| ModelError::NetworkError(format!("读取响应失败: {}", e)))?;
 
-        // 打印响应详情
-        info!("========== AutoGLM 响应 ==========");
-        info!("状态码: {}", status);
-        info!("响应体 ({} 字节):", response_text.len());
-        info!("================================");
+        debug!("AutoGLM 响应: {} ({} 字节)", status, response_text.len());
+        if logger::body_logging().llm_responses {
+            info!("AutoGLM 响应体: {}", response_text);
+        }
 
         if !status.is_success() {
             warn!("AutoGLM 请求失败: {} - {}", status, response_text);
@@ -338,6 +381,10 @@ impl AutoGLMClient {
                 return Err(ModelError::RateLimit);
             }
 
+            if status.is_server_error() {
+                return Err(ModelError::ServerError { status: status.as_u16(), message: response_text });
+            }
+
             return Err(ModelError::ApiError(format!(
                 "请求失败: {} - {}",
                 status, response_text
@@ -353,9 +400,24 @@ impl AutoGLMClient {
         Ok(chat_response)
     }
 
-    /// 解析 AutoGLM 响应（使用 ActionEnum 的通用解析方法）
-    fn parse_response(&self, content: &str) -> (Option<String>, Vec<ActionEnum>) {
-        ActionEnum::parse_from_response(content)
+    /// 解析 AutoGLM 响应（使用 ActionEnum 的通用解析方法，按配置的输出格式）
+    ///
+    /// 百分比坐标按屏幕分辨率换算，分辨率为 0 时视为未知
+    fn parse_response(&self, content: &str, screen_size: (u32, u32)) -> (Option<String>, Vec<ActionEnum>) {
+        let screen_size = Some(screen_size).filter(|&(width, height)| width > 0 && height > 0);
+        ActionEnum::parse_with_format(content, self.config.action_format, screen_size)
+    }
+
+    /// JSON 输出模式下，在系统提示词后追加 JSON 格式说明
+    fn apply_action_format(&self, messages: &mut Vec<ChatMessage>) {
+        if self.config.action_format != ActionFormat::Json {
+            return;
+        }
+        let instructions = prompts::get_json_action_format_prompt();
+        match messages.iter_mut().find(|m| m.role == MessageRole::System) {
+            Some(system) => *system = ChatMessage::system(format!("{}\n\n{}", system.text(), instructions)),
+            None => messages.insert(0, ChatMessage::system(instructions)),
+        }
     }
 
     /// 阶段1: 大模型规划动作
@@ -363,6 +425,8 @@ impl AutoGLMClient {
     async fn plan_action(
         &self,
         messages: Vec<ChatMessage>,
+        metrics: &mut PerformanceMetrics,
+        payload_trim: &mut PayloadTrim,
     ) -> Result<String, ModelError> {
         let planning_model = self.config.planning_model_name
             .as_ref()
@@ -374,19 +438,9 @@ impl AutoGLMClient {
 
         let start_time = Instant::now();
 
-        // 转换对话历史（agent.rs 已经包含了系统提示词，不需要再添加）
-        let api_messages: Vec<ApiChatMessage> = messages.iter().map(|msg| {
-            let role = match msg.role {
-                MessageRole::System => ApiMessageRole::System,
-                MessageRole::User => ApiMessageRole::User,
-                MessageRole::Assistant => ApiMessageRole::Assistant,
-            };
-
-            ApiChatMessage {
-                role,
-                content: MessageContent::Text(msg.content.clone()),
-            }
-        }).collect();
+        // 规划模型只看文字对话历史（agent.rs 已经包含了系统提示词，不需要再添加）
+        let mut api_messages: Vec<ChatMessage> = messages.iter().map(ChatMessage::text_only).collect();
+        payload_trim.merge(self.enforce_payload_limits(&mut api_messages));
 
         let request = ChatRequest {
             model: planning_model.to_string(),
@@ -408,22 +462,17 @@ impl AutoGLMClient {
             ModelError::ParseError("规划模型响应中没有选择项".to_string())
         })?;
 
-        let planning_output = match &choice.message.content {
-            MessageContent::Text(text) => text.clone(),
-            _ => return Err(ModelError::ParseError("规划模型输出格式错误".to_string())),
-        };
+        let planning_output = choice.message.text();
 
         let duration = start_time.elapsed().as_millis() as u64;
         info!("规划请求: {} (耗时: {}ms)", planning_output, duration);
+        metrics.add_stage("planning", planning_model, duration);
 
         // 记录到日志文件（对话格式）
         let log_messages: Vec<LogMessage> = api_messages.iter().map(|msg| {
             LogMessage {
                 role: format!("{:?}", msg.role).to_lowercase(),
-                content: match &msg.content {
-                    MessageContent::Text(text) => text.clone(),
-                    _ => "".to_string(),
-                },
+                content: msg.text(),
             }
         }).collect();
 
@@ -445,6 +494,8 @@ impl AutoGLMClient {
         screenshot: &str,
         screen_width: u32,
         screen_height: u32,
+        metrics: &mut PerformanceMetrics,
+        payload_trim: &mut PayloadTrim,
     ) -> Result<String, ModelError> {
         let execution_model = self.config.execution_model_name
             .as_ref()
@@ -463,27 +514,12 @@ impl AutoGLMClient {
             action_description
         );
 
-        let api_messages = vec![
-            ApiChatMessage {
-                role: ApiMessageRole::System,
-                content: MessageContent::Text(system_prompt),
-            },
-            ApiChatMessage {
-                role: ApiMessageRole::User,
-                content: MessageContent::Multimodal(vec![
-                    crate::agent::llm::types::ContentBlock {
-                        block_type: "image_url".to_string(),
-                        text: None,
-                        image_url: Some(crate::agent::llm::types::ImageUrl::from_base64(screenshot)),
-                    },
-                    crate::agent::llm::types::ContentBlock {
-                        block_type: "text".to_string(),
-                        text: Some(user_message.clone()),
-                        image_url: None,
-                    },
-                ]),
-            },
+        let mut api_messages = vec![
+            ChatMessage::system(system_prompt),
+            ChatMessage::user_with_image(user_message.clone(), screenshot),
         ];
+        self.apply_action_format(&mut api_messages);
+        payload_trim.merge(self.enforce_payload_limits(&mut api_messages));
 
         let request = ChatRequest {
             model: execution_model.to_string(),
@@ -505,13 +541,11 @@ impl AutoGLMClient {
             ModelError::ParseError("执行模型响应中没有选择项".to_string())
         })?;
 
-        let execution_output = match &choice.message.content {
-            MessageContent::Text(text) => text.clone(),
-            _ => return Err(ModelError::ParseError("执行模型输出格式错误".to_string())),
-        };
+        let execution_output = choice.message.text();
 
         let duration = start_time.elapsed().as_millis() as u64;
         info!("执行输出: {} (耗时: {}ms)", execution_output, duration);
+        metrics.add_stage("execution", execution_model, duration);
 
         // 记录到日志文件（对话格式）
         let log_messages = vec![
@@ -545,9 +579,11 @@ impl AutoGLMClient {
         screen_height: u32,
     ) -> Result<ModelResponse, ModelError> {
         let start_time = Instant::now();
+        let mut metrics = PerformanceMetrics::default();
+        let mut payload_trim = PayloadTrim::default();
 
         // 阶段1: 大模型规划（不需要截图，作为提问者）
-        let planning_request = self.plan_action(messages.clone()).await?;
+        let planning_request = self.plan_action(messages.clone(), &mut metrics, &mut payload_trim).await?;
         info!("规划结果: {}", planning_request);
 
         // 阶段2: 小模型执行（需要截图，作为答题者）
@@ -555,19 +591,27 @@ impl AutoGLMClient {
             &planning_request,
             screenshot,
             screen_width,
-            screen_height
+            screen_height,
+            &mut metrics,
+            &mut payload_trim,
         ).await?;
 
         // 尝试解析
-        let (thinking, parsed_actions) = self.parse_response(&content);
+        let (thinking, parsed_actions) = self.parse_response(&content, (screen_width, screen_height));
 
         // 阶段3: 大模型修正（如果解析失败）
         if parsed_actions.is_empty() {
             info!("解析失败，进入阶段3: 大模型修正");
+            let correction_start = Instant::now();
             match self.send_auxiliary_request(&content).await {
                 Ok(corrected_content) => {
                     content = corrected_content;
                     info!("修正完成");
+                    metrics.add_stage(
+                        "correction",
+                        self.config.auxiliary_model_name.as_deref().unwrap_or_default(),
+                        correction_start.elapsed().as_millis() as u64,
+                    );
                 },
                 Err(e) => {
                     error!("修正失败: {}", e);
@@ -579,10 +623,15 @@ impl AutoGLMClient {
         let total_time = start_time.elapsed().as_secs_f64();
 
         // 最终解析
-        let (thinking, parsed_actions) = self.parse_response(&content);
+        let (thinking, parsed_actions) = self.parse_response(&content, (screen_width, screen_height));
+
+        metrics.total_time = total_time;
 
         info!("📊 三阶段性能指标:");
         info!("   总推理时间: {:.3}s", total_time);
+        for stage in &metrics.stages {
+            info!("   {} ({}): {}ms", stage.stage, stage.model, stage.duration_ms);
+        }
         info!("   解析到的操作数: {}", parsed_actions.len());
 
         Ok(ModelResponse {
@@ -591,6 +640,8 @@ impl AutoGLMClient {
             confidence: 0.8,
             reasoning: thinking,
             tokens_used: 0, // 三阶段模式需要单独计算
+            metrics,
+            payload_trim: payload_trim.into_option(),
         })
     }
 }
@@ -601,6 +652,7 @@ impl ModelClient for AutoGLMClient {
         &self,
         messages: Vec<ChatMessage>,
         screenshot: Option<&str>,
+        screen_size: (u32, u32),
     ) -> Result<ModelResponse, ModelError> {
         debug!("查询 AutoGLM，消息数量: {}", messages.len());
 
@@ -609,13 +661,9 @@ impl ModelClient for AutoGLMClient {
             let screenshot = screenshot.ok_or_else(|| {
                 ModelError::ParseError("三阶段模式需要截图".to_string())
             })?;
+            let (screen_width, screen_height) = screen_size;
 
-            // TODO: 从设备获取屏幕尺寸，这里先使用固定值
-            // 后续可以通过参数传入或从配置读取
-            let screen_width = 1080;
-            let screen_height = 2400;
-
-            info!("启用三阶段模式");
+            info!("启用三阶段模式，屏幕分辨率: {}x{}", screen_width, screen_height);
             return self.process_three_stage_internal(
                 messages,
                 screenshot,
@@ -627,43 +675,13 @@ impl ModelClient for AutoGLMClient {
         // 否则使用原有的单阶段流程
         let start_time = Instant::now();
 
-        // 转换消息格式
-        let mut api_messages = vec![];
-
-        // 找到最后一条用户消息的索引（用于添加截图）
-        let last_user_msg_index = messages.iter().rposition(|msg| {
-            matches!(msg.role, MessageRole::User)
-        });
-
-        for (idx, msg) in messages.iter().enumerate() {
-            let role = match msg.role {
-                MessageRole::System => ApiMessageRole::System,
-                MessageRole::User => ApiMessageRole::User,
-                MessageRole::Assistant => ApiMessageRole::Assistant,
-            };
-
-            // 只在最后一条用户消息中添加截图
-            let is_last_user_msg = last_user_msg_index == Some(idx);
-
-            let content = if is_last_user_msg && screenshot.is_some() {
-                MessageContent::Multimodal(vec![
-                    crate::agent::llm::types::ContentBlock {
-                        block_type: "image_url".to_string(),
-                        text: None,
-                        image_url: Some(crate::agent::llm::types::ImageUrl::from_base64(screenshot.unwrap())),
-                    },
-                    crate::agent::llm::types::ContentBlock {
-                        block_type: "text".to_string(),
-                        text: Some(msg.content.clone()),
-                        image_url: None,
-                    },
-                ])
-            } else {
-                MessageContent::Text(msg.content.clone())
-            };
-
-            api_messages.push(ApiChatMessage { role, content });
+        // 只在最后一条用户消息中添加截图
+        let mut api_messages = messages;
+        if let Some(screenshot) = screenshot {
+            attach_screenshot(&mut api_messages, screenshot);
         }
+        self.apply_action_format(&mut api_messages);
+        let payload_trim = self.enforce_payload_limits(&mut api_messages);
 
         // 构建请求
         let request = ChatRequest {
@@ -672,28 +690,61 @@ impl ModelClient for AutoGLMClient {
             max_tokens: Some(self.config.max_tokens),
             temperature: Some(self.config.temperature),
             top_p: Some(self.config.top_p),
-            stream: Some(false),
+            stream: Some(self.config.stream),
         };
 
-        // 发送请求
-        let chat_response = self.send_request(request).await?;
+        let mut metrics = PerformanceMetrics::default();
 
-        // 解析响应
-        let choice = chat_response.choices.first().ok_or_else(|| {
-            ModelError::ParseError("响应中没有选择项".to_string())
-        })?;
+        // 发送请求（流式响应可以得到首 token 时间，但没有 token 用量）
+        let (mut content, usage) = if self.config.stream {
+            let output = self.send_stream_request(request).await?;
+            metrics.time_to_first_token = output.time_to_first_token;
+            metrics.time_to_thinking_end = output.time_to_thinking_end;
+            (output.content, None)
+        } else {
+            let chat_response = self.send_request(request).await?;
 
-        let mut content = match &choice.message.content {
-            MessageContent::Text(text) => text.clone(),
-            _ => "".to_string(),
+            // 解析响应
+            let choice = chat_response.choices.first().ok_or_else(|| {
+                ModelError::ParseError("响应中没有选择项".to_string())
+            })?;
+            (choice.message.text(), chat_response.usage)
         };
+        metrics.add_stage("inference", &self.config.model_name, start_time.elapsed().as_millis() as u64);
+
+        // 使用 AutoGLM 特殊解析
+        let (mut thinking, mut parsed_actions) = self.parse_response(&content, screen_size);
 
-        // 使用辅助模型优化响应（如果配置了辅助模型名称）
-        if self.config.auxiliary_model_name.is_some() {
+        // 只有主模型响应无法解析出操作时才使用辅助模型修正（如果配置了辅助模型名称）
+        let mut corrected = false;
+        if parsed_actions.is_empty() && self.config.auxiliary_model_name.is_some() {
             info!("主模型响应无法解析，使用辅助模型修正");
+            let correction_start = Instant::now();
             match self.send_auxiliary_request(&content).await {
                 Ok(corrected_content) => {
-                    content = corrected_content;
+                    let (corrected_thinking, corrected_actions) = self.parse_response(&corrected_content, screen_size);
+                    if corrected_actions.is_empty() {
+                        warn!("辅助模型修正后仍无法解析，使用原始响应");
+                    } else {
+                        corrected = true;
+                        metrics.add_stage(
+                            "correction",
+                            self.config.auxiliary_model_name.as_deref().unwrap_or_default(),
+                            correction_start.elapsed().as_millis() as u64,
+                        );
+                        self.log_api_call(
+                            "auxiliary_correction",
+                            vec![LogMessage {
+                                role: "user".to_string(),
+                                content: content.clone(),
+                            }],
+                            &corrected_content,
+                            correction_start.elapsed().as_millis() as u64,
+                        ).await;
+                        content = corrected_content;
+                        thinking = corrected_thinking.or(thinking);
+                        parsed_actions = corrected_actions;
+                    }
                 },
                 Err(e) => {
                     warn!("辅助模型修正失败: {}, 使用原始响应", e);
@@ -704,24 +755,30 @@ impl ModelClient for AutoGLMClient {
 
         let total_time = start_time.elapsed().as_secs_f64();
 
-        // 使用 AutoGLM 特殊解析
-        let (thinking, parsed_actions) = self.parse_response(&content);
-
-        let usage = chat_response.usage.unwrap_or(Usage {
+        let usage = usage.unwrap_or(Usage {
             prompt_tokens: 0,
             completion_tokens: 0,
             total_tokens: 0,
         });
 
         // 打印性能指标
-        info!("📊 AutoGLM 性能指标:");
-        info!("   总推理时间: {:.3}s", total_time);
-        info!("   使用 tokens: {}", usage.total_tokens);
-        if let Some(ref t) = thinking {
-            info!("   思考过程: {}", t);
+        debug!("📊 AutoGLM 性能指标:");
+        debug!("   总推理时间: {:.3}s", total_time);
+        if let Some(ttft) = metrics.time_to_first_token {
+            debug!("   首 token 时间: {:.3}s", ttft);
+        }
+        if let Some(thinking_end) = metrics.time_to_thinking_end {
+            debug!("   思考结束时间: {:.3}s", thinking_end);
+        }
+        debug!("   使用 tokens: {}", usage.total_tokens);
+        debug!("   解析到的操作数: {}", parsed_actions.len());
+        debug!("   辅助模型修正: {}", if corrected { "是" } else { "否" });
+        if logger::body_logging().llm_responses {
+            if let Some(ref t) = thinking {
+                info!("AutoGLM 思考过程: {}", t);
+            }
+            info!("AutoGLM 完整响应: {}", &content);
         }
-        info!("   解析到的操作数: {}", parsed_actions.len());
-        info!("   完整响应: {}", &content);
 
         Ok(ModelResponse {
             content: content.clone(),
@@ -729,6 +786,11 @@ impl ModelClient for AutoGLMClient {
             confidence: 0.8,
             reasoning: thinking,
             tokens_used: usage.total_tokens,
+            metrics: PerformanceMetrics {
+                total_time,
+                ..metrics
+            },
+            payload_trim: payload_trim.into_option(),
         })
     }
 
@@ -752,6 +814,23 @@ impl ModelClient for AutoGLMClient {
     }
 }
 
+/// 解析一条 SSE 数据中的增量文本（OpenAI 兼容的 `choices[0].delta.content` 或 token 事件）
+fn parse_stream_token(data: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(data).ok()?;
+    if let Some(content) = value.pointer("/choices/0/delta/content") {
+        return content.as_str().map(str::to_string);
+    }
+    match serde_json::from_value::<StreamEvent>(value).ok()? {
+        StreamEvent::Token { token } => Some(token),
+        StreamEvent::MessageEnd => None,
+    }
+}
+
+/// 已输出的内容中是否已经结束思考、开始给出操作
+fn is_thinking_finished(content: &str) -> bool {
+    ["</think>", "<answer>", "do(", "finish("].iter().any(|marker| content.contains(marker))
+}
+
 /// ChatResponse 类型（如果未在 types.rs 中定义）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatResponse {
@@ -766,7 +845,7 @@ pub struct ChatResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Choice {
     pub index: usize,
-    pub message: ApiChatMessage,
+    pub message: ChatMessage,
     pub finish_reason: Option<String>,
 }
 
@@ -777,6 +856,30 @@ pub struct Usage {
     pub total_tokens: u32,
 }
 
+
+/// 按 `[logging]` 配置记录请求内容：消息文字（不含图片数据）与截图数量、大小
+fn log_request_body(request: &ChatRequest) {
+    let body_logging = logger::body_logging();
+    if body_logging.llm_screenshots {
+        let images: Vec<usize> = request
+            .messages
+            .iter()
+            .filter_map(|m| match &m.content {
+                MessageContent::Multimodal(blocks) => Some(blocks),
+                MessageContent::Text(_) => None,
+            })
+            .flatten()
+            .filter_map(|block| block.image_url.as_ref().map(|image| image.url.len()))
+            .collect();
+        info!("AutoGLM 请求截图: {} 张，大小 {:?} 字节", images.len(), images);
+    }
+    if body_logging.llm_requests {
+        for (i, message) in request.messages.iter().enumerate() {
+            info!("AutoGLM 请求消息 #{} [{:?}]: {}", i, message.role, message.text());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -788,7 +891,7 @@ mod tests {
         let response = r#"Thinking...
 finish(message="Task completed successfully")"#;
 
-        let (thinking, actions) = client.parse_response(response);
+        let (thinking, actions) = client.parse_response(response, (1080, 2400));
 
         // 验证 action 解析成功
         assert!(!actions.is_empty());
@@ -805,7 +908,7 @@ finish(message="Task completed successfully")"#;
         let response = r#"Analyzing screen...
 do(action="Tap", element=[500, 800])"#;
 
-        let (thinking, actions) = client.parse_response(response);
+        let (thinking, actions) = client.parse_response(response, (1080, 2400));
 
         // 验证 thinking 部分（应该是 None，因为没有 <thinking> 标签）
         assert!(thinking.is_none());
@@ -823,7 +926,7 @@ do(action="Tap", element=[500, 800])"#;
         let response = r#"<thinking>I should tap the button at coordinates 100, 200</thinking>
 do(action="Tap", element=[100, 200])"#;
 
-        let (thinking, actions) = client.parse_response(response);
+        let (thinking, actions) = client.parse_response(response, (1080, 2400));
 
         // 验证 thinking 部分（从 <thinking> 标签提取）
         assert_eq!(thinking, Some("I should tap the button at coordinates 100, 200".to_string()));
@@ -840,7 +943,7 @@ do(action="Tap", element=[100, 200])"#;
         let client = AutoGLMClient::new(ModelConfig::default()).unwrap();
         let response = r#"Some random text without markers"#;
 
-        let (thinking, actions) = client.parse_response(response);
+        let (thinking, actions) = client.parse_response(response, (1080, 2400));
 
         // thinking 应该为 None（没有 <thinking> 标签），actions 应该为空
         assert!(thinking.is_none());
@@ -851,11 +954,11 @@ do(action="Tap", element=[100, 200])"#;
     fn test_parse_priority() {
         let client = AutoGLMClient::new(ModelConfig::default()).unwrap();
 
-        // finish(message= 优先级最高
+        // 无法解析的 do(...) 被丢弃，只剩 finish(...)
         let response1 = r#"Text...
 do(action=tap)
 finish(message="done")"#;
-        let (thinking, actions) = client.parse_response(response1);
+        let (thinking, actions) = client.parse_response(response1, (1080, 2400));
         // thinking 应该是 None（没有 <thinking> 标签）
         assert!(thinking.is_none());
         assert_eq!(actions.len(), 1);
@@ -865,13 +968,33 @@ finish(message="done")"#;
         let response2 = r#"<thinking>Thought</thinking>
 <answer>answer content</answer>
 do(action="Launch", app="微信")"#;
-        let (thinking, actions) = client.parse_response(response2);
+        let (thinking, actions) = client.parse_response(response2, (1080, 2400));
         // thinking 应该是 Some("Thought")
         assert_eq!(thinking, Some("Thought".to_string()));
         assert_eq!(actions.len(), 1);
         assert_eq!(actions[0].action_type(), "launch");
     }
 
+    #[test]
+    fn test_parse_do_then_finish() {
+        let client = AutoGLMClient::new(ModelConfig::default()).unwrap();
+
+        // do(...) 按顺序保留，finish(...) 放在最后，多余的 finish(...) 被忽略
+        let response = r#"finish(message="已发送")
+do(action="Type", text="你好")
+do(action="Tap", element=[900, 2200])
+finish(message="重复")"#;
+        let (_, actions) = client.parse_response(response, (1080, 2400));
+        let types: Vec<String> = actions.iter().map(|a| a.action_type()).collect();
+        assert_eq!(types, ["type", "tap", "finish"]);
+        assert!(matches!(&actions[2], ActionEnum::Finish(f) if f.result == "已发送"));
+
+        // finish 消息中的 do( 文本不会被当作操作
+        let (_, actions) = client.parse_response(r#"finish(message="请手动 do(action=\"Back\")")"#, (1080, 2400));
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].action_type(), "finish");
+    }
+
     #[test]
     fn test_parse_do_action_launch() {
         let client = AutoGLMClient::new(ModelConfig::default()).unwrap();
@@ -879,7 +1002,7 @@ do(action="Launch", app="微信")"#;
 do(action="Launch", app="微信")"#;
 
         println!("Testing response: {:?}", response);
-        let (thinking, actions) = client.parse_response(response);
+        let (thinking, actions) = client.parse_response(response, (1080, 2400));
 
         println!("Got thinking: {:?}", thinking);
         println!("Got actions: {:?}", actions);
@@ -897,7 +1020,7 @@ do(action="Launch", app="微信")"#;
         let response = r#"应用正在加载中
 do(action="Wait", duration=1, message="应用正在加载中，请稍等。")"#;
 
-        let (thinking, actions) = client.parse_response(response);
+        let (thinking, actions) = client.parse_response(response, (1080, 2400));
 
         // thinking 应该是 None（没有 <thinking> 标签）
         assert!(thinking.is_none());
@@ -906,6 +1029,25 @@ do(action="Wait", duration=1, message="应用正在加载中，请稍等。")"#;
         assert_eq!(actions[0].action_type(), "wait");
     }
 
+    #[test]
+    fn test_parse_keyed_and_percentage_coordinates() {
+        let client = AutoGLMClient::new(ModelConfig::default()).unwrap();
+
+        let (_, actions) = client.parse_response(r#"do(action="Tap", x=320, y=71%)"#, (1080, 2400));
+        assert!(matches!(&actions[0], ActionEnum::Tap(tap) if tap.x == 320 && tap.y == 1704));
+
+        let (_, actions) = client.parse_response(r#"do(action="Tap", element=[50%, 200.6])"#, (1080, 2400));
+        assert!(matches!(&actions[0], ActionEnum::Tap(tap) if tap.x == 540 && tap.y == 201));
+
+        let (_, actions) = client.parse_response(r#"do(action="Swipe", start=[50%, 80%], end=[50%, 20%])"#, (1000, 2000));
+        assert!(matches!(&actions[0], ActionEnum::Swipe(s)
+            if (s.start_x, s.start_y, s.end_x, s.end_y) == (500, 1600, 500, 400)));
+
+        // 分辨率未知时百分比坐标无法换算
+        let (_, actions) = client.parse_response(r#"do(action="Tap", x=320, y=71%)"#, (0, 0));
+        assert!(actions.is_empty());
+    }
+
     #[test]
     fn test_parse_finish_multiline() {
         let client = AutoGLMClient::new(ModelConfig::default()).unwrap();
@@ -917,7 +1059,7 @@ do(action="Wait", duration=1, message="应用正在加载中，请稍等。")"#;
 
 您想打开哪个应用来浏览？")"#;
 
-        let (thinking, actions) = client.parse_response(response);
+        let (thinking, actions) = client.parse_response(response, (1080, 2400));
 
         // thinking 应该是 None（没有 <thinking> 标签）
         assert!(thinking.is_none());
@@ -935,4 +1077,16 @@ do(action="Wait", duration=1, message="应用正在加载中，请稍等。")"#;
             panic!("Expected FinishAction");
         }
     }
+
+    #[test]
+    fn test_parse_stream_token() {
+        assert_eq!(
+            parse_stream_token(r#"{"choices":[{"delta":{"content":"点击"}}]}"#).as_deref(),
+            Some("点击")
+        );
+        assert_eq!(parse_stream_token(r#"{"type":"token","token":"do("}"#).as_deref(), Some("do("));
+        assert_eq!(parse_stream_token(r#"{"type":"message_end"}"#), None);
+        assert!(!is_thinking_finished("<think>当前在桌面"));
+        assert!(is_thinking_finished("<think>当前在桌面</think>"));
+    }
 }
@@ -1,10 +1,13 @@
 use async_trait::async_trait;
 use reqwest::Client;
 use std::time::Duration;
-use tracing::{debug, error, info};
-use crate::agent::core::traits::{ModelClient, ModelResponse, ModelError, ModelInfo};
+use tracing::{debug, error, info, warn};
+use crate::agent::core::message::{attach_screenshot, ChatMessage};
+use crate::agent::core::traits::{ModelClient, ModelResponse, ModelError, ModelInfo, PerformanceMetrics};
 use crate::agent::llm::types::{ChatRequest, ChatResponse, ModelConfig};
 use crate::agent::llm::parser::parse_action_from_response;
+use crate::agent::actions::base::ActionEnum;
+use crate::agent::llm::payload_guard::PayloadLimits;
 
 /// OpenAI 兼容的 LLM 客户端
 pub struct OpenAIClient {
@@ -56,6 +59,10 @@ impl OpenAIClient {
                 return Err(ModelError::RateLimit);
             }
 
+            if status.is_server_error() {
+                return Err(ModelError::ServerError { status: status.as_u16(), message: response_text });
+            }
+
             return Err(ModelError::ApiError(format!(
                 "请求失败: {} - {}",
                 status, response_text
@@ -76,53 +83,21 @@ impl ModelClient for OpenAIClient {
 
     async fn query_with_messages(
         &self,
-        messages: Vec<crate::agent::core::traits::ChatMessage>,
+        messages: Vec<ChatMessage>,
         screenshot: Option<&str>,
+        screen_size: (u32, u32),
     ) -> Result<ModelResponse, ModelError> {
         debug!("查询 LLM，消息数量: {}", messages.len());
+        let start_time = std::time::Instant::now();
 
-        // 转换消息格式
-        let mut api_messages = vec![];
-
-        // 找到最后一条用户消息的索引（用于添加截图）
-        let last_user_msg_index = messages.iter().rposition(|msg| {
-            matches!(msg.role, crate::agent::core::traits::MessageRole::User)
-        });
-
-        for (idx, msg) in messages.iter().enumerate() {
-            let role = match msg.role {
-                crate::agent::core::traits::MessageRole::System => {
-                    crate::agent::llm::types::MessageRole::System
-                }
-                crate::agent::core::traits::MessageRole::User => {
-                    crate::agent::llm::types::MessageRole::User
-                }
-                crate::agent::core::traits::MessageRole::Assistant => {
-                    crate::agent::llm::types::MessageRole::Assistant
-                }
-            };
-
-            // 只在最后一条用户消息中添加截图
-            let is_last_user_msg = last_user_msg_index == Some(idx);
-
-            let content = if is_last_user_msg && screenshot.is_some() {
-                crate::agent::llm::types::MessageContent::Multimodal(vec![
-                    crate::agent::llm::types::ContentBlock {
-                        block_type: "image_url".to_string(),
-                        text: None,
-                        image_url: Some(crate::agent::llm::types::ImageUrl::from_base64(screenshot.unwrap())),
-                    },
-                    crate::agent::llm::types::ContentBlock {
-                        block_type: "text".to_string(),
-                        text: Some(msg.content.clone()),
-                        image_url: None,
-                    },
-                ])
-            } else {
-                crate::agent::llm::types::MessageContent::Text(msg.content.clone())
-            };
-
-            api_messages.push(crate::agent::llm::types::ChatMessage { role, content });
+        // 只在最后一条用户消息中添加截图
+        let mut api_messages = messages;
+        if let Some(screenshot) = screenshot {
+            attach_screenshot(&mut api_messages, screenshot);
+        }
+        let payload_trim = PayloadLimits::from_config(&self.config).enforce(&mut api_messages);
+        if !payload_trim.is_empty() {
+            warn!("请求体超出限制，已裁剪: {}", payload_trim);
         }
 
         // 构建请求
@@ -143,10 +118,7 @@ impl ModelClient for OpenAIClient {
             ModelError::ParseError("响应中没有选择项".to_string())
         })?;
 
-        let content = match &choice.message.content {
-            crate::agent::llm::types::MessageContent::Text(text) => text.clone(),
-            _ => "".to_string(),
-        };
+        let content = choice.message.text();
 
         let usage = chat_response.usage.unwrap_or(crate::agent::llm::types::Usage {
             prompt_tokens: 0,
@@ -154,17 +126,23 @@ impl ModelClient for OpenAIClient {
             total_tokens: 0,
         });
 
-        // 解析操作（目前返回空 Vec，因为 GPT 客户端暂不使用）
-        // TODO: 实现 ParsedAction 到 ActionEnum 的转换
-        let _action = parse_action_from_response(&content)?;
-        let actions = Vec::new();
+        let (reasoning, actions) = parse_actions(&content, screen_size)?;
+
+        let duration = start_time.elapsed();
+        let mut metrics = PerformanceMetrics {
+            total_time: duration.as_secs_f64(),
+            ..Default::default()
+        };
+        metrics.add_stage("inference", &self.config.model_name, duration.as_millis() as u64);
 
         Ok(ModelResponse {
             content: content.clone(),
             actions,
             confidence: 0.8,
-            reasoning: None,
+            reasoning,
             tokens_used: usage.total_tokens,
+            metrics,
+            payload_trim: payload_trim.into_option(),
         })
     }
 
@@ -177,15 +155,35 @@ impl ModelClient for OpenAIClient {
             context_window: 128000, // GPT-4o 的上下文窗口
         }
     }
+}
 
-    fn set_logger(&self, _logger: Option<std::sync::Arc<crate::agent::logger::AgentLogger>>) {
-        // OpenAI 客户端暂不使用日志记录
+/// 解析回复中的思考内容与全部操作
+///
+/// 按出现顺序解析所有 `do(...)` / `finish(...)`；没有时退回单个操作的宽松解析（JSON、完成关键词）。
+fn parse_actions(content: &str, screen_size: (u32, u32)) -> Result<(Option<String>, Vec<ActionEnum>), ModelError> {
+    let screen_size = Some(screen_size).filter(|&(width, height)| width > 0 && height > 0);
+    let (thinking, actions) = ActionEnum::parse_for_screen(content, screen_size);
+    if !actions.is_empty() {
+        return Ok((thinking, actions));
     }
+
+    let actions = match parse_action_from_response(content)? {
+        Some(parsed) => match ActionEnum::try_from(parsed) {
+            Ok(action) => vec![action],
+            Err(e) => {
+                warn!("{}", e);
+                Vec::new()
+            }
+        },
+        None => Vec::new(),
+    };
+    Ok((thinking, actions))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::core::traits::{ModelCapabilities, ParsedAction};
 
     #[test]
     fn test_model_config_default() {
@@ -203,4 +201,40 @@ mod tests {
         assert_eq!(config.provider, "local");
         assert_eq!(config.base_url, "http://localhost:8000/v1");
     }
+
+    #[test]
+    fn test_default_capabilities() {
+        let client = OpenAIClient::new(ModelConfig::default()).unwrap();
+        assert_eq!(client.capabilities(), ModelCapabilities { vision: true, three_stage: false });
+        client.set_logger(None);
+    }
+
+    #[test]
+    fn test_parse_multiple_actions() {
+        let content = r#"<thinking>先点击搜索框再返回</thinking>
+do(action="Tap", element=[100,200])
+do(action="Back")
+finish(message="已完成")"#;
+        let (reasoning, actions) = parse_actions(content, (1080, 2400)).unwrap();
+        assert_eq!(reasoning.as_deref(), Some("先点击搜索框再返回"));
+        assert!(matches!(
+            actions.as_slice(),
+            [ActionEnum::Tap(_), ActionEnum::Back(_), ActionEnum::Finish(_)]
+        ));
+
+        // 没有 do(...) 时退回 JSON 格式
+        let (_, actions) = parse_actions(r#"{"action": "home"}"#, (0, 0)).unwrap();
+        assert!(matches!(actions.as_slice(), [ActionEnum::Home(_)]));
+    }
+
+    #[test]
+    fn test_parsed_action_conversion() {
+        let parsed = |action_type: &str| ParsedAction {
+            action_type: action_type.to_string(),
+            parameters: serde_json::json!({}),
+            reasoning: String::new(),
+        };
+        assert!(matches!(ActionEnum::try_from(parsed("back")), Ok(ActionEnum::Back(_))));
+        assert!(matches!(ActionEnum::try_from(parsed("fly")), Err(ModelError::ParseError(_))));
+    }
 }
@@ -0,0 +1,287 @@
+//! 模型端点故障切换
+//!
+//! 主端点连续出现网络错误、超时或 5xx 时切换到备用端点；使用备用端点期间，
+//! 每隔 `failover_probe_secs` 秒用下一次请求试探主端点，成功后切换回主端点。
+//! 同一主端点的健康状态在所有客户端之间共享。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::agent::core::message::ChatMessage;
+use crate::agent::core::traits::{ModelClient, ModelError, ModelInfo, ModelResponse};
+use crate::agent::llm::metrics::model_metrics;
+use crate::agent::logger::AgentLogger;
+
+/// 端点切换事件
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelFailoverEvent {
+    /// 主端点的模型名称
+    pub model: String,
+    /// 主端点地址
+    pub endpoint: String,
+    /// 切换后是否使用备用端点
+    pub fallback_active: bool,
+    /// 切换原因
+    pub reason: String,
+}
+
+/// 订阅端点切换事件
+pub fn subscribe_failover_events() -> broadcast::Receiver<ModelFailoverEvent> {
+    failover_sender().subscribe()
+}
+
+fn failover_sender() -> &'static broadcast::Sender<ModelFailoverEvent> {
+    static SENDER: OnceLock<broadcast::Sender<ModelFailoverEvent>> = OnceLock::new();
+    SENDER.get_or_init(|| broadcast::channel(32).0)
+}
+
+/// 主端点健康状态
+#[derive(Debug)]
+struct EndpointHealth {
+    /// 连续失败次数
+    failures: AtomicU32,
+    /// 是否正在使用备用端点
+    fallback_active: AtomicBool,
+    /// 上次切换到备用端点或试探主端点的时间
+    last_probe: Mutex<Instant>,
+}
+
+impl EndpointHealth {
+    /// 获取（或创建）主端点的共享健康状态
+    fn shared(key: &str) -> Arc<Self> {
+        static REGISTRY: OnceLock<Mutex<HashMap<String, Arc<EndpointHealth>>>> = OnceLock::new();
+        let mut registry = REGISTRY.get_or_init(Default::default).lock().unwrap();
+        Arc::clone(registry.entry(key.to_string()).or_insert_with(|| {
+            Arc::new(EndpointHealth {
+                failures: AtomicU32::new(0),
+                fallback_active: AtomicBool::new(false),
+                last_probe: Mutex::new(Instant::now()),
+            })
+        }))
+    }
+
+    /// 本次请求是否发往主端点：未切换，或已到试探时间
+    fn should_try_primary(&self, probe_interval: Duration) -> bool {
+        if !self.fallback_active.load(Ordering::SeqCst) {
+            return true;
+        }
+        let mut last_probe = self.last_probe.lock().unwrap();
+        if last_probe.elapsed() >= probe_interval {
+            *last_probe = Instant::now();
+            return true;
+        }
+        false
+    }
+}
+
+/// 是否为应切换端点的错误（网络错误、超时、5xx）
+pub fn is_failover_error(error: &ModelError) -> bool {
+    matches!(
+        error,
+        ModelError::NetworkError(_) | ModelError::Timeout | ModelError::ServerError { .. }
+    )
+}
+
+/// 带备用端点的模型客户端
+pub struct FailoverClient {
+    primary: Arc<dyn ModelClient>,
+    fallback: Arc<dyn ModelClient>,
+    model: String,
+    endpoint: String,
+    threshold: u32,
+    probe_interval: Duration,
+    health: Arc<EndpointHealth>,
+}
+
+impl FailoverClient {
+    pub fn new(
+        primary: Arc<dyn ModelClient>,
+        fallback: Arc<dyn ModelClient>,
+        model: String,
+        endpoint: String,
+        threshold: u32,
+        probe_interval: Duration,
+    ) -> Self {
+        let health = EndpointHealth::shared(&format!("{}#{}", endpoint, model));
+        Self {
+            primary,
+            fallback,
+            model,
+            endpoint,
+            threshold: threshold.max(1),
+            probe_interval,
+            health,
+        }
+    }
+
+    /// 当前是否使用备用端点
+    pub fn fallback_active(&self) -> bool {
+        self.health.fallback_active.load(Ordering::SeqCst)
+    }
+
+    /// 切换端点，记录指标并发送事件
+    fn switch(&self, fallback_active: bool, reason: String) {
+        if self.health.fallback_active.swap(fallback_active, Ordering::SeqCst) == fallback_active {
+            return;
+        }
+        *self.health.last_probe.lock().unwrap() = Instant::now();
+        self.health.failures.store(0, Ordering::SeqCst);
+
+        if fallback_active {
+            warn!("模型端点 {} ({}) 不可用，切换到备用端点: {}", self.endpoint, self.model, reason);
+        } else {
+            info!("模型端点 {} ({}) 已恢复，切换回主端点", self.endpoint, self.model);
+        }
+        model_metrics().record_failover(&self.model, fallback_active);
+        let _ = failover_sender().send(ModelFailoverEvent {
+            model: self.model.clone(),
+            endpoint: self.endpoint.clone(),
+            fallback_active,
+            reason,
+        });
+    }
+}
+
+#[async_trait]
+impl ModelClient for FailoverClient {
+    async fn query_with_messages(
+        &self,
+        messages: Vec<ChatMessage>,
+        screenshot: Option<&str>,
+        screen_size: (u32, u32),
+    ) -> Result<ModelResponse, ModelError> {
+        if !self.health.should_try_primary(self.probe_interval) {
+            return self.fallback.query_with_messages(messages, screenshot, screen_size).await;
+        }
+
+        let probing = self.fallback_active();
+        match self.primary.query_with_messages(messages.clone(), screenshot, screen_size).await {
+            Ok(response) => {
+                self.health.failures.store(0, Ordering::SeqCst);
+                if probing {
+                    self.switch(false, "主端点试探成功".to_string());
+                }
+                Ok(response)
+            }
+            Err(e) if is_failover_error(&e) => {
+                if probing {
+                    warn!("主端点 {} 仍不可用: {}", self.endpoint, e);
+                    return self.fallback.query_with_messages(messages, screenshot, screen_size).await;
+                }
+                let failures = self.health.failures.fetch_add(1, Ordering::SeqCst) + 1;
+                if failures < self.threshold {
+                    return Err(e);
+                }
+                self.switch(true, format!("连续 {} 次失败: {}", failures, e));
+                self.fallback.query_with_messages(messages, screenshot, screen_size).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn set_logger(&self, logger: Option<Arc<AgentLogger>>) {
+        self.primary.set_logger(logger.clone());
+        self.fallback.set_logger(logger);
+    }
+
+    fn supports_three_stage(&self) -> bool {
+        if self.fallback_active() {
+            self.fallback.supports_three_stage()
+        } else {
+            self.primary.supports_three_stage()
+        }
+    }
+
+    fn info(&self) -> ModelInfo {
+        if self.fallback_active() {
+            self.fallback.info()
+        } else {
+            self.primary.info()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::core::traits::PerformanceMetrics;
+
+    /// 可控制成败的测试客户端
+    struct StubClient {
+        name: &'static str,
+        healthy: AtomicBool,
+    }
+
+    #[async_trait]
+    impl ModelClient for StubClient {
+        async fn query_with_messages(
+            &self,
+            _messages: Vec<ChatMessage>,
+            _screenshot: Option<&str>,
+            _screen_size: (u32, u32),
+        ) -> Result<ModelResponse, ModelError> {
+            if !self.healthy.load(Ordering::SeqCst) {
+                return Err(ModelError::ServerError { status: 503, message: "unavailable".to_string() });
+            }
+            Ok(ModelResponse {
+                content: self.name.to_string(),
+                actions: Vec::new(),
+                confidence: 1.0,
+                reasoning: None,
+                tokens_used: 0,
+                metrics: PerformanceMetrics::default(),
+                payload_trim: None,
+            })
+        }
+
+        fn info(&self) -> ModelInfo {
+            ModelInfo {
+                name: self.name.to_string(),
+                provider: "stub".to_string(),
+                supports_vision: false,
+                max_tokens: 0,
+                context_window: 0,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failover_and_recovery() {
+        let primary = Arc::new(StubClient { name: "primary", healthy: AtomicBool::new(false) });
+        let fallback = Arc::new(StubClient { name: "fallback", healthy: AtomicBool::new(true) });
+        let client = FailoverClient::new(
+            primary.clone(),
+            fallback,
+            "test-failover".to_string(),
+            "http://primary.test".to_string(),
+            2,
+            Duration::ZERO,
+        );
+        let mut events = subscribe_failover_events();
+        let query = || client.query_with_messages(vec![ChatMessage::user("hi")], None, (1080, 2400));
+
+        assert!(query().await.is_err());
+        assert_eq!(query().await.unwrap().content, "fallback");
+        assert!(client.fallback_active());
+
+        // 试探间隔为 0，下一次请求即试探主端点
+        primary.healthy.store(true, Ordering::SeqCst);
+        assert_eq!(query().await.unwrap().content, "primary");
+        assert!(!client.fallback_active());
+
+        let mut switches = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if event.model == "test-failover" {
+                switches.push(event.fallback_active);
+            }
+        }
+        assert_eq!(switches, vec![true, false]);
+    }
+}
@@ -0,0 +1,163 @@
+//! 任务成功评估
+//!
+//! 任务 finish 后，将任务描述、模型声明的结果与最终截图发送给独立的评估模型，
+//! 由其给出是否成功、完成度评分与判断依据，作为任务结果的一部分记录。
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{debug, info};
+use crate::agent::core::traits::ModelError;
+use crate::agent::llm::prompts;
+use crate::agent::llm::types::{
+    ChatMessage, ChatRequest, ChatResponse, ContentBlock, ImageUrl, MessageContent, MessageRole, ModelConfig,
+};
+
+/// 评估模型给出的任务评估结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskEvaluation {
+    /// 是否判定为成功
+    pub success: bool,
+    /// 完成度评分 (0-1)
+    pub score: f32,
+    /// 判断依据
+    pub rationale: String,
+    /// 评估模型名称
+    #[serde(default)]
+    pub model: String,
+}
+
+/// 从评估模型的回复中提取 JSON 结果
+pub fn parse_evaluation(content: &str) -> Option<TaskEvaluation> {
+    let start = content.find('{')?;
+    let end = content.rfind('}')?;
+    let value: serde_json::Value = serde_json::from_str(content.get(start..=end)?).ok()?;
+
+    let success = value.get("success")?.as_bool()?;
+    let score = value
+        .get("score")
+        .and_then(|v| v.as_f64())
+        .map(|s| s.clamp(0.0, 1.0) as f32)
+        .unwrap_or(if success { 1.0 } else { 0.0 });
+    let rationale = value
+        .get("rationale")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    Some(TaskEvaluation { success, score, rationale, model: String::new() })
+}
+
+/// 评估模型客户端（OpenAI 兼容接口）
+pub struct JudgeClient {
+    client: Client,
+    config: ModelConfig,
+    model_name: String,
+}
+
+impl JudgeClient {
+    /// 根据模型配置创建评估客户端，未配置评估模型时返回 None
+    pub fn from_config(config: &ModelConfig) -> Result<Option<Self>, ModelError> {
+        let Some(model_name) = config.judge_model_name.clone() else {
+            return Ok(None);
+        };
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout))
+            .build()
+            .map_err(|e| ModelError::ApiError(format!("创建 HTTP 客户端失败: {}", e)))?;
+
+        Ok(Some(Self { client, config: config.clone(), model_name }))
+    }
+
+    /// 评估任务是否完成
+    ///
+    /// `screenshots` 按时间顺序排列，最后一张为最终界面。
+    pub async fn evaluate(
+        &self,
+        task: &str,
+        result: &str,
+        screenshots: &[String],
+    ) -> Result<TaskEvaluation, ModelError> {
+        info!("使用评估模型 {} 评估任务: {}", self.model_name, task);
+
+        let mut blocks = vec![ContentBlock {
+            block_type: "text".to_string(),
+            text: Some(format!("任务: {}\n执行助手声明的结果: {}", task, result)),
+            image_url: None,
+        }];
+        blocks.extend(screenshots.iter().map(|screenshot| ContentBlock {
+            block_type: "image_url".to_string(),
+            text: None,
+            image_url: Some(ImageUrl::from_base64(screenshot)),
+        }));
+
+        let request = ChatRequest {
+            model: self.model_name.clone(),
+            messages: vec![
+                ChatMessage {
+                    role: MessageRole::System,
+                    content: MessageContent::Text(prompts::get_judge_system_prompt()),
+                },
+                ChatMessage {
+                    role: MessageRole::User,
+                    content: MessageContent::Multimodal(blocks),
+                },
+            ],
+            max_tokens: Some(1024),
+            temperature: Some(0.0),
+            top_p: Some(0.85),
+            stream: Some(false),
+        };
+
+        let url = format!("{}/chat/completions", self.config.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| ModelError::NetworkError(format!("发送请求失败: {}", e)))?;
+
+        let status = response.status();
+        let response_text = response
+            .text()
+            .await
+            .map_err(|e| ModelError::NetworkError(format!("读取响应失败: {}", e)))?;
+        if !status.is_success() {
+            return Err(ModelError::ApiError(format!("请求失败: {} - {}", status, response_text)));
+        }
+
+        let chat_response: ChatResponse = serde_json::from_str(&response_text)
+            .map_err(|e| ModelError::ParseError(format!("解析响应失败: {}", e)))?;
+        let content = match chat_response.choices.first().map(|c| &c.message.content) {
+            Some(MessageContent::Text(text)) => text.clone(),
+            _ => return Err(ModelError::ParseError("评估模型响应中没有文本内容".to_string())),
+        };
+        debug!("评估模型回复: {}", content);
+
+        let mut evaluation = parse_evaluation(&content)
+            .ok_or_else(|| ModelError::ParseError(format!("无法解析评估结果: {}", content)))?;
+        evaluation.model = self.model_name.clone();
+        Ok(evaluation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_evaluation() {
+        let content = "```json\n{\"success\": false, \"score\": 1.4, \"rationale\": \"消息输入框仍有未发送的文本\"}\n```";
+        let evaluation = parse_evaluation(content).unwrap();
+        assert!(!evaluation.success);
+        assert_eq!(evaluation.score, 1.0);
+        assert_eq!(evaluation.rationale, "消息输入框仍有未发送的文本");
+
+        let evaluation = parse_evaluation(r#"{"success": true}"#).unwrap();
+        assert_eq!(evaluation.score, 1.0);
+        assert!(parse_evaluation("任务已完成").is_none());
+    }
+}
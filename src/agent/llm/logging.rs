@@ -0,0 +1,181 @@
+//! 模型查询日志
+//!
+//! [`LoggingClient`] 包装任意模型客户端，把每次查询的模型、各阶段耗时、token 消耗与失败原因写入 Agent 日志，
+//! 并计入按模型汇总的调用统计（[`usage_stats`]）。
+//! 日志归属到发起查询时的任务与步骤（[`AgentLogger::scope`]），查询期间任务结束或开始新任务也不会记错。
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use crate::agent::core::message::ChatMessage;
+use crate::agent::core::traits::{ModelCapabilities, ModelClient, ModelError, ModelInfo, ModelResponse};
+use crate::agent::llm::usage_stats::usage_stats;
+use crate::agent::logger::{AgentLogger, LogScope};
+
+/// 记录查询日志的模型客户端
+pub struct LoggingClient {
+    inner: Arc<dyn ModelClient>,
+    logger: Mutex<Option<Arc<AgentLogger>>>,
+}
+
+impl LoggingClient {
+    pub fn new(inner: Arc<dyn ModelClient>) -> Self {
+        Self { inner, logger: Mutex::new(None) }
+    }
+
+    /// 包装客户端并设置日志记录器
+    pub fn wrap(inner: Arc<dyn ModelClient>, logger: Arc<AgentLogger>) -> Arc<dyn ModelClient> {
+        let client = Self::new(inner);
+        client.set_logger(Some(logger));
+        Arc::new(client)
+    }
+}
+
+#[async_trait]
+impl ModelClient for LoggingClient {
+    async fn query_with_messages(
+        &self,
+        messages: Vec<ChatMessage>,
+        screenshot: Option<&str>,
+        screen_size: (u32, u32),
+    ) -> Result<ModelResponse, ModelError> {
+        let logger = self.logger.lock().unwrap().clone();
+        let scope = match &logger {
+            Some(logger) => logger.scope().await,
+            None => LogScope::default(),
+        };
+        let start_time = Instant::now();
+        let result = self.inner.query_with_messages(messages, screenshot, screen_size).await;
+        // 故障切换客户端查询后才确定实际使用的端点
+        let info = self.inner.info();
+        match &result {
+            Ok(response) => usage_stats().record_success(
+                &info.name,
+                start_time.elapsed().as_millis() as u64,
+                response.tokens_used,
+            ),
+            Err(_) => usage_stats().record_error(&info.name),
+        }
+
+        let Some(logger) = logger else {
+            return result;
+        };
+        let logged = match &result {
+            Ok(response) => {
+                logger
+                    .log_model_metrics(
+                        &scope,
+                        &info.name,
+                        &info.provider,
+                        response.tokens_used,
+                        &response.metrics,
+                        response.payload_trim.as_ref(),
+                    )
+                    .await
+            }
+            Err(e) => {
+                let duration_ms = start_time.elapsed().as_millis() as u64;
+                logger.log_model_error(&scope, &info.name, &info.provider, &e.to_string(), duration_ms).await
+            }
+        };
+        if let Err(e) = logged {
+            warn!("记录模型查询日志失败: {}", e);
+        }
+        result
+    }
+
+    fn set_logger(&self, logger: Option<Arc<AgentLogger>>) {
+        self.inner.set_logger(logger.clone());
+        *self.logger.lock().unwrap() = logger;
+    }
+
+    fn supports_three_stage(&self) -> bool {
+        self.inner.supports_three_stage()
+    }
+
+    fn info(&self) -> ModelInfo {
+        self.inner.info()
+    }
+
+    fn capabilities(&self) -> ModelCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::core::traits::PerformanceMetrics;
+
+    struct StubClient {
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl ModelClient for StubClient {
+        async fn query_with_messages(
+            &self,
+            _messages: Vec<ChatMessage>,
+            _screenshot: Option<&str>,
+            _screen_size: (u32, u32),
+        ) -> Result<ModelResponse, ModelError> {
+            if self.fail {
+                return Err(ModelError::Timeout);
+            }
+            let mut metrics = PerformanceMetrics { total_time: 0.5, ..Default::default() };
+            metrics.add_stage("inference", "stub-model", 500);
+            Ok(ModelResponse {
+                content: String::new(),
+                actions: Vec::new(),
+                confidence: 1.0,
+                reasoning: None,
+                tokens_used: 42,
+                metrics,
+                payload_trim: None,
+            })
+        }
+
+        fn info(&self) -> ModelInfo {
+            ModelInfo {
+                name: "stub-model".to_string(),
+                provider: "stub".to_string(),
+                supports_vision: false,
+                max_tokens: 0,
+                context_window: 0,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_logs_are_scoped_to_task_and_step() {
+        let dir = std::env::temp_dir().join(format!("scrs_model_log_{}", std::process::id()));
+        let logger = Arc::new(AgentLogger::new("agent_1", dir.to_str().unwrap()).unwrap());
+        logger.set_task_id("task_1".to_string()).await;
+        logger.set_step(3);
+
+        let query = |client: Arc<dyn ModelClient>| async move {
+            client.query_with_messages(vec![ChatMessage::user("hi")], None, (1080, 2400)).await
+        };
+        assert!(query(LoggingClient::wrap(Arc::new(StubClient { fail: false }), logger.clone())).await.is_ok());
+        assert!(query(LoggingClient::wrap(Arc::new(StubClient { fail: true }), logger.clone())).await.is_err());
+
+        let content: String = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        let entries: Vec<serde_json::Value> = content.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(entries.len(), 2);
+        for entry in &entries {
+            assert_eq!((entry["task_id"].as_str(), entry["step"].as_u64()), (Some("task_1"), Some(3)));
+            assert_eq!(entry["model"], "stub-model");
+        }
+        assert_eq!((entries[0]["event"].as_str(), entries[0]["tokens_used"].as_u64()), (Some("model_metrics"), Some(42)));
+        assert_eq!(entries[0]["metrics"]["stages"][0]["stage"], "inference");
+        assert_eq!(entries[1]["event"], "model_error");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,157 @@
+//! 模型调用性能指标汇总
+//!
+//! 按模型与阶段累计调用次数和耗时，以及端点故障切换情况，以 Prometheus 文本格式导出（`GET /metrics`）。
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+
+use crate::agent::core::traits::PerformanceMetrics;
+
+/// 累计值（次数与总和）
+#[derive(Debug, Default, Clone, Copy)]
+struct Summary {
+    count: u64,
+    sum: f64,
+}
+
+impl Summary {
+    fn observe(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// 模型 -> 总推理时间（秒）
+    inference: BTreeMap<String, Summary>,
+    /// 模型 -> 首 token 时间（秒）
+    time_to_first_token: BTreeMap<String, Summary>,
+    /// (模型, 阶段) -> 阶段耗时（秒）
+    stages: BTreeMap<(String, String), Summary>,
+    /// (模型, 切换目标) -> 端点切换次数
+    failovers: BTreeMap<(String, &'static str), u64>,
+    /// 模型 -> 是否正在使用备用端点
+    fallback_active: BTreeMap<String, bool>,
+}
+
+/// 模型性能指标汇总
+#[derive(Debug, Default)]
+pub struct ModelMetrics {
+    inner: Mutex<Inner>,
+}
+
+/// 进程内共享的指标汇总
+pub fn model_metrics() -> &'static ModelMetrics {
+    static METRICS: OnceLock<ModelMetrics> = OnceLock::new();
+    METRICS.get_or_init(ModelMetrics::default)
+}
+
+impl ModelMetrics {
+    /// 记录一次模型查询
+    pub fn record(&self, model: &str, metrics: &PerformanceMetrics) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.inference.entry(model.to_string()).or_default().observe(metrics.total_time);
+        if let Some(ttft) = metrics.time_to_first_token {
+            inner.time_to_first_token.entry(model.to_string()).or_default().observe(ttft);
+        }
+        for stage in &metrics.stages {
+            inner
+                .stages
+                .entry((stage.model.clone(), stage.stage.clone()))
+                .or_default()
+                .observe(stage.duration_ms as f64 / 1000.0);
+        }
+    }
+
+    /// 记录一次端点切换
+    pub fn record_failover(&self, model: &str, fallback_active: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        let target = if fallback_active { "fallback" } else { "primary" };
+        *inner.failovers.entry((model.to_string(), target)).or_default() += 1;
+        inner.fallback_active.insert(model.to_string(), fallback_active);
+    }
+
+    /// 导出为 Prometheus 文本格式
+    pub fn render_prometheus(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        let mut out = String::new();
+
+        write_summary(
+            &mut out,
+            "scrs_model_inference_seconds",
+            "模型查询总耗时",
+            inner.inference.iter().map(|(model, s)| (format!("model=\"{}\"", escape(model)), *s)),
+        );
+        write_summary(
+            &mut out,
+            "scrs_model_time_to_first_token_seconds",
+            "流式响应首个 token 时间",
+            inner.time_to_first_token.iter().map(|(model, s)| (format!("model=\"{}\"", escape(model)), *s)),
+        );
+        write_summary(
+            &mut out,
+            "scrs_model_stage_seconds",
+            "各阶段耗时",
+            inner.stages.iter().map(|((model, stage), s)| {
+                (format!("model=\"{}\",stage=\"{}\"", escape(model), escape(stage)), *s)
+            }),
+        );
+
+        let _ = writeln!(out, "# HELP scrs_model_failover_total 模型端点切换次数");
+        let _ = writeln!(out, "# TYPE scrs_model_failover_total counter");
+        for ((model, target), count) in &inner.failovers {
+            let _ = writeln!(out, "scrs_model_failover_total{{model=\"{}\",target=\"{}\"}} {}", escape(model), target, count);
+        }
+        let _ = writeln!(out, "# HELP scrs_model_fallback_active 是否正在使用备用端点");
+        let _ = writeln!(out, "# TYPE scrs_model_fallback_active gauge");
+        for (model, active) in &inner.fallback_active {
+            let _ = writeln!(out, "scrs_model_fallback_active{{model=\"{}\"}} {}", escape(model), u8::from(*active));
+        }
+        out
+    }
+}
+
+fn write_summary(out: &mut String, name: &str, help: &str, series: impl Iterator<Item = (String, Summary)>) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} summary", name);
+    for (labels, summary) in series {
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, summary.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, summary.count);
+    }
+}
+
+/// 转义标签值中的反斜杠、引号与换行
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus() {
+        let registry = ModelMetrics::default();
+        let mut metrics = PerformanceMetrics {
+            time_to_first_token: Some(0.5),
+            total_time: 2.0,
+            ..Default::default()
+        };
+        metrics.add_stage("planning", "glm-4.7", 1200);
+        metrics.add_stage("execution", "autoglm-phone", 800);
+        registry.record("autoglm-phone", &metrics);
+        registry.record("autoglm-phone", &PerformanceMetrics { total_time: 1.0, ..Default::default() });
+        registry.record_failover("autoglm-phone", true);
+
+        let text = registry.render_prometheus();
+        assert!(text.contains("# TYPE scrs_model_inference_seconds summary"));
+        assert!(text.contains("scrs_model_inference_seconds_sum{model=\"autoglm-phone\"} 3"));
+        assert!(text.contains("scrs_model_inference_seconds_count{model=\"autoglm-phone\"} 2"));
+        assert!(text.contains("scrs_model_time_to_first_token_seconds_count{model=\"autoglm-phone\"} 1"));
+        assert!(text.contains("scrs_model_stage_seconds_sum{model=\"glm-4.7\",stage=\"planning\"} 1.2"));
+        assert!(text.contains("scrs_model_failover_total{model=\"autoglm-phone\",target=\"fallback\"} 1"));
+        assert!(text.contains("scrs_model_fallback_active{model=\"autoglm-phone\"} 1"));
+    }
+}
@@ -4,6 +4,13 @@ pub mod parser;
 pub mod providers;
 pub mod autoglm_client;
 pub mod prompts;
+pub mod judge;
+pub mod metrics;
+pub mod failover;
+pub mod logging;
+pub mod usage_stats;
+pub mod payload_guard;
+pub mod thinking;
 
 pub use client::*;
 pub use types::*;
@@ -11,3 +18,4 @@ pub use parser::*;
 pub use providers::*;
 pub use autoglm_client::*;
 pub use prompts::*;
+pub use judge::*;
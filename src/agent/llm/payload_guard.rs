@@ -0,0 +1,252 @@
+//! 请求体大小与图片数量限制
+//!
+//! 多模态请求体过大时网关会直接拒绝（或静默截断）。发送前估算请求体大小，
+//! 超出限制时依次：去掉较早的图片、缩小当前截图、删除最早的对话历史，并返回裁剪报告。
+
+use serde::Serialize;
+
+use crate::agent::core::message::{ChatMessage, ImageUrl, MessageContent, MessageRole};
+use crate::agent::core::screenshot_history::downscale_base64_image;
+use crate::agent::llm::types::ModelConfig;
+
+/// 请求体之外的固定开销估算（模型名、参数等）
+const REQUEST_OVERHEAD_BYTES: usize = 512;
+
+/// 裁剪报告
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PayloadTrim {
+    /// 裁剪前估算的请求体大小（字节）
+    pub original_bytes: usize,
+    /// 裁剪后估算的请求体大小（字节）
+    pub final_bytes: usize,
+    /// 去掉的图片数
+    pub images_removed: usize,
+    /// 缩小的图片数
+    pub images_downsized: usize,
+    /// 删除的历史消息数
+    pub messages_removed: usize,
+}
+
+impl PayloadTrim {
+    /// 是否进行了裁剪
+    pub fn is_empty(&self) -> bool {
+        self.images_removed == 0 && self.images_downsized == 0 && self.messages_removed == 0
+    }
+
+    /// 合并多次请求（如三阶段模式的规划与执行）的裁剪报告
+    pub fn merge(&mut self, other: PayloadTrim) {
+        if other.is_empty() {
+            return;
+        }
+        self.original_bytes += other.original_bytes;
+        self.final_bytes += other.final_bytes;
+        self.images_removed += other.images_removed;
+        self.images_downsized += other.images_downsized;
+        self.messages_removed += other.messages_removed;
+    }
+
+    /// 有裁剪时返回报告
+    pub fn into_option(self) -> Option<Self> {
+        if self.is_empty() { None } else { Some(self) }
+    }
+}
+
+impl std::fmt::Display for PayloadTrim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "请求体 {} -> {} 字节，去掉 {} 张图片，缩小 {} 张图片，删除 {} 条历史消息",
+            self.original_bytes, self.final_bytes, self.images_removed, self.images_downsized, self.messages_removed
+        )
+    }
+}
+
+/// 请求体限制
+#[derive(Debug, Clone, Copy)]
+pub struct PayloadLimits {
+    /// 请求体最大字节数（0 表示不限制）
+    pub max_bytes: usize,
+    /// 最多图片数（0 表示不限制）
+    pub max_images: usize,
+}
+
+impl PayloadLimits {
+    pub fn from_config(config: &ModelConfig) -> Self {
+        Self {
+            max_bytes: config.max_payload_bytes,
+            max_images: config.max_images,
+        }
+    }
+
+    /// 检查并裁剪消息，使请求体满足限制
+    ///
+    /// 最后一条用户消息中的第一张图片视为当前截图，最后才会被缩小；最后一条消息与系统消息不会被删除
+    pub fn enforce(&self, messages: &mut Vec<ChatMessage>) -> PayloadTrim {
+        let mut trim = PayloadTrim {
+            original_bytes: estimate_payload_bytes(messages),
+            ..Default::default()
+        };
+
+        let current = current_image(messages);
+        let mut older = image_positions(messages);
+        older.retain(|position| Some(*position) != current);
+
+        // 1. 图片数量：先去掉较早的图片
+        if self.max_images > 0 {
+            let total = older.len() + usize::from(current.is_some());
+            let excess = total.saturating_sub(self.max_images).min(older.len());
+            trim.images_removed += remove_images(messages, &older[..excess]);
+            older.drain(..excess);
+        }
+
+        if self.max_bytes > 0 {
+            // 2. 去掉较早的图片直到满足大小限制
+            while estimate_payload_bytes(messages) > self.max_bytes && !older.is_empty() {
+                let position = older.remove(0);
+                trim.images_removed += remove_images(messages, &[position]);
+                // 同一条消息中后面的图片下标前移
+                for other in older.iter_mut().filter(|p| p.0 == position.0 && p.1 > position.1) {
+                    other.1 -= 1;
+                }
+            }
+
+            // 3. 缩小当前截图
+            if let Some((message_index, block_index)) = current_image(messages) {
+                let mut downsized = false;
+                while estimate_payload_bytes(messages) > self.max_bytes {
+                    let Some(url) = image_url_mut(messages, message_index, block_index) else {
+                        break;
+                    };
+                    let before = url.url.len();
+                    match downscale_base64_image(&url.url, None) {
+                        Some(smaller) if smaller.len() + ImageUrl::from_base64("").url.len() < before => {
+                            *url = ImageUrl::from_base64(&smaller);
+                            downsized = true;
+                        }
+                        _ => break,
+                    }
+                }
+                trim.images_downsized += usize::from(downsized);
+            }
+
+            // 4. 删除最早的对话历史（保留系统消息与最后一条消息）
+            while estimate_payload_bytes(messages) > self.max_bytes {
+                let Some(index) = (0..messages.len().saturating_sub(1)).find(|&i| messages[i].role != MessageRole::System) else {
+                    break;
+                };
+                messages.remove(index);
+                trim.messages_removed += 1;
+            }
+        }
+
+        trim.final_bytes = estimate_payload_bytes(messages);
+        trim
+    }
+}
+
+/// 估算请求体大小（序列化后的消息加上固定开销）
+pub fn estimate_payload_bytes(messages: &[ChatMessage]) -> usize {
+    serde_json::to_vec(messages).map(|v| v.len()).unwrap_or(0) + REQUEST_OVERHEAD_BYTES
+}
+
+/// 所有图片的位置 (消息下标, 内容块下标)，按出现顺序
+fn image_positions(messages: &[ChatMessage]) -> Vec<(usize, usize)> {
+    messages
+        .iter()
+        .enumerate()
+        .flat_map(|(message_index, message)| match &message.content {
+            MessageContent::Text(_) => Vec::new(),
+            MessageContent::Multimodal(blocks) => blocks
+                .iter()
+                .enumerate()
+                .filter(|(_, block)| block.image_url.is_some())
+                .map(|(block_index, _)| (message_index, block_index))
+                .collect(),
+        })
+        .collect()
+}
+
+/// 当前截图的位置：最后一条用户消息中的第一张图片
+fn current_image(messages: &[ChatMessage]) -> Option<(usize, usize)> {
+    let message_index = messages.iter().rposition(|m| m.role == MessageRole::User)?;
+    image_positions(messages).into_iter().find(|p| p.0 == message_index)
+}
+
+fn image_url_mut(messages: &mut [ChatMessage], message_index: usize, block_index: usize) -> Option<&mut ImageUrl> {
+    match &mut messages.get_mut(message_index)?.content {
+        MessageContent::Multimodal(blocks) => blocks.get_mut(block_index)?.image_url.as_mut(),
+        MessageContent::Text(_) => None,
+    }
+}
+
+/// 去掉指定位置的图片（位置需按顺序给出），返回去掉的数量
+fn remove_images(messages: &mut [ChatMessage], positions: &[(usize, usize)]) -> usize {
+    // 倒序删除，避免下标变化
+    for &(message_index, block_index) in positions.iter().rev() {
+        if let MessageContent::Multimodal(blocks) = &mut messages[message_index].content {
+            blocks.remove(block_index);
+        }
+    }
+    positions.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::core::message::ContentBlock;
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use std::io::Cursor;
+
+    fn noise_png(width: u32, height: u32) -> String {
+        let image = image::RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x * 7 + y * 13) as u8, (x * y) as u8, (x ^ y) as u8])
+        });
+        let mut output = Cursor::new(Vec::new());
+        image::DynamicImage::ImageRgb8(image).write_to(&mut output, image::ImageFormat::Png).unwrap();
+        STANDARD.encode(output.into_inner())
+    }
+
+    #[test]
+    fn test_enforce_limits() {
+        let screenshot = noise_png(400, 800);
+        let history = noise_png(200, 400);
+        let build = || {
+            vec![
+                ChatMessage::system("提示词"),
+                ChatMessage::user("任务: 打开设置"),
+                ChatMessage::assistant("点击设置"),
+                // 与实际请求一致：当前截图在前，历史截图附在文字之后
+                ChatMessage {
+                    role: MessageRole::User,
+                    content: MessageContent::Multimodal(vec![
+                        ContentBlock::image(&screenshot),
+                        ContentBlock::text("继续"),
+                        ContentBlock::image(&history),
+                    ]),
+                },
+            ]
+        };
+
+        // 图片数量限制：只保留当前截图
+        let mut messages = build();
+        let trim = PayloadLimits { max_bytes: 0, max_images: 1 }.enforce(&mut messages);
+        assert_eq!(trim.images_removed, 1);
+        assert_eq!(messages[3].image_count(), 1);
+        assert!(matches!(&messages[3].content, MessageContent::Multimodal(blocks)
+            if blocks[0].image_url.as_ref().unwrap().url.ends_with(&screenshot)));
+
+        // 大小限制：去掉历史图片并缩小当前截图
+        let mut messages = build();
+        let limit = screenshot.len() / 2;
+        let trim = PayloadLimits { max_bytes: limit, max_images: 0 }.enforce(&mut messages);
+        assert_eq!(trim.images_removed, 1);
+        assert_eq!(trim.images_downsized, 1);
+        assert!(trim.final_bytes <= limit);
+        assert_eq!(messages.len(), 4);
+
+        // 未超限时不做任何修改
+        let mut messages = build();
+        assert!(PayloadLimits { max_bytes: 0, max_images: 0 }.enforce(&mut messages).is_empty());
+    }
+}
@@ -47,6 +47,12 @@ Your output should STRICTLY follow the format:
   <answer>
   do(action="Swipe", start=[x1,y1], end=[x2,y2])
   </answer>
+- **Scroll**
+  Scroll the content in a direction without computing coordinates. direction is the direction to browse: "down" reveals content below, "up" reveals content above, "left"/"right" likewise. distance is "short", "half" or "full" (default "half"). Optionally add element=[x,y] to scroll inside a specific list or panel.
+  **Examples**:
+  <answer>
+  do(action="Scroll", direction="down", distance="half")
+  </answer>
 - **Long Press**
   Perform a long press action on a specified screen area.
   You can add the element to the action to specify the long press area. The element is a list of 2 integers, representing the coordinates of the long press point.
@@ -54,6 +60,81 @@ Your output should STRICTLY follow the format:
   <answer>
   do(action="Long Press", element=[x,y])
   </answer>
+- **Switch IME**
+  Switch the input method. Usually unnecessary: Type switches to ADBKeyboard automatically for non-ASCII text, and the original input method is restored after the task.
+  **Example**:
+  <answer>
+  do(action="Switch IME", ime="com.android.adbkeyboard/.AdbIME")
+  </answer>
+- **Volume**
+  Press the volume keys. direction is "up", "down" or "mute"; times is the number of presses, use "max" to reach the maximum or minimum volume.
+  **Example**:
+  <answer>
+  do(action="Volume", direction="up", times="max")
+  </answer>
+- **Power**
+  Press the power button to turn the screen off or on.
+  **Example**:
+  <answer>
+  do(action="Power")
+  </answer>
+- **Media**
+  Control media playback. key is "play_pause", "next" or "previous".
+  **Example**:
+  <answer>
+  do(action="Media", key="play_pause")
+  </answer>
+- **Take Screenshot**
+  Take a system screenshot that is saved on the device (e.g. to share it later).
+  **Example**:
+  <answer>
+  do(action="Take Screenshot")
+  </answer>
+- **Read Notifications**
+  Read the current notifications as a structured list (package, title, text). The list is returned in the action result, no need to open the notification shade.
+  **Example**:
+  <answer>
+  do(action="Read Notifications")
+  </answer>
+- **Tap Notification**
+  Open a notification by its number in the list returned by Read Notifications (index, starting from 1) or by a keyword in its title or text.
+  **Examples**:
+  <answer>
+  do(action="Tap Notification", index=1)
+  </answer>
+  <answer>
+  do(action="Tap Notification", text="张三")
+  </answer>
+- **Clear Notifications**
+  Clear all dismissible notifications.
+  **Example**:
+  <answer>
+  do(action="Clear Notifications")
+  </answer>
+- **Set Location**
+  Set a mock GPS location on the device (latitude and longitude in decimal degrees), e.g. before using maps or ride-hailing apps.
+  **Example**:
+  <answer>
+  do(action="Set Location", latitude=39.9042, longitude=116.4074)
+  </answer>
+- **Clear Location**
+  Stop the mock location and restore the real GPS.
+  **Example**:
+  <answer>
+  do(action="Clear Location")
+  </answer>
+- **Send File**
+  Copy a file from this device to another device (given by its serial in the task), e.g. a photo or a downloaded document. target_path is optional and defaults to the Download folder.
+  **Example**:
+  <answer>
+  do(action="Send File", device="emulator-5556", path="/sdcard/DCIM/Camera/IMG_0001.jpg")
+  </answer>
+- **Send Clipboard**
+  Copy the current clipboard content of this device to the clipboard of another device (given by its serial in the task).
+  **Example**:
+  <answer>
+  do(action="Send Clipboard", device="emulator-5556")
+  </answer>
 - **Launch**
   Launch an app. Try to use launch action when you need to launch an app. Check the instruction to choose the right app before you use this action.
   **Example**:
@@ -289,6 +370,7 @@ mod tests {
         assert!(prompt.contains("do(action=\"Tap\""));
         assert!(prompt.contains("do(action=\"Type\""));
         assert!(prompt.contains("do(action=\"Swipe\""));
+        assert!(prompt.contains("do(action=\"Scroll\""));
         assert!(prompt.contains("finish(message="));
     }
 
@@ -393,7 +475,15 @@ do(action="ActionType", ...)
 - **Tap**: do(action="Tap", element=[x,y])
 - **Type**: do(action="Type", text="...")
 - **Swipe**: do(action="Swipe", start=[x1,y1], end=[x2,y2])
+- **Scroll**: do(action="Scroll", direction="up|down|left|right", distance="short|half|full")（方向为浏览方向，down 表示查看下方内容；可加 element=[x,y] 指定滚动区域）
 - **Long Press**: do(action="Long Press", element=[x,y])
+- **Volume**: do(action="Volume", direction="up|down|mute", times=次数或"max")
+- **Power**: do(action="Power")
+- **Media**: do(action="Media", key="play_pause|next|previous")
+- **Take Screenshot**: do(action="Take Screenshot")（截图保存到设备）
+- **Read Notifications**: do(action="Read Notifications")（结果中返回通知列表）
+- **Tap Notification**: do(action="Tap Notification", index=序号) 或 do(action="Tap Notification", text="关键字")
+- **Clear Notifications**: do(action="Clear Notifications")
 - **Launch**: do(action="Launch", app="应用名")
 - **Back**: do(action="Back")
 - **Wait**: do(action="Wait", duration=秒数, message="...")
@@ -407,3 +497,47 @@ do(action="ActionType", ...)
 - 在thinking中说明你在截图中看到的内容和定位过程
 - 如果无法在截图中找到请求的目标，说明你看到的内容并提出合理推测"#)
 }
+
+/// 获取 JSON 操作输出格式的说明
+/// 模型配置为 JSON 输出时追加到主提示词/执行提示词之后，覆盖其中的 do(...) 输出格式
+pub fn get_json_action_format_prompt() -> String {
+    r#"# 输出格式（覆盖上文的 do(...) 格式）
+只输出一个 JSON 对象，不要输出其他内容:
+{"thinking": "简要说明当前屏幕和决策依据", "actions": [{"action": "Tap", "element": [x, y]}]}
+
+- actions 中每一项的 action 为操作名称，其余字段为参数，名称与上文 do(action=...) 中的操作和参数相同，例如:
+  {"action": "Type", "text": "天气"}
+  {"action": "Swipe", "start": [x1, y1], "end": [x2, y2]}
+  {"action": "Launch", "app": "微信"}
+- 坐标使用整数数组，不要使用字符串
+- 任务完成时: {"thinking": "...", "actions": [{"action": "finish", "message": "结果说明"}]}
+- 不要添加 thinking 和 actions 以外的字段"#.to_string()
+}
+
+/// 获取评估模型的系统提示词
+/// 用于任务结束后由独立模型根据最终截图判定任务是否真正完成
+pub fn get_judge_system_prompt() -> String {
+    r#"# 角色定义
+你是一个手机自动化任务的评审员。你的责任是根据任务描述、执行助手声明的结果以及任务结束时的屏幕截图，客观判断任务是否真正完成。
+
+# 任务说明
+你将收到:
+1. 用户的任务描述
+2. 执行助手在 finish 中给出的结果说明
+3. 任务结束时的一张或多张屏幕截图（按时间顺序，最后一张为最终界面）
+
+你需要:
+1. 对照任务要求检查截图中是否有任务完成的证据（例如消息已发送、页面已打开、设置已生效）
+2. 不要轻信执行助手的结果说明，以截图中可见的内容为准
+3. 给出 0 到 1 之间的完成度评分
+
+# 输出格式
+只输出一个 JSON 对象，不要输出其他内容:
+{"success": true, "score": 0.9, "rationale": "判断依据"}
+
+# 重要提示
+- success 为 true 表示任务已完成，false 表示未完成或无法确认
+- score 表示完成度，1 为完全完成，0 为完全未完成
+- rationale 用一两句中文说明截图中看到的关键证据"#
+        .to_string()
+}
@@ -3,10 +3,31 @@ use crate::agent::llm::client::OpenAIClient;
 use crate::agent::llm::autoglm_client::AutoGLMClient;
 use crate::agent::llm::types::ModelConfig;
 use crate::agent::core::traits::ModelError;
+use crate::agent::llm::failover::FailoverClient;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// 创建模型客户端（工厂函数）
+///
+/// 配置了备用端点时返回带故障切换的客户端
 pub fn create_model_client(config: &ModelConfig) -> Result<Arc<dyn ModelClient>, ModelError> {
+    let primary = create_provider_client(config)?;
+    let Some(fallback_config) = &config.fallback else {
+        return Ok(primary);
+    };
+    let fallback = create_provider_client(fallback_config)?;
+    Ok(Arc::new(FailoverClient::new(
+        primary,
+        fallback,
+        config.model_name.clone(),
+        config.base_url.clone(),
+        config.failover_threshold,
+        Duration::from_secs(config.failover_probe_secs),
+    )))
+}
+
+/// 按提供商创建单个端点的客户端
+fn create_provider_client(config: &ModelConfig) -> Result<Arc<dyn ModelClient>, ModelError> {
     match config.provider.as_str() {
         "openai" | "azure" => {
             let client = OpenAIClient::new(config.clone())?;
@@ -0,0 +1,131 @@
+//! 模型思考过程的实时推送
+//!
+//! 流式响应时，模型客户端把收到的 token 按 [`THINKING_FLUSH_INTERVAL`] 合并后广播为 [`ThinkingEvent`]，
+//! Socket.IO 客户端订阅后以 `agent/thinking` 事件接收，不必等整步完成就能看到模型的推理过程。
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// 合并 token 的时间窗口
+pub const THINKING_FLUSH_INTERVAL: Duration = Duration::from_millis(150);
+
+/// 缓冲超过该字符数时立即推送
+const MAX_BUFFERED_CHARS: usize = 256;
+
+/// 回复所处的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThinkingPhase {
+    /// 思考过程
+    Thinking,
+    /// 思考结束后的回答（操作指令）
+    Answer,
+}
+
+/// 一段增量 token
+#[derive(Debug, Clone, Serialize)]
+pub struct ThinkingEvent {
+    pub agent_id: String,
+    pub device_serial: Option<String>,
+    pub phase: ThinkingPhase,
+    /// 自上次推送以来新增的文本
+    pub delta: String,
+    /// 本次回复是否结束
+    pub done: bool,
+}
+
+/// 订阅思考过程事件
+pub fn subscribe_thinking_events() -> broadcast::Receiver<ThinkingEvent> {
+    thinking_sender().subscribe()
+}
+
+/// 广播思考过程事件（没有订阅者时直接丢弃）
+pub fn publish_thinking_event(event: ThinkingEvent) {
+    let _ = thinking_sender().send(event);
+}
+
+fn thinking_sender() -> &'static broadcast::Sender<ThinkingEvent> {
+    static SENDER: OnceLock<broadcast::Sender<ThinkingEvent>> = OnceLock::new();
+    SENDER.get_or_init(|| broadcast::channel(256).0)
+}
+
+/// 单次流式回复的 token 合并器
+pub struct ThinkingStream {
+    agent_id: String,
+    device_serial: Option<String>,
+    phase: ThinkingPhase,
+    buffer: String,
+    last_flush: Instant,
+}
+
+impl ThinkingStream {
+    pub fn new(agent_id: impl Into<String>, device_serial: Option<String>) -> Self {
+        Self {
+            agent_id: agent_id.into(),
+            device_serial,
+            phase: ThinkingPhase::Thinking,
+            buffer: String::new(),
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// 追加 token，`thinking_finished` 表示到目前为止的回复中思考已结束；需要推送时返回事件
+    pub fn push(&mut self, token: &str, thinking_finished: bool) -> Option<ThinkingEvent> {
+        // 思考结束时先推送缓冲的思考内容，新 token 归入回答
+        if thinking_finished && self.phase == ThinkingPhase::Thinking {
+            let flushed = (!self.buffer.is_empty()).then(|| self.take(false));
+            self.phase = ThinkingPhase::Answer;
+            self.buffer.push_str(token);
+            return flushed;
+        }
+        self.buffer.push_str(token);
+        let due = self.last_flush.elapsed() >= THINKING_FLUSH_INTERVAL || self.buffer.chars().count() >= MAX_BUFFERED_CHARS;
+        due.then(|| self.take(false))
+    }
+
+    /// 回复结束，推送剩余内容
+    pub fn finish(&mut self) -> ThinkingEvent {
+        self.take(true)
+    }
+
+    fn take(&mut self, done: bool) -> ThinkingEvent {
+        self.last_flush = Instant::now();
+        ThinkingEvent {
+            agent_id: self.agent_id.clone(),
+            device_serial: self.device_serial.clone(),
+            phase: self.phase,
+            delta: std::mem::take(&mut self.buffer),
+            done,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_are_coalesced_until_phase_change() {
+        let mut stream = ThinkingStream::new("agent_1", Some("emulator-5554".to_string()));
+
+        assert!(stream.push("<think>先打开", false).is_none());
+        assert!(stream.push("设置", false).is_none());
+
+        let thinking = stream.push("</think>do(", true).unwrap();
+        assert_eq!((thinking.phase, thinking.delta.as_str(), thinking.done), (ThinkingPhase::Thinking, "<think>先打开设置", false));
+
+        let answer = stream.finish();
+        assert_eq!((answer.phase, answer.delta.as_str(), answer.done), (ThinkingPhase::Answer, "</think>do(", true));
+    }
+
+    #[test]
+    fn test_large_buffer_flushes_immediately() {
+        let mut stream = ThinkingStream::new("agent_1", None);
+        let event = stream.push(&"想".repeat(MAX_BUFFERED_CHARS), false).unwrap();
+        assert_eq!(event.delta.chars().count(), MAX_BUFFERED_CHARS);
+        assert!(stream.finish().delta.is_empty());
+    }
+}
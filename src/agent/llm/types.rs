@@ -1,56 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-/// LLM 请求消息
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ChatMessage {
-    pub role: MessageRole,
-    pub content: MessageContent,
-}
-
-/// 消息角色
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
-pub enum MessageRole {
-    System,
-    User,
-    Assistant,
-}
-
-/// 消息内容（支持文本和图片）
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum MessageContent {
-    Text(String),
-    Multimodal(Vec<ContentBlock>),
-}
-
-/// 内容块
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ContentBlock {
-    #[serde(rename = "type")]
-    pub block_type: String,
-
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub text: Option<String>,
+use crate::agent::actions::ActionFormat;
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub image_url: Option<ImageUrl>,
-}
-
-/// 图片 URL
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ImageUrl {
-    pub url: String,
-}
-
-impl ImageUrl {
-    /// 从 base64 创建图片 URL
-    pub fn from_base64(base64_data: &str) -> Self {
-        Self {
-            url: format!("data:image/png;base64,{}", base64_data),
-        }
-    }
-}
+pub use crate::agent::core::message::{ChatMessage, ContentBlock, ImageUrl, MessageContent, MessageRole};
 
 /// LLM 请求
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -90,8 +42,9 @@ pub struct Usage {
     pub total_tokens: u32,
 }
 
-/// 模型配置
+/// 模型配置（缺省字段使用默认值，便于在配置文件中只写需要的字段，如备用端点）
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModelConfig {
     /// 模型提供商（openai, local, azure）
     pub provider: String,
@@ -132,6 +85,59 @@ pub struct ModelConfig {
     /// 是否启用三阶段模式
     /// 启用后，使用大模型规划，小模型执行，大模型修正的三阶段流程
     pub enable_three_stage: bool,
+
+    /// 评估模型名称（可选，用于任务 finish 后根据最终截图判定是否成功）
+    /// 如果为 None，则不进行评估
+    #[serde(default)]
+    pub judge_model_name: Option<String>,
+
+    /// 任务启动时允许指定的模型名称（已配置的主/规划/执行/辅助模型总是允许）
+    #[serde(default)]
+    pub allowed_task_models: Vec<String>,
+
+    /// 是否使用流式响应（可以测量首 token 时间，但拿不到 token 用量）
+    #[serde(default)]
+    pub stream: bool,
+
+    /// 备用模型端点（可选），主端点连续网络错误或 5xx 时切换到备用端点
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<Box<ModelConfig>>,
+
+    /// 连续失败多少次后切换到备用端点
+    #[serde(default = "default_failover_threshold")]
+    pub failover_threshold: u32,
+
+    /// 使用备用端点期间，每隔多少秒尝试一次主端点
+    #[serde(default = "default_failover_probe_secs")]
+    pub failover_probe_secs: u64,
+
+    /// 请求体最大字节数（0 表示不限制），超出时裁剪图片与历史消息
+    #[serde(default = "default_max_payload_bytes")]
+    pub max_payload_bytes: usize,
+
+    /// 单次请求最多图片数（0 表示不限制）
+    #[serde(default)]
+    pub max_images: usize,
+
+    /// 模型输出操作的格式（pseudo_code / json），JSON 解析失败时退回伪代码解析
+    #[serde(default)]
+    pub action_format: ActionFormat,
+
+    /// 每百万 tokens 的价格（按模型名称），用于 `/stats/models` 估算费用
+    #[serde(default)]
+    pub token_prices: std::collections::BTreeMap<String, f64>,
+}
+
+fn default_max_payload_bytes() -> usize {
+    8 * 1024 * 1024
+}
+
+fn default_failover_threshold() -> u32 {
+    3
+}
+
+fn default_failover_probe_secs() -> u64 {
+    60
 }
 
 impl Default for ModelConfig {
@@ -149,17 +155,43 @@ impl Default for ModelConfig {
             planning_model_name: None,
             execution_model_name: None,
             enable_three_stage: false,
+            judge_model_name: None,
+            allowed_task_models: Vec::new(),
+            stream: false,
+            fallback: None,
+            failover_threshold: default_failover_threshold(),
+            failover_probe_secs: default_failover_probe_secs(),
+            max_payload_bytes: default_max_payload_bytes(),
+            max_images: 0,
+            action_format: ActionFormat::default(),
+            token_prices: Default::default(),
         }
     }
 }
 
 impl ModelConfig {
+    /// 解析 API Key 中的密钥引用（`file:` / `keychain:` / `env:`，见 [`crate::secrets`]），
+    /// 并登记解析结果，使其不出现在日志与错误信息中
+    pub fn resolve_secrets(&mut self) -> Result<(), String> {
+        self.api_key = crate::secrets::resolve_secret(&self.api_key)?;
+        crate::secrets::register_secret(&self.api_key);
+        if let Some(fallback) = &mut self.fallback {
+            fallback.resolve_secrets()?;
+        }
+        Ok(())
+    }
+
     /// 从环境变量加载配置
     pub fn from_env() -> Self {
         let mut config = Self::default();
 
-        if let Ok(api_key) = std::env::var("OPENAI_API_KEY") {
-            config.api_key = api_key;
+        match crate::secrets::env_secret("OPENAI_API_KEY") {
+            Some(Ok(api_key)) => {
+                crate::secrets::register_secret(&api_key);
+                config.api_key = api_key;
+            }
+            Some(Err(e)) => tracing::warn!("读取 OPENAI_API_KEY 失败: {}", e),
+            None => {}
         }
 
         if let Ok(base_url) = std::env::var("OPENAI_BASE_URL") {
@@ -188,6 +220,16 @@ impl ModelConfig {
             planning_model_name: None,
             execution_model_name: None,
             enable_three_stage: false,
+            judge_model_name: None,
+            allowed_task_models: Vec::new(),
+            stream: false,
+            fallback: None,
+            failover_threshold: default_failover_threshold(),
+            failover_probe_secs: default_failover_probe_secs(),
+            max_payload_bytes: default_max_payload_bytes(),
+            max_images: 0,
+            action_format: ActionFormat::default(),
+            token_prices: Default::default(),
         }
     }
 
@@ -211,6 +253,16 @@ impl ModelConfig {
             planning_model_name: None,
             execution_model_name: None,
             enable_three_stage: false,
+            judge_model_name: None,
+            allowed_task_models: Vec::new(),
+            stream: false,
+            fallback: None,
+            failover_threshold: default_failover_threshold(),
+            failover_probe_secs: default_failover_probe_secs(),
+            max_payload_bytes: default_max_payload_bytes(),
+            max_images: 0,
+            action_format: ActionFormat::default(),
+            token_prices: Default::default(),
         }
     }
 }
@@ -0,0 +1,262 @@
+//! 按模型汇总的调用统计
+//!
+//! 按日期（UTC）与模型累计调用次数、失败次数、耗时与 token 消耗，定期保存到 [`USAGE_STATS_FILE`]，重启后继续累计。
+//! `GET /stats/models` 按 `[model.token_prices]` 配置的单价估算费用，用于对比不同模型的用量、发现耗时或失败率的退化。
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// 统计数据文件
+pub const USAGE_STATS_FILE: &str = "stats/model_usage.json";
+
+/// 保留的天数
+const RETENTION_DAYS: i64 = 90;
+
+/// 保存间隔
+const PERSIST_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 累计值
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageCounters {
+    pub calls: u64,
+    pub errors: u64,
+    /// 成功调用的总耗时（毫秒）
+    pub latency_ms: u64,
+    pub tokens: u64,
+}
+
+impl UsageCounters {
+    fn add(&mut self, other: &UsageCounters) {
+        self.calls += other.calls;
+        self.errors += other.errors;
+        self.latency_ms += other.latency_ms;
+        self.tokens += other.tokens;
+    }
+}
+
+/// 一段时间内的用量汇总
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageSummary {
+    pub calls: u64,
+    pub errors: u64,
+    /// 失败率（0 ~ 1）
+    pub error_rate: f64,
+    /// 成功调用的平均耗时（毫秒）
+    pub avg_latency_ms: Option<f64>,
+    pub tokens: u64,
+    /// 估算费用，没有配置该模型单价时为空
+    pub estimated_cost: Option<f64>,
+}
+
+impl UsageSummary {
+    fn new(counters: &UsageCounters, price_per_million: Option<f64>) -> Self {
+        let successes = counters.calls - counters.errors;
+        Self {
+            calls: counters.calls,
+            errors: counters.errors,
+            error_rate: if counters.calls == 0 { 0.0 } else { counters.errors as f64 / counters.calls as f64 },
+            avg_latency_ms: (successes > 0).then(|| counters.latency_ms as f64 / successes as f64),
+            tokens: counters.tokens,
+            estimated_cost: price_per_million.map(|price| counters.tokens as f64 / 1_000_000.0 * price),
+        }
+    }
+}
+
+/// 某天的用量
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    #[serde(flatten)]
+    pub summary: UsageSummary,
+}
+
+/// 一个模型的用量
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelUsage {
+    pub model: String,
+    #[serde(flatten)]
+    pub summary: UsageSummary,
+    /// 按日期升序的每日用量
+    pub daily: Vec<DailyUsage>,
+}
+
+/// 日期 -> 模型 -> 累计值
+type DailyCounters = BTreeMap<NaiveDate, BTreeMap<String, UsageCounters>>;
+
+/// 模型调用统计
+#[derive(Debug, Default)]
+pub struct ModelUsageStats {
+    days: Mutex<DailyCounters>,
+    /// 上次保存后有新的记录
+    dirty: AtomicBool,
+}
+
+/// 进程内共享的调用统计
+pub fn usage_stats() -> &'static ModelUsageStats {
+    static STATS: OnceLock<ModelUsageStats> = OnceLock::new();
+    STATS.get_or_init(ModelUsageStats::default)
+}
+
+impl ModelUsageStats {
+    /// 记录一次成功的调用
+    pub fn record_success(&self, model: &str, latency_ms: u64, tokens: u32) {
+        let counters = UsageCounters { calls: 1, errors: 0, latency_ms, tokens: tokens as u64 };
+        self.record_at(Utc::now().date_naive(), model, &counters);
+    }
+
+    /// 记录一次失败的调用
+    pub fn record_error(&self, model: &str) {
+        let counters = UsageCounters { calls: 1, errors: 1, ..Default::default() };
+        self.record_at(Utc::now().date_naive(), model, &counters);
+    }
+
+    fn record_at(&self, date: NaiveDate, model: &str, counters: &UsageCounters) {
+        let mut days = self.days.lock().unwrap();
+        days.entry(date).or_default().entry(model.to_string()).or_default().add(counters);
+        if let Some(oldest) = date.checked_sub_signed(chrono::Duration::days(RETENTION_DAYS)) {
+            days.retain(|day, _| *day > oldest);
+        }
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// 截至 `today` 最近 `days` 天（0 表示全部）各模型的用量，按调用次数降序
+    pub fn report(&self, days: u32, today: NaiveDate, prices: &BTreeMap<String, f64>) -> Vec<ModelUsage> {
+        let since = match days {
+            0 => NaiveDate::MIN,
+            days => today - chrono::Duration::days(days as i64 - 1),
+        };
+        let data = self.days.lock().unwrap();
+
+        let mut models: BTreeMap<&str, Vec<(NaiveDate, UsageCounters)>> = BTreeMap::new();
+        for (date, usage) in data.range(since..) {
+            for (model, counters) in usage {
+                models.entry(model).or_default().push((*date, *counters));
+            }
+        }
+
+        let mut report: Vec<ModelUsage> = models
+            .into_iter()
+            .map(|(model, daily)| {
+                let price = prices.get(model).copied();
+                let mut total = UsageCounters::default();
+                for (_, counters) in &daily {
+                    total.add(counters);
+                }
+                ModelUsage {
+                    model: model.to_string(),
+                    summary: UsageSummary::new(&total, price),
+                    daily: daily
+                        .iter()
+                        .map(|(date, counters)| DailyUsage { date: *date, summary: UsageSummary::new(counters, price) })
+                        .collect(),
+                }
+            })
+            .collect();
+        report.sort_by_key(|usage| std::cmp::Reverse(usage.summary.calls));
+        report
+    }
+
+    /// 读取保存的统计并合并到当前数据
+    pub fn load(&self, path: &Path) -> std::io::Result<()> {
+        let json = match std::fs::read(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let saved: DailyCounters = serde_json::from_slice(&json)?;
+        let mut days = self.days.lock().unwrap();
+        for (date, usage) in saved {
+            let day = days.entry(date).or_default();
+            for (model, counters) in usage {
+                day.entry(model).or_default().add(&counters);
+            }
+        }
+        Ok(())
+    }
+
+    /// 有新的记录时保存到文件
+    pub fn save_if_dirty(&self, path: &Path) -> std::io::Result<()> {
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let json = serde_json::to_vec_pretty(&*self.days.lock().unwrap())?;
+        let result = (|| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(path, json)
+        })();
+        if result.is_err() {
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+        result
+    }
+}
+
+/// 读取保存的统计，之后每分钟保存一次
+pub fn spawn_usage_persistence(path: impl Into<PathBuf>) {
+    let path = path.into();
+    if let Err(e) = usage_stats().load(&path) {
+        warn!("读取模型调用统计 {:?} 失败: {}", path, e);
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PERSIST_INTERVAL);
+        loop {
+            interval.tick().await;
+            let path = path.clone();
+            let result = tokio::task::spawn_blocking(move || usage_stats().save_if_dirty(&path)).await;
+            if let Ok(Err(e)) = result {
+                warn!("保存模型调用统计失败: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    #[test]
+    fn test_report_and_persistence() {
+        let stats = ModelUsageStats::default();
+        let success = |latency_ms, tokens| UsageCounters { calls: 1, errors: 0, latency_ms, tokens };
+        let error = UsageCounters { calls: 1, errors: 1, ..Default::default() };
+        stats.record_at(date(1), "glm-4.7", &success(3000, 2_000_000));
+        stats.record_at(date(2), "autoglm-phone", &success(800, 1000));
+        stats.record_at(date(2), "autoglm-phone", &success(1200, 1000));
+        stats.record_at(date(3), "autoglm-phone", &error);
+
+        let prices = BTreeMap::from([("glm-4.7".to_string(), 2.0)]);
+        let report = stats.report(0, date(3), &prices);
+        assert_eq!(report.iter().map(|u| u.model.as_str()).collect::<Vec<_>>(), vec!["autoglm-phone", "glm-4.7"]);
+        let autoglm = &report[0].summary;
+        assert_eq!((autoglm.calls, autoglm.errors, autoglm.tokens), (3, 1, 2000));
+        assert!((autoglm.error_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!((autoglm.avg_latency_ms, autoglm.estimated_cost), (Some(1000.0), None));
+        assert_eq!(report[0].daily.iter().map(|d| d.date).collect::<Vec<_>>(), vec![date(2), date(3)]);
+        assert_eq!(report[0].daily[1].summary.avg_latency_ms, None);
+        assert_eq!(report[1].summary.estimated_cost, Some(4.0));
+
+        // 只统计最近两天
+        let report = stats.report(2, date(3), &prices);
+        assert_eq!(report.len(), 1);
+
+        let path = std::env::temp_dir().join(format!("scrs_model_usage_{}.json", std::process::id()));
+        stats.save_if_dirty(&path).unwrap();
+        let restored = ModelUsageStats::default();
+        restored.load(&path).unwrap();
+        assert_eq!(restored.report(0, date(3), &prices), stats.report(0, date(3), &prices));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -4,8 +4,44 @@ use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::Mutex;
 use base64::Engine;
+use crate::agent::core::screenshot_store::{strip_data_url, ScreenshotLogPolicy, ScreenshotPersistence};
+
+/// 读取日志目录下全部 `.jsonl` 文件中的事件，目录不存在时返回空
+///
+/// `filter` 先按原始行做字符串匹配，避免逐行解析整个日志；通过的行解析为 JSON，无法解析的行被跳过
+pub fn scan_log_events(log_dir: impl AsRef<Path>, filter: impl Fn(&str) -> bool) -> std::io::Result<Vec<serde_json::Value>> {
+    let entries = match fs::read_dir(log_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut events = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+        for line in fs::read_to_string(&path)?.lines() {
+            if !filter(line) {
+                continue;
+            }
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(line) {
+                events.push(value);
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// 读取日志中属于某个任务的指定事件
+pub fn read_task_events(log_dir: impl AsRef<Path>, event: &str, task_id: &str) -> std::io::Result<Vec<serde_json::Value>> {
+    let mut events = scan_log_events(log_dir, |line| line.contains(event) && line.contains(task_id))?;
+    events.retain(|value| value["event"] == event && value["task_id"] == task_id);
+    Ok(events)
+}
 
 /// Agent 操作日志条目
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,12 +88,24 @@ pub struct ActionResultLog {
     pub duration_ms: u32,
 }
 
+/// 日志条目归属的任务与步骤
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LogScope {
+    pub task_id: Option<String>,
+    pub step: usize,
+}
+
 /// Agent 日志记录器
 pub struct AgentLogger {
     agent_id: String,
+    device_serial: Option<String>,
     log_dir: String,
     log_file: Arc<Mutex<std::fs::File>>,
     current_task_id: Arc<Mutex<Option<String>>>,
+    /// 正在执行的步骤，模型查询的日志据此归属到步骤
+    current_step: AtomicUsize,
+    /// 截图保存策略
+    screenshot_policy: ScreenshotLogPolicy,
 }
 
 impl AgentLogger {
@@ -77,36 +125,94 @@ impl AgentLogger {
 
         Ok(Self {
             agent_id: agent_id.to_string(),
+            device_serial: None,
             log_dir: log_dir.to_string(),
             log_file: Arc::new(Mutex::new(log_file)),
             current_task_id: Arc::new(Mutex::new(None)),
+            current_step: AtomicUsize::new(0),
+            screenshot_policy: ScreenshotLogPolicy::default(),
         })
     }
 
-    /// 设置当前任务 ID
+    /// 记录 Agent 所控制的设备
+    pub fn with_device_serial(mut self, serial: &str) -> Self {
+        self.device_serial = Some(serial.to_string());
+        self
+    }
+
+    /// 设置截图保存策略（格式、内嵌缩略图或不保存）
+    pub fn with_screenshot_policy(mut self, policy: ScreenshotLogPolicy) -> Self {
+        self.screenshot_policy = policy;
+        self
+    }
+
+    /// Agent ID
+    pub fn agent_id(&self) -> &str {
+        &self.agent_id
+    }
+
+    /// Agent 所控制的设备序列号
+    pub fn device_serial(&self) -> Option<&str> {
+        self.device_serial.as_deref()
+    }
+
+    /// 设置当前任务 ID（步骤从 0 重新开始）
     pub async fn set_task_id(&self, task_id: String) {
         *self.current_task_id.lock().await = Some(task_id);
+        self.current_step.store(0, Ordering::SeqCst);
     }
 
-    /// 保存截图到文件
-    pub fn save_screenshot(&self, step_number: usize, screenshot_base64: &str) -> Result<String, std::io::Error> {
-        // 创建 screenshots 子目录
-        let screenshots_dir = format!("{}/screenshots", self.log_dir);
-        fs::create_dir_all(&screenshots_dir)?;
+    /// 设置正在执行的步骤
+    pub fn set_step(&self, step: usize) {
+        self.current_step.store(step, Ordering::SeqCst);
+    }
 
-        // 生成文件名：agent_id_step_timestamp.png
-        let timestamp = Utc::now().format("%Y%m%d_%H%M%S_%3f").to_string();
-        let filename = format!("{}/{}_step_{}.png", screenshots_dir, self.agent_id, timestamp);
+    /// 正在执行的步骤
+    pub fn current_step(&self) -> usize {
+        self.current_step.load(Ordering::SeqCst)
+    }
+
+    /// 当前的任务与步骤
+    pub async fn scope(&self) -> LogScope {
+        LogScope {
+            task_id: self.current_task_id.lock().await.clone(),
+            step: self.current_step(),
+        }
+    }
+
+    /// 按截图保存策略保存截图：返回文件路径，内嵌模式下返回缩略图的 base64，不保存截图时返回 None
+    pub fn save_screenshot(&self, step_number: usize, screenshot_base64: &str) -> Result<Option<String>, std::io::Error> {
+        if self.screenshot_policy.mode == ScreenshotPersistence::None {
+            return Ok(None);
+        }
 
         // 解码 base64
-        let base64_data = screenshot_base64.trim_start_matches("data:image/png;base64,");
+        let base64_data = strip_data_url(screenshot_base64);
         let image_bytes = base64::engine::general_purpose::STANDARD.decode(base64_data)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Base64 解码失败: {}", e)))?;
 
+        if self.screenshot_policy.mode == ScreenshotPersistence::Inline {
+            return self.screenshot_policy.thumbnail(&image_bytes).map(Some);
+        }
+
+        // 创建 screenshots 子目录
+        let screenshots_dir = format!("{}/screenshots", self.log_dir);
+        fs::create_dir_all(&screenshots_dir)?;
+
+        // 生成文件名：agent_id_step_timestamp.<格式扩展名>
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S_%3f").to_string();
+        let filename = format!(
+            "{}/{}_step_{}.{}",
+            screenshots_dir,
+            self.agent_id,
+            timestamp,
+            self.screenshot_policy.format.extension()
+        );
+
         // 写入文件
-        fs::write(&filename, image_bytes)?;
+        fs::write(&filename, self.screenshot_policy.encode(&image_bytes)?)?;
 
-        Ok(filename)
+        Ok(Some(filename))
     }
 
     /// 记录操作
@@ -126,7 +232,7 @@ impl AgentLogger {
         // 如果有截图，保存到文件并记录路径
         let screenshot_path = if let Some(ref base64) = screenshot_base64 {
             match self.save_screenshot(step_number, base64) {
-                Ok(path) => Some(path),
+                Ok(path) => path,
                 Err(e) => {
                     eprintln!("保存截图失败: {}", e);
                     None
@@ -162,16 +268,18 @@ impl AgentLogger {
 
         // 写入文件
         let mut file = self.log_file.lock().await;
-        file.write_all(line_with_newline.as_bytes())?;
+        file.write_all(crate::secrets::redact(&line_with_newline).as_bytes())?;
         file.flush()?;
 
         Ok(())
     }
 
-    /// 记录任务开始
+    /// 记录任务开始，沿用 [`Self::begin_task`] 生成的任务 ID
     pub async fn log_task_start(&self, task: &str) -> Result<(), std::io::Error> {
-        let task_id = format!("{}_{:?}", self.agent_id, Utc::now().timestamp());
-        self.set_task_id(task_id.clone()).await;
+        let task_id = match self.task_id().await {
+            Some(task_id) => task_id,
+            None => self.begin_task().await,
+        };
 
         let entry = serde_json::json!({
             "timestamp": Utc::now().to_rfc3339(),
@@ -179,11 +287,217 @@ impl AgentLogger {
             "task_id": task_id,
             "event": "task_start",
             "task": task,
+            "device_serial": self.device_serial,
+        });
+
+        let json_line = format!("{}\n", entry);
+        let mut file = self.log_file.lock().await;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// 为新任务生成任务 ID 并设为当前任务，任务在后台开始执行前调用方即可得到任务 ID
+    pub async fn begin_task(&self) -> String {
+        let task_id = format!("{}_{:?}", self.agent_id, Utc::now().timestamp());
+        self.set_task_id(task_id.clone()).await;
+        task_id
+    }
+
+    /// 获取当前任务 ID
+    pub async fn task_id(&self) -> Option<String> {
+        self.current_task_id.lock().await.clone()
+    }
+
+    /// 记录执行任务的设备元数据
+    pub async fn log_task_device(
+        &self,
+        task_id: &str,
+        metadata: &crate::scrcpy::device_info::DeviceMetadata,
+    ) -> Result<(), std::io::Error> {
+        let entry = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "agent_id": self.agent_id,
+            "task_id": task_id,
+            "event": "task_device",
+            "device": metadata,
+        });
+
+        let json_line = format!("{}\n", entry);
+        let mut file = self.log_file.lock().await;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// 记录任务开始时的设备时区与时钟偏差
+    pub async fn log_device_clock(
+        &self,
+        task_id: &str,
+        clock: &crate::agent::executor::DeviceClock,
+    ) -> Result<(), std::io::Error> {
+        let entry = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "agent_id": self.agent_id,
+            "task_id": task_id,
+            "event": "device_clock",
+            "clock": clock,
+        });
+
+        let json_line = format!("{}\n", entry);
+        let mut file = self.log_file.lock().await;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// 记录任务产物（如流量记录文件）
+    pub async fn log_task_artifact(&self, task_id: &str, kind: &str, path: &str) -> Result<(), std::io::Error> {
+        let entry = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "agent_id": self.agent_id,
+            "task_id": task_id,
+            "event": "task_artifact",
+            "kind": kind,
+            "path": path,
+        });
+
+        let json_line = format!("{}\n", entry);
+        let mut file = self.log_file.lock().await;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// 记录上传到产物存储的任务产物
+    pub async fn log_artifact_uploaded(
+        &self,
+        task_id: &str,
+        artifact: &crate::agent::core::artifacts::TaskArtifact,
+    ) -> Result<(), std::io::Error> {
+        let entry = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "agent_id": self.agent_id,
+            "task_id": task_id,
+            "event": crate::agent::core::artifacts::ARTIFACT_UPLOADED_EVENT,
+            "artifact": artifact,
+        });
+
+        let json_line = format!("{}\n", entry);
+        let mut file = self.log_file.lock().await;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// 记录评估模型对任务的评估结果
+    pub async fn log_task_evaluation(
+        &self,
+        task_id: &str,
+        evaluation: &crate::agent::llm::TaskEvaluation,
+    ) -> Result<(), std::io::Error> {
+        let entry = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "agent_id": self.agent_id,
+            "task_id": task_id,
+            "event": "task_evaluation",
+            "evaluation": evaluation,
+        });
+
+        let json_line = format!("{}\n", entry);
+        let mut file = self.log_file.lock().await;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// 记录一次模型查询的性能指标（总耗时、首 token 时间与各阶段耗时）及请求体裁剪情况
+    ///
+    /// `scope` 为发起查询时的任务与步骤
+    pub async fn log_model_metrics(
+        &self,
+        scope: &LogScope,
+        model: &str,
+        provider: &str,
+        tokens_used: u32,
+        metrics: &crate::agent::core::traits::PerformanceMetrics,
+        payload_trim: Option<&crate::agent::llm::payload_guard::PayloadTrim>,
+    ) -> Result<(), std::io::Error> {
+        let entry = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "agent_id": self.agent_id,
+            "task_id": scope.task_id,
+            "event": "model_metrics",
+            "step": scope.step,
+            "model": model,
+            "provider": provider,
+            "tokens_used": tokens_used,
+            "metrics": metrics,
+            "payload_trim": payload_trim,
+        });
+
+        let json_line = format!("{}\n", entry);
+        let mut file = self.log_file.lock().await;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// 记录一次失败的模型查询
+    pub async fn log_model_error(
+        &self,
+        scope: &LogScope,
+        model: &str,
+        provider: &str,
+        error: &str,
+        duration_ms: u64,
+    ) -> Result<(), std::io::Error> {
+        let entry = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "agent_id": self.agent_id,
+            "task_id": scope.task_id,
+            "event": "model_error",
+            "step": scope.step,
+            "model": model,
+            "provider": provider,
+            "error": error,
+            "duration_ms": duration_ms,
+        });
+
+        let json_line = format!("{}\n", entry);
+        let mut file = self.log_file.lock().await;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+
+    /// 记录一步对话（本步新增的提示消息与模型回复），用于还原任务对话
+    pub async fn log_conversation_step(
+        &self,
+        conversation: &crate::agent::core::transcript::ConversationStep,
+    ) -> Result<(), std::io::Error> {
+        let task_id = self.current_task_id.lock().await.clone();
+
+        let entry = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "agent_id": self.agent_id,
+            "task_id": task_id,
+            "event": crate::agent::core::transcript::CONVERSATION_STEP_EVENT,
+            "step": conversation.step,
+            "conversation": conversation,
         });
 
         let json_line = format!("{}\n", entry);
         let mut file = self.log_file.lock().await;
-        file.write_all(json_line.as_bytes())?;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
         file.flush()?;
 
         Ok(())
@@ -205,7 +519,30 @@ impl AgentLogger {
 
         let json_line = format!("{}\n", entry);
         let mut file = self.log_file.lock().await;
-        file.write_all(json_line.as_bytes())?;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
+        file.flush()?;
+
+        // 清除任务 ID
+        *self.current_task_id.lock().await = None;
+
+        Ok(())
+    }
+
+    /// 记录任务被停止
+    pub async fn log_task_stopped(&self, step: usize) -> Result<(), std::io::Error> {
+        let task_id = self.current_task_id.lock().await.clone();
+
+        let entry = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "agent_id": self.agent_id,
+            "task_id": task_id,
+            "event": "task_stopped",
+            "step": step,
+        });
+
+        let json_line = format!("{}\n", entry);
+        let mut file = self.log_file.lock().await;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
         file.flush()?;
 
         // 清除任务 ID
@@ -229,7 +566,7 @@ impl AgentLogger {
 
         let json_line = format!("{}\n", entry);
         let mut file = self.log_file.lock().await;
-        file.write_all(json_line.as_bytes())?;
+        file.write_all(crate::secrets::redact(&json_line).as_bytes())?;
         file.flush()?;
 
         // 清除任务 ID
@@ -8,8 +8,11 @@ pub mod llm;
 pub mod executor;
 pub mod context;
 pub mod config;
+#[cfg(feature = "streaming")]
 pub mod api;
 pub mod pool;
+pub mod experiments;
+pub mod bench;
 pub mod socket_server;
 pub mod logger;
 
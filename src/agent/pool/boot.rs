@@ -0,0 +1,113 @@
+//! 设备启动完成检查
+//!
+//! 设备重启后 adb 很早就能看到它，但系统服务尚未就绪，此时截图、输入都会失败。
+//! 建立连接前先确认 `sys.boot_completed` 为 1 且包管理服务可用，未就绪时轮询等待。
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::time::Instant;
+use tracing::{debug, info};
+
+/// 轮询间隔
+const BOOT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// 单次 adb 命令超时
+const BOOT_COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 启动状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BootState {
+    /// `sys.boot_completed` 为 1
+    pub boot_completed: bool,
+    /// 包管理服务可以响应查询
+    pub package_manager_ready: bool,
+}
+
+impl BootState {
+    pub fn is_ready(&self) -> bool {
+        self.boot_completed && self.package_manager_ready
+    }
+
+    /// 尚未就绪的部分，用于日志与错误信息
+    pub fn pending(&self) -> String {
+        let mut pending = Vec::new();
+        if !self.boot_completed {
+            pending.push("系统未启动完成");
+        }
+        if !self.package_manager_ready {
+            pending.push("包管理服务未就绪");
+        }
+        pending.join("、")
+    }
+}
+
+/// `getprop sys.boot_completed` 的输出是否表示启动完成
+pub fn is_boot_completed(output: &str) -> bool {
+    output.trim() == "1"
+}
+
+/// `pm path android` 的输出是否表示包管理服务可用（未就绪时报 `Can't find service: package` 等错误）
+pub fn is_package_manager_ready(output: &str) -> bool {
+    output.trim_start().starts_with("package:")
+}
+
+/// 执行 adb shell 命令，失败或超时时返回空字符串
+async fn shell(serial: &str, args: &[&str]) -> String {
+    let command = crate::platform::adb_device_command(serial).arg("shell").args(args).output();
+    match tokio::time::timeout(BOOT_COMMAND_TIMEOUT, command).await {
+        Ok(Ok(output)) => String::from_utf8_lossy(&output.stdout).into_owned(),
+        Ok(Err(e)) => {
+            debug!("设备 {} 执行 {:?} 失败: {}", serial, args, e);
+            String::new()
+        }
+        Err(_) => {
+            debug!("设备 {} 执行 {:?} 超时", serial, args);
+            String::new()
+        }
+    }
+}
+
+/// 检查设备当前的启动状态
+pub async fn check_boot(serial: &str) -> BootState {
+    let boot_completed = is_boot_completed(&shell(serial, &["getprop", "sys.boot_completed"]).await);
+    let package_manager_ready = boot_completed && is_package_manager_ready(&shell(serial, &["pm", "path", "android"]).await);
+    BootState { boot_completed, package_manager_ready }
+}
+
+/// 等待设备启动完成，最多等待 `timeout`；已就绪的设备只检查一次
+pub async fn wait_for_boot(serial: &str, timeout: Duration) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+    let mut state = check_boot(serial).await;
+    if state.is_ready() {
+        return Ok(());
+    }
+    info!("设备 {} 尚未就绪（{}），等待启动完成", serial, state.pending());
+    while Instant::now() + BOOT_POLL_INTERVAL <= deadline {
+        tokio::time::sleep(BOOT_POLL_INTERVAL).await;
+        state = check_boot(serial).await;
+        if state.is_ready() {
+            info!("设备 {} 已启动完成", serial);
+            return Ok(());
+        }
+    }
+    Err(format!("设备 {} 在 {} 秒内未就绪（{}）", serial, timeout.as_secs(), state.pending()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boot_outputs() {
+        assert!(is_boot_completed("1\n"));
+        assert!(!is_boot_completed("\n"));
+        assert!(!is_boot_completed("0"));
+        assert!(is_package_manager_ready("package:/system/framework/framework-res.apk\n"));
+        assert!(!is_package_manager_ready("Error: Could not access the Package Manager.  Is the system running?"));
+        assert!(!is_package_manager_ready(""));
+
+        let state = BootState { boot_completed: true, package_manager_ready: false };
+        assert!(!state.is_ready());
+        assert_eq!(state.pending(), "包管理服务未就绪");
+    }
+}
@@ -0,0 +1,288 @@
+//! 设备池容量控制
+//!
+//! 限制同时执行任务的 Agent 数与 scrcpy 会话数，超出时按配置排队等待或直接拒绝（与 HTTP 429 相同的语义），
+//! 避免服务过载时 adb 与模型 API 被大量并发请求拖垮。排队的任务按优先级取得名额；
+//! 开启抢占时高优先级任务可以借用一个低优先级任务的名额。同时统计利用率，随 `/metrics` 导出。
+
+use std::fmt::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::scheduler::{GatePermit, PriorityGate};
+use super::types::{DevicePoolConfig, TaskPriority};
+use crate::agent::core::agent::PhoneAgent;
+use crate::agent::core::traits::{Agent, AgentError};
+use crate::error::AppError;
+
+/// 容量不足时返回的状态码（HTTP 429 Too Many Requests）
+pub const CAPACITY_EXCEEDED_STATUS: u16 = 429;
+
+/// 是否为容量不足导致的拒绝
+pub fn is_capacity_error(error: &AppError) -> bool {
+    matches!(error, AppError::AgentError(AgentError::CapacityExceeded(_)))
+}
+
+/// 容量使用情况
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CapacitySnapshot {
+    /// 正在执行任务的 Agent 数
+    pub busy_agents: usize,
+    /// 最多同时执行任务的 Agent 数（0 表示不限制）
+    pub max_busy_agents: usize,
+    /// 已建立的 scrcpy 会话数
+    pub streams: usize,
+    /// 最多 scrcpy 会话数（0 表示不限制）
+    pub max_streams: usize,
+    /// 正在排队等待的任务数
+    pub waiting: usize,
+    /// 被抢占而暂停的任务数（仍占用各自的名额）
+    pub preempted: usize,
+    /// 累计准入的任务数
+    pub admitted_total: u64,
+    /// 累计排队过的任务数
+    pub queued_total: u64,
+    /// 累计被拒绝的任务与会话数
+    pub rejected_total: u64,
+    /// 累计抢占次数
+    pub preempted_total: u64,
+}
+
+impl CapacitySnapshot {
+    /// Agent 利用率（不限制时为 0）
+    pub fn agent_utilization(&self) -> f64 {
+        if self.max_busy_agents == 0 {
+            0.0
+        } else {
+            self.busy_agents as f64 / self.max_busy_agents as f64
+        }
+    }
+
+    /// 导出为 Prometheus 文本格式
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        let gauges = [
+            ("scrs_pool_busy_agents", "正在执行任务的 Agent 数", self.busy_agents as f64),
+            ("scrs_pool_max_busy_agents", "最多同时执行任务的 Agent 数（0 表示不限制）", self.max_busy_agents as f64),
+            ("scrs_pool_agent_utilization", "Agent 利用率", self.agent_utilization()),
+            ("scrs_pool_streams", "已建立的 scrcpy 会话数", self.streams as f64),
+            ("scrs_pool_max_streams", "最多 scrcpy 会话数（0 表示不限制）", self.max_streams as f64),
+            ("scrs_pool_admission_waiting", "正在排队等待的任务数", self.waiting as f64),
+            ("scrs_pool_preempted_tasks", "被抢占而暂停的任务数", self.preempted as f64),
+        ];
+        for (name, help, value) in gauges {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            let _ = writeln!(out, "{} {}", name, value);
+        }
+        let _ = writeln!(out, "# HELP scrs_pool_admission_total 任务准入结果");
+        let _ = writeln!(out, "# TYPE scrs_pool_admission_total counter");
+        for (result, count) in [("admitted", self.admitted_total), ("queued", self.queued_total), ("rejected", self.rejected_total), ("preempted", self.preempted_total)] {
+            let _ = writeln!(out, "scrs_pool_admission_total{{result=\"{}\"}} {}", result, count);
+        }
+        out
+    }
+}
+
+/// 任务执行许可，任务结束时释放
+#[derive(Debug)]
+pub struct AgentPermit {
+    _slot: Option<GatePermit>,
+    _preempted: Option<Preempted>,
+}
+
+/// 被抢占的低优先级任务，抢占它的任务结束时恢复执行
+struct Preempted {
+    agent: Arc<PhoneAgent>,
+    count: Arc<AtomicUsize>,
+}
+
+impl std::fmt::Debug for Preempted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Preempted").field("device", &self.agent.device_serial()).finish()
+    }
+}
+
+impl Drop for Preempted {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+        let agent = Arc::clone(&self.agent);
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    match agent.resume().await {
+                        Ok(()) => info!("设备 {} 上被抢占的任务已恢复", agent.device_serial()),
+                        Err(e) => warn!("恢复设备 {} 上被抢占的任务失败: {}", agent.device_serial(), e),
+                    }
+                });
+            }
+            Err(_) => warn!("设备 {} 上被抢占的任务无法恢复：没有运行时", agent.device_serial()),
+        }
+    }
+}
+
+/// 容量限制器
+#[derive(Debug)]
+pub struct CapacityLimiter {
+    max_busy_agents: usize,
+    max_streams: usize,
+    admission_wait: Duration,
+    agents: PriorityGate,
+    preempted: Arc<AtomicUsize>,
+    admitted: AtomicU64,
+    queued: AtomicU64,
+    rejected: AtomicU64,
+    preempted_total: AtomicU64,
+}
+
+impl CapacityLimiter {
+    pub fn new(config: &DevicePoolConfig) -> Self {
+        Self {
+            max_busy_agents: config.max_busy_agents,
+            max_streams: config.max_streams,
+            admission_wait: Duration::from_secs(config.admission_wait_secs),
+            agents: PriorityGate::new(config.max_busy_agents),
+            preempted: Arc::new(AtomicUsize::new(0)),
+            admitted: AtomicU64::new(0),
+            queued: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            preempted_total: AtomicU64::new(0),
+        }
+    }
+
+    /// 有空位时立即取得任务执行许可
+    pub fn try_admit_agent(&self) -> Option<AgentPermit> {
+        let slot = self.agents.try_acquire()?;
+        self.admitted.fetch_add(1, Ordering::SeqCst);
+        Some(AgentPermit { _slot: Some(slot), _preempted: None })
+    }
+
+    /// 申请任务执行许可：有空位时立即返回，否则按优先级最多排队 `admission_wait_secs` 秒，仍无空位则拒绝
+    pub async fn admit_agent(&self, priority: TaskPriority) -> Result<AgentPermit, AppError> {
+        if let Some(permit) = self.try_admit_agent() {
+            return Ok(permit);
+        }
+        if self.admission_wait.is_zero() {
+            return Err(self.reject(format!("正在执行任务的 Agent 已达上限 {}", self.max_busy_agents)));
+        }
+
+        info!(
+            "Agent 已达上限 {}，{} 优先级任务排队等待（最多 {} 秒）",
+            self.max_busy_agents,
+            priority,
+            self.admission_wait.as_secs()
+        );
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        match self.agents.acquire(priority, self.admission_wait).await {
+            Some(slot) => {
+                self.admitted.fetch_add(1, Ordering::SeqCst);
+                Ok(AgentPermit { _slot: Some(slot), _preempted: None })
+            }
+            None => Err(self.reject(format!(
+                "排队 {} 秒后仍没有空闲的 Agent 名额（上限 {}）",
+                self.admission_wait.as_secs(),
+                self.max_busy_agents
+            ))),
+        }
+    }
+
+    /// 借用已暂停的低优先级任务的名额，许可释放时恢复该任务
+    pub fn preempt(&self, paused: Arc<PhoneAgent>) -> AgentPermit {
+        self.admitted.fetch_add(1, Ordering::SeqCst);
+        self.preempted_total.fetch_add(1, Ordering::SeqCst);
+        self.preempted.fetch_add(1, Ordering::SeqCst);
+        AgentPermit {
+            _slot: None,
+            _preempted: Some(Preempted {
+                agent: paused,
+                count: Arc::clone(&self.preempted),
+            }),
+        }
+    }
+
+    /// 检查是否还能再建立一个 scrcpy 会话
+    pub fn check_stream(&self, active_streams: usize) -> Result<(), AppError> {
+        if self.max_streams > 0 && active_streams >= self.max_streams {
+            return Err(self.reject(format!("scrcpy 会话数已达上限 {}", self.max_streams)));
+        }
+        Ok(())
+    }
+
+    /// 当前容量使用情况
+    pub fn snapshot(&self, streams: usize) -> CapacitySnapshot {
+        CapacitySnapshot {
+            busy_agents: self.agents.held(),
+            max_busy_agents: self.max_busy_agents,
+            streams,
+            max_streams: self.max_streams,
+            waiting: self.agents.waiting(),
+            preempted: self.preempted.load(Ordering::SeqCst),
+            admitted_total: self.admitted.load(Ordering::SeqCst),
+            queued_total: self.queued.load(Ordering::SeqCst),
+            rejected_total: self.rejected.load(Ordering::SeqCst),
+            preempted_total: self.preempted_total.load(Ordering::SeqCst),
+        }
+    }
+
+    fn reject(&self, reason: String) -> AppError {
+        warn!("容量不足，拒绝请求: {}", reason);
+        self.rejected.fetch_add(1, Ordering::SeqCst);
+        AppError::AgentError(AgentError::CapacityExceeded(reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_admission_limits() {
+        let config = DevicePoolConfig {
+            max_busy_agents: 1,
+            max_streams: 2,
+            admission_wait_secs: 0,
+            ..Default::default()
+        };
+        let limiter = CapacityLimiter::new(&config);
+
+        let permit = limiter.admit_agent(TaskPriority::Normal).await.unwrap();
+        let rejected = limiter.admit_agent(TaskPriority::Normal).await.unwrap_err();
+        assert!(is_capacity_error(&rejected));
+        assert_eq!(limiter.snapshot(0).busy_agents, 1);
+
+        drop(permit);
+        let _permit = limiter.admit_agent(TaskPriority::Normal).await.unwrap();
+        let snapshot = limiter.snapshot(2);
+        assert_eq!((snapshot.admitted_total, snapshot.rejected_total), (2, 1));
+        assert_eq!(snapshot.agent_utilization(), 1.0);
+
+        assert!(limiter.check_stream(1).is_ok());
+        assert!(limiter.check_stream(2).is_err());
+        assert!(limiter.snapshot(2).render_prometheus().contains("scrs_pool_admission_total{result=\"rejected\"} 2"));
+    }
+
+    #[tokio::test]
+    async fn test_admission_queue() {
+        let config = DevicePoolConfig {
+            max_busy_agents: 1,
+            admission_wait_secs: 5,
+            ..Default::default()
+        };
+        let limiter = Arc::new(CapacityLimiter::new(&config));
+        let permit = limiter.admit_agent(TaskPriority::Normal).await.unwrap();
+
+        let waiter = {
+            let limiter = Arc::clone(&limiter);
+            tokio::spawn(async move { limiter.admit_agent(TaskPriority::Normal).await.map(|_| ()) })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(limiter.snapshot(0).waiting, 1);
+
+        drop(permit);
+        assert!(waiter.await.unwrap().is_ok());
+        assert_eq!(limiter.snapshot(0).queued_total, 1);
+    }
+}
@@ -0,0 +1,81 @@
+//! 配置文件监视
+//!
+//! 定期检查配置文件的修改时间，文件中的 `[model]` / `[agent]` 段变化后更新设备池配置，`[apps.aliases]` 段变化后更新应用别名
+
+use super::DevicePool;
+use crate::agent::actions::aliases::app_aliases;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration};
+use tracing::{info, warn};
+
+/// 检查间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 启动配置文件监视任务，启动时立即应用一次
+pub fn spawn_config_watcher(pool: Arc<DevicePool>, path: PathBuf) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("监视配置文件: {:?}", path);
+        let mut last_modified: Option<SystemTime> = None;
+        let mut ticker = interval(POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let Ok(modified) = tokio::fs::metadata(&path).await.and_then(|m| m.modified()) else {
+                continue;
+            };
+            if last_modified == Some(modified) {
+                continue;
+            }
+            last_modified = Some(modified);
+
+            if let Err(e) = app_aliases().load_config_file(&path) {
+                warn!("{}", e);
+            }
+            match load_patch(&path).await {
+                Ok(Some(patch)) => match pool.apply_config_patch(&patch) {
+                    Ok(version) => info!("已从 {:?} 加载模型/Agent 配置，版本: {}", path, version),
+                    Err(e) => warn!("应用配置文件 {:?} 失败: {}", path, e),
+                },
+                Ok(None) => {}
+                Err(e) => warn!("{}", e),
+            }
+        }
+    })
+}
+
+/// 读取配置文件中的 `[model]` 与 `[agent]` 段，两者都没有时返回 None
+async fn load_patch(path: &Path) -> Result<Option<serde_json::Value>, String> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("读取配置文件 {:?} 失败: {}", path, e))?;
+    parse_patch(&content).map_err(|e| format!("解析配置文件 {:?} 失败: {}", path, e))
+}
+
+fn parse_patch(content: &str) -> Result<Option<serde_json::Value>, toml::de::Error> {
+    let value: serde_json::Value = toml::from_str(content)?;
+    let mut patch = serde_json::Map::new();
+    for key in ["model", "agent"] {
+        if let Some(section) = value.get(key) {
+            patch.insert(key.to_string(), section.clone());
+        }
+    }
+    Ok((!patch.is_empty()).then_some(serde_json::Value::Object(patch)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_patch() {
+        let patch = parse_patch("agent_server = true\n[model]\ntemperature = 0.3\n[agent]\nmax_steps = 50\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(patch["model"]["temperature"], 0.3);
+        assert_eq!(patch["agent"]["max_steps"], 50);
+        assert!(patch.get("agent_server").is_none());
+        assert!(parse_patch("agent_server = true").unwrap().is_none());
+    }
+}
@@ -3,8 +3,10 @@
 //! 表示池中的单个设备及其状态
 
 use crate::agent::core::agent::PhoneAgent;
-use crate::agent::pool::types::DeviceStatus;
+use crate::agent::pool::types::{DeviceStatus, TaskPriority};
 use crate::scrcpy::scrcpy::ScrcpyConnect;
+use crate::agent::executor::DeviceCapabilities;
+use crate::scrcpy::device_info::DeviceMetadata;
 use chrono::{DateTime, Utc};
 use std::sync::Arc;
 
@@ -19,9 +21,18 @@ pub struct DeviceEntry {
     /// Scrcpy 连接（懒加载）
     pub scrcpy: Option<Arc<ScrcpyConnect>>,
 
+    /// scrcpy-server 本地转发端口（每个设备会话独立分配）
+    pub scrcpy_server_port: Option<u16>,
+
     /// Agent 实例（按需创建）
     pub agent: Option<Arc<PhoneAgent>>,
 
+    /// 创建 Agent 时的配置版本，配置更新后空闲的 Agent 会被重建
+    pub config_version: u64,
+
+    /// 设备级模型配置覆盖（只包含要覆盖的字段，合并到全局模型配置上）
+    pub model_override: Option<serde_json::Value>,
+
     /// 当前状态
     pub status: DeviceStatus,
 
@@ -36,6 +47,15 @@ pub struct DeviceEntry {
 
     /// 当前任务描述（如果有）
     pub current_task: Option<String>,
+
+    /// 最近启动的任务的优先级
+    pub current_priority: TaskPriority,
+
+    /// 连接时读取的设备元数据
+    pub metadata: Option<DeviceMetadata>,
+
+    /// 连接时探测的设备能力，执行器据此选择操作方式
+    pub capabilities: Option<DeviceCapabilities>,
 }
 
 impl DeviceEntry {
@@ -46,12 +66,18 @@ impl DeviceEntry {
             serial,
             name,
             scrcpy: None,
+            scrcpy_server_port: None,
             agent: None,
+            config_version: 0,
+            model_override: None,
             status: DeviceStatus::Registered,
             last_used: now,
             created_at: now,
             current_task_id: None,
             current_task: None,
+            current_priority: TaskPriority::default(),
+            metadata: None,
+            capabilities: None,
         }
     }
 
@@ -93,11 +119,22 @@ impl DeviceEntry {
             name: self.name.clone(),
             status: self.status.clone(),
             has_agent: self.agent.is_some(),
+            scrcpy_server_port: self.scrcpy_server_port,
             last_used: self.last_used.timestamp(),
             idle_seconds: self.idle_seconds(),
+            model_override: self.model_override.clone(),
+            metadata: self.metadata.clone(),
+            capabilities: self.capabilities,
+            session: self.scrcpy.as_ref().map(|connect| connect.session_state()),
         }
     }
 
+    /// 标记当前 Agent 的配置已过期，空闲时按新配置重建
+    pub fn mark_agent_outdated(&mut self) {
+        // 与任何配置版本都不相等
+        self.config_version = u64::MAX;
+    }
+
     /// 设置状态
     pub fn set_status(&mut self, status: DeviceStatus) {
         self.status = status;
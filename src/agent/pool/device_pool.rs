@@ -3,21 +3,36 @@
 //! 统一管理设备连接、Agent 创建和生命周期
 
 use super::types::{
-    DeviceStatus, DevicePoolConfig, DevicePoolEvent,
+    DeviceStatus, DevicePoolConfig, DevicePoolEvent, TaskOverrides, TaskPriority,
 };
+use super::boot::{check_boot, wait_for_boot};
+use super::capacity::{AgentPermit, CapacityLimiter, CapacitySnapshot};
+use super::drain::{DrainController, DrainStatus};
+use super::idempotency::{Submission, SubmissionClaim, SubmissionRegistry};
+use super::scheduler::{GatePermit, PriorityGate};
 use super::device_entry::DeviceEntry;
-use crate::agent::core::agent::PhoneAgent;
-use crate::agent::core::traits::Agent;
+use super::emulator::{EmulatorInstance, EmulatorManager};
+use super::farm::{DeviceFarm, FarmDeviceHealth, HealthChange};
+use super::event_log::{EventLog, EventPage, SequencedEvent};
+use crate::agent::actions::ActionEnum;
+use crate::agent::core::agent::{PhoneAgent, AGENT_LOG_DIR};
+use crate::agent::core::progress;
+use crate::agent::core::task_record::{self, TaskRecord};
+use crate::agent::core::traits::{ActionResult, Agent, AgentError, AgentStatus, ModelClient};
 use crate::agent::core::state::AgentConfig;
-use crate::agent::executor::ScrcpyDeviceWrapper;
-use crate::agent::llm::{create_model_client, ModelConfig};
+use crate::agent::executor::{DeviceCapabilities, ScrcpyDeviceWrapper};
+use crate::agent::experiments::{ExperimentRegistry, Variant};
+use crate::agent::llm::{create_model_client, JudgeClient, ModelConfig};
+use crate::agent::llm::failover::subscribe_failover_events;
 use crate::error::AppError;
 use adb_client::server::ADBServer;
 use adb_client::server_device::ADBServerDevice;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, RwLock as StdRwLock};
+use std::time::Duration;
 use tokio::sync::{broadcast, RwLock};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 /// 设备池
@@ -28,17 +43,41 @@ pub struct DevicePool {
     /// 配置
     config: DevicePoolConfig,
 
-    /// 事件发送器
-    event_tx: broadcast::Sender<DevicePoolEvent>,
+    /// 事件日志（分配序号并广播）
+    events: Arc<EventLog>,
 
     /// ADB 服务器引用
     adb_server: Arc<RwLock<ADBServer>>,
 
-    /// LLM 客户端配置
-    model_config: ModelConfig,
+    /// LLM 客户端配置（可在运行时更新）
+    model_config: StdRwLock<ModelConfig>,
 
-    /// Agent 配置
-    agent_config: AgentConfig,
+    /// Agent 配置（可在运行时更新）
+    agent_config: StdRwLock<AgentConfig>,
+
+    /// 配置版本，每次更新递增
+    config_version: AtomicU64,
+
+    /// 提示词/模型 A/B 实验
+    experiments: Arc<ExperimentRegistry>,
+
+    /// Agent 与 scrcpy 会话的容量控制
+    capacity: CapacityLimiter,
+
+    /// 维护前的排空状态
+    drain: DrainController,
+
+    /// 每台设备的执行名额（同一设备同时只执行一个任务，其余按优先级排队）
+    device_slots: StdMutex<HashMap<String, Arc<PriorityGate>>>,
+
+    /// 客户端任务 ID 的提交记录，用于识别重复提交
+    submissions: Arc<SubmissionRegistry>,
+
+    /// 按需启动的模拟器
+    emulators: EmulatorManager,
+
+    /// 通过 `adb connect` 接入的容器化设备
+    farm: DeviceFarm,
 }
 
 impl DevicePool {
@@ -46,24 +85,419 @@ impl DevicePool {
     pub fn new(
         config: DevicePoolConfig,
         adb_server: Arc<RwLock<ADBServer>>,
-        model_config: ModelConfig,
-        agent_config: AgentConfig,
+        mut model_config: ModelConfig,
+        mut agent_config: AgentConfig,
     ) -> Self {
-        let (event_tx, _) = broadcast::channel(100);
+        if let Err(e) = model_config.resolve_secrets() {
+            warn!("解析模型 API Key 失败: {}", e);
+        }
+        if let Err(e) = agent_config.resolve_secrets() {
+            warn!("解析回调签名密钥失败: {}", e);
+        }
 
         Self {
             devices: Arc::new(RwLock::new(HashMap::new())),
+            capacity: CapacityLimiter::new(&config),
+            drain: DrainController::default(),
+            device_slots: StdMutex::new(HashMap::new()),
+            submissions: Arc::new(SubmissionRegistry::default()),
+            emulators: EmulatorManager::new(config.emulator.clone()),
+            farm: DeviceFarm::new(config.farm.clone()),
+            events: Arc::new(EventLog::new(config.event_log_capacity)),
             config,
-            event_tx,
             adb_server,
-            model_config,
-            agent_config,
+            model_config: StdRwLock::new(model_config),
+            agent_config: StdRwLock::new(agent_config),
+            config_version: AtomicU64::new(0),
+            experiments: Arc::new(ExperimentRegistry::default()),
+        }
+    }
+
+    /// 获取当前模型配置
+    pub fn model_config(&self) -> ModelConfig {
+        self.model_config.read().unwrap().clone()
+    }
+
+    /// 获取当前 Agent 配置
+    pub fn agent_config(&self) -> AgentConfig {
+        self.agent_config.read().unwrap().clone()
+    }
+
+    /// 当前配置版本
+    pub fn config_version(&self) -> u64 {
+        self.config_version.load(Ordering::SeqCst)
+    }
+
+    /// 更新模型/Agent 配置，返回新的配置版本
+    ///
+    /// 正在执行任务的 Agent 继续使用旧配置直到任务结束，之后获取 Agent 时按新配置重建
+    pub fn update_config(&self, model_config: Option<ModelConfig>, agent_config: Option<AgentConfig>) -> Result<u64, AppError> {
+        if let Some(agent_config) = &agent_config {
+            agent_config.validate().map_err(AppError::Unknown)?;
+        }
+        if let Some(mut model_config) = model_config {
+            model_config.resolve_secrets().map_err(AppError::Unknown)?;
+            // 先校验能否创建客户端，避免写入无效配置
+            create_model_client(&model_config)?;
+            *self.model_config.write().unwrap() = model_config;
+        }
+        if let Some(mut agent_config) = agent_config {
+            agent_config.resolve_secrets().map_err(AppError::Unknown)?;
+            *self.agent_config.write().unwrap() = agent_config;
+        }
+        let version = self.config_version.fetch_add(1, Ordering::SeqCst) + 1;
+        self.events.send(DevicePoolEvent::ConfigUpdated { version });
+        info!("模型/Agent 配置已更新，版本: {}", version);
+        Ok(version)
+    }
+
+    /// 将 `{"model": {...}, "agent": {...}}` 形式的部分配置合并到当前配置
+    ///
+    /// 只需给出要修改的字段，例如 `{"model": {"temperature": 0.3}}`
+    pub fn apply_config_patch(&self, patch: &serde_json::Value) -> Result<u64, AppError> {
+        let model_config = match patch.get("model") {
+            Some(model_patch) => Some(merge_config(&self.model_config(), model_patch)?),
+            None => None,
+        };
+        let agent_config = match patch.get("agent") {
+            Some(agent_patch) => Some(merge_config(&self.agent_config(), agent_patch)?),
+            None => None,
+        };
+        self.update_config(model_config, agent_config)
+    }
+
+    /// 获取实验注册表
+    pub fn experiments(&self) -> &Arc<ExperimentRegistry> {
+        &self.experiments
+    }
+
+    /// 申请客户端提供的任务 ID，重复提交时返回已有的提交
+    pub fn claim_submission(&self, task_id: &str, serial: &str, task: &str) -> SubmissionClaim {
+        self.submissions.claim(task_id, serial, task)
+    }
+
+    /// 查询客户端任务 ID 对应的提交
+    pub fn submission(&self, task_id: &str) -> Option<Submission> {
+        self.submissions.get(task_id)
+    }
+
+    /// 已提交任务的任务记录：按 Agent 为该任务生成的任务 ID 查找内存中的进度或 Agent 日志，
+    /// 同一 Agent 之后执行的其它任务不会被当作该任务；任务仍在启动中或找不到记录时返回 None
+    pub async fn submission_status(&self, submission: &Submission) -> Option<TaskRecord> {
+        let task_id = submission.agent_task_id.clone()?;
+        if let Some(replay) = progress::progress_since(&task_id, 0) {
+            return Some(TaskRecord::from_progress(&replay));
         }
+        tokio::task::spawn_blocking(move || task_record::load_task_record(AGENT_LOG_DIR, &task_id))
+            .await
+            .ok()?
+            .ok()
+            .flatten()
     }
 
-    /// 订阅事件
-    pub fn subscribe_events(&self) -> broadcast::Receiver<DevicePoolEvent> {
-        self.event_tx.subscribe()
+    /// 为 Agent 的下一个任务分配实验变体，返回变体名称
+    pub async fn assign_experiment(&self, agent: &PhoneAgent, experiment_id: &str) -> Result<String, AppError> {
+        let assignment = self
+            .experiments
+            .assign(experiment_id)
+            .await
+            .map_err(AppError::Unknown)?;
+
+        let model_client = self.variant_model_client(&assignment.variant)?;
+        let variant = assignment.variant.name.clone();
+        agent
+            .set_experiment(Arc::clone(&self.experiments), assignment, model_client)
+            .await;
+        Ok(variant)
+    }
+
+    /// 设置设备级模型配置覆盖（只需给出要覆盖的字段），`None` 表示恢复使用全局配置
+    ///
+    /// 返回设备生效的模型配置。设备上正在执行的任务继续使用旧配置
+    pub async fn set_model_override(
+        &self,
+        serial: &str,
+        model_override: Option<serde_json::Value>,
+    ) -> Result<ModelConfig, AppError> {
+        let effective = Self::effective_model_config(&self.model_config(), model_override.as_ref())?;
+        // 先校验能否创建客户端，避免写入无效配置
+        create_model_client(&effective)?;
+
+        let mut devices = self.devices.write().await;
+        let entry = devices
+            .get_mut(serial)
+            .ok_or_else(|| AppError::AgentError(
+                crate::agent::core::traits::AgentError::DeviceNotFound(serial.to_string()),
+            ))?;
+        entry.model_override = model_override;
+        entry.mark_agent_outdated();
+
+        info!("设备 {} 模型配置已更新: {}", serial, effective.model_name);
+        Ok(effective)
+    }
+
+    /// 获取设备生效的模型配置（全局配置合并设备级覆盖）
+    pub async fn device_model_config(&self, serial: &str) -> Result<ModelConfig, AppError> {
+        let devices = self.devices.read().await;
+        let entry = devices
+            .get(serial)
+            .ok_or_else(|| AppError::AgentError(
+                crate::agent::core::traits::AgentError::DeviceNotFound(serial.to_string()),
+            ))?;
+        Self::effective_model_config(&self.model_config(), entry.model_override.as_ref())
+    }
+
+    fn effective_model_config(
+        model_config: &ModelConfig,
+        model_override: Option<&serde_json::Value>,
+    ) -> Result<ModelConfig, AppError> {
+        match model_override {
+            Some(patch) => {
+                let mut config: ModelConfig = merge_config(model_config, patch)?;
+                config.resolve_secrets().map_err(AppError::Unknown)?;
+                Ok(config)
+            }
+            None => Ok(model_config.clone()),
+        }
+    }
+
+    /// 为 Agent 的下一个任务应用模型与参数覆盖
+    pub async fn apply_task_overrides(&self, agent: &PhoneAgent, overrides: &TaskOverrides) -> Result<(), AppError> {
+        let invalid = |e: String| AppError::AgentError(
+            crate::agent::core::traits::AgentError::ValidationError(e),
+        );
+        let base = self.device_model_config(agent.device_serial()).await?;
+        let model_config = overrides.apply(&base).map_err(invalid)?;
+        let model_client = if overrides.overrides_model() {
+            Some(create_model_client(&model_config)?)
+        } else {
+            None
+        };
+        agent.set_task_override(model_client, overrides.max_steps, overrides.max_execution_time).await;
+        info!("Agent {} 下一个任务使用覆盖参数: {:?}", agent.id(), overrides);
+        Ok(())
+    }
+
+    /// 变体覆盖了模型配置时，为其创建独立的模型客户端
+    pub fn variant_model_client(&self, variant: &Variant) -> Result<Option<Arc<dyn ModelClient>>, AppError> {
+        if !variant.overrides_model() {
+            return Ok(None);
+        }
+        Ok(Some(create_model_client(&variant.apply(&self.model_config()))?))
+    }
+
+    /// 订阅事件，事件带序号，错过的事件可以用 [`DevicePool::events_since`] 补齐
+    pub fn subscribe_events(&self) -> broadcast::Receiver<SequencedEvent> {
+        self.events.subscribe()
+    }
+
+    /// 序号大于 `since` 的事件，最多 `limit` 条
+    pub fn events_since(&self, since: u64, limit: usize) -> EventPage {
+        self.events.since(since, limit)
+    }
+
+    /// adb server 重启后替换 ADBServer 客户端，并重新建立空闲设备的连接
+    ///
+    /// 正在执行任务的设备保持不变（adb 命令每次都新建连接，重启后即可继续使用）
+    pub fn reconnect_after_adb_restart(self: &Arc<Self>) {
+        let pool = Arc::clone(self);
+        let mut restarts = crate::adb_server::subscribe_restarts();
+        tokio::spawn(async move {
+            loop {
+                match restarts.recv().await {
+                    Ok(restarts) => {
+                        *pool.adb_server.write().await = crate::adb_server::new_adb_server();
+                        pool.events.send(DevicePoolEvent::AdbServerRestarted { restarts });
+                        pool.reconnect_idle_devices().await;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// 断开并重新连接所有已连接且没有任务在执行的设备
+    async fn reconnect_idle_devices(&self) {
+        let connected: Vec<(String, Option<Arc<PhoneAgent>>, bool)> = self
+            .devices
+            .read()
+            .await
+            .values()
+            .filter_map(|entry| {
+                let streaming = entry.scrcpy.as_ref()?.is_started();
+                Some((entry.serial.clone(), entry.agent.clone(), streaming))
+            })
+            .collect();
+
+        for (serial, agent, streaming) in connected {
+            if let Some(agent) = agent
+                && matches!(agent.status().await, AgentStatus::Running { .. } | AgentStatus::Paused { .. })
+            {
+                info!("设备 {} 正在执行任务，adb server 重启后不重新连接", serial);
+                continue;
+            }
+            let _ = self.disconnect_device(&serial).await;
+            // 之前在投屏的设备同时重启投屏服务
+            let reconnected = if streaming {
+                self.start_stream(&serial).await.map(|_| ())
+            } else {
+                self.connect_device(&serial).await
+            };
+            match reconnected {
+                Ok(()) => info!("adb server 重启后已重新连接设备 {}", serial),
+                Err(e) => warn!("adb server 重启后重新连接设备 {} 失败: {}", serial, e),
+            }
+        }
+    }
+
+    /// 受管理的模拟器
+    pub fn list_emulators(&self) -> Vec<EmulatorInstance> {
+        self.emulators.list()
+    }
+
+    /// 启动一个模拟器，启动完成后注册到设备池
+    pub async fn boot_emulator(&self) -> Result<EmulatorInstance, AppError> {
+        let instance = self.emulators.boot().await.map_err(AppError::EmulatorError)?;
+        let name = self.emulators.config().avd.clone().map(|avd| format!("{} #{}", avd, instance.slot + 1));
+        if let Err(e) = self.register_device(instance.serial.clone(), name).await {
+            let _ = self.emulators.destroy(&instance.serial).await;
+            return Err(e);
+        }
+        self.events.send(DevicePoolEvent::EmulatorBooted {
+            serial: instance.serial.clone(),
+        });
+        Ok(instance)
+    }
+
+    /// 从设备池注销并关闭模拟器
+    pub async fn destroy_emulator(&self, serial: &str) -> Result<(), AppError> {
+        if !self.emulators.is_managed(serial) {
+            return Err(AppError::EmulatorError(format!("不是受管理的模拟器: {}", serial)));
+        }
+        let _ = self.unregister_device(serial).await;
+        self.emulators.destroy(serial).await.map_err(AppError::EmulatorError)?;
+        self.events.send(DevicePoolEvent::EmulatorDestroyed {
+            serial: serial.to_string(),
+        });
+        Ok(())
+    }
+
+    /// 定期回收任务已结束或长时间空闲的模拟器（未启用模拟器管理时不启动）
+    pub fn spawn_emulator_reaper(self: &Arc<Self>) {
+        if !self.emulators.enabled() {
+            return;
+        }
+        let pool = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(10));
+            loop {
+                interval.tick().await;
+                let mut busy = HashSet::new();
+                let agents: Vec<Arc<PhoneAgent>> =
+                    pool.devices.read().await.values().filter_map(|entry| entry.agent.clone()).collect();
+                for agent in agents {
+                    if matches!(agent.status().await, AgentStatus::Running { .. } | AgentStatus::Paused { .. }) {
+                        busy.insert(agent.device_serial().to_string());
+                    }
+                }
+                for serial in pool.emulators.reclaimable(|serial| busy.contains(serial)) {
+                    info!("回收模拟器 {}", serial);
+                    if let Err(e) = pool.destroy_emulator(&serial).await {
+                        warn!("回收模拟器 {} 失败: {}", serial, e);
+                    }
+                }
+            }
+        });
+    }
+
+    /// 农场设备的健康状态
+    pub fn farm_health(&self) -> Vec<FarmDeviceHealth> {
+        self.farm.health()
+    }
+
+    /// 接入并注册配置中的农场设备
+    pub async fn connect_farm_devices(&self) {
+        for address in self.farm.addresses() {
+            if let Err(e) = self.farm.connect(address).await {
+                warn!("接入农场设备 {} 失败: {}", address, e);
+                continue;
+            }
+            if !self.devices.read().await.contains_key(address)
+                && let Err(e) = self.register_device(address.clone(), Some(format!("farm:{}", address))).await
+            {
+                warn!("注册农场设备 {} 失败: {}", address, e);
+            }
+        }
+    }
+
+    /// 接入农场设备并定期检查健康状态：连续失败的设备标记为离线，恢复后重新可用（没有农场设备时不启动）
+    pub fn spawn_farm_monitor(self: &Arc<Self>) {
+        if self.farm.addresses().is_empty() {
+            return;
+        }
+        let pool = Arc::clone(self);
+        tokio::spawn(async move {
+            pool.connect_farm_devices().await;
+            let interval_secs = pool.farm.config().health_check_interval_secs;
+            if interval_secs == 0 {
+                return;
+            }
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                for address in pool.farm.addresses() {
+                    match pool.farm.check(address).await {
+                        Some(HealthChange::Unhealthy(error)) => {
+                            let _ = pool.disconnect_device(address).await;
+                            if let Some(entry) = pool.devices.write().await.get_mut(address) {
+                                entry.set_status(DeviceStatus::Offline);
+                            }
+                            pool.events.send(DevicePoolEvent::FarmDeviceUnhealthy {
+                                serial: address.clone(),
+                                error,
+                            });
+                        }
+                        Some(HealthChange::Recovered) => {
+                            let mut devices = pool.devices.write().await;
+                            match devices.get_mut(address) {
+                                Some(entry) => entry.set_status(DeviceStatus::Registered),
+                                None => {
+                                    drop(devices);
+                                    let _ = pool.register_device(address.clone(), Some(format!("farm:{}", address))).await;
+                                }
+                            }
+                            pool.events.send(DevicePoolEvent::FarmDeviceRecovered {
+                                serial: address.clone(),
+                            });
+                        }
+                        None => {}
+                    }
+                }
+            }
+        });
+    }
+
+    /// 将模型端点切换事件转发为设备池事件
+    pub fn forward_failover_events(&self) {
+        let events = Arc::clone(&self.events);
+        let mut failover_events = subscribe_failover_events();
+        tokio::spawn(async move {
+            loop {
+                match failover_events.recv().await {
+                    Ok(event) => {
+                        events.send(DevicePoolEvent::ModelFailover {
+                            model: event.model,
+                            fallback_active: event.fallback_active,
+                            reason: event.reason,
+                        });
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
     }
 
     /// 注册设备
@@ -99,7 +533,7 @@ impl DevicePool {
         let entry = DeviceEntry::new(serial.clone(), name);
         devices.insert(serial.clone(), entry);
 
-        let _ = self.event_tx.send(DevicePoolEvent::DeviceRegistered {
+        self.events.send(DevicePoolEvent::DeviceRegistered {
             serial: serial.clone(),
         });
 
@@ -116,14 +550,18 @@ impl DevicePool {
             if let Some(agent) = entry.agent.take() {
                 let _ = agent.stop().await;
             }
-            // ScrcpyConnect 会在 Drop 时自动清理
 
-            let _ = self
-                .event_tx
+            self
+                .events
                 .send(DevicePoolEvent::DeviceDisconnected {
                     serial: serial.to_string(),
                 });
+            drop(devices);
 
+            // 投屏服务持有自身的引用，需要显式停止
+            if let Some(connect) = entry.scrcpy.take() {
+                connect.stop().await;
+            }
             info!("设备已注销: {}", serial);
             Ok(())
         } else {
@@ -135,8 +573,13 @@ impl DevicePool {
         }
     }
 
-    /// 连接设备（懒加载 ScrcpyConnect）
+    /// 连接设备（懒加载 ScrcpyConnect），设备刚启动或重启时先等待系统就绪
     pub async fn connect_device(&self, serial: &str) -> Result<(), AppError> {
+        let needs_connect = self.devices.read().await.get(serial).is_some_and(|entry| entry.scrcpy.is_none());
+        if needs_connect && self.config.boot_wait_secs > 0 {
+            self.wait_until_booted(serial).await?;
+        }
+
         let mut devices = self.devices.write().await;
 
         let entry = devices
@@ -153,25 +596,140 @@ impl DevicePool {
             return Ok(());
         }
 
+        // 排空期间不再建立新的 scrcpy 会话
+        self.drain.check_admission()?;
+
+        // 检查 scrcpy 会话数限制
+        let active_streams = devices.values().filter(|e| e.scrcpy.is_some()).count();
+        self.capacity.check_stream(active_streams)?;
+
+        let entry = devices.get_mut(serial).unwrap();
         // 更新状态
         entry.set_status(DeviceStatus::Connecting);
 
-        // 创建 ScrcpyConnect（默认端口 27183）
-        let scrcpy_connect = crate::scrcpy::scrcpy::ScrcpyConnect::new(27183);
+        // 为该设备分配独立的本地转发端口，避免多设备冲突
+        let in_use: HashSet<u16> = devices
+            .values()
+            .filter_map(|e| e.scrcpy_server_port)
+            .collect();
+        let scrcpy_server_port = Self::allocate_forward_port(&in_use);
+
+        let entry = devices.get_mut(serial).unwrap();
+        let scrcpy_connect = crate::scrcpy::scrcpy::ScrcpyConnect::new(scrcpy_server_port)
+            .with_options(self.config.scrcpy_options.clone());
 
+        self.forward_stream_state(serial, &scrcpy_connect);
         entry.scrcpy = Some(Arc::new(scrcpy_connect));
+        entry.scrcpy_server_port = Some(scrcpy_server_port);
         entry.set_status(DeviceStatus::Connected);
 
-        let _ = self
-            .event_tx
+        self
+            .events
             .send(DevicePoolEvent::DeviceConnected {
                 serial: serial.to_string(),
             });
 
-        info!("设备已连接: {}", serial);
+        info!("设备已连接: {} (转发端口: {})", serial, scrcpy_server_port);
+        drop(devices);
+
+        // 读取设备元数据（型号、Android 版本等），失败不影响连接
+        match crate::scrcpy::device_info::query(serial).await {
+            Ok(metadata) => {
+                info!("设备 {} 元数据: {}", serial, metadata.summary());
+                if let Some(entry) = self.devices.write().await.get_mut(serial) {
+                    entry.metadata = Some(metadata);
+                }
+            }
+            Err(e) => warn!("读取设备 {} 元数据失败: {}", serial, e),
+        }
+
+        // 探测设备能力，失败时执行器按全部支持处理
+        match DeviceCapabilities::probe(serial).await {
+            Ok(capabilities) => {
+                info!("设备 {} 能力: {}", serial, capabilities.summary());
+                if let Some(entry) = self.devices.write().await.get_mut(serial) {
+                    entry.capabilities = Some(capabilities);
+                }
+            }
+            Err(e) => warn!("探测设备 {} 能力失败: {}", serial, e),
+        }
         Ok(())
     }
 
+    /// 等待设备启动完成，等待期间设备状态为 Connecting，超时后标记为错误
+    async fn wait_until_booted(&self, serial: &str) -> Result<(), AppError> {
+        if check_boot(serial).await.is_ready() {
+            return Ok(());
+        }
+        if let Some(entry) = self.devices.write().await.get_mut(serial) {
+            entry.set_status(DeviceStatus::Connecting);
+        }
+        if let Err(e) = wait_for_boot(serial, Duration::from_secs(self.config.boot_wait_secs)).await {
+            warn!("{}", e);
+            if let Some(entry) = self.devices.write().await.get_mut(serial) {
+                entry.set_status(DeviceStatus::Error(e.clone()));
+            }
+            return Err(AppError::DeviceNotConnected(e));
+        }
+        Ok(())
+    }
+
+    /// 启动设备的投屏会话（REST `/connect`）：未注册的设备自动注册，与 Agent 共用同一个 ScrcpyConnect，
+    /// 每台设备只有一个 scrcpy 会话
+    pub async fn start_stream(&self, serial: &str) -> Result<Arc<crate::scrcpy::scrcpy::ScrcpyConnect>, AppError> {
+        if !self.devices.read().await.contains_key(serial)
+            && let Err(e) = self.register_device(serial.to_string(), None).await
+            // 并发请求可能已经注册了该设备
+            && !self.devices.read().await.contains_key(serial)
+        {
+            return Err(e);
+        }
+        self.connect_device(serial).await?;
+
+        // 并发的断开请求可能已经清理了连接
+        let connect = self
+            .get_scrcpy_connect(serial)
+            .await
+            .ok_or_else(|| AppError::DeviceNotConnected(serial.to_string()))?;
+        if !connect.is_started() {
+            let device = crate::adb_server::fetch_device(&self.adb_server, serial)
+                .await
+                .map_err(|e| AppError::AdbError(format!("无法通过 adb 获取设备 {}: {:?}", serial, e)))?;
+            if connect.start(Arc::new(device)) {
+                info!("设备 {} 投屏服务已启动，Socket.IO 端口: {}", serial, connect.get_port());
+            }
+        }
+        Ok(connect)
+    }
+
+    /// 将投屏会话状态变化转发为设备池事件，ScrcpyConnect 释放后结束
+    fn forward_stream_state(&self, serial: &str, connect: &crate::scrcpy::scrcpy::ScrcpyConnect) {
+        let mut rx = connect.subscribe_session_state();
+        let events = Arc::clone(&self.events);
+        let serial = serial.to_string();
+        tokio::spawn(async move {
+            while rx.changed().await.is_ok() {
+                let snapshot = rx.borrow_and_update().clone();
+                events.send(DevicePoolEvent::StreamStateChanged {
+                    serial: serial.clone(),
+                    state: snapshot.state,
+                    reason: snapshot.reason,
+                });
+            }
+        });
+    }
+
+    /// 分配一个未被池内其他设备占用的本地转发端口
+    fn allocate_forward_port(in_use: &HashSet<u16>) -> u16 {
+        loop {
+            let port = crate::scrcpy::scrcpy::allocate_local_port();
+            if !in_use.contains(&port) {
+                return port;
+            }
+            debug!("端口 {} 已被其他设备占用，重新分配", port);
+        }
+    }
+
     /// 断开设备
     pub async fn disconnect_device(&self, serial: &str) -> Result<(), AppError> {
         let mut devices = self.devices.write().await;
@@ -191,19 +749,39 @@ impl DevicePool {
         }
 
         // 清理连接
-        entry.scrcpy = None;
+        let connect = entry.scrcpy.take();
+        entry.scrcpy_server_port = None;
         entry.set_status(DeviceStatus::Disconnected);
 
-        let _ = self
-            .event_tx
+        self
+            .events
             .send(DevicePoolEvent::DeviceDisconnected {
                 serial: serial.to_string(),
             });
+        drop(devices);
 
+        // 停止投屏服务（关闭 Socket.IO 监听、删除端口转发、结束 scrcpy-server），不持有设备锁
+        if let Some(connect) = connect {
+            connect.stop().await;
+        }
         info!("设备已断开: {}", serial);
         Ok(())
     }
 
+    /// 重启设备的投屏服务（客户端需重新连接），设备未连接时返回错误
+    pub async fn restart_stream(&self, serial: &str) -> Result<Arc<crate::scrcpy::scrcpy::ScrcpyConnect>, AppError> {
+        let connect = self
+            .get_scrcpy_connect(serial)
+            .await
+            .ok_or_else(|| AppError::DeviceNotConnected(serial.to_string()))?;
+        let device = crate::adb_server::fetch_device(&self.adb_server, serial)
+            .await
+            .map_err(|e| AppError::AdbError(format!("无法通过 adb 获取设备 {}: {:?}", serial, e)))?;
+        connect.restart(Arc::new(device)).await;
+        info!("设备 {} 投屏服务已重启", serial);
+        Ok(connect)
+    }
+
     /// 获取设备的 Agent（按需创建）
     pub async fn get_agent(&self, serial: &str) -> Result<Arc<PhoneAgent>, AppError> {
         // 确保设备已连接
@@ -218,17 +796,31 @@ impl DevicePool {
                 ),
             ))?;
 
-        // 如果 Agent 已存在，直接返回
+        // 如果 Agent 已存在，直接返回；配置已更新且 Agent 空闲时按新配置重建
         if let Some(agent) = &entry.agent {
-            debug!("复用现有 Agent: {} (设备: {})", agent.id(), serial);
-            let agent_arc = Arc::clone(agent);
-            entry.touch();
-            return Ok(agent_arc);
+            let outdated = entry.config_version != self.config_version()
+                && !matches!(agent.status().await, AgentStatus::Running { .. } | AgentStatus::Paused { .. });
+            if !outdated {
+                debug!("复用现有 Agent: {} (设备: {})", agent.id(), serial);
+                let agent_arc = Arc::clone(agent);
+                entry.touch();
+                return Ok(agent_arc);
+            }
+
+            let agent_id = agent.id().to_string();
+            info!("配置已更新，重建 Agent: {} (设备: {})", agent_id, serial);
+            entry.agent = None;
+            self.events.send(DevicePoolEvent::AgentDestroyed {
+                serial: serial.to_string(),
+                agent_id,
+            });
         }
 
         // 提取需要的数据以避免借用问题
         let scrcpy_opt = entry.scrcpy.clone();
         let name_opt = entry.name.clone();
+        let model_override = entry.model_override.clone();
+        let capabilities = entry.capabilities;
 
         // 创建新的 Agent
         let scrcpy = scrcpy_opt
@@ -241,8 +833,8 @@ impl DevicePool {
 
         // 创建 ADB device (需要在释放 devices 锁之前)
         drop(devices); // 先释放写锁
-        let mut adb_server = self.adb_server.write().await;
-        let adb_device = adb_server.get_device_by_name(&serial)
+        let adb_device = crate::adb_server::fetch_device(&self.adb_server, serial)
+            .await
             .map_err(|_| AppError::AgentError(
                 crate::agent::core::traits::AgentError::DeviceNotFound(serial.to_string())
             ))?;
@@ -252,16 +844,19 @@ impl DevicePool {
             name_opt.unwrap_or_else(|| serial.to_string()),
             Arc::clone(scrcpy),
             Arc::new(adb_device),
-        ));
-        let model_client = create_model_client(&self.model_config)?;
+        ).with_capabilities(capabilities));
+        let config_version = self.config_version();
+        let model_config = Self::effective_model_config(&self.model_config(), model_override.as_ref())?;
+        let model_client = create_model_client(&model_config)?;
 
         let agent_id = Uuid::new_v4().to_string();
         let agent = PhoneAgent::new(
             agent_id.clone(),
             device,
             model_client,
-            self.agent_config.clone(),
-        )?;
+            self.agent_config(),
+        )?
+        .with_judge(JudgeClient::from_config(&model_config)?.map(Arc::new));
 
         let agent_arc = Arc::new(agent);
 
@@ -269,9 +864,10 @@ impl DevicePool {
         let mut devices = self.devices.write().await;
         let entry = devices.get_mut(serial).unwrap();
         entry.agent = Some(Arc::clone(&agent_arc));
+        entry.config_version = config_version;
         entry.set_status(DeviceStatus::Busy);
 
-        let _ = self.event_tx.send(DevicePoolEvent::AgentCreated {
+        self.events.send(DevicePoolEvent::AgentCreated {
             serial: serial.to_string(),
             agent_id: agent_id.clone(),
         });
@@ -280,6 +876,219 @@ impl DevicePool {
         Ok(agent_arc)
     }
 
+    /// 申请任务执行许可，正在执行任务的 Agent 已达上限时按优先级排队或拒绝
+    ///
+    /// 开启抢占时，高优先级任务会暂停一个正在执行的低优先级任务（在步骤边界）并借用它的名额，
+    /// 许可释放时恢复被暂停的任务。许可交给 Agent（[`PhoneAgent::set_task_guard`]）持有，任务结束时释放
+    pub async fn admit_task(&self, priority: TaskPriority) -> Result<AgentPermit, AppError> {
+        if let Some(permit) = self.capacity.try_admit_agent() {
+            return Ok(permit);
+        }
+        if self.config.preemption
+            && priority == TaskPriority::High
+            && let Some(agent) = self.preemptible_agent().await
+        {
+            match agent.pause().await {
+                Ok(()) => {
+                    info!("高优先级任务抢占设备 {} 上的低优先级任务", agent.device_serial());
+                    return Ok(self.capacity.preempt(agent));
+                }
+                Err(e) => warn!("暂停设备 {} 上的低优先级任务失败: {}", agent.device_serial(), e),
+            }
+        }
+        self.capacity.admit_agent(priority).await
+    }
+
+    /// 正在执行、可以被抢占的低优先级任务所在的 Agent
+    async fn preemptible_agent(&self) -> Option<Arc<PhoneAgent>> {
+        let candidates: Vec<Arc<PhoneAgent>> = self
+            .devices
+            .read()
+            .await
+            .values()
+            .filter(|entry| entry.current_priority == TaskPriority::Low)
+            .filter_map(|entry| entry.agent.clone())
+            .collect();
+        for agent in candidates {
+            if !agent.pause_requested() && matches!(agent.status().await, AgentStatus::Running { .. }) {
+                return Some(agent);
+            }
+        }
+        None
+    }
+
+    /// 取得设备的执行名额：设备正在执行其它任务时按优先级排队，最多等待 `admission_wait_secs` 秒
+    async fn acquire_device_slot(&self, serial: &str, priority: TaskPriority) -> Result<GatePermit, AppError> {
+        let gate = Arc::clone(
+            self.device_slots
+                .lock()
+                .unwrap()
+                .entry(serial.to_string())
+                .or_insert_with(|| Arc::new(PriorityGate::new(1))),
+        );
+        if gate.is_full() {
+            info!("设备 {} 正在执行任务，{} 优先级任务排队等待", serial, priority);
+        }
+        gate.acquire(priority, Duration::from_secs(self.config.admission_wait_secs))
+            .await
+            .ok_or(AppError::AgentError(crate::agent::core::traits::AgentError::AlreadyRunning))
+    }
+
+    /// 启动任务：依次取得设备执行名额与 Agent 执行许可（均按优先级排队）后启动 Agent，返回任务 ID
+    pub async fn start_task(&self, agent: &PhoneAgent, task: String, priority: TaskPriority) -> Result<String, AppError> {
+        // 持有准入凭据直到 Agent 进入执行状态，排空期间据此等待启动中的任务
+        let _admission = self.drain.admit()?;
+        let serial = agent.device_serial().to_string();
+        let device_slot = self.acquire_device_slot(&serial, priority).await?;
+        let permit = self.admit_task(priority).await?;
+        // 排队期间可能已开始排空
+        self.drain.check_admission()?;
+        agent.set_task_guard(Some(Box::new((device_slot, permit)))).await;
+        match agent.start(task).await {
+            Ok(agent_id) => {
+                self.emulators.mark_used(&serial);
+                if let Some(entry) = self.devices.write().await.get_mut(&serial) {
+                    entry.current_priority = priority;
+                }
+                Ok(agent_id)
+            }
+            Err(e) => {
+                agent.set_task_guard(None).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// 不经过模型，直接在设备上依次执行一组操作，返回与操作一一对应的结果
+    ///
+    /// 复用设备 Agent 的操作处理器（校验、重试与日志）；与任务共用设备执行名额，设备正在执行任务时排队等待
+    pub async fn execute_actions(&self, serial: &str, actions: &[ActionEnum]) -> Result<Vec<ActionResult>, AppError> {
+        info!("设备 {} 直接执行 {} 个操作", serial, actions.len());
+        self.with_idle_agent(serial, |agent| async move {
+            agent.action_handler().execute_multiple_actions(actions).await
+        })
+        .await
+    }
+
+    /// 在空闲设备的 Agent 上执行 `op`，准入与排队规则与 [`Self::execute_actions`] 相同
+    pub async fn with_idle_agent<T, F, Fut>(&self, serial: &str, op: F) -> Result<T, AppError>
+    where
+        F: FnOnce(Arc<PhoneAgent>) -> Fut,
+        Fut: std::future::Future<Output = T>,
+    {
+        let _admission = self.drain.admit()?;
+        let _device_slot = self.acquire_device_slot(serial, TaskPriority::Normal).await?;
+        let agent = self.get_agent(serial).await?;
+        if matches!(agent.status().await, AgentStatus::Running { .. } | AgentStatus::Paused { .. }) {
+            return Err(AppError::AgentError(AgentError::AlreadyRunning));
+        }
+        Ok(op(agent).await)
+    }
+
+    /// 开始排空设备池：立即停止接受新任务，等待正在执行的任务结束，超过 `grace` 后停止剩余任务，
+    /// 随后断开全部 scrcpy 会话并进入静默状态。已在排空时保持原有进度
+    pub async fn start_drain(self: &Arc<Self>, grace: Duration) -> DrainStatus {
+        if let Some(generation) = self.drain.begin(grace.as_secs()) {
+            info!("设备池开始排空，宽限期 {} 秒", grace.as_secs());
+            self.events.send(DevicePoolEvent::PoolDraining { grace_secs: grace.as_secs() });
+            let pool = Arc::clone(self);
+            tokio::spawn(async move { pool.run_drain(generation, grace).await });
+        }
+        self.drain_status().await
+    }
+
+    /// 排空流程，排空被恢复时提前退出
+    async fn run_drain(&self, generation: u64, grace: Duration) {
+        let deadline = tokio::time::Instant::now() + grace;
+        // 宽限期后已停止的 Agent；开始排空前已通过准入的任务可能稍后才进入执行状态，同样需要停止
+        let mut stopped = HashSet::new();
+        loop {
+            if !self.drain.is_current(generation) {
+                return;
+            }
+            let running = self.running_agents().await;
+            if running.is_empty() && self.drain.in_flight() == 0 {
+                break;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                let pending: Vec<_> = running.iter().filter(|agent| !stopped.contains(agent.id())).collect();
+                if !pending.is_empty() {
+                    warn!("排空宽限期已过，停止 {} 个仍在执行的任务", pending.len());
+                }
+                for agent in &pending {
+                    if let Err(e) = agent.stop().await {
+                        warn!("停止设备 {} 上的任务失败: {}", agent.device_serial(), e);
+                    }
+                    stopped.insert(agent.id().to_string());
+                }
+                self.drain.record_cancelled(generation, pending.len());
+                if self.drain.in_flight() == 0 {
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        let connected: Vec<String> = self
+            .devices
+            .read()
+            .await
+            .iter()
+            .filter(|(_, entry)| entry.scrcpy.is_some())
+            .map(|(serial, _)| serial.clone())
+            .collect();
+        for serial in connected {
+            if !self.drain.is_current(generation) {
+                return;
+            }
+            if let Err(e) = self.disconnect_device(&serial).await {
+                warn!("排空时断开设备 {} 失败: {}", serial, e);
+            }
+        }
+
+        if self.drain.mark_quiesced(generation) {
+            let cancelled_tasks = self.drain.status(0, 0).cancelled_tasks;
+            info!("设备池已排空（停止 {} 个任务），可以安全停止服务", cancelled_tasks);
+            self.events.send(DevicePoolEvent::PoolQuiesced { cancelled_tasks });
+        }
+    }
+
+    /// 结束排空，恢复接受新任务；原本未在排空时返回 false
+    pub fn resume_accepting(&self) -> bool {
+        let resumed = self.drain.resume();
+        if resumed {
+            info!("设备池恢复接受任务");
+            self.events.send(DevicePoolEvent::PoolResumed);
+        }
+        resumed
+    }
+
+    /// 当前排空状态
+    pub async fn drain_status(&self) -> DrainStatus {
+        let running = self.running_agents().await.len() + self.drain.in_flight();
+        let streams = self.devices.read().await.values().filter(|e| e.scrcpy.is_some()).count();
+        self.drain.status(running, streams)
+    }
+
+    /// 正在执行（含暂停）任务的 Agent
+    async fn running_agents(&self) -> Vec<Arc<PhoneAgent>> {
+        let agents: Vec<Arc<PhoneAgent>> =
+            self.devices.read().await.values().filter_map(|entry| entry.agent.clone()).collect();
+        let mut running = Vec::new();
+        for agent in agents {
+            if matches!(agent.status().await, AgentStatus::Running { .. } | AgentStatus::Paused { .. }) {
+                running.push(agent);
+            }
+        }
+        running
+    }
+
+    /// 当前容量使用情况
+    pub async fn capacity_snapshot(&self) -> CapacitySnapshot {
+        let streams = self.devices.read().await.values().filter(|e| e.scrcpy.is_some()).count();
+        self.capacity.snapshot(streams)
+    }
+
     /// 释放设备的 Agent
     pub async fn release_agent(&self, serial: &str) -> Result<(), AppError> {
         let mut devices = self.devices.write().await;
@@ -300,7 +1109,7 @@ impl DevicePool {
             entry.set_status(DeviceStatus::Connected);
             entry.complete_task();
 
-            let _ = self.event_tx.send(DevicePoolEvent::AgentDestroyed {
+            self.events.send(DevicePoolEvent::AgentDestroyed {
                 serial: serial.to_string(),
                 agent_id,
             });
@@ -332,6 +1141,12 @@ impl DevicePool {
         devices.get(serial).map(|entry| entry.to_info())
     }
 
+    /// 获取设备的 ScrcpyConnect（如果已连接）
+    pub async fn get_scrcpy_connect(&self, serial: &str) -> Option<Arc<crate::scrcpy::scrcpy::ScrcpyConnect>> {
+        let devices = self.devices.read().await;
+        devices.get(serial).and_then(|entry| entry.scrcpy.clone())
+    }
+
     /// 获取所有设备详细信息
     pub async fn get_all_devices_info(&self) -> Vec<crate::agent::pool::types::DeviceInfo> {
         let devices = self.devices.read().await;
@@ -344,6 +1159,7 @@ impl DevicePool {
         let threshold = self.config.idle_cleanup_threshold as i64;
 
         let mut cleaned = 0;
+        let mut stopped = Vec::new();
 
         for (serial, entry) in devices.iter_mut() {
             if entry.is_idle(threshold) {
@@ -357,11 +1173,17 @@ impl DevicePool {
                 // 可选：断开连接（如果空闲时间超过阈值的两倍）
                 if entry.idle_seconds() > threshold * 2 {
                     info!("断开空闲连接: {}", serial);
-                    entry.scrcpy = None;
+                    stopped.extend(entry.scrcpy.take());
+                    entry.scrcpy_server_port = None;
                     entry.set_status(DeviceStatus::Disconnected);
                 }
             }
         }
+        drop(devices);
+
+        for connect in stopped {
+            connect.stop().await;
+        }
 
         if cleaned > 0 {
             info!("清理了 {} 个空闲设备", cleaned);
@@ -407,8 +1229,8 @@ impl DevicePool {
         let task_clone = task.clone();
         entry.start_task(task_id, task);
 
-        let _ = self
-            .event_tx
+        self
+            .events
             .send(DevicePoolEvent::TaskStarted {
                 serial: serial.to_string(),
                 task: task_clone,
@@ -435,8 +1257,8 @@ impl DevicePool {
 
         entry.complete_task();
 
-        let _ = self
-            .event_tx
+        self
+            .events
             .send(DevicePoolEvent::TaskCompleted {
                 serial: serial.to_string(),
                 result,
@@ -463,7 +1285,7 @@ impl DevicePool {
 
         entry.complete_task();
 
-        let _ = self.event_tx.send(DevicePoolEvent::TaskFailed {
+        self.events.send(DevicePoolEvent::TaskFailed {
             serial: serial.to_string(),
             error,
         });
@@ -471,3 +1293,59 @@ impl DevicePool {
         Ok(())
     }
 }
+
+/// 将 JSON 对象中的字段覆盖到配置上
+fn merge_config<T: serde::Serialize + serde::de::DeserializeOwned>(
+    current: &T,
+    patch: &serde_json::Value,
+) -> Result<T, AppError> {
+    let patch = patch
+        .as_object()
+        .ok_or_else(|| AppError::Unknown("配置必须是对象".to_string()))?;
+    let mut value = serde_json::to_value(current)?;
+    if let Some(object) = value.as_object_mut() {
+        for (key, field) in patch {
+            object.insert(key.clone(), field.clone());
+        }
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+    use crate::agent::core::state::TaskLimits;
+
+    #[tokio::test]
+    async fn test_model_override() {
+        let adb_server = ADBServer::new_from_path(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 5037), None);
+        let pool = DevicePool::new(
+            DevicePoolConfig::default(),
+            Arc::new(RwLock::new(adb_server)),
+            ModelConfig::default(),
+            AgentConfig::default(),
+        );
+        pool.register_device("emulator-5554".to_string(), None).await.unwrap();
+
+        let model = pool
+            .set_model_override("emulator-5554", Some(serde_json::json!({ "model_name": "gpt-4o-mini" })))
+            .await
+            .unwrap();
+        assert_eq!(model.model_name, "gpt-4o-mini");
+
+        // 全局配置更新后，未覆盖的字段跟随全局配置
+        pool.apply_config_patch(&serde_json::json!({ "model": { "temperature": 0.5 } })).unwrap();
+        let model = pool.device_model_config("emulator-5554").await.unwrap();
+        assert_eq!((model.model_name.as_str(), model.temperature), ("gpt-4o-mini", 0.5));
+
+        assert!(pool.set_model_override("emulator-5554", Some(serde_json::json!({ "provider": "unknown" }))).await.is_err());
+        pool.set_model_override("emulator-5554", None).await.unwrap();
+        assert_eq!(pool.device_model_config("emulator-5554").await.unwrap().model_name, "gpt-4o");
+
+        // 超出范围的任务限制不会写入配置
+        assert!(pool.apply_config_patch(&serde_json::json!({ "agent": { "max_execution_time": 0 } })).is_err());
+        pool.apply_config_patch(&serde_json::json!({ "agent": { "max_steps": 80, "max_execution_time": 900 } })).unwrap();
+        assert_eq!(pool.agent_config().limits(), TaskLimits { max_steps: 80, max_execution_time: 900 });
+    }
+}
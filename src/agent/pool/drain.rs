@@ -0,0 +1,244 @@
+//! 设备池排空（维护模式）
+//!
+//! 升级或重启繁忙的服务前先排空设备池：不再接受新任务，等待正在执行的任务完成，
+//! 超过宽限期仍未结束的任务被停止，最后断开全部 scrcpy 会话。进入静默（quiesced）状态后即可安全地停止服务，
+//! 也可以随时恢复接受任务。
+
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::agent::core::traits::AgentError;
+use crate::error::AppError;
+
+/// 排空期间拒绝新任务时返回的状态码（HTTP 503 Service Unavailable）
+pub const DRAINING_STATUS: u16 = 503;
+
+/// 是否为设备池排空导致的拒绝
+pub fn is_draining_error(error: &AppError) -> bool {
+    matches!(error, AppError::AgentError(AgentError::Draining))
+}
+
+/// 排空阶段
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrainPhase {
+    /// 正常接受任务
+    #[default]
+    Accepting,
+    /// 不再接受新任务，等待正在执行的任务结束
+    Draining,
+    /// 任务已全部结束、scrcpy 会话已断开，可以安全停止服务
+    Quiesced,
+}
+
+/// 排空状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DrainStatus {
+    pub phase: DrainPhase,
+    /// 开始排空的时间
+    pub started_at: Option<DateTime<Utc>>,
+    /// 宽限期（秒），超过后停止仍在执行的任务
+    pub grace_secs: u64,
+    /// 宽限期截止时间
+    pub deadline: Option<DateTime<Utc>>,
+    /// 进入静默状态的时间
+    pub quiesced_at: Option<DateTime<Utc>>,
+    /// 仍在执行（含暂停、排队与启动中）的任务数
+    pub running_tasks: usize,
+    /// 仍然建立的 scrcpy 会话数
+    pub streams: usize,
+    /// 因超过宽限期而被停止的任务数
+    pub cancelled_tasks: usize,
+}
+
+#[derive(Debug, Default)]
+struct DrainState {
+    phase: DrainPhase,
+    /// 每次开始排空递增，恢复后旧的排空任务据此退出
+    generation: u64,
+    started_at: Option<DateTime<Utc>>,
+    grace_secs: u64,
+    quiesced_at: Option<DateTime<Utc>>,
+    cancelled_tasks: usize,
+    /// 已通过准入检查、尚未开始执行（排队或启动中）的任务数
+    in_flight: usize,
+}
+
+/// 排空控制：记录当前阶段并在排空期间拒绝新任务
+#[derive(Debug, Default)]
+pub struct DrainController {
+    state: Mutex<DrainState>,
+}
+
+/// 准入凭据：持有期间任务计入启动中的任务，排空不会在其释放前进入静默状态
+#[derive(Debug)]
+pub struct Admission<'a> {
+    drain: &'a DrainController,
+}
+
+impl Drop for Admission<'_> {
+    fn drop(&mut self) {
+        self.drain.state.lock().unwrap().in_flight -= 1;
+    }
+}
+
+impl DrainController {
+    /// 是否正在排空或已静默
+    pub fn is_draining(&self) -> bool {
+        self.state.lock().unwrap().phase != DrainPhase::Accepting
+    }
+
+    /// 排空期间拒绝新任务
+    pub fn check_admission(&self) -> Result<(), AppError> {
+        if self.is_draining() {
+            return Err(AppError::AgentError(AgentError::Draining));
+        }
+        Ok(())
+    }
+
+    /// 准入检查并登记启动中的任务：与开始排空在同一把锁内完成，排空开始后不会再有任务通过检查
+    pub fn admit(&self) -> Result<Admission<'_>, AppError> {
+        let mut state = self.state.lock().unwrap();
+        if state.phase != DrainPhase::Accepting {
+            return Err(AppError::AgentError(AgentError::Draining));
+        }
+        state.in_flight += 1;
+        Ok(Admission { drain: self })
+    }
+
+    /// 已通过准入检查、尚未开始执行的任务数
+    pub fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight
+    }
+
+    /// 开始排空，返回本次排空的编号；已在排空时返回 None
+    pub fn begin(&self, grace_secs: u64) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+        if state.phase != DrainPhase::Accepting {
+            return None;
+        }
+        *state = DrainState {
+            phase: DrainPhase::Draining,
+            generation: state.generation + 1,
+            started_at: Some(Utc::now()),
+            grace_secs,
+            quiesced_at: None,
+            cancelled_tasks: 0,
+            in_flight: state.in_flight,
+        };
+        Some(state.generation)
+    }
+
+    /// 编号为 `generation` 的排空是否仍在进行（未被恢复或重新开始）
+    pub fn is_current(&self, generation: u64) -> bool {
+        let state = self.state.lock().unwrap();
+        state.generation == generation && state.phase == DrainPhase::Draining
+    }
+
+    /// 记录超过宽限期被停止的任务
+    pub fn record_cancelled(&self, generation: u64, count: usize) {
+        let mut state = self.state.lock().unwrap();
+        if state.generation == generation {
+            state.cancelled_tasks += count;
+        }
+    }
+
+    /// 标记为静默，排空已被恢复或仍有启动中的任务时返回 false
+    pub fn mark_quiesced(&self, generation: u64) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.generation != generation || state.phase != DrainPhase::Draining || state.in_flight > 0 {
+            return false;
+        }
+        state.phase = DrainPhase::Quiesced;
+        state.quiesced_at = Some(Utc::now());
+        true
+    }
+
+    /// 恢复接受任务，原本未在排空时返回 false
+    pub fn resume(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.phase == DrainPhase::Accepting {
+            return false;
+        }
+        state.phase = DrainPhase::Accepting;
+        true
+    }
+
+    /// 当前排空状态
+    pub fn status(&self, running_tasks: usize, streams: usize) -> DrainStatus {
+        let state = self.state.lock().unwrap();
+        if state.phase == DrainPhase::Accepting {
+            return DrainStatus { running_tasks, streams, ..Default::default() };
+        }
+        DrainStatus {
+            phase: state.phase,
+            started_at: state.started_at,
+            grace_secs: state.grace_secs,
+            deadline: state
+                .started_at
+                .map(|started| started + chrono::Duration::seconds(state.grace_secs as i64)),
+            quiesced_at: state.quiesced_at,
+            running_tasks,
+            streams,
+            cancelled_tasks: state.cancelled_tasks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_lifecycle() {
+        let drain = DrainController::default();
+        assert!(drain.check_admission().is_ok());
+
+        let generation = drain.begin(30).unwrap();
+        assert!(drain.begin(60).is_none());
+        assert!(is_draining_error(&drain.check_admission().unwrap_err()));
+        drain.record_cancelled(generation, 2);
+
+        let status = drain.status(1, 3);
+        assert_eq!(status.phase, DrainPhase::Draining);
+        assert_eq!(status.grace_secs, 30);
+        assert_eq!(status.cancelled_tasks, 2);
+        assert_eq!((status.running_tasks, status.streams), (1, 3));
+
+        assert!(drain.mark_quiesced(generation));
+        assert_eq!(drain.status(0, 0).phase, DrainPhase::Quiesced);
+
+        assert!(drain.resume());
+        assert!(!drain.resume());
+        assert!(drain.check_admission().is_ok());
+        assert_eq!(drain.status(0, 0), DrainStatus::default());
+    }
+
+    #[test]
+    fn test_resumed_drain_does_not_quiesce() {
+        let drain = DrainController::default();
+        let first = drain.begin(10).unwrap();
+        drain.resume();
+        let second = drain.begin(10).unwrap();
+        assert!(!drain.is_current(first));
+        assert!(!drain.mark_quiesced(first));
+        assert!(drain.is_current(second));
+        assert!(drain.mark_quiesced(second));
+    }
+
+    #[test]
+    fn test_in_flight_blocks_quiesce() {
+        let drain = DrainController::default();
+        let admission = drain.admit().unwrap();
+        let generation = drain.begin(10).unwrap();
+        assert!(is_draining_error(&drain.admit().unwrap_err()));
+        assert_eq!(drain.in_flight(), 1);
+        assert!(!drain.mark_quiesced(generation));
+
+        drop(admission);
+        assert_eq!(drain.in_flight(), 0);
+        assert!(drain.mark_quiesced(generation));
+    }
+}
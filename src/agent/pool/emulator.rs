@@ -0,0 +1,405 @@
+//! 模拟器管理
+//!
+//! 按需启动 Android 模拟器（SDK 的 `emulator` + AVD，或 Cuttlefish 的 `launch_cvd`），启动完成后由设备池注册，
+//! 任务结束后销毁，没有真机时也能弹性扩充自动化容量。同一 AVD 以 `-read-only` 启动，可以同时运行多个实例。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::process::{Child, Command};
+use tracing::{info, warn};
+
+/// Cuttlefish 第 1 个实例的 adb 端口
+const CUTTLEFISH_ADB_PORT: u16 = 6520;
+/// 检查启动状态的间隔
+const BOOT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 模拟器类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmulatorBackend {
+    /// Android SDK 模拟器（AVD）
+    #[default]
+    Avd,
+    /// Cuttlefish 虚拟设备
+    Cuttlefish,
+}
+
+/// 模拟器配置（`[pool.emulator]` 段）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmulatorConfig {
+    pub backend: EmulatorBackend,
+    /// AVD 名称（`avdmanager list avd`），AVD 模式必填
+    pub avd: Option<String>,
+    /// `emulator` 或 `launch_cvd` 所在目录，未配置时 AVD 模式使用 SDK 下的 `emulator` 目录，否则从 PATH 查找
+    pub bin_dir: Option<String>,
+    /// 最多同时运行的模拟器数（0 表示不启用模拟器管理）
+    pub max_instances: usize,
+    /// 第 1 个 AVD 实例的控制台端口（adb 序列号为 `emulator-<端口>`，每个实例占用 2 个端口）
+    pub base_port: u16,
+    /// 等待系统启动完成的最长时间（秒）
+    pub boot_timeout_secs: u64,
+    /// 无窗口运行
+    pub headless: bool,
+    /// 额外的启动参数
+    pub extra_args: Vec<String>,
+    /// 执行过任务的模拟器在任务结束后销毁
+    pub destroy_after_task: bool,
+    /// 启动后一直没有任务的模拟器在空闲多久后销毁（秒，0 表示不销毁）
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for EmulatorConfig {
+    fn default() -> Self {
+        Self {
+            backend: EmulatorBackend::Avd,
+            avd: None,
+            bin_dir: None,
+            max_instances: 0,
+            base_port: 5554,
+            boot_timeout_secs: 180,
+            headless: true,
+            extra_args: Vec::new(),
+            destroy_after_task: true,
+            idle_timeout_secs: 600,
+        }
+    }
+}
+
+/// 模拟器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmulatorState {
+    Booting,
+    Ready,
+}
+
+/// 受管理的模拟器
+#[derive(Debug, Clone, Serialize)]
+pub struct EmulatorInstance {
+    pub serial: String,
+    pub backend: EmulatorBackend,
+    /// 实例序号（从 0 开始）
+    pub slot: usize,
+    pub state: EmulatorState,
+    /// 是否执行过任务
+    pub used: bool,
+    /// 已运行时长（秒）
+    pub uptime_secs: u64,
+    #[serde(skip)]
+    started_at: Instant,
+}
+
+struct Running {
+    instance: EmulatorInstance,
+    child: Option<Child>,
+}
+
+/// 模拟器进程管理，设备注册与销毁时机由设备池负责
+pub struct EmulatorManager {
+    config: EmulatorConfig,
+    instances: Mutex<HashMap<String, Running>>,
+}
+
+impl EmulatorManager {
+    pub fn new(config: EmulatorConfig) -> Self {
+        Self {
+            config,
+            instances: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &EmulatorConfig {
+        &self.config
+    }
+
+    /// 是否启用了模拟器管理
+    pub fn enabled(&self) -> bool {
+        self.config.max_instances > 0
+    }
+
+    /// 是否为受管理的模拟器
+    pub fn is_managed(&self, serial: &str) -> bool {
+        self.instances.lock().unwrap().contains_key(serial)
+    }
+
+    /// 所有受管理的模拟器
+    pub fn list(&self) -> Vec<EmulatorInstance> {
+        let mut instances: Vec<EmulatorInstance> = self
+            .instances
+            .lock()
+            .unwrap()
+            .values()
+            .map(|running| EmulatorInstance {
+                uptime_secs: running.instance.started_at.elapsed().as_secs(),
+                ..running.instance.clone()
+            })
+            .collect();
+        instances.sort_by_key(|instance| instance.slot);
+        instances
+    }
+
+    /// 记录模拟器已开始执行任务
+    pub fn mark_used(&self, serial: &str) {
+        if let Some(running) = self.instances.lock().unwrap().get_mut(serial) {
+            running.instance.used = true;
+        }
+    }
+
+    /// 启动一个模拟器并等待系统启动完成，返回其 adb 序列号
+    pub async fn boot(&self) -> Result<EmulatorInstance, String> {
+        if !self.enabled() {
+            return Err("未启用模拟器管理（[pool.emulator] max_instances 为 0）".to_string());
+        }
+        if self.config.backend == EmulatorBackend::Avd && self.config.avd.is_none() {
+            return Err("未配置 AVD 名称（[pool.emulator] avd）".to_string());
+        }
+
+        let (serial, slot) = {
+            let mut instances = self.instances.lock().unwrap();
+            let slot = free_slot(instances.values().map(|r| r.instance.slot), self.config.max_instances)
+                .ok_or_else(|| format!("模拟器数量已达上限: {}", self.config.max_instances))?;
+            let serial = serial_for(&self.config, slot);
+            instances.insert(
+                serial.clone(),
+                Running {
+                    instance: EmulatorInstance {
+                        serial: serial.clone(),
+                        backend: self.config.backend,
+                        slot,
+                        state: EmulatorState::Booting,
+                        used: false,
+                        uptime_secs: 0,
+                        started_at: Instant::now(),
+                    },
+                    child: None,
+                },
+            );
+            (serial, slot)
+        };
+
+        info!("启动模拟器 {}（{:?}）", serial, self.config.backend);
+        let child = launch_command(&self.config, slot)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(self.config.backend == EmulatorBackend::Avd)
+            .spawn();
+        match child {
+            Ok(child) => {
+                if let Some(running) = self.instances.lock().unwrap().get_mut(&serial) {
+                    running.child = Some(child);
+                }
+            }
+            Err(e) => {
+                self.instances.lock().unwrap().remove(&serial);
+                return Err(format!("启动模拟器失败: {}", e));
+            }
+        }
+
+        if let Err(e) = self.wait_for_boot(&serial).await {
+            let _ = self.destroy(&serial).await;
+            return Err(e);
+        }
+
+        let mut instances = self.instances.lock().unwrap();
+        let running = instances
+            .get_mut(&serial)
+            .ok_or_else(|| format!("模拟器 {} 在启动过程中被销毁", serial))?;
+        running.instance.state = EmulatorState::Ready;
+        info!("模拟器 {} 启动完成，用时 {:?}", serial, running.instance.started_at.elapsed());
+        Ok(running.instance.clone())
+    }
+
+    /// 轮询 `sys.boot_completed`，模拟器进程提前退出或超时时返回错误
+    async fn wait_for_boot(&self, serial: &str) -> Result<(), String> {
+        let deadline = Instant::now() + Duration::from_secs(self.config.boot_timeout_secs);
+        let mut connected = false;
+        while Instant::now() < deadline {
+            tokio::time::sleep(BOOT_POLL_INTERVAL).await;
+
+            let exited = match self.instances.lock().unwrap().get_mut(serial) {
+                // `launch_cvd --daemon` 启动后即退出，只检查 AVD 进程
+                Some(running) if self.config.backend == EmulatorBackend::Avd => running
+                    .child
+                    .as_mut()
+                    .and_then(|child| child.try_wait().ok().flatten()),
+                Some(_) => None,
+                None => return Err(format!("模拟器 {} 已被销毁", serial)),
+            };
+            if let Some(status) = exited {
+                return Err(format!("模拟器进程已退出: {}", status));
+            }
+
+            if self.config.backend == EmulatorBackend::Cuttlefish && !connected {
+                connected = crate::platform::adb_command()
+                    .args(["connect", serial])
+                    .output()
+                    .await
+                    .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("connected"));
+            }
+
+            let booted = crate::platform::adb_device_command(serial)
+                .args(["shell", "getprop", "sys.boot_completed"])
+                .output()
+                .await
+                .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "1");
+            if booted {
+                return Ok(());
+            }
+        }
+        Err(format!("模拟器 {} 启动超时（{} 秒）", serial, self.config.boot_timeout_secs))
+    }
+
+    /// 关闭并移除模拟器
+    pub async fn destroy(&self, serial: &str) -> Result<(), String> {
+        let running = self
+            .instances
+            .lock()
+            .unwrap()
+            .remove(serial)
+            .ok_or_else(|| format!("不是受管理的模拟器: {}", serial))?;
+
+        let stopped = match running.instance.backend {
+            EmulatorBackend::Avd => crate::platform::adb_device_command(serial)
+                .args(["emu", "kill"])
+                .output()
+                .await
+                .is_ok_and(|output| output.status.success()),
+            EmulatorBackend::Cuttlefish => {
+                let _ = crate::platform::adb_command().args(["disconnect", serial]).output().await;
+                tool_command(&self.config, "stop_cvd")
+                    .env("CUTTLEFISH_INSTANCE", (running.instance.slot + 1).to_string())
+                    .output()
+                    .await
+                    .is_ok_and(|output| output.status.success())
+            }
+        };
+
+        if let Some(mut child) = running.child {
+            if !stopped {
+                warn!("模拟器 {} 未能正常关闭，结束进程", serial);
+                let _ = child.kill().await;
+            } else if tokio::time::timeout(Duration::from_secs(10), child.wait()).await.is_err() {
+                let _ = child.kill().await;
+            }
+        }
+        info!("模拟器已销毁: {}", serial);
+        Ok(())
+    }
+
+    /// 需要回收的模拟器：执行过任务且当前空闲（`destroy_after_task`），或启动后空闲超过 `idle_timeout_secs`
+    pub fn reclaimable(&self, is_busy: impl Fn(&str) -> bool) -> Vec<String> {
+        let idle_timeout = Duration::from_secs(self.config.idle_timeout_secs);
+        self.instances
+            .lock()
+            .unwrap()
+            .values()
+            .map(|running| &running.instance)
+            .filter(|instance| instance.state == EmulatorState::Ready && !is_busy(&instance.serial))
+            .filter(|instance| {
+                if instance.used {
+                    self.config.destroy_after_task
+                } else {
+                    !idle_timeout.is_zero() && instance.started_at.elapsed() >= idle_timeout
+                }
+            })
+            .map(|instance| instance.serial.clone())
+            .collect()
+    }
+}
+
+/// 第一个未被占用的实例序号
+fn free_slot(used: impl Iterator<Item = usize>, max_instances: usize) -> Option<usize> {
+    let used: Vec<usize> = used.collect();
+    (0..max_instances).find(|slot| !used.contains(slot))
+}
+
+/// 实例的 adb 序列号
+fn serial_for(config: &EmulatorConfig, slot: usize) -> String {
+    match config.backend {
+        EmulatorBackend::Avd => format!("emulator-{}", config.base_port as usize + slot * 2),
+        EmulatorBackend::Cuttlefish => format!("0.0.0.0:{}", CUTTLEFISH_ADB_PORT as usize + slot),
+    }
+}
+
+/// 模拟器工具命令，优先使用 `bin_dir` 或 SDK 下的路径
+fn tool_command(config: &EmulatorConfig, tool: &str) -> Command {
+    let file = format!("{}{}", tool, std::env::consts::EXE_SUFFIX);
+    let dir = config.bin_dir.as_ref().map(PathBuf::from).or_else(|| {
+        (config.backend == EmulatorBackend::Avd)
+            .then(|| ["ANDROID_HOME", "ANDROID_SDK_ROOT"].iter().find_map(|key| std::env::var(key).ok()))
+            .flatten()
+            .map(|sdk| PathBuf::from(sdk).join("emulator"))
+            .filter(|dir| dir.join(&file).is_file())
+    });
+    Command::new(dir.map(|dir| dir.join(&file)).unwrap_or_else(|| PathBuf::from(file)))
+}
+
+/// 启动第 `slot` 个实例的命令
+fn launch_command(config: &EmulatorConfig, slot: usize) -> Command {
+    let mut command;
+    match config.backend {
+        EmulatorBackend::Avd => {
+            command = tool_command(config, "emulator");
+            command
+                .args(["-avd", config.avd.as_deref().unwrap_or_default()])
+                .args(["-port", &(config.base_port as usize + slot * 2).to_string()])
+                .args(["-read-only", "-no-snapshot-save", "-no-boot-anim", "-no-audio"]);
+            if config.headless {
+                command.arg("-no-window");
+            }
+        }
+        EmulatorBackend::Cuttlefish => {
+            command = tool_command(config, "launch_cvd");
+            command
+                .arg("--daemon")
+                .arg(format!("--base_instance_num={}", slot + 1))
+                .args(["--num_instances=1", "--report_anonymous_usage_stats=n"]);
+            if !config.headless {
+                command.arg("--start_webrtc=true");
+            }
+        }
+    }
+    command.args(&config.extra_args);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_slots_and_commands() {
+        let config = EmulatorConfig {
+            avd: Some("Pixel_7_API_34".to_string()),
+            max_instances: 3,
+            bin_dir: Some("/opt/sdk/emulator".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(free_slot([0, 2].into_iter(), 3), Some(1));
+        assert_eq!(free_slot([0, 1, 2].into_iter(), 3), None);
+        assert_eq!(serial_for(&config, 1), "emulator-5556");
+
+        let command = launch_command(&config, 1);
+        let args: Vec<_> = command.as_std().get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert!(command.as_std().get_program().to_string_lossy().starts_with("/opt/sdk/emulator/emulator"));
+        assert!(args.windows(2).any(|pair| pair == ["-port", "5556"]));
+        assert!(args.contains(&"-no-window".to_string()));
+
+        let cuttlefish = EmulatorConfig {
+            backend: EmulatorBackend::Cuttlefish,
+            ..config
+        };
+        assert_eq!(serial_for(&cuttlefish, 2), "0.0.0.0:6522");
+        let args: Vec<_> = launch_command(&cuttlefish, 2).as_std().get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert!(args.contains(&"--base_instance_num=3".to_string()));
+
+        let manager = EmulatorManager::new(EmulatorConfig::default());
+        assert!(!manager.enabled() && manager.list().is_empty());
+    }
+}
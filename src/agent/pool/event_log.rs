@@ -0,0 +1,150 @@
+//! 设备池事件日志
+//!
+//! broadcast 通道在订阅者处理过慢时会丢弃事件（只返回 `Lagged`）。设备池给每个事件编上单调递增的序号，
+//! 并在固定容量的环形缓冲区中保留最近的事件：订阅者记录最后处理的序号，发现序号不连续或收到 `Lagged` 后，
+//! 用 [`EventLog::since`]（`GET /pool/events?since=N`）补齐缺失的事件。
+
+use std::collections::VecDeque;
+use std::sync::Mutex as StdMutex;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use super::types::DevicePoolEvent;
+
+/// 默认保留的事件数
+pub const DEFAULT_EVENT_LOG_CAPACITY: usize = 1000;
+
+/// 带序号的设备池事件
+#[derive(Debug, Clone, Serialize)]
+pub struct SequencedEvent {
+    /// 序号，从 1 开始单调递增
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: DevicePoolEvent,
+}
+
+/// 按序号获取的一页事件
+#[derive(Debug, Clone, Serialize)]
+pub struct EventPage {
+    /// 事件日志的标识，服务重启后变化，此时序号从 1 重新开始
+    pub epoch: String,
+    pub events: Vec<SequencedEvent>,
+    /// 下次请求使用的游标（本页最后一个事件的序号，没有新事件时不变）
+    pub next: u64,
+    /// 最新事件的序号
+    pub latest: u64,
+    /// 游标之后的部分事件已被覆盖，客户端应重新获取设备与任务的完整状态
+    pub gap: bool,
+}
+
+#[derive(Debug)]
+struct EventRing {
+    next_seq: u64,
+    events: VecDeque<SequencedEvent>,
+}
+
+/// 设备池事件日志：分配序号、保留最近的事件并广播
+#[derive(Debug)]
+pub struct EventLog {
+    capacity: usize,
+    epoch: String,
+    ring: StdMutex<EventRing>,
+    tx: broadcast::Sender<SequencedEvent>,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(100);
+        Self {
+            capacity: capacity.max(1),
+            epoch: Utc::now().timestamp_millis().to_string(),
+            ring: StdMutex::new(EventRing { next_seq: 1, events: VecDeque::new() }),
+            tx,
+        }
+    }
+
+    /// 记录并广播事件，返回分配的序号
+    pub fn send(&self, event: DevicePoolEvent) -> u64 {
+        let mut ring = self.ring.lock().unwrap();
+        let event = SequencedEvent { seq: ring.next_seq, timestamp: Utc::now(), event };
+        ring.next_seq += 1;
+        if ring.events.len() >= self.capacity {
+            ring.events.pop_front();
+        }
+        ring.events.push_back(event.clone());
+        // 持有锁时广播，保证订阅者收到的序号有序
+        let _ = self.tx.send(event.clone());
+        event.seq
+    }
+
+    /// 订阅之后的事件
+    pub fn subscribe(&self) -> broadcast::Receiver<SequencedEvent> {
+        self.tx.subscribe()
+    }
+
+    /// 序号大于 `since` 的事件，最多 `limit` 条
+    pub fn since(&self, since: u64, limit: usize) -> EventPage {
+        let ring = self.ring.lock().unwrap();
+        let latest = ring.next_seq - 1;
+        let oldest = ring.events.front().map_or(ring.next_seq, |e| e.seq);
+        let events: Vec<SequencedEvent> = ring.events.iter().filter(|e| e.seq > since).take(limit).cloned().collect();
+        EventPage {
+            epoch: self.epoch.clone(),
+            next: events.last().map_or(since.min(latest), |e| e.seq),
+            latest,
+            gap: since + 1 < oldest,
+            events,
+        }
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_LOG_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registered(serial: &str) -> DevicePoolEvent {
+        DevicePoolEvent::DeviceRegistered { serial: serial.to_string() }
+    }
+
+    #[test]
+    fn test_since_returns_events_after_cursor() {
+        let log = EventLog::new(10);
+        let mut rx = log.subscribe();
+        for i in 0..3 {
+            log.send(registered(&format!("device-{}", i)));
+        }
+        assert_eq!(rx.try_recv().unwrap().seq, 1);
+
+        let page = log.since(1, 1);
+        assert_eq!(page.events.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![2]);
+        assert_eq!((page.next, page.latest, page.gap), (2, 3, false));
+
+        let page = log.since(3, 10);
+        assert!(page.events.is_empty());
+        assert_eq!(page.next, 3);
+
+        let json = serde_json::to_value(&log.since(0, 1).events[0]).unwrap();
+        assert_eq!((json["seq"].as_u64(), json["type"].as_str()), (Some(1), Some("device_registered")));
+    }
+
+    #[test]
+    fn test_overwritten_events_report_gap() {
+        let log = EventLog::new(2);
+        for i in 0..5 {
+            log.send(registered(&format!("device-{}", i)));
+        }
+        let page = log.since(1, 10);
+        assert!(page.gap);
+        assert_eq!(page.events.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![4, 5]);
+        assert!(!log.since(3, 10).gap);
+    }
+}
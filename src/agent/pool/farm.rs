@@ -0,0 +1,251 @@
+//! 容器化设备农场
+//!
+//! 管理通过 `adb connect` 接入的设备：redroid 等 Android 容器、STF 远程调试地址等（`host:port`）。
+//! 容器没有物理屏幕，只有一块虚拟显示屏，分辨率由容器启动参数决定；需要时在接入后用 `wm size` / `wm density` 统一设置。
+//! 健康检查以 `sys.boot_completed` 与显示屏是否可用判断，容器重启后 adb 连接会断开，检查失败时自动重新 `adb connect`。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// 设备农场配置（`[pool.farm]` 段）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FarmConfig {
+    /// 设备地址（`host:port`），如 redroid 容器的 `redroid-1:5555`
+    pub devices: Vec<String>,
+    /// 单次 adb 命令超时（秒）
+    pub command_timeout_secs: u64,
+    /// 健康检查间隔（秒，0 表示不检查）
+    pub health_check_interval_secs: u64,
+    /// 连续检查失败多少次后将设备标记为离线
+    pub max_failures: u32,
+    /// 接入后设置的虚拟显示屏分辨率（`[宽, 高]`），未配置时保持容器的设置
+    pub display_size: Option<(u32, u32)>,
+    /// 接入后设置的屏幕密度
+    pub display_density: Option<u32>,
+}
+
+impl Default for FarmConfig {
+    fn default() -> Self {
+        Self {
+            devices: Vec::new(),
+            command_timeout_secs: 10,
+            health_check_interval_secs: 30,
+            max_failures: 3,
+            display_size: None,
+            display_density: None,
+        }
+    }
+}
+
+/// 农场设备健康状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FarmDeviceHealth {
+    pub address: String,
+    /// 当前是否可用
+    pub healthy: bool,
+    /// 连续检查失败次数
+    pub consecutive_failures: u32,
+    /// 虚拟显示屏分辨率
+    pub display_size: Option<(u32, u32)>,
+    pub last_error: Option<String>,
+}
+
+/// 一次健康检查后的状态变化
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthChange {
+    /// 连续失败达到上限，变为不可用
+    Unhealthy(String),
+    /// 从不可用恢复
+    Recovered,
+}
+
+/// 农场设备的接入与健康检查，设备注册与状态由设备池负责
+pub struct DeviceFarm {
+    config: FarmConfig,
+    health: Mutex<HashMap<String, FarmDeviceHealth>>,
+}
+
+impl DeviceFarm {
+    pub fn new(config: FarmConfig) -> Self {
+        let health = config
+            .devices
+            .iter()
+            .map(|address| {
+                (
+                    address.clone(),
+                    FarmDeviceHealth {
+                        address: address.clone(),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect();
+        Self {
+            config,
+            health: Mutex::new(health),
+        }
+    }
+
+    pub fn config(&self) -> &FarmConfig {
+        &self.config
+    }
+
+    /// 配置中的设备地址
+    pub fn addresses(&self) -> &[String] {
+        &self.config.devices
+    }
+
+    /// 所有农场设备的健康状态
+    pub fn health(&self) -> Vec<FarmDeviceHealth> {
+        let mut health: Vec<FarmDeviceHealth> = self.health.lock().unwrap().values().cloned().collect();
+        health.sort_by(|a, b| a.address.cmp(&b.address));
+        health
+    }
+
+    /// `adb connect` 接入设备，并按配置设置虚拟显示屏
+    pub async fn connect(&self, address: &str) -> Result<(), String> {
+        let output = self.adb(crate::platform::adb_command().args(["connect", address])).await?;
+        if !is_connected(&output) {
+            return Err(format!("adb connect {} 失败: {}", address, output.trim()));
+        }
+        self.configure_display(address).await;
+        Ok(())
+    }
+
+    /// 设置容器的虚拟显示屏分辨率与密度
+    async fn configure_display(&self, address: &str) {
+        if let Some((width, height)) = self.config.display_size {
+            let size = format!("{}x{}", width, height);
+            if let Err(e) = self.adb(crate::platform::adb_device_command(address).args(["shell", "wm", "size", &size])).await {
+                warn!("设置设备 {} 分辨率失败: {}", address, e);
+            }
+        }
+        if let Some(density) = self.config.display_density
+            && let Err(e) = self.adb(crate::platform::adb_device_command(address).args(["shell", "wm", "density", &density.to_string()])).await
+        {
+            warn!("设置设备 {} 屏幕密度失败: {}", address, e);
+        }
+    }
+
+    /// 检查设备：系统已启动且虚拟显示屏可用，返回显示屏分辨率
+    async fn probe(&self, address: &str) -> Result<(u32, u32), String> {
+        let booted = self.adb(crate::platform::adb_device_command(address).args(["shell", "getprop", "sys.boot_completed"])).await?;
+        if booted.trim() != "1" {
+            return Err("系统尚未启动完成".to_string());
+        }
+        let size = self.adb(crate::platform::adb_device_command(address).args(["shell", "wm", "size"])).await?;
+        parse_display_size(&size).ok_or_else(|| format!("虚拟显示屏不可用: {}", size.trim()))
+    }
+
+    /// 检查一台设备，失败时重新 `adb connect`，返回状态变化
+    pub async fn check(&self, address: &str) -> Option<HealthChange> {
+        let result = match self.probe(address).await {
+            Ok(size) => Ok(size),
+            Err(e) => {
+                // 容器重启后旧连接失效，重新接入后再检查一次
+                let _ = self.adb(crate::platform::adb_command().args(["disconnect", address])).await;
+                match self.connect(address).await {
+                    Ok(()) => self.probe(address).await,
+                    Err(_) => Err(e),
+                }
+            }
+        };
+
+        let mut health = self.health.lock().unwrap();
+        let entry = health.entry(address.to_string()).or_insert_with(|| FarmDeviceHealth {
+            address: address.to_string(),
+            ..Default::default()
+        });
+        record_check(entry, result, self.config.max_failures)
+    }
+
+    /// 执行 adb 命令，返回标准输出
+    async fn adb(&self, command: &mut tokio::process::Command) -> Result<String, String> {
+        let timeout = Duration::from_secs(self.config.command_timeout_secs);
+        let output = tokio::time::timeout(timeout, command.output())
+            .await
+            .map_err(|_| "adb 命令超时".to_string())?
+            .map_err(|e| format!("执行 adb 失败: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// 记录检查结果，返回状态变化
+fn record_check(entry: &mut FarmDeviceHealth, result: Result<(u32, u32), String>, max_failures: u32) -> Option<HealthChange> {
+    match result {
+        Ok(size) => {
+            let recovered = !entry.healthy && entry.consecutive_failures >= max_failures;
+            entry.healthy = true;
+            entry.consecutive_failures = 0;
+            entry.display_size = Some(size);
+            entry.last_error = None;
+            if recovered {
+                info!("农场设备 {} 已恢复", entry.address);
+            }
+            recovered.then_some(HealthChange::Recovered)
+        }
+        Err(e) => {
+            entry.consecutive_failures += 1;
+            entry.last_error = Some(e.clone());
+            if entry.consecutive_failures == max_failures {
+                entry.healthy = false;
+                warn!("农场设备 {} 连续 {} 次检查失败: {}", entry.address, max_failures, e);
+                return Some(HealthChange::Unhealthy(e));
+            }
+            None
+        }
+    }
+}
+
+/// `adb connect` 是否成功
+fn is_connected(output: &str) -> bool {
+    output.contains("connected to") && !output.contains("failed") && !output.contains("unable")
+}
+
+/// 解析 `wm size` 输出，有 Override size 时以其为准
+fn parse_display_size(output: &str) -> Option<(u32, u32)> {
+    let parse = |line: &str| {
+        let (width, height) = line.split(':').nth(1)?.trim().split_once('x')?;
+        Some((width.parse().ok()?, height.parse().ok()?))
+    };
+    let size = output
+        .lines()
+        .find(|line| line.starts_with("Override size"))
+        .or_else(|| output.lines().find(|line| line.starts_with("Physical size")))
+        .and_then(parse)?;
+    (size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_farm_health_tracking() {
+        assert!(is_connected("connected to redroid-1:5555\n"));
+        assert!(is_connected("already connected to 10.0.0.8:5555\n"));
+        assert!(!is_connected("failed to connect to '10.0.0.8:5555': Connection refused\n"));
+
+        assert_eq!(parse_display_size("Physical size: 720x1280\n"), Some((720, 1280)));
+        assert_eq!(parse_display_size("Physical size: 720x1280\nOverride size: 1080x1920\n"), Some((1080, 1920)));
+        assert_eq!(parse_display_size("Physical size: 0x0\n"), None);
+
+        let mut entry = FarmDeviceHealth {
+            address: "redroid-1:5555".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(record_check(&mut entry, Err("offline".to_string()), 2), None);
+        assert_eq!(record_check(&mut entry, Err("offline".to_string()), 2), Some(HealthChange::Unhealthy("offline".to_string())));
+        assert_eq!(record_check(&mut entry, Err("offline".to_string()), 2), None);
+        assert_eq!(record_check(&mut entry, Ok((720, 1280)), 2), Some(HealthChange::Recovered));
+        assert!(entry.healthy && entry.consecutive_failures == 0);
+    }
+}
@@ -0,0 +1,165 @@
+//! 任务提交去重
+//!
+//! 客户端可以在 `agent/start` 中带上自己生成的任务 ID（幂等键）。客户端超时重试、网络抖动导致的重复提交
+//! 不会再启动第二个任务，而是返回已提交任务的状态。记录在 [`SUBMISSION_TTL`] 后过期。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::info;
+
+/// 提交记录保留时长
+pub const SUBMISSION_TTL: Duration = Duration::from_secs(24 * 3600);
+
+/// 已提交的任务
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Submission {
+    /// 客户端提供的任务 ID
+    pub task_id: String,
+    pub device_serial: String,
+    pub task: String,
+    /// 执行任务的 Agent ID，任务仍在排队或启动中时为 None
+    pub agent_id: Option<String>,
+    /// Agent 为本次执行生成的任务 ID（进度事件与 Agent 日志中的 `task_id`），任务仍在排队或启动中时为 None
+    pub agent_task_id: Option<String>,
+    #[serde(skip)]
+    submitted_at: Instant,
+}
+
+/// 申请任务 ID 的结果
+#[derive(Debug)]
+pub enum SubmissionClaim {
+    /// 首次提交，启动成功后调用 [`SubmissionTicket::started`]
+    New(SubmissionTicket),
+    /// 重复提交同一任务
+    Duplicate(Submission),
+    /// 任务 ID 已被其它设备或任务使用
+    Conflict(Submission),
+}
+
+/// 首次提交的凭据，未调用 [`SubmissionTicket::started`] 就被丢弃时（启动失败）释放任务 ID，允许客户端重试
+#[derive(Debug)]
+pub struct SubmissionTicket {
+    registry: Arc<SubmissionRegistry>,
+    task_id: String,
+    started: bool,
+}
+
+impl SubmissionTicket {
+    /// 记录任务已启动
+    pub fn started(mut self, agent_id: &str, agent_task_id: Option<String>) {
+        if let Some(submission) = self.registry.records.lock().unwrap().get_mut(&self.task_id) {
+            submission.agent_id = Some(agent_id.to_string());
+            submission.agent_task_id = agent_task_id;
+        }
+        self.started = true;
+    }
+}
+
+impl Drop for SubmissionTicket {
+    fn drop(&mut self) {
+        if !self.started {
+            self.registry.records.lock().unwrap().remove(&self.task_id);
+        }
+    }
+}
+
+/// 任务提交记录
+#[derive(Debug)]
+pub struct SubmissionRegistry {
+    ttl: Duration,
+    records: Mutex<HashMap<String, Submission>>,
+}
+
+impl Default for SubmissionRegistry {
+    fn default() -> Self {
+        Self::new(SUBMISSION_TTL)
+    }
+}
+
+impl SubmissionRegistry {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            records: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 申请任务 ID：未提交过时占用该 ID，否则返回已有的提交
+    pub fn claim(self: &Arc<Self>, task_id: &str, device_serial: &str, task: &str) -> SubmissionClaim {
+        let mut records = self.records.lock().unwrap();
+        records.retain(|_, submission| submission.submitted_at.elapsed() < self.ttl);
+
+        if let Some(existing) = records.get(task_id) {
+            info!("重复提交任务 {}（设备: {}）", task_id, existing.device_serial);
+            return if existing.device_serial == device_serial && existing.task == task {
+                SubmissionClaim::Duplicate(existing.clone())
+            } else {
+                SubmissionClaim::Conflict(existing.clone())
+            };
+        }
+
+        records.insert(
+            task_id.to_string(),
+            Submission {
+                task_id: task_id.to_string(),
+                device_serial: device_serial.to_string(),
+                task: task.to_string(),
+                agent_id: None,
+                agent_task_id: None,
+                submitted_at: Instant::now(),
+            },
+        );
+        SubmissionClaim::New(SubmissionTicket {
+            registry: Arc::clone(self),
+            task_id: task_id.to_string(),
+            started: false,
+        })
+    }
+
+    /// 查询已提交的任务
+    pub fn get(&self, task_id: &str) -> Option<Submission> {
+        self.records
+            .lock()
+            .unwrap()
+            .get(task_id)
+            .filter(|submission| submission.submitted_at.elapsed() < self.ttl)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_submission() {
+        let registry = Arc::new(SubmissionRegistry::default());
+
+        let SubmissionClaim::New(ticket) = registry.claim("job-1", "emulator-5554", "打开设置") else {
+            panic!("首次提交应当成功");
+        };
+        // 启动完成前的重复提交也不会再启动任务
+        assert!(matches!(registry.claim("job-1", "emulator-5554", "打开设置"), SubmissionClaim::Duplicate(s) if s.agent_id.is_none()));
+        ticket.started("agent-1", Some("agent-1_1767261600".to_string()));
+
+        match registry.claim("job-1", "emulator-5554", "打开设置") {
+            SubmissionClaim::Duplicate(submission) => {
+                assert_eq!(submission.agent_id.as_deref(), Some("agent-1"));
+                assert_eq!(submission.agent_task_id.as_deref(), Some("agent-1_1767261600"));
+            }
+            other => panic!("应当识别为重复提交: {:?}", other),
+        }
+        assert!(matches!(registry.claim("job-1", "emulator-5556", "打开设置"), SubmissionClaim::Conflict(_)));
+
+        // 启动失败时释放任务 ID，允许重试
+        let SubmissionClaim::New(ticket) = registry.claim("job-2", "emulator-5554", "打开相机") else {
+            panic!("首次提交应当成功");
+        };
+        drop(ticket);
+        assert!(registry.get("job-2").is_none());
+        assert!(matches!(registry.claim("job-2", "emulator-5554", "打开相机"), SubmissionClaim::New(_)));
+    }
+}
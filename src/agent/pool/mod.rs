@@ -2,15 +2,37 @@
 //!
 //! 提供统一的设备管理、连接池化、Agent 按需创建等功能
 
+mod boot;
+mod capacity;
+mod drain;
+mod scheduler;
+mod idempotency;
 mod device_pool;
 mod device_entry;
 mod types;
+mod config_watcher;
+mod emulator;
+mod farm;
+mod event_log;
 
+pub use boot::{check_boot, wait_for_boot, BootState};
+pub use capacity::{AgentPermit, CapacitySnapshot, CAPACITY_EXCEEDED_STATUS, is_capacity_error};
+pub use drain::{DrainController, DrainPhase, DrainStatus, DRAINING_STATUS, is_draining_error};
 pub use device_pool::DevicePool;
+pub use scheduler::{GatePermit, PriorityGate};
+pub use idempotency::{Submission, SubmissionClaim, SubmissionRegistry, SubmissionTicket, SUBMISSION_TTL};
 pub use device_entry::DeviceEntry;
+pub use config_watcher::spawn_config_watcher;
+pub use emulator::{EmulatorBackend, EmulatorConfig, EmulatorInstance, EmulatorManager, EmulatorState};
+pub use farm::{DeviceFarm, FarmConfig, FarmDeviceHealth};
+pub use event_log::{EventLog, EventPage, SequencedEvent, DEFAULT_EVENT_LOG_CAPACITY};
 pub use types::{
     DeviceStatus,
     DevicePoolConfig,
     DevicePoolEvent,
     DevicePoolError,
+    TaskOverrides,
+    TaskPriority,
+    MAX_TASK_EXECUTION_SECS,
+    MAX_TASK_STEPS,
 };
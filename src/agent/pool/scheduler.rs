@@ -0,0 +1,234 @@
+//! 按优先级排队的执行名额
+//!
+//! 名额用尽时申请者进入等待队列，名额释放后直接交给优先级最高、提交最早的等待者，
+//! 而不是先到先得。设备池的 Agent 名额与每台设备的执行名额（容量为 1）都通过它排队。
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+use super::types::TaskPriority;
+
+#[derive(Debug)]
+struct Waiter {
+    priority: TaskPriority,
+    seq: u64,
+    tx: oneshot::Sender<()>,
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+    held: usize,
+    next_seq: u64,
+    waiters: Vec<Waiter>,
+}
+
+impl GateState {
+    /// 优先级最高、提交最早的等待者
+    fn next_waiter(&self) -> Option<usize> {
+        self.waiters
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.priority.cmp(&b.priority).then(b.seq.cmp(&a.seq)))
+            .map(|(idx, _)| idx)
+    }
+
+    /// 释放一个名额：有等待者时直接转交，否则归还
+    fn release(&mut self) {
+        while let Some(idx) = self.next_waiter() {
+            if self.waiters.remove(idx).tx.send(()).is_ok() {
+                return;
+            }
+        }
+        self.held = self.held.saturating_sub(1);
+    }
+}
+
+/// 按优先级排队的名额
+#[derive(Debug)]
+pub struct PriorityGate {
+    /// 名额数（0 表示不限制）
+    capacity: usize,
+    state: Arc<Mutex<GateState>>,
+}
+
+/// 已取得的名额，drop 时释放
+#[derive(Debug)]
+pub struct GatePermit {
+    state: Arc<Mutex<GateState>>,
+}
+
+impl Drop for GatePermit {
+    fn drop(&mut self) {
+        self.state.lock().unwrap().release();
+    }
+}
+
+/// 排队中的申请；被取消时退出队列，已转交的名额归还
+struct PendingAcquire {
+    state: Arc<Mutex<GateState>>,
+    seq: u64,
+    rx: oneshot::Receiver<()>,
+    done: bool,
+}
+
+impl PendingAcquire {
+    /// 结束排队，返回是否已经取得名额
+    fn finish(&mut self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        self.done = true;
+        if let Some(idx) = state.waiters.iter().position(|w| w.seq == self.seq) {
+            state.waiters.remove(idx);
+            return false;
+        }
+        self.rx.try_recv().is_ok()
+    }
+}
+
+impl Drop for PendingAcquire {
+    fn drop(&mut self) {
+        if !self.done && self.finish() {
+            self.state.lock().unwrap().release();
+        }
+    }
+}
+
+impl PriorityGate {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Arc::new(Mutex::new(GateState::default())),
+        }
+    }
+
+    fn permit(&self) -> GatePermit {
+        GatePermit { state: Arc::clone(&self.state) }
+    }
+
+    /// 在已持有锁时占用空闲名额；有人排队时不插队
+    fn take_free(&self, state: &mut GateState) -> bool {
+        if self.capacity > 0 && (state.held >= self.capacity || !state.waiters.is_empty()) {
+            return false;
+        }
+        state.held += 1;
+        true
+    }
+
+    /// 有空闲名额时立即取得
+    pub fn try_acquire(&self) -> Option<GatePermit> {
+        let mut state = self.state.lock().unwrap();
+        self.take_free(&mut state).then(|| self.permit())
+    }
+
+    /// 取得名额，没有空闲时按优先级排队最多 `wait`，超时返回 None
+    pub async fn acquire(&self, priority: TaskPriority, wait: Duration) -> Option<GatePermit> {
+        // 检查名额与进入队列在同一次加锁内完成，避免两者之间释放的名额无人领取
+        let (tx, rx) = oneshot::channel();
+        let seq = {
+            let mut state = self.state.lock().unwrap();
+            if self.take_free(&mut state) {
+                return Some(self.permit());
+            }
+            if wait.is_zero() {
+                return None;
+            }
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiters.push(Waiter { priority, seq, tx });
+            seq
+        };
+        let mut pending = PendingAcquire {
+            state: Arc::clone(&self.state),
+            seq,
+            rx,
+            done: false,
+        };
+
+        let granted = match tokio::time::timeout(wait, &mut pending.rx).await {
+            Ok(Ok(())) => {
+                pending.done = true;
+                true
+            }
+            _ => pending.finish(),
+        };
+        granted.then(|| self.permit())
+    }
+
+    /// 是否已没有空闲名额
+    pub fn is_full(&self) -> bool {
+        self.capacity > 0 && self.held() >= self.capacity
+    }
+
+    /// 已被占用的名额数
+    pub fn held(&self) -> usize {
+        self.state.lock().unwrap().held
+    }
+
+    /// 正在排队的申请数
+    pub fn waiting(&self) -> usize {
+        self.state.lock().unwrap().waiters.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_waiters_served_by_priority() {
+        let gate = Arc::new(PriorityGate::new(1));
+        let holder = gate.try_acquire().unwrap();
+        assert!(gate.try_acquire().is_none());
+
+        let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+        for (name, priority) in [("low", TaskPriority::Low), ("normal", TaskPriority::Normal), ("high", TaskPriority::High)] {
+            let gate = Arc::clone(&gate);
+            let order_tx = order_tx.clone();
+            tokio::spawn(async move {
+                let _permit = gate.acquire(priority, Duration::from_secs(5)).await.unwrap();
+                order_tx.send(name).unwrap();
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            });
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(gate.waiting(), 3);
+
+        drop(holder);
+        let mut order = Vec::new();
+        for _ in 0..3 {
+            order.push(order_rx.recv().await.unwrap());
+        }
+        assert_eq!(order, ["high", "normal", "low"]);
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!((gate.held(), gate.waiting()), (0, 0));
+        assert!(gate.acquire(TaskPriority::Low, Duration::ZERO).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_release_while_queueing_is_not_lost() {
+        let gate = Arc::new(PriorityGate::new(1));
+        let holder = gate.try_acquire().unwrap();
+        let waiter = {
+            let gate = Arc::clone(&gate);
+            tokio::spawn(async move { gate.acquire(TaskPriority::Normal, Duration::from_secs(5)).await.is_some() })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        // 名额转交给排队者，后来的申请不能插队
+        drop(holder);
+        assert!(gate.try_acquire().is_none());
+        assert!(waiter.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_leaves_queue() {
+        let gate = PriorityGate::new(1);
+        let holder = gate.try_acquire().unwrap();
+        assert!(gate.acquire(TaskPriority::High, Duration::from_millis(20)).await.is_none());
+        assert_eq!(gate.waiting(), 0);
+
+        drop(holder);
+        assert_eq!(gate.held(), 0);
+    }
+}
@@ -1,5 +1,9 @@
 //! 设备池相关的类型定义
 
+use crate::agent::llm::ModelConfig;
+use crate::scrcpy::options::ScrcpyOptions;
+use super::emulator::EmulatorConfig;
+use super::farm::FarmConfig;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -22,6 +26,21 @@ pub enum DeviceStatus {
     Error(String),
 }
 
+impl DeviceStatus {
+    /// 状态代码（REST 接口中的 `status` 字段）
+    pub fn code(&self) -> &'static str {
+        match self {
+            DeviceStatus::Registered => "registered",
+            DeviceStatus::Connecting => "connecting",
+            DeviceStatus::Connected => "connected",
+            DeviceStatus::Busy => "busy",
+            DeviceStatus::Disconnected => "disconnected",
+            DeviceStatus::Offline => "offline",
+            DeviceStatus::Error(_) => "error",
+        }
+    }
+}
+
 impl fmt::Display for DeviceStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -38,6 +57,7 @@ impl fmt::Display for DeviceStatus {
 
 /// 设备池配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DevicePoolConfig {
     /// 最大并发连接数
     pub max_connections: usize,
@@ -53,6 +73,34 @@ pub struct DevicePoolConfig {
 
     /// 健康检查间隔（秒）
     pub health_check_interval: u64,
+
+    /// 建立连接前等待设备启动完成（`sys.boot_completed` 与包管理服务）的最长时间（秒），0 表示不检查
+    pub boot_wait_secs: u64,
+
+    /// scrcpy 会话选项（视频编码偏好等）
+    #[serde(default)]
+    pub scrcpy_options: ScrcpyOptions,
+
+    /// 最多同时执行任务的 Agent 数（0 表示不限制）
+    pub max_busy_agents: usize,
+
+    /// 最多同时建立的 scrcpy 会话数（0 表示不限制）
+    pub max_streams: usize,
+
+    /// Agent 名额已满或设备正忙时任务排队等待的最长时间（秒），0 表示直接拒绝
+    pub admission_wait_secs: u64,
+
+    /// 名额已满时是否允许高优先级任务抢占：暂停一个低优先级任务（在步骤边界），结束后再恢复
+    pub preemption: bool,
+
+    /// 按需启动的模拟器（`[pool.emulator]` 段）
+    pub emulator: EmulatorConfig,
+
+    /// 通过 `adb connect` 接入的容器化设备（`[pool.farm]` 段）
+    pub farm: FarmConfig,
+
+    /// 事件日志保留的最近事件数，供客户端按序号补齐错过的事件
+    pub event_log_capacity: usize,
 }
 
 impl Default for DevicePoolConfig {
@@ -63,22 +111,184 @@ impl Default for DevicePoolConfig {
             idle_cleanup_threshold: 300, // 5 分钟
             auto_reconnect: true,
             health_check_interval: 60,
+            boot_wait_secs: 120,
+            scrcpy_options: ScrcpyOptions::default(),
+            max_busy_agents: 0,
+            max_streams: 0,
+            admission_wait_secs: 0,
+            preemption: false,
+            emulator: EmulatorConfig::default(),
+            farm: FarmConfig::default(),
+            event_log_capacity: super::event_log::DEFAULT_EVENT_LOG_CAPACITY,
+        }
+    }
+}
+
+impl DevicePoolConfig {
+    /// 读取配置文件中的 `[pool]` 段，没有该段时返回 None
+    pub fn from_config_file(path: &std::path::Path) -> Result<Option<Self>, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("读取配置文件 {:?} 失败: {}", path, e))?;
+        let value: toml::Table = toml::from_str(&content).map_err(|e| format!("解析配置文件 {:?} 失败: {}", path, e))?;
+        value
+            .get("pool")
+            .map(|pool| pool.clone().try_into().map_err(|e| format!("解析 [pool] 段失败: {}", e)))
+            .transpose()
+    }
+}
+
+/// 任务优先级，排队时高优先级先执行，同优先级按提交顺序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl fmt::Display for TaskPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskPriority::Low => write!(f, "low"),
+            TaskPriority::Normal => write!(f, "normal"),
+            TaskPriority::High => write!(f, "high"),
         }
     }
 }
 
+pub use crate::agent::core::state::{MAX_TASK_EXECUTION_SECS, MAX_TASK_STEPS};
+
+/// 单个任务的模型与参数覆盖（随 agent/start 请求提交，只影响该任务）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskOverrides {
+    /// 模型名称，须为已配置的模型或在 `allowed_task_models` 中
+    #[serde(default)]
+    pub model_name: Option<String>,
+    /// 采样温度（0 ~ 2）
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// 最大步数（1 ~ [`MAX_TASK_STEPS`]）
+    #[serde(default)]
+    pub max_steps: Option<usize>,
+    /// 最长执行时间（秒，1 ~ [`MAX_TASK_EXECUTION_SECS`]）
+    #[serde(default)]
+    pub max_execution_time: Option<u64>,
+    /// 是否启用三阶段模式
+    #[serde(default)]
+    pub three_stage: Option<bool>,
+}
+
+impl TaskOverrides {
+    /// 是否没有任何覆盖项
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 是否需要使用与设备配置不同的模型客户端
+    pub fn overrides_model(&self) -> bool {
+        self.model_name.is_some() || self.temperature.is_some() || self.three_stage.is_some()
+    }
+
+    /// 校验覆盖项并应用到模型配置上
+    pub fn apply(&self, base: &ModelConfig) -> Result<ModelConfig, String> {
+        if let Some(max_steps) = self.max_steps
+            && !(1..=MAX_TASK_STEPS).contains(&max_steps)
+        {
+            return Err(format!("max_steps 必须在 1 ~ {} 之间: {}", MAX_TASK_STEPS, max_steps));
+        }
+        if let Some(max_execution_time) = self.max_execution_time
+            && !(1..=MAX_TASK_EXECUTION_SECS).contains(&max_execution_time)
+        {
+            return Err(format!(
+                "max_execution_time 必须在 1 ~ {} 秒之间: {}",
+                MAX_TASK_EXECUTION_SECS, max_execution_time
+            ));
+        }
+
+        let mut config = base.clone();
+        if let Some(model_name) = &self.model_name {
+            let allowed = [
+                Some(&base.model_name),
+                base.planning_model_name.as_ref(),
+                base.execution_model_name.as_ref(),
+                base.auxiliary_model_name.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .chain(&base.allowed_task_models)
+            .any(|name| name == model_name);
+            if !allowed {
+                return Err(format!("不允许使用模型: {}", model_name));
+            }
+            config.model_name = model_name.clone();
+            if config.execution_model_name.is_some() {
+                config.execution_model_name = Some(model_name.clone());
+            }
+        }
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(format!("temperature 必须在 0 ~ 2 之间: {}", temperature));
+            }
+            config.temperature = temperature;
+        }
+        if let Some(three_stage) = self.three_stage {
+            config.enable_three_stage = three_stage;
+        }
+        Ok(config)
+    }
+}
+
 /// 设备池事件
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum DevicePoolEvent {
     /// 设备注册
     DeviceRegistered { serial: String },
 
+    /// 模型/Agent 配置已更新（之后创建的 Agent 使用新配置）
+    ConfigUpdated { version: u64 },
+
+    /// 模型端点切换（`fallback_active` 为 true 表示切换到备用端点）
+    ModelFailover { model: String, fallback_active: bool, reason: String },
+
+    /// 模拟器启动完成并已注册
+    EmulatorBooted { serial: String },
+
+    /// 模拟器已销毁
+    EmulatorDestroyed { serial: String },
+
+    /// 农场设备连续健康检查失败，已标记为离线
+    FarmDeviceUnhealthy { serial: String, error: String },
+
+    /// 农场设备已恢复
+    FarmDeviceRecovered { serial: String },
+
+    /// 设备池开始排空（不再接受新任务），`grace_secs` 后停止仍在执行的任务
+    PoolDraining { grace_secs: u64 },
+
+    /// 设备池排空完成：任务已全部结束、scrcpy 会话已断开
+    PoolQuiesced { cancelled_tasks: usize },
+
+    /// 设备池恢复接受任务
+    PoolResumed,
+
+    /// adb server 已重启（`restarts` 为累计重启次数），空闲设备随后重新连接
+    AdbServerRestarted { restarts: u64 },
+
     /// 设备连接
     DeviceConnected { serial: String },
 
     /// 设备断开
     DeviceDisconnected { serial: String },
 
+    /// 投屏会话状态变化（`reason` 为进入 Degraded 等状态的原因）
+    StreamStateChanged {
+        serial: String,
+        state: crate::scrcpy::session_state::SessionState,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+
     /// Agent 创建
     AgentCreated { serial: String, agent_id: String },
 
@@ -133,6 +343,61 @@ pub struct DeviceInfo {
     pub name: Option<String>,
     pub status: DeviceStatus,
     pub has_agent: bool,
+    pub scrcpy_server_port: Option<u16>,
     pub last_used: i64, // timestamp
     pub idle_seconds: i64,
+    /// 设备级模型配置覆盖
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_override: Option<serde_json::Value>,
+    /// 设备元数据（型号、Android 版本、ABI、屏幕密度）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<crate::scrcpy::device_info::DeviceMetadata>,
+    /// 设备能力（`input swipe` 持续时间、motionevent、UHID、`wm size`、screenrecord）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<crate::agent::executor::DeviceCapabilities>,
+    /// 投屏会话状态（设备已连接时返回）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<crate::scrcpy::session_state::SessionStateSnapshot>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_overrides_apply() {
+        let base = ModelConfig {
+            planning_model_name: Some("glm-4.7".to_string()),
+            allowed_task_models: vec!["gpt-4o-mini".to_string()],
+            ..Default::default()
+        };
+
+        let overrides = TaskOverrides {
+            model_name: Some("gpt-4o-mini".to_string()),
+            temperature: Some(0.7),
+            three_stage: Some(true),
+            ..Default::default()
+        };
+        let config = overrides.apply(&base).unwrap();
+        assert_eq!(config.model_name, "gpt-4o-mini");
+        assert_eq!(config.temperature, 0.7);
+        assert!(config.enable_three_stage);
+
+        // 已配置的规划模型允许直接使用
+        assert!(TaskOverrides { model_name: Some("glm-4.7".to_string()), ..Default::default() }.apply(&base).is_ok());
+        assert!(TaskOverrides { model_name: Some("gpt-5".to_string()), ..Default::default() }.apply(&base).is_err());
+        assert!(TaskOverrides { temperature: Some(3.0), ..Default::default() }.apply(&base).is_err());
+        assert!(TaskOverrides { max_steps: Some(0), ..Default::default() }.apply(&base).is_err());
+        assert!(TaskOverrides { max_execution_time: Some(0), ..Default::default() }.apply(&base).is_err());
+        assert!(TaskOverrides { max_execution_time: Some(600), ..Default::default() }.apply(&base).is_ok());
+
+        let parsed: TaskOverrides = serde_json::from_value(serde_json::json!({
+            "device_serial": "emulator-5554",
+            "task": "打开设置",
+            "max_steps": 20,
+            "model_name": null
+        })).unwrap();
+        assert_eq!(parsed, TaskOverrides { max_steps: Some(20), ..Default::default() });
+        assert!(!parsed.overrides_model());
+    }
 }
@@ -9,9 +9,23 @@ use socketioxide::{
 };
 use std::sync::Arc;
 use tracing::{info, error, debug};
-use crate::agent::pool::DevicePool;
+use crate::agent::pool::{CAPACITY_EXCEEDED_STATUS, DRAINING_STATUS, DevicePool, SubmissionClaim, is_capacity_error, is_draining_error};
 use crate::agent::core::traits::Agent;
+use crate::agent::core::callback::TaskCallback;
+use crate::agent::core::history::HistoryQuery;
+use crate::agent::core::agent_group::{AgentGroup, AgentGroupConfig, AgentGroupEvent};
+use crate::agent::core::collaboration::CollaborationPlan;
+use crate::agent::core::progress::{self, TaskProgress, TaskProgressEvent};
+use crate::agent::core::task_record::{self, TaskRecord, TaskRecordState};
+use crate::agent::core::agent::AGENT_LOG_DIR;
+use crate::agent::llm::thinking::subscribe_thinking_events;
 use axum::Router;
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use tokio::task::AbortHandle;
+
+/// 连接上的进度订阅（任务 ID 或 Agent ID -> 转发任务）
+type ProgressAttachments = Arc<std::sync::Mutex<HashMap<String, AbortHandle>>>;
 
 /// Agent Socket.IO 服务器
 ///
@@ -19,20 +33,27 @@ use axum::Router;
 pub struct AgentSocketServer {
     io: Arc<SocketIo>,
     layer: SocketIoLayer,
-    port: u16,
+    addr: String,
 }
 
 impl AgentSocketServer {
     /// 创建新的 Agent Socket.IO 服务器
     ///
     /// # 参数
-    /// - `port`: Socket.IO 服务端口（建议 4000）
+    /// - `addr`: Socket.IO 服务监听地址（如 `0.0.0.0:4000`）
     /// - `device_pool`: 设备池实例
-    pub fn new(port: u16, device_pool: Arc<DevicePool>) -> Self {
-        let (layer, io) = SocketIo::new_layer();
+    pub fn new(addr: impl Into<String>, device_pool: Arc<DevicePool>) -> Self {
+        Self::new_with_path(addr, "/socket.io", device_pool)
+    }
+
+    /// 创建使用指定 Socket.IO 请求路径的服务器（如反向代理下的 `/scrs/socket.io`）
+    pub fn new_with_path(addr: impl Into<String>, req_path: impl Into<String>, device_pool: Arc<DevicePool>) -> Self {
+        let addr = addr.into();
+        let req_path = req_path.into();
+        let (layer, io) = SocketIo::builder().req_path(req_path.clone()).build_layer();
         let io = Arc::new(io);
 
-        info!("创建 Agent Socket.IO 服务器，端口: {}", port);
+        info!("创建 Agent Socket.IO 服务器，地址: {}，路径: {}", addr, req_path);
 
         // 注册默认命名空间的 Agent 处理器
         let device_pool_clone = Arc::clone(&device_pool);
@@ -42,12 +63,12 @@ impl AgentSocketServer {
             register_agent_handlers_with_pool(socket, Arc::clone(&device_pool_clone)).await;
         });
 
-        Self { io, layer, port }
+        Self { io, layer, addr }
     }
 
     /// 启动服务器
     pub async fn run(self) {
-        let addr = format!("0.0.0.0:{}", self.port);
+        let addr = self.addr;
         info!("Agent Socket.IO 服务器启动于: {}", addr);
 
         // 创建 axum 应用，集成 Socket.IO layer
@@ -76,24 +97,100 @@ impl AgentSocketServer {
         &self.io
     }
 
-    /// 获取端口
-    pub fn port(&self) -> u16 {
-        self.port
+    /// 获取监听地址
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// 取出 Socket.IO layer，用于单端口模式下挂载到 API 服务的路由
+    pub fn into_layer(self) -> SocketIoLayer {
+        self.layer
     }
 }
 
 /// 直接使用 DevicePool 注册 Agent 处理器
 ///
 /// 这是 register_agent_handlers 的简化版本，不需要完整的 IContext
+/// 把任务进度事件以 `agent/progress` 转发给客户端，任务结束或连接断开后停止
+fn forward_progress(
+    socket: SocketRef,
+    mut events: broadcast::Receiver<TaskProgressEvent>,
+    filter: impl Fn(&TaskProgressEvent) -> bool + Send + 'static,
+) -> AbortHandle {
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    // 错过的事件可以用 agent/attach 按游标补发
+                    let _ = socket.emit("agent/progress/lagged", &serde_json::json!({ "skipped": skipped }));
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if !filter(&event) {
+                continue;
+            }
+            let finished = matches!(event.progress, TaskProgress::Finished { .. });
+            if socket.emit("agent/progress", &event).is_err() || finished {
+                break;
+            }
+        }
+    })
+    .abort_handle()
+}
+
+/// 记录连接上的进度订阅，替换同一任务的旧订阅
+fn attach_progress(attachments: &ProgressAttachments, key: String, handle: AbortHandle) {
+    let mut attachments = attachments.lock().unwrap();
+    attachments.retain(|_, handle| !handle.is_finished());
+    if let Some(previous) = attachments.insert(key, handle) {
+        previous.abort();
+    }
+}
+
+/// 内存中仍有进度的任务 ID：`id` 可以是进度事件中的任务 ID、`agent/start` 时提供的任务 ID 或 Agent ID
+fn resolve_live_task(pool: &DevicePool, id: &str) -> Option<String> {
+    if progress::progress_since(id, u64::MAX).is_some() {
+        return Some(id.to_string());
+    }
+    if let Some(task_id) = pool.submission(id).and_then(|submission| submission.agent_task_id) {
+        return progress::progress_since(&task_id, u64::MAX).map(|_| task_id);
+    }
+    progress::latest_task_of_agent(id)
+}
+
+/// 查询任务记录：内存中仍有进度时以进度为准并用 Agent 日志补全，否则从 Agent 日志还原；
+/// 返回记录以及进度是否仍在内存中
+async fn find_task(pool: &DevicePool, id: &str) -> Result<Option<(TaskRecord, bool)>, String> {
+    let live = resolve_live_task(pool, id);
+    let log_id = match &live {
+        Some(task_id) => task_id.clone(),
+        None => pool.submission(id).and_then(|submission| submission.agent_task_id).unwrap_or_else(|| id.to_string()),
+    };
+    let log = tokio::task::spawn_blocking(move || task_record::load_task_record(AGENT_LOG_DIR, &log_id))
+        .await
+        .map_err(|e| format!("读取 Agent 日志失败: {}", e))?
+        .map_err(|e| format!("读取 Agent 日志失败: {}", e))?;
+    match live.and_then(|task_id| progress::progress_since(&task_id, 0)) {
+        Some(replay) => Ok(Some((TaskRecord::from_progress(&replay).merge_log(log), true))),
+        None => Ok(log.map(|record| (record, false))),
+    }
+}
+
 async fn register_agent_handlers_with_pool(socket: SocketRef, device_pool: Arc<DevicePool>) {
     use socketioxide::extract::Data;
     use serde_json::json;
 
+    let attachments: ProgressAttachments = Arc::default();
+
     // agent/start
     {
         let pool = Arc::clone(&device_pool);
+        let attachments = Arc::clone(&attachments);
         socket.on("agent/start", move |s: SocketRef, data: Data<serde_json::Value>| {
             let pool = Arc::clone(&pool);
+            let attachments = Arc::clone(&attachments);
             async move {
                 debug!("收到 agent/start 请求: {:?}", data.0);
 
@@ -104,6 +201,68 @@ async fn register_agent_handlers_with_pool(socket: SocketRef, device_pool: Arc<D
                 let task = data.0.get("task")
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
+                let capture_traffic = data.0.get("capture_traffic")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let record_screen = data.0.get("record_screen")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let usage_expectation = data.0.get("expected_app")
+                    .and_then(|v| v.as_str())
+                    .map(|app| crate::agent::executor::UsageExpectation {
+                        app: app.to_string(),
+                        min_foreground_secs: data.0.get("min_foreground_secs").and_then(|v| v.as_u64()),
+                    });
+                // 任务的目标应用，离开时提醒模型或重新启动
+                let foreground_guard = match data.0.get("target_app").and_then(|v| v.as_str()) {
+                    Some(app) => {
+                        let relaunch = data.0.get("relaunch_target_app").and_then(|v| v.as_bool()).unwrap_or(false);
+                        match crate::agent::executor::ForegroundGuard::new(app, relaunch) {
+                            Ok(guard) => Some(guard),
+                            Err(e) => {
+                                let _ = s.emit("agent/start/response", &json!({
+                                    "success": false,
+                                    "error": e
+                                }));
+                                return;
+                            }
+                        }
+                    }
+                    None => None,
+                };
+                // humanize 可以是 true（使用默认选项）或选项对象
+                let humanize = match data.0.get("humanize") {
+                    Some(serde_json::Value::Bool(true)) => Some(crate::agent::executor::HumanizeOptions::default()),
+                    Some(value @ serde_json::Value::Object(_)) => serde_json::from_value(value.clone()).ok(),
+                    _ => None,
+                };
+
+                // 只作用于本任务的模型与参数覆盖
+                let overrides: crate::agent::pool::TaskOverrides = match serde_json::from_value(data.0.clone()) {
+                    Ok(overrides) => overrides,
+                    Err(e) => {
+                        let _ = s.emit("agent/start/response", &json!({
+                            "success": false,
+                            "error": format!("任务参数无效: {}", e)
+                        }));
+                        return;
+                    }
+                };
+
+                // 任务优先级（high/normal/low），排队与抢占时使用
+                let priority: crate::agent::pool::TaskPriority = match data.0.get("priority") {
+                    None => Default::default(),
+                    Some(value) => match serde_json::from_value(value.clone()) {
+                        Ok(priority) => priority,
+                        Err(e) => {
+                            let _ = s.emit("agent/start/response", &json!({
+                                "success": false,
+                                "error": format!("任务优先级无效: {}", e)
+                            }));
+                            return;
+                        }
+                    },
+                };
 
                 if device_serial.is_empty() || task.is_empty() {
                     let _ = s.emit("agent/start/response", &json!({
@@ -112,6 +271,58 @@ async fn register_agent_handlers_with_pool(socket: SocketRef, device_pool: Arc<D
                     }));
                     return;
                 }
+                if data.0.get("experiment_id").and_then(|v| v.as_str()).is_some() && overrides.overrides_model() {
+                    let _ = s.emit("agent/start/response", &json!({
+                        "success": false,
+                        "error": "实验任务不能同时指定模型参数"
+                    }));
+                    return;
+                }
+
+                let task_id = data.0.get("task_id").or_else(|| data.0.get("idempotency_key")).and_then(|v| v.as_str());
+
+                // 任务结束时的结果回调
+                let callback = match data.0.get("callback_url").and_then(|v| v.as_str()) {
+                    Some(url) => match TaskCallback::new(url, task_id.map(str::to_string)) {
+                        Ok(callback) => Some(callback),
+                        Err(e) => {
+                            let _ = s.emit("agent/start/response", &json!({
+                                "success": false,
+                                "error": e
+                            }));
+                            return;
+                        }
+                    },
+                    None => None,
+                };
+
+                // 客户端任务 ID：重复提交时返回已提交任务的状态，不再启动新任务
+                let mut submission = None;
+                if let Some(task_id) = task_id {
+                    match pool.claim_submission(task_id, device_serial, task) {
+                        SubmissionClaim::New(ticket) => submission = Some(ticket),
+                        SubmissionClaim::Duplicate(existing) => {
+                            let task_record = pool.submission_status(&existing).await;
+                            let _ = s.emit("agent/start/response", &json!({
+                                "success": true,
+                                "duplicate": true,
+                                "task_id": existing.task_id,
+                                "agent_id": existing.agent_id,
+                                "device_serial": existing.device_serial,
+                                "task": existing.task,
+                                "task_record": task_record
+                            }));
+                            return;
+                        }
+                        SubmissionClaim::Conflict(existing) => {
+                            let _ = s.emit("agent/start/response", &json!({
+                                "success": false,
+                                "error": format!("任务 ID {} 已用于设备 {} 上的其它任务", existing.task_id, existing.device_serial)
+                            }));
+                            return;
+                        }
+                    }
+                }
 
                 // 注册设备（如果尚未注册）
                 let _ = pool.register_device(device_serial.to_string(), None).await;
@@ -119,9 +330,52 @@ async fn register_agent_handlers_with_pool(socket: SocketRef, device_pool: Arc<D
                 // 获取或创建 Agent
                 match pool.get_agent(device_serial).await {
                     Ok(agent) => {
-                        // 启动任务
-                        match agent.start(task.to_string()).await {
+                        if !overrides.is_empty()
+                            && let Err(e) = pool.apply_task_overrides(&agent, &overrides).await
+                        {
+                            let _ = s.emit("agent/start/response", &json!({
+                                "success": false,
+                                "error": e.to_string()
+                            }));
+                            return;
+                        }
+                        agent.set_capture_traffic(capture_traffic).await;
+                        agent.set_record_screen(record_screen).await;
+                        agent.set_usage_expectation(usage_expectation).await;
+                        let humanized = humanize.is_some();
+                        agent.set_humanize(humanize).await;
+                        agent.set_foreground_guard(foreground_guard).await;
+                        agent.set_callback(callback).await;
+
+                        // 加入实验时分配变体
+                        let mut variant = None;
+                        if let Some(experiment_id) = data.0.get("experiment_id").and_then(|v| v.as_str()) {
+                            match pool.assign_experiment(&agent, experiment_id).await {
+                                Ok(name) => variant = Some(name),
+                                Err(e) => {
+                                    let _ = s.emit("agent/start/response", &json!({
+                                        "success": false,
+                                        "error": e.to_string()
+                                    }));
+                                    return;
+                                }
+                            }
+                        }
+
+                        // 按优先级申请执行名额并启动任务
+                        // 启动前订阅，避免漏掉任务开始事件
+                        let events = progress::subscribe_progress();
+                        match pool.start_task(&agent, task.to_string(), priority).await {
                             Ok(agent_id) => {
+                                // 推送本任务的进度（agent/progress），断线重连后用 agent/attach 恢复
+                                let progress_agent = agent_id.clone();
+                                let handle = forward_progress(s.clone(), events, move |event| event.agent_id == progress_agent);
+                                attach_progress(&attachments, agent_id.clone(), handle);
+
+                                if let Some(ticket) = submission {
+                                    ticket.started(&agent_id, agent.task_id().await);
+                                }
+
                                 // 更新任务状态
                                 let _ = pool.update_task_status(
                                     device_serial,
@@ -132,15 +386,24 @@ async fn register_agent_handlers_with_pool(socket: SocketRef, device_pool: Arc<D
                                 let _ = s.emit("agent/start/response", &json!({
                                     "success": true,
                                     "agent_id": agent_id,
+                                    "task_id": task_id,
                                     "device_serial": device_serial,
-                                    "task": task
+                                    "task": task,
+                                    "capture_traffic": capture_traffic,
+                                    "record_screen": record_screen,
+                                    "humanize": humanized,
+                                    "variant": variant,
+                                    "overrides": overrides,
+                                    "priority": priority
                                 }));
                             }
                             Err(e) => {
                                 error!("启动 Agent 任务失败: {}", e);
                                 let _ = s.emit("agent/start/response", &json!({
                                     "success": false,
-                                    "error": e.to_string()
+                                    "error": e.to_string(),
+                                    "status": is_capacity_error(&e).then_some(CAPACITY_EXCEEDED_STATUS)
+                                        .or(is_draining_error(&e).then_some(DRAINING_STATUS))
                                 }));
                             }
                         }
@@ -175,6 +438,487 @@ async fn register_agent_handlers_with_pool(socket: SocketRef, device_pool: Arc<D
         });
     }
 
+    // agent/traffic
+    {
+        let pool = Arc::clone(&device_pool);
+        socket.on("agent/traffic", move |s: SocketRef, data: Data<serde_json::Value>| {
+            let pool = Arc::clone(&pool);
+            async move {
+                debug!("收到 agent/traffic 请求: {:?}", data.0);
+
+                let device_serial = data.0.get("device_serial")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+
+                let artifact = match pool.get_agent(device_serial).await {
+                    Ok(agent) => agent.traffic_artifact().await,
+                    Err(e) => {
+                        let _ = s.emit("agent/traffic/response", &json!({
+                            "success": false,
+                            "error": e.to_string()
+                        }));
+                        return;
+                    }
+                };
+
+                let Some(path) = artifact else {
+                    let _ = s.emit("agent/traffic/response", &json!({
+                        "success": false,
+                        "error": "最近一次任务没有流量记录"
+                    }));
+                    return;
+                };
+
+                let har = tokio::fs::read(&path)
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).map_err(|e| e.to_string()));
+
+                match har {
+                    Ok(har) => {
+                        let _ = s.emit("agent/traffic/response", &json!({
+                            "success": true,
+                            "device_serial": device_serial,
+                            "path": path,
+                            "har": har
+                        }));
+                    }
+                    Err(e) => {
+                        error!("读取流量记录失败: {}", e);
+                        let _ = s.emit("agent/traffic/response", &json!({
+                            "success": false,
+                            "error": e
+                        }));
+                    }
+                }
+            }
+        });
+    }
+
+    // agent/status：查询任务状态与 since_step 之后的执行步骤
+    {
+        let pool = Arc::clone(&device_pool);
+        socket.on("agent/status", move |s: SocketRef, data: Data<serde_json::Value>| {
+            let pool = Arc::clone(&pool);
+            async move {
+                let device_serial = data.0.get("device_serial")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let since_step = data.0.get("since_step")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0) as usize;
+
+                let agent = match pool.get_agent(device_serial).await {
+                    Ok(agent) => agent,
+                    Err(e) => {
+                        let _ = s.emit("agent/status/response", &json!({
+                            "success": false,
+                            "error": e.to_string()
+                        }));
+                        return;
+                    }
+                };
+
+                // 截图体积较大，只返回步骤的文字信息与截图哈希，图片通过 agent/step/screenshot 按需获取
+                let query = HistoryQuery { since_step, limit: 0, ..Default::default() };
+                let steps = agent.history_page(&query).await.steps;
+
+                let _ = s.emit("agent/status/response", &json!({
+                    "success": true,
+                    "device_serial": device_serial,
+                    "status": agent.status().await,
+                    "stats": agent.task_stats().await,
+                    "result": agent.task_result().await,
+                    "steps": steps
+                }));
+            }
+        });
+    }
+
+    // agent/history：分页、过滤查询执行历史
+    // 参数：device_serial，以及 offset / limit / since_step / action_type / failed_only / include_screenshots
+    {
+        let pool = Arc::clone(&device_pool);
+        socket.on("agent/history", move |s: SocketRef, data: Data<serde_json::Value>| {
+            let pool = Arc::clone(&pool);
+            async move {
+                let device_serial = data.0.get("device_serial")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let query = match serde_json::from_value::<HistoryQuery>(data.0) {
+                    Ok(query) => query,
+                    Err(e) => {
+                        let _ = s.emit("agent/history/response", &json!({
+                            "success": false,
+                            "error": format!("查询参数错误: {}", e)
+                        }));
+                        return;
+                    }
+                };
+
+                let agent = match pool.get_agent(&device_serial).await {
+                    Ok(agent) => agent,
+                    Err(e) => {
+                        let _ = s.emit("agent/history/response", &json!({
+                            "success": false,
+                            "error": e.to_string()
+                        }));
+                        return;
+                    }
+                };
+
+                let _ = s.emit("agent/history/response", &json!({
+                    "success": true,
+                    "device_serial": device_serial,
+                    "history": agent.history_page(&query).await
+                }));
+            }
+        });
+    }
+
+    // agent/step/screenshot：按需读取某一步执行前的截图
+    {
+        let pool = Arc::clone(&device_pool);
+        socket.on("agent/step/screenshot", move |s: SocketRef, data: Data<serde_json::Value>| {
+            let pool = Arc::clone(&pool);
+            async move {
+                let device_serial = data.0.get("device_serial")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let step_number = data.0.get("step_number")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0) as usize;
+
+                let agent = match pool.get_agent(device_serial).await {
+                    Ok(agent) => agent,
+                    Err(e) => {
+                        let _ = s.emit("agent/step/screenshot/response", &json!({
+                            "success": false,
+                            "error": e.to_string()
+                        }));
+                        return;
+                    }
+                };
+
+                let stored = agent.history().await
+                    .into_iter()
+                    .find(|step| step.step_number == step_number)
+                    .and_then(|step| step.screenshot);
+                let response = match stored.map(|stored| stored.load().map(|data| (stored.hash, data))) {
+                    Some(Ok((hash, screenshot))) => json!({
+                        "success": true,
+                        "device_serial": device_serial,
+                        "step_number": step_number,
+                        "screenshot_hash": hash,
+                        "screenshot": screenshot
+                    }),
+                    Some(Err(e)) => {
+                        error!("读取步骤截图失败: {}", e);
+                        json!({ "success": false, "error": format!("读取截图失败: {}", e) })
+                    }
+                    None => json!({ "success": false, "error": format!("步骤 {} 没有截图", step_number) }),
+                };
+                let _ = s.emit("agent/step/screenshot/response", &response);
+            }
+        });
+    }
+
+    // agent/collaborate：多设备协作任务
+    {
+        let pool = Arc::clone(&device_pool);
+        socket.on("agent/collaborate", move |s: SocketRef, data: Data<serde_json::Value>| {
+            let pool = Arc::clone(&pool);
+            async move {
+                debug!("收到 agent/collaborate 请求: {:?}", data.0);
+
+                let plan = match serde_json::from_value::<CollaborationPlan>(data.0)
+                    .map_err(|e| e.to_string())
+                    .and_then(|plan| plan.validate().map(|_| plan))
+                {
+                    Ok(plan) => plan,
+                    Err(e) => {
+                        let _ = s.emit("agent/collaborate/response", &json!({
+                            "success": false,
+                            "error": format!("无效的协作任务: {}", e)
+                        }));
+                        return;
+                    }
+                };
+
+                // 使用设备池中的 Agent 组成协作组
+                let group = AgentGroup::new(AgentGroupConfig::default(), pool.model_config())
+                    .with_pool(Arc::clone(&pool));
+                for role in &plan.roles {
+                    let _ = pool.register_device(role.device_serial.clone(), None).await;
+                    match pool.get_agent(&role.device_serial).await {
+                        Ok(agent) => {
+                            group.add_agent(agent).await;
+                        }
+                        Err(e) => {
+                            let _ = s.emit("agent/collaborate/response", &json!({
+                                "success": false,
+                                "error": format!("角色 {} 获取 Agent 失败: {}", role.role, e)
+                            }));
+                            return;
+                        }
+                    }
+                }
+
+                // 转发协作过程中的跨 Agent 事件
+                let mut events = group.subscribe_events();
+                let event_socket = s.clone();
+                tokio::spawn(async move {
+                    while let Ok(event) = events.recv().await {
+                        let payload = match event {
+                            AgentGroupEvent::CollaborationMessage { collaboration_id, from_role, content } => json!({
+                                "type": "message",
+                                "collaboration_id": collaboration_id,
+                                "from_role": from_role,
+                                "content": content
+                            }),
+                            AgentGroupEvent::AgentStarted { agent_id, task } => json!({
+                                "type": "agent_started", "agent_id": agent_id, "task": task
+                            }),
+                            AgentGroupEvent::AgentCompleted { agent_id, result } => json!({
+                                "type": "agent_completed", "agent_id": agent_id, "result": result
+                            }),
+                            AgentGroupEvent::AgentFailed { agent_id, reason, error } => json!({
+                                "type": "agent_failed", "agent_id": agent_id, "reason": reason, "error": error
+                            }),
+                            AgentGroupEvent::CollaborationFinished { .. } => break,
+                            _ => continue,
+                        };
+                        let _ = event_socket.emit("agent/collaboration/event", &payload);
+                    }
+                });
+
+                match group.run_collaboration(plan).await {
+                    Ok(report) => {
+                        let _ = s.emit("agent/collaborate/response", &json!({
+                            "success": report.success,
+                            "report": report
+                        }));
+                    }
+                    Err(e) => {
+                        error!("协作任务执行失败: {}", e);
+                        let _ = s.emit("agent/collaborate/response", &json!({
+                            "success": false,
+                            "error": e.to_string()
+                        }));
+                    }
+                }
+            }
+        });
+    }
+
+    // agent/thinking/subscribe：订阅模型思考过程（流式响应时按 token 增量推送 agent/thinking 事件）
+    {
+        let subscription: Arc<std::sync::Mutex<Option<tokio::task::AbortHandle>>> = Arc::default();
+        let on_subscribe = Arc::clone(&subscription);
+        socket.on("agent/thinking/subscribe", move |s: SocketRef, data: Data<serde_json::Value>| {
+            let subscription = Arc::clone(&on_subscribe);
+            async move {
+                debug!("收到 agent/thinking/subscribe 请求: {:?}", data.0);
+
+                // 可按设备或 Agent 过滤，均未指定时接收所有 Agent 的思考过程
+                let device_serial = data.0.get("device_serial").and_then(|v| v.as_str()).map(str::to_string);
+                let agent_id = data.0.get("agent_id").and_then(|v| v.as_str()).map(str::to_string);
+
+                let mut events = subscribe_thinking_events();
+                let event_socket = s.clone();
+                let (serial_filter, agent_filter) = (device_serial.clone(), agent_id.clone());
+                let handle = tokio::spawn(async move {
+                    loop {
+                        let event = match events.recv().await {
+                            Ok(event) => event,
+                            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                        };
+                        if serial_filter.as_ref().is_some_and(|serial| event.device_serial.as_ref() != Some(serial))
+                            || agent_filter.as_ref().is_some_and(|id| &event.agent_id != id)
+                        {
+                            continue;
+                        }
+                        if event_socket.emit("agent/thinking", &event).is_err() {
+                            break;
+                        }
+                    }
+                });
+                if let Some(previous) = subscription.lock().unwrap().replace(handle.abort_handle()) {
+                    previous.abort();
+                }
+
+                let _ = s.emit("agent/thinking/subscribe/response", &json!({
+                    "success": true,
+                    "device_serial": device_serial,
+                    "agent_id": agent_id
+                }));
+            }
+        });
+
+        let on_unsubscribe = Arc::clone(&subscription);
+        socket.on("agent/thinking/unsubscribe", move |s: SocketRef| {
+            if let Some(handle) = on_unsubscribe.lock().unwrap().take() {
+                handle.abort();
+            }
+            async move {
+                let _ = s.emit("agent/thinking/unsubscribe/response", &json!({ "success": true }));
+            }
+        });
+
+        let attachments = Arc::clone(&attachments);
+        socket.on_disconnect(move |_s: SocketRef| {
+            if let Some(handle) = subscription.lock().unwrap().take() {
+                handle.abort();
+            }
+            for (_, handle) in attachments.lock().unwrap().drain() {
+                handle.abort();
+            }
+            async {}
+        });
+    }
+
+    // agent/attach：重连后重新订阅任务进度，补发游标之后错过的事件
+    {
+        let pool = Arc::clone(&device_pool);
+        let attachments = Arc::clone(&attachments);
+        socket.on("agent/attach", move |s: SocketRef, data: Data<serde_json::Value>| {
+            let pool = Arc::clone(&pool);
+            let attachments = Arc::clone(&attachments);
+            async move {
+                debug!("收到 agent/attach 请求: {:?}", data.0);
+
+                let requested = data.0.get("task_id").and_then(|v| v.as_str()).unwrap_or("");
+                let cursor = data.0.get("cursor").and_then(|v| v.as_u64()).unwrap_or(0);
+
+                // 先订阅再补发，补发之后的事件由订阅转发，不会遗漏
+                let events = progress::subscribe_progress();
+                // 任务 ID 可以是进度事件中的任务 ID，也可以是 agent/start 时客户端提供的任务 ID（对应该 Agent 最近的任务）
+                let replay = progress::progress_since(requested, cursor).or_else(|| {
+                    let agent_id = pool.submission(requested)?.agent_id?;
+                    progress::progress_since(&progress::latest_task_of_agent(&agent_id)?, cursor)
+                });
+                let Some(replay) = replay else {
+                    let _ = s.emit("agent/attach/response", &json!({
+                        "success": false,
+                        "error": format!("任务不存在或进度已过期: {}", requested)
+                    }));
+                    return;
+                };
+
+                if replay.summary.status.is_none() {
+                    let (task_id, after) = (replay.task_id.clone(), replay.cursor);
+                    let handle = forward_progress(s.clone(), events, move |event| event.task_id == task_id && event.seq > after);
+                    attach_progress(&attachments, replay.task_id.clone(), handle);
+                }
+
+                let mut response = serde_json::to_value(&replay).unwrap_or_default();
+                response["success"] = json!(true);
+                let _ = s.emit("agent/attach/response", &response);
+            }
+        });
+    }
+
+    // agent/detach：取消任务进度订阅
+    {
+        let attachments = Arc::clone(&attachments);
+        socket.on("agent/detach", move |s: SocketRef, data: Data<serde_json::Value>| {
+            let task_id = data.0.get("task_id").and_then(|v| v.as_str()).unwrap_or("").to_string();
+            let detached = attachments.lock().unwrap().remove(&task_id).map(|handle| handle.abort()).is_some();
+            async move {
+                let _ = s.emit("agent/detach/response", &json!({
+                    "success": detached,
+                    "task_id": task_id
+                }));
+            }
+        });
+    }
+
+    // agent/task/status：按任务 ID 查询任务状态，不要求是启动任务的连接
+    {
+        let pool = Arc::clone(&device_pool);
+        socket.on("agent/task/status", move |s: SocketRef, data: Data<serde_json::Value>| {
+            let pool = Arc::clone(&pool);
+            async move {
+                let requested = data.0.get("task_id").and_then(|v| v.as_str()).unwrap_or("");
+                if requested.is_empty() {
+                    let _ = s.emit("agent/task/status/response", &json!({
+                        "success": false,
+                        "error": "缺少 task_id 参数"
+                    }));
+                    return;
+                }
+                let response = match find_task(&pool, requested).await {
+                    Ok(Some((task, _))) => json!({ "success": true, "task": task }),
+                    Ok(None) => json!({ "success": false, "error": format!("任务不存在: {}", requested) }),
+                    Err(e) => json!({ "success": false, "error": e }),
+                };
+                let _ = s.emit("agent/task/status/response", &response);
+            }
+        });
+    }
+
+    // agent/task/subscribe：按任务 ID 订阅任务进度，补发游标之后的事件；已结束的任务只返回最终状态
+    {
+        let pool = Arc::clone(&device_pool);
+        let attachments = Arc::clone(&attachments);
+        socket.on("agent/task/subscribe", move |s: SocketRef, data: Data<serde_json::Value>| {
+            let pool = Arc::clone(&pool);
+            let attachments = Arc::clone(&attachments);
+            async move {
+                debug!("收到 agent/task/subscribe 请求: {:?}", data.0);
+
+                let requested = data.0.get("task_id").and_then(|v| v.as_str()).unwrap_or("");
+                let cursor = data.0.get("cursor").and_then(|v| v.as_u64()).unwrap_or(0);
+                if requested.is_empty() {
+                    let _ = s.emit("agent/task/subscribe/response", &json!({
+                        "success": false,
+                        "error": "缺少 task_id 参数"
+                    }));
+                    return;
+                }
+
+                // 先订阅再补发，补发之后的事件由订阅转发，不会遗漏
+                let events = progress::subscribe_progress();
+                let (task, live) = match find_task(&pool, requested).await {
+                    Ok(Some(found)) => found,
+                    Ok(None) => {
+                        let _ = s.emit("agent/task/subscribe/response", &json!({
+                            "success": false,
+                            "error": format!("任务不存在: {}", requested)
+                        }));
+                        return;
+                    }
+                    Err(e) => {
+                        let _ = s.emit("agent/task/subscribe/response", &json!({
+                            "success": false,
+                            "error": e
+                        }));
+                        return;
+                    }
+                };
+
+                let replay = live.then(|| progress::progress_since(&task.task_id, cursor)).flatten();
+                let subscribed = task.state == TaskRecordState::Running && replay.is_some();
+                if let Some(replay) = replay.as_ref().filter(|_| subscribed) {
+                    let (task_id, after) = (replay.task_id.clone(), replay.cursor);
+                    let handle = forward_progress(s.clone(), events, move |event| event.task_id == task_id && event.seq > after);
+                    attach_progress(&attachments, replay.task_id.clone(), handle);
+                }
+
+                let _ = s.emit("agent/task/subscribe/response", &json!({
+                    "success": true,
+                    "task": task,
+                    "subscribed": subscribed,
+                    "cursor": replay.as_ref().map(|replay| replay.cursor),
+                    "truncated": replay.as_ref().is_some_and(|replay| replay.truncated),
+                    "events": replay.map(|replay| replay.events).unwrap_or_default()
+                }));
+            }
+        });
+    }
+
     // agent/stop
     {
         let pool = Arc::clone(&device_pool);
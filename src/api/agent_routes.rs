@@ -0,0 +1,1324 @@
+//! 依赖 Agent 模块的 HTTP 接口：单个与批量直接操作、界面控件查询、执行历史、任务对话、截图时间线与产物、示范案例、应用别名、模拟定位、设备间传输、A/B 实验、基准测试、运行时（全局及设备级）模型配置、模型性能指标与调用统计、设备池容量、事件与排空、模拟器管理与设备农场
+
+use std::sync::Arc;
+use axum::{
+    extract::{State, Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use crate::context::context::IContext;
+use crate::scrcpy::macro_recorder::MacroStore;
+use crate::agent::context::{KnowledgeBase, WorkedExample};
+use crate::agent::actions::{ActionEnum, KeyCode, PressKeyAction, SwipeAction, TapAction, TypeAction};
+use crate::agent::actions::aliases::{app_aliases, AppAlias, APP_ALIASES_FILE};
+use crate::agent::bench::{self, BenchReport, BenchRunRequest};
+use crate::agent::llm::metrics::model_metrics;
+use crate::agent::llm::usage_stats::{usage_stats, ModelUsage};
+use crate::agent::experiments::{AssignmentStrategy, Experiment, ExperimentRegistry, ExperimentResults, Variant};
+use crate::agent::executor::location::{self, GeoLocation};
+use crate::agent::executor::transfer::{self, FileTransfer};
+use crate::agent::executor::ui_tree::{UiMatch, UiSelector, UiTree};
+use crate::agent::{AgentConfig, ModelConfig};
+use crate::agent::core::history::{HistoryPage, HistoryQuery};
+use crate::agent::core::agent::AGENT_LOG_DIR;
+use crate::agent::core::artifacts::{self, artifact_store, TaskArtifact};
+use crate::agent::core::transcript::{self, TaskConversation};
+use crate::agent::core::frames::{self, FrameQuery, FrameTimeline};
+use crate::agent::core::traits::{Action, ActionResult, Agent, AgentError, Device};
+use crate::agent::pool::{is_draining_error, CapacitySnapshot, DrainPhase, DrainStatus, EmulatorInstance, EventPage, FarmDeviceHealth};
+use crate::error::AppError;
+use super::annotate::CoordinateSpace;
+use super::api::{ApiResponse, ApiServer};
+use super::API_VERSION_PREFIX;
+
+/// 单次批量操作最多包含的操作数
+pub const MAX_BATCH_ACTIONS: usize = 100;
+
+/// 批量操作中单个操作的结果
+#[derive(Debug, Serialize)]
+pub struct ActionOutcome {
+    /// 操作在请求中的序号（从 0 开始）
+    pub index: usize,
+    /// 操作类型
+    pub action: String,
+    #[serde(flatten)]
+    pub result: ActionResult,
+}
+
+/// 批量操作结果
+#[derive(Debug, Serialize)]
+pub struct ActionBatchResult {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<ActionOutcome>,
+}
+
+/// 点击请求
+#[derive(Debug, Deserialize)]
+pub struct TapRequest {
+    pub x: u32,
+    pub y: u32,
+    /// 坐标系，默认为与 Agent 操作一致的 0~1000 逻辑坐标
+    #[serde(default)]
+    pub coords: CoordinateSpace,
+}
+
+/// 滑动请求
+#[derive(Debug, Deserialize)]
+pub struct SwipeRequest {
+    pub start_x: u32,
+    pub start_y: u32,
+    pub end_x: u32,
+    pub end_y: u32,
+    #[serde(default = "default_swipe_duration_ms")]
+    pub duration_ms: u32,
+    #[serde(default)]
+    pub coords: CoordinateSpace,
+}
+
+fn default_swipe_duration_ms() -> u32 {
+    300
+}
+
+/// 输入文本请求
+#[derive(Debug, Deserialize)]
+pub struct TypeRequest {
+    pub text: String,
+}
+
+/// 按键：名称（如 `"back"`、`"enter"`）或 Android keycode
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum KeyInput {
+    Code(u32),
+    Name(String),
+}
+
+/// 按键请求
+#[derive(Debug, Deserialize)]
+pub struct KeyRequest {
+    pub key: KeyInput,
+    /// 连续按键次数
+    #[serde(default = "default_key_repeat")]
+    pub repeat: u32,
+}
+
+fn default_key_repeat() -> u32 {
+    1
+}
+
+/// 界面控件查询参数
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct UiQuery {
+    /// 类似 XPath 的选择器，如 `//*[contains(@text,'设置')]`；未指定时返回完整控件树
+    pub query: Option<String>,
+}
+
+/// 界面控件：完整控件树或选择器匹配到的控件
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum UiDump {
+    Tree(UiTree),
+    Matches {
+        width: u32,
+        height: u32,
+        matches: Vec<UiMatch>,
+    },
+}
+
+/// 任务产物及其下载链接
+#[derive(Debug, Serialize)]
+pub struct TaskArtifactLink {
+    #[serde(flatten)]
+    pub artifact: TaskArtifact,
+    /// 下载链接：S3 存储为预签名 URL，本地存储为本服务的下载接口
+    pub url: String,
+    /// 预签名 URL 的有效期（秒），本地存储为 None
+    pub expires_in_secs: Option<u64>,
+}
+
+/// 将宏转换为示范案例的请求
+#[derive(Debug, Deserialize)]
+pub struct TeachMacroRequest {
+    /// 演示所完成的任务描述
+    pub task: String,
+    /// 应用包名，未指定时使用录制时的前台应用
+    pub app: Option<String>,
+}
+
+/// 文件传输请求
+#[derive(Debug, Deserialize)]
+pub struct TransferFileRequest {
+    pub from_device: String,
+    pub to_device: String,
+    pub source_path: String,
+    #[serde(default)]
+    pub target_path: Option<String>,
+}
+
+/// 剪贴板传输请求
+#[derive(Debug, Deserialize)]
+pub struct TransferClipboardRequest {
+    pub from_device: String,
+    pub to_device: String,
+}
+
+/// 创建实验请求
+#[derive(Debug, Deserialize)]
+pub struct CreateExperimentRequest {
+    pub name: String,
+    #[serde(default)]
+    pub strategy: AssignmentStrategy,
+    pub variants: Vec<Variant>,
+}
+
+/// 添加应用别名请求
+#[derive(Debug, Deserialize)]
+pub struct AppAliasesRequest {
+    /// 名称 -> 包名
+    pub aliases: std::collections::BTreeMap<String, String>,
+}
+
+/// 设备池事件查询参数
+#[derive(Debug, Deserialize)]
+pub struct PoolEventsQuery {
+    /// 上次处理的事件序号，返回之后的事件
+    #[serde(default)]
+    pub since: u64,
+    /// 最多返回的事件数
+    #[serde(default = "default_pool_events_limit")]
+    pub limit: usize,
+}
+
+fn default_pool_events_limit() -> usize {
+    200
+}
+
+/// 设备池排空参数
+#[derive(Debug, Deserialize)]
+pub struct DrainQuery {
+    /// 宽限期（秒），超过后停止仍在执行的任务
+    #[serde(default = "default_drain_grace_secs")]
+    pub grace_secs: u64,
+}
+
+fn default_drain_grace_secs() -> u64 {
+    300
+}
+
+/// 模型调用统计查询参数
+#[derive(Debug, Deserialize)]
+pub struct ModelStatsQuery {
+    /// 统计最近多少天（0 表示全部）
+    #[serde(default = "default_model_stats_days")]
+    pub days: u32,
+}
+
+fn default_model_stats_days() -> u32 {
+    7
+}
+
+/// 当前生效的模型/Agent 配置
+#[derive(Debug, Serialize)]
+pub struct EffectiveConfig {
+    /// 配置版本，每次更新递增
+    pub version: u64,
+    /// 模型配置（API Key 已脱敏）
+    pub model: ModelConfig,
+    /// Agent 配置（回调签名密钥已脱敏）
+    pub agent: AgentConfig,
+}
+
+impl ApiServer {
+    /// 注册 Agent 相关路由
+    pub(super) fn agent_routes(
+        app: Router<Arc<dyn IContext + Sync + Send>>,
+    ) -> Router<Arc<dyn IContext + Sync + Send>> {
+        app.route("/device/{serial}/actions", post(Self::execute_actions))
+            .route("/device/{serial}/tap", post(Self::tap))
+            .route("/device/{serial}/swipe", post(Self::swipe))
+            .route("/device/{serial}/type", post(Self::type_text))
+            .route("/device/{serial}/key", post(Self::press_key))
+            .route("/device/{serial}/ui", get(Self::get_ui))
+            .route("/device/{serial}/history", get(Self::get_history))
+            .route("/tasks/{id}/conversation", get(Self::get_task_conversation))
+            .route("/tasks/{id}/frames", get(Self::get_task_frames))
+            .route("/tasks/{id}/artifacts", get(Self::list_task_artifacts))
+            .route("/tasks/{id}/artifacts/{*name}", get(Self::download_task_artifact))
+            .route("/device/{serial}/macro/{name}/teach", post(Self::teach_macro))
+            .route("/device/{serial}/location", post(Self::set_location).delete(Self::clear_location))
+            .route("/transfer/file", post(Self::transfer_file))
+            .route("/transfer/clipboard", post(Self::transfer_clipboard))
+            .route("/experiments", get(Self::list_experiments).post(Self::create_experiment))
+            .route("/experiments/{id}/results", get(Self::get_experiment_results))
+            .route("/bench/run", post(Self::run_bench))
+            .route("/bench/{id}", get(Self::get_bench_report))
+            .route("/config", get(Self::get_config))
+            .route("/config/model", put(Self::update_model_config))
+            .route(
+                "/device/{serial}/model",
+                get(Self::get_device_model).put(Self::set_device_model).delete(Self::clear_device_model),
+            )
+            .route("/metrics", get(Self::get_metrics))
+            .route("/stats/models", get(Self::get_model_stats))
+            .route("/pool/capacity", get(Self::get_pool_capacity))
+            .route("/pool/events", get(Self::get_pool_events))
+            .route("/pool/drain", get(Self::get_drain_status).post(Self::start_drain).delete(Self::resume_pool))
+            .route("/emulators", get(Self::list_emulators).post(Self::boot_emulator))
+            .route("/emulators/{serial}", delete(Self::destroy_emulator))
+            .route("/farm", get(Self::get_farm_health))
+            .route("/apps/aliases", get(Self::list_app_aliases).post(Self::add_app_aliases))
+            .route("/apps/aliases/{name}", delete(Self::remove_app_alias))
+    }
+
+    /// 不经过模型直接在设备上依次执行一组操作（`ActionEnum` 数组），返回每个操作的结果
+    ///
+    /// 执行前先校验全部操作，任一无效时不执行；执行中某个操作失败不影响后续操作
+    async fn execute_actions(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+        Json(actions): Json<Vec<ActionEnum>>,
+    ) -> (StatusCode, Json<ApiResponse<ActionBatchResult>>) {
+        if actions.is_empty() || actions.len() > MAX_BATCH_ACTIONS {
+            return Self::api_error(
+                StatusCode::BAD_REQUEST,
+                format!("操作数应为 1 到 {} 个，实际 {} 个", MAX_BATCH_ACTIONS, actions.len()),
+            );
+        }
+        if let Some((index, e)) = actions
+            .iter()
+            .enumerate()
+            .find_map(|(index, action)| action.validate().err().map(|e| (index, e)))
+        {
+            return Self::api_error(StatusCode::BAD_REQUEST, format!("操作 #{} 验证失败: {}", index, e));
+        }
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let results = match pool.execute_actions(&serial, &actions).await {
+            Ok(results) => results,
+            Err(e) => return Self::api_error(Self::action_error_status(&e), format!("执行操作失败: {}", e)),
+        };
+        let results: Vec<ActionOutcome> = actions
+            .iter()
+            .zip(results)
+            .enumerate()
+            .map(|(index, (action, result))| ActionOutcome { index, action: action.action_type(), result })
+            .collect();
+        let succeeded = results.iter().filter(|outcome| outcome.result.success).count();
+        let failed = results.len() - succeeded;
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: failed == 0,
+                message: format!("{}/{} 个操作成功", succeeded, results.len()),
+                data: Some(ActionBatchResult { succeeded, failed, results }),
+            })
+        )
+    }
+
+    /// 直接操作失败时的状态码
+    fn action_error_status(error: &AppError) -> StatusCode {
+        match error {
+            AppError::AgentError(AgentError::DeviceNotFound(_)) => StatusCode::NOT_FOUND,
+            AppError::AgentError(AgentError::AlreadyRunning) => StatusCode::CONFLICT,
+            e if is_draining_error(e) => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// 按像素坐标直接操作设备，不经过逻辑坐标换算；`points` 超出屏幕时返回 400，准入与排队规则与其它直接操作相同
+    async fn execute_pixel_action<F, Fut>(
+        ctx: &Arc<dyn IContext + Sync + Send>,
+        serial: &str,
+        message: String,
+        points: Vec<(u32, u32)>,
+        op: F,
+    ) -> (StatusCode, Json<ApiResponse<ActionResult>>)
+    where
+        F: FnOnce(Arc<dyn Device>) -> Fut,
+        Fut: std::future::Future<Output = Result<(), AppError>>,
+    {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let start = std::time::Instant::now();
+        let outcome = pool
+            .with_idle_agent(serial, |agent| async move {
+                let device = Arc::clone(agent.device());
+                let size = match device.screen_size().await {
+                    Ok(size) => size,
+                    Err(e) => return Ok(Err(e)),
+                };
+                if let Some(error) = pixel_out_of_screen(&points, size) {
+                    return Err(error);
+                }
+                Ok(op(device).await)
+            })
+            .await;
+        let elapsed = start.elapsed().as_millis() as u32;
+        match outcome {
+            Ok(Err(error)) => Self::api_error(StatusCode::BAD_REQUEST, error),
+            Ok(Ok(Ok(()))) => {
+                let result = ActionResult::success(message, elapsed);
+                (StatusCode::OK, Json(ApiResponse { success: true, message: result.message.clone(), data: Some(result) }))
+            }
+            Ok(Ok(Err(e))) => {
+                let result = ActionResult::failure(format!("{}失败: {}", message, e), elapsed);
+                (StatusCode::BAD_GATEWAY, Json(ApiResponse { success: false, message: result.message.clone(), data: Some(result) }))
+            }
+            Err(e) => Self::api_error(Self::action_error_status(&e), format!("执行操作失败: {}", e)),
+        }
+    }
+
+    /// 直接执行单个操作
+    async fn execute_single_action(
+        ctx: &Arc<dyn IContext + Sync + Send>,
+        serial: &str,
+        action: ActionEnum,
+    ) -> (StatusCode, Json<ApiResponse<ActionResult>>) {
+        if let Err(e) = action.validate() {
+            return Self::api_error(StatusCode::BAD_REQUEST, format!("操作验证失败: {}", e));
+        }
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        match pool.execute_actions(serial, std::slice::from_ref(&action)).await {
+            Ok(mut results) => match results.pop() {
+                Some(result) => (
+                    if result.success { StatusCode::OK } else { StatusCode::BAD_GATEWAY },
+                    Json(ApiResponse {
+                        success: result.success,
+                        message: result.message.clone(),
+                        data: Some(result),
+                    })
+                ),
+                None => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, "操作没有返回结果".to_string()),
+            },
+            Err(e) => Self::api_error(Self::action_error_status(&e), format!("执行操作失败: {}", e)),
+        }
+    }
+
+    /// 点击，如 `{"x": 500, "y": 800}`（逻辑坐标）或 `{"x": 540, "y": 1200, "coords": "pixel"}`
+    async fn tap(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+        Json(req): Json<TapRequest>,
+    ) -> (StatusCode, Json<ApiResponse<ActionResult>>) {
+        let (x, y) = (req.x, req.y);
+        if req.coords == CoordinateSpace::Pixel {
+            let message = format!("点击像素坐标 ({}, {})", x, y);
+            return Self::execute_pixel_action(&ctx, &serial, message, vec![(x, y)], |device| async move {
+                device.tap_pixel(x, y).await
+            })
+            .await;
+        }
+        Self::execute_single_action(&ctx, &serial, ActionEnum::Tap(TapAction { x, y, description: None })).await
+    }
+
+    /// 滑动，`duration_ms` 默认 300
+    async fn swipe(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+        Json(req): Json<SwipeRequest>,
+    ) -> (StatusCode, Json<ApiResponse<ActionResult>>) {
+        if req.coords == CoordinateSpace::Pixel {
+            let (start, end, duration_ms) = ((req.start_x, req.start_y), (req.end_x, req.end_y), req.duration_ms);
+            let message = format!("滑动像素坐标 {:?} -> {:?}", start, end);
+            return Self::execute_pixel_action(&ctx, &serial, message, vec![start, end], |device| async move {
+                device.swipe_pixel(start, end, duration_ms).await
+            })
+            .await;
+        }
+        let action = ActionEnum::Swipe(SwipeAction {
+            start_x: req.start_x,
+            start_y: req.start_y,
+            end_x: req.end_x,
+            end_y: req.end_y,
+            duration_ms: req.duration_ms,
+            description: None,
+        });
+        Self::execute_single_action(&ctx, &serial, action).await
+    }
+
+    /// 在当前焦点输入框中输入文本
+    async fn type_text(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+        Json(req): Json<TypeRequest>,
+    ) -> (StatusCode, Json<ApiResponse<ActionResult>>) {
+        Self::execute_single_action(&ctx, &serial, ActionEnum::Type(TypeAction { text: req.text, description: None })).await
+    }
+
+    /// 按键，如 `{"key": "back"}`、`{"key": 66}` 或 `{"key": "volume_up", "repeat": 5}`
+    async fn press_key(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+        Json(req): Json<KeyRequest>,
+    ) -> (StatusCode, Json<ApiResponse<ActionResult>>) {
+        let (keycode, key) = match &req.key {
+            KeyInput::Code(code) => (KeyCode::from_android_keycode(*code), code.to_string()),
+            KeyInput::Name(name) => (KeyCode::from_name(name), name.clone()),
+        };
+        let Some(keycode) = keycode else {
+            return Self::api_error(StatusCode::BAD_REQUEST, format!("不支持的按键: {}", key));
+        };
+        let action = ActionEnum::PressKey(PressKeyAction { keycode, repeat: req.repeat, description: None });
+        Self::execute_single_action(&ctx, &serial, action).await
+    }
+
+    /// 当前界面的控件树，带 `?query=` 时只返回选择器匹配到的控件及其中心点坐标
+    async fn get_ui(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+        Query(query): Query<UiQuery>,
+    ) -> (StatusCode, Json<ApiResponse<UiDump>>) {
+        let selector = match query.query.as_deref().map(UiSelector::parse).transpose() {
+            Ok(selector) => selector,
+            Err(e) => return Self::api_error(StatusCode::BAD_REQUEST, e),
+        };
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let agent = match pool.get_agent(&serial).await {
+            Ok(agent) => agent,
+            Err(e) => return Self::api_error(Self::action_error_status(&e), format!("获取设备失败: {}", e)),
+        };
+        let xml = match agent.device().ui_dump().await {
+            Ok(xml) => xml,
+            Err(e) => return Self::api_error(StatusCode::BAD_GATEWAY, format!("获取界面控件失败: {}", e)),
+        };
+        let tree = UiTree::parse(&xml);
+        let (message, dump) = match selector {
+            Some(selector) => {
+                let matches = tree.query(&selector);
+                (
+                    format!("匹配到 {} 个控件", matches.len()),
+                    UiDump::Matches { width: tree.width, height: tree.height, matches },
+                )
+            }
+            None => (format!("屏幕 {}x{}", tree.width, tree.height), UiDump::Tree(tree)),
+        };
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message,
+                data: Some(dump),
+            })
+        )
+    }
+
+    /// 分页、过滤查询设备 Agent 的执行历史，如 `?offset=0&limit=20&failed_only=true`
+    async fn get_history(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+        Query(query): Query<HistoryQuery>,
+    ) -> (StatusCode, Json<ApiResponse<HistoryPage>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let agent = match pool.get_agent(&serial).await {
+            Ok(agent) => agent,
+            Err(e) => return Self::api_error(StatusCode::NOT_FOUND, format!("获取设备 Agent 失败: {}", e)),
+        };
+        let page = agent.history_page(&query).await;
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("共 {} 个步骤", page.total),
+                data: Some(page),
+            })
+        )
+    }
+
+    /// 任务的完整对话（按顺序的提示消息、截图引用与模型回复及解析出的操作），从 Agent 日志中还原
+    async fn get_task_conversation(Path(task_id): Path<String>) -> (StatusCode, Json<ApiResponse<TaskConversation>>) {
+        let result = tokio::task::spawn_blocking(move || transcript::load_task_conversation(AGENT_LOG_DIR, &task_id)).await;
+        match result {
+            Ok(Ok(Some(conversation))) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("共 {} 条消息", conversation.messages.len()),
+                    data: Some(conversation),
+                })
+            ),
+            Ok(Ok(None)) => Self::api_error(StatusCode::NOT_FOUND, "未找到任务对话记录".to_string()),
+            Ok(Err(e)) => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("读取 Agent 日志失败: {}", e)),
+            Err(e) => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("读取 Agent 日志失败: {}", e)),
+        }
+    }
+
+    /// 任务的截图时间线：离指定步骤或时间点最近的一帧与前后帧的缩略图，如 `?ts=2026-01-01T10:00:30Z&radius=5`
+    async fn get_task_frames(
+        Path(task_id): Path<String>,
+        Query(query): Query<FrameQuery>,
+    ) -> (StatusCode, Json<ApiResponse<FrameTimeline>>) {
+        let target = match query.target() {
+            Ok(target) => target,
+            Err(e) => return Self::api_error(StatusCode::BAD_REQUEST, e),
+        };
+        let radius = query.radius();
+        let result = tokio::task::spawn_blocking(move || frames::load_frame_timeline(AGENT_LOG_DIR, &task_id, target, radius)).await;
+        match result {
+            Ok(Ok(Some(timeline))) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("第 {} / {} 帧", timeline.frame.index + 1, timeline.total),
+                    data: Some(timeline),
+                })
+            ),
+            Ok(Ok(None)) => Self::api_error(StatusCode::NOT_FOUND, "未找到任务截图".to_string()),
+            Ok(Err(e)) => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("读取 Agent 日志失败: {}", e)),
+            Err(e) => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("读取 Agent 日志失败: {}", e)),
+        }
+    }
+
+    /// 任务上传到产物存储的截图、流量记录与报告，附带下载链接
+    async fn list_task_artifacts(Path(task_id): Path<String>) -> (StatusCode, Json<ApiResponse<Vec<TaskArtifactLink>>>) {
+        let log_task_id = task_id.clone();
+        let result = tokio::task::spawn_blocking(move || artifacts::load_task_artifacts(AGENT_LOG_DIR, &log_task_id)).await;
+        let uploaded = match result {
+            Ok(Ok(uploaded)) => uploaded,
+            Ok(Err(e)) => return Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("读取 Agent 日志失败: {}", e)),
+            Err(e) => return Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("读取 Agent 日志失败: {}", e)),
+        };
+        let store = artifact_store();
+        let links: Vec<TaskArtifactLink> = uploaded
+            .into_iter()
+            .map(|artifact| match store.download_url(&artifact.key) {
+                Some(presigned) => TaskArtifactLink { artifact, url: presigned.url, expires_in_secs: Some(presigned.expires_in_secs) },
+                None => TaskArtifactLink {
+                    url: format!("{}/tasks/{}/artifacts/{}", API_VERSION_PREFIX, task_id, artifact.name),
+                    artifact,
+                    expires_in_secs: None,
+                },
+            })
+            .collect();
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("共 {} 个产物", links.len()),
+                data: Some(links),
+            })
+        )
+    }
+
+    /// 下载任务产物：S3 存储重定向到预签名 URL，本地存储直接返回文件
+    async fn download_task_artifact(Path((task_id, name)): Path<(String, String)>) -> Response {
+        if !artifacts::is_valid_name(&task_id) || !artifacts::is_valid_name(&name) {
+            return Self::api_error::<()>(StatusCode::BAD_REQUEST, format!("无效的产物名称: {}", name)).into_response();
+        }
+        let store = artifact_store();
+        let key = store.key(&task_id, &name);
+        if let Some(presigned) = store.download_url(&key) {
+            return Redirect::temporary(&presigned.url).into_response();
+        }
+        let Some(path) = store.local_path(&key) else {
+            return Self::api_error::<()>(StatusCode::NOT_FOUND, format!("未找到产物: {}", name)).into_response();
+        };
+        match tokio::fs::read(&path).await {
+            Ok(body) => ([(header::CONTENT_TYPE, artifacts::content_type(&name))], body).into_response(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Self::api_error::<()>(StatusCode::NOT_FOUND, format!("未找到产物: {}", name)).into_response()
+            }
+            Err(e) => Self::api_error::<()>(StatusCode::INTERNAL_SERVER_ERROR, format!("读取产物失败: {}", e)).into_response(),
+        }
+    }
+
+    /// 模型性能指标与设备池容量（Prometheus 文本格式）
+    async fn get_metrics(State(ctx): State<Arc<dyn IContext + Sync + Send>>) -> impl IntoResponse {
+        let mut body = model_metrics().render_prometheus();
+        if let Some(pool) = ctx.get_device_pool().read().await.clone() {
+            body.push_str(&pool.capacity_snapshot().await.render_prometheus());
+        }
+        body.push_str(&crate::storage::storage_monitor().render_prometheus());
+        (
+            [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+            body,
+        )
+    }
+
+    /// 各模型的调用次数、失败率、平均耗时、token 消耗与估算费用，如 `?days=30`
+    async fn get_model_stats(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Query(query): Query<ModelStatsQuery>,
+    ) -> (StatusCode, Json<ApiResponse<Vec<ModelUsage>>>) {
+        let prices = match ctx.get_device_pool().read().await.clone() {
+            Some(pool) => pool.model_config().token_prices,
+            None => Default::default(),
+        };
+        let report = usage_stats().report(query.days, chrono::Utc::now().date_naive(), &prices);
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("共 {} 个模型", report.len()),
+                data: Some(report),
+            })
+        )
+    }
+
+    /// 设备池容量使用情况
+    async fn get_pool_capacity(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+    ) -> (StatusCode, Json<ApiResponse<CapacitySnapshot>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let snapshot = pool.capacity_snapshot().await;
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("{} 个 Agent 正在执行任务，{} 个 scrcpy 会话", snapshot.busy_agents, snapshot.streams),
+                data: Some(snapshot),
+            })
+        )
+    }
+
+    /// 序号大于 `since` 的设备池事件，订阅者用来补齐错过的事件
+    async fn get_pool_events(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Query(query): Query<PoolEventsQuery>,
+    ) -> (StatusCode, Json<ApiResponse<EventPage>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let page = pool.events_since(query.since, query.limit);
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("{} 个事件，最新序号 {}", page.events.len(), page.latest),
+                data: Some(page),
+            })
+        )
+    }
+
+    /// 开始排空设备池，如 `?grace_secs=600`；返回当前排空状态，`phase` 为 `quiesced` 时可以安全停止服务
+    async fn start_drain(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Query(query): Query<DrainQuery>,
+    ) -> (StatusCode, Json<ApiResponse<DrainStatus>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let status = pool.start_drain(std::time::Duration::from_secs(query.grace_secs)).await;
+        (
+            StatusCode::ACCEPTED,
+            Json(ApiResponse {
+                success: true,
+                message: format!("设备池正在排空，{} 个任务执行中，{} 个 scrcpy 会话", status.running_tasks, status.streams),
+                data: Some(status),
+            })
+        )
+    }
+
+    /// 设备池排空进度
+    async fn get_drain_status(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+    ) -> (StatusCode, Json<ApiResponse<DrainStatus>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let status = pool.drain_status().await;
+        let message = match status.phase {
+            DrainPhase::Accepting => "设备池正常接受任务".to_string(),
+            DrainPhase::Draining => format!("设备池正在排空，{} 个任务执行中，{} 个 scrcpy 会话", status.running_tasks, status.streams),
+            DrainPhase::Quiesced => "设备池已排空，可以安全停止服务".to_string(),
+        };
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message,
+                data: Some(status),
+            })
+        )
+    }
+
+    /// 结束排空，恢复接受新任务
+    async fn resume_pool(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+    ) -> (StatusCode, Json<ApiResponse<()>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let message = if pool.resume_accepting() {
+            "设备池已恢复接受任务"
+        } else {
+            "设备池未在排空"
+        };
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: message.to_string(),
+                data: None,
+            })
+        )
+    }
+
+    /// 受管理的模拟器
+    async fn list_emulators(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+    ) -> (StatusCode, Json<ApiResponse<Vec<EmulatorInstance>>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let emulators = pool.list_emulators();
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("{} 个模拟器", emulators.len()),
+                data: Some(emulators),
+            })
+        )
+    }
+
+    /// 启动一个模拟器，启动完成（可能需要数分钟）后注册到设备池并返回其序列号
+    async fn boot_emulator(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+    ) -> (StatusCode, Json<ApiResponse<EmulatorInstance>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        match pool.boot_emulator().await {
+            Ok(instance) => {
+                info!("模拟器已启动: {}", instance.serial);
+                (
+                    StatusCode::OK,
+                    Json(ApiResponse {
+                        success: true,
+                        message: format!("模拟器 {} 已启动", instance.serial),
+                        data: Some(instance),
+                    })
+                )
+            }
+            Err(e) => Self::api_error(StatusCode::SERVICE_UNAVAILABLE, format!("启动模拟器失败: {}", e)),
+        }
+    }
+
+    /// 注销并关闭模拟器
+    async fn destroy_emulator(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+    ) -> (StatusCode, Json<ApiResponse<()>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        match pool.destroy_emulator(&serial).await {
+            Ok(()) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("模拟器 {} 已销毁", serial),
+                    data: None,
+                })
+            ),
+            Err(e) => Self::api_error(StatusCode::NOT_FOUND, format!("销毁模拟器失败: {}", e)),
+        }
+    }
+
+    /// 农场设备的健康状态
+    async fn get_farm_health(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+    ) -> (StatusCode, Json<ApiResponse<Vec<FarmDeviceHealth>>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let health = pool.farm_health();
+        let healthy = health.iter().filter(|device| device.healthy).count();
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("{}/{} 台农场设备可用", healthy, health.len()),
+                data: Some(health),
+            })
+        )
+    }
+
+    /// 列出应用别名
+    async fn list_app_aliases() -> (StatusCode, Json<ApiResponse<Vec<AppAlias>>>) {
+        let aliases = app_aliases().list();
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("共 {} 个应用别名", aliases.len()),
+                data: Some(aliases),
+            })
+        )
+    }
+
+    /// 添加（或覆盖）应用别名并保存
+    async fn add_app_aliases(
+        Json(req): Json<AppAliasesRequest>,
+    ) -> (StatusCode, Json<ApiResponse<Vec<AppAlias>>>) {
+        let count = req.aliases.len();
+        if let Err(e) = app_aliases().insert(req.aliases) {
+            return Self::api_error(StatusCode::BAD_REQUEST, e);
+        }
+        if let Err(e) = app_aliases().save(std::path::Path::new(APP_ALIASES_FILE)) {
+            return Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, e);
+        }
+        info!("已添加 {} 个应用别名", count);
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("已添加 {} 个应用别名", count),
+                data: Some(app_aliases().list()),
+            })
+        )
+    }
+
+    /// 删除通过接口添加的应用别名
+    async fn remove_app_alias(
+        Path(name): Path<String>,
+    ) -> (StatusCode, Json<ApiResponse<()>>) {
+        if !app_aliases().remove(&name) {
+            return Self::api_error(StatusCode::NOT_FOUND, format!("应用别名 {} 不存在（配置文件中的别名需在配置文件中删除）", name));
+        }
+        if let Err(e) = app_aliases().save(std::path::Path::new(APP_ALIASES_FILE)) {
+            return Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, e);
+        }
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("应用别名 {} 已删除", name),
+                data: None,
+            })
+        )
+    }
+
+    /// 将已保存的宏及其截图转换为示范案例，存入应用知识库
+    async fn teach_macro(
+        Path((serial, name)): Path<(String, String)>,
+        Json(req): Json<TeachMacroRequest>,
+    ) -> (StatusCode, Json<ApiResponse<WorkedExample>>) {
+        if req.task.trim().is_empty() {
+            return Self::api_error(StatusCode::BAD_REQUEST, "任务描述不能为空".to_string());
+        }
+        let device_macro = match MacroStore::for_device(&serial).load(&name).await {
+            Ok(m) => m,
+            Err(e) => return Self::api_error(StatusCode::NOT_FOUND, e),
+        };
+
+        let (example, screenshots) = WorkedExample::from_macro(&device_macro, req.task.trim(), req.app);
+        match KnowledgeBase::default().save(&example, &screenshots).await {
+            Ok(path) => {
+                info!("宏 {} 已转换为示范案例: {:?} ({} 步)", name, path, example.steps.len());
+                (
+                    StatusCode::OK,
+                    Json(ApiResponse {
+                        success: true,
+                        message: format!("示范案例 {} 已保存", example.name),
+                        data: Some(example),
+                    })
+                )
+            }
+            Err(e) => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        }
+    }
+
+    /// 设置设备模拟定位
+    async fn set_location(
+        Path(serial): Path<String>,
+        Json(req): Json<GeoLocation>,
+    ) -> (StatusCode, Json<ApiResponse<GeoLocation>>) {
+        if let Err(e) = req.validate() {
+            return Self::api_error(StatusCode::BAD_REQUEST, e);
+        }
+        match location::set_mock_location(&serial, &req).await {
+            Ok(()) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("已设置模拟定位: ({}, {})", req.latitude, req.longitude),
+                    data: Some(req),
+                })
+            ),
+            Err(e) => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        }
+    }
+
+    /// 停止设备模拟定位
+    async fn clear_location(Path(serial): Path<String>) -> (StatusCode, Json<ApiResponse<String>>) {
+        match location::clear_mock_location(&serial).await {
+            Ok(()) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: "已停止模拟定位".to_string(),
+                    data: Some(serial),
+                })
+            ),
+            Err(e) => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        }
+    }
+
+    /// 在设备之间复制文件
+    async fn transfer_file(Json(req): Json<TransferFileRequest>) -> (StatusCode, Json<ApiResponse<FileTransfer>>) {
+        match transfer::transfer_file(&req.from_device, &req.to_device, &req.source_path, req.target_path.as_deref()).await {
+            Ok(result) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("已复制 {} 字节到 {}:{}", result.bytes, result.to_device, result.target_path),
+                    data: Some(result),
+                })
+            ),
+            Err(e) => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        }
+    }
+
+    /// 在设备之间复制剪贴板内容
+    async fn transfer_clipboard(Json(req): Json<TransferClipboardRequest>) -> (StatusCode, Json<ApiResponse<String>>) {
+        match transfer::transfer_clipboard(&req.from_device, &req.to_device).await {
+            Ok(text) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("已将 {} 的剪贴板复制到 {}", req.from_device, req.to_device),
+                    data: Some(text),
+                })
+            ),
+            Err(e) => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        }
+    }
+
+    /// 获取设备池的实验注册表
+    async fn experiments(ctx: &Arc<dyn IContext + Sync + Send>) -> Option<Arc<ExperimentRegistry>> {
+        ctx.get_device_pool()
+            .read()
+            .await
+            .as_ref()
+            .map(|pool| Arc::clone(pool.experiments()))
+    }
+
+    /// 列出所有实验
+    async fn list_experiments(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+    ) -> (StatusCode, Json<ApiResponse<Vec<Experiment>>>) {
+        let Some(experiments) = Self::experiments(&ctx).await else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let list = experiments.list().await;
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("共 {} 个实验", list.len()),
+                data: Some(list),
+            })
+        )
+    }
+
+    /// 创建实验
+    async fn create_experiment(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Json(req): Json<CreateExperimentRequest>,
+    ) -> (StatusCode, Json<ApiResponse<Experiment>>) {
+        let Some(experiments) = Self::experiments(&ctx).await else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        match experiments.create(req.name, req.strategy, req.variants).await {
+            Ok(experiment) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("实验 {} 已创建", experiment.name),
+                    data: Some(experiment),
+                })
+            ),
+            Err(e) => Self::api_error(StatusCode::BAD_REQUEST, e),
+        }
+    }
+
+    /// 获取实验按变体汇总的结果
+    async fn get_experiment_results(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(id): Path<String>,
+    ) -> (StatusCode, Json<ApiResponse<ExperimentResults>>) {
+        let Some(experiments) = Self::experiments(&ctx).await else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        match experiments.get(&id).await {
+            Some(experiment) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("实验 {} 共 {} 个任务", experiment.name, experiment.outcomes.len()),
+                    data: Some(experiment.results()),
+                })
+            ),
+            None => Self::api_error(StatusCode::NOT_FOUND, format!("实验不存在: {}", id)),
+        }
+    }
+
+    /// 启动基准测试，返回报告 ID
+    async fn run_bench(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Json(req): Json<BenchRunRequest>,
+    ) -> (StatusCode, Json<ApiResponse<String>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        match bench::start_bench(pool, req).await {
+            Ok(id) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("基准测试已启动，报告 ID: {}", id),
+                    data: Some(id),
+                })
+            ),
+            Err(e) => Self::api_error(StatusCode::BAD_REQUEST, e),
+        }
+    }
+
+    /// 获取基准测试报告（运行中时为当前进度）
+    async fn get_bench_report(
+        Path(id): Path<String>,
+    ) -> (StatusCode, Json<ApiResponse<BenchReport>>) {
+        match BenchReport::load(&id).await {
+            Ok(report) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("已完成 {}/{} 次运行", report.results.len(), report.total_runs),
+                    data: Some(report),
+                })
+            ),
+            Err(e) => Self::api_error(StatusCode::NOT_FOUND, e),
+        }
+    }
+
+    /// 获取当前生效的模型/Agent 配置
+    async fn get_config(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+    ) -> (StatusCode, Json<ApiResponse<EffectiveConfig>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: "获取配置成功".to_string(),
+                data: Some(Self::effective_config(&pool)),
+            })
+        )
+    }
+
+    /// 更新模型/Agent 配置，无需重启
+    ///
+    /// 请求体为 `{"model": {...}, "agent": {...}}`，也可以直接给出模型配置字段；只需包含要修改的字段。
+    /// 正在执行的任务继续使用旧配置，之后创建的 Agent 使用新配置
+    async fn update_model_config(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Json(body): Json<serde_json::Value>,
+    ) -> (StatusCode, Json<ApiResponse<EffectiveConfig>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        let mut patch = if body.get("model").is_some() || body.get("agent").is_some() {
+            body
+        } else {
+            serde_json::json!({ "model": body })
+        };
+        // 原样提交 GET /config 返回的脱敏 API Key 时保留原值
+        let current = pool.model_config();
+        let masked_key = mask_api_key(&current.api_key);
+        if let Some(model) = patch.get_mut("model").and_then(|m| m.as_object_mut()) {
+            if model.get("api_key").and_then(|k| k.as_str()) == Some(masked_key.as_str()) {
+                model.remove("api_key");
+            }
+            // 备用端点整体替换，脱敏的 API Key 换回原值
+            if let Some(current_fallback) = &current.fallback
+                && let Some(fallback) = model.get_mut("fallback").and_then(|f| f.as_object_mut())
+                && fallback.get("api_key").and_then(|k| k.as_str()) == Some(mask_api_key(&current_fallback.api_key).as_str())
+            {
+                fallback.insert("api_key".to_string(), current_fallback.api_key.clone().into());
+            }
+        }
+        if let Some(secret) = pool.agent_config().callback_secret
+            && let Some(agent) = patch.get_mut("agent").and_then(|a| a.as_object_mut())
+            && agent.get("callback_secret").and_then(|s| s.as_str()) == Some(mask_api_key(&secret).as_str())
+        {
+            agent.remove("callback_secret");
+        }
+        match pool.apply_config_patch(&patch) {
+            Ok(version) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("配置已更新，版本: {}", version),
+                    data: Some(Self::effective_config(&pool)),
+                })
+            ),
+            Err(e) => Self::api_error(StatusCode::BAD_REQUEST, format!("更新配置失败: {}", e)),
+        }
+    }
+
+    /// 获取设备生效的模型配置
+    async fn get_device_model(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+    ) -> (StatusCode, Json<ApiResponse<ModelConfig>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        match pool.device_model_config(&serial).await {
+            Ok(model) => Self::device_model_response(&serial, model),
+            Err(e) => Self::api_error(StatusCode::NOT_FOUND, format!("获取设备模型配置失败: {}", e)),
+        }
+    }
+
+    /// 设置设备级模型配置覆盖，请求体只需包含要覆盖的字段（如 `{"model_name": "glm-4v-flash"}`）
+    async fn set_device_model(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+        Json(mut model_override): Json<serde_json::Value>,
+    ) -> (StatusCode, Json<ApiResponse<ModelConfig>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        if pool.get_device_info(&serial).await.is_none()
+            && let Err(e) = pool.register_device(serial.clone(), None).await
+        {
+            return Self::api_error(StatusCode::BAD_REQUEST, format!("注册设备失败: {}", e));
+        }
+        // 原样提交 GET 返回的脱敏 API Key 时不覆盖
+        if let Some(model) = model_override.as_object_mut()
+            && model.get("api_key").and_then(|k| k.as_str()).is_some_and(|k| k.ends_with("****"))
+        {
+            model.remove("api_key");
+        }
+        match pool.set_model_override(&serial, Some(model_override)).await {
+            Ok(model) => Self::device_model_response(&serial, model),
+            Err(e) => Self::api_error(StatusCode::BAD_REQUEST, format!("设置设备模型配置失败: {}", e)),
+        }
+    }
+
+    /// 清除设备级模型配置覆盖，恢复使用全局配置
+    async fn clear_device_model(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+    ) -> (StatusCode, Json<ApiResponse<ModelConfig>>) {
+        let Some(pool) = ctx.get_device_pool().read().await.clone() else {
+            return Self::api_error(StatusCode::SERVICE_UNAVAILABLE, "设备池未初始化".to_string());
+        };
+        match pool.set_model_override(&serial, None).await {
+            Ok(model) => Self::device_model_response(&serial, model),
+            Err(e) => Self::api_error(StatusCode::NOT_FOUND, format!("清除设备模型配置失败: {}", e)),
+        }
+    }
+
+    fn device_model_response(serial: &str, mut model: ModelConfig) -> (StatusCode, Json<ApiResponse<ModelConfig>>) {
+        mask_model_config(&mut model);
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("设备 {} 使用模型: {}", serial, model.model_name),
+                data: Some(model),
+            })
+        )
+    }
+
+    fn effective_config(pool: &crate::agent::DevicePool) -> EffectiveConfig {
+        let mut model = pool.model_config();
+        mask_model_config(&mut model);
+        let mut agent = pool.agent_config();
+        agent.callback_secret = agent.callback_secret.as_deref().map(mask_api_key);
+        EffectiveConfig {
+            version: pool.config_version(),
+            model,
+            agent,
+        }
+    }
+}
+
+/// 模型配置脱敏（包括备用端点的 API Key）
+/// 像素坐标超出屏幕时返回错误说明
+fn pixel_out_of_screen(points: &[(u32, u32)], (width, height): (u32, u32)) -> Option<String> {
+    points
+        .iter()
+        .find(|&&(x, y)| x >= width || y >= height)
+        .map(|(x, y)| format!("坐标 ({}, {}) 超出屏幕 {}x{}", x, y, width, height))
+}
+
+fn mask_model_config(model: &mut ModelConfig) {
+    model.api_key = mask_api_key(&model.api_key);
+    if let Some(fallback) = model.fallback.as_mut() {
+        fallback.api_key = mask_api_key(&fallback.api_key);
+    }
+}
+
+/// API Key 脱敏，只保留前 4 个字符
+fn mask_api_key(api_key: &str) -> String {
+    if api_key.is_empty() {
+        return String::new();
+    }
+    format!("{}****", api_key.chars().take(4).collect::<String>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_batch_format() {
+        let actions: Vec<ActionEnum> = serde_json::from_str(
+            r#"[{"Tap": {"x": 540, "y": 1200}}, {"Type": {"text": "咖啡"}}, {"Wait": {"duration_ms": 1000}}]"#,
+        )
+        .unwrap();
+        assert_eq!(actions.len(), 3);
+        assert!(actions.iter().all(|action| action.validate().is_ok()));
+
+        let outcome = ActionOutcome {
+            index: 1,
+            action: actions[1].action_type(),
+            result: ActionResult::success("ok".to_string(), 12),
+        };
+        let value = serde_json::to_value(&outcome).unwrap();
+        assert_eq!(value["index"], 1);
+        assert_eq!(value["action"], "type");
+        assert_eq!(value["success"], true);
+        assert_eq!(value["duration_ms"], 12);
+    }
+
+    #[test]
+    fn test_single_action_requests() {
+        let tap: TapRequest = serde_json::from_str(r#"{"x": 540, "y": 1200, "coords": "pixel"}"#).unwrap();
+        assert_eq!(tap.coords, CoordinateSpace::Pixel);
+        assert_eq!((tap.x, tap.y), (540, 1200));
+
+        assert_eq!(pixel_out_of_screen(&[(tap.x, tap.y)], (1080, 2400)), None);
+        assert!(pixel_out_of_screen(&[(0, 0), (1080, 1200)], (1080, 2400)).unwrap().contains("超出屏幕"));
+
+        let swipe: SwipeRequest = serde_json::from_str(r#"{"start_x": 500, "start_y": 800, "end_x": 500, "end_y": 200}"#).unwrap();
+        assert_eq!((swipe.coords, swipe.duration_ms), (CoordinateSpace::Logical, 300));
+
+        let key: KeyRequest = serde_json::from_str(r#"{"key": "back"}"#).unwrap();
+        assert!(matches!(key.key, KeyInput::Name(ref name) if name == "back"));
+        assert_eq!(key.repeat, 1);
+        let key: KeyRequest = serde_json::from_str(r#"{"key": 66, "repeat": 2}"#).unwrap();
+        assert!(matches!(key.key, KeyInput::Code(66)));
+    }
+}
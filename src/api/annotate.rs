@@ -0,0 +1,263 @@
+//! 截图标注接口
+//!
+//! 网页端纠正 Agent 时，操作员在截图上框出元素，服务端在当前截图上画出带编号的框并返回，
+//! 纠正说明中可以直接引用"框 2"。坐标默认使用 Agent 操作的 1000x1000 逻辑坐标，也可以使用截图像素坐标。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use base64::prelude::*;
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use super::api::{ApiResponse, ApiServer};
+use crate::context::context::IContext;
+use crate::scrcpy::macro_recorder::capture_screenshot;
+use crate::scrcpy::overlay::{to_logical, LOGICAL_SCALE};
+
+/// 单次请求最多标注的区域数
+const MAX_REGIONS: usize = 50;
+/// 未指定颜色时依次使用的颜色
+const PALETTE: [[u8; 3]; 6] = [
+    [0xff, 0x3b, 0x30],
+    [0x34, 0xc7, 0x59],
+    [0x00, 0x7a, 0xff],
+    [0xff, 0x95, 0x00],
+    [0xaf, 0x52, 0xde],
+    [0x5a, 0xc8, 0xfa],
+];
+/// 3x5 点阵数字，每行 3 位
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// 坐标系
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoordinateSpace {
+    /// 1000x1000 逻辑坐标（与 Agent 操作一致）
+    #[default]
+    Logical,
+    /// 截图像素坐标
+    Pixel,
+}
+
+/// 待标注的区域（左上角与右下角）
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnnotateRegion {
+    pub x1: u32,
+    pub y1: u32,
+    pub x2: u32,
+    pub y2: u32,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// 颜色（如 `#ff0000`），未指定时按顺序选取
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// 标注请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnnotateRequest {
+    pub regions: Vec<AnnotateRegion>,
+    #[serde(default)]
+    pub coords: CoordinateSpace,
+}
+
+/// 已标注的区域
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnnotatedRegion {
+    /// 框上绘制的编号（从 1 开始）
+    pub index: usize,
+    pub label: Option<String>,
+    /// 截图像素坐标 `[x1, y1, x2, y2]`
+    pub bounds: [u32; 4],
+    /// 中心点的逻辑坐标，可直接用于 Agent 操作
+    pub center: (u32, u32),
+}
+
+/// 标注结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnotatedScreenshot {
+    /// base64 编码的 PNG
+    pub image: String,
+    pub width: u32,
+    pub height: u32,
+    pub regions: Vec<AnnotatedRegion>,
+}
+
+/// 解析 `#rrggbb` 颜色
+fn parse_color(value: &str) -> Option<Rgba<u8>> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Rgba([channel(0)?, channel(2)?, channel(4)?, 0xff]))
+}
+
+fn fill_rect(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// 在左上角绘制编号标签
+fn draw_badge(image: &mut RgbaImage, x: u32, y: u32, number: usize, color: Rgba<u8>, scale: u32) {
+    let digits: Vec<usize> = number.to_string().bytes().map(|b| (b - b'0') as usize).collect();
+    let padding = scale;
+    let width = digits.len() as u32 * 4 * scale - scale + padding * 2;
+    let height = 5 * scale + padding * 2;
+    // 框贴近顶部时把标签放到框内
+    let y = y.saturating_sub(height).max(if y < height { y } else { 0 });
+    fill_rect(image, x, y, width, height, color);
+
+    let white = Rgba([0xff, 0xff, 0xff, 0xff]);
+    for (i, digit) in digits.iter().enumerate() {
+        let origin_x = x + padding + i as u32 * 4 * scale;
+        for (row, bits) in DIGITS[*digit].iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) != 0 {
+                    fill_rect(image, origin_x + col * scale, y + padding + row as u32 * scale, scale, scale, white);
+                }
+            }
+        }
+    }
+}
+
+/// 在截图上绘制带编号的框
+pub fn annotate(png: &[u8], request: &AnnotateRequest) -> Result<AnnotatedScreenshot, String> {
+    let mut image = image::load_from_memory(png)
+        .map_err(|e| format!("解析截图失败: {}", e))?
+        .to_rgba8();
+    let (width, height) = image.dimensions();
+    let to_pixel = |value: u32, size: u32| match request.coords {
+        CoordinateSpace::Logical => (value.min(LOGICAL_SCALE) as u64 * size as u64 / LOGICAL_SCALE as u64) as u32,
+        CoordinateSpace::Pixel => value,
+    }
+    .min(size.saturating_sub(1));
+    let thickness = (width / 270).max(2);
+    let scale = (width / 180).max(2);
+
+    let mut regions = Vec::with_capacity(request.regions.len());
+    for (i, region) in request.regions.iter().enumerate() {
+        let color = region
+            .color
+            .as_deref()
+            .and_then(parse_color)
+            .unwrap_or_else(|| {
+                let [r, g, b] = PALETTE[i % PALETTE.len()];
+                Rgba([r, g, b, 0xff])
+            });
+        let (x1, x2) = (to_pixel(region.x1.min(region.x2), width), to_pixel(region.x1.max(region.x2), width));
+        let (y1, y2) = (to_pixel(region.y1.min(region.y2), height), to_pixel(region.y1.max(region.y2), height));
+        let box_width = x2 - x1 + 1;
+        let box_height = y2 - y1 + 1;
+
+        fill_rect(&mut image, x1, y1, box_width, thickness, color);
+        fill_rect(&mut image, x1, (y2 + 1).saturating_sub(thickness), box_width, thickness, color);
+        fill_rect(&mut image, x1, y1, thickness, box_height, color);
+        fill_rect(&mut image, (x2 + 1).saturating_sub(thickness), y1, thickness, box_height, color);
+        draw_badge(&mut image, x1, y1, i + 1, color, scale);
+
+        let center_x = (x1 + x2) / 2;
+        let center_y = (y1 + y2) / 2;
+        regions.push(AnnotatedRegion {
+            index: i + 1,
+            label: region.label.clone(),
+            bounds: [x1, y1, x2, y2],
+            center: (to_logical(center_x, width), to_logical(center_y, height)),
+        });
+    }
+
+    let mut output = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut output, image::ImageFormat::Png)
+        .map_err(|e| format!("编码截图失败: {}", e))?;
+    Ok(AnnotatedScreenshot {
+        image: BASE64_STANDARD.encode(output.into_inner()),
+        width,
+        height,
+        regions,
+    })
+}
+
+impl ApiServer {
+    /// 截取当前屏幕并绘制标注框
+    pub(super) async fn annotate_screenshot(
+        State(_ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+        Json(req): Json<AnnotateRequest>,
+    ) -> (StatusCode, Json<ApiResponse<AnnotatedScreenshot>>) {
+        if req.regions.is_empty() || req.regions.len() > MAX_REGIONS {
+            return Self::api_error(StatusCode::BAD_REQUEST, format!("标注区域数量须为 1~{}", MAX_REGIONS));
+        }
+        let screenshot = match capture_screenshot(&serial).await.and_then(|data| {
+            BASE64_STANDARD.decode(data).map_err(|e| format!("解码截图失败: {}", e))
+        }) {
+            Ok(screenshot) => screenshot,
+            Err(e) => return Self::api_error(StatusCode::BAD_GATEWAY, format!("设备 {} 截图失败: {}", serial, e)),
+        };
+
+        match tokio::task::spawn_blocking(move || annotate(&screenshot, &req)).await {
+            Ok(Ok(annotated)) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("已标注 {} 个区域", annotated.regions.len()),
+                    data: Some(annotated),
+                })
+            ),
+            Ok(Err(e)) => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+            Err(e) => Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("标注任务失败: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate_regions() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::DynamicImage::ImageRgba8(RgbaImage::from_pixel(540, 1200, Rgba([0, 0, 0, 0xff])))
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+
+        let request: AnnotateRequest = serde_json::from_value(serde_json::json!({
+            "regions": [
+                {"x1": 100, "y1": 500, "x2": 300, "y2": 600, "label": "搜索框", "color": "#00ff00"},
+                {"x1": 0, "y1": 0, "x2": 999, "y2": 999}
+            ]
+        }))
+        .unwrap();
+        let annotated = annotate(png.get_ref(), &request).unwrap();
+        assert_eq!((annotated.width, annotated.height), (540, 1200));
+        assert_eq!(annotated.regions[0].bounds, [54, 600, 162, 720]);
+        assert_eq!(annotated.regions[0].center, (200, 550));
+
+        let image = image::load_from_memory(&BASE64_STANDARD.decode(&annotated.image).unwrap()).unwrap().to_rgba8();
+        // 框线使用指定颜色，框内保持原样
+        assert_eq!(image.get_pixel(100, 720), &Rgba([0, 0xff, 0, 0xff]));
+        assert_eq!(image.get_pixel(100, 660), &Rgba([0, 0, 0, 0xff]));
+
+        assert_eq!(parse_color("#ff3b30"), Some(Rgba([0xff, 0x3b, 0x30, 0xff])));
+        assert_eq!(parse_color("red"), None);
+    }
+}
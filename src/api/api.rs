@@ -1,24 +1,48 @@
 use std::sync::Arc;
-use std::net::TcpListener;
 use axum::{
-    extract::{State, Path},
+    extract::{State, Path, OriginalUri, Query},
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::{get, post},
     Json, Router,
     body::Body,
+    extract::Request,
 };
+use tower::ServiceExt;
 use serde::{Deserialize, Serialize};
 use tracing::{info, debug, warn};
 use rust_embed::RustEmbed;
 use crate::context::context::{IContext};
-use crate::scrcpy::scrcpy::ScrcpyConnect;
+use crate::scrcpy::scrcpy::{ScrcpyConnect, allocate_local_port, socket_io_path};
+use crate::scrcpy::control_auth::ControlToken;
+use crate::scrcpy::stats::SessionStatsSnapshot;
+use crate::scrcpy::session_state::SessionStateSnapshot;
+use crate::scrcpy::device_info::{self, DeviceMetadata};
+use crate::scrcpy::macro_recorder::{MacroAction, MacroStore};
+use crate::storage::{storage_monitor, StorageUsage};
+
+/// 磁盘用量查询参数
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct StorageQuery {
+    /// 立即重新统计（并按配额清理），否则返回最近一次定期检查的结果
+    pub refresh: bool,
+}
 
 /// 设备信息结构
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeviceInfo {
     pub serial: String,
     pub status: String,
+    /// 型号、Android 版本、ABI 等设备元数据（设备在线时读取）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<DeviceMetadata>,
+    /// 投屏会话状态（设备已连接时返回）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionStateSnapshot>,
+    /// 设备池连接设备时探测的设备能力
+    #[cfg(feature = "agent")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<crate::agent::executor::DeviceCapabilities>,
 }
 
 /// 设备列表响应
@@ -39,6 +63,36 @@ pub struct ConnectDeviceRequest {
 pub struct ConnectResponse {
     pub serial: String,
     pub socketio_port: u16,
+    /// Socket.IO 请求路径（包含路径前缀，单端口模式下为 `{base}/scrcpy/{serial}/socket.io`）
+    pub socketio_path: String,
+    /// 控制令牌，客户端通过 `scrcpy_ctl_auth` 事件提交后才能发送 `scrcpy_ctl` 控制事件
+    pub control: ControlToken,
+}
+
+impl ConnectResponse {
+    fn new(serial: &str, connect: &ScrcpyConnect) -> Self {
+        Self {
+            serial: serial.to_string(),
+            socketio_port: connect.get_port(),
+            socketio_path: socket_io_path(serial),
+            control: connect.issue_control_token(),
+        }
+    }
+}
+
+/// 开始录制宏请求
+#[derive(Debug, Deserialize)]
+pub struct StartMacroRecordingRequest {
+    pub name: String,
+}
+
+/// 宏录制结果
+#[derive(Debug, Serialize)]
+pub struct MacroSummary {
+    pub name: String,
+    pub duration_ms: u64,
+    pub event_count: usize,
+    pub actions: Vec<MacroAction>,
 }
 
 /// API 响应
@@ -60,52 +114,64 @@ pub struct ApiServer {
 
 impl ApiServer {
     pub fn new(ctx: Arc<dyn IContext + Sync + Send>) -> Self {
-        let app = Router::new()
-            .route("/devices", get(Self::get_devices))
-            .route("/connect", post(Self::connect_device))
-            .route("/disconnect", post(Self::disconnect_device))
-            .route("/device/{serial}/status", get(Self::get_device_status))
-            .route("/hello", get(Self::hello))
-            .route("/web/{*path}", get(Self::serve_web_file))
-            .with_state(ctx);
+        let app = Self::routes().with_state(ctx);
         ApiServer { app }
     }
 
+    /// 将所有路由挂载到路径前缀下（如反向代理的 `/scrs`），前缀为空时不变
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        if !base_path.is_empty() {
+            self.app = Router::new().nest(base_path, self.app);
+        }
+        self
+    }
+
     /// 启动 API 服务器
-    pub async fn run(self) {
-        let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")
+    pub async fn run(self, addr: &str) {
+        let listener = tokio::net::TcpListener::bind(addr)
             .await
-            .expect("Failed to bind to 0.0.0.0:3000");
-        println!("Server running on http://0.0.0.0:3000");
-        
+            .unwrap_or_else(|e| panic!("Failed to bind to {}: {}", addr, e));
+        println!("Server running on http://{}", addr);
+
         if let Err(e) = axum::serve(listener, self.app).await {
             eprintln!("Server error: {:?}", e);
         }
     }
 
     /// 获取设备列表
-    async fn get_devices(
+    pub(super) async fn get_devices(
         State(ctx): State<Arc<dyn IContext + Sync + Send>>,
     ) -> Json<DevicesResponse> {
         debug!("收到获取设备列表请求");
         
         // 通过 ADBServer 获取当前连接的设备
-        let mut adb_server = ctx.get_adb_server().write().await;
-        let adb_devices: Result<Vec<adb_client::server::DeviceShort>, adb_client::RustADBError> = adb_server.devices();
+        let adb_devices = crate::adb_server::list_devices(ctx.get_adb_server()).await;
 
-        let devices: Vec<DeviceInfo> = match adb_devices {
+        let mut devices: Vec<DeviceInfo> = match adb_devices {
             Ok(devs) => devs.iter().map(|device: &adb_client::server::DeviceShort| {
                 info!("ADB 设备: {} - 状态: {}", device.identifier, device.state);
                 DeviceInfo {
                     serial: device.identifier.clone(),
                     status: device.state.to_string(),
+                    metadata: None,
+                    session: None,
+                    #[cfg(feature = "agent")]
+                    capabilities: None,
                 }
             }).collect(),
             Err(e) => {
                 warn!("获取设备列表失败: {:?}", e);
                 vec![]
             }
-        }; 
+        };
+
+        // 在线设备附带元数据，首次读取后缓存
+        for device in devices.iter_mut().filter(|device| device.status == "device") {
+            match device_info::metadata(&device.serial).await {
+                Ok(metadata) => device.metadata = Some(metadata),
+                Err(e) => debug!("读取设备 {} 元数据失败: {}", device.serial, e),
+            }
+        }
 
         let count = devices.len();
         info!("获取设备列表成功，共 {} 个设备", count);
@@ -116,12 +182,33 @@ impl ApiServer {
     }
 
     /// 连接设备
-    async fn connect_device(
+    pub(super) async fn connect_device(
         State(ctx): State<Arc<dyn IContext + Sync + Send>>,
         Json(req): Json<ConnectDeviceRequest>,
     ) -> (StatusCode, Json<ApiResponse<ConnectResponse>>) {
         debug!("收到连接设备请求: {}", req.serial);
 
+        // 启用设备池时由设备池管理会话，REST、投屏与 Agent 共用同一个 ScrcpyConnect
+        #[cfg(feature = "agent")]
+        if let Some(pool) = ctx.get_device_pool().read().await.clone() {
+            return match pool.start_stream(&req.serial).await {
+                Ok(connect) => {
+                    info!("设备 {} 连接成功，Socket.IO 端口: {}", req.serial, connect.get_port());
+                    (
+                        StatusCode::OK,
+                        Json(ApiResponse {
+                            success: true,
+                            message: format!("设备 {} 连接成功", req.serial),
+                            data: Some(ConnectResponse::new(&req.serial, &connect)),
+                        })
+                    )
+                }
+                Err(e @ crate::error::AppError::AdbError(_)) => Self::api_error(StatusCode::BAD_GATEWAY, e.to_string()),
+                Err(e) => Self::api_error(StatusCode::SERVICE_UNAVAILABLE, format!("连接设备 {} 失败: {}", req.serial, e)),
+            };
+        }
+
+        // 未启用设备池（仅投屏模式）时由 Context 管理连接
         // 优先检查设备是否已连接
         {
             let scrcpy_read = ctx.get_scrcpy().read().await;
@@ -133,10 +220,7 @@ impl ApiServer {
                         Json(ApiResponse {
                             success: true,
                             message: format!("设备 {} 已连接", req.serial),
-                            data: Some(ConnectResponse {
-                                serial: req.serial.clone(),
-                                socketio_port: connect.get_port(),
-                            }),
+                            data: Some(ConnectResponse::new(&req.serial, connect)),
                         })
                     );
                 }
@@ -144,28 +228,46 @@ impl ApiServer {
         }
         // 释放读锁
 
+        // adb 调用在阻塞线程池中执行，不持有 scrcpy 锁
+        let device = match crate::adb_server::fetch_device(ctx.get_adb_server(), &req.serial).await {
+            Ok(device) => device,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_GATEWAY,
+                    Json(ApiResponse {
+                        success: false,
+                        message: format!("无法通过 adb 获取设备 {}: {:?}", req.serial, e),
+                        data: None,
+                    })
+                );
+            }
+        };
+
         let mut scrcpy = ctx.get_scrcpy().write().await;
-        let mut adb = ctx.get_adb_server().write().await;
-        let device = adb.get_device_by_name(&req.serial).unwrap();
-
-         // 动态分配可用端口
-        let listener = TcpListener::bind("127.0.0.1:0")
-            .expect("Failed to bind to an available port");
-        let scrcpy_server_port = listener.local_addr()
-            .expect("Failed to get local address")
-            .port();
-        drop(listener);
+        // 等待 adb 期间其它请求可能已连接同一设备
+        if let Some(connect) = scrcpy.get_device_connect(&req.serial) {
+            return (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("设备 {} 已连接", req.serial),
+                    data: Some(ConnectResponse::new(&req.serial, connect)),
+                })
+            );
+        }
+
+        // 动态分配可用端口
+        let scrcpy_server_port = allocate_local_port();
         // 创建 ScrcpyConnect（会自动分配 socket.io 端口）
         let connect = Arc::new(ScrcpyConnect::new(scrcpy_server_port));
         let socket_io_port = connect.get_port();
 
         info!("设备 {} Socket.IO 端口: {}", req.serial, socket_io_port);
 
-        // 启动 scrcpy 连接（scrcpy_server_port 会在 run 内部自动分配）
-        let connect_clone = Arc::clone(&connect);
-        tokio::spawn(async move {
-            connect_clone.run(Arc::new(device)).await;
-        });
+        // 启动投屏服务
+        connect.start(Arc::new(device));
+
+        let response = ConnectResponse::new(&req.serial, &connect);
 
         // 添加设备到管理列表
         scrcpy.add_device(req.serial.clone(), connect);
@@ -176,23 +278,38 @@ impl ApiServer {
             Json(ApiResponse {
                 success: true,
                 message: format!("设备 {} 连接成功", req.serial),
-                data: Some(ConnectResponse {
-                    serial: req.serial.clone(),
-                    socketio_port: socket_io_port,
-                }),
+                data: Some(response),
             })
         )
     }
 
     /// 断开设备连接
-    async fn disconnect_device(
+    pub(super) async fn disconnect_device(
         State(ctx): State<Arc<dyn IContext + Sync + Send>>,
         Json(req): Json<ConnectDeviceRequest>,
     ) -> (StatusCode, Json<ApiResponse<String>>) {
         debug!("收到断开设备请求: {}", req.serial);
-        let mut scrcpy = ctx.get_scrcpy().write().await;
-        
-        if !scrcpy.is_device_connected(&req.serial) {
+
+        #[cfg(feature = "agent")]
+        if let Some(pool) = ctx.get_device_pool().read().await.clone()
+            && pool.get_scrcpy_connect(&req.serial).await.is_some()
+        {
+            if let Err(e) = pool.disconnect_device(&req.serial).await {
+                return Self::api_error(StatusCode::BAD_REQUEST, format!("断开设备 {} 失败: {}", req.serial, e));
+            }
+            info!("设备 {} 断开连接成功", req.serial);
+            return (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("设备 {} 断开连接成功", req.serial),
+                    data: Some(req.serial),
+                })
+            );
+        }
+
+        // 先从管理列表移除再停止，停止期间不持有锁
+        let Some(connect) = ctx.get_scrcpy().write().await.remove_device(&req.serial) else {
             warn!("设备 {} 未连接", req.serial);
             return (
                 StatusCode::BAD_REQUEST,
@@ -202,9 +319,9 @@ impl ApiServer {
                     data: None,
                 })
             );
-        }
+        };
 
-        scrcpy.remove_device(&req.serial);
+        connect.stop().await;
         info!("设备 {} 断开连接成功", req.serial);
         
         (
@@ -217,16 +334,80 @@ impl ApiServer {
         )
     }
 
+    /// 重启设备的投屏服务（关闭 Socket.IO 服务与设备上的 scrcpy-server 后重新启动，客户端需重新连接）
+    pub(super) async fn restart_device_stream(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+    ) -> (StatusCode, Json<ApiResponse<ConnectResponse>>) {
+        debug!("收到重启投屏服务请求: {}", serial);
+
+        #[cfg(feature = "agent")]
+        if let Some(pool) = ctx.get_device_pool().read().await.clone() {
+            return match pool.restart_stream(&serial).await {
+                Ok(connect) => (
+                    StatusCode::OK,
+                    Json(ApiResponse {
+                        success: true,
+                        message: format!("设备 {} 投屏服务已重启", serial),
+                        data: Some(ConnectResponse::new(&serial, &connect)),
+                    })
+                ),
+                Err(e @ crate::error::AppError::AdbError(_)) => Self::api_error(StatusCode::BAD_GATEWAY, e.to_string()),
+                Err(e) => Self::api_error(StatusCode::BAD_REQUEST, format!("重启设备 {} 投屏服务失败: {}", serial, e)),
+            };
+        }
+
+        let Some(connect) = ctx.get_scrcpy().read().await.get_device_connect(&serial).cloned() else {
+            return Self::api_error(StatusCode::BAD_REQUEST, format!("设备 {} 未连接", serial));
+        };
+        let device = match crate::adb_server::fetch_device(ctx.get_adb_server(), &serial).await {
+            Ok(device) => device,
+            Err(e) => return Self::api_error(StatusCode::BAD_GATEWAY, format!("无法通过 adb 获取设备 {}: {:?}", serial, e)),
+        };
+        connect.restart(Arc::new(device)).await;
+        info!("设备 {} 投屏服务已重启", serial);
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("设备 {} 投屏服务已重启", serial),
+                data: Some(ConnectResponse::new(&serial, &connect)),
+            })
+        )
+    }
+
     /// 获取设备状态
-    async fn get_device_status(
+    pub(super) async fn get_device_status(
         State(ctx): State<Arc<dyn IContext + Sync + Send>>,
         axum::extract::Path(serial): axum::extract::Path<String>,
     ) -> (StatusCode, Json<ApiResponse<DeviceInfo>>) {
         debug!("收到获取设备状态请求: {}", serial);
+
+        // 设备池中的设备返回设备池记录的状态
+        #[cfg(feature = "agent")]
+        if let Some(pool) = ctx.get_device_pool().read().await.clone()
+            && let Some(info) = pool.get_device_info(&serial).await
+        {
+            return (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: "获取设备状态成功".to_string(),
+                    data: Some(DeviceInfo {
+                        status: info.status.code().to_string(),
+                        metadata: info.metadata.or_else(|| device_info::cached(&serial)),
+                        session: info.session,
+                        capabilities: info.capabilities,
+                        serial,
+                    }),
+                })
+            );
+        }
+
         let scrcpy = ctx.get_scrcpy().read().await;
         
         match scrcpy.get_device_connect(&serial) {
-            Some(_connect) => {
+            Some(connect) => {
                 info!("获取设备 {} 状态成功", serial);
                 (
                     StatusCode::OK,
@@ -236,6 +417,10 @@ impl ApiServer {
                         data: Some(DeviceInfo {
                             serial: serial.clone(),
                             status: "connected".to_string(),
+                            metadata: device_info::cached(&serial),
+                            session: Some(connect.session_state()),
+                            #[cfg(feature = "agent")]
+                            capabilities: None,
                         }),
                     })
                 )
@@ -254,14 +439,271 @@ impl ApiServer {
         }
     }
 
+    /// 查找设备的 ScrcpyConnect：启用设备池时查找设备池，否则查找 Context 管理的连接
+    async fn find_connect(
+        ctx: &Arc<dyn IContext + Sync + Send>,
+        serial: &str,
+    ) -> Option<Arc<ScrcpyConnect>> {
+        #[cfg(feature = "agent")]
+        if let Some(pool) = ctx.get_device_pool().read().await.as_ref() {
+            return pool.get_scrcpy_connect(serial).await;
+        }
+        ctx.get_scrcpy().read().await.get_device_connect(serial).cloned()
+    }
+
+    /// 单端口模式：将 `/scrcpy/{serial}/socket.io` 请求转发给设备会话的 Socket.IO 路由
+    pub(super) async fn forward_scrcpy_socket_io(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path((serial, _rest)): Path<(String, String)>,
+        req: Request,
+    ) -> Response {
+        let router = Self::find_connect(&ctx, &serial).await.and_then(|c| c.mounted_router());
+        match router {
+            Some(router) => match router.oneshot(req).await {
+                Ok(response) => response.into_response(),
+                Err(never) => match never {},
+            },
+            None => Self::api_error::<()>(
+                StatusCode::NOT_FOUND,
+                format!("设备 {} 没有挂载的 scrcpy 会话", serial),
+            ).into_response(),
+        }
+    }
+
+    /// 构造失败响应
+    pub(super) fn api_error<T>(status: StatusCode, message: String) -> (StatusCode, Json<ApiResponse<T>>) {
+        // 错误信息可能包含上游返回的请求内容，返回前隐藏密钥
+        let message = crate::secrets::redact(&message).into_owned();
+        warn!("{}", message);
+        (
+            status,
+            Json(ApiResponse {
+                success: false,
+                message,
+                data: None,
+            })
+        )
+    }
+
+    /// 获取设备 scrcpy 会话统计
+    pub(super) async fn get_session_stats(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+    ) -> (StatusCode, Json<ApiResponse<SessionStatsSnapshot>>) {
+        debug!("收到获取会话统计请求: {}", serial);
+
+        match Self::find_connect(&ctx, &serial).await {
+            Some(connect) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: "获取会话统计成功".to_string(),
+                    data: Some(connect.stats().snapshot()),
+                })
+            ),
+            None => {
+                warn!("设备 {} 没有 scrcpy 会话", serial);
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(ApiResponse {
+                        success: false,
+                        message: format!("设备 {} 没有 scrcpy 会话", serial),
+                        data: None,
+                    })
+                )
+            }
+        }
+    }
+
+    /// 列出获得控制权限的客户端
+    pub(super) async fn list_control_clients(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+    ) -> (StatusCode, Json<ApiResponse<Vec<String>>>) {
+        let Some(connect) = Self::find_connect(&ctx, &serial).await else {
+            return Self::api_error(StatusCode::NOT_FOUND, format!("设备 {} 没有 scrcpy 会话", serial));
+        };
+        let clients = connect.control_clients();
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("共 {} 个客户端有控制权限", clients.len()),
+                data: Some(clients),
+            })
+        )
+    }
+
+    /// 撤销客户端的控制权限，客户端仍可继续观看
+    pub(super) async fn revoke_control(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path((serial, client_id)): Path<(String, String)>,
+    ) -> (StatusCode, Json<ApiResponse<String>>) {
+        let Some(connect) = Self::find_connect(&ctx, &serial).await else {
+            return Self::api_error(StatusCode::NOT_FOUND, format!("设备 {} 没有 scrcpy 会话", serial));
+        };
+        if !connect.revoke_control(&client_id) {
+            return Self::api_error(StatusCode::NOT_FOUND, format!("客户端 {} 没有控制权限", client_id));
+        }
+        info!("设备 {} 撤销客户端 {} 的控制权限", serial, client_id);
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("已撤销客户端 {} 的控制权限", client_id),
+                data: Some(client_id),
+            })
+        )
+    }
+
+    /// 列出设备已保存的宏
+    pub(super) async fn list_macros(Path(serial): Path<String>) -> Json<ApiResponse<Vec<String>>> {
+        let names = MacroStore::for_device(&serial).list().await;
+        Json(ApiResponse {
+            success: true,
+            message: format!("共 {} 个宏", names.len()),
+            data: Some(names),
+        })
+    }
+
+    /// 开始录制宏
+    pub(super) async fn start_macro_recording(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+        Json(req): Json<StartMacroRecordingRequest>,
+    ) -> (StatusCode, Json<ApiResponse<String>>) {
+        let Some(connect) = Self::find_connect(&ctx, &serial).await else {
+            return Self::api_error(StatusCode::NOT_FOUND, format!("设备 {} 没有 scrcpy 会话", serial));
+        };
+
+        match connect.start_macro_recording(&serial, &req.name).await {
+            Ok(()) => {
+                info!("设备 {} 开始录制宏: {}", serial, req.name);
+                (
+                    StatusCode::OK,
+                    Json(ApiResponse {
+                        success: true,
+                        message: format!("开始录制宏: {}", req.name),
+                        data: Some(req.name),
+                    })
+                )
+            }
+            Err(e) => Self::api_error(StatusCode::BAD_REQUEST, e),
+        }
+    }
+
+    /// 停止录制宏并保存
+    pub(super) async fn stop_macro_recording(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path(serial): Path<String>,
+    ) -> (StatusCode, Json<ApiResponse<MacroSummary>>) {
+        let Some(connect) = Self::find_connect(&ctx, &serial).await else {
+            return Self::api_error(StatusCode::NOT_FOUND, format!("设备 {} 没有 scrcpy 会话", serial));
+        };
+
+        match connect.stop_macro_recording(&serial).await {
+            Ok(device_macro) => (
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    message: format!("宏 {} 已保存", device_macro.name),
+                    data: Some(MacroSummary {
+                        name: device_macro.name,
+                        duration_ms: device_macro.duration_ms,
+                        event_count: device_macro.events.len(),
+                        actions: device_macro.actions,
+                    }),
+                })
+            ),
+            Err(e) => Self::api_error(StatusCode::BAD_REQUEST, e),
+        }
+    }
+
+    /// 回放已保存的宏（后台执行，立即返回）
+    pub(super) async fn play_macro(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+        Path((serial, name)): Path<(String, String)>,
+    ) -> (StatusCode, Json<ApiResponse<String>>) {
+        let Some(connect) = Self::find_connect(&ctx, &serial).await else {
+            return Self::api_error(StatusCode::NOT_FOUND, format!("设备 {} 没有 scrcpy 会话", serial));
+        };
+
+        let serial_for_play = serial.clone();
+        let name_for_play = name.clone();
+        tokio::spawn(async move {
+            match connect.play_macro(&serial_for_play, &name_for_play).await {
+                Ok(count) => info!("设备 {} 宏 {} 回放完成，注入 {} 条消息", serial_for_play, name_for_play, count),
+                Err(e) => warn!("设备 {} 宏 {} 回放失败: {}", serial_for_play, name_for_play, e),
+            }
+        });
+
+        (
+            StatusCode::ACCEPTED,
+            Json(ApiResponse {
+                success: true,
+                message: format!("开始回放宏: {}", name),
+                data: Some(name),
+            })
+        )
+    }
+
     /// 测试端点
-    async fn hello() -> String {
+    /// 获取 adb server 状态
+    pub(super) async fn get_adb_status() -> Json<crate::adb_server::AdbServerStatus> {
+        Json(crate::adb_server::status().await)
+    }
+
+    /// 日志、步骤截图与任务产物目录的磁盘用量，如 `?refresh=true`
+    pub(super) async fn get_storage_usage(Query(query): Query<StorageQuery>) -> (StatusCode, Json<ApiResponse<StorageUsage>>) {
+        let usage = match storage_monitor().usage() {
+            Some(usage) if !query.refresh => usage,
+            _ => match tokio::task::spawn_blocking(|| storage_monitor().check()).await {
+                Ok(usage) => usage,
+                Err(e) => return Self::api_error(StatusCode::INTERNAL_SERVER_ERROR, format!("统计磁盘用量失败: {}", e)),
+            },
+        };
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: format!("共 {} 字节", usage.total_bytes),
+                data: Some(usage),
+            }),
+        )
+    }
+
+    /// 重启 adb server
+    ///
+    /// 重启后替换共享的 ADBServer 客户端，设备池收到重启事件后重新建立设备连接
+    pub(super) async fn restart_adb(
+        State(ctx): State<Arc<dyn IContext + Sync + Send>>,
+    ) -> (StatusCode, Json<ApiResponse<crate::adb_server::AdbServerStatus>>) {
+        info!("收到重启 adb server 请求");
+        if let Err(e) = crate::adb_server::restart().await {
+            warn!("重启 adb server 失败: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse { success: false, message: e, data: None }),
+            );
+        }
+        *ctx.get_adb_server().write().await = crate::adb_server::new_adb_server();
+        (
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: "adb server 已重启".to_string(),
+                data: Some(crate::adb_server::status().await),
+            }),
+        )
+    }
+
+    pub(super) async fn hello() -> String {
         "你好，欢迎使用 Axum Scrcpy API！".to_string()
     }
 
     /// 服务 Web 静态文件
     /// 支持 /web/* 路径访问 assets/root/ 下的所有文件
-    async fn serve_web_file(Path(path): Path<String>) -> impl IntoResponse {
+    pub(super) async fn serve_web_file(OriginalUri(uri): OriginalUri, Path(path): Path<String>) -> impl IntoResponse {
         // 处理根路径请求
         let file_path = if path.is_empty() || path == "/" {
             "index.html"
@@ -270,42 +712,64 @@ impl ApiServer {
             path.trim_start_matches('/')
         };
 
-        // 根据文件扩展名获取 MIME 类型
-        let mime_type = Self::get_mime_type(file_path);
-
-        // 从嵌入的文件中获取
-        match RootAssets::get(file_path) {
-            Some(content) => {
-                Response::builder()
-                    .header("Content-Type", mime_type)
-                    .body(Body::from(content.data.to_vec()))
-                    .unwrap()
+        // 从嵌入的文件中获取；如果请求的是目录，尝试添加 index.html
+        let found = match RootAssets::get(file_path) {
+            Some(content) => Some((file_path.to_string(), content)),
+            None if !file_path.contains('.') => {
+                let index_path = format!("{}/index.html", file_path);
+                RootAssets::get(&index_path).map(|content| (index_path, content))
             }
-            None => {
-                // 如果请求的是目录，尝试添加 index.html
-                if !file_path.contains('.') {
-                    match RootAssets::get(&format!("{}/index.html", file_path)) {
-                        Some(content) => {
-                            Response::builder()
-                                .header("Content-Type", "text/html")
-                                .body(Body::from(content.data.to_vec()))
-                                .unwrap()
-                        }
-                        None => {
-                            Response::builder()
-                                .status(StatusCode::NOT_FOUND)
-                                .body(Body::from("文件未找到"))
-                                .unwrap()
-                        }
-                    }
-                } else {
-                    Response::builder()
-                        .status(StatusCode::NOT_FOUND)
-                        .body(Body::from("文件未找到"))
-                        .unwrap()
-                }
+            None => None,
+        };
+        let Some((file_path, content)) = found else {
+            return Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::from("文件未找到"))
+                .unwrap();
+        };
+
+        // 根据文件扩展名获取 MIME 类型
+        let mime_type = Self::get_mime_type(&file_path);
+        let body = if mime_type == "text/html" {
+            // 反向代理下由请求路径推出路径前缀，改写页面中的绝对路径
+            let base_path = uri.path()
+                .strip_suffix(path.as_str())
+                .and_then(|p| p.trim_end_matches('/').strip_suffix("/web"))
+                .unwrap_or("");
+            let html = String::from_utf8_lossy(&content.data);
+            Body::from(Self::rewrite_html(&html, base_path))
+        } else {
+            Body::from(content.data.to_vec())
+        };
+        Response::builder()
+            .header("Content-Type", mime_type)
+            .body(body)
+            .unwrap()
+    }
+
+    /// 为页面注入路径前缀 `window.SCRS_BASE_PATH`，并为 `src="/..."`、`href="/..."` 加上前缀
+    fn rewrite_html(html: &str, base_path: &str) -> String {
+        let script = format!("<script>window.SCRS_BASE_PATH = {:?};</script>", base_path);
+        let html = match html.find("<head>") {
+            Some(i) => format!("{}{}{}", &html[..i + 6], script, &html[i + 6..]),
+            None => format!("{}{}", script, html),
+        };
+        if base_path.is_empty() {
+            return html;
+        }
+        let mut result = String::with_capacity(html.len());
+        let mut rest = html.as_str();
+        while let Some(i) = rest.find("=\"/") {
+            let (head, tail) = rest.split_at(i + 2);
+            result.push_str(head);
+            // 跳过协议相对地址 //cdn...
+            if !tail[1..].starts_with('/') {
+                result.push_str(base_path);
             }
+            rest = tail;
         }
+        result.push_str(rest);
+        result
     }
 
     /// 根据文件扩展名获取 MIME 类型
@@ -354,3 +818,19 @@ impl ApiServer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_html() {
+        let html = r#"<html><head><script src="https://cdn/x.js"></script></head><a href="/web/">x</a><img src="//cdn/a.png"></html>"#;
+        let rewritten = ApiServer::rewrite_html(html, "/scrs");
+        assert!(rewritten.contains(r#"<head><script>window.SCRS_BASE_PATH = "/scrs";</script>"#));
+        assert!(rewritten.contains(r#"href="/scrs/web/""#));
+        assert!(rewritten.contains(r#"src="//cdn/a.png""#));
+        assert!(rewritten.contains(r#"src="https://cdn/x.js""#));
+        assert!(ApiServer::rewrite_html(html, "").contains(r#"href="/web/""#));
+    }
+}
@@ -1 +1,7 @@
-pub mod api;
\ No newline at end of file
+pub mod api;
+mod annotate;
+mod routes;
+#[cfg(feature = "agent")]
+mod agent_routes;
+
+pub use routes::API_VERSION_PREFIX;
@@ -0,0 +1,127 @@
+//! 路由注册：REST 接口统一挂载在 `/v1` 版本前缀下，原有的无前缀路径作为兼容别名保留，
+//! 响应附带 `Deprecation` 与指向 `/v1` 路径的 `Link` 头，便于客户端迁移。
+//! 新增的接口只需加到 [`ApiServer::rest_routes`]（或 Agent 路由）中即可同时获得版本前缀。
+
+use std::sync::Arc;
+use axum::{
+    extract::{OriginalUri, Request},
+    http::{HeaderName, HeaderValue},
+    middleware::{self, Next},
+    response::Response,
+    routing::{any, delete, get, post},
+    Router,
+};
+use crate::context::context::IContext;
+use super::api::ApiServer;
+
+pub use crate::config::API_VERSION_PREFIX;
+
+/// 兼容别名的下线说明
+const LEGACY_ROUTE_NOTICE: &str = "无版本前缀的接口路径已弃用，请改用 /v1 前缀";
+
+type AppRouter = Router<Arc<dyn IContext + Sync + Send>>;
+
+impl ApiServer {
+    /// 全部路由：`/v1` 下的 REST 接口、无前缀的兼容别名，以及不随版本变化的网页、投屏转发与健康检查
+    pub(super) fn routes() -> AppRouter {
+        let rest = Self::rest_routes();
+        Router::new()
+            .nest(API_VERSION_PREFIX, rest.clone())
+            .merge(rest.layer(middleware::from_fn(deprecate_legacy_route)))
+            .route("/hello", get(Self::hello))
+            .route("/web/{*path}", get(Self::serve_web_file))
+            .route("/scrcpy/{serial}/{*rest}", any(Self::forward_scrcpy_socket_io))
+    }
+
+    /// REST 接口（相对版本前缀的路径）
+    fn rest_routes() -> AppRouter {
+        let app = Router::new()
+            .route("/devices", get(Self::get_devices))
+            .route("/connect", post(Self::connect_device))
+            .route("/disconnect", post(Self::disconnect_device))
+            .route("/device/{serial}/status", get(Self::get_device_status))
+            .route("/device/{serial}/restart", post(Self::restart_device_stream))
+            .route("/device/{serial}/session/stats", get(Self::get_session_stats))
+            .route("/device/{serial}/control", get(Self::list_control_clients))
+            .route("/device/{serial}/control/{client_id}", delete(Self::revoke_control))
+            .route("/device/{serial}/macros", get(Self::list_macros))
+            .route("/device/{serial}/macro/record/start", post(Self::start_macro_recording))
+            .route("/device/{serial}/macro/record/stop", post(Self::stop_macro_recording))
+            .route("/device/{serial}/macro/{name}/play", post(Self::play_macro))
+            .route("/device/{serial}/annotate", post(Self::annotate_screenshot))
+            .route("/adb/status", get(Self::get_adb_status))
+            .route("/storage", get(Self::get_storage_usage))
+            .route("/adb/restart", post(Self::restart_adb));
+        #[cfg(feature = "agent")]
+        let app = Self::agent_routes(app);
+        app
+    }
+}
+
+/// 兼容别名的响应附带弃用信息（`Deprecation`、`Link: <...>; rel="successor-version"`、`Warning`）
+async fn deprecate_legacy_route(request: Request, next: Next) -> Response {
+    let successor = successor_path(&request);
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(HeaderName::from_static("deprecation"), HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor)) {
+        headers.insert(axum::http::header::LINK, link);
+    }
+    if let Ok(warning) = HeaderValue::from_str(&format!("299 - \"{}\"", LEGACY_ROUTE_NOTICE)) {
+        headers.insert(axum::http::header::WARNING, warning);
+    }
+    response
+}
+
+/// 兼容别名对应的 `/v1` 路径（保留路径前缀与查询参数）
+fn successor_path(request: &Request) -> String {
+    let path = request.uri().path();
+    let original = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.clone())
+        .unwrap_or_else(|| request.uri().clone());
+    let base = original.path().strip_suffix(path).unwrap_or("");
+    match original.query() {
+        Some(query) => format!("{}{}{}?{}", base, API_VERSION_PREFIX, path, query),
+        None => format!("{}{}{}", base, API_VERSION_PREFIX, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        let rest = Router::new().route("/devices", get(|| async { "ok" }));
+        let app = Router::new()
+            .nest(API_VERSION_PREFIX, rest.clone())
+            .merge(rest.layer(middleware::from_fn(deprecate_legacy_route)));
+        Router::new().nest("/scrs", app)
+    }
+
+    #[test]
+    fn test_routes_register_without_conflicts() {
+        let _ = ApiServer::routes();
+    }
+
+    #[tokio::test]
+    async fn test_legacy_route_is_deprecated() {
+        let response = router()
+            .oneshot(Request::get("/scrs/devices?all=1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(response.headers()["deprecation"], "true");
+        assert_eq!(response.headers()["link"], "</scrs/v1/devices?all=1>; rel=\"successor-version\"");
+
+        let response = router()
+            .oneshot(Request::get("/scrs/v1/devices").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert!(response.headers().get("deprecation").is_none());
+    }
+}
@@ -0,0 +1,145 @@
+//! 服务端客户端：REST API（端口 3000）与 Agent Socket.IO（端口 4000）
+//!
+//! Socket.IO 只实现了命令行需要的最小子集：Engine.IO v4 WebSocket 传输、
+//! 默认命名空间、事件收发与心跳响应。
+
+use anyhow::{anyhow, bail, Context, Result};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+/// 等待 Socket.IO 响应的超时时间
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// REST 接口版本前缀
+const API_PREFIX: &str = "/v1";
+
+/// REST API 客户端
+pub struct RestClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl RestClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    async fn parse(response: reqwest::Response) -> Result<Value> {
+        let status = response.status();
+        let body: Value = response.json().await.context("解析响应失败")?;
+        if !status.is_success() || body.get("success") == Some(&Value::Bool(false)) {
+            let message = body.get("message").and_then(|v| v.as_str()).unwrap_or_default();
+            bail!("请求失败 ({}): {}", status, message);
+        }
+        Ok(body)
+    }
+
+    pub async fn get(&self, path: &str) -> Result<Value> {
+        let url = format!("{}{}{}", self.base_url, API_PREFIX, path);
+        let response = self.http.get(&url).send().await.with_context(|| format!("无法访问 {}", url))?;
+        Self::parse(response).await
+    }
+
+    pub async fn post(&self, path: &str, body: &Value) -> Result<Value> {
+        let url = format!("{}{}{}", self.base_url, API_PREFIX, path);
+        let response = self.http.post(&url).json(body).send().await.with_context(|| format!("无法访问 {}", url))?;
+        Self::parse(response).await
+    }
+}
+
+/// Socket.IO 客户端
+pub struct SocketClient {
+    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl SocketClient {
+    /// 连接到 Agent Socket.IO 服务并加入默认命名空间
+    pub async fn connect(base_url: &str) -> Result<Self> {
+        let url = format!(
+            "{}/socket.io/?EIO=4&transport=websocket",
+            base_url.trim_end_matches('/').replacen("http", "ws", 1)
+        );
+        let (ws, _) = tokio_tungstenite::connect_async(&url)
+            .await
+            .with_context(|| format!("无法连接 {}", url))?;
+        let mut client = Self { ws };
+
+        // Engine.IO open 包，随后连接默认命名空间
+        let open = client.next_text().await?;
+        if !open.starts_with('0') {
+            bail!("意外的握手响应: {}", open);
+        }
+        client.send("40").await?;
+        loop {
+            let packet = client.next_text().await?;
+            if packet.starts_with("40") {
+                return Ok(client);
+            }
+            if let Some(error) = packet.strip_prefix("44") {
+                bail!("命名空间连接被拒绝: {}", error);
+            }
+        }
+    }
+
+    async fn send(&mut self, packet: &str) -> Result<()> {
+        self.ws.send(Message::text(packet)).await.context("发送失败")
+    }
+
+    /// 读取下一个文本包，自动响应心跳
+    async fn next_text(&mut self) -> Result<String> {
+        loop {
+            let message = self.ws.next().await.ok_or_else(|| anyhow!("连接已关闭"))??;
+            let Message::Text(text) = message else {
+                continue;
+            };
+            if text.as_str() == "2" {
+                self.send("3").await?;
+                continue;
+            }
+            return Ok(text.to_string());
+        }
+    }
+
+    /// 发送事件
+    pub async fn emit(&mut self, event: &str, data: Value) -> Result<()> {
+        self.send(&format!("42{}", json!([event, data]))).await
+    }
+
+    /// 等待指定事件，返回事件数据
+    pub async fn wait_event(&mut self, event: &str) -> Result<Value> {
+        tokio::time::timeout(RESPONSE_TIMEOUT, async {
+            loop {
+                let packet = self.next_text().await?;
+                let Some(payload) = packet.strip_prefix("42") else {
+                    continue;
+                };
+                let Ok(Value::Array(mut items)) = serde_json::from_str::<Value>(payload) else {
+                    continue;
+                };
+                if items.first().and_then(|v| v.as_str()) == Some(event) {
+                    return Ok(items.get_mut(1).map(Value::take).unwrap_or(Value::Null));
+                }
+            }
+        })
+        .await
+        .map_err(|_| anyhow!("等待 {} 超时", event))?
+    }
+
+    /// 发送请求事件并等待 `{event}/response`，响应中 success 为 false 时返回错误
+    pub async fn request(&mut self, event: &str, data: Value) -> Result<Value> {
+        self.emit(event, data).await?;
+        let response = self.wait_event(&format!("{}/response", event)).await?;
+        if response.get("success") == Some(&Value::Bool(false)) {
+            let error = response.get("error").and_then(|v| v.as_str()).unwrap_or("未知错误");
+            bail!("{} 失败: {}", event, error);
+        }
+        Ok(response)
+    }
+}
@@ -0,0 +1,365 @@
+//! scrs-cli：在终端中操作 scrcpy-rs 服务
+//!
+//! 通过现有的 REST API 与 Agent Socket.IO 接口列出设备、连接设备、启动任务、
+//! 跟踪执行进度、获取报告，以及按脚本批量执行上述命令。
+
+mod client;
+mod script;
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+use client::{RestClient, SocketClient};
+
+/// 跟踪进度时的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Parser)]
+#[command(name = "scrs-cli", version, about = "scrcpy-rs 服务命令行客户端")]
+struct Cli {
+    /// REST API 地址
+    #[arg(long, global = true, env = "SCRS_API_URL", default_value = "http://127.0.0.1:3000")]
+    api: String,
+
+    /// Agent Socket.IO 地址
+    #[arg(long, global = true, env = "SCRS_AGENT_URL", default_value = "http://127.0.0.1:4000")]
+    agent: String,
+
+    /// 以 JSON 输出原始响应
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// 列出 ADB 设备及其 Agent 状态
+    Devices,
+    /// 连接设备（启动 scrcpy 会话）
+    Connect { serial: String },
+    /// 在设备上启动 Agent 任务
+    Start {
+        serial: String,
+        task: String,
+        /// 记录任务期间的网络流量
+        #[arg(long)]
+        capture_traffic: bool,
+        /// 用设备自带的 screenrecord 录制任务过程
+        #[arg(long)]
+        record_screen: bool,
+        /// 任务完成后校验该应用确实在前台运行过
+        #[arg(long)]
+        expected_app: Option<String>,
+        /// 目标应用最少前台时长（秒）
+        #[arg(long)]
+        min_foreground_secs: Option<u64>,
+        /// 任务的目标应用，离开时提醒模型
+        #[arg(long)]
+        target_app: Option<String>,
+        /// 离开目标应用时自动重新启动
+        #[arg(long, requires = "target_app")]
+        relaunch_target_app: bool,
+        /// 加入 A/B 实验
+        #[arg(long)]
+        experiment: Option<String>,
+        /// 启用输入拟人化（默认选项）
+        #[arg(long)]
+        humanize: bool,
+        /// 本任务使用的模型（须在服务端允许列表中）
+        #[arg(long)]
+        model: Option<String>,
+        /// 本任务的采样温度
+        #[arg(long)]
+        temperature: Option<f32>,
+        /// 本任务的最大步数
+        #[arg(long)]
+        max_steps: Option<usize>,
+        /// 本任务的最长执行时间（秒）
+        #[arg(long)]
+        max_time: Option<u64>,
+        /// 本任务是否使用三阶段模式（true/false）
+        #[arg(long)]
+        three_stage: Option<bool>,
+        /// 启动后持续输出进度直到任务结束
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// 持续输出设备上当前任务的进度，直到任务结束
+    Tail { serial: String },
+    /// 查看设备上当前（或最近一次）任务的状态
+    Status { serial: String },
+    /// 停止设备上的 Agent
+    Stop { serial: String },
+    /// 获取报告
+    Report {
+        #[command(subcommand)]
+        kind: ReportKind,
+    },
+    /// 按脚本依次执行命令（每行一条子命令，# 开头为注释）
+    Run { script: PathBuf },
+}
+
+#[derive(Debug, Subcommand)]
+enum ReportKind {
+    /// 基准测试报告
+    Bench { id: String },
+    /// A/B 实验结果
+    Experiment { id: String },
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = execute(&cli, &cli.command).await {
+        eprintln!("错误: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+/// 执行一条子命令
+async fn execute(cli: &Cli, command: &Command) -> Result<()> {
+    let rest = RestClient::new(&cli.api);
+    match command {
+        Command::Devices => {
+            let adb = rest.get("/devices").await?;
+            let mut socket = SocketClient::connect(&cli.agent).await?;
+            let pool = socket.request("agent/devices", json!({})).await?;
+            if cli.json {
+                return print_json(&json!({ "adb": adb, "pool": pool }));
+            }
+            print_devices(&adb, &pool);
+        }
+        Command::Connect { serial } => {
+            let response = rest.post("/connect", &json!({ "serial": serial })).await?;
+            if cli.json {
+                return print_json(&response);
+            }
+            println!("{}", response["message"].as_str().unwrap_or("已连接"));
+        }
+        Command::Start {
+            serial,
+            task,
+            capture_traffic,
+            record_screen,
+            expected_app,
+            min_foreground_secs,
+            target_app,
+            relaunch_target_app,
+            experiment,
+            humanize,
+            model,
+            temperature,
+            max_steps,
+            max_time,
+            three_stage,
+            follow,
+        } => {
+            let mut socket = SocketClient::connect(&cli.agent).await?;
+            let response = socket
+                .request(
+                    "agent/start",
+                    json!({
+                        "device_serial": serial,
+                        "task": task,
+                        "capture_traffic": capture_traffic,
+                        "record_screen": record_screen,
+                        "expected_app": expected_app,
+                        "min_foreground_secs": min_foreground_secs,
+                        "target_app": target_app,
+                        "relaunch_target_app": relaunch_target_app,
+                        "experiment_id": experiment,
+                        "humanize": humanize,
+                        "model_name": model,
+                        "temperature": temperature,
+                        "max_steps": max_steps,
+                        "max_execution_time": max_time,
+                        "three_stage": three_stage,
+                    }),
+                )
+                .await?;
+            if cli.json {
+                print_json(&response)?;
+            } else {
+                println!("任务已启动: {} (Agent {})", task, response["agent_id"].as_str().unwrap_or("-"));
+                if let Some(variant) = response["variant"].as_str() {
+                    println!("实验变体: {}", variant);
+                }
+            }
+            if *follow {
+                tail(&mut socket, serial, cli.json).await?;
+            }
+        }
+        Command::Tail { serial } => {
+            let mut socket = SocketClient::connect(&cli.agent).await?;
+            tail(&mut socket, serial, cli.json).await?;
+        }
+        Command::Status { serial } => {
+            let mut socket = SocketClient::connect(&cli.agent).await?;
+            let response = socket.request("agent/status", json!({ "device_serial": serial })).await?;
+            if cli.json {
+                return print_json(&response);
+            }
+            println!("{}", describe_status(&response["status"]));
+            print_stats(&response["stats"]);
+        }
+        Command::Stop { serial } => {
+            let mut socket = SocketClient::connect(&cli.agent).await?;
+            socket.request("agent/stop", json!({ "device_serial": serial })).await?;
+            println!("已停止 {}", serial);
+        }
+        Command::Report { kind } => {
+            let response = match kind {
+                ReportKind::Bench { id } => rest.get(&format!("/bench/{}", id)).await?,
+                ReportKind::Experiment { id } => rest.get(&format!("/experiments/{}/results", id)).await?,
+            };
+            if cli.json {
+                return print_json(&response["data"]);
+            }
+            println!("{}", response["message"].as_str().unwrap_or_default());
+            let data = &response["data"];
+            print_variant_table(data.get("summary").or_else(|| data.get("variants")).unwrap_or(&Value::Null));
+        }
+        Command::Run { script } => {
+            let content = std::fs::read_to_string(script).with_context(|| format!("读取脚本 {:?} 失败", script))?;
+            for (line_no, args) in script::parse(&content)? {
+                println!("> {}", args.join(" "));
+                let line = Cli::try_parse_from(std::iter::once("scrs-cli".to_string()).chain(args))
+                    .with_context(|| format!("脚本第 {} 行无效", line_no))?;
+                if matches!(line.command, Command::Run { .. }) {
+                    bail!("脚本第 {} 行: 不支持嵌套执行脚本", line_no);
+                }
+                Box::pin(execute(cli, &line.command))
+                    .await
+                    .with_context(|| format!("脚本第 {} 行执行失败", line_no))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 轮询任务状态并输出新的执行步骤，任务结束时返回；任务失败时返回错误
+async fn tail(socket: &mut SocketClient, serial: &str, json_output: bool) -> Result<()> {
+    let mut last_step = 0u64;
+    loop {
+        let response = socket
+            .request("agent/status", json!({ "device_serial": serial, "since_step": last_step }))
+            .await?;
+
+        for step in response["steps"].as_array().into_iter().flatten() {
+            last_step = last_step.max(step["step_number"].as_u64().unwrap_or(last_step));
+            if json_output {
+                println!("{}", step);
+            } else {
+                let mark = if step["success"].as_bool().unwrap_or(false) { "✓" } else { "✗" };
+                println!(
+                    "[{}] {} {} - {}",
+                    step["step_number"],
+                    mark,
+                    step["action_description"].as_str().unwrap_or_default(),
+                    step["message"].as_str().unwrap_or_default()
+                );
+            }
+        }
+
+        let status = &response["status"];
+        if status.get("Running").is_none() && status.get("Paused").is_none() {
+            if json_output {
+                println!("{}", response);
+            } else {
+                println!("{}", describe_status(status));
+                if let Some(result) = response["result"].as_str() {
+                    println!("结果: {}", result);
+                }
+                print_stats(&response["stats"]);
+            }
+            if let Some(failed) = status.get("Failed") {
+                bail!("任务失败: {}", failed["error"].as_str().unwrap_or_default());
+            }
+            return Ok(());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn print_json(value: &Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// AgentStatus 的文字描述
+fn describe_status(status: &Value) -> String {
+    if let Some(running) = status.get("Running") {
+        return format!("运行中: {} (第 {} 步)", running["task"].as_str().unwrap_or_default(), running["step"]);
+    }
+    if let Some(paused) = status.get("Paused") {
+        return format!("已暂停: {} (第 {} 步)", paused["task"].as_str().unwrap_or_default(), paused["step"]);
+    }
+    if let Some(completed) = status.get("Completed") {
+        let mut text = format!("已完成: {}", completed["task"].as_str().unwrap_or_default());
+        if let Some(evaluation) = completed.get("evaluation").filter(|e| !e.is_null()) {
+            text.push_str(&format!(
+                "\n评估: {} (评分 {}) {}",
+                if evaluation["success"].as_bool().unwrap_or(false) { "成功" } else { "失败" },
+                evaluation["score"],
+                evaluation["rationale"].as_str().unwrap_or_default()
+            ));
+        }
+        return text;
+    }
+    if let Some(failed) = status.get("Failed") {
+        return format!("失败: {}", failed["error"].as_str().unwrap_or_default());
+    }
+    "空闲".to_string()
+}
+
+fn print_stats(stats: &Value) {
+    if stats.is_object() {
+        println!(
+            "步数: {}  耗时: {:.1}s  tokens: {}",
+            stats["steps"],
+            stats["duration_ms"].as_f64().unwrap_or_default() / 1000.0,
+            stats["tokens_used"]
+        );
+    }
+}
+
+fn print_devices(adb: &Value, pool: &Value) {
+    let pool_devices = pool["devices"].as_array().cloned().unwrap_or_default();
+    println!("{:<24} {:<12} {:<12} AGENT", "SERIAL", "ADB", "POOL");
+    for device in adb["devices"].as_array().into_iter().flatten() {
+        let serial = device["serial"].as_str().unwrap_or_default();
+        let pooled = pool_devices.iter().find(|d| d["serial"].as_str() == Some(serial));
+        let pool_status = pooled.map(|d| d["status"].to_string().trim_matches('"').to_string());
+        let has_agent = pooled.and_then(|d| d["has_agent"].as_bool()).unwrap_or(false);
+        println!(
+            "{:<24} {:<12} {:<12} {}",
+            serial,
+            device["status"].as_str().unwrap_or_default(),
+            pool_status.as_deref().unwrap_or("-"),
+            if has_agent { "yes" } else { "-" }
+        );
+    }
+}
+
+/// 输出按变体汇总的结果表
+fn print_variant_table(variants: &Value) {
+    let Some(variants) = variants.as_array() else {
+        return;
+    };
+    println!("{:<16} {:>6} {:>8} {:>8} {:>10} {:>10}", "VARIANT", "RUNS", "SUCCESS", "STEPS", "TIME(s)", "TOKENS");
+    for v in variants {
+        println!(
+            "{:<16} {:>6} {:>7.1}% {:>8.1} {:>10.1} {:>10.0}",
+            v["variant"].as_str().unwrap_or_default(),
+            v["tasks"],
+            v["success_rate"].as_f64().unwrap_or_default() * 100.0,
+            v["avg_steps"].as_f64().unwrap_or_default(),
+            v["avg_duration_ms"].as_f64().unwrap_or_default() / 1000.0,
+            v["avg_tokens"].as_f64().unwrap_or_default(),
+        );
+    }
+}
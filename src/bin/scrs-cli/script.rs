@@ -0,0 +1,88 @@
+//! 脚本解析
+//!
+//! 脚本每行是一条子命令（不含程序名），参数按 shell 规则以空白分隔，
+//! 支持单引号、双引号与反斜杠转义；空行和 `#` 开头的行会被忽略。
+
+use anyhow::{bail, Result};
+
+/// 解析脚本，返回 (行号, 参数列表)
+pub fn parse(content: &str) -> Result<Vec<(usize, Vec<String>)>> {
+    let mut commands = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match split_args(line) {
+            Some(args) => commands.push((index + 1, args)),
+            None => bail!("脚本第 {} 行引号不匹配: {}", index + 1, line),
+        }
+    }
+    Ok(commands)
+}
+
+/// 按 shell 规则拆分参数，引号不匹配时返回 None
+fn split_args(line: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                current.push(chars.next()?);
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+        if quote.is_none() && !current.is_empty() {
+            in_arg = true;
+        }
+    }
+
+    if quote.is_some() {
+        return None;
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Some(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_script() {
+        let script = r#"
+# 每日冒烟测试
+connect emulator-5554
+start emulator-5554 "打开设置并进入 WLAN 页面" --follow
+start emulator-5554 '搜索 "天气"' --humanize
+"#;
+        let commands = parse(script).unwrap();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0], (3, vec!["connect".to_string(), "emulator-5554".to_string()]));
+        assert_eq!(commands[1].1[2], "打开设置并进入 WLAN 页面");
+        assert_eq!(commands[2].1[2], "搜索 \"天气\"");
+        assert_eq!(split_args(r#"start a "" b\ c"#).unwrap(), vec!["start", "a", "", "b c"]);
+        assert!(parse("start \"未闭合").is_err());
+    }
+}
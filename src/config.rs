@@ -0,0 +1,271 @@
+//! 服务配置
+//!
+//! 从 `SCRS_CONFIG` 指定的 TOML 文件加载（未指定时读取当前目录下的 `scrs.toml`，
+//! 文件不存在则使用默认值），环境变量可覆盖单项配置。
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 默认配置文件
+const DEFAULT_CONFIG_FILE: &str = "scrs.toml";
+/// 当前 REST 接口版本前缀（Agent 结果回调中的接口链接也使用它，不依赖 `streaming` 特性）
+pub const API_VERSION_PREFIX: &str = "/v1";
+
+/// 服务配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// 是否启动 Agent Socket.IO 服务；关闭后不创建设备池，也无需配置模型 API Key
+    /// （环境变量 `SCRS_AGENT_SERVER` 覆盖）
+    pub agent_server: bool,
+    /// adb 可执行文件路径，未配置时在 Android SDK 目录与 PATH 中查找
+    /// （环境变量 `SCRS_ADB_PATH` 覆盖）
+    pub adb_path: Option<String>,
+    /// 全局 adb server 地址（`host:port`），如另一台机器或设备农场上以 `adb -a` 启动的 server，
+    /// 未配置时使用本机 server（环境变量 `SCRS_ADB_SERVER` 覆盖）
+    pub adb_server: Option<String>,
+    /// 按设备指定的 adb server 地址（`[adb_servers]` 段，设备序列号 = `host:port`），优先于全局配置
+    pub adb_servers: std::collections::HashMap<String, String>,
+    /// HTTP API 监听地址（环境变量 `SCRS_API_ADDR` 覆盖）
+    pub api_addr: String,
+    /// Agent Socket.IO 监听地址（环境变量 `SCRS_AGENT_ADDR` 覆盖）
+    pub agent_addr: String,
+    /// 设备投屏 Socket.IO 的监听地址，需要远程访问时设置为 `0.0.0.0`
+    /// （环境变量 `SCRS_SCRCPY_HOST` 覆盖）
+    pub scrcpy_host: String,
+    /// 设备投屏 Socket.IO 的端口范围（含两端），未设置时随机分配
+    /// （环境变量 `SCRS_SCRCPY_PORTS`，格式 `5000-5100`）
+    pub scrcpy_port_range: Option<(u16, u16)>,
+    /// 单端口模式：Agent 与设备投屏的 Socket.IO 都挂载到 API 服务上，只监听 `api_addr`
+    /// （环境变量 `SCRS_SINGLE_PORT` 覆盖）
+    pub single_port: bool,
+    /// 反向代理下的路径前缀（如 `/scrs`），作用于所有路由、Socket.IO 路径与内嵌网页
+    /// （环境变量 `SCRS_BASE_PATH` 覆盖）
+    pub base_path: String,
+    /// 日志级别与请求/响应内容开关（`[logging]` 段）
+    pub logging: LoggingConfig,
+    /// 磁盘用量监控与配额（`[storage]` 段）
+    pub storage: crate::storage::StorageConfig,
+}
+
+/// 日志配置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// 本服务日志的默认级别，未设置时为 `info`，交互模式下为 `warn`（环境变量 `SCRS_LOG_LEVEL` 覆盖）
+    pub level: Option<String>,
+    /// 按子系统覆盖日志级别（`[logging.modules]` 段），如 `"agent::llm" = "warn"`、`scrcpy = "debug"`
+    pub modules: std::collections::BTreeMap<String, String>,
+    /// 是否记录模型请求的消息内容（不含图片数据）
+    pub llm_request_bodies: bool,
+    /// 是否记录模型的完整响应
+    pub llm_response_bodies: bool,
+    /// 是否记录请求中附带的截图数量与大小
+    pub llm_screenshots: bool,
+}
+
+impl LoggingConfig {
+    /// tracing 过滤指令：本服务默认级别、各子系统级别与 axum
+    pub fn filter_directives(&self, repl_mode: bool) -> Vec<String> {
+        let default_level = if repl_mode { "warn" } else { "info" };
+        let mut directives = vec![format!("scrcpy_rs={}", self.level.as_deref().unwrap_or(default_level))];
+        directives.extend(self.modules.iter().map(|(module, level)| {
+            let module = module.trim_start_matches("scrcpy_rs::");
+            format!("scrcpy_rs::{}={}", module, level)
+        }));
+        directives.push("axum=info".to_string());
+        directives
+    }
+
+    /// 模型请求/响应内容的记录开关
+    pub fn body_logging(&self) -> crate::logger::BodyLogging {
+        crate::logger::BodyLogging {
+            llm_requests: self.llm_request_bodies,
+            llm_responses: self.llm_response_bodies,
+            llm_screenshots: self.llm_screenshots,
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            agent_server: cfg!(feature = "agent"),
+            adb_path: None,
+            adb_server: None,
+            adb_servers: Default::default(),
+            api_addr: "0.0.0.0:3000".to_string(),
+            agent_addr: "0.0.0.0:4000".to_string(),
+            scrcpy_host: "127.0.0.1".to_string(),
+            scrcpy_port_range: None,
+            single_port: false,
+            base_path: String::new(),
+            logging: LoggingConfig::default(),
+            storage: Default::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// 使用的配置文件：`SCRS_CONFIG` 指定的文件，或存在时的 `scrs.toml`
+    pub fn config_path() -> Option<PathBuf> {
+        match std::env::var("SCRS_CONFIG") {
+            Ok(path) => Some(PathBuf::from(path)),
+            Err(_) => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()),
+        }
+    }
+
+    /// 加载配置文件并应用环境变量覆盖
+    pub fn load() -> Result<Self, String> {
+        let mut config = match Self::config_path() {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply_env(|key| std::env::var(key).ok())?;
+        Ok(config)
+    }
+
+    /// 从 TOML 文件加载
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("读取配置文件 {:?} 失败: {}", path, e))?;
+        toml::from_str(&content).map_err(|e| format!("解析配置文件 {:?} 失败: {}", path, e))
+    }
+
+    /// 使用环境变量覆盖配置
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        if let Some(value) = var("SCRS_AGENT_SERVER") {
+            self.agent_server = parse_bool(&value)
+                .ok_or_else(|| format!("SCRS_AGENT_SERVER 的值无效: {}", value))?;
+        }
+        if let Some(value) = var("SCRS_ADB_PATH") {
+            self.adb_path = Some(value);
+        }
+        if let Some(value) = var("SCRS_ADB_SERVER") {
+            self.adb_server = Some(value);
+        }
+        if let Some(value) = var("SCRS_API_ADDR") {
+            self.api_addr = value;
+        }
+        if let Some(value) = var("SCRS_AGENT_ADDR") {
+            self.agent_addr = value;
+        }
+        if let Some(value) = var("SCRS_SCRCPY_HOST") {
+            self.scrcpy_host = value;
+        }
+        if let Some(value) = var("SCRS_SCRCPY_PORTS") {
+            self.scrcpy_port_range = Some(parse_port_range(&value)
+                .ok_or_else(|| format!("SCRS_SCRCPY_PORTS 的值无效: {}", value))?);
+        }
+        if let Some(value) = var("SCRS_SINGLE_PORT") {
+            self.single_port = parse_bool(&value)
+                .ok_or_else(|| format!("SCRS_SINGLE_PORT 的值无效: {}", value))?;
+        }
+        if let Some(value) = var("SCRS_BASE_PATH") {
+            self.base_path = value;
+        }
+        if let Some(value) = var("SCRS_LOG_LEVEL") {
+            self.logging.level = Some(value);
+        }
+        Ok(())
+    }
+
+    /// 规范化后的路径前缀：以 `/` 开头、不以 `/` 结尾，未配置时为空字符串
+    pub fn normalized_base_path(&self) -> String {
+        let path = self.base_path.trim().trim_matches('/');
+        if path.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", path)
+        }
+    }
+
+    /// 应用全局与按设备配置的 adb server 地址
+    pub fn apply_adb_servers(&self) -> Result<(), String> {
+        let default = self.adb_server.as_deref().map(crate::adb_server::AdbServerAddr::parse).transpose()?;
+        crate::adb_server::set_default_server(default);
+        for (serial, addr) in &self.adb_servers {
+            crate::adb_server::set_device_server(serial, Some(crate::adb_server::AdbServerAddr::parse(addr)?));
+        }
+        Ok(())
+    }
+
+    /// API 服务端口（单端口模式下设备投屏 Socket.IO 也使用此端口）
+    pub fn api_port(&self) -> Option<u16> {
+        self.api_addr.rsplit(':').next()?.parse().ok()
+    }
+}
+
+/// 解析 `起始-结束` 格式的端口范围
+fn parse_port_range(value: &str) -> Option<(u16, u16)> {
+    let (start, end) = value.split_once('-')?;
+    let range = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+    (range.0 <= range.1).then_some(range)
+}
+
+/// 解析布尔型环境变量
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_config_env_override() {
+        let mut config: ServerConfig = toml::from_str("agent_server = true").unwrap();
+        assert!(config.agent_server);
+
+        config.apply_env(|key| (key == "SCRS_AGENT_SERVER").then(|| "off".to_string())).unwrap();
+        assert!(!config.agent_server);
+
+        config.apply_env(|key| (key == "SCRS_ADB_PATH").then(|| r"C:\Android\adb.exe".to_string())).unwrap();
+        assert_eq!(config.adb_path.as_deref(), Some(r"C:\Android\adb.exe"));
+
+        config.apply_env(|key| match key {
+            "SCRS_API_ADDR" => Some("127.0.0.1:8080".to_string()),
+            "SCRS_SCRCPY_PORTS" => Some("5000-5100".to_string()),
+            "SCRS_SINGLE_PORT" => Some("1".to_string()),
+            _ => None,
+        }).unwrap();
+        assert_eq!(config.api_port(), Some(8080));
+        assert_eq!(config.scrcpy_port_range, Some((5000, 5100)));
+        assert!(config.single_port);
+        assert_eq!(config.normalized_base_path(), "");
+        config.apply_env(|key| (key == "SCRS_BASE_PATH").then(|| "scrs/".to_string())).unwrap();
+        assert_eq!(config.normalized_base_path(), "/scrs");
+        assert_eq!(toml::from_str::<ServerConfig>("scrcpy_port_range = [6000, 6010]").unwrap().scrcpy_port_range, Some((6000, 6010)));
+        let remote: ServerConfig = toml::from_str("adb_server = \"10.0.0.5:5037\"\n[adb_servers]\nfarm-1 = \"10.0.0.6\"").unwrap();
+        assert_eq!(remote.adb_server.as_deref(), Some("10.0.0.5:5037"));
+        assert_eq!(remote.adb_servers.get("farm-1").map(String::as_str), Some("10.0.0.6"));
+
+        assert!(config.apply_env(|_| Some("maybe".to_string())).is_err());
+        assert!(config.apply_env(|key| (key == "SCRS_SCRCPY_PORTS").then(|| "5100-5000".to_string())).is_err());
+        assert_eq!(toml::from_str::<ServerConfig>("").unwrap(), ServerConfig::default());
+    }
+
+    #[test]
+    fn test_logging_config() {
+        let config: ServerConfig = toml::from_str(
+            "[logging]\nllm_response_bodies = true\n[logging.modules]\n\"agent::llm\" = \"warn\"\n\"scrcpy_rs::scrcpy\" = \"debug\"",
+        )
+        .unwrap();
+        assert_eq!(
+            config.logging.filter_directives(false),
+            ["scrcpy_rs=info", "scrcpy_rs::agent::llm=warn", "scrcpy_rs::scrcpy=debug", "axum=info"]
+        );
+        assert_eq!(config.logging.filter_directives(true)[0], "scrcpy_rs=warn");
+        let body = config.logging.body_logging();
+        assert!(body.llm_responses && !body.llm_requests && !body.llm_screenshots);
+
+        let mut config = config;
+        config.apply_env(|key| (key == "SCRS_LOG_LEVEL").then(|| "debug".to_string())).unwrap();
+        assert_eq!(config.logging.filter_directives(true)[0], "scrcpy_rs=debug");
+    }
+}
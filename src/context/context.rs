@@ -3,7 +3,9 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use crate::scrcpy::scrcpy::ScrcpyConnect;
+#[cfg(feature = "agent")]
 use crate::agent::core::agent_group::AgentGroup;
+#[cfg(feature = "agent")]
 use crate::agent::pool::DevicePool;
 
 /// Scrcpy 服务器，负责管理设备连接和屏幕镜像
@@ -29,9 +31,9 @@ impl ScrcpyServer {
         self.devices.insert(serial, connect);
     }
 
-    /// 从管理列表中移除设备
-    pub fn remove_device(&mut self, serial: &str) {
-        self.devices.remove(serial);
+    /// 从管理列表中移除设备，返回移除的连接（调用方负责停止）
+    pub fn remove_device(&mut self, serial: &str) -> Option<Arc<ScrcpyConnect>> {
+        self.devices.remove(serial)
     }
 
     /// 获取设备连接实例
@@ -51,7 +53,9 @@ impl Default for ScrcpyServer {
 pub trait IContext: Send + Sync {
     fn get_scrcpy(&self) -> &RwLock<ScrcpyServer>;
     fn get_adb_server(&self) -> &Arc<RwLock<ADBServer>>;
+    #[cfg(feature = "agent")]
     fn get_agent_group(&self) -> &RwLock<Option<Arc<AgentGroup>>>;
+    #[cfg(feature = "agent")]
     fn get_device_pool(&self) -> &RwLock<Option<Arc<DevicePool>>>;
 }
 
@@ -59,7 +63,9 @@ pub trait IContext: Send + Sync {
 pub struct Context {
     scrcpy: RwLock<ScrcpyServer>,
     adb_server: Arc<RwLock<ADBServer>>,
+    #[cfg(feature = "agent")]
     agent_group: RwLock<Option<Arc<AgentGroup>>>,
+    #[cfg(feature = "agent")]
     device_pool: RwLock<Option<Arc<DevicePool>>>,
 }
 
@@ -68,18 +74,22 @@ impl Context {
     pub fn new() -> Self {
         Context {
             scrcpy: RwLock::new(ScrcpyServer::new()),
-            adb_server: Arc::new(RwLock::new(ADBServer::default())),
+            adb_server: Arc::new(RwLock::new(crate::adb_server::new_adb_server())),
+            #[cfg(feature = "agent")]
             agent_group: RwLock::new(None),
+            #[cfg(feature = "agent")]
             device_pool: RwLock::new(None),
         }
     }
 
     /// 设置 Agent 组
+    #[cfg(feature = "agent")]
     pub async fn set_agent_group(&self, group: Arc<AgentGroup>) {
         *self.agent_group.write().await = Some(group);
     }
 
     /// 设置设备池
+    #[cfg(feature = "agent")]
     pub async fn set_device_pool(&self, pool: Arc<DevicePool>) {
         *self.device_pool.write().await = Some(pool);
     }
@@ -94,10 +104,12 @@ impl IContext for Context {
         &self.adb_server
     }
 
+    #[cfg(feature = "agent")]
     fn get_agent_group(&self) -> &RwLock<Option<Arc<AgentGroup>>> {
         &self.agent_group
     }
 
+    #[cfg(feature = "agent")]
     fn get_device_pool(&self) -> &RwLock<Option<Arc<DevicePool>>> {
         &self.device_pool
     }
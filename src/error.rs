@@ -24,6 +24,10 @@ pub enum AppError {
     #[error("Scrcpy 错误: {0}")]
     ScrcpyError(String),
 
+    /// 模拟器错误
+    #[error("模拟器错误: {0}")]
+    EmulatorError(String),
+
     /// IO 错误
     #[error("IO 错误: {0}")]
     IoError(#[from] std::io::Error),
@@ -37,14 +41,17 @@ pub enum AppError {
     Unknown(String),
 
     /// Agent 错误
+    #[cfg(feature = "agent")]
     #[error("Agent 错误: {0}")]
     AgentError(#[from] crate::agent::core::traits::AgentError),
 
     /// Model 错误
+    #[cfg(feature = "agent")]
     #[error("Model 错误: {0}")]
     ModelError(#[from] crate::agent::core::traits::ModelError),
 
     /// Action 错误
+    #[cfg(feature = "agent")]
     #[error("Action 错误: {0}")]
     ActionError(#[from] crate::agent::core::traits::ActionError),
 }
@@ -0,0 +1,74 @@
+//! scrcpy-rs：Android 设备投屏与手机自动化
+//!
+//! 除了作为独立服务运行（`scrcpy-rs` 二进制），也可以作为库嵌入其他 Rust 项目，
+//! 按需启用以下特性：
+//!
+//! - `scrcpy`：scrcpy-server 会话（视频流、控制消息、宏录制）
+//! - `streaming`：HTTP 服务与共享 Context，依赖 `scrcpy`
+//! - `llm`：模型客户端依赖
+//! - `agent`：手机自动化 Agent（设备抽象、操作、设备池、Agent Socket.IO 服务），依赖 `scrcpy` 与 `llm`
+//! - `cli`：命令行客户端 `scrs-cli`
+//!
+//! 默认启用全部特性。只需要手机自动化时：
+//!
+//! ```toml
+//! scrcpy-rs = { version = "0.1", default-features = false, features = ["agent"] }
+//! ```
+//!
+//! ```ignore
+//! use scrcpy_rs::{Agent, DevicePool, DevicePoolConfig, AgentConfig, ModelConfig};
+//!
+//! let pool = DevicePool::new(DevicePoolConfig::default(), adb_server, model_config, AgentConfig::default());
+//! pool.register_device("emulator-5554".to_string(), None).await?;
+//! let agent = pool.get_agent("emulator-5554").await?;
+//! agent.start("打开设置".to_string()).await?;
+//! ```
+
+pub mod adb_server;
+pub mod config;
+pub mod error;
+pub mod logger;
+pub mod platform;
+pub mod secrets;
+pub mod storage;
+
+#[cfg(feature = "scrcpy")]
+pub mod scrcpy;
+
+#[cfg(feature = "agent")]
+pub mod agent;
+
+#[cfg(feature = "streaming")]
+pub mod api;
+#[cfg(feature = "streaming")]
+pub mod context;
+
+pub use config::ServerConfig;
+pub use error::AppError;
+
+/// 字节序列的十六进制小写表示（摘要、签名等）
+#[cfg(feature = "scrcpy")]
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    use std::fmt::Write as _;
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut out, b| {
+        let _ = write!(out, "{:02x}", b);
+        out
+    })
+}
+
+#[cfg(feature = "scrcpy")]
+pub use scrcpy::scrcpy::ScrcpyConnect;
+#[cfg(feature = "scrcpy")]
+pub use scrcpy::options::ScrcpyOptions;
+
+#[cfg(feature = "agent")]
+pub use agent::{
+    Action, ActionEnum, ActionHandler, Agent, AgentConfig, AgentGroup, Device, DevicePool,
+    DevicePoolConfig, DevicePoolEvent, DeviceStatus, ModelClient, ModelConfig, PhoneAgent,
+    ScrcpyDeviceWrapper, AgentSocketServer, create_model_client,
+};
+
+#[cfg(feature = "streaming")]
+pub use api::api::ApiServer;
+#[cfg(feature = "streaming")]
+pub use context::{Context, IContext};
@@ -1,9 +1,36 @@
 use std::{
     fs::OpenOptions,
     io::Write,
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
 
+/// 请求/响应内容的记录开关（`[logging]` 段），默认都不记录
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BodyLogging {
+    /// 记录模型请求的消息内容（不含图片数据）
+    pub llm_requests: bool,
+    /// 记录模型的完整响应
+    pub llm_responses: bool,
+    /// 记录请求中附带的截图数量与大小
+    pub llm_screenshots: bool,
+}
+
+static BODY_LOGGING: RwLock<BodyLogging> = RwLock::new(BodyLogging {
+    llm_requests: false,
+    llm_responses: false,
+    llm_screenshots: false,
+});
+
+/// 设置请求/响应内容的记录开关
+pub fn set_body_logging(body_logging: BodyLogging) {
+    *BODY_LOGGING.write().unwrap() = body_logging;
+}
+
+/// 当前的请求/响应内容记录开关
+pub fn body_logging() -> BodyLogging {
+    *BODY_LOGGING.read().unwrap()
+}
+
 /// 设备日志记录器
 #[derive(Clone)]
 pub struct DeviceLogger {
@@ -18,7 +45,10 @@ impl DeviceLogger {
         // 创建 logs 目录（如果不存在）
         std::fs::create_dir_all("logs").expect("无法创建 logs 目录");
 
-        let log_path = format!("logs/ws_{}.log", device_serial);
+        let log_path = std::path::Path::new("logs")
+            .join(format!("ws_{}.log", crate::platform::sanitize_file_name(device_serial)))
+            .to_string_lossy()
+            .to_string();
 
         DeviceLogger {
             device_serial: device_serial.to_string(),
@@ -44,7 +74,7 @@ impl DeviceLogger {
 
         if let Some(ref mut file) = *file_guard {
             let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
-            let log_line = format!("{} [{}] {}\n", timestamp, self.device_serial, message);
+            let log_line = format!("{} [{}] {}\n", timestamp, self.device_serial, crate::secrets::redact(message));
 
             if let Err(e) = file.write_all(log_line.as_bytes()) {
                 eprintln!("写入日志文件失败: {:?}", e);
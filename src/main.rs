@@ -1,50 +1,200 @@
-mod api;
-mod context;
-mod error;
-mod scrcpy;
-mod logger;
-mod agent;
+#[cfg(feature = "agent")]
+mod repl;
 
 use std::sync::Arc;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use tracing_subscriber::{EnvFilter, fmt};
 
-use context::context::{Context, IContext};
-use agent::{
+use scrcpy_rs::{Context, IContext, ApiServer, ServerConfig};
+use scrcpy_rs::scrcpy::scrcpy::SocketIoListen;
+#[cfg(feature = "agent")]
+use scrcpy_rs::{
     DevicePool, DevicePoolConfig,
     AgentConfig, ModelConfig, AgentSocketServer,
 };
+#[cfg(feature = "agent")]
+use scrcpy_rs::agent::core::artifacts::{set_artifact_store, ArtifactConfig};
 
 #[tokio::main]
 async fn main() {
-    // 初始化日志系统
-    let filter = EnvFilter::from_default_env()
-        .add_directive("scrcpy_rs=debug".parse().unwrap())
-        .add_directive("axum=info".parse().unwrap());
+    // 交互模式下默认只输出警告，避免日志淹没命令提示符
+    let repl_mode = std::env::args().any(|arg| arg == "--repl");
+
+    // 先加载配置，日志级别由 [logging] 段决定
+    let config = match ServerConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("加载配置失败: {}", e);
+            return;
+        }
+    };
 
+    // 初始化日志系统
+    let mut filter = EnvFilter::from_default_env();
+    for directive in config.logging.filter_directives(repl_mode) {
+        match directive.parse() {
+            Ok(directive) => filter = filter.add_directive(directive),
+            Err(e) => eprintln!("忽略无效的日志级别配置 {}: {}", directive, e),
+        }
+    }
     fmt()
         .with_env_filter(filter)
+        .with_writer(scrcpy_rs::secrets::RedactingWriter)
         .init();
+    scrcpy_rs::logger::set_body_logging(config.logging.body_logging());
 
     info!("启动 Scrcpy API 服务器...");
 
+    let adb_path = scrcpy_rs::platform::init_adb_path(config.adb_path.as_deref());
+    info!("使用 adb: {}", adb_path.display());
+    if let Err(e) = config.apply_adb_servers() {
+        error!("adb server 配置无效: {}", e);
+        return;
+    }
+    if let Err(e) = scrcpy_rs::adb_server::ensure_running().await {
+        warn!("启动 adb server 失败: {}", e);
+    }
+
+    let base_path = config.normalized_base_path();
+    if !base_path.is_empty() {
+        info!("路径前缀: {}", base_path);
+    }
+
+    // 单端口模式下设备投屏 Socket.IO 挂载到 API 服务，交互模式不启动 API 服务
+    let mounted_port = if config.single_port && !repl_mode {
+        match config.api_port() {
+            Some(port) => Some(port),
+            None => {
+                error!("无法从 api_addr {} 解析端口", config.api_addr);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+    scrcpy_rs::scrcpy::scrcpy::init_socket_io_listen(SocketIoListen {
+        host: config.scrcpy_host.clone(),
+        port_range: config.scrcpy_port_range,
+        mounted_port,
+        base_path: base_path.clone(),
+    });
+
+    // 定期统计日志与产物目录的磁盘用量，超出配额时清理最旧的产物
+    scrcpy_rs::storage::spawn_storage_monitor(config.storage.clone());
+
     // 创建 Context 实例，包含 ScrcpyServer 和 ADBServer
     let ctx = Arc::new(Context::new());
 
+    // 交互模式：不启动服务，直接在终端中操作设备
+    if repl_mode {
+        #[cfg(feature = "agent")]
+        {
+            let device_pool = create_device_pool(&ctx).await;
+            repl::run(ctx, device_pool).await;
+        }
+        #[cfg(not(feature = "agent"))]
+        error!("交互模式需要启用 agent 特性");
+        return;
+    }
+
+    // 创建 API 服务器
+    #[allow(unused_mut)]
+    let mut api_server = ApiServer::new(ctx.clone() as Arc<dyn IContext + Sync + Send>)
+        .with_base_path(&base_path);
+
+    // 创建 Agent Socket.IO 服务器，单端口模式下挂载到 API 服务
+    #[cfg(feature = "agent")]
+    let mut agent_socket_server = None;
+    #[cfg(feature = "agent")]
+    if config.agent_server {
+        let device_pool = create_device_pool(&ctx).await;
+        let server = AgentSocketServer::new_with_path(
+            config.agent_addr.clone(),
+            format!("{}/socket.io", base_path),
+            device_pool,
+        );
+        if config.single_port {
+            info!("单端口模式：Agent Socket.IO 挂载到 API 服务 {}", config.api_addr);
+            api_server.app = api_server.app.layer(server.into_layer());
+        } else {
+            info!("Agent Socket.IO 服务器配置完成，地址: {}", config.agent_addr);
+            agent_socket_server = Some(server);
+        }
+    }
+    #[cfg(not(feature = "agent"))]
+    if config.agent_server {
+        error!("未启用 agent 特性，Agent Socket.IO 服务不会启动");
+    }
+    if !config.agent_server {
+        info!("Agent Socket.IO 服务已关闭");
+    }
+
+    // 启动 API 服务器
+    let api_addr = config.api_addr.clone();
+    let api_handle = tokio::spawn(async move {
+        api_server.run(&api_addr).await;
+    });
+
+    #[cfg(feature = "agent")]
+    if let Some(agent_socket_server) = agent_socket_server {
+        // 启动 Agent Socket.IO 服务器
+        let agent_handle = tokio::spawn(async move {
+            agent_socket_server.run().await;
+        });
+
+        // 等待两个服务器
+        tokio::select! {
+            result = api_handle => {
+                if let Err(e) = result {
+                    error!("API 服务器运行失败: {:?}", e);
+                }
+            }
+            result = agent_handle => {
+                if let Err(e) = result {
+                    error!("Agent Socket.IO 服务器运行失败: {:?}", e);
+                }
+            }
+        }
+        return;
+    }
+
+    if let Err(e) = api_handle.await {
+        error!("API 服务器运行失败: {:?}", e);
+    }
+}
+
+/// 创建设备池并设置到 Context
+#[cfg(feature = "agent")]
+async fn create_device_pool(ctx: &Context) -> Arc<DevicePool> {
     // 初始化 DevicePool
-    let device_pool_config = DevicePoolConfig::default();
+    // 配置文件中的 [pool] 段（容量限制等）
+    let device_pool_config = match ServerConfig::config_path().map(|path| DevicePoolConfig::from_config_file(&path)) {
+        Some(Ok(Some(config))) => config,
+        Some(Err(e)) => {
+            error!("{}，使用默认设备池配置", e);
+            DevicePoolConfig::default()
+        }
+        _ => DevicePoolConfig::default(),
+    };
     let adb_server = Arc::clone(ctx.get_adb_server());
 
     let model_config = ModelConfig {
         provider: "autoglm".to_string(),
         model_name: "autoglm-phone".to_string(),
-        api_key: std::env::var("AUTOGLM_API_KEY")
-            .or_else(|_| std::env::var("OPENAI_API_KEY"))
-            .unwrap_or_else(|_| {
-                error!("未设置 API Key！请设置环境变量 AUTOGLM_API_KEY 或 OPENAI_API_KEY");
+        api_key: match scrcpy_rs::secrets::env_secret("AUTOGLM_API_KEY")
+            .or_else(|| scrcpy_rs::secrets::env_secret("OPENAI_API_KEY"))
+        {
+            Some(Ok(api_key)) => api_key,
+            Some(Err(e)) => {
+                error!("读取 API Key 失败: {}", e);
+                "sk-test".to_string()
+            }
+            None => {
+                error!("未设置 API Key！请设置环境变量 AUTOGLM_API_KEY 或 OPENAI_API_KEY（或 *_FILE 指向的密钥文件）");
                 error!("从 https://open.bigmodel.cn/ 获取 API Key");
                 "sk-test".to_string()
-            }),
+            }
+        },
         base_url: "https://open.bigmodel.cn/api/paas/v4".to_string(),
         max_tokens: 4096,
         temperature: 0.2,
@@ -54,6 +204,16 @@ async fn main() {
         planning_model_name: Some("glm-4.7".to_string()), // 规划模型（大模型，用于三阶段模式）
         execution_model_name: Some("autoglm-phone".to_string()), // 执行模型（小模型，用于三阶段模式）
         enable_three_stage: true, // 启用三阶段模式
+        judge_model_name: std::env::var("AUTOGLM_JUDGE_MODEL").ok(), // 评估模型（可选，用于判定任务是否成功）
+        allowed_task_models: Vec::new(), // 任务可额外指定的模型（可在配置文件 [model] 段中设置）
+        stream: false, // 流式响应（可在配置文件 [model] 段中开启，用于测量首 token 时间）
+        fallback: None, // 备用模型端点（可在配置文件 [model.fallback] 段中设置）
+        failover_threshold: 3,
+        failover_probe_secs: 60,
+        max_payload_bytes: 8 * 1024 * 1024, // 请求体上限，超出时裁剪图片与历史消息
+        max_images: 0,
+        action_format: Default::default(), // 操作输出格式（可在配置文件 [model] 段中设为 "json"）
+        token_prices: Default::default(), // 每百万 tokens 价格（可在配置文件 [model.token_prices] 段中设置）
     };
 
     // 检查 API Key 是否有效
@@ -62,7 +222,8 @@ async fn main() {
         error!("⚠️  请设置环境变量 AUTOGLM_API_KEY");
         error!("⚠️  例如: export AUTOGLM_API_KEY=your_actual_api_key");
     } else {
-        info!("✓ API Key 已配置: {}...", &model_config.api_key[..model_config.api_key.len().min(10)]);
+        scrcpy_rs::secrets::register_secret(&model_config.api_key);
+        info!("✓ API Key 已配置");
     }
 
     let agent_config = AgentConfig::default();
@@ -78,34 +239,44 @@ async fn main() {
     ctx.set_device_pool(Arc::clone(&device_pool)).await;
     info!("DevicePool 初始化完成");
 
-    // 创建并启动 API 服务器
-    let api_server = api::api::ApiServer::new(ctx.clone() as Arc<dyn IContext + Sync + Send>);
+    // 模型端点切换时发出设备池事件
+    device_pool.forward_failover_events();
 
-    // 启动 API 服务器（端口 3000）
-    let api_handle = tokio::spawn(async move {
-        api_server.run().await;
-    });
+    // adb server 重启后重新建立设备连接
+    device_pool.reconnect_after_adb_restart();
 
-    // 创建并启动 Agent Socket.IO 服务器（端口 4000）
-    let agent_socket_server = AgentSocketServer::new(4000, device_pool);
-    info!("Agent Socket.IO 服务器配置完成，端口: 4000");
-
-    // 启动 Agent Socket.IO 服务器
-    let agent_handle = tokio::spawn(async move {
-        agent_socket_server.run().await;
-    });
+    // 通过接口添加的应用别名（配置文件 [apps.aliases] 段由配置监视任务加载）
+    let aliases_file = std::path::Path::new(scrcpy_rs::agent::actions::aliases::APP_ALIASES_FILE);
+    if let Err(e) = scrcpy_rs::agent::actions::aliases::app_aliases().load(aliases_file) {
+        error!("{}", e);
+    }
 
-    // 等待两个服务器
-    tokio::select! {
-        result = api_handle => {
-            if let Err(e) = result {
-                error!("API 服务器运行失败: {:?}", e);
-            }
-        }
-        result = agent_handle => {
-            if let Err(e) = result {
-                error!("Agent Socket.IO 服务器运行失败: {:?}", e);
+    // 配置文件中的 [artifacts] 段（任务产物存储，默认本地目录）
+    match ServerConfig::config_path().map(|path| ArtifactConfig::from_config_file(&path)) {
+        Some(Ok(Some(config))) => match config.build() {
+            Ok(store) => {
+                info!("任务产物存储: {:?}", config.backend);
+                set_artifact_store(store);
             }
-        }
+            Err(e) => error!("{}，使用本地产物存储", e),
+        },
+        Some(Err(e)) => error!("{}，使用本地产物存储", e),
+        _ => {}
     }
+
+    // 按模型汇总的调用统计，重启后继续累计
+    scrcpy_rs::agent::llm::usage_stats::spawn_usage_persistence(scrcpy_rs::agent::llm::usage_stats::USAGE_STATS_FILE);
+
+    // 任务结束后回收按需启动的模拟器
+    device_pool.spawn_emulator_reaper();
+
+    // 接入容器化设备并定期检查健康状态
+    device_pool.spawn_farm_monitor();
+
+    // 配置文件中的 [model] / [agent] / [apps.aliases] 段修改后自动生效
+    if let Some(path) = ServerConfig::config_path() {
+        scrcpy_rs::agent::pool::spawn_config_watcher(Arc::clone(&device_pool), path);
+    }
+
+    device_pool
 }
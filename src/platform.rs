@@ -0,0 +1,116 @@
+//! 平台相关的路径与进程处理
+//!
+//! 统一处理 Linux / macOS / Windows 之间的差异：adb 可执行文件的查找、
+//! 临时目录、以及由设备序列号等外部输入生成的文件名。
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 解析后的 adb 路径
+static ADB_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// 设置 adb 路径（通常来自配置），需在首次执行 adb 命令前调用；未设置时自动查找
+pub fn init_adb_path(configured: Option<&str>) -> &'static Path {
+    ADB_PATH.get_or_init(|| {
+        find_adb(configured, |key| std::env::var(key).ok(), |path| path.is_file())
+    })
+}
+
+/// adb 可执行文件路径
+pub fn adb_path() -> &'static Path {
+    init_adb_path(None)
+}
+
+/// 创建执行 adb 的命令
+pub fn adb_command() -> tokio::process::Command {
+    #[allow(unused_mut)]
+    let mut command = tokio::process::Command::new(adb_path());
+    // Windows 下避免为每条 adb 命令弹出控制台窗口
+    #[cfg(windows)]
+    command.creation_flags(0x0800_0000);
+    command
+}
+
+/// 创建针对某台设备执行 adb 的命令（`adb [-H host -P port] -s serial`），设备位于远程 adb server 时自动带上地址
+pub fn adb_device_command(serial: &str) -> tokio::process::Command {
+    let mut command = adb_command();
+    if let Some(remote) = crate::adb_server::server_for(serial) {
+        command
+            .args(["-H", &remote.addr.ip().to_string()])
+            .args(["-P", &remote.addr.port().to_string()]);
+    }
+    command.args(["-s", serial]);
+    command
+}
+
+/// 按以下顺序查找 adb：配置的路径、`ANDROID_HOME` / `ANDROID_SDK_ROOT` 下的 platform-tools、
+/// 各平台 Android Studio 默认 SDK 目录，都不存在时使用 PATH 中的 `adb`
+fn find_adb(
+    configured: Option<&str>,
+    env: impl Fn(&str) -> Option<String>,
+    exists: impl Fn(&Path) -> bool,
+) -> PathBuf {
+    if let Some(path) = configured.filter(|p| !p.trim().is_empty()) {
+        return PathBuf::from(path);
+    }
+
+    let adb_file = format!("adb{}", std::env::consts::EXE_SUFFIX);
+    let mut sdk_roots: Vec<PathBuf> = ["ANDROID_HOME", "ANDROID_SDK_ROOT"]
+        .iter()
+        .filter_map(|key| env(key))
+        .map(PathBuf::from)
+        .collect();
+    if cfg!(windows) {
+        sdk_roots.extend(env("LOCALAPPDATA").map(|dir| Path::new(&dir).join("Android").join("Sdk")));
+    } else if let Some(home) = env("HOME") {
+        let home = Path::new(&home);
+        if cfg!(target_os = "macos") {
+            sdk_roots.push(home.join("Library").join("Android").join("sdk"));
+        } else {
+            sdk_roots.push(home.join("Android").join("Sdk"));
+        }
+    }
+
+    sdk_roots
+        .into_iter()
+        .map(|root| root.join("platform-tools").join(&adb_file))
+        .find(|path| exists(path))
+        .unwrap_or_else(|| PathBuf::from(adb_file))
+}
+
+/// 本程序使用的临时目录
+pub fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join("scrcpy-rs");
+    let _ = std::fs::create_dir_all(&dir);
+    dir
+}
+
+/// 将任意字符串（如 `192.168.1.2:5555` 这样的设备序列号）转换为各平台都合法的文件名
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_adb() {
+        let adb_file = format!("adb{}", std::env::consts::EXE_SUFFIX);
+        let sdk = Path::new("sdk").join("platform-tools").join(&adb_file);
+
+        // 配置优先
+        assert_eq!(find_adb(Some("/opt/adb"), |_| None, |_| true), PathBuf::from("/opt/adb"));
+
+        // ANDROID_HOME 下存在 adb
+        let env = |key: &str| (key == "ANDROID_HOME").then(|| "sdk".to_string());
+        assert_eq!(find_adb(None, env, |p| p == sdk), sdk);
+
+        // 均不存在时回退到 PATH
+        assert_eq!(find_adb(None, env, |_| false), PathBuf::from(&adb_file));
+
+        assert_eq!(sanitize_file_name("192.168.1.2:5555"), "192_168_1_2_5555");
+    }
+}
@@ -0,0 +1,260 @@
+//! 交互式调试模式（`--repl`）
+//!
+//! 不启动 HTTP / Socket.IO 服务，直接在终端中选择设备，
+//! 通过 ActionHandler 执行单个操作或通过 PhoneAgent 执行完整任务，便于本地调试设备行为。
+
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use scrcpy_rs::agent::core::traits::AgentStatus;
+use scrcpy_rs::agent::pool::TaskPriority;
+use scrcpy_rs::{ActionEnum, Agent, Context, DevicePool, IContext, PhoneAgent};
+
+/// 任务进度轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+const HELP: &str = "\
+命令:
+  devices                     列出 ADB 设备
+  use <serial>                选择设备
+  tap <x> <y>                 点击
+  long_press <x> <y> [ms]     长按
+  double_tap <x> <y>          双击
+  swipe <x1> <y1> <x2> <y2> [ms]
+                              滑动
+  type <文本>                 输入文本
+  back | home | recent        导航键
+  launch <应用名或包名>       启动应用
+  wait <ms>                   等待
+  screenshot                  截图
+  do(action=\"...\", ...)       按模型输出格式执行任意操作
+  task <任务描述>             由 Agent 执行完整任务（Ctrl-C 停止）
+  status                      查看 Agent 状态
+  help                        显示帮助
+  quit | exit                 退出";
+
+/// 运行交互式调试循环，直到输入 quit 或标准输入关闭
+pub async fn run(ctx: Arc<Context>, pool: Arc<DevicePool>) {
+    println!("scrcpy-rs 交互模式，输入 help 查看命令");
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut current: Option<Arc<PhoneAgent>> = None;
+
+    loop {
+        let prompt = match &current {
+            Some(agent) => format!("{}> ", agent.device_serial()),
+            None => "> ".to_string(),
+        };
+        let mut stdout = tokio::io::stdout();
+        let _ = stdout.write_all(prompt.as_bytes()).await;
+        let _ = stdout.flush().await;
+
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            _ => break,
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (command, rest) = match line.split_once(char::is_whitespace) {
+            Some((command, rest)) => (command, rest.trim()),
+            None => (line, ""),
+        };
+
+        match command {
+            "quit" | "exit" => break,
+            "help" => println!("{}", HELP),
+            "devices" => list_devices(&ctx).await,
+            "use" => match select_device(&pool, rest).await {
+                Ok(agent) => {
+                    println!("已选择设备 {}", rest);
+                    current = Some(agent);
+                }
+                Err(e) => println!("错误: {}", e),
+            },
+            _ => {
+                let Some(agent) = &current else {
+                    println!("请先使用 use <serial> 选择设备");
+                    continue;
+                };
+                match command {
+                    "task" => run_task(&pool, agent, unquote(rest)).await,
+                    "status" => println!("{:?}", agent.status().await),
+                    _ => execute_action(agent, line, command, rest).await,
+                }
+            }
+        }
+    }
+
+    if let Some(agent) = current {
+        let _ = agent.stop().await;
+    }
+}
+
+/// 列出 ADB 设备
+async fn list_devices(ctx: &Context) {
+    match scrcpy_rs::adb_server::list_devices(ctx.get_adb_server()).await {
+        Ok(devices) if devices.is_empty() => println!("没有已连接的设备"),
+        Ok(devices) => {
+            for device in devices {
+                println!("{}\t{}", device.identifier, device.state);
+            }
+        }
+        Err(e) => println!("错误: 获取设备列表失败: {:?}", e),
+    }
+}
+
+/// 注册并连接设备，返回其 Agent
+async fn select_device(pool: &DevicePool, serial: &str) -> Result<Arc<PhoneAgent>, String> {
+    if serial.is_empty() {
+        return Err("用法: use <serial>".to_string());
+    }
+    let _ = pool.register_device(serial.to_string(), None).await;
+    pool.get_agent(serial).await.map_err(|e| e.to_string())
+}
+
+/// 解析并执行单个操作
+async fn execute_action(agent: &PhoneAgent, line: &str, command: &str, rest: &str) {
+    let action = match parse_action(line, command, rest) {
+        Ok(action) => action,
+        Err(e) => {
+            println!("错误: {}", e);
+            return;
+        }
+    };
+    match agent.action_handler().execute_parsed_action(&action).await {
+        Ok(result) => println!("✓ {} ({}ms)", result.message, result.duration_ms),
+        Err(e) => println!("✗ {}", e),
+    }
+}
+
+/// 将一行命令解析为操作
+fn parse_action(line: &str, command: &str, rest: &str) -> Result<ActionEnum, String> {
+    if line.starts_with("do(") {
+        let (_, actions) = ActionEnum::parse_from_response(line);
+        return actions.into_iter().next().ok_or_else(|| format!("无法解析操作: {}", line));
+    }
+
+    let numbers = |count: usize| -> Result<Vec<u32>, String> {
+        let values: Vec<u32> = rest
+            .split_whitespace()
+            .map(|v| v.parse().map_err(|_| format!("无效的数字: {}", v)))
+            .collect::<Result<_, _>>()?;
+        if values.len() < count {
+            return Err(format!("{} 需要至少 {} 个数字参数", command, count));
+        }
+        Ok(values)
+    };
+
+    let (action_type, params) = match command {
+        "tap" | "double_tap" => {
+            let v = numbers(2)?;
+            (command, json!({ "x": v[0], "y": v[1] }))
+        }
+        "long_press" => {
+            let v = numbers(2)?;
+            ("long_press", json!({ "x": v[0], "y": v[1], "duration_ms": v.get(2).copied().unwrap_or(1000) }))
+        }
+        "swipe" => {
+            let v = numbers(4)?;
+            ("swipe", json!({
+                "start_x": v[0],
+                "start_y": v[1],
+                "end_x": v[2],
+                "end_y": v[3],
+                "duration_ms": v.get(4).copied().unwrap_or(300)
+            }))
+        }
+        "type" => ("type", json!({ "text": unquote(rest) })),
+        "back" | "home" | "recent" | "screenshot" => (command, json!({})),
+        "wait" => ("wait", json!({ "duration_ms": numbers(1)?[0] })),
+        "launch" => ("launch", json!({ "package": unquote(rest) })),
+        _ => return Err(format!("未知命令: {}，输入 help 查看命令", command)),
+    };
+    ActionEnum::from_json(action_type, params).map_err(|e| format!("参数错误: {}", e))
+}
+
+/// 去掉首尾成对的引号
+fn unquote(text: &str) -> String {
+    let text = text.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = text.strip_prefix(quote).and_then(|t| t.strip_suffix(quote)) {
+            return inner.to_string();
+        }
+    }
+    text.to_string()
+}
+
+/// 经设备池由 Agent 执行完整任务，输出每一步直到任务结束；Ctrl-C 停止任务
+async fn run_task(pool: &DevicePool, agent: &PhoneAgent, task: String) {
+    if task.is_empty() {
+        println!("用法: task <任务描述>");
+        return;
+    }
+    if let Err(e) = pool.start_task(agent, task, TaskPriority::Normal).await {
+        println!("错误: 启动任务失败: {}", e);
+        return;
+    }
+
+    let mut printed = 0;
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        for step in agent.history().await.iter().skip(printed) {
+            let mark = if step.result.success { "✓" } else { "✗" };
+            println!("[{}] {} {} - {}", step.step_number, mark, step.action_description, step.result.message);
+            printed += 1;
+        }
+
+        match agent.status().await {
+            AgentStatus::Running { .. } | AgentStatus::Paused { .. } => {}
+            AgentStatus::Completed { steps, duration_ms, .. } => {
+                let result = agent.task_result().await.unwrap_or_default();
+                println!("任务完成（{} 步，{:.1}s）: {}", steps, duration_ms as f64 / 1000.0, result);
+                return;
+            }
+            AgentStatus::Failed { error, .. } => {
+                println!("任务失败: {}", error);
+                return;
+            }
+            AgentStatus::Idle => {
+                println!("任务已停止");
+                return;
+            }
+        }
+
+        tokio::select! {
+            _ = &mut ctrl_c => {
+                println!("正在停止任务...");
+                let _ = agent.stop().await;
+                return;
+            }
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_action() {
+        let action = parse_action("tap 100 200", "tap", "100 200").unwrap();
+        assert!(matches!(action, ActionEnum::Tap(ref a) if a.x == 100 && a.y == 200));
+
+        let action = parse_action("swipe 1 2 3 4", "swipe", "1 2 3 4").unwrap();
+        assert!(matches!(action, ActionEnum::Swipe(ref a) if a.end_y == 4 && a.duration_ms == 300));
+
+        let action = parse_action("type \"你好 世界\"", "type", "\"你好 世界\"").unwrap();
+        assert!(matches!(action, ActionEnum::Type(ref a) if a.text == "你好 世界"));
+
+        let line = r#"do(action="Back")"#;
+        assert!(matches!(parse_action(line, line, "").unwrap(), ActionEnum::Back(_)));
+
+        assert!(parse_action("tap 100", "tap", "100").is_err());
+        assert!(parse_action("fly", "fly", "").is_err());
+    }
+}
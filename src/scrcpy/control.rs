@@ -0,0 +1,218 @@
+//! scrcpy 控制消息解析
+//!
+//! 将客户端通过 scrcpy_ctl 发送的二进制控制消息解析为结构化事件。
+//! 协议字段均为大端序，参见 scrcpy `ControlMessageReader`。
+
+use serde::{Deserialize, Serialize};
+
+/// 控制消息类型：注入按键
+pub const TYPE_INJECT_KEYCODE: u8 = 0;
+/// 控制消息类型：注入文本
+pub const TYPE_INJECT_TEXT: u8 = 1;
+/// 控制消息类型：注入触摸
+pub const TYPE_INJECT_TOUCH_EVENT: u8 = 2;
+/// 控制消息类型：注入滚动
+pub const TYPE_INJECT_SCROLL_EVENT: u8 = 3;
+/// 控制消息类型：返回键或点亮屏幕
+pub const TYPE_BACK_OR_SCREEN_ON: u8 = 4;
+/// 控制消息类型：展开通知栏
+pub const TYPE_EXPAND_NOTIFICATION_PANEL: u8 = 5;
+/// 控制消息类型：展开快捷设置
+pub const TYPE_EXPAND_SETTINGS_PANEL: u8 = 6;
+/// 控制消息类型：收起通知栏
+pub const TYPE_COLLAPSE_PANELS: u8 = 7;
+/// 控制消息类型：开关屏幕显示
+pub const TYPE_SET_DISPLAY_POWER: u8 = 10;
+/// 控制消息类型：旋转屏幕
+pub const TYPE_ROTATE_DEVICE: u8 = 11;
+
+/// 按键 / 触摸动作：按下
+pub const ACTION_DOWN: u8 = 0;
+/// 按键 / 触摸动作：抬起
+pub const ACTION_UP: u8 = 1;
+/// 触摸动作：移动
+pub const ACTION_MOVE: u8 = 2;
+/// 触摸动作：取消
+pub const ACTION_CANCEL: u8 = 3;
+/// 触摸动作：多点触控中的其它手指按下
+pub const ACTION_POINTER_DOWN: u8 = 5;
+/// 触摸动作：多点触控中的其它手指抬起
+pub const ACTION_POINTER_UP: u8 = 6;
+
+/// 解析后的控制事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlEvent {
+    /// 按键事件
+    Key { action: u8, keycode: u32, repeat: u32, meta_state: u32 },
+    /// 文本输入
+    Text { text: String },
+    /// 触摸事件（坐标基于 screen_width x screen_height）
+    Touch {
+        action: u8,
+        pointer_id: u64,
+        x: i32,
+        y: i32,
+        screen_width: u16,
+        screen_height: u16,
+        pressure: u16,
+    },
+    /// 滚动事件
+    Scroll { x: i32, y: i32, screen_width: u16, screen_height: u16, hscroll: i16, vscroll: i16 },
+    /// 返回键 / 点亮屏幕
+    BackOrScreenOn { action: u8 },
+    /// 其他未解析的消息类型
+    Other { msg_type: u8 },
+}
+
+/// 单条控制消息：原始字节及解析结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlMessage {
+    pub raw: Vec<u8>,
+    pub event: ControlEvent,
+}
+
+fn be_u16(b: &[u8]) -> u16 {
+    u16::from_be_bytes([b[0], b[1]])
+}
+
+fn be_u32(b: &[u8]) -> u32 {
+    u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+}
+
+fn be_u64(b: &[u8]) -> u64 {
+    u64::from_be_bytes(b[..8].try_into().unwrap())
+}
+
+/// 解析单条消息，返回事件和消息长度；数据不足时返回 None
+fn parse_one(data: &[u8]) -> Option<(ControlEvent, usize)> {
+    let msg_type = *data.first()?;
+    match msg_type {
+        TYPE_INJECT_KEYCODE if data.len() >= 14 => Some((
+            ControlEvent::Key {
+                action: data[1],
+                keycode: be_u32(&data[2..6]),
+                repeat: be_u32(&data[6..10]),
+                meta_state: be_u32(&data[10..14]),
+            },
+            14,
+        )),
+        TYPE_INJECT_TEXT if data.len() >= 5 => {
+            let len = be_u32(&data[1..5]) as usize;
+            let text = data.get(5..5 + len)?;
+            Some((
+                ControlEvent::Text { text: String::from_utf8_lossy(text).to_string() },
+                5 + len,
+            ))
+        }
+        TYPE_INJECT_TOUCH_EVENT if data.len() >= 32 => Some((
+            ControlEvent::Touch {
+                action: data[1],
+                pointer_id: be_u64(&data[2..10]),
+                x: be_u32(&data[10..14]) as i32,
+                y: be_u32(&data[14..18]) as i32,
+                screen_width: be_u16(&data[18..20]),
+                screen_height: be_u16(&data[20..22]),
+                pressure: be_u16(&data[22..24]),
+            },
+            32,
+        )),
+        TYPE_INJECT_SCROLL_EVENT if data.len() >= 21 => Some((
+            ControlEvent::Scroll {
+                x: be_u32(&data[1..5]) as i32,
+                y: be_u32(&data[5..9]) as i32,
+                screen_width: be_u16(&data[9..11]),
+                screen_height: be_u16(&data[11..13]),
+                hscroll: be_u16(&data[13..15]) as i16,
+                vscroll: be_u16(&data[15..17]) as i16,
+            },
+            21,
+        )),
+        TYPE_BACK_OR_SCREEN_ON if data.len() >= 2 => {
+            Some((ControlEvent::BackOrScreenOn { action: data[1] }, 2))
+        }
+        TYPE_EXPAND_NOTIFICATION_PANEL | TYPE_EXPAND_SETTINGS_PANEL | TYPE_COLLAPSE_PANELS | TYPE_ROTATE_DEVICE => {
+            Some((ControlEvent::Other { msg_type }, 1))
+        }
+        TYPE_SET_DISPLAY_POWER if data.len() >= 2 => Some((ControlEvent::Other { msg_type }, 2)),
+        TYPE_INJECT_KEYCODE | TYPE_INJECT_TEXT | TYPE_INJECT_TOUCH_EVENT
+        | TYPE_INJECT_SCROLL_EVENT | TYPE_BACK_OR_SCREEN_ON | TYPE_SET_DISPLAY_POWER => None,
+        // 其他类型长度不固定，整个剩余数据视为一条消息
+        _ => Some((ControlEvent::Other { msg_type }, data.len())),
+    }
+}
+
+/// 编码触摸消息（32 字节，不带鼠标按键）
+pub fn encode_touch(action: u8, pointer_id: u64, x: i32, y: i32, screen_width: u16, screen_height: u16, pressure: u16) -> Vec<u8> {
+    let mut buf = vec![TYPE_INJECT_TOUCH_EVENT, action];
+    buf.extend_from_slice(&pointer_id.to_be_bytes());
+    buf.extend_from_slice(&x.to_be_bytes());
+    buf.extend_from_slice(&y.to_be_bytes());
+    buf.extend_from_slice(&screen_width.to_be_bytes());
+    buf.extend_from_slice(&screen_height.to_be_bytes());
+    buf.extend_from_slice(&pressure.to_be_bytes());
+    buf.extend_from_slice(&[0; 8]);
+    buf
+}
+
+/// 解析一个数据包中的所有控制消息
+///
+/// 末尾不完整的消息会被忽略。
+pub fn parse_messages(mut data: &[u8]) -> Vec<ControlMessage> {
+    let mut messages = Vec::new();
+    while let Some((event, len)) = parse_one(data) {
+        messages.push(ControlMessage { raw: data[..len].to_vec(), event });
+        data = &data[len..];
+    }
+    messages
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// 构造触摸消息
+    pub(crate) fn touch(action: u8, x: u32, y: u32) -> Vec<u8> {
+        encode_touch(action, u64::MAX, x as i32, y as i32, 1080, 2400, 0xffff)
+    }
+
+    /// 构造按键消息
+    pub(crate) fn key(action: u8, keycode: u32) -> Vec<u8> {
+        let mut buf = vec![TYPE_INJECT_KEYCODE, action];
+        buf.extend_from_slice(&keycode.to_be_bytes());
+        buf.extend_from_slice(&[0; 8]);
+        buf
+    }
+
+    #[test]
+    fn test_parse_touch_and_key() {
+        let mut data = touch(ACTION_DOWN, 100, 200);
+        data.extend(key(ACTION_UP, 4));
+
+        let messages = parse_messages(&data);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].event,
+            ControlEvent::Touch {
+                action: ACTION_DOWN,
+                pointer_id: u64::MAX,
+                x: 100,
+                y: 200,
+                screen_width: 1080,
+                screen_height: 2400,
+                pressure: 0xffff,
+            }
+        );
+        assert_eq!(messages[0].raw.len(), 32);
+        assert_eq!(
+            messages[1].event,
+            ControlEvent::Key { action: ACTION_UP, keycode: 4, repeat: 0, meta_state: 0 }
+        );
+    }
+
+    #[test]
+    fn test_parse_truncated() {
+        let data = touch(ACTION_DOWN, 1, 2);
+        assert!(parse_messages(&data[..20]).is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+//! scrcpy_ctl 控制权限
+//!
+//! 连接到设备 Socket.IO 命名空间的客户端默认只能观看。`POST /connect` 返回短期有效的控制令牌，
+//! 客户端发送 `scrcpy_ctl_auth` 事件提交令牌后才能通过 `scrcpy_ctl` 注入控制事件；
+//! 令牌在有效期内可以重复使用（断线重连），过期后需要重新调用 `/connect` 获取。控制权限可以按客户端撤销，
+//! 撤销时该客户端使用的令牌同时作废，客户端无法用同一令牌重新授权或换一个连接重连。
+//!
+//! 有效期只约束提交令牌（授权握手）的时间：已授权的连接在断开或被撤销前一直保有控制权限，不随令牌过期失效。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// 控制令牌有效期
+pub const CONTROL_TOKEN_TTL: Duration = Duration::from_secs(120);
+
+/// 签发的控制令牌
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ControlToken {
+    pub token: String,
+    /// 有效期（秒）
+    pub expires_in_secs: u64,
+}
+
+/// 一个设备会话的控制令牌与已授权的客户端
+#[derive(Debug, Default)]
+pub struct ControlAuth {
+    /// 令牌 -> 过期时间
+    tokens: Mutex<HashMap<String, Instant>>,
+    /// 已授权的客户端（Socket.IO 连接 ID）-> 使用的令牌
+    clients: Mutex<HashMap<String, String>>,
+}
+
+impl ControlAuth {
+    /// 签发新的控制令牌，同时清理过期的令牌
+    pub fn issue(&self) -> ControlToken {
+        self.issue_at(Instant::now())
+    }
+
+    fn issue_at(&self, now: Instant) -> ControlToken {
+        let token = new_token();
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.retain(|_, expires_at| *expires_at > now);
+        tokens.insert(token.clone(), now + CONTROL_TOKEN_TTL);
+        ControlToken { token, expires_in_secs: CONTROL_TOKEN_TTL.as_secs() }
+    }
+
+    /// 校验客户端提交的令牌，有效时授权该客户端
+    pub fn authorize(&self, client_id: &str, token: &str) -> Result<(), String> {
+        self.authorize_at(client_id, token, Instant::now())
+    }
+
+    fn authorize_at(&self, client_id: &str, token: &str, now: Instant) -> Result<(), String> {
+        match self.tokens.lock().unwrap().get(token) {
+            Some(expires_at) if *expires_at > now => {}
+            Some(_) => return Err("控制令牌已过期，请重新连接设备获取".to_string()),
+            None => return Err("控制令牌无效".to_string()),
+        }
+        self.clients.lock().unwrap().insert(client_id.to_string(), token.to_string());
+        Ok(())
+    }
+
+    /// 客户端是否可以注入控制事件；授权后直到断开或被撤销都有效，与令牌是否过期无关
+    pub fn is_authorized(&self, client_id: &str) -> bool {
+        self.clients.lock().unwrap().contains_key(client_id)
+    }
+
+    /// 撤销客户端的控制权限并作废它使用的令牌，返回因此失去控制权限的客户端（包括用同一令牌授权的其他连接），
+    /// 客户端之前未授权时为空
+    pub fn revoke(&self, client_id: &str) -> Vec<String> {
+        let mut clients = self.clients.lock().unwrap();
+        let Some(token) = clients.get(client_id).cloned() else {
+            return Vec::new();
+        };
+        self.tokens.lock().unwrap().remove(&token);
+        let mut revoked: Vec<String> = clients.iter().filter(|(_, t)| **t == token).map(|(id, _)| id.clone()).collect();
+        clients.retain(|_, t| *t != token);
+        revoked.sort();
+        revoked
+    }
+
+    /// 客户端断开：移除授权，令牌保留以便断线重连
+    pub fn disconnect(&self, client_id: &str) {
+        self.clients.lock().unwrap().remove(client_id);
+    }
+
+    /// 已授权的客户端
+    pub fn clients(&self) -> Vec<String> {
+        let mut clients: Vec<String> = self.clients.lock().unwrap().keys().cloned().collect();
+        clients.sort();
+        clients
+    }
+}
+
+/// 生成随机令牌（32 位十六进制），随机数来自操作系统的安全随机源（UUID v4，122 位随机）
+fn new_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorize_and_revoke() {
+        let auth = ControlAuth::default();
+        let now = Instant::now();
+        let token = auth.issue_at(now);
+        assert_eq!(token.token.len(), 32);
+        assert_ne!(token.token, auth.issue_at(now).token);
+
+        assert!(!auth.is_authorized("a"));
+        assert!(auth.authorize_at("a", "wrong", now).is_err());
+        auth.authorize_at("a", &token.token, now).unwrap();
+        // 有效期内可以重复使用
+        auth.authorize_at("b", &token.token, now + Duration::from_secs(60)).unwrap();
+        assert!(auth.authorize_at("c", &token.token, now + CONTROL_TOKEN_TTL).unwrap_err().contains("过期"));
+        assert_eq!(auth.clients(), vec!["a", "b"]);
+        // 令牌过期不影响已授权的连接
+        assert!(auth.is_authorized("a"));
+
+        // 断开连接后可以用同一令牌重连
+        auth.disconnect("a");
+        assert!(!auth.is_authorized("a"));
+        auth.authorize_at("a2", &token.token, now + Duration::from_secs(60)).unwrap();
+
+        // 撤销后令牌作废，用同一令牌授权的连接一并失去权限
+        let other = auth.issue_at(now);
+        auth.authorize_at("d", &other.token, now).unwrap();
+        assert_eq!(auth.revoke("a2"), vec!["a2", "b"]);
+        assert!(auth.revoke("a2").is_empty());
+        assert!(!auth.is_authorized("b"));
+        assert!(auth.authorize_at("a3", &token.token, now + Duration::from_secs(60)).unwrap_err().contains("无效"));
+        assert!(auth.is_authorized("d"));
+    }
+}
@@ -0,0 +1,145 @@
+//! 设备元数据
+//!
+//! 连接设备时通过一次 `getprop` 读取型号、Android 版本、SDK、CPU ABI 与屏幕密度，
+//! scrcpy 会话开始后再补上 64 字节头中的设备名称。结果按序列号缓存，供设备列表、Agent 提示词与任务报告使用。
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+/// 设备元数据
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceMetadata {
+    /// scrcpy 头中的设备名称
+    pub name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    /// Android 版本（如 "14"）
+    pub android_version: Option<String>,
+    pub sdk: Option<u32>,
+    /// 首选 CPU ABI（如 "arm64-v8a"）
+    pub abi: Option<String>,
+    /// 屏幕密度（dpi）
+    pub density: Option<u32>,
+}
+
+impl DeviceMetadata {
+    /// 从 `getprop` 的属性表生成
+    pub fn from_props(props: &HashMap<String, String>) -> Self {
+        let prop = |keys: &[&str]| keys.iter().find_map(|key| props.get(*key).filter(|v| !v.is_empty()).cloned());
+        Self {
+            name: None,
+            manufacturer: prop(&["ro.product.manufacturer"]),
+            model: prop(&["ro.product.model"]),
+            android_version: prop(&["ro.build.version.release"]),
+            sdk: prop(&["ro.build.version.sdk"]).and_then(|v| v.parse().ok()),
+            abi: prop(&["ro.product.cpu.abi"]),
+            density: prop(&["ro.sf.lcd_density", "qemu.sf.lcd_density"]).and_then(|v| v.parse().ok()),
+        }
+    }
+
+    /// 一行描述，用于提示词与日志，如 `Google Pixel 7，Android 14（SDK 34），arm64-v8a，420 dpi`
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        let device = [self.manufacturer.as_deref(), self.model.as_deref().or(self.name.as_deref())]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !device.is_empty() {
+            parts.push(device);
+        }
+        match (&self.android_version, self.sdk) {
+            (Some(version), Some(sdk)) => parts.push(format!("Android {}（SDK {}）", version, sdk)),
+            (Some(version), None) => parts.push(format!("Android {}", version)),
+            (None, Some(sdk)) => parts.push(format!("SDK {}", sdk)),
+            (None, None) => {}
+        }
+        parts.extend(self.abi.clone());
+        parts.extend(self.density.map(|density| format!("{} dpi", density)));
+        parts.join("，")
+    }
+}
+
+/// 解析 `adb shell getprop` 的输出（每行 `[key]: [value]`）
+pub fn parse_getprop(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once("]: [")?;
+            Some((key.strip_prefix('[')?.to_string(), value.strip_suffix(']')?.to_string()))
+        })
+        .collect()
+}
+
+fn cache() -> &'static Mutex<HashMap<String, DeviceMetadata>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, DeviceMetadata>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 读取设备属性并更新缓存（保留已知的设备名称）
+pub async fn query(serial: &str) -> Result<DeviceMetadata, String> {
+    let output = crate::platform::adb_device_command(serial)
+        .args(["shell", "getprop"])
+        .output()
+        .await
+        .map_err(|e| format!("执行 getprop 失败: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("getprop 失败: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let mut metadata = DeviceMetadata::from_props(&parse_getprop(&String::from_utf8_lossy(&output.stdout)));
+    let mut cache = cache().lock().unwrap();
+    metadata.name = cache.get(serial).and_then(|cached| cached.name.clone());
+    cache.insert(serial.to_string(), metadata.clone());
+    Ok(metadata)
+}
+
+/// 已缓存的设备元数据
+pub fn cached(serial: &str) -> Option<DeviceMetadata> {
+    cache().lock().unwrap().get(serial).cloned()
+}
+
+/// 已缓存时直接返回，否则读取设备属性
+pub async fn metadata(serial: &str) -> Result<DeviceMetadata, String> {
+    match cached(serial) {
+        Some(metadata) => Ok(metadata),
+        None => query(serial).await,
+    }
+}
+
+/// 记录 scrcpy 头中的设备名称
+pub fn set_device_name(serial: &str, name: &str) {
+    cache().lock().unwrap().entry(serial.to_string()).or_default().name = Some(name.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_from_getprop() {
+        let output = "[ro.build.version.release]: [14]\n\
+                      [ro.build.version.sdk]: [34]\n\
+                      [ro.product.cpu.abi]: [arm64-v8a]\n\
+                      [ro.product.manufacturer]: [Google]\n\
+                      [ro.product.model]: [Pixel 7]\n\
+                      [ro.sf.lcd_density]: [420]\n\
+                      [persist.sys.locale]: []\n";
+        let props = parse_getprop(output);
+        assert_eq!(props.get("persist.sys.locale").map(String::as_str), Some(""));
+
+        let metadata = DeviceMetadata::from_props(&props);
+        assert_eq!(metadata.sdk, Some(34));
+        assert_eq!(metadata.density, Some(420));
+        assert_eq!(metadata.summary(), "Google Pixel 7，Android 14（SDK 34），arm64-v8a，420 dpi");
+
+        // 模拟器的密度在 qemu 属性中
+        let emulator = DeviceMetadata::from_props(&parse_getprop("[qemu.sf.lcd_density]: [440]\n[ro.build.version.sdk]: [30]"));
+        assert_eq!((emulator.density, emulator.summary().as_str()), (Some(440), "SDK 30，440 dpi"));
+
+        set_device_name("test-device", "Pixel 7");
+        assert_eq!(cached("test-device").and_then(|m| m.name).as_deref(), Some("Pixel 7"));
+    }
+}
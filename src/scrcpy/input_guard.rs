@@ -0,0 +1,260 @@
+//! scrcpy_ctl 输入校验与限流
+//!
+//! 客户端发送的控制数据在写入设备的控制 socket 之前逐条校验：数据包长度、消息类型与动作码、
+//! 文本长度，以及触摸 / 滚动坐标是否落在声明的屏幕尺寸内、声明的尺寸是否与视频画面一致。
+//! 抬起与取消触摸（如指针移出画面边缘时发送的 CANCEL）不会因坐标越界被丢弃，而是把坐标收拢到屏幕内，
+//! 避免设备上的指针一直处于按下状态。
+//! 每个客户端按消息数限流。不通过的数据包整体丢弃，客户端收到带 `code` 的 `scrcpy_ctl_error` 事件。
+
+use std::time::Instant;
+
+use serde::Serialize;
+
+use super::control::{
+    parse_messages, ControlEvent, ControlMessage, ACTION_CANCEL, ACTION_POINTER_UP, ACTION_UP, TYPE_COLLAPSE_PANELS,
+    TYPE_EXPAND_NOTIFICATION_PANEL, TYPE_EXPAND_SETTINGS_PANEL, TYPE_ROTATE_DEVICE, TYPE_SET_DISPLAY_POWER,
+};
+
+/// 单个数据包的最大长度
+pub const MAX_CONTROL_PACKET_LEN: usize = 4096;
+/// 注入文本的最大字节数（与 scrcpy-server 的限制一致）
+pub const MAX_INJECT_TEXT_LEN: usize = 300;
+/// 触摸动作码上限（MotionEvent.ACTION_BUTTON_RELEASE）
+const MAX_TOUCH_ACTION: u8 = 12;
+
+/// 拒绝原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectCode {
+    /// 没有控制权限
+    Unauthorized,
+    /// 数据包为空或超过长度上限
+    InvalidLength,
+    /// 消息不完整或文本不是 UTF-8
+    Malformed,
+    /// 不允许的消息类型
+    UnsupportedType,
+    /// 无效的动作码
+    InvalidAction,
+    /// 坐标超出屏幕范围
+    OutOfBounds,
+    /// 声明的屏幕尺寸与视频画面不一致
+    SizeMismatch,
+    /// 超过发送频率限制
+    RateLimited,
+}
+
+/// 被拒绝的数据包
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlRejection {
+    pub code: RejectCode,
+    pub message: String,
+}
+
+impl ControlRejection {
+    fn new(code: RejectCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    /// 客户端还没有提交有效的控制令牌
+    pub fn unauthorized() -> Self {
+        Self::new(RejectCode::Unauthorized, "没有控制权限，请先通过 scrcpy_ctl_auth 提交控制令牌")
+    }
+
+    /// `scrcpy_ctl_error` 事件内容
+    pub fn to_event(&self, length: usize) -> serde_json::Value {
+        serde_json::json!({ "code": self.code, "error": self.message, "length": length })
+    }
+}
+
+/// 校验数据包，返回解析出的消息；`frame_size` 为当前视频画面的宽高（未知时不比较尺寸）
+pub fn validate_packet(data: &[u8], frame_size: Option<(u32, u32)>) -> Result<Vec<ControlMessage>, ControlRejection> {
+    if data.is_empty() || data.len() > MAX_CONTROL_PACKET_LEN {
+        return Err(ControlRejection::new(
+            RejectCode::InvalidLength,
+            format!("数据包长度 {} 字节不在 1 ~ {} 之间", data.len(), MAX_CONTROL_PACKET_LEN),
+        ));
+    }
+    let mut messages = parse_messages(data);
+    let parsed: usize = messages.iter().map(|m| m.raw.len()).sum();
+    if parsed != data.len() {
+        return Err(ControlRejection::new(
+            RejectCode::Malformed,
+            format!("第 {} 字节起的消息不完整（类型 {}）", parsed, data[parsed]),
+        ));
+    }
+    for message in &mut messages {
+        validate_message(message, frame_size)?;
+    }
+    Ok(messages)
+}
+
+fn validate_message(message: &mut ControlMessage, frame_size: Option<(u32, u32)>) -> Result<(), ControlRejection> {
+    let invalid_action = |action: u8| ControlRejection::new(RejectCode::InvalidAction, format!("无效的动作码: {}", action));
+    match &message.event {
+        ControlEvent::Key { action, .. } | ControlEvent::BackOrScreenOn { action } if *action > 1 => Err(invalid_action(*action)),
+        ControlEvent::Key { .. } | ControlEvent::BackOrScreenOn { .. } => Ok(()),
+        ControlEvent::Text { text } => {
+            let bytes = &message.raw[5..];
+            if bytes.len() > MAX_INJECT_TEXT_LEN {
+                return Err(ControlRejection::new(
+                    RejectCode::InvalidLength,
+                    format!("文本 {} 字节，超过上限 {}", bytes.len(), MAX_INJECT_TEXT_LEN),
+                ));
+            }
+            if std::str::from_utf8(bytes).is_err() {
+                return Err(ControlRejection::new(RejectCode::Malformed, format!("文本不是有效的 UTF-8: {:?}", text)));
+            }
+            Ok(())
+        }
+        ControlEvent::Touch { action, .. } if *action > MAX_TOUCH_ACTION => Err(invalid_action(*action)),
+        ControlEvent::Touch { action: ACTION_UP | ACTION_CANCEL | ACTION_POINTER_UP, screen_width, screen_height, .. } => {
+            check_size(*screen_width, *screen_height, frame_size)?;
+            clamp_touch(message);
+            Ok(())
+        }
+        ControlEvent::Touch { x, y, screen_width, screen_height, .. }
+        | ControlEvent::Scroll { x, y, screen_width, screen_height, .. } => {
+            check_position(*x, *y, *screen_width, *screen_height, frame_size)
+        }
+        ControlEvent::Other { msg_type } => match *msg_type {
+            TYPE_EXPAND_NOTIFICATION_PANEL | TYPE_EXPAND_SETTINGS_PANEL | TYPE_COLLAPSE_PANELS | TYPE_ROTATE_DEVICE => Ok(()),
+            TYPE_SET_DISPLAY_POWER if message.raw[1] > 1 => Err(invalid_action(message.raw[1])),
+            TYPE_SET_DISPLAY_POWER => Ok(()),
+            msg_type => Err(ControlRejection::new(RejectCode::UnsupportedType, format!("不允许的消息类型: {}", msg_type))),
+        },
+    }
+}
+
+fn check_size(width: u16, height: u16, frame_size: Option<(u32, u32)>) -> Result<(), ControlRejection> {
+    let (width, height) = (width as u32, height as u32);
+    if let Some((frame_width, frame_height)) = frame_size
+        && (width, height) != (frame_width, frame_height)
+        && (width, height) != (frame_height, frame_width)
+    {
+        return Err(ControlRejection::new(
+            RejectCode::SizeMismatch,
+            format!("屏幕尺寸 {}x{} 与视频画面 {}x{} 不一致", width, height, frame_width, frame_height),
+        ));
+    }
+    Ok(())
+}
+
+fn check_position(x: i32, y: i32, width: u16, height: u16, frame_size: Option<(u32, u32)>) -> Result<(), ControlRejection> {
+    check_size(width, height, frame_size)?;
+    let (width, height) = (width as u32, height as u32);
+    if x < 0 || y < 0 || x as u32 >= width || y as u32 >= height {
+        return Err(ControlRejection::new(
+            RejectCode::OutOfBounds,
+            format!("坐标 ({}, {}) 超出屏幕 {}x{}", x, y, width, height),
+        ));
+    }
+    Ok(())
+}
+
+/// 把触摸坐标收拢到屏幕范围内，同时改写原始字节
+fn clamp_touch(message: &mut ControlMessage) {
+    let ControlEvent::Touch { x, y, screen_width, screen_height, .. } = &mut message.event else {
+        return;
+    };
+    *x = (*x).clamp(0, (*screen_width as i32 - 1).max(0));
+    *y = (*y).clamp(0, (*screen_height as i32 - 1).max(0));
+    message.raw[10..14].copy_from_slice(&x.to_be_bytes());
+    message.raw[14..18].copy_from_slice(&y.to_be_bytes());
+}
+
+/// 按消息数限流的令牌桶，容量为每秒允许的消息数（允许一秒的突发）
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_sec: f64,
+    available: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(per_sec: u32) -> Self {
+        Self { per_sec: per_sec as f64, available: per_sec as f64, last_refill: Instant::now() }
+    }
+
+    /// 尝试消耗 `count` 条消息的额度
+    pub fn try_acquire(&mut self, count: usize) -> Result<(), ControlRejection> {
+        self.try_acquire_at(count, Instant::now())
+    }
+
+    fn try_acquire_at(&mut self, count: usize, now: Instant) -> Result<(), ControlRejection> {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.available = (self.available + elapsed * self.per_sec).min(self.per_sec);
+        self.last_refill = now;
+        if (count as f64) > self.available {
+            return Err(ControlRejection::new(
+                RejectCode::RateLimited,
+                format!("控制消息超过每秒 {} 条的限制", self.per_sec),
+            ));
+        }
+        self.available -= count as f64;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrcpy::control::tests::{key, touch};
+    use crate::scrcpy::control::{ACTION_DOWN, ACTION_MOVE, TYPE_INJECT_TEXT};
+    use std::time::Duration;
+
+    fn text(bytes: &[u8]) -> Vec<u8> {
+        let mut buf = vec![TYPE_INJECT_TEXT];
+        buf.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        buf.extend_from_slice(bytes);
+        buf
+    }
+
+    fn code(data: &[u8], frame_size: Option<(u32, u32)>) -> Option<RejectCode> {
+        validate_packet(data, frame_size).err().map(|r| r.code)
+    }
+
+    #[test]
+    fn test_validate_packet() {
+        let mut data = touch(ACTION_DOWN, 100, 200);
+        data.extend(key(ACTION_DOWN, 4));
+        data.extend(text("你好".as_bytes()));
+        data.extend([TYPE_SET_DISPLAY_POWER, 1]);
+        assert_eq!(validate_packet(&data, Some((1080, 2400))).unwrap().len(), 4);
+        // 横屏后宽高互换
+        assert_eq!(code(&touch(ACTION_DOWN, 100, 200), Some((2400, 1080))), None);
+
+        assert_eq!(code(&[], None), Some(RejectCode::InvalidLength));
+        assert_eq!(code(&data[..40], None), Some(RejectCode::Malformed));
+        assert_eq!(code(&[9, 0, 0], None), Some(RejectCode::UnsupportedType));
+        assert_eq!(code(&key(7, 4), None), Some(RejectCode::InvalidAction));
+        assert_eq!(code(&touch(ACTION_DOWN, 1080, 10), None), Some(RejectCode::OutOfBounds));
+        assert_eq!(code(&touch(ACTION_DOWN, 10, 10), Some((720, 1600))), Some(RejectCode::SizeMismatch));
+        assert_eq!(code(&text(&[0xff, 0xfe]), None), Some(RejectCode::Malformed));
+        assert_eq!(code(&text(&[b'a'; 301]), None), Some(RejectCode::InvalidLength));
+    }
+
+    #[test]
+    fn test_release_at_edge_is_clamped() {
+        // 指针移出画面右边缘时发送的抬起 / 取消事件
+        assert_eq!(code(&touch(ACTION_MOVE, 1080, 10), None), Some(RejectCode::OutOfBounds));
+        for action in [ACTION_UP, ACTION_CANCEL] {
+            let messages = validate_packet(&touch(action, 1080, 2400), Some((1080, 2400))).unwrap();
+            assert!(matches!(messages[0].event, ControlEvent::Touch { x: 1079, y: 2399, .. }));
+            assert_eq!(parse_messages(&messages[0].raw)[0].event, messages[0].event);
+        }
+        assert_eq!(code(&touch(ACTION_UP, 1080, 10), Some((720, 1600))), Some(RejectCode::SizeMismatch));
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut limiter = RateLimiter::new(10);
+        let start = limiter.last_refill;
+        limiter.try_acquire_at(8, start).unwrap();
+        assert_eq!(limiter.try_acquire_at(3, start).unwrap_err().code, RejectCode::RateLimited);
+        limiter.try_acquire_at(3, start + Duration::from_millis(100)).unwrap();
+        // 额度不超过容量
+        limiter.try_acquire_at(10, start + Duration::from_secs(10)).unwrap();
+        assert!(limiter.try_acquire_at(1, start + Duration::from_secs(10)).is_err());
+    }
+}
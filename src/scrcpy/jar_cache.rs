@@ -0,0 +1,132 @@
+//! scrcpy-server.jar 推送缓存
+//!
+//! 每次会话启动都推送 jar 需要数秒。记录每台设备上次推送的 jar 摘要，启动前与设备上的文件比对，
+//! 一致时跳过推送；只有 scrcpy-server 版本（或外部 jar）变化、设备上的文件被删除或覆盖时才重新推送。
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use super::server_version::SelectedServer;
+
+/// 查询设备端文件的超时
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 已推送到设备的 jar
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PushedJar {
+    sha256: String,
+    size: usize,
+}
+
+fn pushed_jars() -> &'static Mutex<HashMap<String, PushedJar>> {
+    static PUSHED: OnceLock<Mutex<HashMap<String, PushedJar>>> = OnceLock::new();
+    PUSHED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 已计算的 jar 摘要，按选定的 scrcpy-server（版本与来源）与文件大小缓存
+fn jar_digests() -> &'static Mutex<HashMap<(SelectedServer, usize), String>> {
+    static DIGESTS: OnceLock<Mutex<HashMap<(SelectedServer, usize), String>>> = OnceLock::new();
+    DIGESTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// jar 内容的 SHA-256（十六进制小写）
+pub fn jar_sha256(data: &[u8]) -> String {
+    crate::hex(&Sha256::digest(data))
+}
+
+/// 选定 scrcpy-server 的 jar 摘要，同一版本只计算一次
+pub fn server_jar_sha256(server: &SelectedServer, data: &[u8]) -> String {
+    let key = (server.clone(), data.len());
+    if let Some(digest) = jar_digests().lock().unwrap().get(&key) {
+        return digest.clone();
+    }
+    let digest = jar_sha256(data);
+    jar_digests().lock().unwrap().insert(key, digest.clone());
+    digest
+}
+
+/// 解析 `sha256sum <path>` 的输出，文件不存在或命令不可用时返回 None
+pub fn parse_sha256sum(output: &str) -> Option<String> {
+    let digest = output.split_whitespace().next()?;
+    (digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit())).then(|| digest.to_ascii_lowercase())
+}
+
+/// 设备上的 jar 与缓存记录的摘要一致时，是否可以直接复用（设备不支持 sha256sum 时退回比较文件大小）
+fn can_reuse(cached: Option<&PushedJar>, sha256: &str, size: usize, device_sha256: Option<&str>, device_size: Option<usize>) -> bool {
+    match device_sha256 {
+        Some(device_sha256) => device_sha256 == sha256,
+        None => cached.is_some_and(|cached| cached.sha256 == sha256 && cached.size == size) && device_size == Some(size),
+    }
+}
+
+/// 执行 adb shell 命令，失败或超时时返回 None
+async fn shell(serial: &str, args: &[&str]) -> Option<String> {
+    let command = crate::platform::adb_device_command(serial).arg("shell").args(args).output();
+    match tokio::time::timeout(CHECK_TIMEOUT, command).await {
+        Ok(Ok(output)) if output.status.success() => Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+        _ => None,
+    }
+}
+
+/// 设备 `device_path` 处的文件是否就是要推送的 jar
+pub async fn is_up_to_date(serial: &str, device_path: &str, sha256: &str, size: usize) -> bool {
+    let device_sha256 = shell(serial, &["sha256sum", device_path]).await.as_deref().and_then(parse_sha256sum);
+    let device_size = match device_sha256 {
+        Some(_) => None,
+        None => shell(serial, &["stat", "-c", "%s", device_path]).await.and_then(|out| out.trim().parse().ok()),
+    };
+    let cached = pushed_jars().lock().unwrap().get(serial).cloned();
+    let reusable = can_reuse(cached.as_ref(), sha256, size, device_sha256.as_deref(), device_size);
+    if reusable {
+        record_pushed(serial, sha256, size);
+    }
+    reusable
+}
+
+/// 记录推送成功的 jar
+pub fn record_pushed(serial: &str, sha256: &str, size: usize) {
+    pushed_jars()
+        .lock()
+        .unwrap()
+        .insert(serial.to_string(), PushedJar { sha256: sha256.to_string(), size });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reuse_decision() {
+        let sha256 = jar_sha256(b"scrcpy-server");
+        assert_eq!(sha256.len(), 64);
+        let line = format!("{}  /data/local/tmp/scrcpy-server.jar\n", sha256.to_uppercase());
+        assert_eq!(parse_sha256sum(&line).as_deref(), Some(sha256.as_str()));
+        assert_eq!(parse_sha256sum("sha256sum: /data/local/tmp/scrcpy-server.jar: No such file or directory"), None);
+
+        // 设备端摘要可用时以它为准
+        assert!(can_reuse(None, &sha256, 13, Some(&sha256), None));
+        assert!(!can_reuse(None, &sha256, 13, Some(&jar_sha256(b"old")), None));
+
+        // 不支持 sha256sum 时要求缓存记录一致且文件大小相同
+        let cached = PushedJar { sha256: sha256.clone(), size: 13 };
+        assert!(can_reuse(Some(&cached), &sha256, 13, None, Some(13)));
+        assert!(!can_reuse(Some(&cached), &sha256, 13, None, None));
+        assert!(!can_reuse(None, &sha256, 13, None, Some(13)));
+        let stale = PushedJar { sha256: jar_sha256(b"old"), size: 13 };
+        assert!(!can_reuse(Some(&stale), &sha256, 13, None, Some(13)));
+    }
+
+    #[test]
+    fn test_server_digest_cached_per_version() {
+        use super::super::server_version::ServerSource;
+
+        let server = SelectedServer { version: "test".to_string(), source: ServerSource::Embedded("test.jar") };
+        let digest = server_jar_sha256(&server, b"scrcpy-server");
+        assert_eq!(digest, jar_sha256(b"scrcpy-server"));
+        // 同一版本与大小直接复用已计算的摘要
+        assert_eq!(server_jar_sha256(&server, b"SCRCPY-SERVER"), digest);
+    }
+}
@@ -0,0 +1,440 @@
+//! 设备输入宏录制与回放
+//!
+//! 录制模式下捕获 scrcpy_ctl 控制消息及其时间戳，保存为宏文件；
+//! 回放时按原始时间间隔将控制消息重新注入设备。
+
+use super::control::{ControlEvent, ControlMessage, ACTION_DOWN, ACTION_UP};
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+/// 宏文件根目录
+pub const MACRO_DIR: &str = "macros";
+
+/// 起止点距离小于该值（像素）视为点击
+const TAP_MAX_DISTANCE: f64 = 16.0;
+/// 按住时长超过该值（毫秒）视为长按
+const LONG_PRESS_MIN_MS: u64 = 500;
+
+/// 录制的单条控制消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroEvent {
+    /// 相对录制开始的时间偏移（毫秒）
+    pub offset_ms: u64,
+    /// 原始控制消息（base64）
+    #[serde(serialize_with = "serialize_raw", deserialize_with = "deserialize_raw")]
+    pub raw: Vec<u8>,
+    /// 解析后的事件
+    pub event: ControlEvent,
+}
+
+fn serialize_raw<S: Serializer>(raw: &[u8], s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&BASE64_STANDARD.encode(raw))
+}
+
+fn deserialize_raw<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+    let encoded = String::deserialize(d)?;
+    BASE64_STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+}
+
+/// 录制期间的屏幕截图（在手势开始时捕获）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroFrame {
+    /// 相对录制开始的时间偏移（毫秒）
+    pub offset_ms: u64,
+    /// PNG 截图（base64）
+    pub screenshot: String,
+}
+
+/// 从控制事件中归纳出的高层操作（坐标基于 screen_width x screen_height）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MacroAction {
+    Tap { offset_ms: u64, x: i32, y: i32, screen_width: u16, screen_height: u16 },
+    LongPress { offset_ms: u64, x: i32, y: i32, duration_ms: u64, screen_width: u16, screen_height: u16 },
+    Swipe {
+        offset_ms: u64,
+        from_x: i32,
+        from_y: i32,
+        to_x: i32,
+        to_y: i32,
+        duration_ms: u64,
+        screen_width: u16,
+        screen_height: u16,
+    },
+    Scroll { offset_ms: u64, x: i32, y: i32, hscroll: i16, vscroll: i16 },
+    Key { offset_ms: u64, keycode: u32 },
+    Text { offset_ms: u64, text: String },
+}
+
+/// 录制好的设备宏
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceMacro {
+    pub name: String,
+    pub device_serial: String,
+    pub created_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    /// 归纳后的操作序列（便于阅读和后续转换）
+    pub actions: Vec<MacroAction>,
+    /// 原始事件序列（回放使用）
+    pub events: Vec<MacroEvent>,
+    /// 录制开始时的前台应用包名
+    #[serde(default)]
+    pub app: Option<String>,
+    /// 录制期间捕获的截图，按时间排序
+    #[serde(default)]
+    pub frames: Vec<MacroFrame>,
+}
+
+/// 将原始事件归纳为点击 / 长按 / 滑动 / 按键等操作
+pub fn summarize(events: &[MacroEvent]) -> Vec<MacroAction> {
+    // pointer_id -> (起始事件偏移, x, y)
+    let mut pointers: HashMap<u64, (u64, i32, i32)> = HashMap::new();
+    let mut actions = Vec::new();
+
+    for e in events {
+        match &e.event {
+            ControlEvent::Touch { action, pointer_id, x, y, screen_width, screen_height, .. } => {
+                if *action == ACTION_DOWN {
+                    pointers.insert(*pointer_id, (e.offset_ms, *x, *y));
+                } else if *action == ACTION_UP {
+                    let Some((start, sx, sy)) = pointers.remove(pointer_id) else {
+                        continue;
+                    };
+                    let duration_ms = e.offset_ms.saturating_sub(start);
+                    let distance = (((x - sx) as f64).powi(2) + ((y - sy) as f64).powi(2)).sqrt();
+                    let (screen_width, screen_height) = (*screen_width, *screen_height);
+                    actions.push(if distance >= TAP_MAX_DISTANCE {
+                        MacroAction::Swipe {
+                            offset_ms: start,
+                            from_x: sx,
+                            from_y: sy,
+                            to_x: *x,
+                            to_y: *y,
+                            duration_ms,
+                            screen_width,
+                            screen_height,
+                        }
+                    } else if duration_ms >= LONG_PRESS_MIN_MS {
+                        MacroAction::LongPress { offset_ms: start, x: sx, y: sy, duration_ms, screen_width, screen_height }
+                    } else {
+                        MacroAction::Tap { offset_ms: start, x: sx, y: sy, screen_width, screen_height }
+                    });
+                }
+            }
+            ControlEvent::Key { action, keycode, .. } if *action == ACTION_DOWN => {
+                actions.push(MacroAction::Key { offset_ms: e.offset_ms, keycode: *keycode });
+            }
+            ControlEvent::Text { text } => {
+                actions.push(MacroAction::Text { offset_ms: e.offset_ms, text: text.clone() });
+            }
+            ControlEvent::Scroll { x, y, hscroll, vscroll, .. } => {
+                actions.push(MacroAction::Scroll {
+                    offset_ms: e.offset_ms,
+                    x: *x,
+                    y: *y,
+                    hscroll: *hscroll,
+                    vscroll: *vscroll,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    actions
+}
+
+/// 进行中的录制
+struct Recording {
+    name: String,
+    started: Instant,
+    events: Vec<MacroEvent>,
+    app: Option<String>,
+    frames: Vec<MacroFrame>,
+}
+
+/// 宏录制器
+#[derive(Default)]
+pub struct MacroRecorder {
+    active: std::sync::Mutex<Option<Recording>>,
+}
+
+impl MacroRecorder {
+    /// 创建新的录制器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 开始录制，已在录制时返回错误
+    pub fn start(&self, name: &str) -> Result<(), String> {
+        validate_name(name)?;
+        let mut active = self.active.lock().unwrap();
+        if let Some(recording) = active.as_ref() {
+            return Err(format!("正在录制宏: {}", recording.name));
+        }
+        *active = Some(Recording {
+            name: name.to_string(),
+            started: Instant::now(),
+            events: Vec::new(),
+            app: None,
+            frames: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// 是否正在录制
+    pub fn is_recording(&self) -> bool {
+        self.active.lock().unwrap().is_some()
+    }
+
+    /// 录制控制消息（未在录制时忽略）
+    ///
+    /// 消息中包含手势开始（触摸按下、按键按下、文本输入）时返回其时间偏移，
+    /// 调用方应在该偏移处补充一张截图。
+    pub fn record(&self, messages: &[ControlMessage]) -> Option<u64> {
+        let mut active = self.active.lock().unwrap();
+        let recording = active.as_mut()?;
+        let offset_ms = recording.started.elapsed().as_millis() as u64;
+        recording.events.extend(messages.iter().map(|m| MacroEvent {
+            offset_ms,
+            raw: m.raw.clone(),
+            event: m.event.clone(),
+        }));
+        messages
+            .iter()
+            .any(|m| is_gesture_start(&m.event))
+            .then_some(offset_ms)
+    }
+
+    /// 设置录制的前台应用
+    pub fn set_app(&self, app: String) {
+        if let Some(recording) = self.active.lock().unwrap().as_mut() {
+            recording.app = Some(app);
+        }
+    }
+
+    /// 在后台截取屏幕并作为指定偏移处的截图加入录制
+    pub fn spawn_capture_frame(self: &Arc<Self>, device_serial: &str, offset_ms: u64) {
+        let recorder = Arc::clone(self);
+        let device_serial = device_serial.to_string();
+        tokio::spawn(async move {
+            match capture_screenshot(&device_serial).await {
+                Ok(screenshot) => recorder.add_frame(offset_ms, screenshot),
+                Err(e) => tracing::warn!("录制截图失败: {}", e),
+            }
+        });
+    }
+
+    /// 添加截图（未在录制时忽略）
+    pub fn add_frame(&self, offset_ms: u64, screenshot: String) {
+        if let Some(recording) = self.active.lock().unwrap().as_mut() {
+            let pos = recording.frames.partition_point(|f| f.offset_ms <= offset_ms);
+            recording.frames.insert(pos, MacroFrame { offset_ms, screenshot });
+        }
+    }
+
+    /// 停止录制并生成宏
+    pub fn stop(&self, device_serial: &str) -> Result<DeviceMacro, String> {
+        let recording = self
+            .active
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| "当前没有进行中的录制".to_string())?;
+
+        Ok(DeviceMacro {
+            name: recording.name,
+            device_serial: device_serial.to_string(),
+            created_at: Utc::now(),
+            duration_ms: recording.started.elapsed().as_millis() as u64,
+            actions: summarize(&recording.events),
+            events: recording.events,
+            app: recording.app,
+            frames: recording.frames,
+        })
+    }
+}
+
+/// 是否为需要截图的手势起点
+fn is_gesture_start(event: &ControlEvent) -> bool {
+    match event {
+        ControlEvent::Touch { action, .. } | ControlEvent::Key { action, .. } => *action == ACTION_DOWN,
+        ControlEvent::Text { .. } => true,
+        _ => false,
+    }
+}
+
+/// 截取设备屏幕，返回 base64 编码的 PNG
+pub async fn capture_screenshot(device_serial: &str) -> Result<String, String> {
+    let output = crate::platform::adb_device_command(device_serial)
+        .args(["exec-out", "screencap", "-p"])
+        .output()
+        .await
+        .map_err(|e| format!("截图失败: {:?}", e))?;
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err("截图命令执行失败".to_string());
+    }
+    Ok(BASE64_STANDARD.encode(&output.stdout))
+}
+
+/// 查询设备前台应用包名
+pub async fn foreground_app(device_serial: &str) -> Option<String> {
+    let output = crate::platform::adb_device_command(device_serial)
+        .args(["shell", "dumpsys window windows | grep -E 'mCurrentFocus'"])
+        .output()
+        .await
+        .ok()?;
+    // 格式: "mCurrentFocus=Window{... u0 com.package.name/com.activity.Name}"
+    let output = String::from_utf8_lossy(&output.stdout);
+    let component = output.split_whitespace().find(|part| part.contains('/'))?;
+    Some(component.split('/').next()?.to_string())
+}
+
+/// 校验宏名称，避免路径穿越
+pub(crate) fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("无效的宏名称: {:?}（仅允许字母、数字、- 和 _）", name))
+    }
+}
+
+/// 宏文件存储，按设备分目录保存为 JSON
+pub struct MacroStore {
+    dir: PathBuf,
+}
+
+impl MacroStore {
+    /// 创建指定设备的宏存储
+    pub fn for_device(device_serial: &str) -> Self {
+        Self { dir: PathBuf::from(MACRO_DIR).join(crate::platform::sanitize_file_name(device_serial)) }
+    }
+
+    /// 保存宏
+    pub async fn save(&self, device_macro: &DeviceMacro) -> Result<PathBuf, String> {
+        validate_name(&device_macro.name)?;
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| format!("创建宏目录失败: {:?}", e))?;
+        let path = self.dir.join(format!("{}.json", device_macro.name));
+        let json = serde_json::to_vec_pretty(device_macro).map_err(|e| e.to_string())?;
+        tokio::fs::write(&path, json)
+            .await
+            .map_err(|e| format!("写入宏文件失败: {:?}", e))?;
+        Ok(path)
+    }
+
+    /// 加载宏
+    pub async fn load(&self, name: &str) -> Result<DeviceMacro, String> {
+        validate_name(name)?;
+        let path = self.dir.join(format!("{}.json", name));
+        let json = tokio::fs::read(&path)
+            .await
+            .map_err(|e| format!("读取宏文件 {:?} 失败: {:?}", path, e))?;
+        serde_json::from_slice(&json).map_err(|e| format!("解析宏文件失败: {}", e))
+    }
+
+    /// 列出所有宏名称
+    pub async fn list(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        if let Ok(mut entries) = tokio::fs::read_dir(&self.dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let path = entry.path();
+                if path.extension().is_some_and(|ext| ext == "json")
+                    && let Some(stem) = path.file_stem()
+                {
+                    names.push(stem.to_string_lossy().to_string());
+                }
+            }
+        }
+        names.sort();
+        names
+    }
+}
+
+/// 按原始时间间隔回放宏，返回注入的消息数
+pub async fn play(
+    device_macro: &DeviceMacro,
+    control_write: Arc<Mutex<Option<OwnedWriteHalf>>>,
+) -> Result<usize, String> {
+    let started = Instant::now();
+    for (i, event) in device_macro.events.iter().enumerate() {
+        tokio::time::sleep_until(started + Duration::from_millis(event.offset_ms)).await;
+        let mut guard = control_write.lock().await;
+        let write_half = guard
+            .as_mut()
+            .ok_or_else(|| format!("control socket 未就绪（已回放 {} 条）", i))?;
+        write_half
+            .write_all(&event.raw)
+            .await
+            .map_err(|e| format!("写入 control socket 失败: {:?}", e))?;
+    }
+    Ok(device_macro.events.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrcpy::control::{parse_messages, tests::{key, touch}};
+
+    /// 触摸动作：移动
+    const ACTION_MOVE: u8 = 2;
+
+    fn event(offset_ms: u64, raw: Vec<u8>) -> MacroEvent {
+        let message = parse_messages(&raw).remove(0);
+        MacroEvent { offset_ms, raw: message.raw, event: message.event }
+    }
+
+    #[test]
+    fn test_summarize_gestures() {
+        let events = vec![
+            event(0, touch(ACTION_DOWN, 100, 100)),
+            event(80, touch(ACTION_UP, 102, 101)),
+            event(1000, touch(ACTION_DOWN, 500, 1500)),
+            event(1100, touch(ACTION_MOVE, 500, 1000)),
+            event(1300, touch(ACTION_UP, 500, 500)),
+            event(2000, touch(ACTION_DOWN, 300, 300)),
+            event(2800, touch(ACTION_UP, 300, 300)),
+            event(3000, key(ACTION_DOWN, 4)),
+            event(3050, key(ACTION_UP, 4)),
+        ];
+
+        let actions = summarize(&events);
+        assert_eq!(actions.len(), 4);
+        assert!(matches!(actions[0], MacroAction::Tap { x: 100, y: 100, .. }));
+        assert!(matches!(actions[1], MacroAction::Swipe { from_y: 1500, to_y: 500, duration_ms: 300, .. }));
+        assert!(matches!(actions[2], MacroAction::LongPress { duration_ms: 800, .. }));
+        assert_eq!(actions[3], MacroAction::Key { offset_ms: 3000, keycode: 4 });
+    }
+
+    #[test]
+    fn test_recorder_roundtrip() {
+        let recorder = MacroRecorder::new();
+        assert!(recorder.stop("emulator-5554").is_err());
+        assert!(recorder.start("../escape").is_err());
+
+        recorder.start("login").unwrap();
+        assert!(recorder.start("other").is_err());
+        assert!(recorder.record(&parse_messages(&key(ACTION_DOWN, 3))).is_some());
+        assert!(recorder.record(&parse_messages(&key(ACTION_UP, 3))).is_none());
+        recorder.add_frame(0, "png".to_string());
+        let recorded = recorder.stop("emulator-5554").unwrap();
+        assert!(!recorder.is_recording());
+
+        let json = serde_json::to_string(&recorded).unwrap();
+        let restored: DeviceMacro = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.events[0].raw, key(ACTION_DOWN, 3));
+        assert_eq!(restored.actions.len(), 1);
+        assert_eq!(restored.frames.len(), 1);
+    }
+}
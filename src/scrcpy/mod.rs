@@ -1 +1,15 @@
-pub mod scrcpy;
\ No newline at end of file
+pub mod scrcpy;
+pub mod server_version;
+pub mod jar_cache;
+pub mod options;
+pub mod stats;
+pub mod session_state;
+pub mod server_error;
+pub mod stream_cache;
+pub mod control;
+pub mod control_auth;
+pub mod input_guard;
+pub mod pointers;
+pub mod macro_recorder;
+pub mod device_info;
+pub mod overlay;
@@ -0,0 +1,188 @@
+//! scrcpy 会话选项
+//!
+//! 包含视频编码等启动参数，以及与客户端的编码能力协商。
+
+use serde::{Deserialize, Serialize};
+
+/// 视频编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoCodec {
+    H264,
+    H265,
+    Av1,
+}
+
+impl VideoCodec {
+    /// 默认协商顺序：优先压缩率更高的编码，H.264 兜底
+    pub const PREFERENCE: [VideoCodec; 3] = [VideoCodec::H265, VideoCodec::Av1, VideoCodec::H264];
+
+    /// scrcpy-server 的 `video_codec` 参数值
+    pub fn as_arg(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "h264",
+            VideoCodec::H265 => "h265",
+            VideoCodec::Av1 => "av1",
+        }
+    }
+
+    /// 从客户端声明的名称解析编码（兼容 avc/hevc 等别名）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "h264" | "h.264" | "avc" => Some(VideoCodec::H264),
+            "h265" | "h.265" | "hevc" => Some(VideoCodec::H265),
+            "av1" => Some(VideoCodec::Av1),
+            _ => None,
+        }
+    }
+}
+
+/// scrcpy 会话选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrcpyOptions {
+    /// 偏好的视频编码；所有客户端都支持时优先使用，否则按默认顺序协商
+    pub video_codec: Option<VideoCodec>,
+
+    /// 视频最大边长
+    pub max_size: u32,
+
+    /// 会话运行中超过多少秒收不到视频帧时看门狗结束设备上的 scrcpy-server 并重启会话（0 表示关闭看门狗）
+    #[serde(default = "default_frame_stall_timeout_secs")]
+    pub frame_stall_timeout_secs: u64,
+
+    /// 客户端需要先通过 `scrcpy_ctl_auth` 提交 `/connect` 返回的控制令牌才能注入控制事件
+    #[serde(default = "default_require_control_token")]
+    pub require_control_token: bool,
+
+    /// 每个客户端每秒最多发送的控制消息数（0 表示不限制）
+    #[serde(default = "default_max_control_messages_per_sec")]
+    pub max_control_messages_per_sec: u32,
+
+    /// 有客户端观看时的最大帧率（0 表示不限制）
+    #[serde(default)]
+    pub max_fps: u32,
+
+    /// 所有客户端都在后台（Agent 监控、缩略图、隐藏的标签页）时的最大帧率，0 表示关闭低功耗模式
+    #[serde(default = "default_low_power_max_fps")]
+    pub low_power_max_fps: u32,
+}
+
+fn default_frame_stall_timeout_secs() -> u64 {
+    30
+}
+
+fn default_require_control_token() -> bool {
+    true
+}
+
+fn default_max_control_messages_per_sec() -> u32 {
+    200
+}
+
+fn default_low_power_max_fps() -> u32 {
+    2
+}
+
+impl Default for ScrcpyOptions {
+    fn default() -> Self {
+        Self {
+            video_codec: None,
+            max_size: 1920,
+            frame_stall_timeout_secs: default_frame_stall_timeout_secs(),
+            require_control_token: default_require_control_token(),
+            max_control_messages_per_sec: default_max_control_messages_per_sec(),
+            max_fps: 0,
+            low_power_max_fps: default_low_power_max_fps(),
+        }
+    }
+}
+
+/// 客户端是否能解码该编码（未声明编码能力的客户端视为仅支持 H.264）
+pub fn client_supports(client_codecs: &[VideoCodec], codec: VideoCodec) -> bool {
+    if client_codecs.is_empty() {
+        codec == VideoCodec::H264
+    } else {
+        client_codecs.contains(&codec)
+    }
+}
+
+impl ScrcpyOptions {
+    /// 根据所有客户端声明的编码能力选出最佳编码，跳过设备上已确认不可用的编码
+    ///
+    /// 未声明编码能力的客户端视为仅支持 H.264；H.264 始终作为最后的选择。
+    pub fn negotiate_codec<'a, I>(&self, client_codecs: I, unavailable: &[VideoCodec]) -> VideoCodec
+    where
+        I: IntoIterator<Item = &'a Vec<VideoCodec>>,
+    {
+        let client_codecs: Vec<&Vec<VideoCodec>> = client_codecs.into_iter().collect();
+        let supported_by_all = |codec: VideoCodec| client_codecs.iter().all(|codecs| client_supports(codecs, codec));
+
+        self.video_codec
+            .into_iter()
+            .chain(VideoCodec::PREFERENCE)
+            .find(|codec| !unavailable.contains(codec) && supported_by_all(*codec))
+            .unwrap_or(VideoCodec::H264)
+    }
+
+    /// 会话使用的最大帧率（0 表示不限制）：没有客户端在观看时进入低功耗模式
+    pub fn session_max_fps(&self, has_viewers: bool) -> u32 {
+        if has_viewers || self.low_power_max_fps == 0 {
+            self.max_fps
+        } else {
+            self.low_power_max_fps
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_codec() {
+        let options = ScrcpyOptions::default();
+
+        let hevc_clients = vec![
+            vec![VideoCodec::H265, VideoCodec::H264],
+            vec![VideoCodec::H265, VideoCodec::Av1, VideoCodec::H264],
+        ];
+        assert_eq!(options.negotiate_codec(&hevc_clients, &[]), VideoCodec::H265);
+
+        // 未声明能力的客户端只支持 H.264
+        let mixed_clients = vec![vec![VideoCodec::H265, VideoCodec::H264], vec![]];
+        assert_eq!(options.negotiate_codec(&mixed_clients, &[]), VideoCodec::H264);
+
+        let preferred = ScrcpyOptions {
+            video_codec: Some(VideoCodec::Av1),
+            ..Default::default()
+        };
+        assert_eq!(preferred.negotiate_codec(&hevc_clients, &[]), VideoCodec::H265);
+        assert_eq!(
+            preferred.negotiate_codec(&vec![vec![VideoCodec::Av1, VideoCodec::H265]], &[]),
+            VideoCodec::Av1
+        );
+        // 设备编码器不可用的编码不再协商，最终回退到 H.264
+        assert_eq!(
+            preferred.negotiate_codec(&vec![vec![VideoCodec::Av1, VideoCodec::H265]], &[VideoCodec::Av1]),
+            VideoCodec::H265
+        );
+        assert_eq!(options.negotiate_codec(&hevc_clients, &[VideoCodec::H265, VideoCodec::Av1]), VideoCodec::H264);
+    }
+
+    #[test]
+    fn test_session_max_fps() {
+        let options = ScrcpyOptions::default();
+        assert_eq!(options.session_max_fps(true), 0);
+        assert_eq!(options.session_max_fps(false), 2);
+
+        let disabled = ScrcpyOptions { max_fps: 30, low_power_max_fps: 0, ..Default::default() };
+        assert_eq!(disabled.session_max_fps(false), 30);
+    }
+
+    #[test]
+    fn test_codec_from_name() {
+        assert_eq!(VideoCodec::from_name("HEVC"), Some(VideoCodec::H265));
+        assert_eq!(VideoCodec::from_name("avc"), Some(VideoCodec::H264));
+        assert_eq!(VideoCodec::from_name("vp9"), None);
+    }
+}
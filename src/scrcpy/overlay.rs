@@ -0,0 +1,136 @@
+//! Agent 操作提示
+//!
+//! Agent 控制正在投屏的设备时，每个操作都通过设备的 Socket.IO 命名空间发送 `agent_action` 事件，
+//! 网页查看器据此在画面上绘制点击、滑动等提示。坐标为相对屏幕宽高的比例（0~1），与视频缩放无关。
+
+use serde::Serialize;
+
+/// Agent 操作使用的逻辑坐标范围（1000x1000）
+pub const LOGICAL_SCALE: u32 = 1000;
+
+/// 像素坐标换算为逻辑坐标，`size` 为该方向的屏幕像素数
+pub fn to_logical(value: u32, size: u32) -> u32 {
+    (value.min(size) as u64 * LOGICAL_SCALE as u64 / size.max(1) as u64) as u32
+}
+
+/// `agent_action` 事件内容
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentActionOverlay {
+    /// 操作类型：tap、long_press、swipe、key、text
+    #[serde(rename = "type")]
+    pub action_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_y: Option<f64>,
+    /// 滑动经过的点（比例坐标）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<(f64, f64)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u32>,
+    /// 按键码或输入的文本
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// 逻辑坐标转换为比例坐标
+fn ratio(value: u32) -> f64 {
+    (value as f64 / LOGICAL_SCALE as f64).clamp(0.0, 1.0)
+}
+
+impl AgentActionOverlay {
+    fn new(action_type: &'static str) -> Self {
+        Self {
+            action_type,
+            x: None,
+            y: None,
+            end_x: None,
+            end_y: None,
+            path: Vec::new(),
+            duration_ms: None,
+            label: None,
+        }
+    }
+
+    /// 点击
+    pub fn tap(x: u32, y: u32) -> Self {
+        Self {
+            x: Some(ratio(x)),
+            y: Some(ratio(y)),
+            ..Self::new("tap")
+        }
+    }
+
+    /// 滑动，起点与终点相同时为长按
+    pub fn swipe(start: (u32, u32), end: (u32, u32), duration_ms: u32) -> Self {
+        if start == end {
+            return Self {
+                duration_ms: Some(duration_ms),
+                ..Self::tap(start.0, start.1)
+            }
+            .with_type("long_press");
+        }
+        Self {
+            x: Some(ratio(start.0)),
+            y: Some(ratio(start.1)),
+            end_x: Some(ratio(end.0)),
+            end_y: Some(ratio(end.1)),
+            duration_ms: Some(duration_ms),
+            ..Self::new("swipe")
+        }
+    }
+
+    /// 沿轨迹滑动
+    pub fn swipe_path(points: &[(u32, u32)], duration_ms: u32) -> Self {
+        let (first, last) = (points.first().copied().unwrap_or_default(), points.last().copied().unwrap_or_default());
+        Self {
+            path: points.iter().map(|&(x, y)| (ratio(x), ratio(y))).collect(),
+            ..Self::swipe(first, last, duration_ms)
+        }
+    }
+
+    /// 按键
+    pub fn key(keycode: u32) -> Self {
+        Self {
+            label: Some(keycode.to_string()),
+            ..Self::new("key")
+        }
+    }
+
+    /// 输入文本
+    pub fn text(text: &str) -> Self {
+        Self {
+            label: Some(text.to_string()),
+            ..Self::new("text")
+        }
+    }
+
+    fn with_type(mut self, action_type: &'static str) -> Self {
+        self.action_type = action_type;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_events() {
+        let tap = serde_json::to_value(AgentActionOverlay::tap(500, 250)).unwrap();
+        assert_eq!(tap, serde_json::json!({"type": "tap", "x": 0.5, "y": 0.25}));
+
+        let long_press = AgentActionOverlay::swipe((100, 100), (100, 100), 800);
+        assert_eq!((long_press.action_type, long_press.duration_ms), ("long_press", Some(800)));
+
+        let swipe = AgentActionOverlay::swipe_path(&[(500, 800), (500, 500), (500, 200)], 300);
+        assert_eq!((swipe.action_type, swipe.end_y, swipe.path.len()), ("swipe", Some(0.2), 3));
+
+        // 超出逻辑范围的坐标截断到屏幕边缘
+        assert_eq!(AgentActionOverlay::tap(1200, 0).x, Some(1.0));
+    }
+}
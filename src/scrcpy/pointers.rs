@@ -0,0 +1,135 @@
+//! 多客户端触摸指针隔离
+//!
+//! 同一设备在多个标签页中打开时，各客户端通常都使用相同的指针 ID（如 0），设备会把交错到达的触摸流当成同一根手指，
+//! 产生“幽灵触摸”。每个客户端分配一个编号，触摸消息的指针 ID 映射为 `(编号 + 1) << 32 | 低 32 位`，
+//! 不同客户端的手势互不干扰。客户端断开时还按下的指针补发抬起事件，避免设备上残留按下状态。
+//! 映射后鼠标（-1）等特殊指针 ID 按普通手指处理。
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use super::control::{
+    encode_touch, ControlEvent, ControlMessage, ACTION_CANCEL, ACTION_DOWN, ACTION_MOVE, ACTION_POINTER_DOWN,
+    ACTION_POINTER_UP, ACTION_UP,
+};
+
+/// 按下中的指针最后的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ActivePointer {
+    x: i32,
+    y: i32,
+    screen_width: u16,
+    screen_height: u16,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// 客户端 ID -> 编号
+    slots: HashMap<String, u32>,
+    /// 客户端 ID -> 映射后的指针 ID -> 最后位置
+    active: HashMap<String, HashMap<u64, ActivePointer>>,
+}
+
+/// 一个设备会话的指针映射
+#[derive(Debug, Default)]
+pub struct PointerRouter {
+    inner: Mutex<Inner>,
+}
+
+/// 客户端编号对应的指针 ID
+fn namespaced(slot: u32, pointer_id: u64) -> u64 {
+    ((slot as u64 + 1) << 32) | (pointer_id & 0xffff_ffff)
+}
+
+impl PointerRouter {
+    /// 将客户端触摸消息的指针 ID 改写为该客户端独占的 ID（同时改写原始字节）
+    pub fn route(&self, client_id: &str, messages: &mut [ControlMessage]) {
+        let mut inner = self.inner.lock().unwrap();
+        let slot = match inner.slots.get(client_id) {
+            Some(slot) => *slot,
+            None => {
+                let used: BTreeSet<u32> = inner.slots.values().copied().collect();
+                let slot = (0..).find(|slot| !used.contains(slot)).unwrap();
+                inner.slots.insert(client_id.to_string(), slot);
+                slot
+            }
+        };
+        let active = inner.active.entry(client_id.to_string()).or_default();
+
+        for message in messages {
+            let ControlEvent::Touch { action, pointer_id, x, y, screen_width, screen_height, .. } = &mut message.event else {
+                continue;
+            };
+            *pointer_id = namespaced(slot, *pointer_id);
+            message.raw[2..10].copy_from_slice(&pointer_id.to_be_bytes());
+            match *action {
+                ACTION_DOWN | ACTION_POINTER_DOWN | ACTION_MOVE => {
+                    let position = ActivePointer { x: *x, y: *y, screen_width: *screen_width, screen_height: *screen_height };
+                    active.insert(*pointer_id, position);
+                }
+                ACTION_UP | ACTION_POINTER_UP | ACTION_CANCEL => {
+                    active.remove(pointer_id);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// 客户端断开：释放编号，返回其仍按下的指针的抬起消息（可直接写入控制 socket）
+    pub fn release(&self, client_id: &str) -> Vec<u8> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.slots.remove(client_id);
+        let Some(active) = inner.active.remove(client_id) else {
+            return Vec::new();
+        };
+        let mut pointers: Vec<(u64, ActivePointer)> = active.into_iter().collect();
+        pointers.sort_by_key(|(pointer_id, _)| *pointer_id);
+        pointers
+            .into_iter()
+            .flat_map(|(pointer_id, p)| encode_touch(ACTION_UP, pointer_id, p.x, p.y, p.screen_width, p.screen_height, 0))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrcpy::control::parse_messages;
+    use crate::scrcpy::control::tests::touch;
+
+    fn pointer_ids(messages: &[ControlMessage]) -> Vec<u64> {
+        messages
+            .iter()
+            .filter_map(|m| match m.event {
+                ControlEvent::Touch { pointer_id, .. } => Some(pointer_id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_clients_get_distinct_pointer_ids() {
+        let router = PointerRouter::default();
+        let mut first = parse_messages(&touch(ACTION_DOWN, 100, 200));
+        let mut second = parse_messages(&touch(ACTION_DOWN, 300, 400));
+        router.route("tab-a", &mut first);
+        router.route("tab-b", &mut second);
+
+        let (a, b) = (pointer_ids(&first)[0], pointer_ids(&second)[0]);
+        assert_ne!(a, b);
+        // 原始字节同步改写
+        assert_eq!(pointer_ids(&parse_messages(&first[0].raw)), vec![a]);
+
+        // tab-a 抬起后没有残留，tab-b 断开时补发抬起
+        router.route("tab-a", &mut parse_messages(&touch(ACTION_UP, 100, 200)));
+        assert!(router.release("tab-a").is_empty());
+        let release = parse_messages(&router.release("tab-b"));
+        assert_eq!(pointer_ids(&release), vec![b]);
+        assert!(matches!(release[0].event, ControlEvent::Touch { action: ACTION_UP, x: 300, y: 400, .. }));
+
+        // 编号释放后复用
+        let mut third = parse_messages(&touch(ACTION_DOWN, 1, 1));
+        router.route("tab-c", &mut third);
+        assert_eq!(pointer_ids(&third)[0], a);
+    }
+}
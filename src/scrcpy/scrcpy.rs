@@ -2,30 +2,64 @@ use adb_client::server_device::ADBServerDevice;
 use socketioxide::{SocketIo, socket::DisconnectReason};
 use bytes::Bytes;
 use std::net::TcpListener;
-use std::sync::Arc;
-use std::collections::HashSet;
-use tokio::sync::mpsc;
+use std::sync::{Arc, Mutex as StdMutex, OnceLock, RwLock as StdRwLock};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 use tracing::{info, error, debug, warn};
 use tower_http::cors::{CorsLayer, Any};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
-use rust_embed::RustEmbed;
 use crate::logger::DeviceLogger;
-
-/// 嵌入的资源文件
-#[derive(RustEmbed)]
-#[folder = "assets/"]
-struct Assets;
-
-/// Socket read state machine for handling first two special messages
+use super::jar_cache;
+use super::server_error::{self, ServerErrorCode};
+use super::server_version::{new_scid, select_server, query_device_sdk};
+use super::options::{ScrcpyOptions, VideoCodec, client_supports};
+use super::stream_cache::{PacketSplitter, StreamCache};
+use super::stats::{FrameCounter, SessionStats};
+use super::session_state::{SessionState, SessionStateSnapshot, SessionStateTracker};
+use super::input_guard::{validate_packet, ControlRejection, RateLimiter};
+use super::control_auth::{ControlAuth, ControlToken};
+use super::pointers::PointerRouter;
+use super::macro_recorder::{self, DeviceMacro, MacroRecorder, MacroStore};
+use super::overlay::AgentActionOverlay;
+
+/// Socket read state machine for handling the device metadata message
+/// (the 1 byte acknowledgment is consumed by the readiness handshake)
 enum ReadState {
-    ReadAck,   // Read 1 byte acknowledgment
     ReadMeta,  // Read 64 bytes device metadata
     ReadData,  // Normal data forwarding
 }
 
+/// scrcpy-server.jar 在设备上的路径
+const SERVER_JAR_DEVICE_PATH: &str = "/data/local/tmp/scrcpy-server.jar";
+
+/// 周期性推送 scrcpy_stats 事件的间隔
+const STATS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 看门狗检查视频帧停滞的间隔
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(2);
+/// scrcpy-server 在设备上的主类，不支持 `scid` 的旧版本服务端按此结束 app_process
+const SERVER_MAIN_CLASS: &str = "com.genymobile.scrcpy.Server";
+
+/// 所有客户端转入后台后等待多久再进入低功耗模式，客户端短时间内反复切换前后台时不会反复重启会话
+const LOW_POWER_DEBOUNCE: Duration = Duration::from_secs(5);
+
+/// 已收到补发数据、接收视频流广播的客户端所在的房间
+const LIVE_ROOM: &str = "live";
+
+/// 等待 scrcpy-server 就绪的最长时间（包含推送 jar 的耗时）
+const READY_TIMEOUT: Duration = Duration::from_secs(20);
+/// 就绪轮询的初始退避间隔
+const READY_INITIAL_BACKOFF: Duration = Duration::from_millis(50);
+/// 就绪轮询的最大退避间隔
+const READY_MAX_BACKOFF: Duration = Duration::from_millis(1000);
+/// 单次读取确认字节的超时时间
+const BANNER_READ_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// 跟踪单个 scrcpy 会话的所有动态管理任务
 struct ScrcpySessionTasks {
     /// scrcpy-server.jar ADB shell 任务句柄
@@ -38,23 +72,40 @@ struct ScrcpySessionTasks {
     broadcast_handle: Option<JoinHandle<()>>,
     /// 共享的写句柄 (scrcpy_ctl -> device)
     scrcpy_control_write: Arc<Mutex<Option<tokio::net::tcp::OwnedWriteHalf>>>,
-    /// 所有连接的 Socket.IO 客户端 ID 集合
-    connected_clients: HashSet<String>,
+    /// 所有连接的 Socket.IO 客户端 ID -> 客户端声明支持的视频编码
+    connected_clients: HashMap<String, Vec<VideoCodec>>,
+    /// 在后台、不需要实时画面的客户端 ID
+    background_clients: HashSet<String>,
     /// 设备元数据 (设备名称)
     device_meta: Option<String>,
+    /// 本次会话使用的 scrcpy-server 版本
+    server_version: Option<String>,
+    /// 本次会话协商出的视频编码
+    video_codec: Option<VideoCodec>,
+    /// 本次会话的最大帧率（0 表示不限制）
+    max_fps: Option<u32>,
 }
 
 impl ScrcpySessionTasks {
     /// 创建新的会话任务跟踪器
     fn new() -> Self {
+        Self::with_control_write(Arc::new(Mutex::new(None)))
+    }
+
+    /// 使用共享的控制写句柄创建会话任务跟踪器
+    fn with_control_write(scrcpy_control_write: Arc<Mutex<Option<tokio::net::tcp::OwnedWriteHalf>>>) -> Self {
         Self {
             scrcpy_jar_handle: None,
             socket_read_handle: None,
             socket_write_handle: None,
             broadcast_handle: None,
-            scrcpy_control_write: Arc::new(Mutex::new(None)),
-            connected_clients: HashSet::new(),
+            scrcpy_control_write,
+            connected_clients: HashMap::new(),
+            background_clients: HashSet::new(),
             device_meta: None,
+            server_version: None,
+            video_codec: None,
+            max_fps: None,
         }
     }
 
@@ -88,10 +139,14 @@ impl ScrcpySessionTasks {
         // 清空所有连接的客户端
         let client_count = self.connected_clients.len();
         self.connected_clients.clear();
+        self.background_clients.clear();
         info!("已清空所有连接的客户端，共 {} 个", client_count);
 
         // 清空设备元数据
         self.device_meta = None;
+        self.server_version = None;
+        self.video_codec = None;
+        self.max_fps = None;
     }
 
     /// 只中止任务，保留客户端集合（用于重启会话）
@@ -126,22 +181,33 @@ impl ScrcpySessionTasks {
 
     /// 移除一个客户端，如果没有剩余客户端则返回 true
     fn remove_client(&mut self, client_id: &str) -> bool {
-        let removed = self.connected_clients.remove(client_id);
+        let removed = self.connected_clients.remove(client_id).is_some();
+        self.background_clients.remove(client_id);
         if removed {
             info!("移除客户端: {}, 剩余客户端数: {}", client_id, self.connected_clients.len());
         }
         self.connected_clients.is_empty()  // 如果没有客户端剩余则返回 true
     }
 
-    /// 添加一个新的客户端
-    fn add_client(&mut self, client_id: String) {
-        self.connected_clients.insert(client_id);
+    /// 添加一个新的客户端，并记录其支持的视频编码与是否在后台
+    fn add_client(&mut self, client_id: String, video_codecs: Vec<VideoCodec>, background: bool) {
+        self.set_background(&client_id, background);
+        self.connected_clients.insert(client_id, video_codecs);
         info!("添加客户端, 当前客户端数: {}", self.connected_clients.len());
     }
 
-    /// 检查会话是否正在运行
-    fn is_session_running(&self) -> bool {
-        self.scrcpy_jar_handle.is_some()
+    /// 标记客户端是否在后台
+    fn set_background(&mut self, client_id: &str, background: bool) {
+        if background {
+            self.background_clients.insert(client_id.to_string());
+        } else {
+            self.background_clients.remove(client_id);
+        }
+    }
+
+    /// 是否有客户端需要实时画面
+    fn has_viewers(&self) -> bool {
+        self.connected_clients.keys().any(|id| !self.background_clients.contains(id))
     }
 }
 
@@ -163,62 +229,371 @@ struct ScrcpySessionState {
     io: Arc<SocketIo>,
     /// 设备日志记录器
     logger: Arc<DeviceLogger>,
+    /// 会话选项
+    options: ScrcpyOptions,
+    /// 会话统计
+    stats: Arc<SessionStats>,
+    /// 会话状态
+    state_tracker: Arc<SessionStateTracker>,
+    /// 新客户端加入时补发的视频流数据
+    live: Mutex<StreamCache>,
+    /// 本会话启动 scrcpy-server 使用的会话 ID，结束进程时只匹配该实例
+    scid: u32,
+    /// 最近一次启动的服务端不支持 `scid`，只能按主类名结束进程
+    legacy_server: AtomicBool,
+    /// 每次客户端前后台变化时递增，等待进入低功耗模式期间又有变化时放弃本次切换
+    stream_mode_generation: AtomicU64,
+    /// 设备上启动失败（编码器不可用）的编码，之后的会话不再协商这些编码
+    unavailable_codecs: StdMutex<Vec<VideoCodec>>,
+}
+
+impl ScrcpySessionState {
+    /// 结束设备上本会话的 scrcpy-server 进程时 `pkill -f` 使用的匹配模式
+    fn server_process_pattern(&self) -> String {
+        if self.legacy_server.load(Ordering::Relaxed) {
+            SERVER_MAIN_CLASS.to_string()
+        } else {
+            format!("scid={:08x}", self.scid)
+        }
+    }
+}
+
+/// 客户端连接时通过 Socket.IO auth 声明的能力
+#[derive(Debug, Default, serde::Deserialize)]
+struct ClientHandshake {
+    /// 客户端支持解码的视频编码（如 `["h265", "h264"]`）
+    #[serde(default)]
+    video_codecs: Vec<String>,
+    /// 客户端不需要实时画面（Agent 监控、缩略图等），只有这类客户端时会话进入低功耗模式
+    #[serde(default)]
+    background: bool,
+}
+
+impl ClientHandshake {
+    /// 解析出可识别的编码，忽略未知名称
+    fn codecs(&self) -> Vec<VideoCodec> {
+        self.video_codecs
+            .iter()
+            .filter_map(|name| VideoCodec::from_name(name))
+            .collect()
+    }
+}
+
+/// 动态分配一个本机可用的 TCP 端口
+///
+/// 绑定 `127.0.0.1:0` 由系统分配端口后立即释放监听器，供调用方使用
+pub fn allocate_local_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .expect("Failed to bind to an available port");
+    let port = listener.local_addr()
+        .expect("Failed to get local address")
+        .port();
+    drop(listener); // 释放监听器，让调用方使用
+    port
+}
+
+/// 设备 Socket.IO 服务的监听配置
+#[derive(Debug, Clone)]
+pub struct SocketIoListen {
+    /// 监听地址，默认 `127.0.0.1`，需要远程访问时设置为 `0.0.0.0`
+    pub host: String,
+    /// 端口范围（含两端），未设置时由系统随机分配
+    pub port_range: Option<(u16, u16)>,
+    /// 单端口模式：不单独监听，挂载到 API 服务的 `/scrcpy/{serial}/socket.io` 路径，值为 API 服务端口
+    pub mounted_port: Option<u16>,
+    /// 反向代理下的路径前缀（如 `/scrs`），为空表示不使用
+    pub base_path: String,
+}
+
+impl Default for SocketIoListen {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port_range: None,
+            mounted_port: None,
+            base_path: String::new(),
+        }
+    }
+}
+
+static SOCKET_IO_LISTEN: OnceLock<SocketIoListen> = OnceLock::new();
+
+/// 设置设备 Socket.IO 服务的监听配置，需在创建首个 [`ScrcpyConnect`] 前调用
+pub fn init_socket_io_listen(listen: SocketIoListen) -> &'static SocketIoListen {
+    SOCKET_IO_LISTEN.get_or_init(|| listen)
+}
+
+/// 设备 Socket.IO 服务的监听配置
+pub fn socket_io_listen() -> &'static SocketIoListen {
+    SOCKET_IO_LISTEN.get_or_init(SocketIoListen::default)
+}
+
+/// 客户端连接设备 Socket.IO 时使用的请求路径（包含路径前缀）
+pub fn socket_io_path(serial: &str) -> String {
+    let listen = socket_io_listen();
+    if listen.mounted_port.is_some() {
+        format!("{}{}", listen.base_path, mounted_socket_io_path(serial))
+    } else {
+        format!("{}/socket.io", listen.base_path)
+    }
+}
+
+/// 单端口模式下设备 Socket.IO 相对 API 路由的请求路径（路径前缀已由 API 路由剥离）
+fn mounted_socket_io_path(serial: &str) -> String {
+    format!("/scrcpy/{}/socket.io", serial)
+}
+
+/// 为设备 Socket.IO 服务分配端口
+///
+/// 配置了端口范围时从上次分配的位置起依次尝试绑定，否则由系统分配
+fn allocate_socket_io_port(listen: &SocketIoListen) -> u16 {
+    static NEXT: AtomicU16 = AtomicU16::new(0);
+
+    let Some((start, end)) = listen.port_range else {
+        let listener = TcpListener::bind((listen.host.as_str(), 0))
+            .expect("Failed to bind to an available port");
+        return listener.local_addr().expect("Failed to get local address").port();
+    };
+    let len = end.saturating_sub(start) as u32 + 1;
+    for _ in 0..len {
+        let offset = NEXT.fetch_add(1, Ordering::Relaxed) as u32 % len;
+        let port = start + offset as u16;
+        if TcpListener::bind((listen.host.as_str(), port)).is_ok() {
+            return port;
+        }
+    }
+    warn!("端口范围 {}-{} 内没有可用端口，改为随机分配", start, end);
+    allocate_socket_io_port(&SocketIoListen { port_range: None, ..listen.clone() })
 }
 
 pub struct ScrcpyConnect {
     port: u16,
     scrcpy_server_port: u16,
+    options: ScrcpyOptions,
+    stats: Arc<SessionStats>,
+    /// 会话状态
+    state_tracker: Arc<SessionStateTracker>,
+    /// 控制 socket 写句柄（会话与宏回放共享）
+    control_write: Arc<Mutex<Option<tokio::net::tcp::OwnedWriteHalf>>>,
+    /// 宏录制器
+    recorder: Arc<MacroRecorder>,
+    /// scrcpy_ctl 控制令牌与已授权的客户端
+    control_auth: Arc<ControlAuth>,
+    /// 各客户端的触摸指针 ID 映射
+    pointers: Arc<PointerRouter>,
+    /// 单端口模式下的 Socket.IO 路由，由 API 服务转发请求
+    mounted: StdRwLock<Option<axum::Router>>,
+    /// 设备的 Socket.IO 服务（投屏服务运行时可用）
+    io: StdRwLock<Option<Arc<SocketIo>>>,
+    /// 正在运行的投屏服务（[`ScrcpyConnect::start`] 启动，[`ScrcpyConnect::stop`] 停止）
+    running: StdMutex<Option<RunningService>>,
+}
+
+/// 投屏服务的停止句柄
+struct RunningService {
+    shutdown: watch::Sender<bool>,
+    handle: JoinHandle<()>,
 }
 
 impl ScrcpyConnect {
 
     pub fn new(scrcpy_server_port: u16) -> ScrcpyConnect {
-        // 动态分配可用端口
-        let listener = TcpListener::bind("127.0.0.1:0")
-            .expect("Failed to bind to an available port");
-        let port = listener.local_addr()
-            .expect("Failed to get local address")
-            .port();
-        drop(listener); // 释放监听器，让 socketio 使用
+        let listen = socket_io_listen();
+        let port = match listen.mounted_port {
+            Some(port) => port,
+            None => allocate_socket_io_port(listen),
+        };
 
-        info!("为设备动态分配 socketio 端口: {}", port);
+        info!("为设备分配 socketio 端口: {}, scrcpy 转发端口: {}", port, scrcpy_server_port);
         ScrcpyConnect {
             port,
-            scrcpy_server_port
+            scrcpy_server_port,
+            options: ScrcpyOptions::default(),
+            stats: Arc::new(SessionStats::new()),
+            state_tracker: Arc::new(SessionStateTracker::new()),
+            control_write: Arc::new(Mutex::new(None)),
+            recorder: Arc::new(MacroRecorder::new()),
+            control_auth: Arc::new(ControlAuth::default()),
+            pointers: Arc::new(PointerRouter::default()),
+            mounted: StdRwLock::new(None),
+            io: StdRwLock::new(None),
+            running: StdMutex::new(None),
         }
     }
 
+    /// 获取会话统计
+    pub fn stats(&self) -> &Arc<SessionStats> {
+        &self.stats
+    }
+
+    /// 当前会话状态
+    pub fn session_state(&self) -> SessionStateSnapshot {
+        self.state_tracker.snapshot()
+    }
+
+    /// 订阅会话状态变化
+    pub fn subscribe_session_state(&self) -> watch::Receiver<SessionStateSnapshot> {
+        self.state_tracker.subscribe()
+    }
+
+    /// 设置会话选项
+    pub fn with_options(mut self, options: ScrcpyOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     pub fn get_port(&self) -> u16 {
         self.port
     }
 
+    /// 在后台启动投屏服务，已启动时不重复启动，返回本次是否启动
+    pub fn start(self: &Arc<Self>, device: Arc<ADBServerDevice>) -> bool {
+        let mut running = self.running.lock().unwrap();
+        if running.as_ref().is_some_and(|r| !r.handle.is_finished()) {
+            return false;
+        }
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let handle = tokio::spawn(Arc::clone(self).run(device, shutdown_rx));
+        *running = Some(RunningService { shutdown, handle });
+        true
+    }
+
+    /// 投屏服务是否正在运行
+    pub fn is_started(&self) -> bool {
+        self.running.lock().unwrap().as_ref().is_some_and(|r| !r.handle.is_finished())
+    }
+
+    /// 停止投屏服务并等待清理完成：断开所有客户端、关闭 Socket.IO 监听、中止会话任务、
+    /// 删除 adb 端口转发并结束设备上的 scrcpy-server。未运行时返回 false
+    pub async fn stop(&self) -> bool {
+        let Some(running) = self.running.lock().unwrap().take() else {
+            return false;
+        };
+        let _ = running.shutdown.send(true);
+        if let Err(e) = running.handle.await
+            && e.is_panic()
+        {
+            warn!("投屏服务异常退出: {:?}", e);
+        }
+        true
+    }
+
+    /// 停止并重新启动投屏服务（Socket.IO 端口不变，客户端需重新连接）
+    pub async fn restart(self: &Arc<Self>, device: Arc<ADBServerDevice>) {
+        self.stop().await;
+        self.start(device);
+    }
+
+    /// 单端口模式下挂载的 Socket.IO 路由（投屏服务运行时可用）
+    pub fn mounted_router(&self) -> Option<axum::Router> {
+        self.mounted.read().unwrap().clone()
+    }
+
+    /// 向正在观看投屏的客户端发送 Agent 操作提示（`agent_action` 事件），没有客户端时忽略
+    pub fn emit_agent_action(&self, overlay: AgentActionOverlay) {
+        let Some(io) = self.io.read().unwrap().clone() else {
+            return;
+        };
+        if self.stats.clients() == 0 {
+            return;
+        }
+        tokio::spawn(async move {
+            if let Err(e) = io.emit("agent_action", &overlay).await {
+                debug!("发送 agent_action 失败: {:?}", e);
+            }
+        });
+    }
+
+    /// 签发 scrcpy_ctl 控制令牌
+    pub fn issue_control_token(&self) -> ControlToken {
+        self.control_auth.issue()
+    }
+
+    /// 已获得控制权限的客户端
+    pub fn control_clients(&self) -> Vec<String> {
+        self.control_auth.clients()
+    }
+
+    /// 撤销客户端的控制权限（连同它使用的令牌）并通知失去权限的客户端（`scrcpy_ctl_revoked` 事件），
+    /// 返回之前是否已授权
+    pub fn revoke_control(&self, client_id: &str) -> bool {
+        let revoked = self.control_auth.revoke(client_id);
+        let io = self.io.read().unwrap().clone();
+        for id in &revoked {
+            let socket = io.as_ref().and_then(|io| io.get_socket(id.parse().ok()?));
+            if let Some(socket) = socket {
+                let _ = socket.emit("scrcpy_ctl_revoked", &serde_json::json!({ "client_id": id }));
+            }
+        }
+        !revoked.is_empty()
+    }
+
+    /// 开始录制宏（捕获之后收到的 scrcpy_ctl 控制消息及手势开始时的截图）
+    pub async fn start_macro_recording(&self, device_serial: &str, name: &str) -> Result<(), String> {
+        self.recorder.start(name)?;
+        self.recorder.spawn_capture_frame(device_serial, 0);
+        if let Some(app) = macro_recorder::foreground_app(device_serial).await {
+            self.recorder.set_app(app);
+        }
+        Ok(())
+    }
+
+    /// 停止录制并保存宏文件
+    pub async fn stop_macro_recording(&self, device_serial: &str) -> Result<DeviceMacro, String> {
+        let device_macro = self.recorder.stop(device_serial)?;
+        let path = MacroStore::for_device(device_serial).save(&device_macro).await?;
+        info!("宏 {} 已保存到 {:?} ({} 条事件)", device_macro.name, path, device_macro.events.len());
+        Ok(device_macro)
+    }
+
+    /// 按原始时间间隔回放已保存的宏，返回注入的消息数
+    pub async fn play_macro(&self, device_serial: &str, name: &str) -> Result<usize, String> {
+        let device_macro = MacroStore::for_device(device_serial).load(name).await?;
+        macro_recorder::play(&device_macro, Arc::clone(&self.control_write)).await
+    }
+
     /**
      * 运行连接 - 事件驱动模式
-     * Socket.IO 服务器持续运行，scrcpy-server 在客户端连接时启动
-     * 注意：调用此方法前，需要确保 ADB 端口转发已设置
+     * Socket.IO 服务器持续运行直到收到停止信号，scrcpy-server 在客户端连接时启动
      */
-    pub async fn run(self: Arc<Self>, device: Arc<ADBServerDevice>) {
+    async fn run(self: Arc<Self>, device: Arc<ADBServerDevice>, mut shutdown: watch::Receiver<bool>) {
         let scrcpy_server_port = self.scrcpy_server_port;
         let socket_io_port = self.port;
 
         // 获取设备序列号用于日志
         let device_serial = device.identifier.as_ref().map(|s| s.as_str()).unwrap_or("unknown");
+        let recorder_serial = device_serial.to_string();
 
         // 创建设备日志记录器
         let logger = Arc::new(DeviceLogger::new(device_serial));
         logger.info(&format!("初始化 Socket.IO 服务器，端口: {}", socket_io_port));
 
         // 创建 Socket.IO 服务器
-        let (layer, io) = SocketIo::new_layer();
+        let mounted = socket_io_listen().mounted_port.is_some();
+        let req_path = if mounted {
+            mounted_socket_io_path(device_serial)
+        } else {
+            socket_io_path(device_serial)
+        };
+        let (layer, io) = SocketIo::builder().req_path(req_path).build_layer();
         let io = Arc::new(io);
+        *self.io.write().unwrap() = Some(Arc::clone(&io));
 
         // 创建会话状态
         let session_state = Arc::new(ScrcpySessionState {
-            session: Arc::new(Mutex::new(ScrcpySessionTasks::new())),
+            session: Arc::new(Mutex::new(ScrcpySessionTasks::with_control_write(Arc::clone(&self.control_write)))),
             device,
             scrcpy_server_port,
             io: io.clone(),
             logger: logger.clone(),
+            options: self.options.clone(),
+            stats: Arc::clone(&self.stats),
+            state_tracker: Arc::clone(&self.state_tracker),
+            live: Mutex::new(StreamCache::default()),
+            scid: new_scid(),
+            legacy_server: AtomicBool::new(false),
+            stream_mode_generation: AtomicU64::new(0),
+            unavailable_codecs: StdMutex::new(Vec::new()),
         });
 
         let cors = CorsLayer::new()
@@ -230,18 +605,35 @@ impl ScrcpyConnect {
             .layer(cors)
             .layer(layer);
 
-        let listener: tokio::net::TcpListener = tokio::net::TcpListener::bind(format!("127.0.0.1:{}", socket_io_port))
-            .await
-            .expect("Failed to bind socketio server");
-
-        info!("Socket.IO 服务器运行在端口: {}, 等待客户端连接...", socket_io_port);
+        let listener = if mounted {
+            info!("Socket.IO 服务器挂载于 {}, 等待客户端连接...", socket_io_path(&recorder_serial));
+            None
+        } else {
+            let addr = format!("{}:{}", socket_io_listen().host, socket_io_port);
+            let listener = tokio::net::TcpListener::bind(&addr)
+                .await
+                .expect("Failed to bind socketio server");
+            info!("Socket.IO 服务器运行在: {}, 等待客户端连接...", addr);
+            Some(listener)
+        };
 
         // 设置事件处理器
         let state_clone = session_state.clone();
+        let recorder = Arc::clone(&self.recorder);
+        let control_auth = Arc::clone(&self.control_auth);
+        let pointers = Arc::clone(&self.pointers);
+        let require_control_token = self.options.require_control_token;
         let logger_clone = Arc::clone(&logger);
-        io.ns("/", move |s: socketioxide::extract::SocketRef| async move {
+        io.ns("/", move |s: socketioxide::extract::SocketRef, auth: socketioxide::extract::TryData<ClientHandshake>| async move {
             let state = state_clone.clone();
+            let self_recorder = Arc::clone(&recorder);
+            let control_auth = Arc::clone(&control_auth);
+            let pointers = Arc::clone(&pointers);
+            let self_recorder_serial = recorder_serial.clone();
             let socket_id = s.id.to_string();
+            let handshake = auth.0.unwrap_or_default();
+            let video_codecs = handshake.codecs();
+            let background = handshake.background;
             let logger_events = Arc::clone(&logger_clone);
 
             logger_events.info(&format!("客户端连接: {}", socket_id));
@@ -266,35 +658,79 @@ impl ScrcpyConnect {
                 }));
             });
 
+            // scrcpy_ctl_auth 事件处理器：提交控制令牌获取控制权限
+            let control_auth_handshake = Arc::clone(&control_auth);
+            let logger_auth = Arc::clone(&logger_events);
+            s.on("scrcpy_ctl_auth", move |s: socketioxide::extract::SocketRef, data: socketioxide::extract::Data<serde_json::Value>| async move {
+                let client_id = s.id.to_string();
+                let token = data.0.get("token").and_then(|v| v.as_str()).unwrap_or_default();
+                let response = match control_auth_handshake.authorize(&client_id, token) {
+                    Ok(()) => {
+                        logger_auth.info(&format!("客户端 {} 获得控制权限", client_id));
+                        serde_json::json!({ "success": true })
+                    }
+                    Err(e) => {
+                        logger_auth.warn(&format!("客户端 {} 控制令牌校验失败: {}", client_id, e));
+                        serde_json::json!({ "success": false, "error": e })
+                    }
+                };
+                let _ = s.emit("scrcpy_ctl_auth_response", &response);
+            });
+
             // scrcpy_ctl 事件处理器
             let scrcpy_control_write_ref = scrcpy_control_write.clone();
             let logger_ctl = Arc::clone(&logger_events);
+            let stats_ctl = Arc::clone(&state.stats);
+            let recorder_ctl = Arc::clone(&self_recorder);
+            let recorder_serial_ctl = self_recorder_serial.clone();
             let socket_id_ctl = socket_id.clone();
+            let control_auth_ctl = Arc::clone(&control_auth);
+            let state_ctl = state.clone();
+            let pointers_ctl = Arc::clone(&pointers);
+            // 每个客户端单独限流
+            let rate_limiter = (state.options.max_control_messages_per_sec > 0)
+                .then(|| Arc::new(StdMutex::new(RateLimiter::new(state.options.max_control_messages_per_sec))));
             s.on("scrcpy_ctl", move |s: socketioxide::extract::SocketRef, data: socketioxide::extract::Data<Bytes>| async move {
+                stats_ctl.record_control_message();
+                let checked = if require_control_token && !control_auth_ctl.is_authorized(&socket_id_ctl) {
+                    Err(ControlRejection::unauthorized())
+                } else {
+                    let frame_size = state_ctl.live.lock().await.frame_size();
+                    validate_packet(&data.0, frame_size).and_then(|messages| match &rate_limiter {
+                        Some(limiter) => limiter.lock().unwrap().try_acquire(messages.len()).map(|()| messages),
+                        None => Ok(messages),
+                    })
+                };
+                let mut messages = match checked {
+                    Ok(messages) => messages,
+                    Err(rejection) => {
+                        stats_ctl.record_control_rejected();
+                        logger_ctl.warn(&format!("丢弃客户端 {} 的 scrcpy_ctl 数据包 ({:?}): {}", socket_id_ctl, rejection.code, rejection.message));
+                        let _ = s.emit("scrcpy_ctl_error", &rejection.to_event(data.0.len()));
+                        return;
+                    }
+                };
+                // 多个标签页同时操作时各自使用独立的指针 ID
+                pointers_ctl.route(&socket_id_ctl, &mut messages);
+                let payload: Vec<u8> = messages.iter().flat_map(|m| m.raw.iter().copied()).collect();
+                if recorder_ctl.is_recording()
+                    && let Some(offset_ms) = recorder_ctl.record(&messages)
+                {
+                    recorder_ctl.spawn_capture_frame(&recorder_serial_ctl, offset_ms);
+                }
                 logger_ctl.debug(&format!("收到 scrcpy_ctl 事件 (客户端: {})，数据长度: {} 字节", socket_id_ctl, data.0.len()));
-                info!("收到 scrcpy_ctl 事件，数据长度: {} 字节", data.0.len());
-
-                // 输出完整的32字节hex数据
-                let hex_str: String = data.0.iter().map(|b| format!("{:02x}", b)).collect();
-                logger_ctl.debug(&format!("完整数据hex: {}", hex_str));
-                info!("完整数据hex: {}", hex_str);
-
-                // 解析关键字段用于调试
-                if data.0.len() >= 24 {
-                    let action = data.0[1];
-                    let x = u32::from_le_bytes([data.0[10], data.0[11], data.0[12], data.0[13]]);
-                    let y = u32::from_le_bytes([data.0[14], data.0[15], data.0[16], data.0[17]]);
-                    let pressure = u16::from_be_bytes([data.0[22], data.0[23]]);
-                    logger_ctl.debug(&format!("解析控制指令: action={}, x={}, y={}, pressure={}", action, x, y, pressure));
-                    info!("解析: action={}, x={}, y={}, pressure={}", action, x, y, pressure);
+                debug!("收到 scrcpy_ctl 事件，数据长度: {} 字节", data.0.len());
+                for message in &messages {
+                    logger_ctl.debug(&format!("解析控制指令: {:?}", message.event));
                 }
 
                 let mut write_guard = scrcpy_control_write_ref.lock().await;
                 if let Some(ref mut write_half) = *write_guard {
-                    if let Err(e) = write_half.write_all(&data.0).await {
+                    if let Err(e) = write_half.write_all(&payload).await {
                         logger_ctl.error(&format!("写入 scrcpy control socket 失败: {:?}", e));
                         error!("写入 scrcpy control socket 失败: {:?}", e);
                         let _ = s.emit("scrcpy_ctl_error", &serde_json::json!({
+                            "code": "write_failed",
                             "error": format!("写入失败: {:?}", e),
                             "length": data.0.len()
                         }));
@@ -310,23 +746,48 @@ impl ScrcpyConnect {
                     logger_ctl.warn("Scrcpy control socket 写句柄未就绪");
                     warn!("Scrcpy control socket 写句柄未就绪");
                     let _ = s.emit("scrcpy_ctl_error", &serde_json::json!({
+                        "code": "not_ready",
                         "error": "control socket 未就绪",
                         "length": data.0.len()
                     }));
                 }
             });
 
-            // 连接处理器 - 启动 scrcpy 会话
+            // scrcpy_visibility 事件处理器：客户端切换到后台（如标签页隐藏）或回到前台，按需切换低功耗模式
+            let state_visibility = state.clone();
+            s.on("scrcpy_visibility", move |s: socketioxide::extract::SocketRef, data: socketioxide::extract::Data<serde_json::Value>| async move {
+                let background = data.0.get("background").and_then(|v| v.as_bool()).unwrap_or(false);
+                {
+                    let mut session = state_visibility.session.lock().await;
+                    if !session.connected_clients.contains_key(&s.id.to_string()) {
+                        return;
+                    }
+                    session.set_background(&s.id.to_string(), background);
+                }
+                tokio::spawn(schedule_stream_mode(state_visibility));
+            });
+
+            // 连接处理器 - 加入或启动 scrcpy 会话
             let state_for_connect = state.clone();
-            let socket_id_for_connect = socket_id.clone();
+            let socket_for_connect = s.clone();
             tokio::spawn(async move {
-                handle_client_connect(state_for_connect, socket_id_for_connect).await;
+                handle_client_connect(state_for_connect, socket_for_connect, video_codecs, background).await;
             });
 
             // 断开连接处理器 - 停止 scrcpy 会话
             let logger_disconnect = Arc::clone(&logger_events);
+            let control_write_disconnect = scrcpy_control_write.clone();
             s.on_disconnect(move |s: socketioxide::extract::SocketRef, _reason: DisconnectReason| async move {
                 let socket_id = s.id.to_string();
+                control_auth.disconnect(&socket_id);
+                // 补发该客户端仍按下的指针的抬起事件
+                let release = pointers.release(&socket_id);
+                if !release.is_empty()
+                    && let Some(write_half) = control_write_disconnect.lock().await.as_mut()
+                    && let Err(e) = write_half.write_all(&release).await
+                {
+                    warn!("补发客户端 {} 的抬起事件失败: {:?}", socket_id, e);
+                }
                 logger_disconnect.info(&format!("客户端断开连接: {}", socket_id));
                 info!("客户端断开连接: {}", socket_id);
 
@@ -334,52 +795,312 @@ impl ScrcpyConnect {
 
                 // 移除客户端并检查是否是最后一个
                 let should_abort = session.remove_client(&socket_id);
+                state.stats.set_clients(session.connected_clients.len());
 
                 if should_abort {
                     logger_disconnect.warn(&format!("最后一个客户端断开，中止 scrcpy 会话: {}", socket_id));
                     info!("最后一个客户端断开，中止 scrcpy 会话: {}", socket_id);
                     session.abort_all().await;
+                    state.state_tracker.stop_session();
+                    state.stats.session_stopped();
                 } else {
                     logger_disconnect.info(&format!("客户端 {} 断开，但仍有 {} 个客户端连接，会话继续",
                           socket_id, session.connected_clients.len()));
                     info!("客户端 {} 断开，但仍有 {} 个客户端连接，会话继续",
                           socket_id, session.connected_clients.len());
+                    // 最后一个观看的客户端离开后进入低功耗模式
+                    drop(session);
+                    tokio::spawn(schedule_stream_mode(Arc::clone(&state)));
                 }
             });
         });
 
-        // 只运行 Socket.IO 服务器
-        axum::serve(listener, app).await.unwrap();
+        // 有客户端连接时周期性推送会话统计
+        let stats_for_emit = Arc::clone(&self.stats);
+        let io_for_stats = io.clone();
+        let stats_handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(STATS_INTERVAL);
+            loop {
+                interval.tick().await;
+                if stats_for_emit.clients() == 0 {
+                    continue;
+                }
+                if let Err(e) = io_for_stats.emit("scrcpy_stats", &stats_for_emit.snapshot()).await {
+                    debug!("发送 scrcpy_stats 失败: {:?}", e);
+                }
+            }
+        });
+
+        // 会话状态变化时通知客户端
+        let mut state_rx = self.state_tracker.subscribe();
+        let io_for_state = io.clone();
+        let state_handle = tokio::spawn(async move {
+            while state_rx.changed().await.is_ok() {
+                let snapshot = state_rx.borrow_and_update().clone();
+                if let Err(e) = io_for_state.emit("scrcpy_state", &snapshot).await {
+                    debug!("发送 scrcpy_state 失败: {:?}", e);
+                }
+            }
+        });
+
+        // 看门狗：视频帧停滞时结束设备上的 scrcpy-server 并重启会话
+        let watchdog_handle = (self.options.frame_stall_timeout_secs > 0).then(|| {
+            let state_for_watchdog = session_state.clone();
+            let stall_timeout = Duration::from_secs(self.options.frame_stall_timeout_secs);
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(WATCHDOG_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if state_for_watchdog.stats.clients() == 0 {
+                        continue;
+                    }
+                    let Some(stall) = state_for_watchdog.stats.frame_stall() else {
+                        continue;
+                    };
+                    if stall >= stall_timeout {
+                        restart_stalled_session(Arc::clone(&state_for_watchdog), stall).await;
+                    } else if stall >= stall_timeout / 2 {
+                        // 停滞超过一半超时时间先标记为降级，恢复出帧后回到 Streaming
+                        let tracker = &state_for_watchdog.state_tracker;
+                        if tracker.current() == SessionState::Streaming {
+                            tracker.transition(
+                                tracker.session(),
+                                SessionState::Degraded,
+                                Some(format!("{:.1} 秒未收到视频帧", stall.as_secs_f64())),
+                            );
+                        }
+                    }
+                }
+            })
+        });
+
+        // 运行 Socket.IO 服务器直到收到停止信号；单端口模式下交由 API 服务转发请求
+        let stop_signal = async move {
+            let _ = shutdown.wait_for(|stop| *stop).await;
+        };
+        match listener {
+            Some(listener) => {
+                tokio::select! {
+                    result = axum::serve(listener, app) => {
+                        if let Err(e) = result {
+                            logger.error(&format!("Socket.IO 服务器异常退出: {:?}", e));
+                        }
+                    }
+                    _ = stop_signal => {}
+                }
+            }
+            None => {
+                *self.mounted.write().unwrap() = Some(app);
+                stop_signal.await;
+            }
+        }
+
+        // 停止：不再接受连接，断开所有客户端并清理会话与设备上的资源
+        logger.info("停止投屏服务");
+        *self.mounted.write().unwrap() = None;
+        *self.io.write().unwrap() = None;
+        stats_handle.abort();
+        state_handle.abort();
+        if let Some(handle) = watchdog_handle {
+            handle.abort();
+        }
+        io.close().await;
+        session_state.session.lock().await.abort_all().await;
+        self.state_tracker.stop_session();
+        self.stats.set_clients(0);
+        self.stats.session_stopped();
+
+        let device_serial = session_state.device.identifier.clone().unwrap_or_default();
+        remove_forward(&device_serial, scrcpy_server_port, &logger).await;
+        kill_device_server(&device_serial, &session_state.server_process_pattern(), &logger).await;
+        info!("设备 {} 投屏服务已停止，Socket.IO 端口 {} 已释放", device_serial, socket_io_port);
+    }
+}
+
+/// 删除本会话端口的 adb 转发
+async fn remove_forward(device_serial: &str, scrcpy_server_port: u16, logger: &DeviceLogger) {
+    match crate::platform::adb_device_command(device_serial)
+        .args(["forward", "--remove", &format!("tcp:{}", scrcpy_server_port)])
+        .output()
+        .await
+    {
+        Ok(output) if output.status.success() => logger.info(&format!("已删除端口转发: tcp:{}", scrcpy_server_port)),
+        Ok(output) => logger.debug(&format!("删除端口转发失败: {}", String::from_utf8_lossy(&output.stderr).trim())),
+        Err(e) => logger.warn(&format!("删除端口转发命令执行失败: {:?}", e)),
+    }
+}
+
+/// 结束设备上本会话的 scrcpy-server 进程（中止 adb shell 任务不一定能结束设备上的 app_process）
+///
+/// 按启动参数中的 `scid` 匹配，不影响同一设备上其他会话或其他工具启动的 scrcpy-server
+async fn kill_device_server(device_serial: &str, pattern: &str, logger: &DeviceLogger) {
+    match crate::platform::adb_device_command(device_serial)
+        .args(["shell", "pkill", "-9", "-f", pattern])
+        .output()
+        .await
+    {
+        Ok(output) if output.status.success() => logger.info("已结束设备上的 scrcpy-server 进程"),
+        Ok(output) => logger.warn(&format!("结束 scrcpy-server 进程失败: {}", String::from_utf8_lossy(&output.stderr).trim())),
+        Err(e) => logger.warn(&format!("结束 scrcpy-server 进程失败: {:?}", e)),
     }
 }
 
 /// 处理客户端连接事件
-async fn handle_client_connect(state: Arc<ScrcpySessionState>, socket_id: String) {
+///
+/// 会话已在运行且新客户端能解码当前编码时直接加入广播，不打断其他客户端的画面；
+/// 否则重启会话按所有客户端重新协商编码
+async fn handle_client_connect(
+    state: Arc<ScrcpySessionState>,
+    socket: socketioxide::extract::SocketRef,
+    video_codecs: Vec<VideoCodec>,
+    background: bool,
+) {
+    let socket_id = socket.id.to_string();
     let mut session = state.session.lock().await;
 
     // 添加此客户端到连接集合
-    session.add_client(socket_id.clone());
+    state.logger.info(&format!("客户端 {} 声明支持的视频编码: {:?}，后台: {}", socket_id, video_codecs, background));
+    let compatible = session.video_codec.is_none_or(|codec| client_supports(&video_codecs, codec));
+    session.add_client(socket_id.clone(), video_codecs, background);
+    state.stats.set_clients(session.connected_clients.len());
+    let max_fps = state.options.session_max_fps(session.has_viewers());
 
     // 检查是否已有会话在运行
-    if session.is_session_running() {
-        info!("新客户端 {} 连接，中止旧的 scrcpy 任务并重启（保留所有客户端）", socket_id);
+    if state.state_tracker.current().is_active() && compatible && session.max_fps.is_none_or(|fps| fps == max_fps) {
+        info!("新客户端 {} 加入正在运行的 scrcpy 会话，当前客户端数: {}", socket_id, session.connected_clients.len());
+        drop(session);
+    } else if state.state_tracker.current().is_active() {
+        if compatible {
+            info!("新客户端 {} 需要实时画面，退出低功耗模式并重启 scrcpy 会话（保留所有客户端）", socket_id);
+        } else {
+            info!("新客户端 {} 不支持当前视频编码 {:?}，中止旧的 scrcpy 任务并重启（保留所有客户端）", socket_id, session.video_codec);
+        }
         // 只中止任务，保留客户端集合
         session.abort_tasks_only().await;
         // 等待清理完成
         drop(session);
         tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
         // 启动新的会话（会广播给所有客户端）
-        start_scrcpy_session(state, socket_id).await;
+        start_scrcpy_session(Arc::clone(&state), socket_id).await;
     } else {
         info!("第一个客户端连接，启动新的 scrcpy 会话");
         drop(session);
-        start_scrcpy_session(state, socket_id).await;
+        start_scrcpy_session(Arc::clone(&state), socket_id).await;
+    }
+    join_live_stream(&state, &socket).await;
+}
+
+/// 向客户端补发设备元数据与缓存的视频流（编码元数据、配置包、最近的关键帧及之后的帧），然后加入广播
+///
+/// 广播任务在持有同一把锁时更新缓存并推送，补发与加入之间不会漏掉或重复数据
+async fn join_live_stream(state: &ScrcpySessionState, socket: &socketioxide::extract::SocketRef) {
+    use base64::prelude::*;
+
+    let live = state.live.lock().await;
+    if let Some(meta) = live.device_meta()
+        && let Err(e) = socket.emit("scrcpy_device_meta", meta)
+    {
+        debug!("向客户端 {} 补发设备元数据失败: {:?}", socket.id, e);
+    }
+    let catch_up = live.catch_up();
+    if !catch_up.is_empty() {
+        state.logger.info(&format!("向客户端 {} 补发 {} 字节视频流", socket.id, catch_up.len()));
+        if let Err(e) = socket.emit("scrcpy", &BASE64_STANDARD.encode(&catch_up)) {
+            debug!("向客户端 {} 补发视频流失败: {:?}", socket.id, e);
+        }
+    }
+    socket.join(LIVE_ROOM);
+}
+
+/// 客户端离开或切换前后台后切换会话帧率：有客户端观看时立即恢复，没有时等待 [`LOW_POWER_DEBOUNCE`]
+/// 期间没有新的变化才进入低功耗模式
+async fn schedule_stream_mode(state: Arc<ScrcpySessionState>) {
+    let generation = state.stream_mode_generation.fetch_add(1, Ordering::SeqCst) + 1;
+    if !state.session.lock().await.has_viewers() {
+        tokio::time::sleep(LOW_POWER_DEBOUNCE).await;
+        if state.stream_mode_generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+    }
+    apply_stream_mode(state).await;
+}
+
+/// 按是否还有客户端在观看切换会话帧率（进入或退出低功耗模式）
+async fn apply_stream_mode(state: Arc<ScrcpySessionState>) {
+    let mut session = state.session.lock().await;
+    let Some(client_id) = session.connected_clients.keys().next().cloned() else {
+        return;
+    };
+    let max_fps = state.options.session_max_fps(session.has_viewers());
+    if !state.state_tracker.current().is_active() || session.max_fps.is_none_or(|fps| fps == max_fps) {
+        return;
+    }
+
+    if session.has_viewers() {
+        state.logger.info("有客户端开始观看，退出低功耗模式并重启 scrcpy 会话");
+    } else {
+        state.logger.info(&format!("没有客户端在观看，进入低功耗模式（最大 {} 帧/秒）并重启 scrcpy 会话", max_fps));
     }
+    session.abort_tasks_only().await;
+    drop(session);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    start_scrcpy_session(state, client_id).await;
+}
+
+/// 视频帧停滞时结束设备上的 scrcpy-server 进程并为现有客户端重启会话
+async fn restart_stalled_session(state: Arc<ScrcpySessionState>, stall: Duration) {
+    let device_serial = state.device.identifier.clone().unwrap_or_default();
+    let mut session = state.session.lock().await;
+    let Some(client_id) = session.connected_clients.keys().next().cloned() else {
+        return;
+    };
+    if !state.state_tracker.current().is_active() {
+        return;
+    }
+
+    let restarts = state.stats.record_watchdog_restart();
+    state.logger.error(&format!("看门狗: {:.1} 秒未收到视频帧，结束 scrcpy-server 并重启会话（第 {} 次）", stall.as_secs_f64(), restarts));
+    warn!("设备 {} 的 scrcpy 会话 {:.1} 秒未收到视频帧，看门狗重启会话（第 {} 次）", device_serial, stall.as_secs_f64(), restarts);
+
+    session.abort_tasks_only().await;
+    drop(session);
+
+    // 卡死的进程需要直接结束
+    kill_device_server(&device_serial, &state.server_process_pattern(), &state.logger).await;
+
+    if let Err(e) = state.io.emit("scrcpy_watchdog", &serde_json::json!({
+        "stalled_secs": stall.as_secs(),
+        "restarts": restarts,
+    })).await {
+        debug!("发送 scrcpy_watchdog 失败: {:?}", e);
+    }
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    start_scrcpy_session(state, client_id).await;
+}
+
+/// 编码器不可用导致启动失败后，以重新协商的编码为现有客户端重启会话
+///
+/// 由会话自身的任务发起，返回装箱的 future 以打断与 [`start_scrcpy_session`] 之间的递归类型
+fn restart_with_fallback_codec(state: Arc<ScrcpySessionState>) -> std::pin::Pin<Box<dyn Future<Output = ()> + Send>> {
+    Box::pin(async move {
+        let mut session = state.session.lock().await;
+        let Some(client_id) = session.connected_clients.keys().next().cloned() else {
+            return;
+        };
+        session.abort_tasks_only().await;
+        drop(session);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        start_scrcpy_session(state, client_id).await;
+    })
 }
 
 /// 启动 scrcpy 会话的所有任务
 async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id: String) {
     state.logger.info(&format!("为客户端 {} 启动 scrcpy 会话", client_socket_id));
+    state.stats.session_started();
+    let session_id = state.state_tracker.start_session();
+    state.live.lock().await.reset();
 
     // 创建通信通道
     let (scrcpy_data_tx, mut scrcpy_data_rx) = mpsc::unbounded_channel::<Vec<u8>>();
@@ -387,7 +1108,9 @@ async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id:
     let scrcpy_control_write = Arc::clone(&state.session.lock().await.scrcpy_control_write);
     let device = Arc::clone(&state.device);
     let io = Arc::clone(&state.io);
-    let socket_addr = format!("127.0.0.1:{}", state.scrcpy_server_port);
+    // 远程 adb server 的端口转发监听在其所在主机上
+    let forward_host = crate::adb_server::forward_host(device.identifier.as_deref().unwrap_or_default());
+    let socket_addr = format!("{}:{}", forward_host, state.scrcpy_server_port);
     let logger = Arc::clone(&state.logger);
 
     // 任务 1: 启动 scrcpy-server.jar (使用 ADB shell 命令)
@@ -395,36 +1118,74 @@ async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id:
     let client_socket_id_jar = client_socket_id.clone();
     let logger_jar = Arc::clone(&logger);
     let scrcpy_server_port = state.scrcpy_server_port;
+    let state_for_jar = state.clone();
+    let tracker_jar = Arc::clone(&state.state_tracker);
+    // 根据当前所有客户端的解码能力协商视频编码
+    let (negotiated_codec, max_fps) = {
+        let mut session = state.session.lock().await;
+        let unavailable = state.unavailable_codecs.lock().unwrap().clone();
+        let codec = state.options.negotiate_codec(session.connected_clients.values(), &unavailable);
+        // jar 任务确定实际使用的编码前，按协商结果判断新客户端能否直接加入
+        session.video_codec = Some(codec);
+        // 所有客户端都在后台时以低帧率运行
+        let max_fps = state.options.session_max_fps(session.has_viewers());
+        session.max_fps = Some(max_fps);
+        (codec, max_fps)
+    };
+    let (codec_tx, codec_rx) = oneshot::channel::<(VideoCodec, String)>();
     let scrcpy_jar_handle = tokio::spawn(async move {
         let device_serial = device_identifier.unwrap();
 
         logger_jar.info(&format!("scrcpy jar 任务启动 (客户端: {})", client_socket_id_jar));
 
-        // 步骤 1: 推送 scrcpy-server.jar 到设备
-        logger_jar.info(&format!("正在推送 scrcpy-server.jar 到设备 {}", device_serial));
-
-        // 获取嵌入的 jar 文件
-        let jar_data = Assets::get("jar/scrcpy-server-v3.3.4.jar");
-        if jar_data.is_none() {
-            logger_jar.error("无法找到嵌入的 scrcpy-server.jar 文件");
-            return;
+        // 步骤 0: 根据设备 Android 版本选择兼容的 scrcpy-server
+        let device_sdk = query_device_sdk(&device_serial).await;
+        let server = match select_server(device_sdk) {
+            Ok(server) => server,
+            Err(e) => {
+                logger_jar.error(&format!("选择 scrcpy-server 版本失败: {}", e));
+                tracker_jar.transition(session_id, SessionState::Degraded, Some(format!("选择 scrcpy-server 版本失败: {}", e)));
+                return;
+            }
+        };
+        logger_jar.info(&format!("设备 SDK: {:?}，选用 scrcpy-server {} ({:?})", device_sdk, server.version, server.source));
+
+        let video_codec = server.effective_codec(negotiated_codec);
+        logger_jar.info(&format!("协商视频编码: {:?}（实际使用 {:?}）", negotiated_codec, video_codec));
+        {
+            let mut session = state_for_jar.session.lock().await;
+            session.server_version = Some(server.version.clone());
+            session.video_codec = Some(video_codec);
         }
-
-        let jar_data = jar_data.unwrap().data.to_vec();
-
-        // 先将 jar 文件写入临时文件
-        let temp_jar_path = format!("/tmp/scrcpy-server-{}.jar", client_socket_id_jar);
-        if let Err(e) = tokio::fs::write(&temp_jar_path, &jar_data).await {
-            logger_jar.error(&format!("写入临时 jar 文件失败: {:?}", e));
-            return;
+        if max_fps > 0 {
+            logger_jar.info(&format!("最大帧率: {}", max_fps));
+        }
+        let _ = codec_tx.send((video_codec, server.version.clone()));
+        if let Err(e) = state_for_jar.io.emit("scrcpy_server_info", &serde_json::json!({
+            "server_version": server.version,
+            "device_sdk": device_sdk,
+            "max_fps": max_fps,
+        })).await {
+            logger_jar.warn(&format!("发送 scrcpy-server 版本信息失败: {:?}", e));
         }
 
-        logger_jar.debug(&format!("临时 jar 文件已创建: {}", temp_jar_path));
+        // 步骤 1: 推送 scrcpy-server.jar 到设备
+        tracker_jar.transition(session_id, SessionState::PushingJar, None);
+        logger_jar.info(&format!("正在推送 scrcpy-server.jar 到设备 {}", device_serial));
+
+        let jar_data = match server.load().await {
+            Ok(data) => data,
+            Err(e) => {
+                logger_jar.error(&e);
+                tracker_jar.transition(session_id, SessionState::Degraded, Some(e));
+                return;
+            }
+        };
 
-        // 删除所有的端口转发
-        logger_jar.debug("删除所有的 forward tcp");
-        let forward_remove_result = tokio::process::Command::new("adb")
-            .args(["-s", &device_serial, "forward", "--remove-all"])
+        // 删除本会话端口上可能残留的转发（不影响其他设备的转发）
+        logger_jar.debug(&format!("删除残留的 forward tcp:{}", scrcpy_server_port));
+        let forward_remove_result = crate::platform::adb_device_command(&device_serial)
+            .args(["forward", "--remove", &format!("tcp:{}", scrcpy_server_port)])
             .output()
             .await;
         match &forward_remove_result {
@@ -438,10 +1199,12 @@ async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id:
             }
         }
 
-        // 设置端口转发
-        logger_jar.debug(&format!("设置端口转发: tcp:{} -> localabstract:scrcpy", scrcpy_server_port));
-        let forward_result = tokio::process::Command::new("adb")
-            .args(["-s", &device_serial, "forward", &format!("tcp:{}", scrcpy_server_port), "localabstract:scrcpy"])
+        // 设置端口转发（2.0 起每个服务端实例监听 `scrcpy_<scid>`）
+        let socket_name = server.socket_name(state_for_jar.scid);
+        state_for_jar.legacy_server.store(!server.supports_scid(), Ordering::Relaxed);
+        logger_jar.debug(&format!("设置端口转发: tcp:{} -> localabstract:{}", scrcpy_server_port, socket_name));
+        let forward_result = crate::platform::adb_device_command(&device_serial)
+            .args(["forward", &format!("tcp:{}", scrcpy_server_port), &format!("localabstract:{}", socket_name)])
             .output()
             .await;
         match &forward_result {
@@ -457,34 +1220,39 @@ async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id:
             }
         }
 
-        // 使用 adb push 推送 jar 文件 (指定设备)
-        let push_result = tokio::process::Command::new("adb")
-            .args(["-s", &device_serial, "push", &temp_jar_path, "/data/local/tmp/scrcpy-server.jar"])
-            .output()
-            .await;
-
-        match &push_result {
-            Ok(output) => {
-                if output.status.success() {
+        // 设备上已有相同的 jar 时跳过推送，否则通过 ADB sync 协议直接推送内存中的 jar（不落地到主机文件系统）
+        let jar_sha256 = jar_cache::server_jar_sha256(&server, &jar_data);
+        let jar_size = jar_data.len();
+        if jar_cache::is_up_to_date(&device_serial, SERVER_JAR_DEVICE_PATH, &jar_sha256, jar_size).await {
+            logger_jar.info(&format!("设备上的 scrcpy-server.jar 与 {} 一致，跳过推送", server.version));
+        } else {
+            match push_server_jar(&device_serial, jar_data).await {
+                Ok(()) => {
+                    jar_cache::record_pushed(&device_serial, &jar_sha256, jar_size);
                     logger_jar.info("推送 scrcpy-server.jar 成功");
-                } else {
-                    logger_jar.error(&format!("推送失败: {:?}", String::from_utf8_lossy(&output.stderr)));
+                }
+                Err(e) => {
+                    logger_jar.error(&format!("推送失败: {}", e));
+                    tracker_jar.transition(session_id, SessionState::Degraded, Some(format!("推送 scrcpy-server.jar 失败: {}", e)));
                     return;
                 }
             }
-            Err(e) => {
-                logger_jar.error(&format!("adb push 执行失败: {:?}", e));
-                return;
-            }
         }
 
         // 步骤 2: 启动 scrcpy-server
-        let command = "CLASSPATH=/data/local/tmp/scrcpy-server.jar app_process / com.genymobile.scrcpy.Server 3.3.4 log_level=info audio=false max_size=1920 tunnel_forward=true";
+        let command = format!(
+            "CLASSPATH={} app_process / {} {} {}",
+            SERVER_JAR_DEVICE_PATH,
+            SERVER_MAIN_CLASS,
+            server.version,
+            server.launch_args(&state_for_jar.options, video_codec, max_fps, state_for_jar.scid).join(" ")
+        );
 
         logger_jar.info(&format!("正在为设备 {} 启动 scrcpy-server", device_serial));
+        tracker_jar.transition(session_id, SessionState::WaitingForServer, None);
 
-        let result = tokio::process::Command::new("adb")
-            .args(["-s", &device_serial, "shell", command])
+        let result = crate::platform::adb_device_command(&device_serial)
+            .args(["shell", &command])
             .output()
             .await;
 
@@ -498,72 +1266,81 @@ async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id:
                     logger_jar.error(&format!("scrcpy-server stderr: {}", String::from_utf8_lossy(&output.stderr)));
                 }
                 logger_jar.info(&format!("scrcpy jar 任务完成，退出码: {:?}", output.status));
+                let shell_output = format!("{}\n{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+                match server_error::diagnose(&shell_output) {
+                    // 设备不支持协商出的编码时记为不可用，回退到其它编码（最终为 H.264）重启会话
+                    Some(error)
+                        if error.code == ServerErrorCode::EncoderError
+                            && video_codec != VideoCodec::H264
+                            && tracker_jar.session() == session_id =>
+                    {
+                        logger_jar.warn(&format!("设备编码器不支持 {:?}，改用其它编码重启会话: {}", video_codec, error.message));
+                        state_for_jar.unavailable_codecs.lock().unwrap().push(video_codec);
+                        tokio::spawn(restart_with_fallback_codec(Arc::clone(&state_for_jar)));
+                    }
+                    // 会话已被替换（如客户端全部断开后服务端被结束）时不再通知
+                    Some(error) if tracker_jar.fail(session_id, error.clone()) => {
+                        logger_jar.error(&format!("scrcpy-server 启动失败 ({:?}): {}", error.code, error.message));
+                        if let Err(e) = state_for_jar.io.emit("scrcpy_error", &error).await {
+                            logger_jar.warn(&format!("发送 scrcpy_error 事件失败: {:?}", e));
+                        }
+                    }
+                    Some(_) => {}
+                    None => {
+                        tracker_jar.transition(session_id, SessionState::Degraded, Some(format!("scrcpy-server 已退出: {}", output.status)));
+                    }
+                }
             }
             Err(e) => {
                 logger_jar.error(&format!("启动 scrcpy jar 失败: {:?}", e));
+                tracker_jar.transition(session_id, SessionState::Degraded, Some(format!("启动 scrcpy-server 失败: {:?}", e)));
             }
         }
-
-        // 清理临时文件
-        let _ = std::fs::remove_file(&temp_jar_path);
     });
 
-    // 等待 jar 文件推送和 scrcpy-server 启动
-    // 推送 jar 文件可能需要一些时间，增加等待时间
-    tokio::time::sleep(tokio::time::Duration::from_millis(3000)).await;
+    // video socket 就绪后通知 control socket 开始连接
+    let (ready_tx, ready_rx) = oneshot::channel::<()>();
 
     // 创建 channel 的克隆，用于在任务间传递
     let scrcpy_data_tx_for_read = scrcpy_data_tx.clone();
     let state_for_read = state.clone();
     let io_for_read = io.clone();
+    let tracker_read = Arc::clone(&state.state_tracker);
 
     // 任务 2: TCP socket 读取数据
     let socket_addr_1 = socket_addr.clone();
     let client_socket_id_1 = client_socket_id.clone();
     let logger_read = Arc::clone(&logger);
     let socket_read_handle = tokio::spawn(async move {
-        logger_read.debug(&format!("客户端 {} 尝试连接 socket read", client_socket_id_1));
+        logger_read.debug(&format!("客户端 {} 等待 scrcpy-server 就绪", client_socket_id_1));
 
-        let stream = match TcpStream::connect(&socket_addr_1).await {
+        // 轮询转发端口直到 scrcpy-server 发送确认字节
+        let stream = match wait_for_scrcpy_ready(&socket_addr_1, &logger_read).await {
             Ok(s) => s,
             Err(e) => {
-                logger_read.error(&format!("socket read 连接失败: {:?}", e));
-                error!("客户端 {} 的 socket read 连接失败: {:?}", client_socket_id_1, e);
+                logger_read.error(&format!("socket read 就绪握手失败: {}", e));
+                error!("客户端 {} 的 socket read 就绪握手失败: {}", client_socket_id_1, e);
+                tracker_read.transition(session_id, SessionState::Degraded, Some(format!("scrcpy-server 未就绪: {}", e)));
                 return;
             }
         };
 
         logger_read.info(&format!("socket read 连接成功 (客户端: {})", client_socket_id_1));
         info!("客户端 {} 的 socket read 连接成功", client_socket_id_1);
+        let _ = ready_tx.send(());
+
+        // 服务端已就绪，说明 jar 任务已完成版本选择和编码协商
+        let video_codec = codec_rx.await.ok();
 
         let mut read = stream;
 
-        // 状态机处理前两个特殊消息
-        let mut state = ReadState::ReadAck;
-        let mut ack_buf = [0u8; 1];
+        // 确认字节已在握手中读取，状态机从设备元数据开始
+        let mut state = ReadState::ReadMeta;
         let mut meta_buf = [0u8; 64];
+        let mut frame_counter = FrameCounter::new();
 
         loop {
             match state {
-                ReadState::ReadAck => {
-                    // 读取 1 字节确认消息
-                    match read.read_exact(&mut ack_buf).await {
-                        Ok(_) => {
-                            logger_read.debug(&format!("收到 scrcpy socket 确认字节: {}", ack_buf[0]));
-                            info!("收到 scrcpy socket 确认字节: {}", ack_buf[0]);
-                            if ack_buf[0] != 0 {
-                                logger_read.warn(&format!("意外的确认字节: {}", ack_buf[0]));
-                                warn!("意外的确认字节: {}", ack_buf[0]);
-                            }
-                            state = ReadState::ReadMeta;
-                        }
-                        Err(e) => {
-                            logger_read.error(&format!("读取确认字节失败: {:?}", e));
-                            error!("读取确认字节失败: {:?}", e);
-                            break;
-                        }
-                    }
-                }
                 ReadState::ReadMeta => {
                     // 读取 64 字节设备元数据
                     match read.read_exact(&mut meta_buf).await {
@@ -576,10 +1353,19 @@ async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id:
                             logger_read.info(&format!("收到设备元数据: {} ({} 字节)", device_name, meta_buf.len()));
                             info!("收到设备元数据: {} ({} 字节)", device_name, meta_buf.len());
 
-                            // 通过 scrcpy_device_meta 事件发送设备元数据
-                            if let Err(e) = io_for_read.emit("scrcpy_device_meta", &device_name).await {
-                                logger_read.error(&format!("发送设备元数据失败: {:?}", e));
-                                error!("发送设备元数据失败: {:?}", e);
+                            // 通过 scrcpy_device_meta 事件发送设备元数据及协商结果
+                            let meta = serde_json::json!({
+                                "device_name": device_name,
+                                "video_codec": video_codec.as_ref().map(|(codec, _)| *codec),
+                                "server_version": video_codec.as_ref().map(|(_, version)| version.clone()),
+                            });
+                            {
+                                let mut live = state_for_read.live.lock().await;
+                                if let Err(e) = io_for_read.to(LIVE_ROOM).emit("scrcpy_device_meta", &meta).await {
+                                    logger_read.error(&format!("发送设备元数据失败: {:?}", e));
+                                    error!("发送设备元数据失败: {:?}", e);
+                                }
+                                live.set_device_meta(meta);
                             }
 
                             // 存储到会话状态
@@ -587,7 +1373,11 @@ async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id:
                                 let mut session = state_for_read.session.lock().await;
                                 session.device_meta = Some(device_name.clone());
                             }
+                            if let Some(serial) = &state_for_read.device.identifier {
+                                super::device_info::set_device_name(serial, &device_name);
+                            }
 
+                            tracker_read.transition(session_id, SessionState::Streaming, None);
                             state = ReadState::ReadData;
                         }
                         Err(e) => {
@@ -608,6 +1398,12 @@ async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id:
                         }
                         Ok(n) => {
                             let data = buf[..n].to_vec();
+                            let frames = frame_counter.feed(&data);
+                            state_for_read.stats.record_stream(n, frames);
+                            // 视频帧停滞后恢复出帧
+                            if frames > 0 && tracker_read.current() == SessionState::Degraded {
+                                tracker_read.transition(session_id, SessionState::Streaming, None);
+                            }
                             if let Err(e) = scrcpy_data_tx_for_read.send(data) {
                                 logger_read.error(&format!("发送数据到 channel 失败: {:?}", e));
                                 error!("发送数据到 channel 失败: {:?}", e);
@@ -623,18 +1419,23 @@ async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id:
                 }
             }
         }
+        // 会话未被中止而视频流结束，说明服务端已退出或连接中断
+        tracker_read.transition(session_id, SessionState::Degraded, Some("视频流已中断".to_string()));
     });
 
-    // 等待第一个 socket 建立
-    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-
     // 任务 3: TCP socket 写入控制数据
     let client_socket_id_2 = client_socket_id.clone();
     let logger_write = Arc::clone(&logger);
     let socket_write_handle = tokio::spawn(async move {
+        // 必须在 video socket 完成握手后再连接 control socket
+        if ready_rx.await.is_err() {
+            logger_write.warn("video socket 未就绪，放弃连接 control socket");
+            return;
+        }
+
         logger_write.debug(&format!("客户端 {} 尝试连接 socket write", client_socket_id_2));
 
-        let stream = match TcpStream::connect(&socket_addr).await {
+        let stream = match connect_with_backoff(&socket_addr, &logger_write).await {
             Ok(s) => s,
             Err(e) => {
                 logger_write.error(&format!("socket write 连接失败: {:?}", e));
@@ -657,18 +1458,29 @@ async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id:
         tokio::time::sleep(tokio::time::Duration::from_secs(u64::MAX)).await;
     });
 
-    // 任务 4: Socket.IO 广播
+    // 任务 4: Socket.IO 广播（按包边界推送给已加入的客户端，同时更新补发缓存）
     let client_socket_id_3 = client_socket_id.clone();
     let logger_broadcast = Arc::clone(&logger);
+    let state_for_broadcast = state.clone();
     let broadcast_handle = tokio::spawn(async move {
         logger_broadcast.info(&format!("广播任务启动 (客户端: {})", client_socket_id_3));
         info!("客户端 {} 的广播任务启动", client_socket_id_3);
 
+        let mut splitter = PacketSplitter::new();
         while let Some(data) = scrcpy_data_rx.recv().await {
             use base64::prelude::*;
-            let base64_data = BASE64_STANDARD.encode(&data);
+            let units = splitter.feed(&data);
+            if units.is_empty() {
+                continue;
+            }
+            let mut live = state_for_broadcast.live.lock().await;
+            for unit in &units {
+                live.push(unit);
+            }
+            let chunk: Vec<u8> = units.iter().flat_map(|unit| unit.data()).copied().collect();
+            let base64_data = BASE64_STANDARD.encode(&chunk);
 
-            if let Err(e) = io.emit("scrcpy", &base64_data).await {
+            if let Err(e) = io.to(LIVE_ROOM).emit("scrcpy", &base64_data).await {
                 logger_broadcast.error(&format!("广播 scrcpy 数据失败: {:?}", e));
                 error!("广播 scrcpy 数据失败: {:?}", e);
             }
@@ -686,9 +1498,81 @@ async fn start_scrcpy_session(state: Arc<ScrcpySessionState>, client_socket_id:
     session.broadcast_handle = Some(broadcast_handle);
 
     // 检查客户端是否仍在集合中（可能已断开连接）
-    if !session.connected_clients.contains(&client_socket_id) {
+    if !session.connected_clients.contains_key(&client_socket_id) {
         warn!("客户端 {} 在会话启动前已断开连接，但会话将继续为其他客户端服务", client_socket_id);
     }
 
     info!("Scrcpy 会话已启动，服务于 {} 个客户端", session.connected_clients.len());
 }
+
+/// 将内存中的 scrcpy-server.jar 推送到设备
+///
+/// adb_client 的 push 为阻塞调用，放到 blocking 线程池中执行
+async fn push_server_jar(device_serial: &str, jar_data: Vec<u8>) -> Result<(), String> {
+    let serial = device_serial.to_string();
+    let result = tokio::task::spawn_blocking(move || {
+        let server_addr = crate::adb_server::server_for(&serial).map(|remote| remote.addr);
+        let mut device = ADBServerDevice::new(serial, server_addr);
+        device
+            .push(std::io::Cursor::new(jar_data), SERVER_JAR_DEVICE_PATH)
+            .map_err(|e| format!("{:?}", e))
+    })
+    .await
+    .map_err(|e| format!("推送任务异常: {:?}", e))?;
+    crate::adb_server::record_result(&result);
+    result
+}
+
+/// 轮询 video socket 直到 scrcpy-server 就绪
+///
+/// adb forward 在设备端服务未监听时也会接受本地连接，随后立即关闭，
+/// 因此必须读到 scrcpy-server 发送的确认字节（0）才算就绪。
+async fn wait_for_scrcpy_ready(addr: &str, logger: &DeviceLogger) -> Result<TcpStream, String> {
+    let deadline = Instant::now() + READY_TIMEOUT;
+    let mut backoff = READY_INITIAL_BACKOFF;
+    let mut attempt = 0u32;
+
+    loop {
+        attempt += 1;
+        let failure = match TcpStream::connect(addr).await {
+            Ok(mut stream) => {
+                let mut banner = [0u8; 1];
+                match tokio::time::timeout(BANNER_READ_TIMEOUT, stream.read_exact(&mut banner)).await {
+                    Ok(Ok(_)) if banner[0] == 0 => {
+                        logger.debug(&format!("scrcpy-server 就绪 (第 {} 次尝试)", attempt));
+                        return Ok(stream);
+                    }
+                    Ok(Ok(_)) => format!("意外的确认字节: {}", banner[0]),
+                    Ok(Err(e)) => format!("读取确认字节失败: {:?}", e),
+                    Err(_) => "读取确认字节超时".to_string(),
+                }
+            }
+            Err(e) => format!("连接失败: {:?}", e),
+        };
+
+        if Instant::now() + backoff > deadline {
+            return Err(format!("等待 scrcpy-server 就绪超时 ({} 次尝试，最后错误: {})", attempt, failure));
+        }
+        logger.debug(&format!("scrcpy-server 未就绪 (第 {} 次尝试): {}，{:?} 后重试", attempt, failure, backoff));
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(READY_MAX_BACKOFF);
+    }
+}
+
+/// 以有界退避重试连接 socket
+async fn connect_with_backoff(addr: &str, logger: &DeviceLogger) -> Result<TcpStream, std::io::Error> {
+    let deadline = Instant::now() + READY_TIMEOUT;
+    let mut backoff = READY_INITIAL_BACKOFF;
+
+    loop {
+        match TcpStream::connect(addr).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) if Instant::now() + backoff <= deadline => {
+                logger.debug(&format!("连接 {} 失败: {:?}，{:?} 后重试", addr, e, backoff));
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(READY_MAX_BACKOFF);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! scrcpy-server 启动失败诊断
+//!
+//! scrcpy-server 启动失败时（Android 版本不支持、编码器错误、jar 版本不匹配等）只会在 shell 输出中留下错误信息。
+//! 按已知的错误特征识别失败原因，生成结构化的 [`ServerError`]，通过设备命名空间的 `scrcpy_error` 事件
+//! 和会话状态的 `error` 字段告诉客户端投屏为什么没有开始。
+
+use serde::{Deserialize, Serialize};
+
+/// 启动失败原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerErrorCode {
+    /// 设备上的 jar 与启动参数中的版本号不一致
+    VersionMismatch,
+    /// 设备上找不到 jar 或 jar 已损坏
+    ServerNotFound,
+    /// 设备 Android 版本不支持当前 scrcpy-server
+    UnsupportedAndroidVersion,
+    /// 视频编码器创建或编码失败
+    EncoderError,
+    /// 缺少注入输入等权限（部分厂商需要额外开启“USB 调试（安全设置）”）
+    PermissionDenied,
+    /// 设备端 scrcpy 套接字已被其他 scrcpy 实例占用
+    AddressInUse,
+    /// 未识别的错误
+    Unknown,
+}
+
+impl ServerErrorCode {
+    fn message(&self) -> &'static str {
+        match self {
+            Self::VersionMismatch => "scrcpy-server 版本与启动参数不一致",
+            Self::ServerNotFound => "设备上找不到 scrcpy-server 或文件已损坏",
+            Self::UnsupportedAndroidVersion => "设备 Android 版本不支持当前 scrcpy-server",
+            Self::EncoderError => "设备视频编码器不可用，可尝试更换视频编码或降低分辨率",
+            Self::PermissionDenied => "scrcpy-server 缺少权限，部分设备需在开发者选项中开启“USB 调试（安全设置）”",
+            Self::AddressInUse => "设备上已有其他 scrcpy 实例在运行",
+            Self::Unknown => "scrcpy-server 启动失败",
+        }
+    }
+}
+
+/// 结构化的启动失败信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerError {
+    pub code: ServerErrorCode,
+    /// 面向用户的说明
+    pub message: String,
+    /// 匹配到的原始输出行
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// 已知的错误特征，按优先级排列（同一输出中先匹配到的原因更具体）
+const SIGNATURES: &[(ServerErrorCode, &[&str])] = &[
+    (ServerErrorCode::VersionMismatch, &["does not match the client"]),
+    (ServerErrorCode::PermissionDenied, &["INJECT_EVENTS", "SecurityException", "Permission Denial"]),
+    (ServerErrorCode::AddressInUse, &["Address already in use"]),
+    (
+        ServerErrorCode::EncoderError,
+        &["Could not create video encoder", "Could not open video stream", "Encoding error", "CodecException", "MediaCodec"],
+    ),
+    (
+        ServerErrorCode::UnsupportedAndroidVersion,
+        &["Unsupported Android version", "NoSuchMethodError", "NoClassDefFoundError", "requires Android"],
+    ),
+    (
+        ServerErrorCode::ServerNotFound,
+        &["ClassNotFoundException", "Could not find or load main class", "Unable to open zip", "No such file or directory"],
+    ),
+];
+
+/// 从 scrcpy-server 的输出中识别启动失败原因；没有任何错误迹象时返回 None
+pub fn diagnose(output: &str) -> Option<ServerError> {
+    let lines: Vec<&str> = output.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    for (code, patterns) in SIGNATURES {
+        if let Some(line) = lines.iter().find(|line| patterns.iter().any(|pattern| line.contains(pattern))) {
+            return Some(ServerError { code: *code, message: code.message().to_string(), detail: Some(line.to_string()) });
+        }
+    }
+    lines
+        .iter()
+        .find(|line| line.contains("ERROR:"))
+        .map(|line| ServerError {
+            code: ServerErrorCode::Unknown,
+            message: ServerErrorCode::Unknown.message().to_string(),
+            detail: Some(line.to_string()),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnose() {
+        let output = "[server] ERROR: Could not create video encoder for h265\njava.lang.IllegalStateException\n\tat android.media.MediaCodec.native_configure";
+        let error = diagnose(output).unwrap();
+        assert_eq!(error.code, ServerErrorCode::EncoderError);
+        assert_eq!(error.detail.as_deref(), Some("[server] ERROR: Could not create video encoder for h265"));
+
+        let output = "[server] ERROR: The server version (3.3.4) does not match the client (3.3.3)";
+        assert_eq!(diagnose(output).unwrap().code, ServerErrorCode::VersionMismatch);
+
+        let output = "java.lang.SecurityException: Injecting to another application requires INJECT_EVENTS permission";
+        assert_eq!(diagnose(output).unwrap().code, ServerErrorCode::PermissionDenied);
+
+        let output = "Exception in thread \"main\" java.lang.NoSuchMethodError: No virtual method getDisplayInfo";
+        assert_eq!(diagnose(output).unwrap().code, ServerErrorCode::UnsupportedAndroidVersion);
+
+        let output = "[server] ERROR: Something unexpected";
+        let error = diagnose(output).unwrap();
+        assert_eq!((error.code, error.message.as_str()), (ServerErrorCode::Unknown, "scrcpy-server 启动失败"));
+
+        assert!(diagnose("[server] INFO: Device: [Google] Pixel 7 (Android 14)\n").is_none());
+        assert!(diagnose("").is_none());
+
+        let json = serde_json::to_value(diagnose("Address already in use").unwrap()).unwrap();
+        assert_eq!(json["code"], "address_in_use");
+    }
+}
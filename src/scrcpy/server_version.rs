@@ -0,0 +1,231 @@
+//! scrcpy-server 版本管理
+//!
+//! 内置多个 scrcpy-server 版本，根据设备 Android 版本选择兼容的服务端，
+//! 并生成与版本对应的启动参数。也支持通过环境变量指定外部 jar。
+
+use rust_embed::RustEmbed;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
+use super::options::{ScrcpyOptions, VideoCodec};
+
+/// 嵌入的 scrcpy-server jar 文件
+#[derive(RustEmbed)]
+#[folder = "assets/jar/"]
+struct ServerJars;
+
+/// 外部 scrcpy-server.jar 路径的环境变量
+pub const SERVER_PATH_ENV: &str = "SCRCPY_SERVER_PATH";
+/// 外部 scrcpy-server.jar 版本号的环境变量（服务端会校验该版本号）
+pub const SERVER_VERSION_ENV: &str = "SCRCPY_SERVER_VERSION";
+
+/// 内置的 scrcpy-server 版本描述
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuiltinServer {
+    /// 版本号（启动时作为第一个参数传给服务端）
+    pub version: &'static str,
+    /// 嵌入资源中的文件名
+    pub asset: &'static str,
+    /// 支持的最低 Android SDK 版本
+    pub min_sdk: u32,
+}
+
+/// 内置版本列表，按版本从新到旧排列
+pub const BUILTIN_SERVERS: &[BuiltinServer] = &[
+    BuiltinServer {
+        version: "3.3.4",
+        asset: "scrcpy-server-v3.3.4.jar",
+        min_sdk: 21,
+    },
+];
+
+/// scrcpy-server jar 来源
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ServerSource {
+    /// 嵌入在二进制中的资源
+    Embedded(&'static str),
+    /// 外部文件路径
+    External(PathBuf),
+}
+
+/// 为设备选定的 scrcpy-server
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SelectedServer {
+    /// 版本号
+    pub version: String,
+    /// jar 来源
+    pub source: ServerSource,
+}
+
+impl SelectedServer {
+    /// 读取 jar 文件内容
+    pub async fn load(&self) -> Result<Vec<u8>, String> {
+        match &self.source {
+            ServerSource::Embedded(asset) => ServerJars::get(asset)
+                .map(|f| f.data.to_vec())
+                .ok_or_else(|| format!("无法找到嵌入的 scrcpy-server 文件: {}", asset)),
+            ServerSource::External(path) => tokio::fs::read(path)
+                .await
+                .map_err(|e| format!("读取外部 scrcpy-server 文件 {:?} 失败: {:?}", path, e)),
+        }
+    }
+
+    /// 主版本号，解析失败时返回 0
+    pub fn major_version(&self) -> u32 {
+        parse_major(&self.version)
+    }
+
+    /// 是否支持选择视频编码（`video_codec` 参数从 2.0 开始支持）
+    pub fn supports_codec_selection(&self) -> bool {
+        self.major_version() >= 2
+    }
+
+    /// 该版本实际能使用的编码，不支持编码选择时只能使用 H.264
+    pub fn effective_codec(&self, codec: VideoCodec) -> VideoCodec {
+        if self.supports_codec_selection() {
+            codec
+        } else {
+            VideoCodec::H264
+        }
+    }
+
+    /// 是否支持会话 ID（`scid` 参数从 2.0 开始支持，同一设备上的多个服务端各用独立的套接字）
+    pub fn supports_scid(&self) -> bool {
+        self.major_version() >= 2
+    }
+
+    /// 服务端监听的抽象套接字名，不支持 `scid` 的版本固定为 `scrcpy`
+    pub fn socket_name(&self, scid: u32) -> String {
+        if self.supports_scid() {
+            format!("scrcpy_{:08x}", scid)
+        } else {
+            "scrcpy".to_string()
+        }
+    }
+
+    /// 生成与版本对应的服务端启动参数（不含版本号本身），`max_fps` 为 0 时不限制帧率
+    pub fn launch_args(&self, options: &ScrcpyOptions, codec: VideoCodec, max_fps: u32, scid: u32) -> Vec<String> {
+        let mut args = vec![
+            "log_level=info".to_string(),
+            format!("max_size={}", options.max_size),
+            "tunnel_forward=true".to_string(),
+        ];
+        if max_fps > 0 {
+            args.push(format!("max_fps={}", max_fps));
+        }
+        // audio 和 video_codec 参数从 2.0 开始支持
+        if self.major_version() >= 2 {
+            args.push(format!("scid={:08x}", scid));
+            args.push("audio=false".to_string());
+            args.push(format!("video_codec={}", self.effective_codec(codec).as_arg()));
+        }
+        args
+    }
+}
+
+/// 生成随机的会话 ID（31 位，服务端按有符号 int 解析）
+pub fn new_scid() -> u32 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+    (hasher.finish() as u32) & 0x7fff_ffff
+}
+
+/// 解析版本号中的主版本
+fn parse_major(version: &str) -> u32 {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .next()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0)
+}
+
+/// 根据设备 SDK 版本选择 scrcpy-server
+///
+/// 优先使用环境变量指定的外部 jar；否则选择兼容该 SDK 的最新内置版本。
+/// SDK 未知时直接使用最新内置版本。
+pub fn select_server(device_sdk: Option<u32>) -> Result<SelectedServer, String> {
+    if let Ok(path) = std::env::var(SERVER_PATH_ENV) {
+        let version = std::env::var(SERVER_VERSION_ENV).map_err(|_| {
+            format!("设置了 {} 但未设置 {}", SERVER_PATH_ENV, SERVER_VERSION_ENV)
+        })?;
+        return Ok(SelectedServer {
+            version,
+            source: ServerSource::External(PathBuf::from(path)),
+        });
+    }
+
+    select_builtin(BUILTIN_SERVERS, device_sdk)
+}
+
+/// 从给定的内置版本列表中选择兼容版本
+fn select_builtin(
+    servers: &[BuiltinServer],
+    device_sdk: Option<u32>,
+) -> Result<SelectedServer, String> {
+    servers
+        .iter()
+        .find(|s| device_sdk.is_none_or(|sdk| sdk >= s.min_sdk))
+        .map(|s| SelectedServer {
+            version: s.version.to_string(),
+            source: ServerSource::Embedded(s.asset),
+        })
+        .ok_or_else(|| format!("没有兼容设备 SDK {:?} 的 scrcpy-server 版本", device_sdk))
+}
+
+/// 查询设备的 Android SDK 版本
+pub async fn query_device_sdk(device_serial: &str) -> Option<u32> {
+    let output = crate::platform::adb_device_command(device_serial)
+        .args(["shell", "getprop", "ro.build.version.sdk"])
+        .output()
+        .await
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVERS: &[BuiltinServer] = &[
+        BuiltinServer { version: "3.3.4", asset: "v3.jar", min_sdk: 21 },
+        BuiltinServer { version: "1.25", asset: "v1.jar", min_sdk: 16 },
+    ];
+
+    #[test]
+    fn test_select_builtin_by_sdk() {
+        let newest = select_builtin(SERVERS, Some(34)).unwrap();
+        assert_eq!(newest.version, "3.3.4");
+        assert_eq!(newest.source, ServerSource::Embedded("v3.jar"));
+
+        let legacy = select_builtin(SERVERS, Some(19)).unwrap();
+        assert_eq!(legacy.version, "1.25");
+
+        assert!(select_builtin(SERVERS, Some(10)).is_err());
+        assert_eq!(select_builtin(SERVERS, None).unwrap().version, "3.3.4");
+    }
+
+    #[test]
+    fn test_launch_args_by_version() {
+        let options = ScrcpyOptions::default();
+
+        let v3 = select_builtin(SERVERS, Some(34)).unwrap();
+        let args = v3.launch_args(&options, VideoCodec::H265, 0, 0x1a2b);
+        assert!(args.contains(&"scid=00001a2b".to_string()));
+        assert_eq!(v3.socket_name(0x1a2b), "scrcpy_00001a2b");
+        assert!(args.contains(&"audio=false".to_string()));
+        assert!(args.contains(&"video_codec=h265".to_string()));
+        assert!(!args.iter().any(|a| a.starts_with("max_fps=")));
+        assert!(v3.launch_args(&options, VideoCodec::H265, 2, 0x1a2b).contains(&"max_fps=2".to_string()));
+
+        let v1 = select_builtin(SERVERS, Some(19)).unwrap();
+        assert_eq!(v1.major_version(), 1);
+        assert_eq!(v1.effective_codec(VideoCodec::H265), VideoCodec::H264);
+        let args = v1.launch_args(&options, VideoCodec::H265, 0, 0x1a2b);
+        assert!(!args.iter().any(|a| a.starts_with("scid=")));
+        assert_eq!(v1.socket_name(0x1a2b), "scrcpy");
+        assert!(!args.contains(&"audio=false".to_string()));
+        assert!(!args.iter().any(|a| a.starts_with("video_codec=")));
+        assert!(new_scid() <= 0x7fff_ffff);
+    }
+}
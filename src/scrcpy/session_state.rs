@@ -0,0 +1,204 @@
+//! scrcpy 会话状态
+//!
+//! 一次投屏会话从客户端连接到出画面需要数秒：选择并推送 scrcpy-server.jar、启动服务端、等待就绪握手、
+//! 读取设备元数据。[`SessionStateTracker`] 记录会话处于哪个阶段，通过 `/device/{serial}/status`、
+//! 设备 Socket.IO 的 `scrcpy_state` 事件和设备池事件提供给客户端。
+//!
+//! 每次启动会话分配新的会话号，被中止的旧会话任务不会覆盖新会话的状态。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::watch;
+
+use super::server_error::ServerError;
+
+/// 会话阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    /// 正在启动（选择 scrcpy-server 版本、协商编码）
+    Starting,
+    /// 正在设置端口转发并推送 scrcpy-server.jar
+    PushingJar,
+    /// 已启动 scrcpy-server，等待就绪握手与设备元数据
+    WaitingForServer,
+    /// 正在推流
+    Streaming,
+    /// 会话仍有客户端但没有正常推流（视频帧停滞、服务端退出或启动失败）
+    Degraded,
+    /// 没有运行中的会话
+    Stopped,
+}
+
+impl SessionState {
+    /// 是否有运行中的会话（未停止）
+    pub fn is_active(&self) -> bool {
+        !matches!(self, SessionState::Stopped)
+    }
+}
+
+/// 会话状态快照
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionStateSnapshot {
+    pub state: SessionState,
+    /// 进入当前状态的时间
+    pub since: DateTime<Utc>,
+    /// 进入 Degraded 等状态的原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// 识别出的 scrcpy-server 启动失败原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ServerError>,
+}
+
+impl SessionStateSnapshot {
+    fn new(state: SessionState, reason: Option<String>) -> Self {
+        Self { state, since: Utc::now(), reason, error: None }
+    }
+}
+
+/// 会话状态跟踪器
+#[derive(Debug)]
+pub struct SessionStateTracker {
+    /// 当前会话号，每次启动或停止会话时递增
+    session: AtomicU64,
+    tx: watch::Sender<SessionStateSnapshot>,
+}
+
+impl SessionStateTracker {
+    pub fn new() -> Self {
+        Self {
+            session: AtomicU64::new(0),
+            tx: watch::Sender::new(SessionStateSnapshot::new(SessionState::Stopped, None)),
+        }
+    }
+
+    /// 开始新会话（进入 Starting），返回会话号
+    pub fn start_session(&self) -> u64 {
+        let session = self.session.fetch_add(1, Ordering::SeqCst) + 1;
+        self.tx.send_replace(SessionStateSnapshot::new(SessionState::Starting, None));
+        session
+    }
+
+    /// 停止当前会话（进入 Stopped），之后旧会话的状态更新被忽略
+    pub fn stop_session(&self) {
+        self.session.fetch_add(1, Ordering::SeqCst);
+        self.tx.send_if_modified(|current| {
+            if current.state == SessionState::Stopped {
+                return false;
+            }
+            *current = SessionStateSnapshot::new(SessionState::Stopped, None);
+            true
+        });
+    }
+
+    /// 更新会话 `session` 的状态；会话已被替换或状态未变化时忽略，返回是否更新。
+    /// 已识别出启动失败原因时，后续更笼统的 Degraded 原因（如就绪握手超时）不覆盖它
+    pub fn transition(&self, session: u64, state: SessionState, reason: Option<String>) -> bool {
+        self.tx.send_if_modified(|current| {
+            // 在锁内比较会话号，避免与 start_session / stop_session 交错
+            if self.session.load(Ordering::SeqCst) != session
+                || (current.state == state && current.reason == reason)
+                || (state == SessionState::Degraded && current.error.is_some())
+            {
+                return false;
+            }
+            *current = SessionStateSnapshot::new(state, reason);
+            true
+        })
+    }
+
+    /// 会话 `session` 的 scrcpy-server 启动失败，进入 Degraded 并记录失败原因；会话已被替换时忽略
+    pub fn fail(&self, session: u64, error: ServerError) -> bool {
+        self.tx.send_if_modified(|current| {
+            if self.session.load(Ordering::SeqCst) != session || current.error.as_ref() == Some(&error) {
+                return false;
+            }
+            let reason = match &error.detail {
+                Some(detail) => format!("{}: {}", error.message, detail),
+                None => error.message.clone(),
+            };
+            *current = SessionStateSnapshot { error: Some(error), ..SessionStateSnapshot::new(SessionState::Degraded, Some(reason)) };
+            true
+        })
+    }
+
+    /// 当前会话号
+    pub fn session(&self) -> u64 {
+        self.session.load(Ordering::SeqCst)
+    }
+
+    /// 当前状态
+    pub fn current(&self) -> SessionState {
+        self.tx.borrow().state
+    }
+
+    /// 当前状态快照
+    pub fn snapshot(&self) -> SessionStateSnapshot {
+        self.tx.borrow().clone()
+    }
+
+    /// 订阅状态变化
+    pub fn subscribe(&self) -> watch::Receiver<SessionStateSnapshot> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for SessionStateTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions_of_replaced_session_are_ignored() {
+        let tracker = SessionStateTracker::new();
+        let mut rx = tracker.subscribe();
+        assert_eq!(tracker.current(), SessionState::Stopped);
+
+        let first = tracker.start_session();
+        assert!(tracker.transition(first, SessionState::PushingJar, None));
+        assert!(!tracker.transition(first, SessionState::PushingJar, None));
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(rx.borrow_and_update().state, SessionState::PushingJar);
+
+        // 新会话开始后，旧会话的任务不能再修改状态
+        let second = tracker.start_session();
+        assert!(!tracker.transition(first, SessionState::Degraded, Some("服务端退出".to_string())));
+        assert_eq!(tracker.current(), SessionState::Starting);
+
+        assert!(tracker.transition(second, SessionState::Degraded, Some("视频帧停滞".to_string())));
+        let snapshot = tracker.snapshot();
+        assert_eq!((snapshot.state, snapshot.reason.as_deref()), (SessionState::Degraded, Some("视频帧停滞")));
+
+        tracker.stop_session();
+        assert!(!tracker.current().is_active());
+        assert!(!tracker.transition(second, SessionState::Streaming, None));
+
+        let json = serde_json::to_value(tracker.snapshot()).unwrap();
+        assert_eq!(json["state"], "stopped");
+        assert!(json.get("reason").is_none());
+    }
+
+    #[test]
+    fn test_server_failure_is_kept() {
+        let tracker = SessionStateTracker::new();
+        let session = tracker.start_session();
+        let error = super::super::server_error::diagnose("[server] ERROR: Could not create video encoder").unwrap();
+        assert!(tracker.fail(session, error.clone()));
+        assert!(!tracker.transition(session, SessionState::Degraded, Some("scrcpy-server 未就绪".to_string())));
+        assert_eq!(tracker.snapshot().error, Some(error));
+
+        let json = serde_json::to_value(tracker.snapshot()).unwrap();
+        assert_eq!(json["error"]["code"], "encoder_error");
+
+        // 新会话清除上次的失败原因
+        tracker.start_session();
+        assert!(tracker.snapshot().error.is_none());
+    }
+}
@@ -0,0 +1,281 @@
+//! scrcpy 会话统计
+//!
+//! 记录每个设备会话的推流字节数、转发帧数、客户端数和控制消息数。
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// scrcpy 视频包头长度：8 字节 PTS/flags + 4 字节包长度
+const PACKET_HEADER_LEN: usize = 12;
+/// 编码元数据长度：4 字节 codec id + 4 字节宽 + 4 字节高
+const CODEC_META_LEN: usize = 12;
+/// PTS 字段中的配置包标志位
+const PACKET_FLAG_CONFIG: u64 = 1 << 63;
+
+/// 单个设备的会话统计（线程安全，可在多个任务间共享）
+#[derive(Debug)]
+pub struct SessionStats {
+    /// ScrcpyConnect 创建时间
+    created_at: Instant,
+    /// 当前 scrcpy-server 会话的启动时间
+    session_started_at: Mutex<Option<Instant>>,
+    /// 推送到客户端的视频字节数
+    bytes_streamed: AtomicU64,
+    /// 转发的视频帧数（不含配置包）
+    frames_forwarded: AtomicU64,
+    /// 收到的控制消息数
+    control_messages: AtomicU64,
+    /// 校验或限流未通过而丢弃的控制数据包数
+    control_rejected: AtomicU64,
+    /// 启动过的 scrcpy-server 会话次数
+    sessions_started: AtomicU64,
+    /// 当前连接的客户端数
+    clients: AtomicUsize,
+    /// 最近一次收到视频帧的时间
+    last_frame_at: Mutex<Option<Instant>>,
+    /// 看门狗因视频帧停滞重启会话的次数
+    watchdog_restarts: AtomicU64,
+}
+
+/// 会话统计快照
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionStatsSnapshot {
+    pub bytes_streamed: u64,
+    pub frames_forwarded: u64,
+    pub control_messages: u64,
+    pub control_rejected: u64,
+    pub sessions_started: u64,
+    pub clients: usize,
+    pub watchdog_restarts: u64,
+    /// ScrcpyConnect 运行时长（秒）
+    pub uptime_secs: u64,
+    /// 当前 scrcpy-server 会话运行时长（秒），未运行时为 None
+    pub session_uptime_secs: Option<u64>,
+}
+
+impl SessionStats {
+    /// 创建新的统计
+    pub fn new() -> Self {
+        Self {
+            created_at: Instant::now(),
+            session_started_at: Mutex::new(None),
+            bytes_streamed: AtomicU64::new(0),
+            frames_forwarded: AtomicU64::new(0),
+            control_messages: AtomicU64::new(0),
+            control_rejected: AtomicU64::new(0),
+            sessions_started: AtomicU64::new(0),
+            clients: AtomicUsize::new(0),
+            last_frame_at: Mutex::new(None),
+            watchdog_restarts: AtomicU64::new(0),
+        }
+    }
+
+    /// 记录 scrcpy-server 会话启动
+    pub fn session_started(&self) {
+        *self.session_started_at.lock().unwrap() = Some(Instant::now());
+        *self.last_frame_at.lock().unwrap() = None;
+        self.sessions_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录 scrcpy-server 会话停止
+    pub fn session_stopped(&self) {
+        *self.session_started_at.lock().unwrap() = None;
+    }
+
+    /// 记录推流数据
+    pub fn record_stream(&self, bytes: usize, frames: u64) {
+        self.bytes_streamed.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.frames_forwarded.fetch_add(frames, Ordering::Relaxed);
+        if frames > 0 {
+            *self.last_frame_at.lock().unwrap() = Some(Instant::now());
+        }
+    }
+
+    /// 会话运行中距离最近一次收到视频帧（尚未收到时从会话启动算起）的时长，会话未运行时为 None
+    ///
+    /// 画面静止时 scrcpy-server 仍会定期重复上一帧，长时间没有帧说明服务端已卡死
+    pub fn frame_stall(&self) -> Option<Duration> {
+        let started_at = (*self.session_started_at.lock().unwrap())?;
+        let last_frame_at = self.last_frame_at.lock().unwrap().unwrap_or(started_at);
+        Some(last_frame_at.max(started_at).elapsed())
+    }
+
+    /// 记录一次看门狗重启
+    pub fn record_watchdog_restart(&self) -> u64 {
+        self.watchdog_restarts.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// 记录一条控制消息
+    pub fn record_control_message(&self) {
+        self.control_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录一个被丢弃的控制数据包
+    pub fn record_control_rejected(&self) {
+        self.control_rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 更新当前客户端数
+    pub fn set_clients(&self, clients: usize) {
+        self.clients.store(clients, Ordering::Relaxed);
+    }
+
+    /// 当前客户端数
+    pub fn clients(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
+    }
+
+    /// 生成统计快照
+    pub fn snapshot(&self) -> SessionStatsSnapshot {
+        SessionStatsSnapshot {
+            bytes_streamed: self.bytes_streamed.load(Ordering::Relaxed),
+            frames_forwarded: self.frames_forwarded.load(Ordering::Relaxed),
+            control_messages: self.control_messages.load(Ordering::Relaxed),
+            control_rejected: self.control_rejected.load(Ordering::Relaxed),
+            sessions_started: self.sessions_started.load(Ordering::Relaxed),
+            clients: self.clients(),
+            watchdog_restarts: self.watchdog_restarts.load(Ordering::Relaxed),
+            uptime_secs: self.created_at.elapsed().as_secs(),
+            session_uptime_secs: self
+                .session_started_at
+                .lock()
+                .unwrap()
+                .map(|t| t.elapsed().as_secs()),
+        }
+    }
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 跨数据块解析 scrcpy 视频流的包头，统计帧数
+///
+/// 输入为设备元数据之后的视频流：先是编码元数据，然后是若干
+/// `包头 + 包数据`。数据块边界可以落在任意位置。
+#[derive(Debug, Default)]
+pub struct FrameCounter {
+    /// 是否已跳过编码元数据
+    codec_meta_done: bool,
+    /// 尚未凑齐的头部字节
+    pending: Vec<u8>,
+    /// 当前包剩余待跳过的数据字节
+    remaining_payload: usize,
+}
+
+impl FrameCounter {
+    /// 创建新的计数器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理一个数据块，返回其中完整出现包头的帧数
+    pub fn feed(&mut self, mut data: &[u8]) -> u64 {
+        let mut frames = 0;
+
+        while !data.is_empty() {
+            if self.remaining_payload > 0 {
+                let skip = self.remaining_payload.min(data.len());
+                self.remaining_payload -= skip;
+                data = &data[skip..];
+                continue;
+            }
+
+            let header_len = if self.codec_meta_done { PACKET_HEADER_LEN } else { CODEC_META_LEN };
+            let need = header_len - self.pending.len();
+            let take = need.min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+
+            if self.pending.len() < header_len {
+                break;
+            }
+
+            if !self.codec_meta_done {
+                self.codec_meta_done = true;
+            } else {
+                let pts_flags = u64::from_be_bytes(self.pending[..8].try_into().unwrap());
+                let len = u32::from_be_bytes(self.pending[8..12].try_into().unwrap());
+                self.remaining_payload = len as usize;
+                if pts_flags & PACKET_FLAG_CONFIG == 0 {
+                    frames += 1;
+                }
+            }
+            self.pending.clear();
+        }
+
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(config: bool, payload: &[u8]) -> Vec<u8> {
+        let pts: u64 = if config { PACKET_FLAG_CONFIG } else { 42 };
+        let mut buf = pts.to_be_bytes().to_vec();
+        buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    #[test]
+    fn test_frame_counter_split_chunks() {
+        let mut stream = vec![0u8; CODEC_META_LEN];
+        stream.extend(packet(true, &[1, 2, 3]));
+        stream.extend(packet(false, &[4; 100]));
+        stream.extend(packet(false, &[5; 7]));
+
+        // 以任意大小切块，结果应与整体输入一致
+        for chunk_size in [1, 5, 13, 64, stream.len()] {
+            let mut counter = FrameCounter::new();
+            let frames: u64 = stream.chunks(chunk_size).map(|c| counter.feed(c)).sum();
+            assert_eq!(frames, 2, "chunk_size={}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_session_stats_snapshot() {
+        let stats = SessionStats::new();
+        assert!(stats.snapshot().session_uptime_secs.is_none());
+
+        stats.session_started();
+        stats.record_stream(1024, 3);
+        stats.record_control_message();
+        stats.set_clients(2);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.bytes_streamed, 1024);
+        assert_eq!(snapshot.frames_forwarded, 3);
+        assert_eq!(snapshot.control_messages, 1);
+        assert_eq!(snapshot.sessions_started, 1);
+        assert_eq!(snapshot.clients, 2);
+        assert!(snapshot.session_uptime_secs.is_some());
+    }
+
+    #[test]
+    fn test_frame_stall() {
+        let stats = SessionStats::new();
+        assert!(stats.frame_stall().is_none());
+
+        stats.session_started();
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(stats.frame_stall().unwrap() >= Duration::from_millis(20));
+
+        // 只有配置包（0 帧）不算收到视频帧
+        stats.record_stream(64, 0);
+        assert!(stats.frame_stall().unwrap() >= Duration::from_millis(20));
+        stats.record_stream(1024, 1);
+        assert!(stats.frame_stall().unwrap() < Duration::from_millis(20));
+
+        stats.session_stopped();
+        assert!(stats.frame_stall().is_none());
+        assert_eq!(stats.record_watchdog_restart(), 1);
+        assert_eq!(stats.snapshot().watchdog_restarts, 1);
+    }
+}
@@ -0,0 +1,270 @@
+//! 视频流缓存
+//!
+//! 会话运行中加入的客户端不重启 scrcpy-server：广播任务把视频流按包切分（[`PacketSplitter`]），
+//! 只在包边界向已加入的客户端推送，同时在 [`StreamCache`] 中保留编码元数据、最近的配置包
+//! （SPS/PPS）和从最近一个关键帧开始的帧。新客户端先收到这些数据，再加入广播即可直接解码。
+
+use serde_json::Value;
+
+/// scrcpy 视频包头长度：8 字节 PTS/flags + 4 字节包长度
+const PACKET_HEADER_LEN: usize = 12;
+/// 编码元数据长度：4 字节 codec id + 4 字节宽 + 4 字节高
+const CODEC_META_LEN: usize = 12;
+/// PTS 字段中的配置包标志位
+const PACKET_FLAG_CONFIG: u64 = 1 << 63;
+/// PTS 字段中的关键帧标志位
+const PACKET_FLAG_KEY_FRAME: u64 = 1 << 62;
+
+/// 默认缓存的最大字节数（关键帧之后的帧超出时不再缓存，新客户端在下一个关键帧前可能出现花屏）
+pub const DEFAULT_MAX_CACHED_BYTES: usize = 8 * 1024 * 1024;
+
+/// 视频流中的一个完整单元
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamUnit {
+    /// 编码元数据（新视频流的开头）
+    CodecMeta(Vec<u8>),
+    /// 一个视频包（包含包头）
+    Packet { config: bool, key_frame: bool, data: Vec<u8> },
+}
+
+impl StreamUnit {
+    /// 原始字节
+    pub fn data(&self) -> &[u8] {
+        match self {
+            StreamUnit::CodecMeta(data) | StreamUnit::Packet { data, .. } => data,
+        }
+    }
+}
+
+/// 将任意切分的视频流数据块重组为完整的 [`StreamUnit`]
+///
+/// 输入为设备元数据之后的视频流：先是编码元数据，然后是若干 `包头 + 包数据`。
+#[derive(Debug, Default)]
+pub struct PacketSplitter {
+    codec_meta_done: bool,
+    /// 尚未凑齐的单元
+    pending: Vec<u8>,
+}
+
+impl PacketSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理一个数据块，返回其中完成的单元
+    pub fn feed(&mut self, mut data: &[u8]) -> Vec<StreamUnit> {
+        let mut units = Vec::new();
+        while !data.is_empty() {
+            let needed = self.needed() - self.pending.len();
+            let take = needed.min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+
+            // 读完包头后才知道包数据的长度
+            if self.pending.len() < self.needed() {
+                continue;
+            }
+            let unit = std::mem::take(&mut self.pending);
+            if !self.codec_meta_done {
+                self.codec_meta_done = true;
+                units.push(StreamUnit::CodecMeta(unit));
+            } else {
+                let pts_flags = u64::from_be_bytes(unit[..8].try_into().unwrap());
+                units.push(StreamUnit::Packet {
+                    config: pts_flags & PACKET_FLAG_CONFIG != 0,
+                    key_frame: pts_flags & PACKET_FLAG_KEY_FRAME != 0,
+                    data: unit,
+                });
+            }
+        }
+        units
+    }
+
+    /// 当前单元的总长度（包头读完前只需要包头长度）
+    fn needed(&self) -> usize {
+        if !self.codec_meta_done {
+            return CODEC_META_LEN;
+        }
+        if self.pending.len() < PACKET_HEADER_LEN {
+            return PACKET_HEADER_LEN;
+        }
+        PACKET_HEADER_LEN + u32::from_be_bytes(self.pending[8..12].try_into().unwrap()) as usize
+    }
+}
+
+/// 新客户端加入时需要补发的数据
+#[derive(Debug)]
+pub struct StreamCache {
+    max_bytes: usize,
+    /// `scrcpy_device_meta` 事件的内容
+    device_meta: Option<Value>,
+    codec_meta: Option<Vec<u8>>,
+    config: Option<Vec<u8>>,
+    /// 最近一个关键帧及之后的帧
+    frames: Vec<Vec<u8>>,
+    frame_bytes: usize,
+    /// 缓存已满，等待下一个关键帧
+    overflowed: bool,
+}
+
+impl StreamCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            device_meta: None,
+            codec_meta: None,
+            config: None,
+            frames: Vec::new(),
+            frame_bytes: 0,
+            overflowed: false,
+        }
+    }
+
+    /// 清空缓存（新会话开始）
+    pub fn reset(&mut self) {
+        *self = Self::new(self.max_bytes);
+    }
+
+    pub fn set_device_meta(&mut self, meta: Value) {
+        self.device_meta = Some(meta);
+    }
+
+    pub fn device_meta(&self) -> Option<&Value> {
+        self.device_meta.as_ref()
+    }
+
+    /// 编码元数据中的视频宽高（旋转屏幕后宽高互换，编码元数据不会更新）
+    pub fn frame_size(&self) -> Option<(u32, u32)> {
+        let meta = self.codec_meta.as_ref()?;
+        let width = u32::from_be_bytes(meta.get(4..8)?.try_into().ok()?);
+        let height = u32::from_be_bytes(meta.get(8..12)?.try_into().ok()?);
+        Some((width, height))
+    }
+
+    /// 记录广播的单元
+    pub fn push(&mut self, unit: &StreamUnit) {
+        match unit {
+            StreamUnit::CodecMeta(data) => {
+                let device_meta = self.device_meta.take();
+                self.reset();
+                self.device_meta = device_meta;
+                self.codec_meta = Some(data.clone());
+            }
+            // 编码器重新配置（如屏幕旋转）后的帧只依赖新的配置包
+            StreamUnit::Packet { config: true, data, .. } => {
+                self.config = Some(data.clone());
+                self.clear_frames();
+            }
+            StreamUnit::Packet { key_frame: true, data, .. } => {
+                self.clear_frames();
+                self.push_frame(data);
+            }
+            StreamUnit::Packet { data, .. } => {
+                if self.frames.is_empty() || self.overflowed {
+                    return;
+                }
+                if self.frame_bytes + data.len() > self.max_bytes {
+                    self.overflowed = true;
+                    return;
+                }
+                self.push_frame(data);
+            }
+        }
+    }
+
+    /// 按顺序拼接的补发数据：编码元数据、配置包、关键帧及之后的帧；还没有编码元数据时为空
+    pub fn catch_up(&self) -> Vec<u8> {
+        let Some(codec_meta) = &self.codec_meta else {
+            return Vec::new();
+        };
+        let mut data = codec_meta.clone();
+        if let Some(config) = &self.config {
+            data.extend_from_slice(config);
+        }
+        for frame in &self.frames {
+            data.extend_from_slice(frame);
+        }
+        data
+    }
+
+    fn push_frame(&mut self, data: &[u8]) {
+        self.frame_bytes += data.len();
+        self.frames.push(data.to_vec());
+    }
+
+    fn clear_frames(&mut self) {
+        self.frames.clear();
+        self.frame_bytes = 0;
+        self.overflowed = false;
+    }
+}
+
+impl Default for StreamCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CACHED_BYTES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(flags: u64, payload: &[u8]) -> Vec<u8> {
+        let mut buf = (flags | 42).to_be_bytes().to_vec();
+        buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    #[test]
+    fn test_splitter_reassembles_units() {
+        let mut stream = vec![7u8; CODEC_META_LEN];
+        stream.extend(packet(PACKET_FLAG_CONFIG, &[1, 2, 3]));
+        stream.extend(packet(PACKET_FLAG_KEY_FRAME, &[4; 100]));
+        stream.extend(packet(0, &[]));
+
+        for chunk_size in [1, 5, 13, 64, stream.len()] {
+            let mut splitter = PacketSplitter::new();
+            let units: Vec<StreamUnit> = stream.chunks(chunk_size).flat_map(|c| splitter.feed(c)).collect();
+            let kinds: Vec<(bool, bool)> = units
+                .iter()
+                .filter_map(|u| match u {
+                    StreamUnit::Packet { config, key_frame, .. } => Some((*config, *key_frame)),
+                    StreamUnit::CodecMeta(_) => None,
+                })
+                .collect();
+            assert_eq!(kinds, vec![(true, false), (false, true), (false, false)], "chunk_size={}", chunk_size);
+            assert_eq!(units.iter().flat_map(|u| u.data().to_vec()).collect::<Vec<_>>(), stream);
+        }
+    }
+
+    #[test]
+    fn test_cache_keeps_config_and_frames_since_keyframe() {
+        let meta = vec![7u8; CODEC_META_LEN];
+        let config = packet(PACKET_FLAG_CONFIG, &[1]);
+        let key = packet(PACKET_FLAG_KEY_FRAME, &[2; 10]);
+        let delta = packet(0, &[3; 10]);
+
+        let mut cache = StreamCache::new(50);
+        assert!(cache.catch_up().is_empty());
+        let mut splitter = PacketSplitter::new();
+        let stream = [meta.clone(), config.clone(), packet(PACKET_FLAG_KEY_FRAME, &[9]), packet(0, &[9]), key.clone(), delta.clone()].concat();
+        for unit in splitter.feed(&stream) {
+            cache.push(&unit);
+        }
+        assert_eq!(cache.catch_up(), [meta.clone(), config.clone(), key.clone(), delta.clone()].concat());
+
+        // 超出上限后不再缓存非关键帧，直到下一个关键帧
+        cache.push(&StreamUnit::Packet { config: false, key_frame: false, data: delta.clone() });
+        assert_eq!(cache.catch_up(), [meta.clone(), config.clone(), key.clone(), delta.clone()].concat());
+        cache.push(&StreamUnit::Packet { config: false, key_frame: true, data: key.clone() });
+        cache.push(&StreamUnit::Packet { config: false, key_frame: false, data: delta.clone() });
+        assert_eq!(cache.catch_up(), [meta.clone(), config, key, delta].concat());
+
+        // 新视频流保留设备元数据，丢弃旧的配置与帧
+        cache.set_device_meta(serde_json::json!({"device_name": "Pixel 7"}));
+        cache.push(&StreamUnit::CodecMeta(meta.clone()));
+        assert_eq!(cache.catch_up(), meta);
+        assert!(cache.device_meta().is_some());
+    }
+}
@@ -0,0 +1,160 @@
+//! 密钥处理
+//!
+//! 配置中的密钥（如模型 API Key）可以直接写值，也可以引用外部来源：
+//!
+//! - `file:/run/secrets/autoglm_api_key`：读取文件内容（docker / k8s secrets），去掉首尾空白
+//! - `keychain:<服务>/<账户>`：读取系统钥匙串（macOS `security`、Linux `secret-tool`）
+//! - `env:<变量名>`：读取另一个环境变量
+//!
+//! 环境变量 `NAME` 未设置时也会读取 `NAME_FILE` 指向的文件（docker secrets 约定）。
+//! 解析后的密钥登记到全局列表，日志输出（tracing、Agent 日志）与返回给客户端的错误信息中出现时替换为 `****`。
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::process::Command;
+use std::sync::RwLock;
+
+use tracing_subscriber::fmt::MakeWriter;
+
+/// 替换密钥的占位符
+pub const REDACTED: &str = "****";
+
+/// 短于该长度的值不登记（如测试用的 `EMPTY`、`sk-test`），避免误伤普通文字
+const MIN_SECRET_LEN: usize = 8;
+
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// 解析密钥引用，普通值原样返回
+pub fn resolve_secret(value: &str) -> Result<String, String> {
+    if let Some(path) = value.strip_prefix("file:") {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("读取密钥文件 {} 失败: {}", path, e))?;
+        return Ok(content.trim().to_string());
+    }
+    if let Some(reference) = value.strip_prefix("keychain:") {
+        return read_keychain(reference);
+    }
+    if let Some(name) = value.strip_prefix("env:") {
+        return std::env::var(name).map_err(|_| format!("密钥引用的环境变量 {} 未设置", name));
+    }
+    Ok(value.to_string())
+}
+
+/// 从环境变量读取密钥：先读 `name`（值可以是密钥引用），未设置时读 `{name}_FILE` 指向的文件
+pub fn env_secret(name: &str) -> Option<Result<String, String>> {
+    if let Ok(value) = std::env::var(name) {
+        return Some(resolve_secret(&value));
+    }
+    let path = std::env::var(format!("{}_FILE", name)).ok()?;
+    Some(resolve_secret(&format!("file:{}", path)))
+}
+
+/// 登记密钥，之后的日志与错误信息中会被替换
+pub fn register_secret(secret: &str) {
+    let secret = secret.trim();
+    if secret.len() < MIN_SECRET_LEN {
+        return;
+    }
+    let mut secrets = SECRETS.write().unwrap();
+    if !secrets.iter().any(|s| s == secret) {
+        secrets.push(secret.to_string());
+        // 先替换较长的密钥，避免一个密钥是另一个的前缀时只替换一部分
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+    }
+}
+
+/// 将文字中已登记的密钥替换为 [`REDACTED`]
+pub fn redact(text: &str) -> Cow<'_, str> {
+    let secrets = SECRETS.read().unwrap();
+    let mut text = Cow::Borrowed(text);
+    for secret in secrets.iter() {
+        if text.contains(secret.as_str()) {
+            text = Cow::Owned(text.replace(secret.as_str(), REDACTED));
+        }
+    }
+    text
+}
+
+/// 输出到标准输出前替换密钥的 tracing writer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedactingWriter;
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // tracing 每条日志整行写入一次，按行替换即可
+        let text = String::from_utf8_lossy(buf);
+        io::stdout().write_all(redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for RedactingWriter {
+    type Writer = RedactingWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        *self
+    }
+}
+
+/// 读取系统钥匙串中的密钥，`reference` 为 `<服务>/<账户>`
+fn read_keychain(reference: &str) -> Result<String, String> {
+    let (service, account) = reference
+        .split_once('/')
+        .ok_or_else(|| format!("钥匙串引用格式应为 keychain:<服务>/<账户>: {}", reference))?;
+
+    let mut command = keychain_command(service, account)
+        .ok_or_else(|| "当前平台不支持读取钥匙串，请改用 file: 引用".to_string())?;
+    let output = command.output().map_err(|e| format!("读取钥匙串失败: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("钥匙串中没有 {}/{}: {}", service, account, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// 读取钥匙串的命令：macOS 使用 `security`，其他 Unix 使用 libsecret 的 `secret-tool`
+fn keychain_command(service: &str, account: &str) -> Option<Command> {
+    if cfg!(target_os = "macos") {
+        let mut command = Command::new("security");
+        command.args(["find-generic-password", "-s", service, "-a", account, "-w"]);
+        Some(command)
+    } else if cfg!(unix) {
+        let mut command = Command::new("secret-tool");
+        command.args(["lookup", "service", service, "account", account]);
+        Some(command)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_secret() {
+        let path = std::env::temp_dir().join(format!("scrs_secret_{}", std::process::id()));
+        std::fs::write(&path, "sk-from-file-123\n").unwrap();
+
+        assert_eq!(resolve_secret(&format!("file:{}", path.display())).unwrap(), "sk-from-file-123");
+        assert_eq!(resolve_secret("sk-plain").unwrap(), "sk-plain");
+        assert!(resolve_secret("file:/nonexistent/scrs_secret").is_err());
+        assert!(resolve_secret("keychain:no-account").is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_redact() {
+        register_secret("EMPTY");
+        register_secret("sk-abcdef123456");
+        register_secret("sk-abcdef123456-long");
+
+        assert_eq!(redact("Bearer sk-abcdef123456-long 失败"), "Bearer **** 失败");
+        assert_eq!(redact("key=sk-abcdef123456"), "key=****");
+        assert_eq!(redact("api_key = EMPTY"), "api_key = EMPTY");
+        assert!(matches!(redact("没有密钥"), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,319 @@
+//! 磁盘用量监控与配额
+//!
+//! 定期统计日志、步骤截图与任务产物目录的磁盘用量（嵌套的监控目录单独统计，不计入上级目录）。
+//! 配置了配额（`[storage]` 段的 `quota_mb`）时，总用量超过配额后从可清理目录中按修改时间删除最旧的文件，
+//! 直到用量降到配额的 90%，避免长时间批量运行时写满磁盘。Agent 日志等不在可清理目录中的文件不会被删除，
+//! 最近修改的文件（可能仍在写入或上传）也会保留。用量通过 `GET /storage` 与 `/metrics` 查看。
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// 清理后的目标用量（占配额的比例）
+const PRUNE_TARGET_RATIO: f64 = 0.9;
+/// 修改时间在此之内的文件不清理
+const PRUNE_MIN_AGE: Duration = Duration::from_secs(600);
+
+/// 磁盘用量配置（`[storage]` 段）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// 统计用量的目录
+    pub dirs: Vec<String>,
+    /// 超出配额时可以清理的目录
+    pub prune_dirs: Vec<String>,
+    /// 所有统计目录的总配额（MB），0 表示不限制
+    pub quota_mb: u64,
+    /// 检查间隔（秒）
+    pub scan_interval_secs: u64,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            dirs: vec!["logs".to_string(), "logs/agent/step_screenshots".to_string(), "logs/artifacts".to_string()],
+            prune_dirs: vec![
+                "logs/artifacts".to_string(),
+                "logs/agent/step_screenshots".to_string(),
+                "logs/traffic".to_string(),
+            ],
+            quota_mb: 0,
+            scan_interval_secs: 300,
+        }
+    }
+}
+
+impl StorageConfig {
+    fn quota_bytes(&self) -> Option<u64> {
+        (self.quota_mb > 0).then(|| self.quota_mb * 1024 * 1024)
+    }
+}
+
+/// 单个目录的用量
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DirUsage {
+    pub path: String,
+    pub bytes: u64,
+    pub files: u64,
+}
+
+/// 磁盘用量
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StorageUsage {
+    pub dirs: Vec<DirUsage>,
+    pub total_bytes: u64,
+    /// 配额（字节），未配置时为 None
+    pub quota_bytes: Option<u64>,
+    /// 清理后仍超出配额（可清理的文件不足）
+    pub over_quota: bool,
+    /// 启动以来清理的文件数
+    pub pruned_files: u64,
+    /// 启动以来清理的字节数
+    pub pruned_bytes: u64,
+    pub scanned_at: DateTime<Utc>,
+}
+
+/// 扫描到的文件
+#[derive(Debug)]
+struct ScannedFile {
+    path: PathBuf,
+    /// 所属的统计目录
+    dir: usize,
+    bytes: u64,
+    modified: SystemTime,
+    prunable: bool,
+}
+
+#[derive(Debug, Default)]
+struct Totals {
+    pruned_files: u64,
+    pruned_bytes: u64,
+}
+
+/// 磁盘用量监控
+#[derive(Debug, Default)]
+pub struct StorageMonitor {
+    config: RwLock<StorageConfig>,
+    usage: RwLock<Option<StorageUsage>>,
+    totals: RwLock<Totals>,
+}
+
+/// 进程内共享的磁盘用量监控
+pub fn storage_monitor() -> &'static StorageMonitor {
+    static MONITOR: OnceLock<StorageMonitor> = OnceLock::new();
+    MONITOR.get_or_init(StorageMonitor::default)
+}
+
+impl StorageMonitor {
+    /// 替换配置
+    pub fn configure(&self, config: StorageConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    /// 最近一次检查的用量
+    pub fn usage(&self) -> Option<StorageUsage> {
+        self.usage.read().unwrap().clone()
+    }
+
+    /// 统计用量并按配额清理（阻塞，在 `spawn_blocking` 中调用）
+    pub fn check(&self) -> StorageUsage {
+        let config = self.config.read().unwrap().clone();
+        let usage = self.check_at(&config, SystemTime::now());
+        *self.usage.write().unwrap() = Some(usage.clone());
+        usage
+    }
+
+    fn check_at(&self, config: &StorageConfig, now: SystemTime) -> StorageUsage {
+        let roots: Vec<PathBuf> = config.dirs.iter().map(PathBuf::from).collect();
+        let prune_roots: Vec<PathBuf> = config.prune_dirs.iter().map(PathBuf::from).collect();
+        let mut files = Vec::new();
+        for (index, root) in roots.iter().enumerate() {
+            scan_dir(root, index, &roots, &prune_roots, &mut files);
+        }
+        let mut total_bytes: u64 = files.iter().map(|f| f.bytes).sum();
+
+        let quota_bytes = config.quota_bytes();
+        let mut pruned = vec![false; files.len()];
+        if let Some(quota) = quota_bytes
+            && total_bytes > quota
+        {
+            let target = (quota as f64 * PRUNE_TARGET_RATIO) as u64;
+            let mut candidates: Vec<usize> = (0..files.len())
+                .filter(|&i| {
+                    let age = now.duration_since(files[i].modified).unwrap_or_default();
+                    files[i].prunable && age >= PRUNE_MIN_AGE
+                })
+                .collect();
+            candidates.sort_by_key(|&i| files[i].modified);
+
+            let (mut count, mut bytes) = (0u64, 0u64);
+            for i in candidates {
+                if total_bytes <= target {
+                    break;
+                }
+                match std::fs::remove_file(&files[i].path) {
+                    Ok(()) => {
+                        pruned[i] = true;
+                        total_bytes -= files[i].bytes;
+                        count += 1;
+                        bytes += files[i].bytes;
+                    }
+                    Err(e) => warn!("清理文件 {:?} 失败: {}", files[i].path, e),
+                }
+            }
+            if count > 0 {
+                info!("磁盘用量超出配额 {} MB，已清理 {} 个最旧的文件（{} 字节）", config.quota_mb, count, bytes);
+                let mut totals = self.totals.write().unwrap();
+                totals.pruned_files += count;
+                totals.pruned_bytes += bytes;
+            }
+            if total_bytes > quota {
+                warn!("清理后磁盘用量 {} 字节仍超出配额 {} MB，请检查不可清理的目录", total_bytes, config.quota_mb);
+            }
+        }
+
+        let mut dirs: Vec<DirUsage> =
+            config.dirs.iter().map(|path| DirUsage { path: path.clone(), bytes: 0, files: 0 }).collect();
+        for (file, pruned) in files.iter().zip(pruned) {
+            if !pruned {
+                dirs[file.dir].bytes += file.bytes;
+                dirs[file.dir].files += 1;
+            }
+        }
+        let totals = self.totals.read().unwrap();
+        StorageUsage {
+            dirs,
+            total_bytes,
+            quota_bytes,
+            over_quota: quota_bytes.is_some_and(|quota| total_bytes > quota),
+            pruned_files: totals.pruned_files,
+            pruned_bytes: totals.pruned_bytes,
+            scanned_at: Utc::now(),
+        }
+    }
+
+    /// Prometheus 文本格式的用量指标
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        let Some(usage) = self.usage() else {
+            return out;
+        };
+        let _ = writeln!(out, "# HELP scrs_storage_bytes 目录磁盘用量");
+        let _ = writeln!(out, "# TYPE scrs_storage_bytes gauge");
+        for dir in &usage.dirs {
+            let _ = writeln!(out, "scrs_storage_bytes{{dir=\"{}\"}} {}", escape(&dir.path), dir.bytes);
+        }
+        let _ = writeln!(out, "# HELP scrs_storage_files 目录文件数");
+        let _ = writeln!(out, "# TYPE scrs_storage_files gauge");
+        for dir in &usage.dirs {
+            let _ = writeln!(out, "scrs_storage_files{{dir=\"{}\"}} {}", escape(&dir.path), dir.files);
+        }
+        if let Some(quota) = usage.quota_bytes {
+            let _ = writeln!(out, "# HELP scrs_storage_quota_bytes 磁盘用量配额");
+            let _ = writeln!(out, "# TYPE scrs_storage_quota_bytes gauge");
+            let _ = writeln!(out, "scrs_storage_quota_bytes {}", quota);
+        }
+        let _ = writeln!(out, "# HELP scrs_storage_pruned_files_total 超出配额清理的文件数");
+        let _ = writeln!(out, "# TYPE scrs_storage_pruned_files_total counter");
+        let _ = writeln!(out, "scrs_storage_pruned_files_total {}", usage.pruned_files);
+        let _ = writeln!(out, "# HELP scrs_storage_pruned_bytes_total 超出配额清理的字节数");
+        let _ = writeln!(out, "# TYPE scrs_storage_pruned_bytes_total counter");
+        let _ = writeln!(out, "scrs_storage_pruned_bytes_total {}", usage.pruned_bytes);
+        out
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// 递归扫描目录，跳过单独统计的嵌套目录
+fn scan_dir(dir: &Path, index: usize, roots: &[PathBuf], prune_roots: &[PathBuf], files: &mut Vec<ScannedFile>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if !roots.contains(&path) {
+                scan_dir(&path, index, roots, prune_roots, files);
+            }
+        } else if metadata.is_file() {
+            files.push(ScannedFile {
+                prunable: prune_roots.iter().any(|root| path.starts_with(root)),
+                path,
+                dir: index,
+                bytes: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+    }
+}
+
+/// 按配置启动定期检查
+pub fn spawn_storage_monitor(config: StorageConfig) {
+    let interval = Duration::from_secs(config.scan_interval_secs.max(10));
+    storage_monitor().configure(config);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = tokio::task::spawn_blocking(|| storage_monitor().check()).await {
+                warn!("检查磁盘用量失败: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, bytes: usize, age_secs: u64, now: SystemTime) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = std::fs::File::create(path).unwrap();
+        file.set_len(bytes as u64).unwrap();
+        file.set_modified(now - Duration::from_secs(age_secs)).unwrap();
+    }
+
+    #[test]
+    fn test_quota_prunes_oldest_artifacts() {
+        let root = std::env::temp_dir().join(format!("scrs_storage_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let now = SystemTime::now();
+        let logs = root.join("logs");
+        let artifacts = logs.join("artifacts");
+        write(&logs.join("agent/agent.jsonl"), 400 * 1024, 7200, now);
+        write(&artifacts.join("t1/a.png"), 300 * 1024, 5000, now);
+        write(&artifacts.join("t2/b.png"), 300 * 1024, 4000, now);
+        write(&artifacts.join("t3/c.png"), 300 * 1024, 60, now);
+
+        let config = StorageConfig {
+            dirs: vec![logs.to_string_lossy().into_owned(), artifacts.to_string_lossy().into_owned()],
+            prune_dirs: vec![artifacts.to_string_lossy().into_owned()],
+            quota_mb: 1,
+            scan_interval_secs: 60,
+        };
+        let monitor = StorageMonitor::default();
+        let usage = monitor.check_at(&config, now);
+
+        // 嵌套目录单独统计；只删除最旧的产物，日志与最近写入的文件保留
+        assert_eq!(usage.dirs[0].bytes, 400 * 1024);
+        assert_eq!((usage.dirs[1].files, usage.pruned_files), (1, 2));
+        assert!(!artifacts.join("t1/a.png").exists());
+        assert!(!artifacts.join("t2/b.png").exists());
+        assert!(artifacts.join("t3/c.png").exists());
+        assert_eq!(usage.total_bytes, 700 * 1024);
+        assert!(!usage.over_quota);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}